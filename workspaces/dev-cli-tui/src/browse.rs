@@ -0,0 +1,141 @@
+//! 文本浏览模式
+//!
+//! 终端不支持原始模式或备用屏幕（`TERM=dumb`、CI、输入输出被重定向）时，`--tui` 自动退回到
+//! 逐行交互的分页浏览：与全屏界面共用同一份状态（[`TuiState`]）、视图与行文本，只是以编号列表
+//! 分页打印，通过简单的命令切换视图、翻页与查看详情。输入结束（EOF）时退出。
+
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use surf_core::ScanResult;
+
+use crate::theme::Theme;
+use crate::{detail_text, format_bytes, list_len, view_rows, view_title, TuiState, TuiView};
+
+/// 每页显示的行数
+const PAGE_SIZE: usize = 20;
+
+const HELP: &str = "命令: 编号 查看/展开  n 下一页  b 上一页  t 切换视图  p 展开/折叠包  q 退出";
+
+/// 运行文本浏览：从 `input` 逐行读取命令，输出写入 `out`
+pub fn run(
+    result: &ScanResult,
+    expand_packages: bool,
+    theme: Theme,
+    loaded_from: Option<&Path>,
+    input: impl BufRead,
+    mut out: impl Write,
+) -> Result<()> {
+    let mut state = TuiState::new(result, expand_packages, theme);
+    let mut page = 0;
+    let summary = &result.summary;
+    writeln!(
+        out,
+        "Surf 文本浏览 | {}: {} 文件, {} 目录, {}",
+        summary.root_path.display(),
+        summary.total_files,
+        summary.total_dirs,
+        format_bytes(summary.total_size_bytes)
+    )?;
+    if let Some(path) = loaded_from {
+        writeln!(out, "离线结果: {}", path.display())?;
+    }
+
+    let mut lines = input.lines();
+    loop {
+        let rows = view_rows(result, &state);
+        let pages = rows.len().div_ceil(PAGE_SIZE).max(1);
+        page = page.min(pages - 1);
+        writeln!(out, "\n== {} ({}/{}) ==", view_title(result, state.view), page + 1, pages)?;
+        if rows.is_empty() {
+            writeln!(out, "  （无内容）")?;
+        }
+        for (i, row) in rows.iter().enumerate().skip(page * PAGE_SIZE).take(PAGE_SIZE) {
+            writeln!(out, "{:>4}. {}", i + 1, row)?;
+        }
+        write!(out, "{}\n> ", HELP)?;
+        out.flush()?;
+
+        let Some(line) = lines.next().transpose()? else {
+            writeln!(out)?;
+            break;
+        };
+        match line.trim() {
+            "q" => break,
+            "" | "n" if page + 1 < pages => page += 1,
+            "" | "n" => writeln!(out, "已是最后一页")?,
+            "b" => page = page.saturating_sub(1),
+            "t" => {
+                state.view = state.view.next();
+                state.density_index = 0;
+                page = 0;
+            }
+            "p" => {
+                state.expand_packages = !state.expand_packages;
+                state.rebuild_tree(result);
+            }
+            command => match command.parse::<usize>() {
+                Ok(n) if (1..=rows.len()).contains(&n) => {
+                    select(result, &mut state, n - 1);
+                    writeln!(out, "\n{}", detail_text(result, &state, loaded_from))?;
+                }
+                _ => writeln!(out, "未知命令: {}", command)?,
+            },
+        }
+    }
+    Ok(())
+}
+
+/// 选中当前视图的第 `index` 行；目录树中同时执行 Enter 操作（展开/折叠目录或查看文件）
+fn select(result: &ScanResult, state: &mut TuiState, index: usize) {
+    if state.view == TuiView::Tree {
+        state.selected_index = index;
+        state.selected_file = None;
+        state.activate_selected(result);
+    } else if index < list_len(result, state.view) {
+        state.density_index = index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_plain_browser_pages_views_and_details() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("videos")).unwrap();
+        fs::write(dir.path().join("videos/a.mp4"), vec![0u8; 4096]).unwrap();
+        fs::write(dir.path().join("notes.txt"), vec![0u8; 1024]).unwrap();
+        let mut request = surf_core::ScanRequest::new(dir.path());
+        request.min_size = Some(0);
+        let result = surf_core::Scanner::new().scan_sync(&request).unwrap();
+
+        // 顶层速览 → 查看第 1 行 → 切换到类别 → 切换到目录树 → 未知命令 → EOF 退出
+        let input = "1\nt\nt\nx\n";
+        let mut out = Vec::new();
+        run(&result, false, Theme::default(), None, input.as_bytes(), &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("== 顶层速览（根目录各直接子项） (1/1) =="));
+        assert!(text.contains("   1. "));
+        assert!(text.contains("选中项: videos/"));
+        assert!(text.contains("== 文件类别分布 (1/1) =="));
+        assert!(text.contains("== 目录树 (Top 2 大文件) (1/1) =="));
+        assert!(text.contains("未知命令: x"));
+
+        // 分页：超出一页的视图可以翻页，越界时提示
+        let mut out = Vec::new();
+        run(&result, false, Theme::default(), None, "n\nq\n".as_bytes(), &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("已是最后一页"));
+
+        // ASCII 模式：占比条形不含方块字符
+        let ascii = Theme { high_contrast: false, ascii: true };
+        let mut out = Vec::new();
+        run(&result, false, ascii, None, "q\n".as_bytes(), &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains(" ##") && !text.contains('█'));
+    }
+}
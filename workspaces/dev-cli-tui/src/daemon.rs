@@ -0,0 +1,150 @@
+//! 索引守护模式（`surf daemon --index PATH`）
+//!
+//! 在后台维护根目录的持久化索引，并通过 TCP 上的换行分隔 JSON-RPC 2.0
+//! （每行一个请求，每行一个响应）立即应答查询，无需等待扫描：
+//! - `index.status`：守护状态（是否就绪、快照代数、生成时间等）；
+//! - `index.query`：当前快照的扫描结果，可选参数 `limit` 截断 Top N 列表；可选参数 `path`
+//!   查询根目录或其直接子目录（由快照截取），快照无法回答的路径返回 `-32602` 错误，
+//!   调用方（如服务端的索引路由）据此回退为实际扫描。
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use surf_core::{IndexDaemon, IndexOptions, ScanRequest};
+
+/// 启动索引守护并阻塞处理 TCP 连接
+pub fn run(request: ScanRequest, options: IndexOptions, host: &str, port: u16) -> Result<()> {
+    let listener = TcpListener::bind((host, port))
+        .with_context(|| format!("监听 {}:{} 失败", host, port))?;
+    let daemon = Arc::new(IndexDaemon::start(request, options));
+    println!(
+        "Surf 索引守护已启动: {}，查询地址 tcp://{}:{}",
+        daemon.root_path().display(),
+        host,
+        port
+    );
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let daemon = daemon.clone();
+        std::thread::spawn(move || {
+            let _ = handle_connection(stream, &daemon);
+        });
+    }
+    Ok(())
+}
+
+/// 逐行读取请求并写回响应，直到连接关闭
+fn handle_connection(stream: TcpStream, daemon: &IndexDaemon) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = handle_line(&line, daemon);
+        writeln!(writer, "{}", response)?;
+    }
+    Ok(())
+}
+
+/// 处理单行 JSON-RPC 请求，返回响应对象
+pub fn handle_line(line: &str, daemon: &IndexDaemon) -> Value {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(e) => return error_response(Value::Null, -32700, &format!("Parse error: {}", e)),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(Value::as_str).unwrap_or_default();
+
+    match method {
+        "index.status" => json!({ "jsonrpc": "2.0", "id": id, "result": daemon.status() }),
+        "index.query" => {
+            let Some(snapshot) = daemon.snapshot() else {
+                return error_response(id, -32001, "Index not ready");
+            };
+            let mut result = match request.pointer("/params/path").and_then(Value::as_str) {
+                Some(path) => match surf_core::index::subtree(&snapshot.result, Path::new(path)) {
+                    Some(result) => result,
+                    None => return error_response(id, -32602, "Path not covered by index"),
+                },
+                None => snapshot.result.clone(),
+            };
+            let limit = request
+                .pointer("/params/limit")
+                .and_then(Value::as_u64)
+                .map(|n| n as usize);
+            if let Some(limit) = limit {
+                result.top_files.truncate(limit);
+            }
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": {
+                    "generation": snapshot.generation,
+                    "indexed_at": surf_core::serde_time::to_value(snapshot.indexed_at),
+                    "result": result,
+                },
+            })
+        }
+        _ => error_response(id, -32601, "Method not found"),
+    }
+}
+
+fn error_response(id: Value, code: i32, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_handle_line_status_and_query() {
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("a.bin")).unwrap().write_all(&[0u8; 64]).unwrap();
+        std::fs::create_dir_all(dir.path().join("sub/deep")).unwrap();
+        File::create(dir.path().join("sub/b.bin")).unwrap().write_all(&[0u8; 32]).unwrap();
+        let mut options = IndexOptions::for_root(dir.path());
+        options.cache_path = None;
+        let daemon = IndexDaemon::start(ScanRequest::new(dir.path()), options);
+        daemon.refresh().unwrap();
+
+        let status = handle_line(r#"{"jsonrpc":"2.0","id":1,"method":"index.status"}"#, &daemon);
+        assert_eq!(status["id"], 1);
+        assert_eq!(status["result"]["ready"], true);
+
+        let query = handle_line(
+            r#"{"jsonrpc":"2.0","id":2,"method":"index.query","params":{"limit":0}}"#,
+            &daemon,
+        );
+        assert_eq!(query["result"]["result"]["summary"]["total_files"], 2);
+        assert_eq!(query["result"]["result"]["top_files"].as_array().unwrap().len(), 0);
+
+        let sub = dir.path().join("sub");
+        let line = json!({ "jsonrpc": "2.0", "id": 4, "method": "index.query", "params": { "path": sub } });
+        let query = handle_line(&line.to_string(), &daemon);
+        assert_eq!(query["result"]["result"]["summary"]["total_files"], 1);
+        assert_eq!(query["result"]["result"]["summary"]["total_size_bytes"], 32);
+        let line = json!({ "jsonrpc": "2.0", "id": 5, "method": "index.query", "params": { "path": sub.join("deep") } });
+        assert_eq!(handle_line(&line.to_string(), &daemon)["error"]["code"], -32602);
+
+        let unknown = handle_line(r#"{"jsonrpc":"2.0","id":3,"method":"nope"}"#, &daemon);
+        assert_eq!(unknown["error"]["code"], -32601);
+        let invalid = handle_line("not json", &daemon);
+        assert_eq!(invalid["error"]["code"], -32700);
+    }
+}
@@ -0,0 +1,332 @@
+//! 对比浏览模式
+//!
+//! `surf diff --tui OLD NEW` 以目录树浏览 [`surf_core::diff`] 的结果：每行显示变化量
+//! （▲ 增长 / ▼ 缩小）与新增、删除标记，子项默认按变化量绝对值降序排列，便于逐层追查
+//! “是什么变大了”。目录树只由对比结果中的条目组成，中间目录本身未列出变化时不显示大小，
+//! 排序时按其下变化最大的条目计算。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode};
+use ratatui::{backend::CrosstermBackend, Terminal};
+use surf_core::{ChangeKind, EntryChange, ScanDiff};
+
+use crate::theme::Theme;
+use crate::{format_bytes, format_delta, list_widget, panel};
+
+/// 子项排序方式（s 键切换）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffSort {
+    /// 按变化量绝对值降序
+    Growth,
+    /// 按名称升序
+    Name,
+}
+
+/// 对比目录树的节点
+#[derive(Debug, Clone)]
+struct DiffNode {
+    name: String,
+    /// 相对根目录的路径
+    path: PathBuf,
+    /// 该条目自身的变化；未列出变化的中间目录为 None
+    change: Option<EntryChange>,
+    is_dir: bool,
+    children: Vec<DiffNode>,
+    expanded: bool,
+}
+
+impl DiffNode {
+    fn new(name: String, path: PathBuf, is_dir: bool) -> Self {
+        Self { name, path, change: None, is_dir, children: Vec::new(), expanded: false }
+    }
+
+    /// 按路径插入变化条目，缺失的中间目录一并创建
+    fn insert(&mut self, change: &EntryChange, is_dir: bool) {
+        let components: Vec<String> =
+            change.path.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+        let mut node = self;
+        for (depth, name) in components.iter().enumerate() {
+            let last = depth + 1 == components.len();
+            let index = match node.children.iter().position(|child| child.name == *name) {
+                Some(index) => index,
+                None => {
+                    let path = node.path.join(name);
+                    node.children.push(DiffNode::new(name.clone(), path, !last || is_dir));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[index];
+        }
+        node.change = Some(change.clone());
+    }
+
+    /// 排序用的变化量：自身的变化与其下各条目变化中绝对值最大者
+    fn weight(&self) -> u64 {
+        let own = self.change.as_ref().map_or(0, |c| c.delta_bytes.unsigned_abs());
+        self.children.iter().map(DiffNode::weight).fold(own, u64::max)
+    }
+
+    fn sort(&mut self, sort: DiffSort) {
+        match sort {
+            DiffSort::Growth => self.children.sort_by_key(|child| (std::cmp::Reverse(child.weight()), child.name.clone())),
+            DiffSort::Name => self.children.sort_by(|a, b| a.name.cmp(&b.name)),
+        }
+        for child in &mut self.children {
+            child.sort(sort);
+        }
+    }
+
+    /// 展开的节点按顺序扁平化为 (深度, 节点)
+    fn flatten<'a>(&'a self, depth: usize, out: &mut Vec<(usize, &'a DiffNode)>) {
+        for child in &self.children {
+            out.push((depth, child));
+            if child.expanded {
+                child.flatten(depth + 1, out);
+            }
+        }
+    }
+
+    fn find_mut(&mut self, path: &Path) -> Option<&mut DiffNode> {
+        if self.path == path {
+            return Some(self);
+        }
+        self.children.iter_mut().find(|child| path.starts_with(&child.path)).and_then(|child| child.find_mut(path))
+    }
+}
+
+/// 对比浏览的状态
+pub struct DiffState {
+    root: DiffNode,
+    /// 当前选中行在扁平化列表中的索引
+    pub selected: usize,
+    pub sort: DiffSort,
+    theme: Theme,
+}
+
+impl DiffState {
+    /// 由对比结果构建目录树：按变化量排序，默认展开第一级
+    pub fn new(changes: &ScanDiff, theme: Theme) -> Self {
+        let mut root = DiffNode::new(String::new(), PathBuf::new(), true);
+        for change in &changes.directories {
+            root.insert(change, true);
+        }
+        for change in &changes.files {
+            root.insert(change, false);
+        }
+        for child in &mut root.children {
+            child.expanded = child.is_dir;
+        }
+        let mut state = DiffState { root, selected: 0, sort: DiffSort::Growth, theme };
+        state.root.sort(state.sort);
+        state
+    }
+
+    fn flat(&self) -> Vec<(usize, &DiffNode)> {
+        let mut out = Vec::new();
+        self.root.flatten(0, &mut out);
+        out
+    }
+
+    /// 可见行数
+    pub fn len(&self) -> usize {
+        self.flat().len()
+    }
+
+    /// 可见行的文本：缩进、展开状态、名称、变化量与新增/删除标记
+    pub fn rows(&self) -> Vec<String> {
+        let (up, down) = self.theme.delta_arrows();
+        self.flat()
+            .into_iter()
+            .map(|(depth, node)| {
+                let prefix = match (node.is_dir, node.expanded) {
+                    (true, true) => "[-] ",
+                    (true, false) => "[+] ",
+                    (false, _) => "    ",
+                };
+                let name = if node.is_dir { format!("{}/", node.name) } else { node.name.clone() };
+                let mut row = format!("{}{}{}", "  ".repeat(depth), prefix, name);
+                if let Some(change) = &node.change {
+                    let arrow = if change.delta_bytes < 0 { down } else { up };
+                    row.push_str(&format!("  {} {}", arrow, format_bytes(change.delta_bytes.unsigned_abs())));
+                    if matches!(change.kind, ChangeKind::Added | ChangeKind::Removed) {
+                        row.push_str(&format!(" [{}]", change.kind.label()));
+                    }
+                    if change.approximate {
+                        row.push_str(" ~");
+                    }
+                }
+                row
+            })
+            .collect()
+    }
+
+    /// 选中行的详情文本
+    pub fn detail(&self, changes: &ScanDiff) -> String {
+        let size = |size: Option<u64>| size.map_or_else(|| "-".to_string(), format_bytes);
+        let header = format!(
+            "总大小变化: {}\n文件数变化: {:+}\n目录数变化: {:+}",
+            format_delta(changes.total_size_delta),
+            changes.total_files_delta,
+            changes.total_dirs_delta
+        );
+        let flat = self.flat();
+        let Some((_, node)) = flat.get(self.selected) else {
+            return format!("{}\n\n（列出的目录与文件均无变化）", header);
+        };
+        let body = match &node.change {
+            Some(change) => format!(
+                "选中: {}\n\n变化: {}\n原大小: {}\n现大小: {}\n变化量: {}{}",
+                node.path.display(),
+                change.kind.label(),
+                size(change.old_size_bytes),
+                size(change.new_size_bytes),
+                format_delta(change.delta_bytes),
+                if change.approximate { "\n\n另一侧的列表不完整，可能只是大小越过了 Top N 下限，变化量为上界" } else { "" }
+            ),
+            None => format!("选中: {}\n\n该目录本身不在对比结果中，仅其下的条目有变化", node.path.display()),
+        };
+        format!("{}\n\n{}", body, header)
+    }
+
+    /// 处理按键；返回 false 表示退出
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        let len = self.len();
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Down if self.selected + 1 < len => self.selected += 1,
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Left => {
+                let Some(path) = self.flat().get(self.selected).map(|(_, node)| node.path.clone()) else {
+                    return true;
+                };
+                if let Some(node) = self.root.find_mut(&path).filter(|node| node.is_dir) {
+                    node.expanded = match code {
+                        KeyCode::Right => true,
+                        KeyCode::Left => false,
+                        _ => !node.expanded,
+                    };
+                }
+            }
+            KeyCode::Char('s') => {
+                // 切换排序后保持选中同一条目
+                let path = self.flat().get(self.selected).map(|(_, node)| node.path.clone());
+                self.sort = match self.sort {
+                    DiffSort::Growth => DiffSort::Name,
+                    DiffSort::Name => DiffSort::Growth,
+                };
+                self.root.sort(self.sort);
+                self.selected = path
+                    .and_then(|path| self.flat().iter().position(|(_, node)| node.path == path))
+                    .unwrap_or(0);
+            }
+            _ => {}
+        }
+        true
+    }
+}
+
+/// 全屏对比浏览主循环
+pub fn run(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    changes: &ScanDiff,
+    theme: Theme,
+) -> Result<()> {
+    let mut state = DiffState::new(changes, theme);
+    let root = if changes.old_root == changes.new_root {
+        changes.new_root.display().to_string()
+    } else {
+        format!("{} -> {}", changes.old_root.display(), changes.new_root.display())
+    };
+    loop {
+        terminal.draw(|f| {
+            let chunks = ratatui::layout::Layout::default()
+                .direction(ratatui::layout::Direction::Vertical)
+                .constraints([ratatui::layout::Constraint::Min(3), ratatui::layout::Constraint::Length(1)])
+                .split(f.size());
+            let content = ratatui::layout::Layout::default()
+                .direction(ratatui::layout::Direction::Horizontal)
+                .constraints([ratatui::layout::Constraint::Percentage(60), ratatui::layout::Constraint::Percentage(40)])
+                .split(chunks[0]);
+
+            let sort = match state.sort {
+                DiffSort::Growth => "按变化量",
+                DiffSort::Name => "按名称",
+            };
+            let title = format!("变化的目录与文件（{}）", sort);
+            f.render_widget(list_widget(title, state.rows(), state.selected, theme), content[0]);
+            let detail = ratatui::widgets::Paragraph::new(state.detail(changes)).block(panel("变化详情", theme));
+            f.render_widget(detail, content[1]);
+
+            let status = format!(
+                "Surf 对比 | {} | 总大小 {} | {} 导航, Enter 展开/折叠, s 切换排序, q/Esc 退出",
+                root,
+                format_delta(changes.total_size_delta),
+                theme.arrows()
+            );
+            f.render_widget(ratatui::widgets::Paragraph::new(status).style(theme.status_style()), chunks[1]);
+        })?;
+
+        if let Event::Key(key) = event::read()? {
+            if !state.handle_key(key.code) {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surf_core::{FileEntry, ScanResult};
+
+    fn snapshot(files: &[(&str, u64)]) -> ScanResult {
+        let mut result = ScanResult::default();
+        result.summary.root_path = "/data".into();
+        result.summary.top_limit = 10;
+        result.summary.total_size_bytes = files.iter().map(|(_, size)| size).sum();
+        result.top_files = files.iter().map(|(path, size)| FileEntry::new(*path, *size)).collect();
+        result
+    }
+
+    #[test]
+    fn test_diff_tree_rows_sorting_and_navigation() {
+        let old = snapshot(&[("/data/logs/app.log", 1000), ("/data/logs/old.log", 500), ("/data/b.bin", 4096)]);
+        let new = snapshot(&[("/data/logs/app.log", 9000), ("/data/a.bin", 2048), ("/data/b.bin", 4096)]);
+        let changes = surf_core::diff(&old, &new);
+        let mut state = DiffState::new(&changes, Theme::default());
+
+        // 按变化量排序：logs/ 下 app.log 增长最多，排在新增的 a.bin 之前；未变化的 b.bin 不出现
+        let rows = state.rows();
+        assert_eq!(rows.len(), 4, "{:?}", rows);
+        assert!(rows[0].starts_with("[-] logs/"));
+        assert!(rows[1].contains("app.log  ▲ 7.81 KB"));
+        assert!(rows[2].contains("old.log  ▼ 500 B [删除]"));
+        assert!(rows[3].contains("a.bin  ▲ 2.00 KB [新增]"));
+        assert!(state.detail(&changes).contains("该目录本身不在对比结果中"));
+
+        // 选中 app.log 后切换为按名称排序，选中项保持不变
+        state.handle_key(KeyCode::Down);
+        assert!(state.detail(&changes).contains("变化量: +7.81 KB"));
+        state.handle_key(KeyCode::Char('s'));
+        assert_eq!(state.sort, DiffSort::Name);
+        assert!(state.rows()[0].contains("a.bin"));
+        assert_eq!(state.selected, 2);
+
+        // 折叠目录，越界导航停在末行，q 退出
+        state.selected = 1;
+        state.handle_key(KeyCode::Enter);
+        assert_eq!(state.len(), 2);
+        assert!(state.rows()[1].starts_with("[+] logs/"));
+        state.handle_key(KeyCode::Down);
+        assert_eq!(state.selected, 1);
+        assert!(!state.handle_key(KeyCode::Char('q')));
+
+        // ASCII 模式以 +/- 代替三角箭头
+        let ascii = DiffState::new(&changes, Theme { high_contrast: false, ascii: true });
+        assert!(ascii.rows()[1].contains("app.log  + 7.81 KB"));
+        assert!(ascii.rows().iter().all(|row| !row.contains(['▲', '▼'])));
+    }
+}
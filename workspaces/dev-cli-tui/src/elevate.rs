@@ -0,0 +1,269 @@
+//! 提权补扫无权限目录（`surf --elevate`）
+//!
+//! 普通权限扫描遇到无法读取的目录时只记录在 `diagnostics.denied_dirs` 中。开启 `--elevate` 后，
+//! 扫描结束时以提权方式（Unix 上经 sudo 提示输入密码，Windows 上经 UAC 确认）重新启动本程序的
+//! 隐藏子命令 `elevated-stat`，只补扫这些目录并把结果写入当前用户创建的临时文件，再嫁接回原结果。
+//!
+//! 提权进程只遍历目录并读取元数据：不加载插件与访问日志，不计算文件摘要，不读写扫描缓存，
+//! 也不修改任何文件（结果文件除外）。提权失败或被取消时保留原结果并给出提示。
+
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use surf_core::{ScanRequest, ScanResult, Scanner};
+
+use crate::Cli;
+
+/// 提权补扫 `base` 中的无权限目录并嫁接回结果；提权失败时原样返回 `base`
+pub fn rescan_denied(cli: &Cli, request: &ScanRequest, base: ScanResult) -> Result<ScanResult> {
+    let dirs = base.diagnostics.denied_dirs.clone();
+    if dirs.is_empty() {
+        return Ok(base);
+    }
+    eprintln!("{} 个目录因权限不足被跳过，正在请求管理员权限补扫（仅读取元数据）…", dirs.len());
+
+    // 结果文件由当前用户预先创建，提权进程只覆盖写入内容，文件归属不变
+    let output = std::env::temp_dir().join(format!("surf-elevated-{}.json", std::process::id()));
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&output)
+        .with_context(|| format!("创建临时结果文件失败: {}", output.display()))?;
+    let outcome = run_elevated(&helper_args(cli, &base, &output, &dirs)).and_then(|()| {
+        let content = std::fs::read(&output).context("读取提权补扫结果失败")?;
+        serde_json::from_slice::<Vec<ScanResult>>(&content).context("解析提权补扫结果失败")
+    });
+    let _ = std::fs::remove_file(&output);
+
+    match outcome {
+        Ok(subtrees) => Ok(surf_core::graft(base, subtrees, request)),
+        Err(e) => {
+            eprintln!("提权补扫失败，结果中不含无权限目录: {:#}", e);
+            Ok(base)
+        }
+    }
+}
+
+/// 提权进程的命令行：沿用影响统计口径的参数，最小文件过滤取首次扫描实际采用的值
+pub fn helper_args(cli: &Cli, base: &ScanResult, output: &Path, dirs: &[PathBuf]) -> Vec<String> {
+    let mut args = vec![
+        "--path".to_string(),
+        cli.path().display().to_string(),
+        "--limit".to_string(),
+        cli.limit.to_string(),
+        "--min-size".to_string(),
+        base.summary.min_size.unwrap_or(0).to_string(),
+        "--follow-symlinks".to_string(),
+        cli.follow_symlinks.to_string(),
+        "--top-dirs-depth".to_string(),
+        cli.top_dirs_depth.to_string(),
+    ];
+    if let Some(threads) = cli.threads {
+        args.extend(["--threads".to_string(), threads.to_string()]);
+    }
+    if let Some(budget) = &cli.memory_budget {
+        args.extend(["--memory-budget".to_string(), budget.clone()]);
+    }
+    if let Some(ops) = cli.max_io_ops {
+        args.extend(["--max-io-ops".to_string(), ops.to_string()]);
+    }
+    if cli.nice {
+        args.push("--nice".to_string());
+    }
+    if let Some(days) = cli.stale_days {
+        args.extend(["--stale-days".to_string(), days.to_string()]);
+    }
+    args.extend(["--stale-mode".to_string(), cli.stale_mode.to_string()]);
+    if let Some(limit) = cli.stale_limit {
+        args.extend(["--stale-limit".to_string(), limit.to_string()]);
+    }
+    if let Some(sort) = cli.stale_sort {
+        args.extend(["--stale-sort".to_string(), sort.to_string()]);
+    }
+    if let Some(limit) = cli.empty_limit {
+        args.extend(["--empty-limit".to_string(), limit.to_string()]);
+    }
+    if let Some(limit) = cli.recency_limit {
+        args.extend(["--recency-limit".to_string(), limit.to_string()]);
+    }
+    if let Some(limit) = cli.extension_top {
+        args.extend(["--extension-top".to_string(), limit.to_string()]);
+    }
+    for selector in &cli.size_histogram {
+        args.extend(["--size-histogram".to_string(), selector.clone()]);
+    }
+    if let Some(depth) = cli.max_depth {
+        args.extend(["--max-depth".to_string(), depth.to_string()]);
+    }
+    for pattern in &cli.exclude {
+        args.extend(["--exclude".to_string(), pattern.clone()]);
+    }
+    for pattern in &cli.include {
+        args.extend(["--include".to_string(), pattern.clone()]);
+    }
+    for regex in &cli.exclude_regex {
+        args.extend(["--exclude-regex".to_string(), regex.clone()]);
+    }
+    for regex in &cli.include_regex {
+        args.extend(["--include-regex".to_string(), regex.clone()]);
+    }
+    if cli.respect_ignore {
+        args.push("--respect-ignore".to_string());
+    }
+    if cli.same_filesystem {
+        args.push("--one-file-system".to_string());
+    }
+    if cli.dedupe_hardlinks {
+        args.push("--dedupe-hardlinks".to_string());
+    }
+    if cli.allocated {
+        args.push("--allocated".to_string());
+    }
+    if cli.owners {
+        args.push("--owners".to_string());
+    }
+    if cli.caffeinate {
+        args.push("--caffeinate".to_string());
+    }
+    if cli.opaque_packages {
+        args.push("--opaque-packages".to_string());
+    }
+    if cli.skips_entries() {
+        args.push("--summary-only".to_string());
+    }
+    args.extend(["elevated-stat".to_string(), "--output".to_string(), output.display().to_string()]);
+    args.extend(dirs.iter().map(|d| d.display().to_string()));
+    args
+}
+
+/// 以提权方式运行本程序并等待退出
+#[cfg(unix)]
+fn run_elevated(args: &[String]) -> Result<()> {
+    let exe = std::env::current_exe().context("获取程序路径失败")?;
+    let status = Command::new("sudo")
+        .arg("--")
+        .arg(exe)
+        .args(args)
+        .status()
+        .context("启动 sudo 失败")?;
+    if !status.success() {
+        anyhow::bail!("提权进程退出状态: {}", status);
+    }
+    Ok(())
+}
+
+/// 以提权方式运行本程序并等待退出
+#[cfg(windows)]
+fn run_elevated(args: &[String]) -> Result<()> {
+    // PowerShell 单引号字符串中的单引号需写成两个
+    let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+    let exe = std::env::current_exe().context("获取程序路径失败")?;
+    let arg_list = args
+        .iter()
+        .map(|a| quote(&msvc_quote(a)))
+        .collect::<Vec<_>>()
+        .join(",");
+    let script = format!(
+        "$p = Start-Process -FilePath {} -ArgumentList {} -Verb RunAs -Wait -PassThru; exit $p.ExitCode",
+        quote(&exe.display().to_string()),
+        arg_list
+    );
+    let status = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .status()
+        .context("启动 PowerShell 失败")?;
+    if !status.success() {
+        anyhow::bail!("提权进程退出状态: {}", status);
+    }
+    Ok(())
+}
+
+/// 按 MSVC 运行库解析命令行的规则给参数加双引号：引号前与参数末尾的反斜杠需要加倍，
+/// 参数中的双引号转义为 `\"`，其余反斜杠原样保留（如 `C:\` 写成 `"C:\\"`，否则结尾的 `\"` 会被当作转义的引号）
+#[cfg(any(windows, test))]
+pub(crate) fn msvc_quote(arg: &str) -> String {
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.extend(std::iter::repeat_n('\\', backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// 隐藏子命令 `elevated-stat`：逐个扫描目录，将结果列表以 JSON 写入 `output`
+pub fn run_helper(cli: &Cli, output: &Path, dirs: &[PathBuf]) -> Result<()> {
+    let results = dirs
+        .iter()
+        .filter_map(|dir| {
+            // 提权后仍无法读取的目录（如网络卷）跳过，保留在原结果的 denied_dirs 中
+            let request = stat_request(cli, dir).ok()?;
+            Scanner::new()
+                .scan_sync(&request)
+                .ok()
+                .filter(|result| !result.diagnostics.denied_dirs.contains(dir))
+        })
+        .collect::<Vec<_>>();
+    std::fs::write(output, serde_json::to_vec(&results)?)
+        .with_context(|| format!("写入补扫结果失败: {}", output.display()))
+}
+
+/// 只读取元数据的扫描请求：不含插件、访问日志、重复检测与并发提示缓存
+pub fn stat_request(cli: &Cli, dir: &Path) -> Result<ScanRequest> {
+    let mut request = ScanRequest::new(dir);
+    request.threads = cli.threads;
+    request.min_size = match &cli.min_size {
+        Some(size) => Some(crate::parse_size_string(size)?),
+        None => Some(0),
+    };
+    request.stale_days = cli.stale_days;
+    request.stale_mode = cli.stale_mode;
+    request.stale_limit = cli.stale_limit;
+    request.stale_sort = cli.stale_sort;
+    request.empty_limit = cli.empty_limit;
+    request.recency_limit = cli.recency_limit;
+    request.extension_top_limit = cli.extension_top;
+    request.size_histograms = cli.size_histogram.clone();
+    request.limit = Some(cli.limit);
+    request.exclude_patterns = cli.exclude.clone();
+    request.include_patterns = cli.include.clone();
+    request.exclude_regex = cli.exclude_regex.clone();
+    request.include_regex = cli.include_regex.clone();
+    // 过滤规则相对首次扫描的根目录（--path）匹配，与补扫目录无关
+    request.filter_root = Some(cli.path().to_path_buf());
+    request.respect_ignore_files = cli.respect_ignore;
+    request.follow_symlinks = cli.follow_symlinks;
+    request.same_filesystem = cli.same_filesystem;
+    request.deduplicate_hardlinks = cli.dedupe_hardlinks;
+    request.allocated_sizes = cli.allocated;
+    request.collect_owners = cli.owners;
+    request.keep_awake = cli.caffeinate;
+    request.opaque_packages = cli.opaque_packages;
+    request.memory_budget = cli.memory_budget.as_deref().map(crate::parse_size_string).transpose()?;
+    request.max_io_ops_per_sec = cli.max_io_ops;
+    request.nice_mode = cli.nice;
+    request.collect_entries = !cli.skips_entries();
+    request.top_dirs_depth = cli.top_dirs_depth;
+    // --max-depth 相对首次扫描的根目录（--path），补扫目录只遍历剩余的深度
+    request.max_depth = cli.max_depth.map(|max| {
+        let depth = dir.strip_prefix(cli.path()).map_or(0, |p| p.components().count());
+        max.saturating_sub(depth)
+    });
+    Ok(request)
+}
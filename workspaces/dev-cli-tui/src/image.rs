@@ -0,0 +1,157 @@
+//! 扫描磁盘镜像内容（`surf --image FILE`）
+//!
+//! 以只读方式挂载 .dmg / .iso / .img 镜像，把挂载点作为扫描根目录，结束后（含出错返回）卸载，
+//! 无需手动挂载即可审计镜像与归档内容。macOS 上使用 `hdiutil attach -readonly` 挂载到临时目录；
+//! Linux 上经 udisks（`udisksctl loop-setup --read-only` 与 `udisksctl mount`）挂载，桌面会话中普通
+//! 用户即可使用，不需要 root；只挂载整个设备，不处理分区表。其他平台以及未启用 `disk-images`
+//! 特性的构建不支持该选项。进程被强制终止时镜像可能保持挂载，需要手动卸载。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+/// 已只读挂载的镜像，离开作用域时卸载
+pub struct MountedImage {
+    mount_point: PathBuf,
+    #[cfg_attr(not(all(feature = "disk-images", target_os = "linux")), allow(dead_code))]
+    device: Option<String>,
+}
+
+impl MountedImage {
+    /// 镜像内容所在的挂载点
+    pub fn mount_point(&self) -> &Path {
+        &self.mount_point
+    }
+}
+
+/// 只读挂载镜像文件
+pub fn attach(image: &Path) -> Result<MountedImage> {
+    if !image.is_file() {
+        anyhow::bail!("镜像文件不存在: {}", image.display());
+    }
+    platform::attach(image)
+}
+
+impl Drop for MountedImage {
+    fn drop(&mut self) {
+        if let Err(e) = platform::detach(self) {
+            eprintln!("卸载镜像失败（挂载点 {}）: {:#}", self.mount_point.display(), e);
+        }
+    }
+}
+
+#[cfg(all(feature = "disk-images", any(target_os = "macos", target_os = "linux")))]
+fn run<S: AsRef<std::ffi::OsStr>>(program: &str, args: &[S]) -> Result<String> {
+    use anyhow::Context;
+
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("无法执行 {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!("{} 执行失败: {}", program, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(all(feature = "disk-images", target_os = "macos"))]
+mod platform {
+    use super::*;
+    use anyhow::Context;
+
+    pub fn attach(image: &Path) -> Result<MountedImage> {
+        let mount_point = std::env::temp_dir().join(format!("surf-image-{}", std::process::id()));
+        std::fs::create_dir_all(&mount_point)
+            .with_context(|| format!("创建挂载点失败: {}", mount_point.display()))?;
+        let args = ["attach", "-readonly", "-nobrowse", "-noautoopen", "-noverify", "-mountpoint"];
+        let mut args: Vec<&std::ffi::OsStr> = args.map(std::ffi::OsStr::new).to_vec();
+        args.extend([mount_point.as_os_str(), image.as_os_str()]);
+        if let Err(e) = run("hdiutil", &args) {
+            let _ = std::fs::remove_dir(&mount_point);
+            return Err(e);
+        }
+        Ok(MountedImage { mount_point, device: None })
+    }
+
+    pub fn detach(image: &MountedImage) -> Result<()> {
+        run("hdiutil", &[std::ffi::OsStr::new("detach"), image.mount_point.as_os_str()])?;
+        let _ = std::fs::remove_dir(&image.mount_point);
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "disk-images", target_os = "linux"))]
+mod platform {
+    use super::*;
+
+    pub fn attach(image: &Path) -> Result<MountedImage> {
+        let args = ["loop-setup", "--read-only", "--no-user-interaction", "-f"];
+        let mut args: Vec<&std::ffi::OsStr> = args.map(std::ffi::OsStr::new).to_vec();
+        args.push(image.as_os_str());
+        let output = run("udisksctl", &args)?;
+        let device = loop_device(&output)
+            .ok_or_else(|| anyhow::anyhow!("无法识别 udisksctl 的输出: {}", output.trim()))?;
+        let mounted = run("udisksctl", &["mount", "--no-user-interaction", "-o", "ro", "-b", &device])
+            .and_then(|output| {
+                mount_point(&output).ok_or_else(|| anyhow::anyhow!("无法识别 udisksctl 的输出: {}", output.trim()))
+            });
+        match mounted {
+            Ok(mount_point) => Ok(MountedImage { mount_point, device: Some(device) }),
+            Err(e) => {
+                let _ = run("udisksctl", &["loop-delete", "--no-user-interaction", "-b", &device]);
+                Err(e)
+            }
+        }
+    }
+
+    pub fn detach(image: &MountedImage) -> Result<()> {
+        let device = image.device.as_deref().unwrap_or_default();
+        run("udisksctl", &["unmount", "--no-user-interaction", "-b", device])?;
+        run("udisksctl", &["loop-delete", "--no-user-interaction", "-b", device])?;
+        Ok(())
+    }
+
+    /// `Mapped file disk.img as /dev/loop0.` → `/dev/loop0`
+    pub fn loop_device(output: &str) -> Option<String> {
+        let device = output.trim().rsplit(" as ").next()?.trim_end_matches('.');
+        device.starts_with("/dev/").then(|| device.to_string())
+    }
+
+    /// `Mounted /dev/loop0 at /media/user/DISK` → `/media/user/DISK`（旧版本末尾带句点）
+    pub fn mount_point(output: &str) -> Option<PathBuf> {
+        let (_, path) = output.trim().split_once(" at ")?;
+        let path = Path::new(path);
+        Some(if path.exists() { path.to_path_buf() } else { PathBuf::from(path.to_string_lossy().trim_end_matches('.')) })
+    }
+}
+
+#[cfg(not(all(feature = "disk-images", any(target_os = "macos", target_os = "linux"))))]
+mod platform {
+    use super::*;
+
+    pub fn attach(_image: &Path) -> Result<MountedImage> {
+        anyhow::bail!("当前平台或构建不支持挂载磁盘镜像（需要 macOS / Linux 并启用 disk-images 特性）")
+    }
+
+    pub fn detach(_image: &MountedImage) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "disk-images", target_os = "linux"))]
+mod tests {
+    use super::platform::{loop_device, mount_point};
+    use super::*;
+
+    #[test]
+    fn test_parse_udisksctl_output() {
+        assert_eq!(loop_device("Mapped file /tmp/disk.img as /dev/loop7.\n").as_deref(), Some("/dev/loop7"));
+        assert_eq!(loop_device("Error setting up loop device"), None);
+        assert_eq!(
+            mount_point("Mounted /dev/loop7 at /media/user/DISK\n"),
+            Some(PathBuf::from("/media/user/DISK"))
+        );
+        assert_eq!(mount_point("Mounted /dev/loop7 at /nonexistent/DISK."), Some(PathBuf::from("/nonexistent/DISK")));
+        assert!(attach(Path::new("/nonexistent/disk.img")).is_err());
+    }
+}
@@ -1,5 +1,7 @@
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use anyhow::{Context, Result};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
@@ -7,55 +9,463 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use surf_core::{ScanRequest, Scanner};
+use surf_core::serde_time::{with_format, TimeFormat};
+use surf_core::{IndexOptions, LiveProgress, ScanPhase, ScanRequest, ScanResult, Scanner, ShardManifest, SymlinkPolicy, TopLevelStat};
+
+mod browse;
+mod daemon;
+mod diff_view;
+mod elevate;
+mod image;
+mod remote;
+mod theme;
 
 /// Surf CLI & TUI frontend for disk scanning and analysis
 #[derive(Parser, Debug)]
 #[command(name = "surf", version = "0.1.0", about = "极速磁盘扫描与分析工具", long_about = None)]
+#[command(group(clap::ArgGroup::new("hashing").args(["duplicates", "hash"]).multiple(true)))]
 struct Cli {
-    /// 扫描起始根目录
-    #[arg(short, long, default_value = ".", value_name = "PATH")]
-    path: PathBuf,
+    /// 扫描起始根目录；可重复指定以同时扫描多个根目录（如 -p /home -p /var），结果合并汇总
+    #[arg(short = 'p', long = "path", default_value = ".", value_name = "PATH", global = true)]
+    paths: Vec<PathBuf>,
     
     /// 并发扫描线程数
     #[arg(short, long, value_name = "N")]
     threads: Option<u16>,
     
-    /// 过滤最小文件尺寸（支持单位：B, KB, MB, GB）
+    /// 过滤最小文件尺寸（支持单位：B, KB, MB, GB）；未指定时按文件系统容量自动选择，0 表示不过滤
     #[arg(short, long, value_name = "SIZE")]
     min_size: Option<String>,
     
     /// 结果展示的最大条目数
-    #[arg(short, long, default_value = "20", value_name = "N")]
+    #[arg(short, long, default_value = "20", value_name = "N", global = true)]
     limit: usize,
     
-    /// 识别陈旧文件的天数阈值（文件最后修改时间超过此天数则视为陈旧）
+    /// 识别陈旧文件的天数阈值（默认按最后修改时间，参照的时间见 --stale-mode）
     #[arg(long, value_name = "DAYS")]
     stale_days: Option<u32>,
 
-    /// 排除规则（glob 模式，可重复传入多次，例如：--exclude "**/*.log" --exclude "node_modules/**"）
+    /// 陈旧判断参照的时间：modified（修改时间）、accessed（访问时间）、created（创建时间）或 any（三者最晚者）
+    #[arg(long, default_value = "modified", value_name = "MODE")]
+    stale_mode: surf_core::StaleMode,
+
+    /// 陈旧文件最多列出的数量（按 --stale-sort 保留排名靠前者），缺省时全部列出
+    #[arg(long, value_name = "N")]
+    stale_limit: Option<usize>,
+
+    /// 陈旧文件的排序依据：age（空闲时间）、size（大小）或 coldness（冷度，需要 --access-log）；
+    /// 缺省时提供访问日志按冷度，否则按空闲时间
+    #[arg(long, value_name = "SORT")]
+    stale_sort: Option<surf_core::StaleSort>,
+
+    /// 空文件与空目录各自最多列出的数量（按路径排序），缺省时全部列出
+    #[arg(long, value_name = "N")]
+    empty_limit: Option<usize>,
+
+    /// 排除规则（glob 模式，可重复传入多次，例如：--exclude "**/*.log" --exclude "node_modules/**"）；
+    /// 规则匹配相对于 --path 的路径，加 "anchored:" 前缀时匹配绝对路径
     #[arg(long, value_name = "GLOB", num_args = 1.., action = clap::ArgAction::Append)]
     exclude: Vec<String>,
+
+    /// 包含规则（glob 模式，支持 {a,b}，可重复传入）：只统计匹配的文件，例如 --include "**/*.{mp4,mkv}"；
+    /// 与 --exclude 同时使用时先包含后排除
+    #[arg(long, value_name = "GLOB", num_args = 1.., action = clap::ArgAction::Append)]
+    include: Vec<String>,
+
+    /// 排除规则（正则表达式，匹配相对于 --path 的路径，加 "anchored:" 前缀时匹配绝对路径，可重复传入），
+    /// 例如按日期命名的目录：--exclude-regex '(^|/)\d{4}-\d{2}-\d{2}$'
+    #[arg(long, value_name = "REGEX", num_args = 1.., action = clap::ArgAction::Append)]
+    exclude_regex: Vec<String>,
+
+    /// 包含规则（正则表达式，可重复传入），与 --include 合并生效
+    #[arg(long, value_name = "REGEX", num_args = 1.., action = clap::ArgAction::Append)]
+    include_regex: Vec<String>,
+
+    /// 遵循 .gitignore 与 .surfignore：跳过被忽略的文件与目录（如 node_modules、target/）
+    #[arg(long)]
+    respect_ignore: bool,
+
+    /// 最大遍历深度（根目录为 0），用于快速浅层扫描：更深的子目录只计数不进入
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// 访问日志文件（每行 `<unix秒时间戳>\t<路径>` 或仅路径），与 --stale-days 配合识别冷数据
+    #[arg(long, value_name = "FILE")]
+    access_log: Option<PathBuf>,
+
+    /// 列出修改时间可疑（早于 1980 年或晚于当前时间）的文件，便于修复来自有问题压缩包的时间戳
+    #[arg(long)]
+    list_suspicious_timestamps: bool,
+
+    /// 列出最大的冗余附属文件（备份副本、编号副本、锁文件、系统元数据与编辑器交换文件）
+    #[arg(long)]
+    list_redundant_files: bool,
+
+    /// 按修改时间列出最旧与最新的文件，各自最多 N 个（与 --limit 无关）
+    #[arg(long, value_name = "N")]
+    recency_limit: Option<usize>,
+
+    /// 在文件类型分布的每个扩展名下列出其最大的 N 个文件
+    #[arg(long, value_name = "N")]
+    extension_top: Option<usize>,
+
+    /// 统计指定扩展名或类别（如 mp4、category:video，可重复传入）的对数刻度文件大小分布，
+    /// 用于评估去重与压缩收益、选择合适的 --min-size
+    #[arg(long = "size-histogram", value_name = "SELECTOR", action = clap::ArgAction::Append)]
+    size_histogram: Vec<String>,
+
+    /// 将文件大小分布以 CSV（每个选择器的每段一行）写入文件
+    #[arg(long, value_name = "FILE", requires = "size_histogram")]
+    size_histogram_csv: Option<PathBuf>,
+
+    /// 读取文件元数据遇到瞬时错误（EINTR/EAGAIN/ESTALE，常见于 NFS）时的最大重试次数
+    #[arg(long, default_value = "3", value_name = "N")]
+    metadata_retries: u32,
+
+    /// 遇到第一个遍历错误（无法读取目录、目录条目或元数据）即中止扫描并以该错误退出，
+    /// 缺省时记录错误并继续扫描其余部分
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// 增量扫描：顶层子树中所有目录的修改时间未变化时直接使用上次的结果（缓存于 ~/.cache/surf/incremental），
+    /// 原地改写的文件不会被察觉
+    #[arg(long)]
+    incremental: bool,
+
+    /// 扫描期间阻止系统睡眠（macOS 电源断言、Windows 线程执行状态、Linux systemd-inhibit），
+    /// 扫描结束或取消时释放
+    #[arg(long)]
+    caffeinate: bool,
+
+    /// 把 .app、.photoslibrary、.framework 等包目录作为单个文件统计（大小为内部文件之和），
+    /// 不进入其内部，使结果与 Finder 的显示一致
+    #[arg(long)]
+    opaque_packages: bool,
+
+    /// 遍历工作集的内存预算（如 256MB）：在途遍历任务超出预算时改为深度优先逐个遍历，
+    /// 适合极深或极宽的目录树；仍使用多个线程
+    #[arg(long, value_name = "SIZE")]
+    memory_budget: Option<String>,
+
+    /// 遍历读取元数据的速率上限（读取目录与文件 stat，次/秒），适合机械硬盘或电池供电时的长时间扫描
+    #[arg(long, value_name = "OPS")]
+    max_io_ops: Option<u64>,
+
+    /// 后台扫描模式：未指定 --max-io-ops 时限速为每秒 2000 次元数据操作，未指定 --threads 时只用 2 个线程
+    #[arg(long)]
+    nice: bool,
+
+    /// 符号链接跟随策略：never（按链接自身计为文件）、dirs（进入目录链接）、all（同时按目标统计文件链接）
+    #[arg(long, default_value = "never", value_name = "POLICY")]
+    follow_symlinks: SymlinkPolicy,
+
+    /// 只统计根目录所在的文件系统（同 du -x）：不进入 /proc、/mnt 下的磁盘或网络共享等挂载点
+    #[arg(short = 'x', long = "one-file-system")]
+    same_filesystem: bool,
+
+    /// 硬链接去重：同一文件的多个硬链接只计入一次，大小反映实际磁盘占用
+    #[arg(long)]
+    dedupe_hardlinks: bool,
+
+    /// 同时统计文件实际分配的磁盘空间（与 du 一致；稀疏文件与压缩卷上可能小于逻辑大小）
+    #[arg(long)]
+    allocated: bool,
+
+    /// 仅汇总模式：只统计摘要、文件类型与目录大小，不保留文件条目（不列出大文件与陈旧文件），
+    /// 内存占用与文件数无关，适合快速扫描超大文件系统
+    #[arg(long)]
+    summary_only: bool,
+
+    /// 按分组输出速览：top 只列出根目录各直接子项的累计大小、文件数与占比（一屏回答“空间都去哪了”），
+    /// 不保留文件条目
+    #[arg(long, value_name = "GROUP")]
+    group_by: Option<GroupBy>,
+
+    /// 记录文件属主、权限位与只读标记，并按属主用户汇总占用（“哪个用户的文件最大”）
+    #[arg(long)]
+    owners: bool,
+
+    /// 最大目录排名统计的目录深度（根目录为 0）
+    #[arg(long, default_value_t = surf_core::DEFAULT_TOP_DIRS_DEPTH, value_name = "N")]
+    top_dirs_depth: usize,
+
+    /// 检测重复文件（遍历结束后对大小相同的文件计算内容摘要）
+    #[arg(long)]
+    duplicates: bool,
+
+    /// 重复检测哈希阶段的线程数（默认取逻辑核心数与 4 的较小值），独立于 --threads
+    #[arg(long, value_name = "N", requires = "duplicates")]
+    hash_threads: Option<u16>,
+
+    /// 为 Top N 大文件计算内容摘要（md5、sha256、blake3），在结果中与路径一同列出
+    #[arg(long, value_name = "ALGO")]
+    hash: Option<surf_core::HashAlgo>,
+
+    /// 哈希阶段（重复检测与 --hash）的读取速率上限（每秒字节数，支持单位：B, KB, MB, GB）
+    #[arg(long, value_name = "SIZE", requires = "hashing")]
+    hash_rate: Option<String>,
+
+    /// 读取文件头识别 MIME 类型（改过扩展名的文件也能识别），在 Top N 大文件中列出
+    #[arg(long)]
+    mime: bool,
+
+    /// 只识别不小于该大小的文件（支持单位：B, KB, MB, GB，默认 64KB）
+    #[arg(long, value_name = "SIZE", requires = "mime")]
+    mime_min_size: Option<String>,
+
+    /// 找出大而旧、估计可压缩的文件（按扩展名估计，--compress-sample 时按文件开头的熵估计），列出压缩后可节省的空间
+    #[arg(long)]
+    compressible: bool,
+
+    /// 可压缩分析的文件大小下限（支持单位：B, KB, MB, GB，默认 16MB）
+    #[arg(long, value_name = "SIZE", requires = "compressible")]
+    compress_min_size: Option<String>,
+
+    /// 可压缩分析的年龄下限：最后修改距今的天数（默认 90）
+    #[arg(long, value_name = "DAYS", requires = "compressible")]
+    compress_min_age: Option<u32>,
+
+    /// 读取每个文件开头的若干字节估算熵（支持单位：B, KB, MB，最多 1MB），扩展名未知的文件也能估计
+    #[arg(long, value_name = "SIZE", requires = "compressible")]
+    compress_sample: Option<String>,
+
+    /// 分析器插件目录（默认 ~/.config/surf/plugins，目录中的每个可执行文件都是一个插件）
+    #[arg(long, value_name = "DIR")]
+    plugins: Option<PathBuf>,
     
+    /// 单次扫描结束后以管理员权限（sudo / UAC）补扫因权限不足被跳过的目录，提权进程只读取元数据
+    #[arg(long, conflicts_with_all = ["remote", "tui", "load"])]
+    elevate: bool,
+
+    /// 只读挂载磁盘镜像（.dmg / .iso / .img）并以挂载点作为扫描根目录（代替 --path），结束后卸载
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["remote", "load", "elevate"])]
+    image: Option<PathBuf>,
+
     /// 启动 JSON-RPC 服务模式
     #[arg(short, long)]
     service: bool,
     
     /// 服务模式监听端口
-    #[arg(long, default_value = "1234", value_name = "PORT")]
+    #[arg(long, default_value = "1234", value_name = "PORT", global = true)]
     port: u16,
     
     /// 服务模式监听地址
-    #[arg(long, default_value = "127.0.0.1", value_name = "HOST")]
+    #[arg(long, default_value = "127.0.0.1", value_name = "HOST", global = true)]
     host: String,
     
     /// 单次模式下以 JSON 格式输出结果
-    #[arg(long)]
+    #[arg(long, global = true)]
     json: bool,
 
-    /// 启动终端用户界面（TUI）模式
+    /// JSON 输出中时间戳的格式：millis（Unix 纪元毫秒数）或 rfc3339（UTC 字符串）
+    #[arg(long, default_value = "millis", value_name = "FORMAT", global = true)]
+    time_format: TimeFormat,
+
+    /// 在远程 surf-service（HOST:PORT）上执行扫描，--path 为服务端机器上的路径
+    #[arg(long, value_name = "HOST:PORT")]
+    remote: Option<String>,
+
+    /// 远程服务的证书指纹（SHA-256，启动 HTTPS 服务时打印）；指定时经 TLS 连接并只接受该证书
+    #[arg(long, value_name = "SHA256", requires = "remote")]
+    remote_fingerprint: Option<surf_client::CertFingerprint>,
+
+    /// 启动终端用户界面（TUI）模式；终端不支持全屏界面时退回文本浏览
     #[arg(long)]
     tui: bool,
+
+    /// 从保存的扫描结果文件（`--json` 输出或分片结果）加载，不重新扫描；配合 --tui 离线浏览
+    #[arg(long, value_name = "FILE")]
+    load: Option<PathBuf>,
+
+    /// TUI 中展开 .app 等包目录的内部结构（默认作为单个条目显示累计大小，可按 p 切换）
+    #[arg(long)]
+    expand_packages: bool,
+
+    /// TUI 配色主题：default 或 high-contrast（反色加粗，以 > 标记选中行；默认读取 ~/.config/surf/config.json 的 theme）
+    #[arg(long, value_name = "THEME")]
+    theme: Option<theme::ThemeName>,
+
+    /// TUI 只使用 ASCII 字符（边框、条形与提示），并以 > 标记选中行，便于读屏软件与受限终端
+    #[arg(long)]
+    ascii: bool,
+
+    /// 子命令（不指定时执行单次扫描）
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// 速览分组方式（--group-by）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GroupBy {
+    /// 根目录的直接子项
+    Top,
+}
+
+impl std::str::FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top" => Ok(GroupBy::Top),
+            other => Err(format!("不支持的分组方式: {}（可选 top）", other)),
+        }
+    }
+}
+
+/// 子命令
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 后台索引守护：持续维护索引并通过 TCP JSON-RPC（换行分隔）立即应答查询
+    Daemon {
+        /// 需要建立索引的根目录
+        #[arg(long, value_name = "PATH")]
+        index: PathBuf,
+        /// 对账间隔（秒）
+        #[arg(long, default_value = "300", value_name = "SECS")]
+        interval: u64,
+    },
+    /// 多进程协同分片扫描
+    Shard {
+        #[command(subcommand)]
+        action: ShardAction,
+    },
+    /// 合并多个扫描结果文件（分片结果或不同根目录的结果）为一个完整结果
+    Merge {
+        /// 扫描结果 JSON 文件（如 `surf shard run --output` 生成的分片结果）
+        #[arg(required = true, value_name = "FILE")]
+        inputs: Vec<PathBuf>,
+        /// 分片清单文件；提供时校验分片是否齐全，否则按根目录去重合并
+        #[arg(long, value_name = "FILE")]
+        manifest: Option<PathBuf>,
+    },
+    /// 对比两次扫描结果，列出新增、删除、增长与缩小的目录和文件
+    Diff {
+        /// 旧的扫描结果文件（`--json` 输出）
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+        /// 新的扫描结果文件；省略时按当前选项重新扫描 OLD 的根目录
+        #[arg(value_name = "NEW")]
+        new: Option<PathBuf>,
+        /// 在全屏界面中以目录树浏览变化（▲/▼ 变化量、新增/删除标记，按变化量排序）
+        #[arg(long, conflicts_with = "json")]
+        tui: bool,
+    },
+    /// 生成释放指定空间的清理方案：依次选取冗余文件、缓存、重复副本与陈旧文件，风险低者优先
+    ///
+    /// 方案只列出建议，不删除任何文件；系统保留的条目与匹配 --keep 的路径不会出现在方案中。
+    Free {
+        /// 需要释放的空间（如 50GB）
+        #[arg(long, value_name = "SIZE")]
+        target: String,
+        /// 保留规则（glob，可多次指定）：匹配的路径不会被建议清理
+        #[arg(long = "keep", value_name = "GLOB")]
+        keep: Vec<String>,
+        /// 逐项预演删除，校验方案中的文件在扫描后是否发生变化
+        #[arg(long)]
+        check: bool,
+    },
+    /// 按条件生成清理方案（供审阅），并执行审阅过的方案
+    Cleanup {
+        #[command(subcommand)]
+        action: CleanupAction,
+    },
+    /// 配置文件与扫描参数的诊断
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// 生成确定性的合成目录树并扫描，核对结果与预期统计（用于复现问题报告）
+    #[command(hide = true)]
+    Selftest {
+        /// 合成目录树的生成位置（必须不存在或为空目录）
+        #[arg(long, value_name = "DIR")]
+        generate: PathBuf,
+        /// 目录树规格 JSON 文件（缺失字段取默认值）；不指定时使用默认规格
+        #[arg(long, value_name = "FILE")]
+        spec: Option<PathBuf>,
+    },
+    /// --elevate 启动的提权补扫进程：扫描给定目录，将结果列表以 JSON 写入文件
+    #[command(hide = true)]
+    ElevatedStat {
+        /// 结果输出文件（由发起补扫的进程预先创建）
+        #[arg(long, value_name = "FILE")]
+        output: PathBuf,
+        /// 需要补扫的目录
+        #[arg(required = true, value_name = "DIR")]
+        dirs: Vec<PathBuf>,
+    },
+}
+
+/// 清理方案子命令
+#[derive(Subcommand, Debug)]
+enum CleanupAction {
+    /// 按条件选取清理条目并输出方案，不删除任何文件；系统保留的条目与匹配 --keep 的路径不会出现在方案中
+    Plan {
+        /// 选取条件（可多次指定）：stale:<天数>（超过该天数未使用的陈旧文件）、
+        /// duplicates（重复文件中除最新副本外的全部副本）、glob:<规则>（匹配规则的文件）
+        #[arg(long = "select", required = true, value_name = "SPEC")]
+        select: Vec<surf_core::cleanup::Selection>,
+        /// 保留规则（glob，可多次指定）：匹配的路径不会被选取
+        #[arg(long = "keep", value_name = "GLOB")]
+        keep: Vec<String>,
+        /// 将方案保存为 JSON 文件，审阅后交给 `cleanup apply` 执行
+        #[arg(long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// 执行方案：逐项校验文件在扫描后是否变化后删除（默认移至回收站）；不加 --yes 时只预演
+    Apply {
+        /// `cleanup plan --output` 保存的方案文件
+        #[arg(value_name = "PLAN")]
+        plan: PathBuf,
+        /// 确认执行删除
+        #[arg(long)]
+        yes: bool,
+        /// 永久删除而不是移至回收站
+        #[arg(long)]
+        permanent: bool,
+        /// 无法移至回收站（没有回收站、不在同一文件系统等）的条目改为永久删除；默认报告失败
+        #[arg(long, conflicts_with = "permanent")]
+        allow_permanent_fallback: bool,
+        /// 将执行记录（每项的成败与原因）保存为 JSON 文件
+        #[arg(long, value_name = "FILE")]
+        transcript: Option<PathBuf>,
+    },
+}
+
+/// 配置子命令
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// 校验配置文件、过滤规则与忽略文件，并输出合并命令行参数后生效的配置
+    ///
+    /// 每个问题给出所在的字段（如 exclude_patterns[1]）或文件与行号；存在会使扫描失败的错误时以非零状态退出，
+    /// 只被忽略而不生效的设置记为警告。
+    Validate,
+}
+
+/// 分片扫描子命令
+#[derive(Subcommand, Debug)]
+enum ShardAction {
+    /// 将 --path 下的顶层目录划分为多个分片并写入清单文件
+    Plan {
+        /// 分片数量
+        #[arg(long, value_name = "N")]
+        shards: usize,
+        /// 清单文件输出路径（需位于各进程均可访问的共享存储上）
+        #[arg(long, value_name = "FILE")]
+        manifest: PathBuf,
+    },
+    /// 认领并扫描一个分片，将结果以 JSON 写入文件
+    Run {
+        /// 分片清单文件
+        #[arg(long, value_name = "FILE")]
+        manifest: PathBuf,
+        /// 指定分片序号；不指定时自动认领下一个未被认领的分片
+        #[arg(long, value_name = "N")]
+        index: Option<usize>,
+        /// 分片结果输出文件
+        #[arg(long, value_name = "FILE")]
+        output: PathBuf,
+    },
 }
 
 /// 解析带单位的大小字符串（例如 "100MB"）为字节数
@@ -93,9 +503,23 @@ fn parse_size_string(size_str: &str) -> Result<u64> {
 }
 
 impl Cli {
+    /// 首个扫描根目录（只针对单一根目录的子命令以此为准）
+    fn path(&self) -> &Path {
+        &self.paths[0]
+    }
+
+    /// 是否不保留文件条目（--summary-only 或 --group-by 速览）
+    fn skips_entries(&self) -> bool {
+        self.summary_only || self.group_by.is_some()
+    }
+
     /// 转换为核心扫描请求
     fn to_scan_request(&self) -> Result<ScanRequest> {
-        let mut request = ScanRequest::new(&self.path);
+        let mut request = ScanRequest::new(self.path());
+        if self.paths.len() > 1 {
+            request.roots = self.paths.clone();
+        }
+        request.auto_min_size = true;
         
         if let Some(threads) = self.threads {
             request.threads = Some(threads);
@@ -110,13 +534,88 @@ impl Cli {
         if let Some(stale_days) = self.stale_days {
             request.stale_days = Some(stale_days);
         }
+        request.stale_mode = self.stale_mode;
+        request.stale_limit = self.stale_limit;
+        request.stale_sort = self.stale_sort;
+        request.empty_limit = self.empty_limit;
 
         request.limit = Some(self.limit);
+        request.metadata_retry.max_retries = self.metadata_retries;
+        request.fail_fast = self.fail_fast;
+        request.incremental = self.incremental;
+        request.keep_awake = self.caffeinate;
+        request.opaque_packages = self.opaque_packages;
+        request.memory_budget = match &self.memory_budget {
+            Some(size) => Some(parse_size_string(size).context("解析 --memory-budget 参数失败")?),
+            None => None,
+        };
+        request.max_io_ops_per_sec = self.max_io_ops;
+        request.nice_mode = self.nice;
+        request.follow_symlinks = self.follow_symlinks;
+        request.deduplicate_hardlinks = self.dedupe_hardlinks;
+        request.same_filesystem = self.same_filesystem;
+        request.allocated_sizes = self.allocated;
+        request.collect_owners = self.owners;
+        request.collect_entries = !self.skips_entries();
+        request.top_dirs_depth = self.top_dirs_depth;
 
-        // 传递排除规则（glob）到核心扫描请求
+        let max_bytes_per_sec = match self.hash_rate {
+            Some(ref rate) => Some(parse_size_string(rate).context("解析 --hash-rate 参数失败")?),
+            None => None,
+        };
+        request.compute_hash = self.hash;
+        request.hash_max_bytes_per_sec = max_bytes_per_sec;
+        request.detect_mime = self.mime;
+        if let Some(ref size) = self.mime_min_size {
+            request.mime_min_size = parse_size_string(size).context("解析 --mime-min-size 参数失败")?;
+        }
+        if self.duplicates {
+            request.dedup = Some(surf_core::DedupOptions {
+                hash_threads: self.hash_threads,
+                max_bytes_per_sec,
+            });
+        }
+        if self.compressible {
+            let mut options = surf_core::CompressionOptions::default();
+            if let Some(ref size) = self.compress_min_size {
+                options.min_size = parse_size_string(size).context("解析 --compress-min-size 参数失败")?;
+            }
+            if let Some(days) = self.compress_min_age {
+                options.min_age_days = days;
+            }
+            if let Some(ref size) = self.compress_sample {
+                options.sample_bytes = parse_size_string(size).context("解析 --compress-sample 参数失败")?;
+            }
+            request.compression = Some(options);
+        }
+
+        // 传递排除与包含规则（glob）到核心扫描请求
         if !self.exclude.is_empty() {
             request.exclude_patterns = self.exclude.clone();
         }
+        request.include_patterns = self.include.clone();
+        request.exclude_regex = self.exclude_regex.clone();
+        request.include_regex = self.include_regex.clone();
+        request.respect_ignore_files = self.respect_ignore;
+        request.max_depth = self.max_depth;
+        request.list_suspicious_timestamps = self.list_suspicious_timestamps;
+        request.list_redundant_files = self.list_redundant_files;
+        request.recency_limit = self.recency_limit;
+        request.extension_top_limit = self.extension_top;
+        request.size_histograms = self.size_histogram.clone();
+
+        if let Some(ref path) = self.access_log {
+            let log = surf_core::AccessLog::load(path)
+                .with_context(|| format!("读取访问日志失败: {}", path.display()))?;
+            request.access_log = Some(Arc::new(log));
+        }
+
+        // 发现分析器插件（未指定目录时使用默认插件目录）
+        let plugin_dir = self.plugins.clone().or_else(surf_core::plugin::default_plugin_dir);
+        if let Some(dir) = plugin_dir {
+            request.plugins = surf_core::plugin::discover(&dir)
+                .with_context(|| format!("读取插件目录失败: {}", dir.display()))?;
+        }
 
         Ok(request)
     }
@@ -124,23 +623,25 @@ impl Cli {
 
 /// 格式化字节数为人类可读字符串
 fn format_bytes(bytes: u64) -> String {
-    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
-    let mut size = bytes as f64;
-    let mut unit_idx = 0;
-    
-    while size >= 1024.0 && unit_idx < UNITS.len() - 1 {
-        size /= 1024.0;
-        unit_idx += 1;
-    }
-    
-    if unit_idx == 0 {
-        format!("{} {}", bytes, UNITS[unit_idx])
-    } else {
-        format!("{:.2} {}", size, UNITS[unit_idx])
+    surf_core::format::format_bytes(bytes)
+}
+
+/// 格式化预计剩余时间（如“1 小时 5 分”“3 分 20 秒”“45 秒”）
+fn format_eta(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{} 秒", seconds),
+        (0, _) => format!("{} 分 {} 秒", minutes, seconds),
+        _ => format!("{} 小时 {} 分", hours, minutes),
     }
 }
 
 /// 打印扫描结果表格
+/// 将 0~1 的占比格式化为百分比文本，缺失时显示 "-"
+fn format_share(share: Option<f64>) -> String {
+    share.map(|s| format!("{:.2}%", s * 100.0)).unwrap_or_else(|| "-".to_string())
+}
+
 fn print_table(result: &surf_core::ScanResult, limit: usize) -> Result<()> {
     let summary = &result.summary;
     
@@ -149,22 +650,282 @@ fn print_table(result: &surf_core::ScanResult, limit: usize) -> Result<()> {
     println!("总文件数: {}", summary.total_files);
     println!("总目录数: {}", summary.total_dirs);
     println!("总大小: {}", format_bytes(summary.total_size_bytes));
+    if let Some(allocated) = summary.total_allocated_bytes {
+        println!("磁盘占用: {}", format_bytes(allocated));
+    }
+    if let Some(disk) = summary.disk_space {
+        println!(
+            "所在磁盘: 容量 {}，可用 {}，本目录占 {:.1}%",
+            format_bytes(disk.total_bytes),
+            format_bytes(disk.available_bytes),
+            disk.share_of(summary.total_size_bytes) * 100.0
+        );
+    }
+    if summary.placeholder_files > 0 {
+        println!(
+            "云端占位文件: {} 个，共 {}（仅在线，未占用本地空间）",
+            summary.placeholder_files,
+            format_bytes(summary.placeholder_bytes)
+        );
+    }
+    if !result.root_summaries.is_empty() {
+        println!("各根目录:");
+        for root in &result.root_summaries {
+            println!(
+                "  {:<48} {:>10} 个文件  {}",
+                root.root_path.display(),
+                root.total_files,
+                format_bytes(root.total_size_bytes)
+            );
+        }
+    }
     println!("扫描耗时: {:.2} 秒", summary.elapsed_seconds);
+    if let Some(min_size) = summary.min_size {
+        let source = if summary.min_size_is_default {
+            "（依据文件系统容量自动选择，--min-size 0 可完整收集）"
+        } else {
+            ""
+        };
+        println!("最小文件过滤: {}{}", format_bytes(min_size), source);
+    }
+    let diagnostics = &result.diagnostics;
+    if let Some(requested) = diagnostics.requested_limit {
+        println!("Top N 数量: 请求的 {} 超过上限，已截断为 {}", requested, summary.top_limit);
+    }
+    if let Some(status) = &diagnostics.keep_awake {
+        match &status.error {
+            None => println!("阻止睡眠: 扫描期间已阻止系统睡眠（{}）", status.mechanism),
+            Some(error) => println!("阻止睡眠: 未能阻止系统睡眠（{}）: {}", status.mechanism, error),
+        }
+    }
+    if diagnostics.cached_subtrees + diagnostics.rescanned_subtrees > 0 {
+        println!(
+            "增量扫描: {} 个顶层子树来自缓存，{} 个重新遍历",
+            diagnostics.cached_subtrees, diagnostics.rescanned_subtrees
+        );
+    }
+    if diagnostics.budget_deferred_dirs > 0 {
+        println!("内存预算: {} 个目录因在途任务已满改为深度优先遍历", diagnostics.budget_deferred_dirs);
+    }
+    if diagnostics.hinted_dirs > 0 {
+        println!(
+            "调度提示: 已应用（拆分 {} 个大目录为 {} 个批次）",
+            diagnostics.hinted_dirs, diagnostics.split_batches
+        );
+    }
+    if diagnostics.skipped_hardlinks > 0 {
+        println!(
+            "硬链接去重: 跳过 {} 个重复链接（{}）",
+            diagnostics.skipped_hardlinks,
+            format_bytes(diagnostics.skipped_hardlink_bytes)
+        );
+    }
+    if diagnostics.revisited_dirs > 0 {
+        println!(
+            "重复目录: 跳过 {} 个重复进入的目录（符号链接环路、多条链接或 bind mount 指向同一目录）:",
+            diagnostics.revisited_dirs
+        );
+        for alias in diagnostics.aliased_dirs.iter().take(limit) {
+            match &alias.alias_of {
+                Some(original) => println!("  {} -> {}", alias.path.display(), original.display()),
+                None => println!("  {}", alias.path.display()),
+            }
+        }
+    }
+    if !diagnostics.skipped_mount_points.is_empty() {
+        println!("单文件系统: 跳过 {} 个其他文件系统的挂载点:", diagnostics.skipped_mount_points.len());
+        for dir in diagnostics.skipped_mount_points.iter().take(limit) {
+            println!("  {}", dir.display());
+        }
+    }
+    if diagnostics.depth_limited_dirs > 0 {
+        println!("深度限制: {} 个超出 --max-depth 的目录未进入，其内容未计入统计", diagnostics.depth_limited_dirs);
+    }
+    if diagnostics.filter_checks > 0 {
+        println!(
+            "过滤规则: 匹配 {} 次，共 {:.1} ms，平均 {} ns/次",
+            diagnostics.filter_checks,
+            diagnostics.filter_match_nanos as f64 / 1e6,
+            diagnostics.filter_match_nanos / diagnostics.filter_checks
+        );
+    }
+    if summary.suspicious_timestamps > 0 {
+        println!(
+            "可疑时间戳: {} 个文件的修改时间早于 1980 年或晚于当前时间，年龄统计中已校正{}",
+            summary.suspicious_timestamps,
+            if result.suspicious_files.is_empty() { "（--list-suspicious-timestamps 可列出）" } else { ":" }
+        );
+        for file in &result.suspicious_files {
+            let modified = file.last_modified.map_or_else(|| "未知".to_string(), surf_core::serde_time::to_rfc3339);
+            println!("  {} {}", modified, file.path.display());
+        }
+    }
+    if summary.full_disk_access_missing {
+        println!(
+            "完全磁盘访问: 未授予，~/Library 等受保护目录未计入总量；请在 系统设置 > 隐私与安全性 > 完全磁盘访问权限 中允许当前终端后重新扫描"
+        );
+    }
+    if !diagnostics.denied_dirs.is_empty() {
+        println!("权限不足: 跳过 {} 个无法读取的目录（--elevate 可提权补扫）:", diagnostics.denied_dirs.len());
+        for dir in diagnostics.denied_dirs.iter().take(limit) {
+            println!("  {}", dir.display());
+        }
+    }
+    if summary.scan_error_count > 0 {
+        println!("遍历错误: {} 个（相应内容未完整统计，--fail-fast 可在首个错误处中止）:", summary.scan_error_count);
+        for error in result.scan_errors.iter().take(limit) {
+            println!("  {}", error);
+        }
+    }
+    if !diagnostics.worker_panics.is_empty() {
+        println!("内部错误: {} 个路径处理时发生 panic，已跳过:", diagnostics.worker_panics.len());
+        for panic in &diagnostics.worker_panics {
+            println!("  {}: {}", panic.path.display(), panic.message);
+        }
+    }
     
     // 显示 Top N 文件（如果结果中有）
     if !result.top_files.is_empty() {
         println!("\nTop {} 大文件:", limit);
-        println!("{:<60} {:<12}", "路径", "大小");
-        println!("{}", "-".repeat(80));
+        println!("{:<60} {:<12} {:>8} {:>8}", "路径", "大小", "占父目录", "占总量");
+        println!("{}", "-".repeat(96));
         
         for file in result.top_files.iter().take(limit) {
             let size_str = format_bytes(file.size_bytes);
-            println!("{:<60} {:<12}", file.path.display(), size_str);
+            let label = match (file.reserved, file.redundant) {
+                (Some(kind), _) => format!(" [系统保留: {}]", kind),
+                (None, Some(kind)) => format!(" [冗余: {}]", kind),
+                (None, None) => String::new(),
+            };
+            println!(
+                "{:<60} {:<12} {:>8} {:>8}{}",
+                file.path.display(),
+                size_str,
+                format_share(file.share_of_parent),
+                format_share(file.share_of_total),
+                label
+            );
+            if let (Some(algo), Some(digest)) = (summary.digest_algo, &file.digest) {
+                println!("  {}: {}", algo, digest);
+            }
+            if let Some(mime) = &file.mime {
+                let by_extension = file.extension.as_deref().map(surf_core::FileCategory::of_extension);
+                let mismatch = surf_core::FileCategory::of_mime(mime).is_some_and(|c| by_extension != Some(c));
+                println!("  类型: {}{}", mime, if mismatch { "（与扩展名不符）" } else { "" });
+            }
         }
+    } else if summary.summary_only {
+        println!("\n（仅汇总模式，未收集文件条目）");
     } else {
         println!("\n（Top N 文件功能尚未在核心扫描引擎中实现）");
     }
+
+    // 系统保留条目由系统管理，不能直接删除
+    if !result.system_reserved.is_empty() {
+        println!("\n系统保留（由系统管理，无法直接删除）:");
+        for entry in &result.system_reserved {
+            println!("  {:<58} {:<12} {}", entry.path.display(), format_bytes(entry.size_bytes), entry.kind);
+        }
+    }
+
+    // 废纸篓、缓存与构建产物等可回收位置
+    let reclaimable = &result.reclaimable_report;
+    if !reclaimable.is_empty() {
+        println!("\n可回收位置（估计可释放 {}）:", format_bytes(reclaimable.total_bytes));
+        for stat in &reclaimable.categories {
+            println!(
+                "  {:<18} {:>6} 处  {:<12} {}",
+                stat.kind.label(),
+                stat.location_count,
+                format_bytes(stat.size_bytes),
+                stat.kind.advice()
+            );
+            for location in &stat.locations {
+                println!("    {:<56} {}", location.path.display(), format_bytes(location.size_bytes));
+            }
+        }
+    }
+
+    // 大而旧的文件压缩或归档后可节省的空间（估计值）
+    if !result.compression_candidates.is_empty() {
+        println!(
+            "\n可压缩文件（{} 个，估计压缩后可节省 {}）:",
+            summary.compression_candidate_count,
+            format_bytes(summary.compression_savings_bytes)
+        );
+        for candidate in &result.compression_candidates {
+            let basis = match candidate.basis {
+                surf_core::compression::CompressionBasis::Sampled => "熵采样",
+                _ => "扩展名",
+            };
+            println!(
+                "  {:<50} {:<12} 压缩比 ~{:>3.0}%  可节省 {:<12} {}",
+                candidate.path.display(),
+                format_bytes(candidate.size_bytes),
+                candidate.estimated_ratio * 100.0,
+                format_bytes(candidate.estimated_savings_bytes),
+                basis
+            );
+        }
+    }
+
+    // 冗余附属文件通常可以直接删除
+    if !result.redundant.is_empty() {
+        println!(
+            "\n冗余文件（{} 个，可释放 {}）{}",
+            summary.redundant_file_count,
+            format_bytes(summary.redundant_bytes),
+            if result.redundant_files.is_empty() { "（--list-redundant-files 可列出）:" } else { ":" }
+        );
+        for stat in &result.redundant {
+            println!("  {:<16} {:>8} 个  {}", stat.kind.label(), stat.file_count, format_bytes(stat.total_size_bytes));
+        }
+        for file in &result.redundant_files {
+            let kind = file.redundant.map(|kind| kind.label()).unwrap_or_default();
+            println!("  {:<58} {:<12} {}", file.path.display(), format_bytes(file.size_bytes), kind);
+        }
+    }
     
+    // 按修改时间排查：最旧的文件可能早已无用，最新的文件说明最近是什么在占用空间
+    print_recency("最旧文件", &result.oldest_files);
+    print_recency("最新文件", &result.newest_files);
+
+    // 显示最大的目录（含子目录的递归大小）与目录占比（根目录及 Top N 文件所在目录）
+    print_dirs("最大目录", &result.top_dirs, limit);
+    print_dirs("目录占比", &result.directories, limit);
+    // 只计直接文件的大小：找出自身堆满文件的目录，而不是目录树很深的上层目录
+    print_density("最大目录（仅直接文件）", &result.top_dirs_shallow, limit);
+    
+    // 显示目录密度排名：少量巨型文件与海量小文件的目录需要不同的清理方式
+    print_density("大文件目录（每文件字节数最大）", &result.density.heavy, limit);
+    print_density("碎文件目录（每 MiB 文件数最大）", &result.density.crowded, limit);
+
+    // 显示重复文件组（按可释放空间降序）
+    if !result.duplicates.is_empty() {
+        let wasted: u64 = result.duplicates.iter().map(|g| g.wasted_bytes()).sum();
+        println!("\n重复文件（{} 组，保留一份可释放 {}）:", result.duplicates.len(), format_bytes(wasted));
+        for group in result.duplicates.iter().take(limit) {
+            println!("{} x {}，可释放 {}", group.paths.len(), format_bytes(group.size_bytes), format_bytes(group.wasted_bytes()));
+            for path in &group.paths {
+                println!("  {}", path.display());
+            }
+        }
+    }
+
+    // 显示空文件与空目录树（清理候选）
+    if summary.empty_file_count > 0 || summary.empty_dir_count > 0 {
+        println!(
+            "\n空文件与空目录（{} 个零字节文件，{} 棵空目录树）:",
+            summary.empty_file_count, summary.empty_dir_count
+        );
+        for path in result.empty_files.iter().take(limit) {
+            println!("  {}", path.display());
+        }
+        for dir in result.empty_dirs.iter().take(limit) {
+            println!("  {}/（{} 个空目录）", dir.path.display(), dir.dir_count);
+        }
+    }
+
     // 显示文件类型分布（如果结果中有）
     if !result.by_extension.is_empty() {
         println!("\n文件类型分布:");
@@ -176,127 +937,884 @@ fn print_table(result: &surf_core::ScanResult, limit: usize) -> Result<()> {
                 stat.extension, 
                 stat.file_count, 
                 format_bytes(stat.total_size_bytes));
+            for file in &stat.top_files {
+                println!("    {:<12} {}", format_bytes(file.size_bytes), file.path.display());
+            }
+        }
+    }
+
+    // 显示文件类别分布：图片、视频、文档、代码等各占多少
+    if !result.by_category.is_empty() {
+        println!("\n文件类别分布:");
+        println!("{:<10} {:>10} {:<12} {:>8}", "类别", "文件数", "总大小", "占总量");
+        println!("{}", "-".repeat(44));
+        for row in &result.by_category {
+            println!(
+                "{:<10} {:>10} {:<12} {:>8}",
+                row.category.label(),
+                row.file_count,
+                format_bytes(row.total_size_bytes),
+                format_share(Some(row.share_of_total))
+            );
         }
     }
-    
+
+    // 显示年龄分布：各年龄段（按最后修改时间）的文件数与大小，一眼看出冷数据有多少
+    if result.age_histogram.iter().any(|bucket| bucket.file_count > 0) {
+        println!("\n年龄分布:");
+        println!("{:<10} {:>10} {:<12} {:>8}", "修改时间", "文件数", "总大小", "占总量");
+        println!("{}", "-".repeat(44));
+        for bucket in &result.age_histogram {
+            println!(
+                "{:<10} {:>10} {:<12} {:>8}",
+                bucket.label,
+                bucket.file_count,
+                format_bytes(bucket.size_bytes),
+                format_share(Some(bucket.share_of_total))
+            );
+        }
+    }
+
+    // 显示文件大小分布（--size-histogram）：只列出有文件的分段
+    for histogram in &result.size_histograms {
+        println!("\n大小分布 {}（{} 个文件，{}）:", histogram.selector, histogram.file_count, format_bytes(histogram.size_bytes));
+        if histogram.file_count == 0 {
+            continue;
+        }
+        println!("{:<24} {:>10} {:<12} {:>8}", "文件大小", "文件数", "总大小", "占比");
+        println!("{}", "-".repeat(58));
+        for bucket in histogram.buckets.iter().filter(|bucket| bucket.file_count > 0) {
+            let range = match bucket.max_bytes {
+                Some(max) => format!("{} - {}", format_bytes(bucket.min_bytes), format_bytes(max)),
+                None => format!(">= {}", format_bytes(bucket.min_bytes)),
+            };
+            println!(
+                "{:<24} {:>10} {:<12} {:>8}",
+                range,
+                bucket.file_count,
+                format_bytes(bucket.size_bytes),
+                format_share(Some(bucket.share_of_selector))
+            );
+        }
+    }
+
+    // 显示按属主统计（--owners）
+    if !result.by_owner.is_empty() {
+        println!("\n属主分布:");
+        println!("{:<16} {:<10} {:<12}", "用户", "文件数", "总大小");
+        println!("{}", "-".repeat(40));
+        for stat in result.by_owner.iter().take(limit) {
+            let user = stat.user.clone().unwrap_or_else(|| format!("uid {}", stat.uid));
+            println!("{:<16} {:<10} {:<12}", user, stat.file_count, format_bytes(stat.total_size_bytes));
+        }
+    }
+
+    // 显示分析器插件结果段
+    for (name, section) in &result.plugin_sections {
+        println!("\n插件 {}:", name);
+        println!("{}", serde_json::to_string_pretty(section)?);
+    }
+    
+    Ok(())
+}
+
+/// 输出顶层速览（--group-by top）：根目录各直接子项的累计大小、文件数与占比
+fn print_top_level(result: &ScanResult, limit: usize) {
+    let summary = &result.summary;
+    println!("\n{}: {}，{} 个文件，{} 个目录（耗时 {:.2} 秒）",
+        summary.root_path.display(),
+        format_bytes(summary.total_size_bytes),
+        summary.total_files,
+        summary.total_dirs,
+        summary.elapsed_seconds);
+    println!("{:<48} {:<12} {:>10} {:>8} {:>8}", "路径", "大小", "文件数", "目录数", "占总量");
+    println!("{}", "-".repeat(92));
+    for row in result.top_level.iter().take(limit) {
+        println!(
+            "{:<48} {:<12} {:>10} {:>8} {:>8}",
+            top_level_name(row, &summary.root_path),
+            format_bytes(row.size_bytes),
+            row.file_count,
+            row.dir_count,
+            format_share(Some(row.share_of_total))
+        );
+    }
+    let rest = result.top_level.get(limit..).unwrap_or_default();
+    if !rest.is_empty() {
+        let bytes: u64 = rest.iter().map(|row| row.size_bytes).sum();
+        println!("（其余 {} 项，共 {}）", rest.len(), format_bytes(bytes));
+    }
+}
+
+/// 顶层速览行的显示名称：子目录显示为相对根目录的名称，根目录直接文件单独标注
+fn top_level_name(row: &TopLevelStat, root: &Path) -> String {
+    if row.root_files {
+        return "（根目录下的文件）".to_string();
+    }
+    let name = row.path.strip_prefix(root).unwrap_or(&row.path);
+    format!("{}/", name.display())
+}
+
+/// 输出目录大小表
+/// 带符号的字节变化量
+fn format_delta(delta: i64) -> String {
+    let sign = if delta < 0 { "-" } else { "+" };
+    format!("{}{}", sign, format_bytes(delta.unsigned_abs()))
+}
+
+/// 打印两次扫描结果的差异
+fn print_diff(changes: &surf_core::ScanDiff, limit: usize) {
+    println!("\n扫描结果对比:");
+    if changes.old_root == changes.new_root {
+        println!("根路径: {}", changes.new_root.display());
+    } else {
+        println!("根路径: {} -> {}", changes.old_root.display(), changes.new_root.display());
+    }
+    println!("总大小变化: {}", format_delta(changes.total_size_delta));
+    println!("文件数变化: {:+}", changes.total_files_delta);
+    println!("目录数变化: {:+}", changes.total_dirs_delta);
+
+    for (title, rows) in [("目录变化", &changes.directories), ("文件变化", &changes.files)] {
+        if rows.is_empty() {
+            continue;
+        }
+        println!("\n{}:", title);
+        println!("{:<60} {:<6} {:>12} {:>12} {:>12}", "路径", "变化", "原大小", "现大小", "变化量");
+        println!("{}", "-".repeat(106));
+        let size = |size: Option<u64>| size.map_or_else(|| "-".to_string(), format_bytes);
+        for change in rows.iter().take(limit) {
+            println!(
+                "{:<60} {:<6} {:>12} {:>12} {:>12}{}",
+                change.path.display(),
+                change.kind.label(),
+                size(change.old_size_bytes),
+                size(change.new_size_bytes),
+                format_delta(change.delta_bytes),
+                if change.approximate { "（可能只是越过了 Top N 下限）" } else { "" }
+            );
+        }
+    }
+    if changes.directories.is_empty() && changes.files.is_empty() {
+        println!("\n（列出的目录与文件均无变化）");
+    }
+}
+
+fn print_dirs(title: &str, rows: &[surf_core::DirStat], limit: usize) {
+    if rows.is_empty() {
+        return;
+    }
+    println!("\n{}:", title);
+    println!("{:<60} {:<12} {:>8} {:>8}", "路径", "大小", "占父目录", "占总量");
+    println!("{}", "-".repeat(96));
+    for dir in rows.iter().take(limit) {
+        println!(
+            "{:<60} {:<12} {:>8} {:>8}",
+            dir.path.display(),
+            format_bytes(dir.size_bytes),
+            format_share(dir.share_of_parent),
+            format_share(Some(dir.share_of_total))
+        );
+    }
+}
+
+/// 输出按修改时间排列的文件列表
+fn print_recency(title: &str, files: &[surf_core::FileEntry]) {
+    if files.is_empty() {
+        return;
+    }
+    println!("\n{}:", title);
+    for file in files {
+        let modified = file.last_modified.map_or_else(|| "未知".to_string(), surf_core::serde_time::to_rfc3339);
+        println!("  {:<25} {:<12} {}", modified, format_bytes(file.size_bytes), file.path.display());
+    }
+}
+
+/// 输出目录密度排名表
+fn print_density(title: &str, rows: &[surf_core::DirDensity], limit: usize) {
+    if rows.is_empty() {
+        return;
+    }
+    println!("\n{}:", title);
+    println!("{:<60} {:>10} {:<12} {:<12} {:>10}", "路径", "文件数", "大小", "每文件", "每MiB文件");
+    println!("{}", "-".repeat(108));
+    for row in rows.iter().take(limit) {
+        println!(
+            "{:<60} {:>10} {:<12} {:<12} {:>10}",
+            row.path.display(),
+            row.file_count,
+            format_bytes(row.size_bytes),
+            format_bytes(row.bytes_per_file),
+            row.files_per_mib
+        );
+    }
+}
+
+fn main() -> Result<()> {
+    let mut cli = Cli::parse();
+    // 扫描镜像时先只读挂载，main 返回（含出错）时 `_image` 离开作用域即卸载
+    let _image = match cli.image.as_deref().filter(|_| !cli.service) {
+        Some(file) => {
+            let image = image::attach(file)?;
+            eprintln!("镜像 {} 已只读挂载于 {}", file.display(), image.mount_point().display());
+            cli.paths = vec![image.mount_point().to_path_buf()];
+            Some(image)
+        }
+        None => None,
+    };
+    // 参数基础校验（仅针对单次扫描/TUI模式）
+    // - 路径存在且可访问
+    // - limit 必须为正数
+    // - threads（如提供）必须为正数
+    if !cli.service {
+        if cli.remote.is_none() && cli.load.is_none() {
+            if let Some(path) = cli.paths.iter().find(|path| !path.exists()) {
+                anyhow::bail!("路径不存在: {}", path.display());
+            }
+        }
+        if cli.elevate && cli.paths.len() > 1 {
+            anyhow::bail!("--elevate 只支持单个 --path");
+        }
+        if cli.limit == 0 {
+            anyhow::bail!("参数 --limit 必须为正整数");
+        }
+        if let Some(t) = cli.threads {
+            if t == 0 {
+                anyhow::bail!("参数 --threads 必须为正整数");
+            }
+        }
+    }
+    
+    if let Some(command) = &cli.command {
+        return run_command(&cli, command);
+    }
+
+    if cli.service {
+        // 服务模式：当前版本仅占位
+        println!("服务模式尚未实现，将在后续迭代中完成");
+        return Ok(());
+    }
+    
+    if cli.tui {
+        // TUI 模式
+        return run_tui(&cli);
+    }
+
+    if let Some(path) = &cli.load {
+        return output_result(&cli, &load_result(path)?);
+    }
+    
+    // 单次扫描模式
+    let request = cli.to_scan_request()?;
+
+    if let Some(addr) = &cli.remote {
+        let result = remote::run(&request, addr, cli.remote_fingerprint)?;
+        return output_result(&cli, &result);
+    }
+    
+    // 创建进度条
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_style(
+        indicatif::ProgressStyle::default_spinner()
+            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
+            .template("{spinner} 扫描中... {msg}")?
+    );
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    
+    // 执行扫描
+    let result = match scan_with_cached_hints(request.clone(), &pb) {
+        Ok(result) => {
+            pb.finish_with_message("扫描完成");
+            result
+        }
+        Err(e) => {
+            pb.finish_with_message("扫描失败");
+            anyhow::bail!("扫描失败: {}", e);
+        }
+    };
+    let result = if cli.elevate {
+        elevate::rescan_denied(&cli, &request, result)?
+    } else {
+        result
+    };
+    
+    output_result(&cli, &result)
+}
+
+/// 读取保存的扫描结果文件（`--json` 输出、分片结果或服务端 scan.result 的返回值）
+fn load_result(path: &Path) -> Result<ScanResult> {
+    let content = std::fs::read(path).with_context(|| format!("读取扫描结果失败: {}", path.display()))?;
+    serde_json::from_slice(&content).with_context(|| format!("解析扫描结果失败: {}", path.display()))
+}
+
+/// 按 --json 选择 JSON 或表格形式输出扫描结果；指定 --size-histogram-csv 时同时导出大小分布
+fn output_result(cli: &Cli, result: &ScanResult) -> Result<()> {
+    if let Some(path) = &cli.size_histogram_csv {
+        std::fs::write(path, surf_core::size_histogram::to_csv(&result.size_histograms))
+            .with_context(|| format!("写入大小分布失败: {}", path.display()))?;
+    }
+    if cli.json {
+        // JSON 输出（直接序列化 ScanResult）
+        let json = with_format(cli.time_format, || serde_json::to_string_pretty(result))?;
+        println!("{}", json);
+    } else if cli.group_by == Some(GroupBy::Top) {
+        print_top_level(result, cli.limit);
+    } else {
+        // 表格输出
+        print_table(result, cli.limit)?;
+    }
+    Ok(())
+}
+
+/// 执行子命令
+fn run_command(cli: &Cli, command: &Command) -> Result<()> {
+    match command {
+        Command::Daemon { index, interval } => {
+            if !index.exists() {
+                anyhow::bail!("路径不存在: {}", index.display());
+            }
+            let mut request = cli.to_scan_request()?;
+            request.root_path = index.clone();
+            let mut options = IndexOptions::for_root(index);
+            options.interval = std::time::Duration::from_secs((*interval).max(1));
+            daemon::run(request, options, &cli.host, cli.port)
+        }
+        Command::Shard { action: ShardAction::Plan { shards, manifest } } => {
+            let mut plan = ShardManifest::plan(cli.path(), *shards)
+                .with_context(|| format!("生成分片清单失败: {}", cli.path().display()))?;
+            plan.limit = Some(cli.limit);
+            plan.save(manifest)
+                .with_context(|| format!("写入分片清单失败: {}", manifest.display()))?;
+            println!("已生成 {} 个分片的清单: {}", plan.shards.len(), manifest.display());
+            Ok(())
+        }
+        Command::Shard { action: ShardAction::Run { manifest, index, output } } => {
+            let plan = ShardManifest::load(manifest)
+                .with_context(|| format!("读取分片清单失败: {}", manifest.display()))?;
+            let index = match index {
+                Some(i) => *i,
+                None => match plan.claim_next(manifest)? {
+                    Some(i) => i,
+                    None => {
+                        println!("所有分片均已被认领，无需继续扫描");
+                        return Ok(());
+                    }
+                },
+            };
+            let assignment = plan
+                .assignment(index)
+                .with_context(|| format!("分片序号超出范围: {}", index))?;
+
+            // 沿用命令行中的线程数、过滤条件等参数，根目录与 Top N 以清单为准
+            let mut request = cli.to_scan_request()?;
+            request.root_path = plan.root_path.clone();
+            request.limit = plan.limit;
+            request.shard = Some(assignment);
+
+            let result = scan_with_cached_hints(request, &indicatif::ProgressBar::hidden())
+                .with_context(|| format!("扫描分片 {} 失败", index))?;
+            std::fs::write(output, serde_json::to_vec(&result)?)
+                .with_context(|| format!("写入分片结果失败: {}", output.display()))?;
+            println!("分片 {}/{} 扫描完成: {}", index + 1, plan.shards.len(), output.display());
+            Ok(())
+        }
+        Command::Merge { inputs, manifest } => {
+            let results = inputs.iter().map(|path| load_result(path)).collect::<Result<Vec<_>>>()?;
+            let merged = match manifest {
+                Some(manifest) => ShardManifest::load(manifest)
+                    .with_context(|| format!("读取分片清单失败: {}", manifest.display()))?
+                    .merge(results)?,
+                None => surf_core::merge(results),
+            };
+            output_result(cli, &merged)
+        }
+        Command::Diff { old, new, tui } => {
+            let old = load_result(old)?;
+            let new = match new {
+                Some(path) => load_result(path)?,
+                None => {
+                    // 沿用命令行中的过滤条件等参数，根目录以旧结果为准
+                    let mut request = cli.to_scan_request()?;
+                    request.root_path = old.summary.root_path.clone();
+                    scan_with_cached_hints(request, &indicatif::ProgressBar::hidden())
+                        .with_context(|| format!("扫描失败: {}", old.summary.root_path.display()))?
+                }
+            };
+            let changes = surf_core::diff(&old, &new);
+            if *tui {
+                let theme = theme::Theme::resolve(cli.theme, cli.ascii);
+                match init_terminal() {
+                    Ok(mut terminal) => {
+                        let res = diff_view::run(&mut terminal, &changes, theme);
+                        restore_terminal(&mut terminal)?;
+                        res?;
+                    }
+                    Err(e) => {
+                        eprintln!("无法进入全屏界面（{}），改为输出对比表格", e);
+                        print_diff(&changes, cli.limit);
+                    }
+                }
+            } else if cli.json {
+                println!("{}", serde_json::to_string_pretty(&changes)?);
+            } else {
+                print_diff(&changes, cli.limit);
+            }
+            Ok(())
+        }
+        Command::Free { target, keep, check } => run_free(cli, target, keep, *check),
+        Command::Cleanup { action: CleanupAction::Plan { select, keep, output } } => {
+            run_cleanup_plan(cli, select, keep, output.as_deref())
+        }
+        Command::Cleanup { action: CleanupAction::Apply { plan, yes, permanent, allow_permanent_fallback, transcript } } => {
+            run_cleanup_apply(cli, plan, *yes, *permanent, *allow_permanent_fallback, transcript.as_deref())
+        }
+        Command::Config { action: ConfigAction::Validate } => run_config_validate(cli),
+        Command::Selftest { generate, spec } => run_selftest(cli, generate, spec.as_deref()),
+        Command::ElevatedStat { output, dirs } => elevate::run_helper(cli, output, dirs),
+    }
+}
+
+/// 生成并输出清理方案
+///
+/// 指定 --load 时基于保存的结果生成方案，否则扫描 --path：沿用命令行中的过滤条件等参数，
+/// 并开启方案所需的冗余文件、重复文件与陈旧文件（默认 180 天）统计。
+/// `surf config validate`：逐项检查配置并输出生效的配置，存在错误时返回错误
+fn run_config_validate(cli: &Cli) -> Result<()> {
+    use surf_core::check::RequestIssue;
+
+    let config_path = theme::config_path();
+    let (config_loaded, mut issues) = match &config_path {
+        Some(path) => theme::check_config(path),
+        None => (false, Vec::new()),
+    };
+    let theme = theme::Theme::resolve(cli.theme, cli.ascii);
+    let theme_source = match (cli.theme, config_loaded) {
+        (Some(_), _) => "命令行 --theme",
+        (None, true) => "配置文件",
+        (None, false) => "默认",
+    };
+    let request = match cli.to_scan_request() {
+        Ok(request) => {
+            issues.extend(surf_core::check::check(&request));
+            Some(request)
+        }
+        Err(e) => {
+            issues.push(RequestIssue::fatal("命令行参数", format!("{:#}", e)));
+            None
+        }
+    };
+    let errors = issues.iter().filter(|issue| issue.fatal).count();
+
+    if cli.json {
+        let report = serde_json::json!({
+            "config_file": config_path,
+            "config_loaded": config_loaded,
+            "theme": {"high_contrast": theme.high_contrast, "ascii": theme.ascii, "source": theme_source},
+            "cache_dir": surf_core::hints::cache_dir(),
+            "request": request,
+            "issues": issues,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        match &config_path {
+            Some(path) => println!("配置文件: {}（{}）", path.display(), if config_loaded { "已读取" } else { "不存在，使用默认值" }),
+            None => println!("配置文件: 未设置 HOME，使用默认值"),
+        }
+        let theme_name = match (theme.high_contrast, theme.ascii) {
+            (false, false) => "默认",
+            (true, false) => "高对比度",
+            (false, true) => "ASCII",
+            (true, true) => "高对比度 + ASCII",
+        };
+        println!("界面主题: {}（来源: {}）", theme_name, theme_source);
+        if let Some(dir) = surf_core::hints::cache_dir() {
+            println!("扫描缓存: {}", dir.display());
+        }
+        if let Some(request) = &request {
+            let plugins: Vec<&str> = request.plugins.iter().map(|plugin| plugin.name.as_str()).collect();
+            println!("插件: {} 个{}", plugins.len(), if plugins.is_empty() { String::new() } else { format!("（{}）", plugins.join(", ")) });
+            println!("生效的扫描请求:\n{}", serde_json::to_string_pretty(request)?);
+        }
+        if issues.is_empty() {
+            println!("未发现问题");
+        }
+        for issue in &issues {
+            println!("[{}] {}: {}", if issue.fatal { "错误" } else { "警告" }, issue.location, issue.message);
+        }
+    }
+    if errors > 0 {
+        anyhow::bail!("配置校验失败: {} 个错误", errors);
+    }
+    Ok(())
+}
+
+fn run_free(cli: &Cli, target: &str, keep: &[String], check: bool) -> Result<()> {
+    let options = surf_core::cleanup::CleanupOptions {
+        target_bytes: parse_size_string(target).context("解析 --target 参数失败")?,
+        keep_patterns: keep.to_vec(),
+    };
+    let result = match &cli.load {
+        Some(path) => load_result(path)?,
+        None => {
+            let mut request = cli.to_scan_request()?;
+            request.list_redundant_files = true;
+            request.dedup.get_or_insert_with(Default::default);
+            request.stale_days.get_or_insert(180);
+            request.limit = Some(cli.limit.max(1000));
+            scan_with_cached_hints(request, &indicatif::ProgressBar::hidden())
+                .with_context(|| format!("扫描失败: {}", cli.path().display()))?
+        }
+    };
+    let plan = surf_core::cleanup::plan(&result, &options).context("解析 --keep 参数失败")?;
+
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+        return Ok(());
+    }
+    println!("\n清理方案（目标 {}）:", format_bytes(plan.target_bytes));
+    let checked = check.then(|| {
+        surf_core::delete::to_trash(&plan.delete_items(), &plan.delete_options(surf_core::delete::DeleteOrigin::Cli, true))
+    });
+    let errors: Vec<_> = checked.iter().flatten().map(|checked| checked.result.error.as_ref()).collect();
+    let failed = print_cleanup_items(&plan, &errors);
+    println!(
+        "\n共 {} 项，合计 {}{}",
+        plan.items.len(),
+        format_bytes(plan.planned_bytes),
+        if plan.target_met { "，已达到目标" } else { "，未达到目标（已列出全部候选）" }
+    );
+    if plan.protected_skipped > 0 {
+        println!("已跳过 {} 个受保护的候选（系统保留或匹配 --keep）", plan.protected_skipped);
+    }
+    if check {
+        if failed > 0 {
+            anyhow::bail!("{} 项未通过预演（在扫描后已变化、受保护或无法移至回收站），请检查后重新生成方案", failed);
+        }
+        println!("预演通过：方案中的文件均与扫描时一致");
+    }
+    Ok(())
+}
+
+/// 输出方案条目表格；`errors` 非空时与条目一一对应，在失败的条目下方列出原因。返回失败的条目数
+fn print_cleanup_items(plan: &surf_core::cleanup::CleanupPlan, errors: &[Option<&surf_core::delete::DeleteError>]) -> usize {
+    println!("根路径: {}", plan.root_path.display());
+    if !plan.items.is_empty() {
+        println!("\n{:<8} {:>12} {:>12} {:<60} 原因", "类别", "大小", "累计", "路径");
+        println!("{}", "-".repeat(116));
+    }
+    let mut cumulative = 0;
+    let mut failed = 0;
+    for (index, item) in plan.items.iter().enumerate() {
+        cumulative += item.size_bytes;
+        println!(
+            "{:<8} {:>12} {:>12} {:<60} {}",
+            item.source.label(),
+            format_bytes(item.size_bytes),
+            format_bytes(cumulative),
+            item.path.display(),
+            item.reason
+        );
+        if let Some(error) = errors.get(index).copied().flatten() {
+            failed += 1;
+            println!("         ✗ {}", error.message);
+        }
+    }
+    failed
+}
+
+/// 按条件生成清理方案，输出或保存供审阅
+fn run_cleanup_plan(cli: &Cli, selections: &[surf_core::cleanup::Selection], keep: &[String], output: Option<&Path>) -> Result<()> {
+    use surf_core::cleanup::Selection;
+
+    let result = match &cli.load {
+        Some(path) => load_result(path)?,
+        None => {
+            let mut request = cli.to_scan_request()?;
+            request.limit = Some(cli.limit.max(1000));
+            // 按选取条件补充扫描选项：陈旧阈值取最小的天数，选取重复文件时启用重复检测
+            for selection in selections {
+                match selection {
+                    Selection::StaleOlderThan { days } => {
+                        request.stale_days = Some(request.stale_days.map_or(*days, |d| d.min(*days)));
+                    }
+                    Selection::DuplicatesExceptNewest => {
+                        request.dedup.get_or_insert_with(Default::default);
+                    }
+                    Selection::Matching { .. } => {}
+                }
+            }
+            scan_with_cached_hints(request, &indicatif::ProgressBar::hidden())
+                .with_context(|| format!("扫描失败: {}", cli.path().display()))?
+        }
+    };
+    let plan = surf_core::cleanup::select(&result, selections, keep).context("解析选取条件或 --keep 参数失败")?;
+    if let Some(path) = output {
+        plan.save(path).with_context(|| format!("写入清理方案失败: {}", path.display()))?;
+    }
+
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+        return Ok(());
+    }
+    let conditions: Vec<String> = selections.iter().map(ToString::to_string).collect();
+    println!("\n清理方案（选取条件 {}）:", conditions.join("、"));
+    print_cleanup_items(&plan, &[]);
+    println!("\n共 {} 项，合计 {}", plan.items.len(), format_bytes(plan.planned_bytes));
+    if plan.protected_skipped > 0 {
+        println!("已跳过 {} 个受保护的候选（系统保留或匹配 --keep）", plan.protected_skipped);
+    }
+    match output {
+        Some(path) => println!("方案已保存到 {}，审阅后执行: surf cleanup apply {} --yes", path.display(), path.display()),
+        None => println!("方案只是建议，未删除任何文件；--output 保存后可用 surf cleanup apply 执行"),
+    }
+    Ok(())
+}
+
+/// 执行审阅过的清理方案；未确认时只预演
+fn run_cleanup_apply(
+    cli: &Cli,
+    plan: &Path,
+    yes: bool,
+    permanent: bool,
+    allow_fallback: bool,
+    transcript_path: Option<&Path>,
+) -> Result<()> {
+    use surf_core::delete::{DeleteMode, DeleteOrigin};
+
+    let plan = surf_core::cleanup::CleanupPlan::load(plan).with_context(|| format!("读取清理方案失败: {}", plan.display()))?;
+    let mode = if permanent { DeleteMode::Permanent } else { DeleteMode::MoveToTrash };
+    let transcript = plan.apply(mode, DeleteOrigin::Cli, !yes, allow_fallback);
+    if let Some(path) = transcript_path {
+        let json = serde_json::to_vec_pretty(&transcript)?;
+        std::fs::write(path, json).with_context(|| format!("写入执行记录失败: {}", path.display()))?;
+    }
+
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&transcript)?);
+    } else {
+        let action = match (yes, permanent) {
+            (false, _) => "预演",
+            (true, false) => "移至回收站",
+            (true, true) => "永久删除",
+        };
+        println!("\n执行清理方案（{}）:", action);
+        let errors: Vec<_> = transcript.entries.iter().map(|entry| entry.result.error.as_ref()).collect();
+        print_cleanup_items(&plan, &errors);
+        // 加 --allow-permanent-fallback 时无法移入回收站的条目已退化为永久删除，需明确告知
+        let fallback = transcript
+            .entries
+            .iter()
+            .filter(|e| e.result.success && mode == DeleteMode::MoveToTrash && e.result.effective_mode == DeleteMode::Permanent)
+            .count();
+        if yes {
+            println!("\n成功 {} 项，释放 {}；失败 {} 项", transcript.succeeded, format_bytes(transcript.freed_bytes), transcript.failed);
+            if fallback > 0 {
+                println!("其中 {} 项无法移至回收站，已永久删除", fallback);
+            }
+        } else {
+            println!("\n预演：{} 项可删除，合计 {}；{} 项未通过", transcript.succeeded, format_bytes(transcript.freed_bytes), transcript.failed);
+            if fallback > 0 {
+                println!("注意：其中 {} 项无法移至回收站，执行时将被永久删除", fallback);
+            }
+            println!("未删除任何文件；确认后加 --yes 执行");
+        }
+    }
+    if transcript.failed > 0 {
+        anyhow::bail!("{} 项未能删除（在扫描后已变化、受保护、无法移至回收站或删除失败）", transcript.failed);
+    }
+    Ok(())
+}
+
+/// 生成合成目录树、扫描并与预期统计比对
+///
+/// 输出的规格 JSON 可直接附在问题报告中，维护者用 `--spec` 复现同一棵目录树。
+fn run_selftest(cli: &Cli, root: &Path, spec: Option<&Path>) -> Result<()> {
+    let spec = match spec {
+        Some(path) => surf_core::testutil::TreeSpec::load(path)
+            .with_context(|| format!("读取目录树规格失败: {}", path.display()))?,
+        None => surf_core::testutil::TreeSpec::new(),
+    };
+    if root.read_dir().is_ok_and(|mut entries| entries.next().is_some()) {
+        anyhow::bail!("生成目录必须不存在或为空: {}", root.display());
+    }
+
+    let started = std::time::Instant::now();
+    let tree = spec
+        .generate(root)
+        .with_context(|| format!("生成合成目录树失败: {}", root.display()))?;
+    let generate_secs = started.elapsed().as_secs_f64();
+
+    // 符号链接按链接本身计入；以当前用户仍可读取的“无权限目录”（例如 root）不扣除
+    let mut expected_files = tree.files + tree.symlinks.len() as u64;
+    let mut expected_bytes = tree.total_bytes
+        + tree
+            .symlinks
+            .iter()
+            .filter_map(|link| std::fs::symlink_metadata(link).ok())
+            .map(|m| m.len())
+            .sum::<u64>();
+    if tree.denied_dirs.iter().any(|dir| std::fs::read_dir(dir).is_err()) {
+        expected_files -= tree.denied_files;
+        expected_bytes -= tree.denied_bytes;
+    }
+
+    let mut request = cli.to_scan_request()?;
+    request.root_path = root.to_path_buf();
+    request.min_size = Some(0);
+    request.auto_min_size = false;
+    request.exclude_patterns.clear();
+    request.include_patterns.clear();
+    request.exclude_regex.clear();
+    request.include_regex.clear();
+    request.respect_ignore_files = false;
+    request.max_depth = None;
+    let scanned = Scanner::new().scan_sync(&request);
+    tree.restore_permissions()
+        .with_context(|| format!("恢复目录权限失败: {}", root.display()))?;
+    let result = scanned.with_context(|| format!("扫描失败: {}", root.display()))?;
+    let summary = &result.summary;
+
+    println!("目录树规格: {}", serde_json::to_string(&spec)?);
+    println!("生成耗时: {:.3}s  扫描耗时: {:.3}s", generate_secs, summary.elapsed_seconds);
+    println!("{:<8} {:>14} {:>14}", "", "预期", "实际");
+    println!("{:<8} {:>14} {:>14}", "目录数", tree.dirs, summary.total_dirs);
+    println!("{:<8} {:>14} {:>14}", "文件数", expected_files, summary.total_files);
+    println!("{:<8} {:>14} {:>14}", "总大小", expected_bytes, summary.total_size_bytes);
+
+    let matches = summary.total_dirs == tree.dirs
+        && summary.total_files == expected_files
+        && summary.total_size_bytes == expected_bytes;
+    if !matches {
+        anyhow::bail!("扫描结果与预期不一致");
+    }
+    println!("自检通过");
     Ok(())
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    // 参数基础校验（仅针对单次扫描/TUI模式）
-    // - 路径存在且可访问
-    // - limit 必须为正数
-    // - threads（如提供）必须为正数
-    if !cli.service {
-        if !cli.path.exists() {
-            anyhow::bail!("路径不存在: {}", cli.path.display());
+/// 执行扫描：从扫描缓存加载该根目录的并发提示，扫描完成后回写最新提示
+///
+/// 扫描期间在 `pb` 上显示当前阶段（如“阶段 1/3：枚举目录”）。缓存读写失败不影响扫描本身。
+fn scan_with_cached_hints(mut request: ScanRequest, pb: &indicatif::ProgressBar) -> std::io::Result<ScanResult> {
+    request.concurrency_hints = surf_core::hints::load_cached(&request.root_path).map(Arc::new);
+    let live = LiveProgress::new();
+    live.plan_phase(ScanPhase::Persisting);
+    request.progress = Some(live.clone());
+
+    // 扫描期间定期在进度条上显示当前阶段与实时计数；可估算总量时附带估算总量与预计剩余时间，限速时附带实际速率
+    let result = Scanner::new().scan_with_progress(&request, std::time::Duration::from_millis(100), |p| {
+        let mut message = format!(
+            "{}，已扫描 {} 个文件 / {} 个目录，{}",
+            p.phase,
+            p.scanned_files,
+            p.scanned_dirs,
+            format_bytes(p.scanned_bytes)
+        );
+        if let Some(total) = p.total_bytes_estimate {
+            message.push_str(&format!(" / 约 {}", format_bytes(total)));
         }
-        if cli.limit == 0 {
-            anyhow::bail!("参数 --limit 必须为正整数");
+        if let Some(eta) = p.eta_seconds {
+            message.push_str(&format!("，预计剩余 {}", format_eta(eta)));
         }
-        if let Some(t) = cli.threads {
-            if t == 0 {
-                anyhow::bail!("参数 --threads 必须为正整数");
-            }
+        if let (Some(rate), Some(limit)) = (p.io_ops_per_sec, p.io_ops_limit) {
+            message.push_str(&format!("，限速 {:.0} / {} 次/秒", rate, limit));
         }
+        pb.set_message(message);
+    })?;
+
+    if let Some(hints) = &result.concurrency_hints {
+        live.begin_phase(ScanPhase::Persisting);
+        pb.set_message(live.phase_progress().to_string());
+        let _ = surf_core::hints::store_cached(hints);
     }
-    
-    if cli.service {
-        // 服务模式：当前版本仅占位
-        println!("服务模式尚未实现，将在后续迭代中完成");
-        return Ok(());
-    }
-    
-    if cli.tui {
-        // TUI 模式
-        return run_tui(&cli);
-    }
-    
-    // 单次扫描模式
-    let request = cli.to_scan_request()?;
-    
-    // 创建进度条
-    let pb = indicatif::ProgressBar::new_spinner();
-    pb.set_style(
-        indicatif::ProgressStyle::default_spinner()
-            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
-            .template("{spinner} 扫描中... {msg}")?
-    );
-    pb.enable_steady_tick(std::time::Duration::from_millis(100));
-    
-    // 执行扫描
-    let scanner = Scanner::new();
-    let result = match scanner.scan_sync(&request) {
-        Ok(result) => {
-            pb.finish_with_message("扫描完成");
-            result
-        }
+    Ok(result)
+}
+
+/// 运行终端用户界面（TUI）模式
+fn run_tui(cli: &Cli) -> Result<()> {
+    // 离线浏览时先读取结果文件，失败时无需恢复终端
+    let loaded = cli.load.as_deref().map(load_result).transpose()?;
+    let theme = theme::Theme::resolve(cli.theme, cli.ascii);
+
+    // 初始化终端；不支持全屏界面时（哑终端、CI、输入输出被重定向）改用文本浏览模式
+    let mut terminal = match init_terminal() {
+        Ok(terminal) => terminal,
         Err(e) => {
-            pb.finish_with_message("扫描失败");
-            anyhow::bail!("扫描失败: {}", e);
+            eprintln!("无法进入全屏界面（{}），改用文本浏览模式", e);
+            let result = match loaded {
+                Some(result) => result,
+                None => scan_for_tui(cli, theme)?,
+            };
+            let stdin = std::io::stdin();
+            return browse::run(&result, cli.expand_packages, theme, cli.load.as_deref(), stdin.lock(), std::io::stdout());
         }
     };
-    
-    // 输出结果
-    if cli.json {
-        // JSON 输出（直接序列化 ScanResult）
-        println!("{}", serde_json::to_string_pretty(&result)?);
-    } else {
-        // 表格输出
-        print_table(&result, cli.limit)?;
-    }
-    
+
+    let result = match loaded {
+        Some(result) => Ok(result),
+        None => scan_for_tui(cli, theme),
+    };
+
+    // 运行 TUI 主循环（扫描失败时同样先恢复终端）
+    let res = result.and_then(|result| run_tui_loop(&mut terminal, &result, cli.expand_packages, theme, cli.load.as_deref()));
+
+    restore_terminal(&mut terminal)?;
+    res
+}
+
+/// 退出原始模式与备用屏幕，恢复终端状态
+fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
     Ok(())
 }
 
-/// 运行终端用户界面（TUI）模式
-fn run_tui(cli: &Cli) -> Result<()> {
-    // 初始化终端
-    enable_raw_mode()?;
+/// 进入原始模式与备用屏幕；任一步失败时撤销已完成的步骤并返回错误
+fn init_terminal() -> Result<Terminal<CrosstermBackend<std::io::Stdout>>> {
+    use std::io::IsTerminal;
+    if std::env::var("TERM").is_ok_and(|term| term == "dumb") {
+        anyhow::bail!("TERM=dumb");
+    }
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        anyhow::bail!("标准输入或输出不是终端");
+    }
+    enable_raw_mode().context("无法启用原始模式")?;
     let mut stdout = std::io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    if let Err(e) = execute!(stdout, EnterAlternateScreen, EnableMouseCapture) {
+        let _ = disable_raw_mode();
+        return Err(e).context("无法切换到备用屏幕");
+    }
+    Terminal::new(CrosstermBackend::new(stdout)).map_err(|e| {
+        let _ = disable_raw_mode();
+        let _ = execute!(std::io::stdout(), LeaveAlternateScreen, DisableMouseCapture);
+        anyhow::Error::from(e)
+    })
+}
 
-    // 执行扫描（带进度条）
+/// 执行扫描（带进度条），供全屏界面与文本浏览模式使用
+fn scan_for_tui(cli: &Cli, theme: theme::Theme) -> Result<ScanResult> {
     let request = cli.to_scan_request()?;
     let pb = indicatif::ProgressBar::new_spinner();
     pb.set_style(
         indicatif::ProgressStyle::default_spinner()
-            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
+            .tick_strings(theme.spinner())
             .template("{spinner} 扫描中... {msg}")?
     );
     pb.enable_steady_tick(std::time::Duration::from_millis(100));
 
-    let scanner = Scanner::new();
-    let result = match scanner.scan_sync(&request) {
+    match scan_with_cached_hints(request, &pb) {
         Ok(result) => {
             pb.finish_with_message("扫描完成");
-            result
+            Ok(result)
         }
         Err(e) => {
             pb.finish_with_message("扫描失败");
             anyhow::bail!("扫描失败: {}", e);
         }
-    };
-
-    // 清理进度条
-    drop(pb);
-
-    // 运行 TUI 主循环
-    let res = run_tui_loop(&mut terminal, &result);
-
-    // 恢复终端状态
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-
-    res
+    }
 }
 
 /// 目录树节点
@@ -306,10 +1824,16 @@ struct TreeNode {
     name: String,
     /// 完整路径
     path: PathBuf,
-    /// 文件大小（字节），目录为0
+    /// 大小（字节）；目录为扫描结果中的目录大小，未知时为0
     size_bytes: u64,
+    /// 占父目录的比例
+    share_of_parent: Option<f64>,
+    /// 占扫描总量的比例
+    share_of_total: Option<f64>,
     /// 是否为目录
     is_dir: bool,
+    /// 是否为折叠显示的包目录（如 .app），作为叶子节点呈现其累计大小
+    is_package: bool,
     /// 子节点
     children: Vec<TreeNode>,
     /// 是否展开
@@ -323,7 +1847,10 @@ impl TreeNode {
             name,
             path,
             size_bytes: 0,
+            share_of_parent: None,
+            share_of_total: None,
             is_dir: true,
+            is_package: false,
             children: Vec::new(),
             expanded: false,
         }
@@ -335,13 +1862,17 @@ impl TreeNode {
             name,
             path,
             size_bytes,
+            share_of_parent: None,
+            share_of_total: None,
             is_dir: false,
+            is_package: false,
             children: Vec::new(),
             expanded: false,
         }
     }
     
     /// 从文件路径列表构建目录树（模拟数据用）
+    #[cfg_attr(not(test), allow(dead_code))]
     fn from_paths(paths: &[PathBuf]) -> Self {
         let mut root = TreeNode::new_dir("root".to_string(), PathBuf::from("."));
         
@@ -358,9 +1889,17 @@ impl TreeNode {
         root
     }
     
-    /// 从 FileEntry 列表构建目录树（真实数据用）
-    fn from_file_entries(entries: &[surf_core::FileEntry]) -> Self {
+    /// 从 FileEntry 列表构建目录树（真实数据用），目录节点的大小与占比取自目录统计
+    ///
+    /// `expand_packages` 为 false 时，包目录（如 .app）作为叶子节点显示其累计大小，不展开内部文件。
+    fn from_file_entries(
+        entries: &[surf_core::FileEntry],
+        directories: &[surf_core::DirStat],
+        expand_packages: bool,
+    ) -> Self {
         let mut root = TreeNode::new_dir("root".to_string(), PathBuf::from("."));
+        let dirs: HashMap<&Path, &surf_core::DirStat> =
+            directories.iter().map(|d| (d.path.as_path(), d)).collect();
         
         for entry in entries {
             let path = &entry.path;
@@ -370,13 +1909,14 @@ impl TreeNode {
             }
             
             // 使用递归辅助函数插入路径，并传递文件大小信息
-            Self::insert_file_entry(&mut root, &components, 0, entry);
+            Self::insert_file_entry(&mut root, &components, 0, entry, &dirs, expand_packages);
         }
         
         root
     }
     
     /// 递归插入路径组件
+    #[cfg_attr(not(test), allow(dead_code))]
     fn insert_path(node: &mut TreeNode, components: &[std::path::Component], depth: usize) {
         if depth >= components.len() {
             return;
@@ -412,7 +1952,14 @@ impl TreeNode {
     }
     
     /// 递归插入文件条目路径组件，携带文件大小信息
-    fn insert_file_entry(node: &mut TreeNode, components: &[std::path::Component], depth: usize, entry: &surf_core::FileEntry) {
+    fn insert_file_entry(
+        node: &mut TreeNode,
+        components: &[std::path::Component],
+        depth: usize,
+        entry: &surf_core::FileEntry,
+        dirs: &HashMap<&Path, &surf_core::DirStat>,
+        expand_packages: bool,
+    ) {
         if depth >= components.len() {
             return;
         }
@@ -424,25 +1971,42 @@ impl TreeNode {
         let child_index = node.children.iter().position(|child| child.name == component_str);
         
         if let Some(index) = child_index {
-            // 节点已存在，继续递归
-            Self::insert_file_entry(&mut node.children[index], components, depth + 1, entry);
+            // 节点已存在，继续递归（折叠的包目录已计入累计大小，无需再插入内部文件）
+            if !node.children[index].is_package {
+                Self::insert_file_entry(&mut node.children[index], components, depth + 1, entry, dirs, expand_packages);
+            }
         } else {
             // 创建新节点
             let is_dir = depth < components.len() - 1;
             let child_path = node.path.join(&component_str);
+            let real_path: PathBuf = components[..=depth].iter().collect();
+            let is_package = is_dir && !expand_packages && surf_core::package::is_package(&real_path);
             let new_node = if is_dir {
-                TreeNode::new_dir(component_str.clone(), child_path.clone())
+                let mut dir = TreeNode::new_dir(component_str.clone(), child_path.clone());
+                if let Some(stat) = dirs.get(real_path.as_path()) {
+                    dir.size_bytes = stat.size_bytes;
+                    dir.share_of_parent = stat.share_of_parent;
+                    dir.share_of_total = Some(stat.share_of_total);
+                }
+                if is_package {
+                    dir.is_dir = false;
+                    dir.is_package = true;
+                }
+                dir
             } else {
-                // 文件节点：设置实际文件大小
-                TreeNode::new_file(component_str.clone(), child_path.clone(), entry.size_bytes)
+                // 文件节点：设置实际文件大小与占比
+                let mut file = TreeNode::new_file(component_str.clone(), child_path.clone(), entry.size_bytes);
+                file.share_of_parent = entry.share_of_parent;
+                file.share_of_total = entry.share_of_total;
+                file
             };
             
             node.children.push(new_node);
             
             // 继续递归（如果是目录）
-            if is_dir {
+            if is_dir && !is_package {
                 let last_index = node.children.len() - 1;
-                Self::insert_file_entry(&mut node.children[last_index], components, depth + 1, entry);
+                Self::insert_file_entry(&mut node.children[last_index], components, depth + 1, entry, dirs, expand_packages);
             }
         }
     }
@@ -462,7 +2026,10 @@ impl TreeNode {
                 name: self.name.clone(),
                 path: self.path.clone(),
                 size_bytes: self.size_bytes,
+                share_of_parent: self.share_of_parent,
+                share_of_total: self.share_of_total,
                 is_dir: self.is_dir,
+                is_package: self.is_package,
                 depth: depth - 1, // 因为根节点深度为0，实际显示时减1
                 expanded: self.expanded,
                 has_children: !self.children.is_empty(),
@@ -488,6 +2055,309 @@ struct TuiState {
     flat_nodes: Vec<FlatNode>,
     /// 当前选中的文件条目（用于详情显示）
     selected_file: Option<surf_core::FileEntry>,
+    /// 左侧面板当前视图
+    view: TuiView,
+    /// 顶层速览与目录密度视图中选中行的索引
+    density_index: usize,
+    /// 是否展开包目录内部结构
+    expand_packages: bool,
+    /// 界面主题
+    theme: theme::Theme,
+}
+
+impl TuiState {
+    /// 初始状态：顶层速览视图，目录树默认展开第一级
+    fn new(result: &ScanResult, expand_packages: bool, theme: theme::Theme) -> Self {
+        let mut state = TuiState {
+            tree_root: TreeNode::new_dir("root".to_string(), PathBuf::from(".")),
+            selected_index: 0,
+            flat_nodes: Vec::new(),
+            selected_file: None,
+            view: TuiView::TopLevel,
+            density_index: 0,
+            expand_packages,
+            theme,
+        };
+        state.rebuild_tree(result);
+        state
+    }
+
+    /// 按当前包目录展开设置重建目录树，默认展开第一级
+    fn rebuild_tree(&mut self, result: &ScanResult) {
+        let mut tree_root = TreeNode::from_file_entries(&result.top_files, &result.directories, self.expand_packages);
+        for child in &mut tree_root.children {
+            child.expanded = true;
+        }
+        self.flat_nodes = tree_root.flatten();
+        self.tree_root = tree_root;
+        self.selected_index = 0;
+        self.selected_file = None;
+    }
+
+    /// 目录树中选中节点的 Enter 操作：目录切换展开状态，文件显示详情
+    fn activate_selected(&mut self, result: &ScanResult) {
+        let Some(node) = self.flat_nodes.get(self.selected_index) else {
+            return;
+        };
+        if node.is_dir {
+            toggle_node_expansion(&mut self.tree_root, &node.path.clone());
+            // 重新扁平化树，保持选中索引不变
+            self.flat_nodes = self.tree_root.flatten();
+        } else {
+            self.selected_file = result.top_files.iter().find(|entry| entry.path == node.path).cloned();
+        }
+    }
+}
+
+/// TUI 左侧面板视图（Tab 键循环切换）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TuiView {
+    /// 顶层速览：根目录各直接子项的累计大小与占比（启动时的默认视图）
+    TopLevel,
+    /// 文件类别分布：图片、视频、文档、代码等
+    Categories,
+    /// 目录树
+    Tree,
+    /// 大文件目录：每文件字节数最大
+    Heavy,
+    /// 碎文件目录：每 MiB 文件数最大
+    Crowded,
+}
+
+impl TuiView {
+    fn next(self) -> Self {
+        match self {
+            TuiView::TopLevel => TuiView::Categories,
+            TuiView::Categories => TuiView::Tree,
+            TuiView::Tree => TuiView::Heavy,
+            TuiView::Heavy => TuiView::Crowded,
+            TuiView::Crowded => TuiView::TopLevel,
+        }
+    }
+}
+
+/// 当前视图对应的目录密度排名；其他视图返回 None
+fn density_rows(result: &ScanResult, view: TuiView) -> Option<&[surf_core::DirDensity]> {
+    match view {
+        TuiView::TopLevel | TuiView::Categories | TuiView::Tree => None,
+        TuiView::Heavy => Some(&result.density.heavy),
+        TuiView::Crowded => Some(&result.density.crowded),
+    }
+}
+
+/// 列表视图（顶层速览、类别分布与目录密度排名）的行数；目录树视图返回 0
+fn list_len(result: &ScanResult, view: TuiView) -> usize {
+    match view {
+        TuiView::TopLevel => result.top_level.len(),
+        TuiView::Categories => result.by_category.len(),
+        _ => density_rows(result, view).map_or(0, <[_]>::len),
+    }
+}
+
+/// 渲染左侧列表：选中行按主题高亮（高对比度与 ASCII 模式另以 `>` 前缀标记）
+fn list_widget(title: String, rows: Vec<String>, selected: usize, theme: theme::Theme) -> ratatui::widgets::List<'static> {
+    let items: Vec<ratatui::widgets::ListItem> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let text = format!("{}{}", theme.marker(i == selected), row);
+            let item = ratatui::widgets::ListItem::new(ratatui::text::Line::from(text));
+            if i == selected {
+                item.style(theme.selected_style())
+            } else {
+                item
+            }
+        })
+        .collect();
+    ratatui::widgets::List::new(items).block(panel(title, theme))
+}
+
+/// 带边框与标题的面板
+fn panel(title: impl Into<String>, theme: theme::Theme) -> ratatui::widgets::Block<'static> {
+    ratatui::widgets::Block::default()
+        .title(title.into())
+        .borders(ratatui::widgets::Borders::ALL)
+        .border_set(theme.border_set())
+}
+
+/// 类别详情：文件数、大小与该类别下最大的几个扩展名
+fn category_detail(result: &ScanResult, row: &surf_core::CategoryStat) -> String {
+    let extensions: Vec<String> = result
+        .by_extension
+        .iter()
+        .filter(|stat| surf_core::FileCategory::of_extension(&stat.extension) == row.category)
+        .take(5)
+        .map(|stat| format!("  .{} {}（{} 个文件）", stat.extension, format_bytes(stat.total_size_bytes), stat.file_count))
+        .collect();
+    format!(
+        "选中类别: {}\n\n文件数: {}\n大小: {}\n占总量: {}\n\n主要扩展名:\n{}",
+        row.category.label(),
+        row.file_count,
+        format_bytes(row.total_size_bytes),
+        format_share(Some(row.share_of_total)),
+        extensions.join("\n")
+    )
+}
+
+/// 视图标题（全屏界面与文本浏览模式共用）
+fn view_title(result: &ScanResult, view: TuiView) -> String {
+    match view {
+        TuiView::TopLevel => "顶层速览（根目录各直接子项）".to_string(),
+        TuiView::Categories => "文件类别分布".to_string(),
+        TuiView::Tree => format!("目录树 (Top {} 大文件)", result.top_files.len()),
+        TuiView::Heavy => "大文件目录（每文件字节数）".to_string(),
+        TuiView::Crowded => "碎文件目录（每 MiB 文件数）".to_string(),
+    }
+}
+
+/// 顶层速览的一行：按占比绘制的条形、占比、大小与名称
+fn top_level_row(result: &ScanResult, row: &TopLevelStat, theme: theme::Theme) -> String {
+    let bar = theme.bar(row.share_of_total);
+    format!(
+        "{:<20} {:>7} {:>10} {}",
+        bar,
+        format_share(Some(row.share_of_total)),
+        format_bytes(row.size_bytes),
+        top_level_name(row, &result.summary.root_path)
+    )
+}
+
+/// 类别分布的一行
+fn category_row(row: &surf_core::CategoryStat, theme: theme::Theme) -> String {
+    let bar = theme.bar(row.share_of_total);
+    format!(
+        "{:<20} {:>7} {:>10} {}",
+        bar,
+        format_share(Some(row.share_of_total)),
+        format_bytes(row.total_size_bytes),
+        row.category.label()
+    )
+}
+
+/// 目录密度排名的一行
+fn density_row(view: TuiView, row: &surf_core::DirDensity) -> String {
+    let metric = match view {
+        TuiView::Heavy => format!("{}/文件", format_bytes(row.bytes_per_file)),
+        _ => format!("{} 文件/MiB", row.files_per_mib),
+    };
+    format!("{} ({}, {} 个文件, {})", row.path.display(), metric, row.file_count, format_bytes(row.size_bytes))
+}
+
+/// 目录树的一行：缩进、展开状态前缀、名称、大小与占父目录比例
+fn tree_row(node: &FlatNode) -> String {
+    // 根据节点类型和展开状态构造前缀
+    let prefix = if node.is_dir {
+        if node.expanded { "[-] " } else { "[+] " }
+    } else if node.is_package {
+        "[包] "
+    } else {
+        "    "
+    };
+    let indent = "  ".repeat(node.depth);
+    if node.is_dir && node.share_of_total.is_none() {
+        format!("{}{}{}", indent, prefix, node.name)
+    } else {
+        format!(
+            "{}{}{} ({}, {})",
+            indent,
+            prefix,
+            node.name,
+            format_bytes(node.size_bytes),
+            format_share(node.share_of_parent)
+        )
+    }
+}
+
+/// 当前视图的全部行文本（文本浏览模式分页显示）
+fn view_rows(result: &ScanResult, state: &TuiState) -> Vec<String> {
+    match state.view {
+        TuiView::TopLevel => result.top_level.iter().map(|row| top_level_row(result, row, state.theme)).collect(),
+        TuiView::Categories => result.by_category.iter().map(|row| category_row(row, state.theme)).collect(),
+        TuiView::Tree => state.flat_nodes.iter().map(tree_row).collect(),
+        view => density_rows(result, view)
+            .unwrap_or_default()
+            .iter()
+            .map(|row| density_row(view, row))
+            .collect(),
+    }
+}
+
+/// 选中项的详情文本（全屏界面右侧面板与文本浏览模式共用）
+fn detail_text(result: &ScanResult, state: &TuiState, loaded_from: Option<&Path>) -> String {
+    let selected_density = density_rows(result, state.view).and_then(|rows| rows.get(state.density_index));
+    let selected_top = (state.view == TuiView::TopLevel)
+        .then(|| result.top_level.get(state.density_index))
+        .flatten();
+    let selected_category = (state.view == TuiView::Categories)
+        .then(|| result.by_category.get(state.density_index))
+        .flatten();
+    let detail_text = if let Some(row) = selected_category {
+        category_detail(result, row)
+    } else if let Some(row) = selected_top {
+        format!(
+            "选中项: {}\n\n路径: {}\n大小: {}\n文件数: {}\n目录数: {}\n占总量: {}",
+            top_level_name(row, &result.summary.root_path),
+            row.path.display(),
+            format_bytes(row.size_bytes),
+            row.file_count,
+            row.dir_count,
+            format_share(Some(row.share_of_total))
+        )
+    } else if let Some(row) = selected_density {
+        format!(
+            "选中目录: {}\n\n直接文件数: {}\n直接文件大小: {}\n每文件字节数: {}\n每 MiB 文件数: {}",
+            row.path.display(),
+            row.file_count,
+            format_bytes(row.size_bytes),
+            format_bytes(row.bytes_per_file),
+            row.files_per_mib
+        )
+    } else if let Some(ref file) = state.selected_file {
+        let size_str = format_bytes(file.size_bytes);
+        let ext_str = file.extension.as_deref().unwrap_or("无扩展名");
+        let modified_str = file.last_modified
+            .and_then(|t| t.elapsed().ok())
+            .map(|d| format!("{} 天前", d.as_secs() / 86400))
+            .unwrap_or_else(|| "未知".to_string());
+        format!("选中文件: {}\n\n路径: {}\n大小: {}\n占父目录: {}\n占总量: {}\n扩展名: {}\n最后修改: {}", 
+            file.path.file_name().and_then(|n| n.to_str()).unwrap_or(""),
+            file.path.display(),
+            size_str,
+            format_share(file.share_of_parent),
+            format_share(file.share_of_total),
+            ext_str,
+            modified_str)
+    } else if state.selected_index < state.flat_nodes.len() {
+        let node = &state.flat_nodes[state.selected_index];
+        let size_str = format_bytes(node.size_bytes);
+        let node_type = if node.is_package {
+            "包（按 p 展开内部结构）"
+        } else if node.is_dir {
+            "目录"
+        } else {
+            "文件"
+        };
+        format!("选中节点: {}\n\n路径: {}\n类型: {}\n大小: {}\n占父目录: {}\n占总量: {}", 
+            node.name,
+            node.path.display(),
+            node_type,
+            size_str,
+            format_share(node.share_of_parent),
+            format_share(node.share_of_total))
+    } else {
+        "无选中节点".to_string()
+    };
+    // 离线浏览时结果来自其他时间或机器，提示选中路径在本机是否仍然存在（类别没有路径）
+    let selected_path = selected_top
+        .map(|row| row.path.as_path())
+        .or(selected_density.map(|row| row.path.as_path()))
+        .or(state.selected_file.as_ref().map(|file| file.path.as_path()))
+        .or(state.flat_nodes.get(state.selected_index).map(|node| node.path.as_path()))
+        .filter(|_| selected_category.is_none());
+    match (loaded_from, selected_path) {
+        (Some(_), Some(path)) => format!("{}\n本机路径: {}", detail_text, path_status(path)),
+        _ => detail_text,
+    }
 }
 
 /// 扁平化的树节点，用于渲染
@@ -496,12 +2366,16 @@ struct FlatNode {
     name: String,
     path: PathBuf,
     size_bytes: u64,
+    share_of_parent: Option<f64>,
+    share_of_total: Option<f64>,
     is_dir: bool,
+    is_package: bool,
     /// 缩进层级
     depth: usize,
     /// 是否展开（仅对目录有效）
     expanded: bool,
     /// 是否有子节点
+    #[cfg_attr(not(test), allow(dead_code))]
     has_children: bool,
 }
 
@@ -526,21 +2400,15 @@ fn toggle_node_expansion(node: &mut TreeNode, target_path: &PathBuf) -> bool {
 }
 
 /// TUI 主事件循环
-fn run_tui_loop(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>, result: &surf_core::ScanResult) -> Result<()> {
-    // 使用真实扫描结果构建目录树
-    let mut tree_root = TreeNode::from_file_entries(&result.top_files);
-    // 默认展开根节点的直接子节点（即第一级目录/文件）
-    for child in &mut tree_root.children {
-        child.expanded = true;
-    }
-    
-    // 初始化 TUI 状态
-    let mut state = TuiState {
-        tree_root: tree_root.clone(),
-        selected_index: 0,
-        flat_nodes: tree_root.flatten(),
-        selected_file: None,
-    };
+fn run_tui_loop(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    result: &surf_core::ScanResult,
+    expand_packages: bool,
+    theme: theme::Theme,
+    loaded_from: Option<&Path>,
+) -> Result<()> {
+    // 使用真实扫描结果构建目录树，默认展开根节点的直接子节点（即第一级目录/文件）
+    let mut state = TuiState::new(result, expand_packages, theme);
     
     loop {
         terminal.draw(|f| {
@@ -563,93 +2431,41 @@ fn run_tui_loop(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>, resu
                 ])
                 .split(main_chunks[0]);
             
-            // 左侧：目录树视图（使用 List widget）
-            let list_block = ratatui::widgets::Block::default()
-                .title(format!("目录树 (Top {} 大文件)", result.top_files.len()))
-                .borders(ratatui::widgets::Borders::ALL);
-            
-            // 创建 List 项，带缩进
-            let items: Vec<ratatui::widgets::ListItem> = state.flat_nodes
-                .iter()
-                .enumerate()
-                .map(|(i, node)| {
-                    // 根据节点类型和展开状态构造前缀
-                    let prefix = if node.is_dir {
-                        if node.expanded { "[-] " } else { "[+] " }
-                    } else {
-                        "    "
-                    };
-                    // 缩进空格
-                    let indent = "  ".repeat(node.depth);
-                    // 显示名称和大小
-                    let display_name = if node.is_dir {
-                        format!("{}{}{}", indent, prefix, node.name)
-                    } else {
-                        let size_str = format_bytes(node.size_bytes);
-                        format!("{}{}{} ({})", indent, prefix, node.name, size_str)
-                    };
-                    let content = ratatui::text::Line::from(display_name);
-                    if i == state.selected_index {
-                        ratatui::widgets::ListItem::new(content)
-                            .style(ratatui::style::Style::default().bg(ratatui::style::Color::Blue))
-                    } else {
-                        ratatui::widgets::ListItem::new(content)
-                    }
-                })
-                .collect();
-            
-            let list = ratatui::widgets::List::new(items)
-                .block(list_block)
-                .highlight_symbol("> ")
-                .highlight_style(ratatui::style::Style::default().bg(ratatui::style::Color::DarkGray));
-            
-            f.render_widget(list, content_chunks[0]);
+            // 左侧：顶层速览、类别分布、目录树视图或目录密度排名（Tab 切换）
+            let (title, selected) = if state.view == TuiView::Tree {
+                (view_title(result, TuiView::Tree), state.selected_index)
+            } else {
+                (format!("{} [Tab 切换]", view_title(result, state.view)), state.density_index)
+            };
+            f.render_widget(list_widget(title, view_rows(result, &state), selected, theme), content_chunks[0]);
             
             // 右侧：文件详情
-            let detail_block = ratatui::widgets::Block::default()
-                .title("文件详情")
-                .borders(ratatui::widgets::Borders::ALL);
+            let detail_block = panel("文件详情", theme);
             
-            // 更新详情文本
-            let detail_text = if let Some(ref file) = state.selected_file {
-                let size_str = format_bytes(file.size_bytes);
-                let ext_str = file.extension.as_deref().unwrap_or("无扩展名");
-                let modified_str = file.last_modified
-                    .and_then(|t| t.elapsed().ok())
-                    .map(|d| format!("{} 天前", d.as_secs() / 86400))
-                    .unwrap_or_else(|| "未知".to_string());
-                format!("选中文件: {}\n\n路径: {}\n大小: {}\n扩展名: {}\n最后修改: {}", 
-                    file.path.file_name().and_then(|n| n.to_str()).unwrap_or(""),
-                    file.path.display(),
-                    size_str,
-                    ext_str,
-                    modified_str)
-            } else if state.selected_index < state.flat_nodes.len() {
-                let node = &state.flat_nodes[state.selected_index];
-                let size_str = format_bytes(node.size_bytes);
-                let node_type = if node.is_dir { "目录" } else { "文件" };
-                format!("选中节点: {}\n\n路径: {}\n类型: {}\n大小: {}", 
-                    node.name,
-                    node.path.display(),
-                    node_type,
-                    size_str)
-            } else {
-                "无选中节点".to_string()
-            };
+            let detail_text = detail_text(result, &state, loaded_from);
             
             let detail_paragraph = ratatui::widgets::Paragraph::new(detail_text)
                 .block(detail_block);
             f.render_widget(detail_paragraph, content_chunks[1]);
             
             // 底部状态栏
-            let status_text = format!("Surf TUI | 扫描: {} 文件, {} 目录, {} | 选中: {}/{} | ↑↓ 导航, Enter 展开/折叠, Enter 查看, q/Esc 退出", 
+            let source = loaded_from.map_or_else(String::new, |path| format!(" | 离线结果: {}", path.display()));
+            let access = if result.summary.full_disk_access_missing {
+                " | 缺少完全磁盘访问权限，受保护目录未计入（a 打开系统设置）"
+            } else {
+                ""
+            };
+            let status_text = format!("Surf TUI{}{} | 扫描: {} 文件, {} 目录, {} | 选中: {}/{} | {} 导航, Enter 展开/折叠, Enter 查看, Tab 切换视图, p 展开/折叠包, q/Esc 退出", 
+                source,
+                access,
                 result.summary.total_files,
                 result.summary.total_dirs,
                 format_bytes(result.summary.total_size_bytes),
                 state.selected_index + 1,
-                state.flat_nodes.len());
+                state.flat_nodes.len(),
+                theme.arrows());
             let status_bar = ratatui::widgets::Paragraph::new(status_text)
-                .style(ratatui::style::Style::default().bg(ratatui::style::Color::DarkGray));
+                .style(theme.status_style());
             f.render_widget(status_bar, main_chunks[1]);
         })?;
 
@@ -659,35 +2475,37 @@ fn run_tui_loop(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>, resu
                 KeyCode::Char('q') | KeyCode::Esc => {
                     break;
                 }
-                KeyCode::Down => {
-                    // 向下移动选中项
-                    if state.selected_index < state.flat_nodes.len() - 1 {
-                        state.selected_index += 1;
+                KeyCode::Char('a') if result.summary.full_disk_access_missing => {
+                    // 打开失败时无处显示错误，忽略即可（状态栏提示仍在）
+                    let _ = surf_core::full_disk_access::open_settings();
+                }
+                KeyCode::Char('p') => {
+                    state.expand_packages = !state.expand_packages;
+                    state.rebuild_tree(result);
+                }
+                KeyCode::Tab => {
+                    state.view = state.view.next();
+                    state.density_index = 0;
+                }
+                KeyCode::Down if state.view != TuiView::Tree => {
+                    let len = list_len(result, state.view);
+                    if state.density_index + 1 < len {
+                        state.density_index += 1;
                     }
                 }
-                KeyCode::Up => {
+                KeyCode::Up if state.view != TuiView::Tree => {
+                    state.density_index = state.density_index.saturating_sub(1);
+                }
+                KeyCode::Down if state.selected_index + 1 < state.flat_nodes.len() => {
+                    // 向下移动选中项
+                    state.selected_index += 1;
+                }
+                KeyCode::Up if state.selected_index > 0 => {
                     // 向上移动选中项
-                    if state.selected_index > 0 {
-                        state.selected_index -= 1;
-                    }
+                    state.selected_index -= 1;
                 }
-                KeyCode::Enter => {
-                    // Enter 键：处理目录展开/折叠或文件详情
-                    if state.selected_index < state.flat_nodes.len() {
-                        let node = &state.flat_nodes[state.selected_index];
-                        if node.is_dir {
-                            // 目录：切换展开状态
-                            toggle_node_expansion(&mut state.tree_root, &node.path);
-                            // 重新扁平化树
-                            state.flat_nodes = state.tree_root.flatten();
-                            // 保持选中索引不变（如果可能）
-                        } else {
-                            // 文件：查找对应的 FileEntry
-                            state.selected_file = result.top_files.iter()
-                                .find(|entry| entry.path == node.path)
-                                .cloned();
-                        }
-                    }
+                KeyCode::Enter if state.selected_index < state.flat_nodes.len() => {
+                    state.activate_selected(result);
                 }
                 _ => {}
             }
@@ -697,6 +2515,15 @@ fn run_tui_loop(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>, resu
     Ok(())
 }
 
+/// 路径在本机的校验状态（离线浏览保存的结果时显示）
+fn path_status(path: &Path) -> &'static str {
+    match std::fs::symlink_metadata(path) {
+        Ok(_) => "存在",
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => "已不存在（结果可能已过期）",
+        Err(_) => "无法访问",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -727,9 +2554,10 @@ mod tests {
             "--host", "0.0.0.0",
             "--port", "4321",
             "--json",
+            "--time-format", "rfc3339",
         ];
-        let cli = Cli::try_parse_from(&args).expect("CLI 参数解析失败");
-        assert_eq!(cli.path, PathBuf::from("/tmp"));
+        let cli = Cli::try_parse_from(args).expect("CLI 参数解析失败");
+        assert_eq!(cli.paths, vec![PathBuf::from("/tmp")]);
         assert_eq!(cli.threads, Some(4));
         assert_eq!(cli.min_size.as_deref(), Some("10KB"));
         assert_eq!(cli.limit, 10);
@@ -738,32 +2566,155 @@ mod tests {
         assert_eq!(cli.host, "0.0.0.0");
         assert_eq!(cli.port, 4321);
         assert!(cli.json);
+        assert_eq!(cli.time_format, TimeFormat::Rfc3339);
         assert!(!cli.service);
         assert!(!cli.tui);
+        assert_eq!(cli.group_by, None);
+
+        // 顶层速览不保留文件条目
+        let cli = Cli::try_parse_from(["surf", "--group-by", "top"]).unwrap();
+        assert_eq!(cli.group_by, Some(GroupBy::Top));
+        assert!(!cli.to_scan_request().unwrap().collect_entries);
+        assert!(Cli::try_parse_from(["surf", "--group-by", "owner"]).is_err());
+
+        // 重复 --path 时同时扫描多个根目录，单个 --path 不设置 roots
+        let cli = Cli::try_parse_from(["surf", "-p", "/home", "--path", "/var"]).unwrap();
+        let request = cli.to_scan_request().unwrap();
+        assert_eq!(request.root_path, PathBuf::from("/home"));
+        assert_eq!(request.roots, vec![PathBuf::from("/home"), PathBuf::from("/var")]);
+        let cli = Cli::try_parse_from(["surf"]).unwrap();
+        assert_eq!(cli.paths, vec![PathBuf::from(".")]);
+        assert!(cli.to_scan_request().unwrap().roots.is_empty());
+
+        // --hash-rate 需要重复检测或 --hash 之一
+        assert!(Cli::try_parse_from(["surf", "--hash-rate", "1MB"]).is_err());
+        let cli = Cli::try_parse_from(["surf", "--hash", "sha256", "--hash-rate", "1MB"]).unwrap();
+        assert_eq!(cli.hash, Some(surf_core::HashAlgo::Sha256));
+
+        // --size-histogram-csv 需要至少一个 --size-histogram
+        assert!(Cli::try_parse_from(["surf", "--size-histogram-csv", "h.csv"]).is_err());
+        let cli = Cli::try_parse_from(["surf", "--size-histogram", "jpg", "--size-histogram", "category:image", "--size-histogram-csv", "h.csv"])
+            .unwrap();
+        assert_eq!(cli.size_histogram, vec!["jpg", "category:image"]);
+        assert_eq!(cli.size_histogram_csv, Some(PathBuf::from("h.csv")));
     }
 
     #[test]
     fn test_cli_to_scan_request_fields() {
         let cli = Cli {
-            path: PathBuf::from("."),
+            paths: vec![PathBuf::from(".")],
             threads: Some(2),
             min_size: Some("2KB".to_string()),
             limit: 5,
             stale_days: Some(30),
+            stale_mode: surf_core::StaleMode::Accessed,
+            stale_limit: Some(100),
+            empty_limit: Some(50),
+            stale_sort: Some(surf_core::StaleSort::Size),
             service: false,
             port: 1234,
             host: "127.0.0.1".to_string(),
             json: false,
+            time_format: TimeFormat::Millis,
             tui: false,
+            expand_packages: false,
+            theme: None,
+            ascii: false,
             exclude: vec!["**/*.log".to_string(), "tmp/**".to_string()],
+            include: vec!["**/*.{mp4,mkv}".to_string()],
+            exclude_regex: vec![r"/\d{4}-\d{2}-\d{2}$".to_string()],
+            include_regex: Vec::new(),
+            respect_ignore: true,
+            max_depth: Some(3),
+            access_log: None,
+            list_suspicious_timestamps: true,
+            list_redundant_files: true,
+            recency_limit: Some(7),
+            extension_top: Some(4),
+            size_histogram: vec!["mp4".to_string(), "category:video".to_string()],
+            size_histogram_csv: None,
+            metadata_retries: 5,
+            fail_fast: true,
+            incremental: true,
+            caffeinate: true,
+            opaque_packages: true,
+            memory_budget: Some("64MB".to_string()),
+            max_io_ops: Some(500),
+            nice: true,
+            follow_symlinks: SymlinkPolicy::FollowDirs,
+            same_filesystem: true,
+            dedupe_hardlinks: true,
+            allocated: true,
+            owners: true,
+            summary_only: false,
+            group_by: None,
+            top_dirs_depth: 2,
+            duplicates: true,
+            hash_threads: Some(2),
+            hash: Some(surf_core::HashAlgo::Blake3),
+            hash_rate: Some("10MB".to_string()),
+            mime: true,
+            mime_min_size: Some("1KB".to_string()),
+            compressible: true,
+            compress_min_size: Some("100MB".to_string()),
+            compress_min_age: Some(365),
+            compress_sample: Some("64KB".to_string()),
+            remote: None,
+            remote_fingerprint: None,
+            load: None,
+            elevate: false,
+            image: None,
+            plugins: Some(PathBuf::from("/nonexistent/surf-plugins")),
+            command: None,
         };
         let req = cli.to_scan_request().expect("转换 ScanRequest 失败");
         assert_eq!(req.root_path, PathBuf::from("."));
         assert_eq!(req.threads, Some(2));
         assert_eq!(req.min_size, Some(2048));
+        assert!(req.auto_min_size);
         assert_eq!(req.limit, Some(5));
         assert_eq!(req.stale_days, Some(30));
+        assert_eq!(req.stale_mode, surf_core::StaleMode::Accessed);
+        assert_eq!(req.stale_limit, Some(100));
+        assert_eq!(req.empty_limit, Some(50));
+        assert_eq!(req.stale_sort, Some(surf_core::StaleSort::Size));
         assert_eq!(req.exclude_patterns, vec!["**/*.log".to_string(), "tmp/**".to_string()]);
+        assert_eq!(req.include_patterns, vec!["**/*.{mp4,mkv}".to_string()]);
+        assert_eq!(req.exclude_regex, vec![r"/\d{4}-\d{2}-\d{2}$".to_string()]);
+        assert!(req.respect_ignore_files);
+        assert_eq!(req.max_depth, Some(3));
+        assert!(req.list_suspicious_timestamps);
+        assert!(req.list_redundant_files);
+        assert_eq!(req.recency_limit, Some(7));
+        assert_eq!(req.extension_top_limit, Some(4));
+        assert_eq!(req.size_histograms, vec!["mp4", "category:video"]);
+        assert!(req.plugins.is_empty());
+        assert_eq!(req.metadata_retry.max_retries, 5);
+        assert!(req.fail_fast);
+        assert!(req.incremental);
+        assert!(req.keep_awake);
+        assert!(req.opaque_packages);
+        assert_eq!(req.memory_budget, Some(64 * 1024 * 1024));
+        assert_eq!(req.max_io_ops_per_sec, Some(500));
+        assert!(req.nice_mode);
+        assert_eq!(req.follow_symlinks, SymlinkPolicy::FollowDirs);
+        assert!(req.same_filesystem);
+        assert!(req.deduplicate_hardlinks);
+        assert!(req.allocated_sizes);
+        assert!(req.collect_owners);
+        assert!(req.collect_entries);
+        assert_eq!(req.top_dirs_depth, 2);
+        let dedup = req.dedup.expect("启用 --duplicates 时应设置重复检测选项");
+        assert_eq!(req.compute_hash, Some(surf_core::HashAlgo::Blake3));
+        assert_eq!(req.hash_max_bytes_per_sec, Some(10 * 1024 * 1024));
+        assert!(req.detect_mime);
+        assert_eq!(req.mime_min_size, 1024);
+        let compression = req.compression.clone().expect("启用 --compressible 时应设置可压缩分析选项");
+        assert_eq!(compression.min_size, 100 * 1024 * 1024);
+        assert_eq!(compression.min_age_days, 365);
+        assert_eq!(compression.sample_bytes, 64 * 1024);
+        assert_eq!(dedup.hash_threads, Some(2));
+        assert_eq!(dedup.max_bytes_per_sec, Some(10 * 1024 * 1024));
     }
 
     #[test]
@@ -773,43 +2724,30 @@ mod tests {
         assert_eq!(format_bytes(1024), "1.00 KB");
         assert_eq!(format_bytes(1024 * 1024), "1.00 MB");
         assert_eq!(format_bytes(1024 * 1024 * 2), "2.00 MB");
+        assert_eq!(format_eta(45), "45 秒");
+        assert_eq!(format_eta(200), "3 分 20 秒");
+        assert_eq!(format_eta(3900), "1 小时 5 分");
     }
 
     #[test]
     fn test_json_output_structure_contains_keys() {
         // 构造一个最小可用的 ScanResult，用于验证 JSON 输出结构
-        let summary = surf_core::ScanSummary {
-            root_path: PathBuf::from("."),
-            total_files: 3,
-            total_dirs: 1,
-            total_size_bytes: 12345,
-            elapsed_seconds: 0.12,
-        };
-        let top_files = vec![
-            surf_core::FileEntry {
-                path: PathBuf::from("a.bin"),
-                size_bytes: 100,
-                last_modified: None,
-                extension: Some("bin".to_string()),
-            },
-            surf_core::FileEntry {
-                path: PathBuf::from("b.log"),
-                size_bytes: 50,
-                last_modified: None,
-                extension: Some("log".to_string()),
-            },
+        // 结果类型为 #[non_exhaustive]，通过 Default 与构造函数构建
+        let mut result = surf_core::ScanResult::default();
+        result.summary.root_path = PathBuf::from(".");
+        result.summary.total_files = 3;
+        result.summary.total_dirs = 1;
+        result.summary.total_size_bytes = 12345;
+        result.summary.elapsed_seconds = 0.12;
+        result.top_files = vec![
+            surf_core::FileEntry::new("a.bin", 100),
+            surf_core::FileEntry::new("b.log", 50),
         ];
-        let by_extension = vec![surf_core::ExtensionStat {
-            extension: "bin".to_string(),
-            file_count: 2,
-            total_size_bytes: 150,
-        }];
-        let result = surf_core::ScanResult {
-            summary,
-            top_files,
-            by_extension,
-            stale_files: Vec::new(),
-        };
+        let mut bin_stat = surf_core::ExtensionStat::default();
+        bin_stat.extension = "bin".to_string();
+        bin_stat.file_count = 2;
+        bin_stat.total_size_bytes = 150;
+        result.by_extension = vec![bin_stat];
 
         let json_str = serde_json::to_string_pretty(&result).expect("JSON 序列化失败");
         let v: Value = serde_json::from_str(&json_str).expect("JSON 解析失败");
@@ -819,6 +2757,7 @@ mod tests {
         assert!(v.get("top_files").is_some());
         assert!(v.get("by_extension").is_some());
         assert!(v.get("stale_files").is_some());
+        assert!(v.get("density").is_some_and(|d| d.get("heavy").is_some() && d.get("crowded").is_some()));
 
         // 断言 summary 关键字段存在
         let summary_obj = v.get("summary").unwrap();
@@ -826,4 +2765,263 @@ mod tests {
         assert!(summary_obj.get("total_dirs").is_some());
         assert!(summary_obj.get("total_size_bytes").is_some());
     }
+
+    #[test]
+    fn test_load_saved_result_for_offline_browse() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.bin"), vec![0u8; 64]).unwrap();
+        let result = Scanner::new().scan_sync(&ScanRequest::new(dir.path())).unwrap();
+
+        // `--json` 输出的结果文件
+        let saved = dir.path().join("result.json");
+        std::fs::write(&saved, serde_json::to_vec_pretty(&result).unwrap()).unwrap();
+        let loaded = load_result(&saved).expect("应能读取保存的结果");
+        assert_eq!(loaded.summary.total_files, result.summary.total_files);
+        assert_eq!(loaded.top_files[0].path, result.top_files[0].path);
+
+        // 服务端 scan.result 返回值附带 task_id / page 等字段，同样可以加载
+        let mut payload = serde_json::to_value(&result).unwrap();
+        payload["task_id"] = Value::from("t-1");
+        payload["page"] = serde_json::json!({ "offset": 0 });
+        let from_service = dir.path().join("service.json");
+        std::fs::write(&from_service, payload.to_string()).unwrap();
+        assert_eq!(load_result(&from_service).unwrap().summary.total_size_bytes, 64);
+
+        assert!(load_result(&dir.path().join("missing.json")).is_err());
+        assert_eq!(path_status(&dir.path().join("a.bin")), "存在");
+        assert!(path_status(&dir.path().join("gone.bin")).starts_with("已不存在"));
+
+        // 加载结果时不要求 --path 存在
+        let cli = Cli::try_parse_from(["surf", "--tui", "--load", "result.json"]).unwrap();
+        assert_eq!(cli.load, Some(PathBuf::from("result.json")));
+    }
+
+    #[test]
+    fn test_cli_shard_and_merge_subcommands() {
+        let cli = Cli::try_parse_from([
+            "surf", "shard", "run", "--manifest", "m.json", "--index", "1", "--output", "s1.json",
+        ])
+        .expect("shard run 解析失败");
+        match cli.command {
+            Some(Command::Shard { action: ShardAction::Run { manifest, index, output } }) => {
+                assert_eq!(manifest, PathBuf::from("m.json"));
+                assert_eq!(index, Some(1));
+                assert_eq!(output, PathBuf::from("s1.json"));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        let cli = Cli::try_parse_from([
+            "surf", "merge", "s0.json", "s1.json", "--manifest", "m.json", "--json",
+        ])
+        .expect("merge 解析失败");
+        assert!(cli.json);
+        match cli.command {
+            Some(Command::Merge { inputs, .. }) => assert_eq!(inputs.len(), 2),
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        // diff 的新结果可以省略（重新扫描旧结果的根目录）
+        let cli = Cli::try_parse_from(["surf", "diff", "last-week.json"]).expect("diff 解析失败");
+        match cli.command {
+            Some(Command::Diff { old, new, tui }) => {
+                assert_eq!(old, PathBuf::from("last-week.json"));
+                assert_eq!(new, None);
+                assert!(!tui);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        let cli = Cli::try_parse_from(["surf", "diff", "--tui", "old.json", "new.json"]).expect("diff --tui 解析失败");
+        assert!(matches!(cli.command, Some(Command::Diff { tui: true, new: Some(_), .. })));
+        assert!(Cli::try_parse_from(["surf", "diff", "--tui", "--json", "old.json", "new.json"]).is_err());
+        assert_eq!(format_delta(-2048), "-2.00 KB");
+    }
+
+    #[test]
+    fn test_cli_free_subcommand() {
+        let cli = Cli::try_parse_from([
+            "surf", "--load", "result.json", "free", "--target", "50GB", "--keep", "**/photos/**", "--keep", "*.key",
+            "--check",
+        ])
+        .expect("free 解析失败");
+        match cli.command {
+            Some(Command::Free { ref target, ref keep, check }) => {
+                assert_eq!(parse_size_string(target).unwrap(), 50 * 1024 * 1024 * 1024);
+                assert_eq!(keep, &["**/photos/**", "*.key"]);
+                assert!(check);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["surf", "free"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_cleanup_plan_then_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("logs")).unwrap();
+        std::fs::write(dir.path().join("logs/app.log"), [0u8; 100]).unwrap();
+        std::fs::write(dir.path().join("data.bin"), [0u8; 200]).unwrap();
+        let out = tempfile::tempdir().unwrap();
+        let plan = out.path().join("plan.json");
+        let transcript = out.path().join("transcript.json");
+        let (path, plan_arg) = (dir.path().display().to_string(), plan.display().to_string());
+
+        let cli = Cli::try_parse_from([
+            "surf", "-p", &path, "--min-size", "0", "cleanup", "plan", "--select", "glob:**/*.log", "--output", &plan_arg,
+        ])
+        .expect("cleanup plan 解析失败");
+        let Some(Command::Cleanup { action: CleanupAction::Plan { ref select, ref keep, ref output } }) = cli.command else {
+            panic!("unexpected command: {:?}", cli.command);
+        };
+        run_cleanup_plan(&cli, select, keep, output.as_deref()).unwrap();
+        let saved = surf_core::cleanup::CleanupPlan::load(&plan).unwrap();
+        assert_eq!(saved.items.len(), 1);
+        assert!(Cli::try_parse_from(["surf", "cleanup", "plan", "--select", "newest"]).is_err());
+        assert!(Cli::try_parse_from(["surf", "cleanup", "plan"]).is_err());
+
+        // 不加 --yes 只预演
+        run_cleanup_apply(&cli, &plan, false, true, false, None).unwrap();
+        assert!(dir.path().join("logs/app.log").exists());
+        let cli = Cli::try_parse_from([
+            "surf", "cleanup", "apply", &plan_arg, "--yes", "--permanent", "--transcript", &transcript.display().to_string(),
+        ])
+        .expect("cleanup apply 解析失败");
+        let Some(Command::Cleanup { action: CleanupAction::Apply { ref plan, yes, permanent, allow_permanent_fallback, ref transcript } }) = cli.command else {
+            panic!("unexpected command: {:?}", cli.command);
+        };
+        run_cleanup_apply(&cli, plan, yes, permanent, allow_permanent_fallback, transcript.as_deref()).unwrap();
+        assert!(!dir.path().join("logs/app.log").exists() && dir.path().join("data.bin").exists());
+        let record: serde_json::Value = serde_json::from_slice(&std::fs::read(transcript.as_ref().unwrap()).unwrap()).unwrap();
+        assert_eq!((record["succeeded"].as_u64(), record["freed_bytes"].as_u64()), (Some(1), Some(100)));
+
+        // 再次执行：文件已不存在，逐项报告失败并以非零状态退出
+        assert!(run_cleanup_apply(&cli, plan, true, true, false, None).is_err());
+    }
+
+    #[test]
+    fn test_cli_config_validate_reports_issues() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().display().to_string();
+        let cli = Cli::try_parse_from(["surf", "-p", &path, "--json", "config", "validate"]).expect("config validate 解析失败");
+        assert!(matches!(cli.command, Some(Command::Config { action: ConfigAction::Validate })));
+        assert!(run_config_validate(&cli).is_ok());
+
+        // 被忽略的 glob 只是警告，非法正则会使扫描失败
+        let cli = Cli::parse_from(["surf", "-p", &path, "--json", "--exclude=a[", "config", "validate"]);
+        assert!(run_config_validate(&cli).is_ok());
+        let cli = Cli::parse_from(["surf", "-p", &path, "--json", "--exclude-regex=(", "config", "validate"]);
+        let error = run_config_validate(&cli).unwrap_err();
+        assert_eq!(error.to_string(), "配置校验失败: 1 个错误");
+    }
+
+    #[test]
+    fn test_elevated_args_use_msvc_quoting() {
+        // 结尾的反斜杠加倍，否则 `"C:\"` 会被解析为转义的引号
+        assert_eq!(elevate::msvc_quote("C:\\"), r#""C:\\""#);
+        assert_eq!(elevate::msvc_quote("C:\\Program Files\\My Data"), r#""C:\Program Files\My Data""#);
+        assert_eq!(elevate::msvc_quote("D:\\secret dir\\"), r#""D:\secret dir\\""#);
+        assert_eq!(elevate::msvc_quote(r#"a\"b"#), r#""a\\\"b""#);
+        assert_eq!(elevate::msvc_quote(""), r#""""#);
+    }
+
+    #[test]
+    fn test_elevated_helper_args_round_trip() {
+        let cli = Cli::parse_from([
+            "surf", "--elevate", "-p", "/opt", "-l", "7", "--exclude", "**/*.log", "--allocated", "--top-dirs-depth", "2",
+            "--max-depth", "3",
+        ]);
+        let mut base = ScanResult::default();
+        base.summary.min_size = Some(4096);
+        let dirs = vec![PathBuf::from("/var/private"), PathBuf::from("/opt/secret dir")];
+        let args = elevate::helper_args(&cli, &base, Path::new("/tmp/out.json"), &dirs);
+
+        // 提权进程解析出的请求沿用首次扫描的统计口径，且不加载插件
+        let helper = Cli::parse_from(std::iter::once("surf".to_string()).chain(args));
+        let Some(Command::ElevatedStat { output, dirs: parsed }) = &helper.command else {
+            panic!("应解析为 elevated-stat 子命令");
+        };
+        assert_eq!(output, Path::new("/tmp/out.json"));
+        assert_eq!(parsed, &dirs);
+        let request = elevate::stat_request(&helper, &dirs[1]).unwrap();
+        assert_eq!(request.root_path, dirs[1]);
+        assert_eq!(request.min_size, Some(4096));
+        assert_eq!(request.limit, Some(7));
+        assert_eq!(request.exclude_patterns, vec!["**/*.log".to_string()]);
+        assert!(request.allocated_sizes);
+        assert_eq!(request.top_dirs_depth, 2);
+        // 补扫的目录位于首次扫描根目录下一层，剩余深度相应减少
+        assert_eq!(request.max_depth, Some(2));
+        assert!(request.plugins.is_empty());
+        assert!(request.dedup.is_none());
+
+        assert!(Cli::try_parse_from(["surf", "--elevate", "--tui"]).is_err());
+    }
+
+    #[test]
+    fn test_share_columns_in_table_and_tree() {
+        assert_eq!(format_share(Some(0.6667)), "66.67%");
+        assert_eq!(format_share(None), "-");
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("a")).unwrap();
+        std::fs::write(dir.path().join("a/x.bin"), [0u8; 300]).unwrap();
+        std::fs::write(dir.path().join("b.bin"), [0u8; 100]).unwrap();
+        let result = surf_core::scan_path(dir.path()).unwrap();
+
+        // 目录节点从目录统计中获得真实大小与占比
+        let mut tree = TreeNode::from_file_entries(&result.top_files, &result.directories, false);
+        expand_all(&mut tree);
+        let flat = tree.flatten();
+        let a = flat.iter().find(|n| n.is_dir && n.name == "a").unwrap();
+        assert_eq!(a.size_bytes, 300);
+        assert_eq!(a.share_of_parent, Some(0.75));
+        let b = flat.iter().find(|n| n.name == "b.bin").unwrap();
+        assert_eq!(b.share_of_total, Some(0.25));
+    }
+
+    #[test]
+    fn test_package_dirs_collapse_to_leaf() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = dir.path().join("Tool.app/Contents");
+        std::fs::create_dir_all(contents.join("MacOS")).unwrap();
+        std::fs::write(contents.join("MacOS/tool"), [0u8; 400]).unwrap();
+        std::fs::write(contents.join("Info.plist"), [0u8; 100]).unwrap();
+        std::fs::write(dir.path().join("c.bin"), [0u8; 500]).unwrap();
+        let result = surf_core::scan_path(dir.path()).unwrap();
+
+        // 默认折叠：包目录作为叶子节点显示累计大小
+        let mut tree = TreeNode::from_file_entries(&result.top_files, &result.directories, false);
+        expand_all(&mut tree);
+        let flat = tree.flatten();
+        let app = flat.iter().find(|n| n.name == "Tool.app").unwrap();
+        assert!(app.is_package && !app.is_dir);
+        assert_eq!(app.size_bytes, 500);
+        assert!(!flat.iter().any(|n| n.name == "tool" || n.name == "Info.plist"));
+
+        // 展开后可浏览包内部结构
+        let mut tree = TreeNode::from_file_entries(&result.top_files, &result.directories, true);
+        expand_all(&mut tree);
+        let flat = tree.flatten();
+        assert!(flat.iter().any(|n| n.name == "Tool.app" && n.is_dir && !n.is_package));
+        assert!(flat.iter().any(|n| n.name == "tool"));
+    }
+
+    #[test]
+    fn test_tree_from_paths() {
+        let mut tree = TreeNode::from_paths(&[PathBuf::from("a/x.bin"), PathBuf::from("a/y.bin"), PathBuf::from("b.bin")]);
+        tree.expanded = true;
+        let flat = tree.flatten();
+        assert_eq!(flat.iter().map(|n| (n.name.as_str(), n.depth, n.has_children)).collect::<Vec<_>>(), vec![("a", 0, true), ("b.bin", 0, false)]);
+        expand_all(&mut tree);
+        let flat = tree.flatten();
+        assert_eq!(flat.iter().map(|n| n.name.as_str()).collect::<Vec<_>>(), vec!["a", "x.bin", "y.bin", "b.bin"]);
+        assert_eq!(flat[1].path, PathBuf::from("./a/x.bin"));
+    }
+
+    fn expand_all(node: &mut TreeNode) {
+        node.expanded = true;
+        for child in &mut node.children {
+            expand_all(child);
+        }
+    }
 }
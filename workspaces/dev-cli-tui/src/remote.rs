@@ -0,0 +1,57 @@
+//! 远程扫描模式（`surf --remote HOST:PORT`）
+//!
+//! 通过 surf-client 在远程 surf-service 上创建扫描任务，轮询进度并拉取完整结果，
+//! 输出方式与本地单次扫描一致。扫描路径为服务端所在机器上的路径。服务以 HTTPS 提供时
+//! 用 `--remote-fingerprint` 指定其证书指纹。
+
+use anyhow::{Context, Result};
+use surf_client::{CertFingerprint, ClientOptions, SurfClient};
+use surf_core::{ScanRequest, ScanResult};
+
+/// 在远程服务上执行扫描并等待结果
+pub fn run(request: &ScanRequest, addr: &str, fingerprint: Option<CertFingerprint>) -> Result<ScanResult> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("创建异步运行时失败")?;
+
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_style(
+        indicatif::ProgressStyle::default_spinner()
+            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
+            .template("{spinner} 远程扫描中... {msg}")?,
+    );
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+
+    let options = ClientOptions { tls_fingerprint: fingerprint, ..Default::default() };
+    let client = SurfClient::with_options(addr, options);
+    let outcome = runtime.block_on(async {
+        let task_id = client
+            .start_scan(request)
+            .await
+            .with_context(|| format!("连接远程服务失败: {}", addr))?;
+        client
+            .wait_for_completion(&task_id, |status| {
+                let phase = status.phase.map(|p| format!("{}，", p)).unwrap_or_default();
+                pb.set_message(format!(
+                    "{}{:.0}%，已扫描 {} 个文件",
+                    phase,
+                    status.progress * 100.0,
+                    status.scanned_files
+                ));
+            })
+            .await?;
+        Ok::<_, anyhow::Error>(client.get_results(&task_id).await?)
+    });
+
+    match outcome {
+        Ok(result) => {
+            pb.finish_with_message("扫描完成");
+            Ok(result)
+        }
+        Err(e) => {
+            pb.finish_with_message("扫描失败");
+            Err(e.context("远程扫描失败"))
+        }
+    }
+}
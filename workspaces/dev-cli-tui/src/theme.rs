@@ -0,0 +1,235 @@
+//! 界面主题
+//!
+//! 默认主题用彩色背景标出选中行、方块字符绘制占比条形。高对比度主题改用反色加粗，并在选中行前加
+//! `>` 标记，不只靠颜色区分状态；`--ascii` 只输出 ASCII 字符（边框、条形、进度动画与按键提示），
+//! 便于读屏软件与受限终端。`--ascii` 同样在行前加标记。
+//!
+//! 命令行未指定 `--theme` 时读取共享配置 `~/.config/surf/config.json` 的 `theme` 字段：
+//! `high-contrast` 为高对比度，`ascii` 为 ASCII 模式，`high-contrast-ascii` 两者兼有；
+//! GUI 写入的 `light` / `dark` 及其他取值按默认主题处理。
+
+use std::path::{Path, PathBuf};
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::border;
+
+/// 命令行可选的主题
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ThemeName {
+    /// 彩色高亮
+    #[default]
+    Default,
+    /// 反色加粗并以前缀标记选中行
+    HighContrast,
+}
+
+/// 生效的界面主题
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Theme {
+    /// 高对比度配色
+    pub high_contrast: bool,
+    /// 只使用 ASCII 字符
+    pub ascii: bool,
+}
+
+/// ASCII 边框
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+impl Theme {
+    /// 由命令行参数确定主题：`--theme` 优先，否则读取配置文件；`--ascii` 总是生效
+    pub fn resolve(name: Option<ThemeName>, ascii: bool) -> Self {
+        let mut theme = match name {
+            Some(name) => Theme { high_contrast: name == ThemeName::HighContrast, ascii: false },
+            None => config_theme().map_or_else(Theme::default, |value| Theme::from_config(&value)),
+        };
+        theme.ascii |= ascii;
+        theme
+    }
+
+    /// 解析配置文件中的 `theme` 取值
+    fn from_config(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "high-contrast" => Theme { high_contrast: true, ascii: false },
+            "ascii" => Theme { high_contrast: false, ascii: true },
+            "high-contrast-ascii" => Theme { high_contrast: true, ascii: true },
+            _ => Theme::default(),
+        }
+    }
+
+    /// 是否在行前加选中标记（不只靠颜色区分选中行）
+    fn marks_selection(self) -> bool {
+        self.high_contrast || self.ascii
+    }
+
+    /// 列表行前缀：选中行为 `> `，其余为等宽空白；默认主题不加前缀
+    pub fn marker(self, selected: bool) -> &'static str {
+        match (self.marks_selection(), selected) {
+            (false, _) => "",
+            (true, true) => "> ",
+            (true, false) => "  ",
+        }
+    }
+
+    /// 按占比绘制的条形（满格 20 个字符）
+    pub fn bar(self, share: f64) -> String {
+        let glyph = if self.ascii { "#" } else { "█" };
+        glyph.repeat((share * 20.0).round() as usize)
+    }
+
+    /// 选中行样式
+    pub fn selected_style(self) -> Style {
+        if self.high_contrast {
+            Style::default().fg(Color::Black).bg(Color::White).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().bg(Color::Blue)
+        }
+    }
+
+    /// 状态栏样式
+    pub fn status_style(self) -> Style {
+        if self.high_contrast {
+            Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD)
+        } else {
+            Style::default().bg(Color::DarkGray)
+        }
+    }
+
+    /// 面板边框字符
+    pub fn border_set(self) -> border::Set {
+        if self.ascii {
+            ASCII_BORDER
+        } else {
+            border::PLAIN
+        }
+    }
+
+    /// 扫描进度动画的帧
+    pub fn spinner(self) -> &'static [&'static str] {
+        if self.ascii {
+            &["|", "/", "-", "\\"]
+        } else {
+            &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]
+        }
+    }
+
+    /// 对比视图中表示增长与缩小的箭头
+    pub fn delta_arrows(self) -> (&'static str, &'static str) {
+        if self.ascii {
+            ("+", "-")
+        } else {
+            ("▲", "▼")
+        }
+    }
+
+    /// 状态栏中上下方向键的提示
+    pub fn arrows(self) -> &'static str {
+        if self.ascii {
+            "Up/Down"
+        } else {
+            "↑↓"
+        }
+    }
+}
+
+/// 共享配置文件路径：`~/.config/surf/config.json`
+pub fn config_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("surf").join("config.json"))
+}
+
+/// 配置文件中的 `theme` 字段；文件不存在或无法解析时返回 None
+fn config_theme() -> Option<String> {
+    let text = std::fs::read_to_string(config_path()?).ok()?;
+    let config: serde_json::Value = serde_json::from_str(&text).ok()?;
+    config.get("theme")?.as_str().map(str::to_string)
+}
+
+/// 配置文件中 `theme` 可用的取值（规范化后）；`light` / `dark` 由 GUI 写入，按默认主题处理
+const KNOWN_THEMES: [&str; 6] = ["default", "high-contrast", "ascii", "high-contrast-ascii", "light", "dark"];
+
+/// 检查配置文件：文件不存在时返回 `(false, [])`；JSON 语法错误、顶层不是对象或 `theme` 不是字符串时
+/// 配置整体或该字段被忽略，记为错误；`theme` 取值未知时按默认主题处理，记为警告
+pub fn check_config(path: &Path) -> (bool, Vec<surf_core::check::RequestIssue>) {
+    match std::fs::read_to_string(path) {
+        Ok(text) => (true, check_config_text(&path.display().to_string(), &text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => (false, Vec::new()),
+        Err(e) => (true, vec![surf_core::check::RequestIssue::fatal(&path.display().to_string(), e.to_string())]),
+    }
+}
+
+fn check_config_text(location: &str, text: &str) -> Vec<surf_core::check::RequestIssue> {
+    use surf_core::check::RequestIssue;
+
+    let config: serde_json::Value = match serde_json::from_str(text) {
+        Ok(config) => config,
+        Err(e) => return vec![RequestIssue::fatal(&format!("{}:{}", location, e.line()), format!("JSON 语法错误: {}", e))],
+    };
+    let Some(config) = config.as_object() else {
+        return vec![RequestIssue::fatal(location, "顶层应为 JSON 对象".to_string())];
+    };
+    // serde_json 的值不带位置，按字段名所在的行定位
+    let line = text.lines().position(|line| line.contains("\"theme\"")).map_or(1, |i| i + 1);
+    let location = format!("{}:{}", location, line);
+    match config.get("theme") {
+        None => Vec::new(),
+        Some(serde_json::Value::String(value)) => {
+            let normalized = value.trim().to_ascii_lowercase().replace('_', "-");
+            if KNOWN_THEMES.contains(&normalized.as_str()) {
+                return Vec::new();
+            }
+            vec![RequestIssue::ignored(&location, format!("theme: 未知的主题 `{}`，按默认主题处理（可选 {}）", value, KNOWN_THEMES[..4].join("、")))]
+        }
+        Some(other) => vec![RequestIssue::fatal(&location, format!("theme: 应为字符串，实际为 {}", other))],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_config_reports_location() {
+        assert!(check_config_text("c.json", "{\"theme\": \"High_Contrast\", \"language\": \"zh\"}").is_empty());
+        let issues = check_config_text("c.json", "{\n  \"theme\": \"solarized\"\n}");
+        assert_eq!((issues[0].location.as_str(), issues[0].fatal), ("c.json:2", false));
+        let issues = check_config_text("c.json", "{\n  \"theme\": 1\n}");
+        assert_eq!((issues[0].location.as_str(), issues[0].fatal), ("c.json:2", true));
+        let issues = check_config_text("c.json", "{\n  \"theme\": \"dark\",\n}");
+        assert_eq!(issues[0].location, "c.json:3");
+        assert!(issues[0].message.starts_with("JSON 语法错误"));
+        assert!(check_config_text("c.json", "[]")[0].fatal);
+    }
+
+    #[test]
+    fn test_theme_from_config_and_glyphs() {
+        assert_eq!(Theme::from_config("high-contrast"), Theme { high_contrast: true, ascii: false });
+        assert_eq!(Theme::from_config("High_Contrast"), Theme { high_contrast: true, ascii: false });
+        assert_eq!(Theme::from_config("ascii"), Theme { high_contrast: false, ascii: true });
+        assert_eq!(Theme::from_config("high-contrast-ascii"), Theme { high_contrast: true, ascii: true });
+        assert_eq!(Theme::from_config("dark"), Theme::default());
+
+        // 命令行参数优先于配置文件，--ascii 与任意主题组合
+        let theme = Theme::resolve(Some(ThemeName::Default), true);
+        assert_eq!(theme, Theme { high_contrast: false, ascii: true });
+
+        let ascii = Theme { high_contrast: false, ascii: true };
+        assert_eq!(ascii.bar(0.5), "#".repeat(10));
+        assert!(ascii.bar(1.0).is_ascii() && ascii.arrows().is_ascii());
+        assert!(ascii.spinner().iter().all(|frame| frame.is_ascii()));
+        let border = ascii.border_set();
+        assert!([border.top_left, border.vertical_left, border.horizontal_top].iter().all(|s| s.is_ascii()));
+        assert_eq!(ascii.marker(true), "> ");
+        assert_eq!(ascii.marker(false), "  ");
+
+        assert_eq!(Theme::default().bar(0.1), "██");
+        assert_eq!(Theme::default().marker(true), "");
+    }
+}
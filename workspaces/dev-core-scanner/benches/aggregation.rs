@@ -0,0 +1,53 @@
+//! 统计汇总吞吐基准：`cargo bench --features testutil --bench aggregation`
+//!
+//! 生成大量大小各异、扩展名混杂的小文件，开启陈旧文件统计（`stale_days = 0`，所有文件均计入）
+//! 并使用较大的 Top N，使每个文件都经过 Top N 堆、陈旧文件堆与扩展名表。分别以 1 个线程与
+//! `SURF_BENCH_THREADS`（默认 8）个线程扫描，输出每秒处理的文件数；各工作线程的统计互不争用时，
+//! 吞吐随线程数增长。
+
+use std::time::Instant;
+
+use surf_core::testutil::TreeSpec;
+use surf_core::{ScanRequest, Scanner};
+
+const ITERATIONS: u32 = 5;
+
+fn main() {
+    let threads: u16 = std::env::var("SURF_BENCH_THREADS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8);
+    let dir = tempfile::tempdir().expect("failed to create temp dir");
+    let tree = TreeSpec::new()
+        .depth(2)
+        .fanout(8)
+        .files_per_dir(300)
+        .file_sizes(1, 4096)
+        .extensions(["txt", "log", "jpg", "rs", "json", "bin"])
+        .generate(dir.path())
+        .expect("failed to generate tree");
+
+    for threads in [1, threads] {
+        let mut request = ScanRequest::new(dir.path());
+        request.threads = Some(threads);
+        request.min_size = Some(0);
+        request.limit = Some(1000);
+        request.stale_days = Some(0);
+        request.stale_limit = Some(1000);
+        let mut best = f64::MAX;
+        for _ in 0..ITERATIONS {
+            let start = Instant::now();
+            let result = Scanner::new().scan_sync(&request).expect("scan failed");
+            assert_eq!(result.summary.total_files, tree.files);
+            assert_eq!(result.stale_files.len(), 1000.min(tree.files as usize));
+            best = best.min(start.elapsed().as_secs_f64());
+        }
+        println!(
+            "aggregation: {} threads, {} files, best of {}: {:.0} files/s",
+            threads,
+            tree.files,
+            ITERATIONS,
+            tree.files as f64 / best
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! 每文件统计开销基准：`cargo bench --features testutil --bench per_file_overhead`
+//!
+//! 生成大量小文件（宽目录树），分别以 1 个线程与 `SURF_BENCH_THREADS`（默认 8）个线程扫描，
+//! 输出每个文件的平均耗时。多线程下的每文件耗时反映计数器与结果锁的争用程度。
+
+use std::time::Instant;
+
+use surf_core::testutil::TreeSpec;
+use surf_core::{ScanRequest, Scanner};
+
+const ITERATIONS: u32 = 5;
+
+fn main() {
+    let threads: u16 = std::env::var("SURF_BENCH_THREADS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8);
+    let dir = tempfile::tempdir().expect("failed to create temp dir");
+    let tree = TreeSpec::new()
+        .depth(2)
+        .fanout(8)
+        .files_per_dir(200)
+        .file_sizes(1, 64)
+        .generate(dir.path())
+        .expect("failed to generate tree");
+
+    for threads in [1, threads] {
+        let mut request = ScanRequest::new(dir.path());
+        request.threads = Some(threads);
+        request.min_size = Some(0);
+        let mut best = f64::MAX;
+        for _ in 0..ITERATIONS {
+            let start = Instant::now();
+            let result = Scanner::new().scan_sync(&request).expect("scan failed");
+            assert_eq!(result.summary.total_files, tree.files);
+            best = best.min(start.elapsed().as_secs_f64());
+        }
+        println!(
+            "per_file_overhead: {} threads, {} files, best of {}: {:.0} ns/file",
+            threads,
+            tree.files,
+            ITERATIONS,
+            best * 1e9 / tree.files as f64
+        );
+    }
+}
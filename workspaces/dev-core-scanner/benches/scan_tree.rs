@@ -0,0 +1,39 @@
+//! 合成目录树扫描基准：`cargo bench --features testutil`
+//!
+//! 通过环境变量 `SURF_BENCH_SPEC` 指定 `TreeSpec` JSON 文件可复现问题报告中的目录树。
+
+use std::path::Path;
+use std::time::Instant;
+
+use surf_core::testutil::TreeSpec;
+use surf_core::{ScanRequest, Scanner};
+
+const ITERATIONS: u32 = 5;
+
+fn main() {
+    let spec = match std::env::var_os("SURF_BENCH_SPEC") {
+        Some(path) => TreeSpec::load(Path::new(&path)).expect("failed to load tree spec"),
+        None => TreeSpec::new().depth(3).fanout(6).files_per_dir(40),
+    };
+    let dir = tempfile::tempdir().expect("failed to create temp dir");
+    let tree = spec.generate(dir.path()).expect("failed to generate tree");
+
+    let request = ScanRequest::new(dir.path());
+    let mut best = f64::MAX;
+    let mut scanned = 0;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        let result = Scanner::new().scan_sync(&request).expect("scan failed");
+        best = best.min(start.elapsed().as_secs_f64());
+        scanned = result.summary.total_files;
+    }
+    println!(
+        "scan_tree: {} files generated, {} scanned, {} dirs, best of {}: {:.3} ms",
+        tree.files,
+        scanned,
+        tree.dirs,
+        ITERATIONS,
+        best * 1000.0
+    );
+    tree.restore_permissions().expect("failed to restore permissions");
+}
@@ -0,0 +1,58 @@
+//! 宽目录扫描基准：`cargo bench --features testutil --bench wide_dirs`
+//!
+//! 生成少数几个各含大量小文件的目录（根目录与其下 4 个子目录各 50000 个，`SURF_BENCH_FILES_PER_DIR`
+//! 可调整，设为 200000 即百万级文件），分别以 1 个线程与 `SURF_BENCH_THREADS`（默认 8）个线程扫描并输出加速比。
+//! 目录内的文件按批次并行读取元数据，加速比不再受限于目录数量。
+
+use std::time::Instant;
+
+use surf_core::testutil::TreeSpec;
+use surf_core::{ScanRequest, Scanner};
+
+const ITERATIONS: u32 = 3;
+
+fn env_or(name: &str, default: usize) -> usize {
+    std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+fn main() {
+    let threads = env_or("SURF_BENCH_THREADS", 8) as u16;
+    let files_per_dir = env_or("SURF_BENCH_FILES_PER_DIR", 50_000);
+    let dir = tempfile::tempdir().expect("failed to create temp dir");
+    let tree = TreeSpec::new()
+        .depth(1)
+        .fanout(4)
+        .files_per_dir(files_per_dir)
+        .file_sizes(1, 64)
+        .generate(dir.path())
+        .expect("failed to generate tree");
+
+    let mut single = None;
+    for threads in [1, threads] {
+        let mut request = ScanRequest::new(dir.path());
+        request.threads = Some(threads);
+        request.min_size = Some(0);
+        let mut best = f64::MAX;
+        let mut batches = 0;
+        for _ in 0..ITERATIONS {
+            let start = Instant::now();
+            let result = Scanner::new().scan_sync(&request).expect("scan failed");
+            assert_eq!(result.summary.total_files, tree.files);
+            best = best.min(start.elapsed().as_secs_f64());
+            batches = result.diagnostics.split_batches;
+        }
+        let speedup = single.map_or(1.0, |single: f64| single / best);
+        single.get_or_insert(best);
+        println!(
+            "wide_dirs: {} threads, {} files in {} dirs, {} batches, best of {}: {:.1} ms ({:.0} ns/file, {:.2}x)",
+            threads,
+            tree.files,
+            tree.dirs,
+            batches,
+            ITERATIONS,
+            best * 1000.0,
+            best * 1e9 / tree.files as f64,
+            speedup
+        );
+    }
+}
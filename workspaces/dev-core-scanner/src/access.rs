@@ -0,0 +1,230 @@
+//! 访问日志回放与冷数据评分
+//!
+//! 许多系统关闭了 atime（`noatime`），仅凭 mtime 判断陈旧文件会把“很少修改但经常读取”
+//! 的文件误判为冷数据。访问日志（例如 fanotify 采集结果，或由 Web 服务器日志映射出的
+//! 文件路径）记录了真实的读取行为，扫描时将其与 mtime 结合：
+//!
+//! - 陈旧判断使用“最近活动时间” = max(mtime, 日志中最近一次访问)；
+//! - 冷度评分 `coldness` ∈ [0, 1)，空闲越久、访问次数越少越接近 1。
+//!
+//! 陈旧判断默认参照修改时间，也可通过 [`StaleMode`] 改为访问时间（atime）、创建时间，
+//! 或三者中最晚的一个（“任何方式都没有动过”）。
+//!
+//! 日志格式为纯文本，每行一条访问记录：`<unix 秒时间戳>\t<路径>`，或仅 `<路径>`
+//! （仅计入访问次数）。空行与 `#` 开头的注释行被忽略。
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::FileEntry;
+
+/// 冷度评分中空闲时间的半衰参考（天）：空闲该天数时时间因子为 0.5
+const COLDNESS_IDLE_DAYS: f64 = 30.0;
+
+/// 陈旧判断参照的时间
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StaleMode {
+    /// 最后修改时间（mtime）
+    #[default]
+    Modified,
+    /// 最后访问时间（atime）；以 `noatime` 挂载的文件系统上不会更新，可配合访问日志使用
+    Accessed,
+    /// 创建时间；文件系统不记录时无法判断
+    Created,
+    /// 修改、访问、创建时间中最晚者：三者都已超过阈值才视为陈旧
+    Any,
+}
+
+impl FromStr for StaleMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "modified" | "mtime" => Ok(StaleMode::Modified),
+            "accessed" | "atime" => Ok(StaleMode::Accessed),
+            "created" | "birthtime" => Ok(StaleMode::Created),
+            "any" => Ok(StaleMode::Any),
+            _ => Err(format!("未知的陈旧判断依据: {}（可选 modified、accessed、created、any）", s)),
+        }
+    }
+}
+
+impl fmt::Display for StaleMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StaleMode::Modified => "modified",
+            StaleMode::Accessed => "accessed",
+            StaleMode::Created => "created",
+            StaleMode::Any => "any",
+        })
+    }
+}
+
+/// 陈旧文件列表的排序依据；设置 `stale_limit` 时也决定保留哪些文件
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StaleSort {
+    /// 空闲时间降序（最久未动的在前）
+    #[default]
+    Age,
+    /// 文件大小降序
+    Size,
+    /// 冷度评分降序（需要访问日志，未评分的按空闲时间排在其后）
+    Coldness,
+}
+
+impl FromStr for StaleSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "age" => Ok(StaleSort::Age),
+            "size" => Ok(StaleSort::Size),
+            "coldness" => Ok(StaleSort::Coldness),
+            _ => Err(format!("未知的陈旧文件排序方式: {}（可选 age、size、coldness）", s)),
+        }
+    }
+}
+
+impl fmt::Display for StaleSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StaleSort::Age => "age",
+            StaleSort::Size => "size",
+            StaleSort::Coldness => "coldness",
+        })
+    }
+}
+
+/// 比较两个陈旧文件的排名：`Greater` 表示 `a` 排在前面（同分时路径较小者在前）
+pub(crate) fn rank_stale(sort: StaleSort, a: &FileEntry, b: &FileEntry) -> Ordering {
+    let by_age = || a.idle_seconds.cmp(&b.idle_seconds);
+    match sort {
+        StaleSort::Age => by_age(),
+        StaleSort::Size => a.size_bytes.cmp(&b.size_bytes),
+        StaleSort::Coldness => a.coldness.partial_cmp(&b.coldness).unwrap_or(Ordering::Equal).then_with(by_age),
+    }
+    .then_with(|| b.path.cmp(&a.path))
+}
+
+/// 按排名降序排列陈旧文件，超出 `limit` 的部分截去
+pub(crate) fn sort_stale(entries: &mut Vec<FileEntry>, sort: StaleSort, limit: Option<usize>) {
+    entries.sort_by(|a, b| rank_stale(sort, b, a));
+    if let Some(limit) = limit {
+        entries.truncate(limit);
+    }
+}
+
+/// 按判断依据选出文件的参照时间（访问日志中的访问记录计入访问时间）；所需时间不可用时为 None
+pub fn reference_time(
+    mode: StaleMode,
+    modified: Option<SystemTime>,
+    accessed: Option<SystemTime>,
+    created: Option<SystemTime>,
+    record: Option<&AccessRecord>,
+) -> Option<SystemTime> {
+    let logged = record.and_then(|r| r.last_access);
+    match mode {
+        StaleMode::Modified => last_activity(modified, record),
+        StaleMode::Accessed => accessed.max(logged),
+        StaleMode::Created => created,
+        StaleMode::Any => modified.max(accessed).max(created).max(logged),
+    }
+}
+
+/// 单个文件的访问统计
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AccessRecord {
+    /// 记录到的访问次数
+    pub count: u64,
+    /// 最近一次访问时间（日志行未携带时间戳时为 None）
+    pub last_access: Option<SystemTime>,
+}
+
+/// 按路径聚合的访问日志
+#[derive(Debug, Clone, Default)]
+pub struct AccessLog {
+    records: HashMap<PathBuf, AccessRecord>,
+}
+
+impl AccessLog {
+    /// 从文件加载访问日志
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(BufReader::new(File::open(path)?))
+    }
+
+    /// 从文本流解析访问日志；无法识别时间戳的行按“仅路径”处理
+    pub fn parse<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut log = Self::default();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (at, path) = match line.split_once('\t') {
+                Some((ts, path)) => match ts.trim().parse::<f64>() {
+                    Ok(secs) if secs >= 0.0 => (Some(UNIX_EPOCH + Duration::from_secs_f64(secs)), path),
+                    _ => (None, line),
+                },
+                None => (None, line),
+            };
+            log.record(PathBuf::from(path), at);
+        }
+        Ok(log)
+    }
+
+    /// 记录一次访问
+    pub fn record(&mut self, path: PathBuf, at: Option<SystemTime>) {
+        let record = self.records.entry(path).or_default();
+        record.count += 1;
+        if at > record.last_access {
+            record.last_access = at;
+        }
+    }
+
+    /// 查询文件的访问统计
+    pub fn get(&self, path: &Path) -> Option<&AccessRecord> {
+        self.records.get(path)
+    }
+
+    /// 日志中记录到的文件数
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// 日志是否为空
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+/// 文件最近一次活动时间：修改时间与记录到的最近访问时间中较晚者
+pub fn last_activity(last_modified: Option<SystemTime>, record: Option<&AccessRecord>) -> Option<SystemTime> {
+    last_modified.max(record.and_then(|r| r.last_access))
+}
+
+/// 最近活动时间距 `now` 已满 `stale_days` 天时返回空闲时长；晚于 `now` 的时间（如未来的 mtime）从不视为陈旧
+pub fn stale_idle(last_active: SystemTime, now: SystemTime, stale_days: u32) -> Option<Duration> {
+    let idle = now.duration_since(last_active).ok()?;
+    (idle.as_secs() >= u64::from(stale_days) * 24 * 60 * 60).then_some(idle)
+}
+
+/// 计算冷度评分 ∈ [0, 1)
+///
+/// 时间因子 `idle / (idle + 30天)` 随空闲时间增长趋近 1，频率因子 `1 / (1 + 访问次数)`
+/// 使频繁访问的文件评分显著降低；两者相乘。
+pub fn coldness(idle: Duration, access_count: u64) -> f64 {
+    let idle_days = idle.as_secs_f64() / 86400.0;
+    let recency = idle_days / (idle_days + COLDNESS_IDLE_DAYS);
+    recency / (1.0 + access_count as f64)
+}
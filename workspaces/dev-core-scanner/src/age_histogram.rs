@@ -0,0 +1,116 @@
+//! 文件年龄分布
+//!
+//! 陈旧文件列表只回答“哪些文件旧”，年龄分布则一眼回答“有多少数据是冷的”：按最后修改时间
+//! 把全部计入统计的文件划入 <7d、7-30d、30-90d、90d-1y、>1y 几个年龄段（修改时间未知的单独一段），
+//! 给出各段的文件数、总大小与占总量的比例。与热力图一样使用校正后的修改时间与扫描参照时间，
+//! 在遍历时按目录任务累计，不需要保留文件条目。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+/// 年龄段数量（含修改时间未知的一段）
+pub const HISTOGRAM_BUCKETS: usize = 6;
+
+/// 年龄段标签（按年龄从新到旧，最后一段为修改时间未知）
+pub const HISTOGRAM_BUCKET_LABELS: [&str; HISTOGRAM_BUCKETS] = ["<7d", "7-30d", "30-90d", "90d-1y", ">1y", "unknown"];
+
+/// 前四个年龄段的上限（天，不含）；超过最后一个上限归入 ">1y"
+const BUCKET_MAX_DAYS: [u64; 4] = [7, 30, 90, 365];
+
+/// 年龄分布中的一段
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct AgeBucket {
+    /// 年龄段标签，见 [`HISTOGRAM_BUCKET_LABELS`]
+    pub label: String,
+    /// 年龄下限（天，含）；修改时间未知的一段为 None
+    pub min_days: Option<u64>,
+    /// 年龄上限（天，不含）；最旧的一段与修改时间未知的一段为 None
+    pub max_days: Option<u64>,
+    /// 文件数
+    pub file_count: u64,
+    /// 总大小（字节）
+    pub size_bytes: u64,
+    /// 占扫描总量的比例
+    pub share_of_total: f64,
+}
+
+/// 按修改时间确定年龄段；修改时间未知或晚于当前时间时归入 "unknown"
+pub(crate) fn bucket_of(modified: Option<SystemTime>, now: SystemTime) -> usize {
+    let Some(age) = modified.and_then(|m| now.duration_since(m).ok()) else {
+        return HISTOGRAM_BUCKETS - 1;
+    };
+    let days = age.as_secs() / 86400;
+    BUCKET_MAX_DAYS.iter().position(|&max| days < max).unwrap_or(BUCKET_MAX_DAYS.len())
+}
+
+/// 单个目录任务内累计的各年龄段 (文件数, 字节数)
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Tally([(u64, u64); HISTOGRAM_BUCKETS]);
+
+impl Tally {
+    pub(crate) fn add(&mut self, bucket: usize, bytes: u64) {
+        let slot = &mut self.0[bucket];
+        slot.0 += 1;
+        slot.1 += bytes;
+    }
+}
+
+/// 全部目录任务共享的年龄分布计数
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    files: [AtomicU64; HISTOGRAM_BUCKETS],
+    bytes: [AtomicU64; HISTOGRAM_BUCKETS],
+}
+
+impl Counters {
+    pub(crate) fn flush(&self, tally: &Tally) {
+        for (i, &(files, bytes)) in tally.0.iter().enumerate().filter(|(_, slot)| slot.0 > 0) {
+            self.files[i].fetch_add(files, Ordering::Relaxed);
+            self.bytes[i].fetch_add(bytes, Ordering::Relaxed);
+        }
+    }
+
+    /// 生成全部年龄段（占比由 [`crate::share::apply_shares`] 填充）
+    pub(crate) fn to_vec(&self) -> Vec<AgeBucket> {
+        (0..HISTOGRAM_BUCKETS)
+            .map(|i| bucket(i, self.files[i].load(Ordering::Relaxed), self.bytes[i].load(Ordering::Relaxed)))
+            .collect()
+    }
+}
+
+fn bucket(index: usize, file_count: u64, size_bytes: u64) -> AgeBucket {
+    let known = index < HISTOGRAM_BUCKETS - 1;
+    AgeBucket {
+        label: HISTOGRAM_BUCKET_LABELS[index].to_string(),
+        min_days: known.then(|| index.checked_sub(1).map_or(0, |prev| BUCKET_MAX_DAYS[prev])),
+        max_days: BUCKET_MAX_DAYS.get(index).copied(),
+        file_count,
+        size_bytes,
+        ..Default::default()
+    }
+}
+
+/// 合并多份年龄分布（按标签累加）；各部分都没有年龄分布时返回空
+pub(crate) fn merge(buckets: impl IntoIterator<Item = AgeBucket>) -> Vec<AgeBucket> {
+    let mut sums: HashMap<String, (u64, u64)> = HashMap::new();
+    for row in buckets {
+        let slot = sums.entry(row.label).or_default();
+        slot.0 += row.file_count;
+        slot.1 += row.size_bytes;
+    }
+    if sums.is_empty() {
+        return Vec::new();
+    }
+    HISTOGRAM_BUCKET_LABELS
+        .iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let (files, bytes) = sums.get(*label).copied().unwrap_or_default();
+            bucket(i, files, bytes)
+        })
+        .collect()
+}
@@ -0,0 +1,45 @@
+//! 文件实际占用的磁盘空间
+//!
+//! `len()` 是文件的逻辑大小。稀疏文件、透明压缩（APFS/NTFS/btrfs 压缩）与块对齐都会让
+//! 实际分配的空间与之不同，`du` 报告的是后者。启用 [`ScanRequest::allocated_sizes`]
+//! 后扫描同时记录两者：
+//!
+//! - Unix：`st_blocks * 512`；
+//! - Windows：`GetCompressedFileSizeW`（压缩或稀疏文件返回实际占用，其余等于逻辑大小）。
+//!
+//! [`ScanRequest::allocated_sizes`]: crate::ScanRequest::allocated_sizes
+
+use std::fs::Metadata;
+use std::path::Path;
+
+/// 文件实际分配的字节数；平台不支持或读取失败时为 None
+#[cfg(unix)]
+pub(crate) fn allocated_size(_path: &Path, metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.blocks() * 512)
+}
+
+#[cfg(windows)]
+pub(crate) fn allocated_size(path: &Path, _metadata: &Metadata) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCompressedFileSizeW(file_name: *const u16, file_size_high: *mut u32) -> u32;
+    }
+
+    const INVALID_FILE_SIZE: u32 = u32::MAX;
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut high = 0u32;
+    // SAFETY: `wide` 以 NUL 结尾且在调用期间有效，`high` 为有效的输出指针
+    let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &mut high) };
+    if low == INVALID_FILE_SIZE && std::io::Error::last_os_error().raw_os_error() != Some(0) {
+        return None;
+    }
+    Some((u64::from(high) << 32) | u64::from(low))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn allocated_size(_path: &Path, _metadata: &Metadata) -> Option<u64> {
+    None
+}
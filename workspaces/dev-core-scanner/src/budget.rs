@@ -0,0 +1,64 @@
+//! 遍历任务的内存预算
+//!
+//! 默认的遍历为每个子目录与每个文件批次派发一个 rayon 任务。极深或极宽的目录树中，
+//! 已派发但尚未完成的任务可能多达数十万个，每个任务都持有路径、目录节点与待处理的目录项，
+//! 峰值内存随树的形状而非线程数增长。
+//!
+//! 设置 `memory_budget` 后，在途任务（已派发、尚未完成）的数量被限制在
+//! `memory_budget / TASK_BYTES` 个（不少于工作线程数，保证所有线程都有事可做）。
+//! 没有余量时不再派发：子目录留在当前任务中，按深度优先顺序逐个遍历，文件批次就地处理。
+//! 任务完成后释放名额，其他线程随即可以继续派发。预算只约束遍历中的工作集，
+//! Top N、目录节点等结果数据的内存由 `limit`、`collect_entries` 等选项决定。
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// 一个在途任务的估算内存：目录读取缓冲、子目录列表与待派发的目录项，
+/// 或一个文件批次的目录项（`hints::FILE_BATCH_SIZE` 个）
+pub const TASK_BYTES: u64 = 32 * 1024;
+
+/// 在途任务名额
+#[derive(Debug)]
+pub(crate) struct TaskBudget {
+    /// 名额上限；未设置预算时不限
+    limit: usize,
+    in_flight: AtomicUsize,
+    /// 因没有名额而留在当前任务中遍历的目录数
+    deferred_dirs: AtomicU64,
+}
+
+impl TaskBudget {
+    /// 按内存预算与工作线程数计算名额；`memory_budget` 为 None 时不限制
+    pub(crate) fn new(memory_budget: Option<u64>, workers: usize) -> Arc<Self> {
+        let limit = memory_budget.map_or(usize::MAX, |bytes| {
+            usize::try_from(bytes / TASK_BYTES).unwrap_or(usize::MAX).max(workers.max(1))
+        });
+        Arc::new(Self { limit, in_flight: AtomicUsize::new(0), deferred_dirs: AtomicU64::new(0) })
+    }
+
+    /// 申请一个名额；名额用尽时返回 None，调用方应就地处理而不是派发
+    pub(crate) fn try_acquire(self: &Arc<Self>) -> Option<TaskPermit> {
+        self.in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |n| (n < self.limit).then_some(n + 1))
+            .ok()
+            .map(|_| TaskPermit(self.clone()))
+    }
+
+    /// 记录一个留在当前任务中遍历的目录
+    pub(crate) fn defer_dir(&self) {
+        self.deferred_dirs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn deferred_dirs(&self) -> u64 {
+        self.deferred_dirs.load(Ordering::Relaxed)
+    }
+}
+
+/// 在途任务持有的名额，任务结束时释放
+pub(crate) struct TaskPermit(Arc<TaskBudget>);
+
+impl Drop for TaskPermit {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
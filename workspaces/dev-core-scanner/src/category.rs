@@ -0,0 +1,162 @@
+//! 文件类别
+//!
+//! 按扩展名把文件归入图片、视频、音频、文档、代码、压缩包、磁盘镜像等高层类别，用于
+//! “照片 vs 视频 vs 文档 vs 代码”这类一眼可读的空间分布。类别统计由 `by_extension` 汇总得到，
+//! 不增加遍历开销；合并结果时同样由合并后的扩展名统计重新计算。无法识别的扩展名归入“其他”。
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ExtensionStat;
+
+/// 文件类别
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum FileCategory {
+    /// 图片（含相机 RAW）
+    Image,
+    /// 视频
+    Video,
+    /// 音频
+    Audio,
+    /// 文档（办公文档、PDF、电子书、纯文本）
+    Document,
+    /// 源代码与脚本、配置
+    Code,
+    /// 压缩包与归档
+    Archive,
+    /// 磁盘镜像与虚拟机磁盘
+    DiskImage,
+    /// 可执行文件与库
+    Executable,
+    /// 无法识别
+    #[default]
+    Other,
+}
+
+impl FileCategory {
+    /// 由扩展名（不含点，大小写不敏感）确定类别
+    pub fn of_extension(extension: &str) -> Self {
+        match extension.to_ascii_lowercase().as_str() {
+            "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tif" | "tiff" | "webp" | "heic" | "heif" | "avif" | "svg"
+            | "ico" | "psd" | "raw" | "cr2" | "cr3" | "nef" | "arw" | "dng" | "orf" | "rw2" | "raf" => {
+                FileCategory::Image
+            }
+            "mp4" | "m4v" | "mkv" | "mov" | "avi" | "wmv" | "flv" | "webm" | "mpg" | "mpeg" | "3gp" | "m2ts"
+            | "vob" => FileCategory::Video,
+            "mp3" | "m4a" | "aac" | "flac" | "wav" | "ogg" | "oga" | "opus" | "wma" | "aiff" | "aif" | "alac"
+            | "mid" | "midi" => FileCategory::Audio,
+            "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "odt" | "ods" | "odp" | "rtf" | "txt"
+            | "md" | "pages" | "numbers" | "key" | "epub" | "mobi" | "csv" | "tex" => FileCategory::Document,
+            "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "cxx" | "go" | "java" | "kt" | "swift" | "m" | "mm" | "py"
+            | "rb" | "php" | "js" | "mjs" | "cjs" | "jsx" | "ts" | "tsx" | "cs" | "scala" | "lua" | "sh" | "bash"
+            | "zsh" | "ps1" | "sql" | "html" | "htm" | "css" | "scss" | "vue" | "json" | "yaml" | "yml"
+            | "toml" | "xml" | "ini" | "gradle" | "cmake" | "proto" => FileCategory::Code,
+            "zip" | "tar" | "gz" | "tgz" | "bz2" | "tbz2" | "xz" | "txz" | "zst" | "7z" | "rar" | "lz4" | "lzma"
+            | "cab" | "jar" | "war" | "whl" | "deb" | "rpm" | "pkg" | "apk" => FileCategory::Archive,
+            "iso" | "img" | "dmg" | "vmdk" | "vdi" | "vhd" | "vhdx" | "qcow2" | "sparseimage" | "sparsebundle" => {
+                FileCategory::DiskImage
+            }
+            "exe" | "dll" | "so" | "dylib" | "a" | "lib" | "o" | "obj" | "bin" | "msi" | "app" | "wasm" => {
+                FileCategory::Executable
+            }
+            _ => FileCategory::Other,
+        }
+    }
+
+    /// 由 MIME 类型（见 [`crate::mime`]）确定类别；无法据此判断时返回 `None`
+    pub fn of_mime(mime: &str) -> Option<Self> {
+        let (kind, subtype) = mime.split_once('/')?;
+        Some(match kind {
+            "image" => FileCategory::Image,
+            "video" => FileCategory::Video,
+            "audio" => FileCategory::Audio,
+            _ if subtype == "pdf" || subtype == "rtf" || subtype == "epub+zip" || subtype.contains("officedocument") => {
+                FileCategory::Document
+            }
+            _ if subtype.contains("executable") || subtype == "x-mach-binary" || subtype == "wasm" => {
+                FileCategory::Executable
+            }
+            _ if ["zip", "gzip", "x-bzip2", "x-xz", "zstd", "x-7z-compressed", "vnd.rar", "x-tar"].contains(&subtype) => {
+                FileCategory::Archive
+            }
+            _ => return None,
+        })
+    }
+
+    /// 类别名称（与序列化形式一致）
+    pub fn name(self) -> &'static str {
+        match self {
+            FileCategory::Image => "image",
+            FileCategory::Video => "video",
+            FileCategory::Audio => "audio",
+            FileCategory::Document => "document",
+            FileCategory::Code => "code",
+            FileCategory::Archive => "archive",
+            FileCategory::DiskImage => "disk_image",
+            FileCategory::Executable => "executable",
+            FileCategory::Other => "other",
+        }
+    }
+
+    /// 中文显示名称
+    pub fn label(self) -> &'static str {
+        match self {
+            FileCategory::Image => "图片",
+            FileCategory::Video => "视频",
+            FileCategory::Audio => "音频",
+            FileCategory::Document => "文档",
+            FileCategory::Code => "代码",
+            FileCategory::Archive => "压缩包",
+            FileCategory::DiskImage => "磁盘镜像",
+            FileCategory::Executable => "可执行文件",
+            FileCategory::Other => "其他",
+        }
+    }
+}
+
+impl fmt::Display for FileCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// 单个类别的统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CategoryStat {
+    /// 类别
+    pub category: FileCategory,
+    /// 文件数
+    pub file_count: u64,
+    /// 总大小（字节）
+    pub total_size_bytes: u64,
+    /// 占扫描总量的比例
+    #[serde(default)]
+    pub share_of_total: f64,
+}
+
+/// 由扩展名统计汇总类别统计（按总大小降序，只含有文件的类别；占比由 [`crate::share::apply_shares`] 填充）
+pub(crate) fn from_extensions(stats: &[ExtensionStat]) -> Vec<CategoryStat> {
+    let mut sums: HashMap<FileCategory, (u64, u64)> = HashMap::new();
+    for stat in stats {
+        let slot = sums.entry(FileCategory::of_extension(&stat.extension)).or_default();
+        slot.0 += stat.file_count;
+        slot.1 += stat.total_size_bytes;
+    }
+    let mut rows: Vec<CategoryStat> = sums
+        .into_iter()
+        .filter(|(_, (files, _))| *files > 0)
+        .map(|(category, (file_count, total_size_bytes))| CategoryStat {
+            category,
+            file_count,
+            total_size_bytes,
+            ..Default::default()
+        })
+        .collect();
+    rows.sort_by(|a, b| b.total_size_bytes.cmp(&a.total_size_bytes).then_with(|| a.category.cmp(&b.category)));
+    rows
+}
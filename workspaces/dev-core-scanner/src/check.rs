@@ -0,0 +1,58 @@
+//! 扫描请求的静态检查
+//!
+//! 部分配置问题不会使扫描失败，而是被静默处理：非法的 glob 规则被忽略，忽略文件中无法解析的行被跳过，
+//! 结果看起来正常，相应的设置却没有生效。[`check`] 不遍历目录，逐项检查请求并列出每个问题所在的字段
+//! （如 `exclude_patterns[2]`）或文件，供 `surf config validate` 等诊断入口使用；会使扫描失败的问题
+//! 同样列出，并标记为 [`fatal`](RequestIssue::fatal)。
+
+use serde::{Deserialize, Serialize};
+
+use crate::ignore_files::IgnoreRules;
+use crate::{filter, size_histogram, ScanRequest};
+
+/// 请求中的一个问题
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RequestIssue {
+    /// 问题所在：请求字段（如 `exclude_patterns[2]`）或文件路径
+    pub location: String,
+    /// 问题描述
+    pub message: String,
+    /// 是否会使扫描失败；为 false 时扫描照常进行，但相应的设置不生效
+    pub fatal: bool,
+}
+
+impl RequestIssue {
+    /// 会使扫描失败的问题
+    pub fn fatal(location: &str, message: String) -> Self {
+        Self { location: location.to_string(), message, fatal: true }
+    }
+
+    /// 扫描照常进行、但相应设置不生效的问题
+    pub fn ignored(location: &str, message: String) -> Self {
+        Self { location: location.to_string(), message, fatal: false }
+    }
+}
+
+/// 检查请求：根目录、过滤规则、大小分布选择器，以及开启 `respect_ignore_files` 时根目录及其祖先目录中的
+/// 忽略文件；没有问题时返回空列表
+pub fn check(request: &ScanRequest) -> Vec<RequestIssue> {
+    let mut issues = Vec::new();
+    match std::fs::metadata(&request.root_path) {
+        Ok(metadata) if !metadata.is_dir() => issues.push(RequestIssue::fatal("root_path", "不是目录".to_string())),
+        Ok(_) => {}
+        Err(e) => issues.push(RequestIssue::fatal("root_path", e.to_string())),
+    }
+    issues.extend(filter::check_rules(request));
+    for (i, selector) in request.size_histograms.iter().enumerate() {
+        if let Err(e) = size_histogram::Counters::new(std::slice::from_ref(selector)) {
+            issues.push(RequestIssue::fatal(&format!("size_histograms[{}]", i), e.to_string()));
+        }
+    }
+    if request.respect_ignore_files {
+        for (path, line) in IgnoreRules::check_root(&request.root_path) {
+            issues.push(RequestIssue::ignored(&path.display().to_string(), format!("无法解析，该行被跳过: {}", line)));
+        }
+    }
+    issues
+}
@@ -0,0 +1,451 @@
+//! 按目标释放空间的清理方案
+//!
+//! [`plan`] 汇总扫描结果中的各类可清理条目，按风险从低到高排序后依次选取，直到累计大小达到目标：
+//!
+//! 1. 冗余附属文件（`redundant_files`：备份副本、锁文件、系统元数据等）；
+//! 2. 缓存目录中的文件（路径中含 `Caches`、`.cache`、`__pycache__` 等目录，取自 `top_files` 与 `stale_files`）；
+//! 3. 重复文件的多余副本（`duplicates`，每组保留一份）；
+//! 4. 陈旧文件（`stale_files`）。
+//!
+//! 同一风险等级内先选大的文件，使条目数尽量少；同一路径出现在多个来源时按风险最低者计入一次。
+//! 系统保留的条目与匹配保留规则（`keep_patterns`，写法同过滤规则，支持 `anchored:` 前缀）的路径
+//! 永远不会出现在方案中；重复文件组中有受保护的路径时保留该份，其余副本仍可清理。
+//!
+//! 也可以用 [`select`] 按明确的条件生成方案（[`Selection`]）：超过若干天未使用的陈旧文件、重复文件组中
+//! 除最新副本外的全部副本、匹配规则的文件；各条件选出的条目合并去重，保护规则同上。
+//!
+//! 方案本身不删除任何文件，可序列化为 JSON 供审阅（[`CleanupPlan::save`] / [`CleanupPlan::load`]）。
+//! 每个条目附带扫描时记录的 [`ExpectedEntry`]，[`CleanupPlan::apply`] 经由 [`delete`](crate::delete)
+//! 的批量接口逐项校验并执行（或预演），返回记录每项成败的 [`CleanupTranscript`]。
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::delete::{self, DeleteItem, DeleteMode, DeleteOptions, DeleteOrigin, DeleteResult, ExpectedEntry, Verification};
+use crate::filter::RuleSet;
+use crate::{FileEntry, ScanResult};
+
+/// 视为缓存的目录名（不区分大小写）
+const CACHE_DIR_NAMES: &[&str] = &["caches", "cache", ".cache", "__pycache__", ".pytest_cache", ".gradle", ".npm"];
+
+/// 可清理条目的来源，按清理风险从低到高排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupSource {
+    /// 冗余附属文件
+    Redundant,
+    /// 缓存目录中的文件
+    Cache,
+    /// 重复文件的多余副本
+    Duplicate,
+    /// 陈旧文件
+    Stale,
+    /// 匹配 [`Selection::Matching`] 规则的文件
+    Matched,
+}
+
+impl CleanupSource {
+    /// 中文名称
+    pub fn label(self) -> &'static str {
+        match self {
+            CleanupSource::Redundant => "冗余文件",
+            CleanupSource::Cache => "缓存",
+            CleanupSource::Duplicate => "重复副本",
+            CleanupSource::Stale => "陈旧文件",
+            CleanupSource::Matched => "规则匹配",
+        }
+    }
+}
+
+/// 清理方案选项
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CleanupOptions {
+    /// 需要释放的字节数
+    pub target_bytes: u64,
+    /// 保留规则：匹配的路径不会被建议清理
+    pub keep_patterns: Vec<String>,
+}
+
+/// [`select`] 的选取条件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Selection {
+    /// 超过 `days` 天未使用的陈旧文件（取自 `stale_files`，需扫描时指定 `stale_days`）
+    StaleOlderThan { days: u32 },
+    /// 重复文件组中除修改时间最新的一份外的全部副本（需扫描时启用重复检测）
+    DuplicatesExceptNewest,
+    /// 路径匹配规则（写法同过滤规则）的文件，取自扫描结果中的全部文件条目
+    Matching { pattern: String },
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selection::StaleOlderThan { days } => write!(f, "stale:{}", days),
+            Selection::DuplicatesExceptNewest => f.write_str("duplicates"),
+            Selection::Matching { pattern } => write!(f, "glob:{}", pattern),
+        }
+    }
+}
+
+impl FromStr for Selection {
+    type Err = String;
+
+    /// `stale:<天数>`、`duplicates` 或 `glob:<规则>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "duplicates" {
+            return Ok(Selection::DuplicatesExceptNewest);
+        }
+        if let Some(days) = s.strip_prefix("stale:") {
+            let days = days.parse().map_err(|_| format!("无效的天数: {}", days))?;
+            return Ok(Selection::StaleOlderThan { days });
+        }
+        match s.strip_prefix("glob:") {
+            Some(pattern) if !pattern.is_empty() => Ok(Selection::Matching { pattern: pattern.to_string() }),
+            _ => Err(format!("不支持的选取条件: {}（可选 stale:<天数>、duplicates、glob:<规则>）", s)),
+        }
+    }
+}
+
+/// 方案中的一个条目
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CleanupItem {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub source: CleanupSource,
+    /// 建议清理的原因
+    pub reason: String,
+    /// 扫描时记录的条目，删除前据此校验
+    pub expected: ExpectedEntry,
+}
+
+impl CleanupItem {
+    fn from_entry(entry: &FileEntry, source: CleanupSource, reason: String) -> Self {
+        Self { path: entry.path.clone(), size_bytes: entry.size_bytes, source, reason, expected: ExpectedEntry::from(entry) }
+    }
+
+    /// 执行或预演删除该条目的选项：校验类型与大小，移至回收站
+    pub fn delete_options(&self, origin: DeleteOrigin, dry_run: bool) -> DeleteOptions {
+        DeleteOptions {
+            mode: DeleteMode::MoveToTrash,
+            origin,
+            dry_run,
+            verify: Verification::Size,
+            expected: Some(self.expected.clone()),
+            scan_root: None,
+            allow_permanent_fallback: false,
+        }
+    }
+}
+
+/// 清理方案
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CleanupPlan {
+    pub root_path: PathBuf,
+    pub target_bytes: u64,
+    /// 方案中条目的总大小
+    pub planned_bytes: u64,
+    /// 是否达到目标；未达到时方案包含全部候选
+    pub target_met: bool,
+    /// 按风险从低到高、同风险按大小降序排列
+    pub items: Vec<CleanupItem>,
+    /// 因系统保留或匹配保留规则而跳过的候选数
+    pub protected_skipped: usize,
+    /// 由 [`select`] 生成时使用的选取条件
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub selections: Vec<Selection>,
+}
+
+impl CleanupPlan {
+    /// 方案中的全部条目，连同 [`Self::delete_options`] 交给 [`to_trash`](crate::delete::to_trash) 批量执行或预演
+    pub fn delete_items(&self) -> Vec<DeleteItem> {
+        self.items.iter().map(|item| DeleteItem::new(&item.path, Some(item.expected.clone()))).collect()
+    }
+
+    /// 批量删除的选项：校验类型与大小，并拒绝删除扫描根目录
+    pub fn delete_options(&self, origin: DeleteOrigin, dry_run: bool) -> DeleteOptions {
+        DeleteOptions {
+            origin,
+            dry_run,
+            verify: Verification::Size,
+            scan_root: Some(self.root_path.clone()),
+            ..DeleteOptions::default()
+        }
+    }
+
+    /// 从 JSON 文件加载方案
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read(path)?;
+        serde_json::from_slice(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// 将方案保存为 JSON 文件，供审阅后再执行
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// 按方案逐项删除（`dry_run` 时只做校验与可行性检查），返回每项的执行记录
+    ///
+    /// 删除前按扫描时记录的类型与大小校验，扫描根目录及系统目录等受保护的路径一律拒绝，
+    /// 见 [`delete::protection_reason`]。某一项失败不影响其余各项。无法移入回收站的条目默认失败，
+    /// `allow_permanent_fallback` 时改为永久删除。
+    pub fn apply(&self, mode: DeleteMode, origin: DeleteOrigin, dry_run: bool, allow_permanent_fallback: bool) -> CleanupTranscript {
+        let options = DeleteOptions { allow_permanent_fallback, ..self.delete_options(origin, dry_run) };
+        let items = self.delete_items();
+        let results = match mode {
+            DeleteMode::MoveToTrash => delete::to_trash(&items, &options),
+            DeleteMode::Permanent => delete::permanent(&items, &options),
+        };
+        let entries: Vec<TranscriptEntry> = self
+            .items
+            .iter()
+            .zip(results)
+            .map(|(item, done)| TranscriptEntry {
+                path: item.path.clone(),
+                size_bytes: item.size_bytes,
+                source: item.source,
+                result: done.result,
+            })
+            .collect();
+        let succeeded = entries.iter().filter(|e| e.result.success).count();
+        CleanupTranscript {
+            root_path: self.root_path.clone(),
+            mode,
+            dry_run,
+            succeeded,
+            failed: entries.len() - succeeded,
+            freed_bytes: entries.iter().filter(|e| e.result.success).map(|e| e.size_bytes).sum(),
+            entries,
+        }
+    }
+}
+
+/// 执行记录中的一项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TranscriptEntry {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub source: CleanupSource,
+    /// 删除结果（失败时含错误码与原因）
+    #[serde(flatten)]
+    pub result: DeleteResult,
+}
+
+/// 执行方案的记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CleanupTranscript {
+    pub root_path: PathBuf,
+    /// 请求的删除方式（各项实际采用的方式见条目的 `effective_mode`）
+    pub mode: DeleteMode,
+    pub dry_run: bool,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// 成功删除（`dry_run` 时为将要删除）的条目总大小
+    pub freed_bytes: u64,
+    /// 与方案条目一一对应，顺序相同
+    pub entries: Vec<TranscriptEntry>,
+}
+
+/// 系统保留条目（及其内容）与匹配保留规则的路径受保护
+fn protector<'a>(result: &'a ScanResult, keep_patterns: &[String]) -> io::Result<impl Fn(&Path) -> bool + 'a> {
+    let root = &result.summary.root_path;
+    let keep = RuleSet::new(keep_patterns, &[])?;
+    let reserved: HashSet<&Path> = result.system_reserved.iter().map(|entry| entry.path.as_path()).collect();
+    Ok(move |path: &Path| {
+        reserved.iter().any(|r| path.starts_with(r)) || keep.matches(path.strip_prefix(root).unwrap_or(path), path)
+    })
+}
+
+/// 由扫描结果生成释放 `options.target_bytes` 字节的清理方案；保留规则非法时返回 `InvalidInput` 错误
+pub fn plan(result: &ScanResult, options: &CleanupOptions) -> io::Result<CleanupPlan> {
+    let root = &result.summary.root_path;
+    let protected = protector(result, &options.keep_patterns)?;
+
+    let mut candidates = Vec::new();
+    let mut protected_skipped = 0;
+    let mut push = |item: CleanupItem, reserved: bool| {
+        if reserved || protected(&item.path) {
+            protected_skipped += 1;
+        } else {
+            candidates.push(item);
+        }
+    };
+    for entry in &result.redundant_files {
+        let reason = entry.redundant.map_or("冗余附属文件", |kind| kind.label()).to_string();
+        push(CleanupItem::from_entry(entry, CleanupSource::Redundant, reason), entry.reserved.is_some());
+    }
+    for entry in result.top_files.iter().chain(&result.stale_files) {
+        if let Some(dir) = cache_dir(entry.path.strip_prefix(root).unwrap_or(&entry.path)) {
+            push(CleanupItem::from_entry(entry, CleanupSource::Cache, format!("位于缓存目录 {}", dir)), entry.reserved.is_some());
+        }
+    }
+    for group in &result.duplicates {
+        // 有受保护的副本时保留它，否则保留排在最前的一份
+        let kept = group.paths.iter().find(|path| protected(path)).or(group.paths.first());
+        let Some(kept) = kept else {
+            continue;
+        };
+        for path in group.paths.iter().filter(|path| *path != kept) {
+            let item = CleanupItem {
+                path: path.clone(),
+                size_bytes: group.size_bytes,
+                source: CleanupSource::Duplicate,
+                reason: format!("与 {} 内容相同", kept.display()),
+                expected: ExpectedEntry { size_bytes: group.size_bytes, ..ExpectedEntry::default() },
+            };
+            push(item, false);
+        }
+    }
+    for entry in &result.stale_files {
+        let reason = match entry.idle_seconds {
+            Some(idle) => format!("{} 天未使用", idle / 86_400),
+            None => "陈旧文件".to_string(),
+        };
+        push(CleanupItem::from_entry(entry, CleanupSource::Stale, reason), entry.reserved.is_some());
+    }
+
+    candidates.sort_by(|a, b| {
+        a.source.cmp(&b.source).then(b.size_bytes.cmp(&a.size_bytes)).then_with(|| a.path.cmp(&b.path))
+    });
+    let mut seen = HashSet::new();
+    let mut items = Vec::new();
+    let mut planned_bytes = 0u64;
+    for item in candidates {
+        if planned_bytes >= options.target_bytes {
+            break;
+        }
+        if seen.insert(item.path.clone()) {
+            planned_bytes += item.size_bytes;
+            items.push(item);
+        }
+    }
+    Ok(CleanupPlan {
+        root_path: root.clone(),
+        target_bytes: options.target_bytes,
+        planned_bytes,
+        target_met: planned_bytes >= options.target_bytes,
+        items,
+        protected_skipped,
+        selections: Vec::new(),
+    })
+}
+
+/// 按条件生成清理方案：各条件选出的条目合并，同一路径按风险最低的来源计入一次，
+/// 受保护的路径不会出现在方案中（重复文件组中受保护的副本同样保留）。方案不设目标（`target_bytes` 为 0）。
+/// 保留规则或匹配规则非法时返回 `InvalidInput` 错误
+pub fn select(result: &ScanResult, selections: &[Selection], keep_patterns: &[String]) -> io::Result<CleanupPlan> {
+    let root = &result.summary.root_path;
+    let protected = protector(result, keep_patterns)?;
+    let mut candidates = Vec::new();
+    let mut protected_skipped = 0;
+    let mut push = |item: CleanupItem, reserved: bool| {
+        if reserved || protected(&item.path) {
+            protected_skipped += 1;
+        } else {
+            candidates.push(item);
+        }
+    };
+
+    for selection in selections {
+        match selection {
+            Selection::StaleOlderThan { days } => {
+                for entry in &result.stale_files {
+                    let Some(idle) = entry.idle_seconds.filter(|&idle| idle >= u64::from(*days) * 86_400) else {
+                        continue;
+                    };
+                    let reason = format!("{} 天未使用", idle / 86_400);
+                    push(CleanupItem::from_entry(entry, CleanupSource::Stale, reason), entry.reserved.is_some());
+                }
+            }
+            Selection::DuplicatesExceptNewest => {
+                for group in &result.duplicates {
+                    // 以当前的修改时间判断最新副本；无法读取的副本不参与（也不会被删除）
+                    let mut copies: Vec<(&PathBuf, SystemTime)> = group
+                        .paths
+                        .iter()
+                        .filter_map(|path| Some((path, fs::metadata(path).ok()?.modified().ok()?)))
+                        .collect();
+                    copies.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+                    let Some(&(newest, _)) = copies.first() else {
+                        continue;
+                    };
+                    for &(path, modified) in &copies[1..] {
+                        let item = CleanupItem {
+                            path: path.clone(),
+                            size_bytes: group.size_bytes,
+                            source: CleanupSource::Duplicate,
+                            reason: format!("与 {} 内容相同（保留最新的副本）", newest.display()),
+                            expected: ExpectedEntry {
+                                size_bytes: group.size_bytes,
+                                last_modified: Some(modified),
+                                ..ExpectedEntry::default()
+                            },
+                        };
+                        push(item, false);
+                    }
+                }
+            }
+            Selection::Matching { pattern } => {
+                let rule = RuleSet::new(std::slice::from_ref(pattern), &[])?;
+                for entry in all_entries(result) {
+                    if rule.matches(entry.path.strip_prefix(root).unwrap_or(&entry.path), &entry.path) {
+                        let reason = format!("匹配 {}", pattern);
+                        push(CleanupItem::from_entry(entry, CleanupSource::Matched, reason), entry.reserved.is_some());
+                    }
+                }
+            }
+        }
+    }
+
+    candidates.sort_by(|a, b| {
+        a.source.cmp(&b.source).then(b.size_bytes.cmp(&a.size_bytes)).then_with(|| a.path.cmp(&b.path))
+    });
+    let mut seen = HashSet::new();
+    candidates.retain(|item| seen.insert(item.path.clone()));
+    let planned_bytes = candidates.iter().map(|item| item.size_bytes).sum();
+    Ok(CleanupPlan {
+        root_path: root.clone(),
+        target_bytes: 0,
+        planned_bytes,
+        target_met: true,
+        items: candidates,
+        protected_skipped,
+        selections: selections.to_vec(),
+    })
+}
+
+/// 扫描结果中的全部文件条目（可能重复）
+fn all_entries(result: &ScanResult) -> impl Iterator<Item = &FileEntry> {
+    result
+        .top_files
+        .iter()
+        .chain(&result.stale_files)
+        .chain(&result.redundant_files)
+        .chain(&result.suspicious_files)
+        .chain(&result.oldest_files)
+        .chain(&result.newest_files)
+        .chain(result.by_extension.iter().flat_map(|stat| &stat.top_files))
+}
+
+/// 路径（相对根目录）所在的缓存目录名；不在缓存目录中时返回 None
+fn cache_dir(relative: &Path) -> Option<String> {
+    let parent = relative.parent()?;
+    parent
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .find(|name| CACHE_DIR_NAMES.iter().any(|cache| name.eq_ignore_ascii_case(cache)))
+        .map(|name| name.into_owned())
+}
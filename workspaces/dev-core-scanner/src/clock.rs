@@ -0,0 +1,43 @@
+//! 分析所用的时钟
+//!
+//! 陈旧文件判断、冷度评分与年龄热力图都以“当前时间”为参照。扫描开始时从 [`ScanRequest::clock`]
+//! 读取一次当前时间，整个扫描共用；未指定时使用系统时钟。注入 [`FixedClock`] 可以按任意时刻分析
+//! （例如“90 天后哪些文件会变陈旧”），测试也因此不依赖真实时间。
+//!
+//! [`ScanRequest::clock`]: crate::ScanRequest::clock
+
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// 时钟：提供分析所用的当前时间
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// 当前时间
+    fn now(&self) -> SystemTime;
+}
+
+/// 系统时钟
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// 固定时钟：始终返回同一时刻
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub SystemTime);
+
+impl FixedClock {
+    /// 相对系统当前时间偏移 `offset` 之后的固定时刻
+    pub fn after(offset: Duration) -> Self {
+        Self(SystemTime::now() + offset)
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
@@ -0,0 +1,248 @@
+//! 可压缩文件分析
+//!
+//! 指定 [`ScanRequest::compression`] 后，遍历时记录达到大小与年龄下限的普通文件，遍历结束后估算每个文件
+//! 压缩（或归档）后的大小，按估计可节省的字节数降序列入 `ScanResult::compression_candidates`：
+//!
+//! - 扩展名启发：日志、文本与数据导出压缩率很高，数据库、未压缩的音频图像、磁盘镜像与可执行文件次之；
+//!   本身已压缩的格式（压缩包、JPEG/PNG、音视频、Office 文档等）直接排除，不读取；
+//! - 熵采样（`sample_bytes` 非零时）：读取文件开头若干字节计算按字节的香农熵，以“熵 / 8”作为压缩比的估计。
+//!   采样结果优先于扩展名启发，扩展名未知的文件只有在采样时才可能入选。
+//!
+//! 估计只用于发现值得归档的文件，实际压缩效果取决于算法与内容。云端占位文件不读取，不参与分析；
+//! 仅汇总扫描不收集候选文件。
+//!
+//! [`ScanRequest::compression`]: crate::ScanRequest::compression
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{lock, serde_time, CancellationToken, FileEntry};
+
+/// 默认的文件大小下限：16 MiB
+pub const DEFAULT_MIN_SIZE: u64 = 16 * 1024 * 1024;
+
+/// 默认的年龄下限：90 天未修改
+pub const DEFAULT_MIN_AGE_DAYS: u32 = 90;
+
+/// 单个文件最多采样的字节数
+pub const MAX_SAMPLE_BYTES: u64 = 1024 * 1024;
+
+/// 估计压缩比高于此值（节省不足一成）的文件不列出
+const MAX_RATIO: f64 = 0.9;
+
+/// 本身已压缩的格式，不作为候选
+const COMPRESSED: &[&str] = &[
+    "7z", "aac", "apk", "avi", "avif", "br", "bz2", "cab", "deb", "dmg", "docx", "epub", "flac", "flv", "gif", "gz",
+    "heic", "heif", "ipa", "jar", "jpeg", "jpg", "lz", "lz4", "lzma", "m4a", "m4v", "mkv", "mov", "mp3", "mp4", "odp",
+    "ods", "odt", "ogg", "opus", "pdf", "png", "pptx", "rar", "rpm", "tbz2", "tgz", "txz", "webm", "webp", "whl",
+    "wmv", "xlsx", "xz", "zip", "zst",
+];
+
+/// 按扩展名估计的压缩比（压缩后大小 / 原大小）
+const EXTENSION_RATIOS: &[(&[&str], f64)] = &[
+    // 日志、文本与数据导出
+    (
+        &["csv", "htm", "html", "json", "jsonl", "log", "md", "ndjson", "out", "sql", "trace", "tsv", "txt", "xml", "yaml", "yml"],
+        0.2,
+    ),
+    // 数据库与转储
+    (&["accdb", "db", "dbf", "dump", "mdb", "sqlite", "sqlite3"], 0.35),
+    // 可执行文件、库与调试符号
+    (&["a", "dll", "dylib", "exe", "lib", "o", "obj", "pdb", "so", "wasm"], 0.45),
+    // 磁盘与虚拟机镜像
+    (&["img", "iso", "qcow2", "vdi", "vhd", "vhdx", "vmdk"], 0.5),
+    // 未压缩的音频与图像
+    (&["aif", "aiff", "bmp", "pcm", "psd", "tif", "tiff", "wav"], 0.6),
+];
+
+/// 可压缩文件分析选项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressionOptions {
+    /// 文件大小下限（字节），默认 [`DEFAULT_MIN_SIZE`]
+    pub min_size: u64,
+    /// 最后修改距今的天数下限，默认 [`DEFAULT_MIN_AGE_DAYS`]
+    pub min_age_days: u32,
+    /// 熵采样读取文件开头的字节数（最多 [`MAX_SAMPLE_BYTES`]），0 表示只按扩展名估计
+    pub sample_bytes: u64,
+}
+
+impl Default for CompressionOptions {
+    fn default() -> Self {
+        Self { min_size: DEFAULT_MIN_SIZE, min_age_days: DEFAULT_MIN_AGE_DAYS, sample_bytes: 0 }
+    }
+}
+
+/// 压缩比的估计依据
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum CompressionBasis {
+    /// 按扩展名启发估计
+    Extension,
+    /// 按文件开头的熵采样估计
+    Sampled,
+}
+
+/// 一个可压缩的候选文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CompressionCandidate {
+    /// 文件路径
+    pub path: PathBuf,
+    /// 文件大小（字节）
+    pub size_bytes: u64,
+    /// 最后修改时间
+    #[serde(default, with = "serde_time::option")]
+    pub last_modified: Option<SystemTime>,
+    /// 估计依据
+    pub basis: CompressionBasis,
+    /// 估计的压缩比（压缩后大小 / 原大小，0~1）
+    pub estimated_ratio: f64,
+    /// 估计压缩后可节省的字节数
+    pub estimated_savings_bytes: u64,
+    /// 采样得到的熵（比特/字节，0~8），仅熵采样时有值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entropy_bits: Option<f64>,
+}
+
+impl CompressionCandidate {
+    fn new(seed: Seed, basis: CompressionBasis, ratio: f64, entropy_bits: Option<f64>) -> Self {
+        // 保留两位小数，便于比较与展示
+        let ratio = (ratio.clamp(0.0, 1.0) * 100.0).round() / 100.0;
+        Self {
+            estimated_savings_bytes: (seed.size_bytes as f64 * (1.0 - ratio)) as u64,
+            path: seed.path,
+            size_bytes: seed.size_bytes,
+            last_modified: seed.last_modified,
+            basis,
+            estimated_ratio: ratio,
+            entropy_bits,
+        }
+    }
+}
+
+/// 按扩展名估计的压缩比；已压缩的格式返回 `Some(1.0)`，未知扩展名返回 `None`
+pub fn extension_ratio(extension: &str) -> Option<f64> {
+    let extension = extension.to_ascii_lowercase();
+    if COMPRESSED.contains(&extension.as_str()) {
+        return Some(1.0);
+    }
+    EXTENSION_RATIOS
+        .iter()
+        .find(|(extensions, _)| extensions.contains(&extension.as_str()))
+        .map(|&(_, ratio)| ratio)
+}
+
+/// 数据按字节的香农熵（比特/字节）
+pub fn entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0u64; 256];
+    for &b in data {
+        counts[b as usize] += 1;
+    }
+    let total = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// 读取文件开头最多 `limit` 字节并计算熵
+fn sample_entropy(path: &Path, limit: u64) -> io::Result<f64> {
+    let file = File::open(crate::long_path::extended(path))?;
+    let mut data = Vec::new();
+    file.take(limit).read_to_end(&mut data)?;
+    Ok(entropy(&data))
+}
+
+/// 遍历时记录的候选文件
+struct Seed {
+    path: PathBuf,
+    size_bytes: u64,
+    last_modified: Option<SystemTime>,
+    /// 扩展名启发的压缩比（未知扩展名为 `None`）
+    ratio: Option<f64>,
+}
+
+/// 遍历期间的候选文件收集
+#[derive(Default)]
+pub(crate) struct Counters {
+    pub(crate) options: Option<CompressionOptions>,
+    seeds: Mutex<Vec<Seed>>,
+}
+
+impl Counters {
+    /// 记录达到大小与年龄下限、且不是已压缩格式的文件（`modified` 为校正后的修改时间）
+    pub(crate) fn offer(&self, entry: &FileEntry, modified: Option<SystemTime>, now: SystemTime) {
+        let Some(options) = &self.options else {
+            return;
+        };
+        if entry.size_bytes < options.min_size.max(1) {
+            return;
+        }
+        let min_age = Duration::from_secs(u64::from(options.min_age_days) * 86_400);
+        if modified.and_then(|m| now.duration_since(m).ok()).is_none_or(|age| age < min_age) {
+            return;
+        }
+        let ratio = entry.extension.as_deref().and_then(extension_ratio);
+        // 已压缩的格式不读取；未知扩展名只在采样时才能估计
+        if ratio.is_some_and(|r| r > MAX_RATIO) || (ratio.is_none() && options.sample_bytes == 0) {
+            return;
+        }
+        lock(&self.seeds).push(Seed {
+            path: entry.path.clone(),
+            size_bytes: entry.size_bytes,
+            last_modified: entry.last_modified,
+            ratio,
+        });
+    }
+
+    /// 估算全部候选文件（在调用方所在的 rayon 线程池上并行采样），返回最多 `limit` 个候选文件，
+    /// 以及全部候选文件的数量与估计可节省的总字节数
+    pub(crate) fn to_vec(&self, limit: usize, cancel: Option<&CancellationToken>) -> (Vec<CompressionCandidate>, u64, u64) {
+        let sample_bytes = self.options.as_ref().map_or(0, |o| o.sample_bytes.min(MAX_SAMPLE_BYTES));
+        let seeds = std::mem::take(&mut *lock(&self.seeds));
+        let mut candidates: Vec<CompressionCandidate> = seeds
+            .into_par_iter()
+            .filter_map(|seed| {
+                if sample_bytes > 0 && !cancel.is_some_and(CancellationToken::is_canceled) {
+                    if let Ok(bits) = sample_entropy(&seed.path, sample_bytes) {
+                        let ratio = bits / 8.0;
+                        return (ratio <= MAX_RATIO)
+                            .then(|| CompressionCandidate::new(seed, CompressionBasis::Sampled, ratio, Some(bits)));
+                    }
+                }
+                // 无法采样时退回扩展名启发
+                let ratio = seed.ratio?;
+                Some(CompressionCandidate::new(seed, CompressionBasis::Extension, ratio, None))
+            })
+            .collect();
+        let count = candidates.len() as u64;
+        let savings = candidates.iter().map(|c| c.estimated_savings_bytes).sum();
+        sort_and_truncate(&mut candidates, limit);
+        (candidates, count, savings)
+    }
+}
+
+/// 按估计可节省的字节数降序、路径升序排列并截断
+pub(crate) fn sort_and_truncate(candidates: &mut Vec<CompressionCandidate>, limit: usize) {
+    candidates.sort_by(|a, b| {
+        b.estimated_savings_bytes
+            .cmp(&a.estimated_savings_bytes)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    candidates.truncate(limit);
+}
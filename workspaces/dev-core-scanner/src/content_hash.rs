@@ -0,0 +1,155 @@
+//! 文件内容摘要
+//!
+//! 指定 [`ScanRequest::compute_hash`] 后，遍历结束时为 Top N 大文件计算内容摘要（MD5 / SHA-256 / BLAKE3），
+//! 写入各条目的 `digest`，便于与备份或其他机器上的副本比对。摘要计算在扫描线程池上并行进行，
+//! 作为“哈希”阶段计入进度；可通过 `hash_max_bytes_per_sec` 限制读取速率，与重复检测共用同一限速方式。
+//! 无法读取的文件不带摘要，扫描已取消时跳过剩余文件。
+//!
+//! [`ScanRequest::compute_hash`]: crate::ScanRequest::compute_hash
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+use crate::dedup::Throttle;
+use crate::{CancellationToken, FileEntry, LiveProgress};
+
+/// 每次读取的块大小
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// 摘要算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum HashAlgo {
+    /// MD5（128 位，仅用于与已有清单比对）
+    Md5,
+    /// SHA-256
+    Sha256,
+    /// BLAKE3（256 位，速度最快）
+    Blake3,
+}
+
+impl HashAlgo {
+    /// 算法名称（与序列化形式一致）
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgo::Md5 => "md5",
+            HashAlgo::Sha256 => "sha256",
+            HashAlgo::Blake3 => "blake3",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgo {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "md5" => Ok(HashAlgo::Md5),
+            "sha256" | "sha-256" => Ok(HashAlgo::Sha256),
+            "blake3" => Ok(HashAlgo::Blake3),
+            other => Err(format!("不支持的摘要算法: {}（可选 md5、sha256、blake3）", other)),
+        }
+    }
+}
+
+/// 增量计算摘要的状态
+enum Hasher {
+    Md5(md5::Md5),
+    Sha256(sha2::Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    fn new(algo: HashAlgo) -> Self {
+        match algo {
+            HashAlgo::Md5 => Hasher::Md5(md5::Md5::new()),
+            HashAlgo::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            HashAlgo::Blake3 => Hasher::Blake3(Box::default()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(h) => h.update(data),
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Blake3(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    fn finish_hex(self) -> String {
+        match self {
+            Hasher::Md5(h) => hex(&h.finalize()),
+            Hasher::Sha256(h) => hex(&h.finalize()),
+            Hasher::Blake3(h) => h.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 计算文件内容摘要（十六进制小写）
+pub fn hash_file(path: &Path, algo: HashAlgo) -> io::Result<String> {
+    hash_with(path, algo, None, None)
+}
+
+/// 计算文件内容摘要：读取前按限速器等待，并累加哈希进度
+fn hash_with(path: &Path, algo: HashAlgo, throttle: Option<&Throttle>, live: Option<&LiveProgress>) -> io::Result<String> {
+    let mut file = File::open(crate::long_path::extended(path))?;
+    let mut hasher = Hasher::new(algo);
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        if let Some(throttle) = throttle {
+            throttle.acquire(n as u64);
+        }
+        hasher.update(&buf[..n]);
+        if let Some(live) = live {
+            live.hashed_bytes.fetch_add(n as u64, Ordering::Relaxed);
+        }
+    }
+    Ok(hasher.finish_hex())
+}
+
+/// 为条目并行计算摘要（在调用方所在的 rayon 线程池上执行）
+pub(crate) fn digest_entries(
+    entries: &mut [FileEntry],
+    algo: HashAlgo,
+    max_bytes_per_sec: Option<u64>,
+    live: &LiveProgress,
+    cancel: Option<&CancellationToken>,
+) {
+    // 云端占位文件不读取（读取会触发下载），不带摘要
+    live.hash_total_bytes.fetch_add(
+        entries.iter().filter(|e| !e.is_placeholder).map(|e| e.size_bytes).sum(),
+        Ordering::Relaxed,
+    );
+    let throttle = max_bytes_per_sec.map(Throttle::new);
+    entries.par_iter_mut().for_each(|entry| {
+        if entry.is_placeholder || cancel.is_some_and(CancellationToken::is_canceled) {
+            return;
+        }
+        entry.digest = hash_with(&entry.path, algo, throttle.as_ref(), Some(live)).ok();
+    });
+}
@@ -0,0 +1,188 @@
+//! 重复文件检测
+//!
+//! 遍历阶段只收集候选文件（路径与大小），遍历结束后进入独立的哈希阶段：
+//! 先按大小分组，只有大小相同的文件才需要读取内容计算摘要，再按摘要分组得到重复文件组。
+//!
+//! 哈希阶段读盘量大，不沿用遍历的线程数：使用独立大小的线程池（`hash_threads`），
+//! 并可按字节/秒限速，避免在机械硬盘或前台使用的机器上占满 IO。
+//!
+//! 摘要为非加密的 64 位哈希，仅用于发现重复；删除前应再次确认文件内容。
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{CancellationToken, LiveProgress, ScanError};
+
+/// 未指定 `hash_threads` 时的哈希线程数上限：读盘为主，更多线程通常只会加剧磁盘寻道
+pub const DEFAULT_MAX_HASH_THREADS: usize = 4;
+
+/// 每次读取的块大小
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// 重复文件检测选项
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DedupOptions {
+    /// 哈希阶段的线程数（默认取逻辑核心数与 [`DEFAULT_MAX_HASH_THREADS`] 的较小值）
+    pub hash_threads: Option<u16>,
+    /// 哈希阶段读取速率上限（字节/秒），`None` 表示不限速
+    pub max_bytes_per_sec: Option<u64>,
+}
+
+impl DedupOptions {
+    /// 哈希阶段实际使用的线程数
+    pub fn effective_threads(&self) -> usize {
+        match self.hash_threads {
+            Some(n) if n > 0 => n as usize,
+            _ => thread::available_parallelism()
+                .map_or(1, |n| n.get())
+                .min(DEFAULT_MAX_HASH_THREADS),
+        }
+    }
+}
+
+/// 一组内容相同的文件
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DuplicateGroup {
+    /// 单个文件大小（字节）
+    pub size_bytes: u64,
+    /// 内容摘要（十六进制）
+    pub digest: String,
+    /// 组内文件路径（按路径排序）
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateGroup {
+    /// 只保留一份时可释放的字节数
+    pub fn wasted_bytes(&self) -> u64 {
+        self.size_bytes * (self.paths.len() as u64).saturating_sub(1)
+    }
+}
+
+/// 多个哈希线程共享的读取限速器：按累计读取量计算应耗时间，超前时休眠补齐
+pub(crate) struct Throttle {
+    bytes_per_sec: u64,
+    started: Instant,
+    consumed: AtomicU64,
+}
+
+impl Throttle {
+    pub(crate) fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.max(1),
+            started: Instant::now(),
+            consumed: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn acquire(&self, bytes: u64) {
+        let consumed = self.consumed.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        let due = Duration::from_secs_f64(consumed as f64 / self.bytes_per_sec as f64);
+        if let Some(wait) = due.checked_sub(self.started.elapsed()) {
+            thread::sleep(wait);
+        }
+    }
+}
+
+/// 计算文件内容摘要，同时累加哈希进度
+fn digest(path: &Path, throttle: Option<&Throttle>, live: &LiveProgress) -> io::Result<String> {
+    let mut file = File::open(crate::long_path::extended(path))?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        if let Some(throttle) = throttle {
+            throttle.acquire(n as u64);
+        }
+        hasher.write(&buf[..n]);
+        live.hashed_bytes.fetch_add(n as u64, Ordering::Relaxed);
+    }
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// 在候选文件中查找重复文件组；按可释放字节数降序排列。无法读取的文件被跳过
+pub(crate) fn find_duplicates(
+    candidates: Vec<(PathBuf, u64)>,
+    options: &DedupOptions,
+    live: &LiveProgress,
+    cancel: Option<&CancellationToken>,
+) -> Result<Vec<DuplicateGroup>, ScanError> {
+    // 空文件全部相同，没有清理价值，不参与检测
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for (path, size) in candidates {
+        if size > 0 {
+            by_size.entry(size).or_default().push(path);
+        }
+    }
+    let to_hash: Vec<(PathBuf, u64)> = by_size
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .flat_map(|(size, paths)| paths.into_iter().map(move |p| (p, size)))
+        .collect();
+    live.hash_total_bytes
+        .store(to_hash.iter().map(|(_, size)| size).sum(), Ordering::Relaxed);
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.effective_threads())
+        .build()
+        .map_err(|e| ScanError::ThreadPool { message: e.to_string() })?;
+    let throttle = options.max_bytes_per_sec.map(Throttle::new);
+    let hashed: Vec<(PathBuf, u64, String)> = pool.install(|| {
+        to_hash
+            .into_par_iter()
+            .filter_map(|(path, size)| {
+                // 已取消时跳过剩余文件，已得到的摘要仍参与分组
+                if cancel.is_some_and(CancellationToken::is_canceled) {
+                    return None;
+                }
+                let digest = digest(&path, throttle.as_ref(), live).ok()?;
+                Some((path, size, digest))
+            })
+            .collect()
+    });
+
+    let mut groups: HashMap<(u64, String), Vec<PathBuf>> = HashMap::new();
+    for (path, size, digest) in hashed {
+        groups.entry((size, digest)).or_default().push(path);
+    }
+    Ok(finish(groups))
+}
+
+/// 合并多份结果中的重复文件组（同一大小与摘要的组合并为一组）
+pub(crate) fn merge(groups: impl IntoIterator<Item = DuplicateGroup>) -> Vec<DuplicateGroup> {
+    let mut merged: HashMap<(u64, String), Vec<PathBuf>> = HashMap::new();
+    for group in groups {
+        merged.entry((group.size_bytes, group.digest)).or_default().extend(group.paths);
+    }
+    finish(merged)
+}
+
+fn finish(groups: HashMap<(u64, String), Vec<PathBuf>>) -> Vec<DuplicateGroup> {
+    let mut groups: Vec<DuplicateGroup> = groups
+        .into_iter()
+        .filter_map(|((size_bytes, digest), mut paths)| {
+            paths.sort();
+            paths.dedup();
+            (paths.len() > 1).then_some(DuplicateGroup { size_bytes, digest, paths })
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.wasted_bytes()
+            .cmp(&a.wasted_bytes())
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+    groups
+}
@@ -0,0 +1,609 @@
+//! 文件删除（见 Architecture.md 4.1 / 5.3）
+//!
+//! 所有具备删除能力的前端共用 [`delete_entry`]（单个条目）与 [`to_trash`] / [`permanent`]（批量，
+//! 逐项返回结果）：核心层只负责执行与校验，二次确认由上层 UI 完成。
+//!
+//! 保护规则（见 [`protection_reason`]）在任何校验之前生效，`dry_run` 同样适用，命中时以
+//! [`ERROR_PROTECTED`] 拒绝：扫描根目录本身及其上级目录（需在 `scan_root` 中给出）、文件系统根目录、
+//! 用户主目录、回收站目录、系统目录（如 `/usr`、`/etc`、`/System`、`C:\Windows` 及其内容，
+//! `/var`、`/Applications`、`C:\Program Files` 等目录本身）以及系统保留文件（见 [`reserved`](crate::reserved)）。
+//! 判断前先规范化父目录（解析其中的符号链接与 `..`），路径自身为符号链接时按链接本身判断。
+//!
+//! 扫描结果可能已经过时：文件在扫描后被修改、替换为同名的新文件，甚至换成了目录。
+//! 调用方传入扫描时记录的条目（[`ExpectedEntry`]）后，删除前会重新读取元数据，按
+//! [`Verification`] 指定的严格程度比对大小与修改时间，不一致时拒绝删除并说明原因。
+//!
+//! 回收站语义：
+//! - macOS：移动到 `~/.Trash`；
+//! - Linux 等其他 Unix：按 XDG Trash 规范移动到 `$XDG_DATA_HOME/Trash`（默认 `~/.local/share/Trash`），
+//!   并写入 `.trashinfo` 记录原路径；
+//! - 无法移入回收站（无回收站目录、回收站不可写、与回收站不在同一文件系统等）时以 [`ERROR_IO`] 失败，
+//!   不删除任何内容；`dry_run` 同样检查这些条件。只有显式设置 `allow_permanent_fallback` 时才退化为
+//!   永久删除，结果中以 `effective_mode = permanent` 标记，由上层 UI 告知用户。
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{serde_time, FileEntry};
+
+/// 错误码：路径不存在
+pub const ERROR_NOT_FOUND: i32 = 1;
+/// 错误码：路径在扫描后发生变化（校验未通过）
+pub const ERROR_CHANGED: i32 = 2;
+/// 错误码：删除或移动时发生 IO 错误
+pub const ERROR_IO: i32 = 3;
+/// 错误码：路径受保护（扫描根目录、系统目录等），见 [`protection_reason`]
+pub const ERROR_PROTECTED: i32 = 4;
+
+/// 删除方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteMode {
+    /// 移至回收站/废纸篓
+    #[default]
+    #[serde(rename = "trash")]
+    MoveToTrash,
+    /// 永久删除
+    Permanent,
+}
+
+/// 删除请求的来源（用于审计与策略演进）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteOrigin {
+    Cli,
+    Tui,
+    Gui,
+    #[default]
+    Service,
+}
+
+/// 删除前校验的严格程度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verification {
+    /// 不校验（仍要求路径存在）
+    Off,
+    /// 校验类型与大小
+    #[default]
+    Size,
+    /// 校验类型、大小与修改时间（毫秒精度）
+    SizeAndMtime,
+}
+
+/// 扫描时记录的条目信息，用于删除前比对
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ExpectedEntry {
+    /// 是否为目录（目录只校验类型，不比对大小与修改时间）
+    #[serde(default)]
+    pub is_dir: bool,
+    /// 扫描时的大小（字节）
+    pub size_bytes: u64,
+    /// 扫描时的修改时间
+    #[serde(default, with = "serde_time::option")]
+    pub last_modified: Option<SystemTime>,
+}
+
+impl ExpectedEntry {
+    /// 目录条目
+    pub fn dir() -> Self {
+        Self { is_dir: true, ..Self::default() }
+    }
+}
+
+impl From<&FileEntry> for ExpectedEntry {
+    fn from(entry: &FileEntry) -> Self {
+        Self {
+            is_dir: false,
+            size_bytes: entry.size_bytes,
+            last_modified: entry.last_modified,
+        }
+    }
+}
+
+/// 删除选项
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeleteOptions {
+    pub mode: DeleteMode,
+    pub origin: DeleteOrigin,
+    /// 只做存在性、校验与可行性检查，不真正删除
+    pub dry_run: bool,
+    /// 校验严格程度；未提供 `expected` 时只要求路径存在
+    pub verify: Verification,
+    /// 扫描时记录的条目
+    pub expected: Option<ExpectedEntry>,
+    /// 扫描根目录：拒绝删除该目录本身及其上级目录
+    pub scan_root: Option<PathBuf>,
+    /// 无法移入回收站时退化为永久删除；默认不退化，返回错误
+    pub allow_permanent_fallback: bool,
+}
+
+/// 删除失败的原因
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DeleteError {
+    /// 错误码（见 `ERROR_*` 常量）
+    pub code: i32,
+    /// 面向用户的说明
+    pub message: String,
+}
+
+/// 删除结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DeleteResult {
+    pub success: bool,
+    /// 实际（dry_run 时为将要）采用的删除方式
+    pub effective_mode: DeleteMode,
+    /// 移入回收站后的位置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trashed_to: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<DeleteError>,
+}
+
+impl DeleteResult {
+    fn failed(effective_mode: DeleteMode, code: i32, message: String) -> Self {
+        Self {
+            success: false,
+            effective_mode,
+            trashed_to: None,
+            error: Some(DeleteError { code, message }),
+        }
+    }
+}
+
+/// 删除文件或目录（目录连同其内容）
+pub fn delete_entry(path: &Path, options: &DeleteOptions) -> DeleteResult {
+    let planned = options.mode;
+    if let Some(reason) = protection_reason(path, options.scan_root.as_deref()) {
+        return DeleteResult::failed(planned, ERROR_PROTECTED, format!("{} {}，已拒绝删除", path.display(), reason));
+    }
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return DeleteResult::failed(planned, ERROR_NOT_FOUND, format!("路径不存在: {}", path.display()));
+        }
+        Err(e) => return DeleteResult::failed(planned, ERROR_IO, format!("无法读取 {}: {}", path.display(), e)),
+    };
+    if let Some(expected) = &options.expected {
+        if let Err(reason) = verify(&metadata, expected, options.verify) {
+            return DeleteResult::failed(
+                planned,
+                ERROR_CHANGED,
+                format!("{} 在扫描后已变化，已拒绝删除：{}。请重新扫描后再试", path.display(), reason),
+            );
+        }
+    }
+    // 移入回收站前确认可行，不可行且未允许退化时拒绝删除
+    let trash = match planned {
+        DeleteMode::MoveToTrash => match usable_trash(trash_root().as_deref(), &metadata) {
+            Ok(trash) => Some(trash),
+            Err(_) if options.allow_permanent_fallback => None,
+            Err(reason) => {
+                return DeleteResult::failed(planned, ERROR_IO, format!("无法将 {} 移至回收站：{}", path.display(), reason));
+            }
+        },
+        DeleteMode::Permanent => None,
+    };
+    if options.dry_run {
+        let effective_mode = if trash.is_some() { DeleteMode::MoveToTrash } else { DeleteMode::Permanent };
+        return DeleteResult { success: true, effective_mode, ..DeleteResult::default() };
+    }
+
+    if let Some(trash) = trash {
+        match move_to_trash(path, &trash) {
+            Ok(trashed_to) => {
+                return DeleteResult {
+                    success: true,
+                    effective_mode: DeleteMode::MoveToTrash,
+                    trashed_to: Some(trashed_to),
+                    error: None,
+                };
+            }
+            Err(_) if options.allow_permanent_fallback => {}
+            Err(e) => {
+                return DeleteResult::failed(planned, ERROR_IO, format!("将 {} 移至回收站失败: {}", path.display(), e));
+            }
+        }
+    }
+    let removed = if metadata.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) };
+    match removed {
+        Ok(()) => DeleteResult { success: true, effective_mode: DeleteMode::Permanent, ..DeleteResult::default() },
+        Err(e) => DeleteResult::failed(DeleteMode::Permanent, ERROR_IO, format!("删除 {} 失败: {}", path.display(), e)),
+    }
+}
+
+/// 批量删除中的一项
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DeleteItem {
+    pub path: PathBuf,
+    /// 扫描时记录的条目，提供时按选项中的 `verify` 校验
+    #[serde(default)]
+    pub expected: Option<ExpectedEntry>,
+}
+
+impl DeleteItem {
+    pub fn new(path: impl Into<PathBuf>, expected: Option<ExpectedEntry>) -> Self {
+        Self { path: path.into(), expected }
+    }
+}
+
+impl From<&FileEntry> for DeleteItem {
+    fn from(entry: &FileEntry) -> Self {
+        Self { path: entry.path.clone(), expected: Some(ExpectedEntry::from(entry)) }
+    }
+}
+
+/// 批量删除中一项的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DeleteItemResult {
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub result: DeleteResult,
+}
+
+/// 批量移入回收站（无法移入时按 [`delete_entry`] 的规则失败，或在允许时退化为永久删除）
+///
+/// 按给定顺序逐项处理，某一项失败不影响其余各项；`options` 中的 `mode` 与 `expected` 被忽略，
+/// 各项的扫描记录取自 [`DeleteItem::expected`]。
+pub fn to_trash(items: &[DeleteItem], options: &DeleteOptions) -> Vec<DeleteItemResult> {
+    batch(items, options, DeleteMode::MoveToTrash)
+}
+
+/// 批量永久删除，规则同 [`to_trash`]
+pub fn permanent(items: &[DeleteItem], options: &DeleteOptions) -> Vec<DeleteItemResult> {
+    batch(items, options, DeleteMode::Permanent)
+}
+
+fn batch(items: &[DeleteItem], options: &DeleteOptions, mode: DeleteMode) -> Vec<DeleteItemResult> {
+    items
+        .iter()
+        .map(|item| {
+            let options = DeleteOptions { mode, expected: item.expected.clone(), ..options.clone() };
+            DeleteItemResult { path: item.path.clone(), result: delete_entry(&item.path, &options) }
+        })
+        .collect()
+}
+
+/// 路径受保护时返回原因（如“是扫描根目录或其上级目录”），否则返回 None
+pub fn protection_reason(path: &Path, scan_root: Option<&Path>) -> Option<String> {
+    let resolved = resolve(path);
+    if resolved.parent().is_none() {
+        return Some("是文件系统根目录".to_string());
+    }
+    if scan_root.is_some_and(|root| resolve(root).starts_with(&resolved)) {
+        return Some("是扫描根目录或其上级目录".to_string());
+    }
+    if home_dir().is_some_and(|home| resolve(&home) == resolved) {
+        return Some("是用户主目录".to_string());
+    }
+    if trash_root().is_some_and(|trash| resolve(&trash) == resolved) {
+        return Some("是回收站目录".to_string());
+    }
+    if let Some(kind) = crate::reserved::classify(&resolved, true).or_else(|| crate::reserved::classify(&resolved, false)) {
+        return Some(format!("是系统保留条目（{}）", kind));
+    }
+    is_system_path(&resolved).then(|| "是系统目录".to_string())
+}
+
+/// 规范化父目录后拼回文件名：父目录中的符号链接与 `..` 被解析，路径自身为符号链接时保持不变
+fn resolve(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(parent).unwrap_or_else(|_| parent.to_path_buf()).join(name),
+        _ => fs::canonicalize(&absolute).unwrap_or(absolute),
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" })
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// 系统目录：内容全部受保护的目录树
+#[cfg(unix)]
+const SYSTEM_TREES: &[&str] =
+    &["/bin", "/boot", "/dev", "/etc", "/lib", "/lib32", "/lib64", "/proc", "/sbin", "/sys", "/usr", "/System", "/private/etc"];
+
+/// 系统目录：只保护目录本身（其中的用户内容仍可删除）
+#[cfg(unix)]
+const SYSTEM_DIRS: &[&str] = &[
+    "/Applications", "/Library", "/Users", "/Volumes", "/home", "/media", "/mnt", "/opt", "/private", "/root", "/run",
+    "/srv", "/tmp", "/var",
+];
+
+#[cfg(unix)]
+fn is_system_path(path: &Path) -> bool {
+    SYSTEM_TREES.iter().any(|tree| path.starts_with(tree)) || SYSTEM_DIRS.iter().any(|dir| path == Path::new(dir))
+}
+
+/// Windows 目录（`%SystemRoot%`）及其内容，以及程序目录本身
+#[cfg(windows)]
+fn is_system_path(path: &Path) -> bool {
+    let lower = |p: &Path| crate::long_path::strip_extended(p).to_string_lossy().to_lowercase();
+    let target = lower(path);
+    let var = |name: &str| std::env::var_os(name).map(|v| lower(Path::new(&v)));
+    if var("SystemRoot").is_some_and(|root| target == root || target.starts_with(&format!("{}\\", root))) {
+        return true;
+    }
+    ["ProgramFiles", "ProgramFiles(x86)", "ProgramData"].iter().filter_map(|&name| var(name)).any(|dir| target == dir)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_system_path(_path: &Path) -> bool {
+    false
+}
+
+/// 按严格程度比对当前元数据与扫描时的记录，不一致时返回原因
+fn verify(metadata: &fs::Metadata, expected: &ExpectedEntry, level: Verification) -> Result<(), String> {
+    if level == Verification::Off {
+        return Ok(());
+    }
+    match (expected.is_dir, metadata.is_dir()) {
+        (true, true) => return Ok(()),
+        (true, false) => return Err("扫描时为目录，现在不是目录".to_string()),
+        (false, true) => return Err("扫描时为文件，现在是目录".to_string()),
+        (false, false) => {}
+    }
+    if metadata.len() != expected.size_bytes {
+        return Err(format!("大小由 {} 字节变为 {} 字节", expected.size_bytes, metadata.len()));
+    }
+    if level == Verification::SizeAndMtime {
+        // 结果文件中的时间为毫秒精度，按毫秒比较
+        let current = metadata.modified().ok().map(serde_time::to_millis);
+        let scanned = expected.last_modified.map(serde_time::to_millis);
+        if current != scanned {
+            let show = |ms: Option<i64>| ms.map_or("未知".to_string(), |ms| serde_time::to_rfc3339(serde_time::from_millis(ms)));
+            return Err(format!("修改时间由 {} 变为 {}", show(scanned), show(current)));
+        }
+    }
+    Ok(())
+}
+
+/// 当前用户的回收站目录（不存在时返回 None，调用方退化为永久删除）
+#[cfg(target_os = "macos")]
+pub(crate) fn trash_root() -> Option<PathBuf> {
+    let trash = PathBuf::from(std::env::var_os("HOME")?).join(".Trash");
+    trash.is_dir().then_some(trash)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub(crate) fn trash_root() -> Option<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    data_home.is_dir().then(|| data_home.join("Trash"))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn trash_root() -> Option<PathBuf> {
+    None
+}
+
+/// 检查条目能否移入回收站 `trash`：回收站存在、可写，且与条目（`metadata`）位于同一文件系统
+/// （跨文件系统无法原子移动）。可行时返回回收站目录，否则返回原因
+pub(crate) fn usable_trash(trash: Option<&Path>, metadata: &fs::Metadata) -> Result<PathBuf, String> {
+    let trash = trash.ok_or("没有可用的回收站目录")?;
+    // 条目实际移入的目录；尚未创建时检查将在其中创建它的最近上级目录
+    let target = if cfg!(target_os = "macos") { trash.to_path_buf() } else { trash.join("files") };
+    let existing = target.ancestors().find(|dir| dir.is_dir()).ok_or("回收站目录不存在")?;
+    #[cfg(unix)]
+    {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = fs::metadata(existing).map_err(|e| format!("无法读取回收站目录 {}: {}", existing.display(), e))?;
+        if dir.dev() != metadata.dev() {
+            return Err(format!("与回收站 {} 不在同一文件系统", trash.display()));
+        }
+        let c_path = CString::new(existing.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+        // SAFETY: c_path 为合法的 NUL 结尾字符串
+        if unsafe { libc::access(c_path.as_ptr(), libc::W_OK) } != 0 {
+            return Err(format!("回收站目录 {} 不可写", existing.display()));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (existing, metadata);
+    Ok(trash.to_path_buf())
+}
+
+/// 移入回收站，返回回收站中的位置；重名时追加序号
+#[cfg(target_os = "macos")]
+pub(crate) fn move_to_trash(path: &Path, trash: &Path) -> io::Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "路径没有文件名"))?;
+    for n in 1u32.. {
+        let target = trash.join(numbered(name, n));
+        if fs::symlink_metadata(&target).is_err() {
+            fs::rename(path, &target)?;
+            return Ok(target);
+        }
+    }
+    unreachable!()
+}
+
+/// 按 XDG Trash 规范移入回收站：先以独占方式创建 `info/<名称>.trashinfo` 占用名称，
+/// 再把文件移到 `files/<名称>`；移动失败（如跨文件系统）时撤销 info 文件
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn move_to_trash(path: &Path, trash: &Path) -> io::Result<PathBuf> {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    let name = path.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "路径没有文件名"))?;
+    let absolute = std::path::absolute(path)?;
+    let (files, info) = (trash.join("files"), trash.join("info"));
+    fs::create_dir_all(&files)?;
+    fs::create_dir_all(&info)?;
+    for n in 1u32.. {
+        let name = numbered(name, n);
+        let mut info_name = name.clone();
+        info_name.push(".trashinfo");
+        let info_path = info.join(info_name);
+        let mut info_file = match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+        let target = files.join(&name);
+        if fs::symlink_metadata(&target).is_ok() {
+            let _ = fs::remove_file(&info_path);
+            continue;
+        }
+        // DeletionDate 规范要求本地时间，这里以 UTC 写入（不带时区后缀，主流实现均可解析）
+        let deleted_at = serde_time::to_rfc3339(SystemTime::now());
+        let written = writeln!(
+            info_file,
+            "[Trash Info]\nPath={}\nDeletionDate={}",
+            percent_encode(&absolute),
+            &deleted_at[..19]
+        )
+        .and_then(|_| fs::rename(path, &target));
+        return match written {
+            Ok(()) => Ok(target),
+            Err(e) => {
+                let _ = fs::remove_file(&info_path);
+                Err(e)
+            }
+        };
+    }
+    unreachable!()
+}
+
+/// 撤销 [`delete_entry`] 的移入回收站操作：把 `trashed_to`（见 [`DeleteResult::trashed_to`]）
+/// 移回原路径 `original`，并清理 XDG 回收站中对应的 `.trashinfo`。
+/// 原路径已被占用或其父目录已不存在时返回错误，不覆盖任何文件。
+/// 只有 `trashed_to` 位于 XDG 回收站的 `files` 目录（且同级存在 `info` 目录）时才清理 `.trashinfo`
+pub fn restore_from_trash(trashed_to: &Path, original: &Path) -> io::Result<()> {
+    rename_no_replace(trashed_to, original).map_err(|e| {
+        if e.kind() == io::ErrorKind::AlreadyExists {
+            io::Error::new(io::ErrorKind::AlreadyExists, format!("原位置已存在同名条目: {}", original.display()))
+        } else {
+            e
+        }
+    })?;
+    let files = trashed_to.parent().filter(|dir| dir.file_name().is_some_and(|n| n == "files"));
+    let info = files.and_then(Path::parent).map(|trash| trash.join("info")).filter(|info| info.is_dir());
+    if let (Some(name), Some(info)) = (trashed_to.file_name(), info) {
+        let mut info_name = name.to_os_string();
+        info_name.push(".trashinfo");
+        let _ = fs::remove_file(info.join(info_name));
+    }
+    Ok(())
+}
+
+/// 把 `from` 移到 `to`，`to` 已存在时返回 `AlreadyExists` 而不覆盖（检查与移动之间没有竞态窗口）
+#[cfg(target_os = "linux")]
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let (c_from, c_to) = (CString::new(from.as_os_str().as_bytes())?, CString::new(to.as_os_str().as_bytes())?);
+    // SAFETY: 两个路径均为合法的 NUL 结尾字符串，在调用期间有效
+    let rc = unsafe {
+        libc::renameat2(libc::AT_FDCWD, c_from.as_ptr(), libc::AT_FDCWD, c_to.as_ptr(), libc::RENAME_NOREPLACE)
+    };
+    if rc == 0 {
+        return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    match err.raw_os_error() {
+        // 文件系统或内核不支持 RENAME_NOREPLACE 时退回可移植实现
+        Some(libc::EINVAL) | Some(libc::ENOSYS) => rename_exclusive(from, to),
+        _ => Err(err),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let (c_from, c_to) = (CString::new(from.as_os_str().as_bytes())?, CString::new(to.as_os_str().as_bytes())?);
+    // SAFETY: 两个路径均为合法的 NUL 结尾字符串，在调用期间有效
+    if unsafe { libc::renamex_np(c_from.as_ptr(), c_to.as_ptr(), libc::RENAME_EXCL) } == 0 {
+        return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    match err.raw_os_error() {
+        // 文件系统不支持 RENAME_EXCL 时退回可移植实现
+        Some(libc::ENOTSUP) | Some(libc::EINVAL) => rename_exclusive(from, to),
+        _ => Err(err),
+    }
+}
+
+/// Windows 上 `MoveFileExW` 不带 `MOVEFILE_REPLACE_EXISTING` 时不覆盖已存在的目标（文件与目录均适用）
+#[cfg(windows)]
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn MoveFileExW(existing_file_name: *const u16, new_file_name: *const u16, flags: u32) -> i32;
+    }
+
+    let wide = |path: &Path| -> Vec<u16> { crate::long_path::extended(path).as_os_str().encode_wide().chain(Some(0)).collect() };
+    let (w_from, w_to) = (wide(from), wide(to));
+    // SAFETY: 两个路径均以 NUL 结尾且在调用期间有效
+    if unsafe { MoveFileExW(w_from.as_ptr(), w_to.as_ptr(), 0) } == 0 {
+        // ERROR_ALREADY_EXISTS / ERROR_FILE_EXISTS 映射为 ErrorKind::AlreadyExists
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    rename_exclusive(from, to)
+}
+
+/// 可移植的不覆盖移动，供不支持原生不覆盖 rename 的 Unix 文件系统使用：文件与符号链接先硬链接到
+/// 目标（目标已存在时失败）再删除源路径；目录先独占创建同名空目录占位，再以 rename 替换该空目录
+/// （POSIX rename 允许以目录替换空目录，Windows 不允许，因此 Windows 不使用此实现）
+#[cfg(not(windows))]
+pub(crate) fn rename_exclusive(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(from)?.is_dir() {
+        fs::create_dir(to)?;
+        return fs::rename(from, to).inspect_err(|_| {
+            let _ = fs::remove_dir(to);
+        });
+    }
+    fs::hard_link(from, to)?;
+    fs::remove_file(from)
+}
+
+/// 第 n 个候选名称：`name`、`name.2`、`name.3`……
+fn numbered(name: &std::ffi::OsStr, n: u32) -> std::ffi::OsString {
+    let mut name = name.to_os_string();
+    if n > 1 {
+        name.push(format!(".{}", n));
+    }
+    name
+}
+
+/// `.trashinfo` 中 Path 字段的 URL 编码（保留 `/` 与非保留字符）
+#[cfg(not(target_os = "macos"))]
+fn percent_encode(path: &Path) -> String {
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().into_owned().into_bytes();
+    let mut encoded = String::with_capacity(bytes.len());
+    for b in bytes {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => encoded.push(b as char),
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
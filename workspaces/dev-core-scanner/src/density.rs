@@ -0,0 +1,207 @@
+//! 目录“重量密度”排名
+//!
+//! 同样占用大量空间的目录，清理方式截然不同：
+//!
+//! - 少量巨型文件（每文件字节数很大，如虚拟机镜像、安装包）：逐个删除或归档即可；
+//! - 海量小文件（每 MiB 条目数很大，如缓存、`node_modules`）：删除单个文件收效甚微，
+//!   应整体删除或打包，且遍历/备份开销主要来自条目数而非字节数。
+//!
+//! 排名依据目录的直接文件（不含子目录），以便准确指出问题所在的那一层目录。
+//! 两个列表均为有界堆，内存占用与 Top N 相同。
+//!
+//! 同一份直接文件统计还按总大小排名（`ScanResult::top_dirs_shallow`）：与按递归大小排名的
+//! `top_dirs` 不同，子目录的大小不计入，排在前面的是自身堆满了文件的“垃圾场”目录，而不是
+//! 只因为目录树很深很大的上层目录。
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::lock;
+
+/// 进入“碎文件目录”排名所需的最少直接文件数，避免只有一两个空文件的目录排在前面
+pub const CROWDED_MIN_FILES: u64 = 100;
+
+/// 目录密度统计行
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DirDensity {
+    /// 目录路径
+    pub path: PathBuf,
+    /// 直接文件数（不含子目录）
+    pub file_count: u64,
+    /// 直接文件总大小
+    pub size_bytes: u64,
+    /// 平均每个文件的字节数
+    pub bytes_per_file: u64,
+    /// 每 MiB 数据对应的文件数
+    pub files_per_mib: u64,
+}
+
+impl DirDensity {
+    /// 由目录的直接文件数与总大小计算密度
+    pub fn new(path: PathBuf, file_count: u64, size_bytes: u64) -> Self {
+        Self {
+            path,
+            file_count,
+            size_bytes,
+            bytes_per_file: size_bytes / file_count.max(1),
+            files_per_mib: ((file_count as u128) << 20).div_ceil(size_bytes.max(1) as u128) as u64,
+        }
+    }
+}
+
+/// 两类目录密度排名
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DensityRanking {
+    /// 每文件字节数最大的目录（少量巨型文件），降序
+    pub heavy: Vec<DirDensity>,
+    /// 每 MiB 文件数最大的目录（海量小文件），降序
+    pub crowded: Vec<DirDensity>,
+}
+
+impl DensityRanking {
+    /// 合并多个排名（各结果中的目录互不重叠），保留前 `limit` 项
+    pub(crate) fn merge(rankings: impl IntoIterator<Item = DensityRanking>, limit: usize) -> Self {
+        let mut merged = Self::default();
+        for ranking in rankings {
+            merged.heavy.extend(ranking.heavy);
+            merged.crowded.extend(ranking.crowded);
+        }
+        sort_and_truncate(&mut merged.heavy, |d| d.bytes_per_file, limit);
+        sort_and_truncate(&mut merged.crowded, |d| d.files_per_mib, limit);
+        merged
+    }
+}
+
+fn sort_and_truncate(rows: &mut Vec<DirDensity>, key: impl Fn(&DirDensity) -> u64, limit: usize) {
+    rows.sort_by(|a, b| key(b).cmp(&key(a)).then_with(|| a.path.cmp(&b.path)));
+    rows.truncate(limit);
+}
+
+type Heap = Mutex<BinaryHeap<Reverse<(u64, Reverse<PathBuf>, u64, u64)>>>;
+
+/// 按直接文件总大小降序、路径升序排列并截断；同一目录的多行（分片扫描时）先合并
+pub(crate) fn merge_shallow(rows: impl IntoIterator<Item = DirDensity>, limit: usize) -> Vec<DirDensity> {
+    let mut totals: HashMap<PathBuf, (u64, u64)> = HashMap::new();
+    for row in rows {
+        let total = totals.entry(row.path).or_default();
+        total.0 += row.file_count;
+        total.1 += row.size_bytes;
+    }
+    let mut rows = totals.into_iter().map(|(path, (files, bytes))| DirDensity::new(path, files, bytes)).collect();
+    sort_and_truncate(&mut rows, |d| d.size_bytes, limit);
+    rows
+}
+
+/// 遍历期间维护三个有界排名堆
+pub(crate) struct DensityTracker {
+    limit: usize,
+    /// 按直接文件总大小排名的条目数（与 `top_dirs` 相同）
+    pub(crate) shallow_limit: usize,
+    heavy: Heap,
+    crowded: Heap,
+    shallow: Heap,
+}
+
+impl DensityTracker {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            shallow_limit: limit,
+            heavy: Mutex::new(BinaryHeap::new()),
+            crowded: Mutex::new(BinaryHeap::new()),
+            shallow: Mutex::new(BinaryHeap::new()),
+        }
+    }
+
+    /// 记录一个目录的直接文件统计
+    pub(crate) fn record(&self, path: &Path, file_count: u64, size_bytes: u64) {
+        if file_count == 0 {
+            return;
+        }
+        let density = DirDensity::new(path.to_path_buf(), file_count, size_bytes);
+        if size_bytes > 0 {
+            self.push(&self.heavy, self.limit, density.bytes_per_file, &density);
+            self.push(&self.shallow, self.shallow_limit, size_bytes, &density);
+        }
+        if file_count >= CROWDED_MIN_FILES {
+            self.push(&self.crowded, self.limit, density.files_per_mib, &density);
+        }
+    }
+
+    fn push(&self, heap: &Heap, limit: usize, key: u64, density: &DirDensity) {
+        if limit == 0 {
+            return;
+        }
+        let mut heap = lock(heap);
+        if heap.len() >= limit {
+            match heap.peek() {
+                Some(Reverse(min)) if min.0 >= key => return,
+                _ => {
+                    heap.pop();
+                }
+            }
+        }
+        heap.push(Reverse((key, Reverse(density.path.clone()), density.file_count, density.size_bytes)));
+    }
+
+    pub(crate) fn to_ranking(&self) -> DensityRanking {
+        DensityRanking {
+            heavy: collect(&self.heavy, |d| d.bytes_per_file, self.limit),
+            crowded: collect(&self.crowded, |d| d.files_per_mib, self.limit),
+        }
+    }
+
+    /// 按直接文件总大小降序的目录（含根目录）
+    pub(crate) fn shallow_to_vec(&self) -> Vec<DirDensity> {
+        collect(&self.shallow, |d| d.size_bytes, self.shallow_limit)
+    }
+}
+
+fn collect(heap: &Heap, key: fn(&DirDensity) -> u64, limit: usize) -> Vec<DirDensity> {
+    let mut rows: Vec<DirDensity> = lock(heap)
+        .iter()
+        .map(|Reverse((_, Reverse(path), files, bytes))| DirDensity::new(path.clone(), *files, *bytes))
+        .collect();
+    sort_and_truncate(&mut rows, key, limit);
+    rows
+}
+
+/// 被拆分为多个批次并行处理的目录：遍历任务自身（直接处理的文件）占一份，每派发一个批次再加一份，
+/// 最后一个完成的一方负责记录该目录的密度
+pub(crate) struct SplitDir {
+    path: PathBuf,
+    remaining: AtomicUsize,
+    files: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl SplitDir {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            remaining: AtomicUsize::new(1),
+            files: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+        }
+    }
+
+    /// 登记一个即将派发的批次（须在派发前调用）
+    pub(crate) fn add_batch(&self) {
+        self.remaining.fetch_add(1, Ordering::AcqRel);
+    }
+
+    pub(crate) fn finish_batch(&self, tracker: &DensityTracker, files: u64, bytes: u64) {
+        self.files.fetch_add(files, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        if self.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+            tracker.record(&self.path, self.files.load(Ordering::Relaxed), self.bytes.load(Ordering::Relaxed));
+        }
+    }
+}
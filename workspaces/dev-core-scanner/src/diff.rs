@@ -0,0 +1,185 @@
+//! 扫描快照对比
+//!
+//! [`diff`] 对比同一目录树先后两次的扫描结果，给出总量变化以及新增、删除、增长与缩小的目录和文件，
+//! 用于回答“上周以来是什么占满了磁盘”。条目按相对根目录的路径匹配，因此两次扫描的根目录可以不同
+//! （例如挂载点变化的镜像或拷贝到别处的目录树）。
+//!
+//! 结果只保留了部分条目，对比范围也以此为限：
+//! - 目录取自 `top_level`、`top_dirs` 与 `directories` 的并集，两侧都出现的目录给出大小变化；
+//!   顶层目录的列表是完整的，只出现在一侧时即为新增或删除，更深的目录只出现在一侧时无法判断，不予报告；
+//! - 文件取自 `top_files`，只出现在一侧时视为新增或删除。另一侧的列表被 Top N 截断或受 `min_size`
+//!   限制时，该文件也可能只是增长或缩小越过了列表下限，此时条目的 `approximate` 为 true，
+//!   `delta_bytes` 是变化量的上界。
+//!
+//! 两次扫描的 `limit` 与 `min_size` 相同时对比最准确。
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ScanResult;
+
+/// 条目的变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// 只出现在新结果中
+    Added,
+    /// 只出现在旧结果中
+    Removed,
+    /// 变大
+    Grown,
+    /// 变小
+    Shrunk,
+}
+
+impl ChangeKind {
+    /// 中文名称
+    pub fn label(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "新增",
+            ChangeKind::Removed => "删除",
+            ChangeKind::Grown => "增长",
+            ChangeKind::Shrunk => "缩小",
+        }
+    }
+}
+
+/// 一个目录或文件的变化
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct EntryChange {
+    /// 相对根目录的路径
+    pub path: PathBuf,
+    /// 变化类型
+    pub kind: ChangeKind,
+    /// 旧结果中的大小（新增时为 None）
+    pub old_size_bytes: Option<u64>,
+    /// 新结果中的大小（删除时为 None）
+    pub new_size_bytes: Option<u64>,
+    /// 大小变化（字节，缩小与删除为负）
+    pub delta_bytes: i64,
+    /// 另一侧的列表不完整，新增或删除也可能只是大小越过了列表下限，`delta_bytes` 为上界
+    #[serde(default)]
+    pub approximate: bool,
+}
+
+/// 两次扫描结果的差异
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ScanDiff {
+    /// 旧结果的根目录
+    pub old_root: PathBuf,
+    /// 新结果的根目录
+    pub new_root: PathBuf,
+    /// 总大小变化（字节）
+    pub total_size_delta: i64,
+    /// 文件数变化
+    pub total_files_delta: i64,
+    /// 目录数变化
+    pub total_dirs_delta: i64,
+    /// 目录的变化（按变化量绝对值降序）
+    pub directories: Vec<EntryChange>,
+    /// 文件的变化（按变化量绝对值降序）
+    pub files: Vec<EntryChange>,
+}
+
+/// 对比旧结果 `old` 与新结果 `new`
+pub fn diff(old: &ScanResult, new: &ScanResult) -> ScanDiff {
+    let (old_dirs, new_dirs) = (dir_sizes(old), dir_sizes(new));
+    let mut directories = Vec::new();
+    for path in old_dirs.keys().chain(new_dirs.keys()).collect::<BTreeSet<_>>() {
+        let (before, after) = (old_dirs.get(path).copied(), new_dirs.get(path).copied());
+        // 更深的目录只出现在一侧时，另一侧可能只是没有列出
+        if (before.is_none() || after.is_none()) && path.components().count() != 1 {
+            continue;
+        }
+        directories.extend(change(path, before, after, false));
+    }
+
+    let (old_files, new_files) = (file_sizes(old), file_sizes(new));
+    let (old_floor, new_floor) = (list_floor(old), list_floor(new));
+    let mut files = Vec::new();
+    for path in old_files.keys().chain(new_files.keys()).collect::<BTreeSet<_>>() {
+        let (before, after) = (old_files.get(path).copied(), new_files.get(path).copied());
+        let approximate = match (before, after) {
+            (Some(_), None) => new_floor > 0,
+            (None, Some(_)) => old_floor > 0,
+            _ => false,
+        };
+        files.extend(change(path, before, after, approximate));
+    }
+
+    sort(&mut directories);
+    sort(&mut files);
+    ScanDiff {
+        old_root: old.summary.root_path.clone(),
+        new_root: new.summary.root_path.clone(),
+        total_size_delta: delta(old.summary.total_size_bytes, new.summary.total_size_bytes),
+        total_files_delta: delta(old.summary.total_files, new.summary.total_files),
+        total_dirs_delta: delta(old.summary.total_dirs, new.summary.total_dirs),
+        directories,
+        files,
+    }
+}
+
+fn delta(before: u64, after: u64) -> i64 {
+    after as i64 - before as i64
+}
+
+/// 由两侧大小生成变化条目；大小不变时返回 None
+fn change(path: &Path, before: Option<u64>, after: Option<u64>, approximate: bool) -> Option<EntryChange> {
+    let delta_bytes = delta(before.unwrap_or(0), after.unwrap_or(0));
+    let kind = match (before, after) {
+        (None, Some(_)) => ChangeKind::Added,
+        (Some(_), None) => ChangeKind::Removed,
+        _ if delta_bytes > 0 => ChangeKind::Grown,
+        _ if delta_bytes < 0 => ChangeKind::Shrunk,
+        _ => return None,
+    };
+    Some(EntryChange {
+        path: path.to_path_buf(),
+        kind,
+        old_size_bytes: before,
+        new_size_bytes: after,
+        delta_bytes,
+        approximate,
+    })
+}
+
+/// 按变化量绝对值降序、路径升序排列
+fn sort(changes: &mut [EntryChange]) {
+    changes.sort_by(|a, b| b.delta_bytes.unsigned_abs().cmp(&a.delta_bytes.unsigned_abs()).then_with(|| a.path.cmp(&b.path)));
+}
+
+/// 相对根目录的路径；不在根目录之下时保留原路径
+fn relative(result: &ScanResult, path: &Path) -> PathBuf {
+    path.strip_prefix(&result.summary.root_path).unwrap_or(path).to_path_buf()
+}
+
+/// 结果中列出的目录（不含根目录）及其递归大小
+fn dir_sizes(result: &ScanResult) -> BTreeMap<PathBuf, u64> {
+    let top_level = result.top_level.iter().filter(|row| !row.root_files).map(|row| (&row.path, row.size_bytes));
+    let dirs = result.top_dirs.iter().chain(&result.directories).map(|dir| (&dir.path, dir.size_bytes));
+    top_level
+        .chain(dirs)
+        .map(|(path, size)| (relative(result, path), size))
+        .filter(|(path, _)| !path.as_os_str().is_empty())
+        .collect()
+}
+
+fn file_sizes(result: &ScanResult) -> BTreeMap<PathBuf, u64> {
+    result.top_files.iter().map(|file| (relative(result, &file.path), file.size_bytes)).collect()
+}
+
+/// 文件列表能反映的最小大小：列表被截断时为其中最小的文件，否则为 `min_size`（完整时为 0）
+fn list_floor(result: &ScanResult) -> u64 {
+    let min_size = result.summary.min_size.unwrap_or(0);
+    let truncated = result.summary.summary_only || result.top_files.len() >= result.summary.top_files_bound();
+    match result.top_files.iter().map(|f| f.size_bytes).min() {
+        Some(smallest) if truncated => smallest.max(min_size),
+        None if truncated => u64::MAX,
+        _ => min_size,
+    }
+}
@@ -0,0 +1,89 @@
+//! 根目录所在文件系统的容量与剩余空间
+//!
+//! 扫描结束时查询一次根目录所在文件系统的容量，写入 `summary.disk_space`，调用方无需平台相关代码
+//! 即可展示“该目录占磁盘的 62%”或剩余空间：
+//!
+//! - Unix：`statvfs`（`f_blocks` / `f_bfree` / `f_bavail` 乘以 `f_frsize`）；
+//! - Windows：`GetDiskFreeSpaceExW`（可用空间按调用者的磁盘配额计算）。
+//!
+//! 查询失败（如网络卷断开）时不带该字段。
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// 文件系统的容量与剩余空间（字节）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DiskSpace {
+    /// 文件系统总容量
+    pub total_bytes: u64,
+    /// 空闲空间（含仅供 root 使用的保留块）
+    pub free_bytes: u64,
+    /// 当前用户可用的空间（不含保留块与超出配额的部分）
+    pub available_bytes: u64,
+}
+
+impl DiskSpace {
+    /// 已用空间（总容量减去空闲空间）
+    pub fn used_bytes(&self) -> u64 {
+        self.total_bytes.saturating_sub(self.free_bytes)
+    }
+
+    /// `bytes` 占文件系统总容量的比例（0~1）；容量未知时为 0
+    pub fn share_of(&self, bytes: u64) -> f64 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+        bytes as f64 / self.total_bytes as f64
+    }
+}
+
+/// 查询路径所在文件系统的容量与剩余空间；平台不支持或查询失败时为 None
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // statvfs 字段类型因平台而异（macOS 上为 u32）
+pub fn query(path: &Path) -> Option<DiskSpace> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path 为合法的 NUL 结尾字符串，stat 为可写的 statvfs 结构体
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let block = stat.f_frsize as u64;
+    Some(DiskSpace {
+        total_bytes: (stat.f_blocks as u64).saturating_mul(block),
+        free_bytes: (stat.f_bfree as u64).saturating_mul(block),
+        available_bytes: (stat.f_bavail as u64).saturating_mul(block),
+    })
+}
+
+#[cfg(windows)]
+pub fn query(path: &Path) -> Option<DiskSpace> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory_name: *const u16,
+            free_bytes_available_to_caller: *mut u64,
+            total_number_of_bytes: *mut u64,
+            total_number_of_free_bytes: *mut u64,
+        ) -> i32;
+    }
+
+    let wide: Vec<u16> = crate::long_path::extended(path).as_os_str().encode_wide().chain(Some(0)).collect();
+    let (mut available, mut total, mut free) = (0u64, 0u64, 0u64);
+    // SAFETY: `wide` 以 NUL 结尾且在调用期间有效，三个输出指针均指向有效的 u64
+    if unsafe { GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, &mut total, &mut free) } == 0 {
+        return None;
+    }
+    Some(DiskSpace { total_bytes: total, free_bytes: free, available_bytes: available })
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn query(_path: &Path) -> Option<DiskSpace> {
+    None
+}
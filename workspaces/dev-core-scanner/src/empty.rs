@@ -0,0 +1,92 @@
+//! 空文件与空目录
+//!
+//! 清理时常需要删除零字节文件与只剩空目录骨架的目录树，而零字节文件不会出现在 Top N 中（通常还被
+//! `min_size` 过滤掉）。扫描时把符合包含/排除规则的零字节普通文件单独计数与记录，不受 `min_size`
+//! 影响。目录在遍历时标记是否含有目录以外的条目（含被过滤、忽略的文件以及未能进入的子目录），
+//! 标记沿目录链向上传播；遍历结束后未被标记而父目录已被标记的目录即一棵空目录树的顶点，删除它即可
+//! 移除整棵骨架。根目录本身不计入。
+//!
+//! 两个列表按路径排序，`empty_limit` 限制各自保留的条目数，总数见 `summary.empty_file_count` 与
+//! `summary.empty_dir_count`。仅汇总扫描只计数，不保留列表。
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::lock;
+use crate::share::DirNode;
+
+/// 一棵空目录树（只含空目录的目录树）的顶点
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct EmptyDir {
+    /// 目录路径
+    pub path: PathBuf,
+    /// 树中的目录数（含自身）
+    pub dir_count: u64,
+}
+
+/// 全部目录任务共享的空文件与空目录收集
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    /// 每个列表最多保留的条目数
+    pub(crate) limit: Option<usize>,
+    /// 是否保留列表（仅汇总扫描只计数）
+    pub(crate) keep_entries: bool,
+    file_count: AtomicU64,
+    files: Mutex<Vec<PathBuf>>,
+    /// 没有目录以外条目的目录，遍历结束后再判断整棵子树是否为空
+    dir_candidates: Mutex<Vec<Arc<DirNode>>>,
+}
+
+impl Counters {
+    pub(crate) fn add_file(&self, path: PathBuf) {
+        self.file_count.fetch_add(1, Ordering::Relaxed);
+        if !self.keep_entries {
+            return;
+        }
+        let mut files = lock(&self.files);
+        files.push(path);
+        // 超出两倍上限时按路径截断，避免大量空文件占用内存
+        if let Some(limit) = self.limit.filter(|&limit| files.len() >= limit.max(1) * 2) {
+            files.sort();
+            files.truncate(limit);
+        }
+    }
+
+    pub(crate) fn add_dir_candidate(&self, node: &Arc<DirNode>) {
+        lock(&self.dir_candidates).push(node.clone());
+    }
+
+    /// 空文件总数与按路径排序、截断后的列表
+    pub(crate) fn files_to_vec(&self) -> (u64, Vec<PathBuf>) {
+        let mut files = std::mem::take(&mut *lock(&self.files));
+        sort_and_truncate(&mut files, self.limit);
+        (self.file_count.load(Ordering::Relaxed), files)
+    }
+
+    /// 空目录树总数与按路径排序、截断后的顶点列表
+    pub(crate) fn dirs_to_vec(&self) -> (u64, Vec<EmptyDir>) {
+        let mut dirs: Vec<EmptyDir> = lock(&self.dir_candidates)
+            .iter()
+            .filter(|node| !node.is_occupied() && node.parent().is_some_and(DirNode::is_occupied))
+            .map(|node| EmptyDir { path: node.path().to_path_buf(), dir_count: node.dirs() + 1 })
+            .collect();
+        let count = dirs.len() as u64;
+        if !self.keep_entries {
+            dirs.clear();
+        }
+        sort_and_truncate(&mut dirs, self.limit);
+        (count, dirs)
+    }
+}
+
+/// 按路径排序并按上限截断（合并结果时同样使用）
+pub(crate) fn sort_and_truncate<T: Ord>(rows: &mut Vec<T>, limit: Option<usize>) {
+    rows.sort();
+    if let Some(limit) = limit {
+        rows.truncate(limit);
+    }
+}
@@ -0,0 +1,96 @@
+//! 扫描失败的原因
+//!
+//! [`Scanner::scan_sync`](crate::Scanner::scan_sync) 等扫描入口以 [`ScanError`] 报告整个扫描为何失败，
+//! 调用方可按变体区分处理（如服务层映射为不同的 JSON-RPC 错误码），无需匹配错误信息。
+//! 遍历中单个目录或文件的读取错误不会使扫描失败，见 [`scan_error`](crate::scan_error)。
+//!
+//! 仍以 `io::Result` 为返回类型的调用方可直接用 `?` 转换：[`ScanError`] 实现了到 `io::Error` 的转换，
+//! 错误类别与 [`ScanError::kind`] 一致。
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 扫描失败的原因
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ScanError {
+    /// 根目录不存在
+    RootNotFound { path: PathBuf },
+    /// 根路径不是目录
+    RootNotDirectory { path: PathBuf },
+    /// 权限不足（`fail_fast` 时遍历遇到无法读取的路径）
+    PermissionDenied { path: PathBuf },
+    /// 请求参数非法（如过滤规则无法编译或超出上限）
+    InvalidRequest { message: String },
+    /// 线程池创建失败或扫描线程异常退出
+    ThreadPool { message: String },
+    /// 扫描在得到结果前被中止
+    Canceled,
+    /// 其他 IO 错误
+    Io { path: PathBuf, source: io::Error },
+}
+
+impl ScanError {
+    /// 由读取 `path` 时的 IO 错误构造：权限不足归为 [`PermissionDenied`](Self::PermissionDenied)
+    pub fn io(path: &Path, source: io::Error) -> Self {
+        match source.kind() {
+            io::ErrorKind::PermissionDenied => ScanError::PermissionDenied { path: path.to_path_buf() },
+            _ => ScanError::Io { path: path.to_path_buf(), source },
+        }
+    }
+
+    /// 对应的 IO 错误类别
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            ScanError::RootNotFound { .. } => io::ErrorKind::NotFound,
+            ScanError::RootNotDirectory { .. } => io::ErrorKind::NotADirectory,
+            ScanError::PermissionDenied { .. } => io::ErrorKind::PermissionDenied,
+            ScanError::InvalidRequest { .. } => io::ErrorKind::InvalidInput,
+            ScanError::ThreadPool { .. } => io::ErrorKind::Other,
+            ScanError::Canceled => io::ErrorKind::Interrupted,
+            ScanError::Io { source, .. } => source.kind(),
+        }
+    }
+
+    /// 出错的路径（与路径无关的错误为 None）
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ScanError::RootNotFound { path }
+            | ScanError::RootNotDirectory { path }
+            | ScanError::PermissionDenied { path }
+            | ScanError::Io { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::RootNotFound { path } => write!(f, "根目录不存在: {}", path.display()),
+            ScanError::RootNotDirectory { path } => write!(f, "根路径不是目录: {}", path.display()),
+            ScanError::PermissionDenied { path } => write!(f, "权限不足: {}", path.display()),
+            ScanError::InvalidRequest { message } => write!(f, "扫描参数无效: {}", message),
+            ScanError::ThreadPool { message } => write!(f, "扫描线程池错误: {}", message),
+            ScanError::Canceled => write!(f, "扫描已取消"),
+            ScanError::Io { path, source } => write!(f, "读取 {} 失败: {}", path.display(), source),
+        }
+    }
+}
+
+impl Error for ScanError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ScanError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<ScanError> for io::Error {
+    fn from(error: ScanError) -> Self {
+        io::Error::new(error.kind(), error)
+    }
+}
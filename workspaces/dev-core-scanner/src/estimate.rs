@@ -0,0 +1,44 @@
+//! 遍历总量估算
+//!
+//! 遍历器边枚举边统计，开始前并不知道要读取多少字节，遍历阶段的进度百分比与剩余时间因此无从计算。
+//! 扫描开始时按以下顺序取一个总字节数估算值，作为遍历阶段进度与 ETA 的分母：
+//!
+//! 1. 并发提示（[`ConcurrencyHints`]）中记录的上次扫描同一根目录计入统计的字节数；
+//! 2. 根目录是文件系统的挂载点（或 `/`）时，该文件系统的已用空间（`statvfs`）。
+//!
+//! 两者都没有时（如首次扫描普通子目录）不估算，进度与 ETA 保持未知。估算值只用于显示：
+//! 过滤规则、`min_size` 或目录内容的变化都会使实际总量偏离估算，因此遍历阶段的进度最多报告到
+//! [`MAX_ESTIMATED_PROGRESS`]，遍历真正结束后才进入下一阶段。
+//!
+//! [`ConcurrencyHints`]: crate::ConcurrencyHints
+
+use std::path::Path;
+
+use crate::{min_size, mount, ScanRequest};
+
+/// 按估算总量计算的遍历阶段进度上限
+pub const MAX_ESTIMATED_PROGRESS: f64 = 0.99;
+
+/// 本次扫描遍历总字节数的估算值；无从估算时返回 None
+pub fn total_bytes(request: &ScanRequest) -> Option<u64> {
+    let previous = request.concurrency_hints.as_deref().map(|hints| hints.scanned_bytes);
+    if let Some(bytes) = previous.filter(|&bytes| bytes > 0) {
+        return Some(bytes);
+    }
+    if !is_mount_point(&request.root_path) {
+        return None;
+    }
+    min_size::filesystem_used(&request.root_path).filter(|&bytes| bytes > 0)
+}
+
+/// 路径是否为文件系统的挂载点：与父目录位于不同设备上，或没有父目录
+fn is_mount_point(path: &Path) -> bool {
+    let canonical = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    match canonical.parent() {
+        None => true,
+        Some(parent) => {
+            let device = mount::device_of(&canonical);
+            device.is_some() && device != mount::device_of(parent)
+        }
+    }
+}
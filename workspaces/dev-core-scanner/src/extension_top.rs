@@ -0,0 +1,55 @@
+//! 按扩展名的 Top N 大文件
+//!
+//! 看到“mp4：80 GB”时，下一步通常是想知道是哪些视频占了空间。请求 `extension_top_limit` 时，
+//! 扫描为每个扩展名分别保留最大的若干个文件，嵌入 `by_extension` 对应行的 `top_files` 中，
+//! 无需再按扩展名过滤重新扫描。每个扩展名的列表各自有界，内存占用随扩展名数量线性增长。
+//! 仅汇总扫描不保留列表。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::redundant::sort_and_truncate;
+use crate::{lock, ExtensionStat, FileEntry};
+
+/// 全部遍历任务共享的按扩展名 Top N 收集
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    /// 每个扩展名最多保留的条目数；`None` 表示不收集
+    pub(crate) limit: Option<usize>,
+    files: Mutex<HashMap<String, Vec<FileEntry>>>,
+}
+
+impl Counters {
+    /// 记录一个文件（扩展名与 `by_extension` 的统计口径相同）
+    pub(crate) fn add(&self, extension: &str, entry: &FileEntry) {
+        let Some(limit) = self.limit.filter(|&limit| limit > 0) else {
+            return;
+        };
+        let mut map = lock(&self.files);
+        let files = match map.get_mut(extension) {
+            Some(files) => files,
+            None => map.entry(extension.to_string()).or_default(),
+        };
+        // 列表已满且不大于其中最小者的文件不会入选，跳过克隆
+        if files.len() >= limit && files.iter().all(|f| f.size_bytes > entry.size_bytes) {
+            return;
+        }
+        files.push(entry.clone());
+        // 超出两倍上限时截断，避免大量文件占用内存
+        if files.len() >= limit * 2 {
+            sort_and_truncate(files, limit);
+        }
+    }
+
+    /// 把各扩展名的列表填入对应的统计行
+    pub(crate) fn attach(&self, stats: &mut [ExtensionStat]) {
+        let limit = self.limit.unwrap_or(0);
+        let mut map = std::mem::take(&mut *lock(&self.files));
+        for stat in stats {
+            if let Some(mut files) = map.remove(&stat.extension) {
+                sort_and_truncate(&mut files, limit);
+                stat.top_files = files;
+            }
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! 路径过滤规则
+//!
+//! 排除与包含规则各有两种写法：glob 模式（`exclude_patterns` / `include_patterns`）与正则表达式
+//! （`exclude_regex` / `include_regex`），后者用于 glob 无法表达的规则，如按日期命名的目录。
+//! 两种写法的规则合并生效：匹配任一排除规则即排除；存在包含规则时，文件需匹配其中任一条。
+//! 判定顺序为先包含后排除，包含规则只作用于文件，排除规则对目录生效时整棵子树被跳过。
+//!
+//! 规则匹配相对于扫描根目录的路径（如 `sub/a.txt`），与根目录位于何处无关：`sub/**` 只排除根目录下的
+//! `sub`，`**/sub/**` 排除任意层级的 `sub`。需要按绝对路径匹配时在规则前加 `anchored:` 前缀，
+//! 如 `anchored:/home/*/.cache/**` 或正则 `anchored:^/var/log/`。glob 与正则两种写法遵循相同的约定。
+//! 提权补扫等以子目录为根的扫描通过 `filter_root` 沿用原扫描的根目录，保证规则含义一致。
+//!
+//! 规则在扫描开始时编译一次，所有遍历任务共享同一个只读的 [`PathFilter`]：同一类 glob 规则合并为
+//! 一个 `GlobSet`、正则合并为一个 `RegexSet`，每个路径只需各匹配一次。正则表达式匹配
+//! `to_string_lossy` 后的路径字符串（Windows 上分隔符为 `\`），不自动锚定。
+//!
+//! 为避免成千上万条规则或病态规则拖慢整次扫描，规则总数、单条长度与 `{a,b}` 展开数量都有上限，
+//! 超出时返回 `InvalidInput` 错误。以 `/**` 结尾的排除规则同时作用于目录本身，目录匹配后整棵子树
+//! 不再逐项匹配。每次匹配的耗时累计在诊断信息中（`filter_checks` / `filter_match_nanos`）。
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use regex::{RegexSet, RegexSetBuilder};
+
+use crate::check::RequestIssue;
+use crate::ScanRequest;
+
+/// 按绝对路径匹配的规则前缀
+pub const ANCHORED_PREFIX: &str = "anchored:";
+
+/// 排除与包含规则（glob 与正则合计）的数量上限
+pub(crate) const MAX_FILTER_PATTERNS: usize = 4096;
+
+/// 单条规则的长度上限（字节）
+pub(crate) const MAX_PATTERN_LEN: usize = 1024;
+
+/// 单条 glob 规则展开 `{a,b}` 后的模式数上限
+const MAX_BRACE_EXPANSIONS: usize = 256;
+
+/// 正则集合编译后的大小上限（字节）
+const REGEX_SIZE_LIMIT: usize = 32 * 1024 * 1024;
+
+/// 一组编译后的规则：相对规则匹配相对于根目录的路径，`anchored:` 规则匹配完整路径
+#[derive(Debug)]
+pub(crate) struct RuleSet {
+    relative: GlobSet,
+    anchored: GlobSet,
+    relative_regex: Option<RegexSet>,
+    anchored_regex: Option<RegexSet>,
+}
+
+impl RuleSet {
+    pub(crate) fn new(globs: &[String], regex: &[String]) -> io::Result<Self> {
+        let (relative, anchored) = split_anchored(globs);
+        let (relative_regex, anchored_regex) = split_anchored(regex);
+        Ok(Self {
+            relative: compile_patterns(&relative)?,
+            anchored: compile_patterns(&anchored)?,
+            relative_regex: compile_regex(&relative_regex)?,
+            anchored_regex: compile_regex(&anchored_regex)?,
+        })
+    }
+
+    pub(crate) fn matches(&self, relative: &Path, full: &Path) -> bool {
+        self.relative.is_match(relative)
+            || self.anchored.is_match(full)
+            || matches_regex(relative, self.relative_regex.as_ref())
+            || matches_regex(full, self.anchored_regex.as_ref())
+    }
+}
+
+/// 编译后的排除与包含规则
+#[derive(Debug)]
+pub(crate) struct PathFilter {
+    /// 相对规则的基准目录
+    root: PathBuf,
+    exclude: RuleSet,
+    /// 只用于目录的排除规则：`dir/**` 去掉结尾的 `/**` 后匹配目录本身
+    exclude_dirs: RuleSet,
+    /// 包含规则；`None` 表示不按包含规则过滤
+    include: Option<RuleSet>,
+    /// 是否存在任何规则（没有规则时不计时）
+    active: bool,
+    checks: AtomicU64,
+    nanos: AtomicU64,
+}
+
+impl PathFilter {
+    /// 编译请求中的规则：非法 glob 模式被忽略（包含规则全部非法时不匹配任何文件，而不是退化为不过滤），
+    /// 非法正则表达式、规则过多或过于复杂时返回 `InvalidInput` 错误
+    pub(crate) fn new(request: &ScanRequest) -> io::Result<Self> {
+        validate(request)?;
+        let has_include = !request.include_patterns.is_empty() || !request.include_regex.is_empty();
+        let dir_prefixes: Vec<String> = request
+            .exclude_patterns
+            .iter()
+            .filter_map(|p| p.strip_suffix("/**"))
+            .filter(|p| !p.is_empty() && *p != ANCHORED_PREFIX)
+            .map(str::to_string)
+            .collect();
+        Ok(Self {
+            root: request.filter_root.clone().unwrap_or_else(|| request.root_path.clone()),
+            exclude: RuleSet::new(&request.exclude_patterns, &request.exclude_regex)?,
+            exclude_dirs: RuleSet::new(&dir_prefixes, &[])?,
+            include: if has_include {
+                Some(RuleSet::new(&request.include_patterns, &request.include_regex)?)
+            } else {
+                None
+            },
+            active: has_include || !request.exclude_patterns.is_empty() || !request.exclude_regex.is_empty(),
+            checks: AtomicU64::new(0),
+            nanos: AtomicU64::new(0),
+        })
+    }
+
+    /// 目录是否匹配排除规则（匹配时整棵子树跳过）
+    pub(crate) fn excludes_dir(&self, path: &Path) -> bool {
+        self.timed(|| {
+            let relative = self.relative(path);
+            self.exclude_dirs.matches(relative, path) || self.exclude.matches(relative, path)
+        })
+    }
+
+    /// 文件是否被过滤掉：未匹配包含规则，或匹配排除规则
+    pub(crate) fn excludes_file(&self, path: &Path) -> bool {
+        self.timed(|| {
+            let relative = self.relative(path);
+            let included = self.include.as_ref().is_none_or(|rules| rules.matches(relative, path));
+            !included || self.exclude.matches(relative, path)
+        })
+    }
+
+    /// 已进行的匹配次数与累计耗时（纳秒）
+    pub(crate) fn cost(&self) -> (u64, u64) {
+        (self.checks.load(Ordering::Relaxed), self.nanos.load(Ordering::Relaxed))
+    }
+
+    /// 相对于基准目录的路径；不在基准目录之下时按原路径匹配
+    fn relative<'p>(&self, path: &'p Path) -> &'p Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    fn timed(&self, check: impl FnOnce() -> bool) -> bool {
+        if !self.active {
+            return false;
+        }
+        let start = Instant::now();
+        let matched = check();
+        self.checks.fetch_add(1, Ordering::Relaxed);
+        self.nanos.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        matched
+    }
+}
+
+/// 按 `anchored:` 前缀拆分为（相对规则，绝对规则），绝对规则去掉前缀
+fn split_anchored(rules: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut relative = Vec::new();
+    let mut anchored = Vec::new();
+    for rule in rules {
+        match rule.strip_prefix(ANCHORED_PREFIX) {
+            Some(rule) => anchored.push(rule.to_string()),
+            None => relative.push(rule.clone()),
+        }
+    }
+    (relative, anchored)
+}
+
+/// 检查规则数量与单条长度
+fn validate(request: &ScanRequest) -> io::Result<()> {
+    let all = [
+        &request.exclude_patterns,
+        &request.include_patterns,
+        &request.exclude_regex,
+        &request.include_regex,
+    ];
+    let count: usize = all.iter().map(|rules| rules.len()).sum();
+    if count > MAX_FILTER_PATTERNS {
+        return Err(invalid(format!("过滤规则过多: {} 条（上限 {}）", count, MAX_FILTER_PATTERNS)));
+    }
+    if let Some(rule) = all.iter().flat_map(|rules| rules.iter()).find(|r| r.len() > MAX_PATTERN_LEN) {
+        return Err(invalid(format!("过滤规则过长: {} 字节（上限 {}）", rule.len(), MAX_PATTERN_LEN)));
+    }
+    Ok(())
+}
+
+/// 逐条检查规则：与编译时不同，扫描时被静默忽略的非法 glob 模式也会列出
+pub(crate) fn check_rules(request: &ScanRequest) -> Vec<RequestIssue> {
+    let mut issues = Vec::new();
+    if let Err(e) = validate(request) {
+        issues.push(RequestIssue::fatal("filters", e.to_string()));
+    }
+    let fields = [
+        ("exclude_patterns", &request.exclude_patterns, false),
+        ("include_patterns", &request.include_patterns, false),
+        ("exclude_regex", &request.exclude_regex, true),
+        ("include_regex", &request.include_regex, true),
+    ];
+    for (field, rules, is_regex) in fields {
+        for (i, rule) in rules.iter().enumerate() {
+            let rule = rule.strip_prefix(ANCHORED_PREFIX).unwrap_or(rule);
+            let issue = if is_regex { check_regex(rule) } else { check_glob(rule) };
+            if let Some(mut issue) = issue {
+                issue.location = format!("{}[{}]", field, i);
+                issues.push(issue);
+            }
+        }
+    }
+    issues
+}
+
+fn check_glob(pattern: &str) -> Option<RequestIssue> {
+    let Some(expanded) = expand_braces_limited(pattern, MAX_BRACE_EXPANSIONS) else {
+        return Some(RequestIssue::fatal("", format!("展开后模式过多（上限 {}）", MAX_BRACE_EXPANSIONS)));
+    };
+    let error = expanded.iter().find_map(|p| GlobBuilder::new(p).build().err())?;
+    Some(RequestIssue::ignored("", format!("非法的 glob 模式，扫描时被忽略: {}", error)))
+}
+
+fn check_regex(pattern: &str) -> Option<RequestIssue> {
+    let error = regex::RegexBuilder::new(pattern).size_limit(REGEX_SIZE_LIMIT).build().err()?;
+    Some(RequestIssue::fatal("", format!("非法的正则表达式: {}", error)))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// 编译正则表达式集合；规则为空时返回 `None`
+fn compile_regex(patterns: &[String]) -> io::Result<Option<RegexSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    RegexSetBuilder::new(patterns)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map(Some)
+        .map_err(|e| invalid(format!("非法的正则表达式: {}", e)))
+}
+
+fn matches_regex(path: &Path, regex: Option<&RegexSet>) -> bool {
+    regex.is_some_and(|set| set.is_match(&path.to_string_lossy()))
+}
+
+/// 编译 glob 模式为一个 `GlobSet`：`{a,b}` 备选先展开（支持嵌套，`globset` 本身不支持嵌套），
+/// 展开过多时返回错误；非法模式被忽略。`*` 可跨越路径分隔符，与 `**` 一样匹配多级目录
+fn compile_patterns(patterns: &[String]) -> io::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let expanded = expand_braces_limited(pattern, MAX_BRACE_EXPANSIONS).ok_or_else(|| {
+            invalid(format!("过滤规则展开后模式过多（上限 {}）: {}", MAX_BRACE_EXPANSIONS, pattern))
+        })?;
+        for glob in expanded.iter().filter_map(|p| GlobBuilder::new(p).build().ok()) {
+            builder.add(glob);
+        }
+    }
+    builder.build().map_err(|e| invalid(format!("编译过滤规则失败: {}", e)))
+}
+
+/// 展开 `{a,b}` 备选（支持嵌套与多组），如 `**/*.{mp4,mkv}` → `**/*.mp4`、`**/*.mkv`
+#[cfg(test)]
+pub(crate) fn expand_braces(pattern: &str) -> Vec<String> {
+    expand_braces_limited(pattern, usize::MAX).unwrap_or_default()
+}
+
+/// 展开 `{a,b}` 备选，结果超过 `limit` 个时立即停止并返回 `None`（避免多组备选的指数级展开）
+fn expand_braces_limited(pattern: &str, limit: usize) -> Option<Vec<String>> {
+    let mut expanded = Vec::new();
+    let mut pending = vec![pattern.to_string()];
+    while let Some(pattern) = pending.pop() {
+        match first_alternatives(&pattern) {
+            Some(options) => pending.extend(options.into_iter().rev()),
+            None => {
+                expanded.push(pattern);
+                if expanded.len() > limit {
+                    return None;
+                }
+            }
+        }
+    }
+    Some(expanded)
+}
+
+/// 展开第一组 `{a,b}`（其余各组保持原样）；没有或括号未闭合时返回 `None`，按字面处理
+fn first_alternatives(pattern: &str) -> Option<Vec<String>> {
+    let open = pattern.find('{')?;
+    // 找到与之配对的右括号，并按顶层逗号切分备选项
+    let mut depth = 0;
+    let mut start = open + 1;
+    let mut options = Vec::new();
+    for (i, ch) in pattern[open..].char_indices().map(|(i, c)| (i + open, c)) {
+        match ch {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    options.push(&pattern[start..i]);
+                    let (prefix, suffix) = (&pattern[..open], &pattern[i + 1..]);
+                    return Some(options.into_iter().map(|option| format!("{}{}{}", prefix, option, suffix)).collect());
+                }
+            }
+            ',' if depth == 1 => {
+                options.push(&pattern[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
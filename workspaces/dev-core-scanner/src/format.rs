@@ -0,0 +1,200 @@
+//! 面向人阅读的数值格式
+//!
+//! 字节数统一在这里格式化为 "1.40 GB" 这类文本，CLI/TUI 与 JSON-RPC 服务共用同一实现。
+//! 可选择单位制：二进制（1024 进位，沿用 KB/MB 写法，默认）、IEC（1024 进位，KiB/MiB）或
+//! SI（1000 进位，kB/MB），并可按地区习惯对整数部分分组、选择小数点符号（如德语 "1.234,50 kB"）。
+//!
+//! 服务端在调用方提供 `format` 选项时，通过 [`annotate_json`] 在原始数值旁补充 `<字段名>_human`
+//! 文本字段，原始字节数保持不变。
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 单位制
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    /// 1024 进位，单位写作 KB/MB/GB（CLI 一直以来的写法）
+    #[default]
+    Binary,
+    /// 1024 进位，单位写作 KiB/MiB/GiB
+    Iec,
+    /// 1000 进位，单位写作 kB/MB/GB
+    Si,
+}
+
+impl UnitSystem {
+    fn base(self) -> f64 {
+        match self {
+            UnitSystem::Binary | UnitSystem::Iec => 1024.0,
+            UnitSystem::Si => 1000.0,
+        }
+    }
+
+    fn units(self) -> &'static [&'static str] {
+        match self {
+            UnitSystem::Binary => &["B", "KB", "MB", "GB", "TB", "PB"],
+            UnitSystem::Iec => &["B", "KiB", "MiB", "GiB", "TiB", "PiB"],
+            UnitSystem::Si => &["B", "kB", "MB", "GB", "TB", "PB"],
+        }
+    }
+}
+
+impl FromStr for UnitSystem {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "binary" => Ok(UnitSystem::Binary),
+            "iec" => Ok(UnitSystem::Iec),
+            "si" | "decimal" => Ok(UnitSystem::Si),
+            _ => Err(format!("未知的单位制: {}（可选 binary、iec、si）", s)),
+        }
+    }
+}
+
+impl fmt::Display for UnitSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UnitSystem::Binary => "binary",
+            UnitSystem::Iec => "iec",
+            UnitSystem::Si => "si",
+        })
+    }
+}
+
+/// 数字的地区写法：千位分组符与小数点符号
+///
+/// 由 BCP 47 语言标签（如 `en-US`、`de`、`fr-CA`）按主语言子标签确定；序列化为原始标签。
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct NumberLocale {
+    tag: String,
+    group: char,
+    decimal: char,
+}
+
+impl NumberLocale {
+    /// 语言标签
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+}
+
+impl FromStr for NumberLocale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+        let (group, decimal) = match language.as_str() {
+            "en" | "zh" | "ja" | "ko" | "th" | "he" | "ms" => (',', '.'),
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" | "ro" => ('.', ','),
+            "fr" | "ru" | "pl" | "sv" | "nb" | "no" | "fi" | "cs" | "sk" | "uk" | "hu" | "bg" => ('\u{a0}', ','),
+            _ => return Err(format!("不支持的地区: {}", s)),
+        };
+        Ok(NumberLocale { tag: s.to_string(), group, decimal })
+    }
+}
+
+impl TryFrom<String> for NumberLocale {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<NumberLocale> for String {
+    fn from(locale: NumberLocale) -> Self {
+        locale.tag
+    }
+}
+
+/// 格式化选项
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct FormatOptions {
+    /// 单位制
+    pub units: UnitSystem,
+    /// 数字的地区写法；缺省时不分组、以 "." 作小数点
+    pub locale: Option<NumberLocale>,
+}
+
+impl FormatOptions {
+    pub fn new(units: UnitSystem, locale: Option<NumberLocale>) -> Self {
+        Self { units, locale }
+    }
+
+    /// 格式化字节数：不足一个进位时按整数字节显示，否则保留两位小数
+    pub fn bytes(&self, bytes: u64) -> String {
+        let units = self.units.units();
+        let base = self.units.base();
+        let mut size = bytes as f64;
+        let mut unit_idx = 0;
+        while size >= base && unit_idx < units.len() - 1 {
+            size /= base;
+            unit_idx += 1;
+        }
+        if unit_idx == 0 {
+            format!("{} {}", self.count(bytes), units[0])
+        } else {
+            let text = format!("{:.2}", size);
+            let (int, frac) = text.split_once('.').unwrap_or((&text, ""));
+            let decimal = self.locale.as_ref().map_or('.', |l| l.decimal);
+            format!("{}{}{} {}", self.group(int), decimal, frac, units[unit_idx])
+        }
+    }
+
+    /// 格式化计数（按地区分组）
+    pub fn count(&self, n: u64) -> String {
+        self.group(&n.to_string())
+    }
+
+    fn group(&self, digits: &str) -> String {
+        let Some(locale) = &self.locale else {
+            return digits.to_string();
+        };
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push(locale.group);
+            }
+            out.push(c);
+        }
+        out
+    }
+}
+
+/// 按默认选项格式化字节数
+pub fn format_bytes(bytes: u64) -> String {
+    FormatOptions::default().bytes(bytes)
+}
+
+/// 在 JSON 中为数值字段补充 `<字段名>_human` 文本：以 `_bytes` 结尾的字段按字节数格式化，
+/// 以 `_count` 结尾的字段及 `total_files` / `total_dirs` 按计数格式化；递归处理嵌套对象与数组
+pub fn annotate_json(value: &mut Value, options: &FormatOptions) {
+    match value {
+        Value::Object(map) => {
+            let mut human = Vec::new();
+            for (key, field) in map.iter_mut() {
+                match field.as_u64() {
+                    Some(n) if key.ends_with("_bytes") => human.push((format!("{}_human", key), options.bytes(n))),
+                    Some(n) if key.ends_with("_count") || key == "total_files" || key == "total_dirs" => {
+                        human.push((format!("{}_human", key), options.count(n)))
+                    }
+                    Some(_) => {}
+                    None => annotate_json(field, options),
+                }
+            }
+            for (key, text) in human {
+                map.insert(key, Value::String(text));
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| annotate_json(item, options)),
+        _ => {}
+    }
+}
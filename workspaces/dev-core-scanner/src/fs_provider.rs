@@ -0,0 +1,37 @@
+//! 遍历访问文件系统的抽象
+//!
+//! 遍历读取目录与识别云端占位文件都经由 [`ScanRequest::fs_provider`]；未指定时使用直接访问文件系统的
+//! [`RealFs`]。测试注入 `testutil::FaultyFs` 即可模拟以 root 运行时无法制造的读取失败、
+//! 测试平台上不存在的占位文件以及处理文件时的 panic，遍历代码中不需要测试专用的分支。
+//!
+//! [`ScanRequest::fs_provider`]: crate::ScanRequest::fs_provider
+
+use std::fmt;
+use std::fs::{self, Metadata, ReadDir};
+use std::io;
+use std::path::Path;
+
+use crate::{long_path, placeholder};
+
+/// 遍历使用的文件系统操作
+pub trait FsProvider: fmt::Debug + Send + Sync {
+    /// 读取目录条目
+    fn read_dir(&self, dir: &Path) -> io::Result<ReadDir>;
+
+    /// 文件是否为仅在线的云端占位文件，见 [`placeholder`]
+    fn is_placeholder(&self, path: &Path, metadata: &Metadata) -> bool;
+}
+
+/// 直接访问文件系统
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFs;
+
+impl FsProvider for RealFs {
+    fn read_dir(&self, dir: &Path) -> io::Result<ReadDir> {
+        fs::read_dir(long_path::extended(dir))
+    }
+
+    fn is_placeholder(&self, _path: &Path, metadata: &Metadata) -> bool {
+        placeholder::is_placeholder(metadata)
+    }
+}
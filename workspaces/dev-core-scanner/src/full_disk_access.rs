@@ -0,0 +1,29 @@
+//! macOS 完全磁盘访问权限检测
+//!
+//! macOS 的隐私保护（TCC）会拒绝未获授权的进程读取 `~/Library/Mail`、`~/Library/Safari`
+//! 等受保护目录。与普通的权限不足（`EACCES`）不同，TCC 拒绝表现为 `EPERM`
+//! （“Operation not permitted”），即使以 root 运行也会出现。遍历器据此识别缺少完全磁盘访问权限，
+//! 在 `summary.full_disk_access_missing` 中标记，前端提示用户授权，而不是默默给出偏小的总量。
+
+use std::io;
+
+/// 系统设置中“完全磁盘访问权限”面板的 URL
+pub const SETTINGS_URL: &str = "x-apple.systempreferences:com.apple.preference.security?Privacy_AllFiles";
+
+/// 读取目录失败是否由缺少完全磁盘访问权限导致（仅 macOS 上可能为真）
+pub(crate) fn is_privacy_denial(err: &io::Error) -> bool {
+    // EPERM = 1
+    cfg!(target_os = "macos") && err.raw_os_error() == Some(1)
+}
+
+/// 打开系统设置中的完全磁盘访问权限面板（非 macOS 平台返回 `Unsupported`）
+pub fn open_settings() -> io::Result<()> {
+    if !cfg!(target_os = "macos") {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "完全磁盘访问权限仅适用于 macOS"));
+    }
+    let status = std::process::Command::new("open").arg(SETTINGS_URL).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("打开系统设置失败: {}", status)));
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+//! 后台扫描句柄
+//!
+//! [`Scanner::spawn`] 在后台线程中执行与 [`Scanner::scan_sync`] 相同的扫描并立即返回 [`ScanHandle`]：
+//! 调用方随时读取进度快照、请求取消，并在方便时等待结果，不必自行组合线程、[`LiveProgress`] 与
+//! [`CancellationToken`]。[`Scanner::scan_with_progress`] 即基于句柄实现。
+//!
+//! 句柄在取得结果前被丢弃时取消后台扫描。
+//!
+//! [`Scanner::spawn`]: crate::Scanner::spawn
+//! [`Scanner::scan_sync`]: crate::Scanner::scan_sync
+//! [`Scanner::scan_with_progress`]: crate::Scanner::scan_with_progress
+
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::{CancellationToken, LiveProgress, ScanError, ScanProgress, ScanRequest, ScanResult, ScanState, Scanner};
+
+/// 后台扫描的句柄，见 [模块文档](self)
+#[derive(Debug)]
+pub struct ScanHandle {
+    live: Arc<LiveProgress>,
+    cancel: CancellationToken,
+    receiver: Receiver<Result<ScanResult, ScanError>>,
+    thread: Option<JoinHandle<()>>,
+    /// 取得结果后的最终状态（`Completed` / `Canceled`，出错或线程异常退出时为 `Failed`）；未取得时为 None
+    outcome: Option<ScanState>,
+}
+
+impl ScanHandle {
+    /// 沿用请求中的进度计数与取消令牌（未提供时新建），在后台线程中开始扫描
+    pub(crate) fn start(request: &ScanRequest) -> Self {
+        let mut request = request.clone();
+        let live = request.progress.get_or_insert_with(LiveProgress::new).clone();
+        let cancel = request.cancel.get_or_insert_with(CancellationToken::new).clone();
+        let (sender, receiver) = channel();
+        let thread = std::thread::spawn(move || {
+            let _ = sender.send(Scanner::new().scan_sync(&request));
+        });
+        Self { live, cancel, receiver, thread: Some(thread), outcome: None }
+    }
+
+    /// 当前进度快照；结果已取得后为其最终状态（`Completed`、`Canceled`，出错时为 `Failed`），
+    /// 之前请求过取消时为 `Canceled`
+    pub fn progress(&self) -> ScanProgress {
+        let state = match &self.outcome {
+            Some(state) => state.clone(),
+            None if self.cancel.is_canceled() => ScanState::Canceled,
+            None => ScanState::Running,
+        };
+        self.live.snapshot(state)
+    }
+
+    /// 扫描实时累加的进度计数（可与其他线程共享）
+    pub fn live(&self) -> &Arc<LiveProgress> {
+        &self.live
+    }
+
+    /// 请求取消：遍历尽快停止，已统计的部分作为状态为 `Canceled` 的结果返回
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// 后台扫描是否已结束（结果可立即取得）
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    /// 最多等待 `timeout`：扫描在此期间结束时返回结果，否则返回 None；结果只会返回一次，
+    /// 之后再调用总是返回 None
+    pub fn wait_timeout(&mut self, timeout: Duration) -> Option<Result<ScanResult, ScanError>> {
+        if self.outcome.is_some() {
+            return None;
+        }
+        match self.receiver.recv_timeout(timeout) {
+            Err(RecvTimeoutError::Timeout) => None,
+            received => Some(self.finish(received.ok())),
+        }
+    }
+
+    /// 等待扫描结束并返回结果；结果已由 [`wait_timeout`](Self::wait_timeout) 取走时返回错误
+    pub fn join(mut self) -> Result<ScanResult, ScanError> {
+        if self.outcome.is_some() {
+            return Err(ScanError::ThreadPool { message: "扫描结果已被取走".to_string() });
+        }
+        let received = self.receiver.recv().ok();
+        self.finish(received)
+    }
+
+    /// 回收扫描线程；线程未发送结果就退出（panic）时返回错误
+    fn finish(&mut self, received: Option<Result<ScanResult, ScanError>>) -> Result<ScanResult, ScanError> {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let result =
+            received.unwrap_or_else(|| Err(ScanError::ThreadPool { message: "扫描线程异常退出".to_string() }));
+        self.outcome = Some(result.as_ref().map_or(ScanState::Failed, |r| r.summary.state.clone()));
+        result
+    }
+}
+
+impl Drop for ScanHandle {
+    fn drop(&mut self) {
+        if self.outcome.is_none() {
+            self.cancel.cancel();
+        }
+    }
+}
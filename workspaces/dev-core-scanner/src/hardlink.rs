@@ -0,0 +1,31 @@
+//! 硬链接去重
+//!
+//! 同一文件的多个硬链接指向相同的数据块，逐个路径累加大小会把这部分磁盘占用计算多次。
+//! 启用去重后，链接数大于 1 的文件按（设备号, inode）登记，只有最先遇到的路径计入统计，
+//! 其余路径被跳过，使总大小与各目录大小反映实际磁盘占用。
+//!
+//! 并行遍历时“最先遇到”的路径不固定，因此被计入的目录可能随扫描而变化，但总量稳定。
+//! 分片扫描的各分片互不知晓对方登记的 inode，跨分片的硬链接仍会重复计数。
+//! 非 Unix 平台无法取得 inode，不做去重。
+
+use std::collections::HashSet;
+use std::fs::Metadata;
+use std::sync::Mutex;
+
+/// 已计入统计的多链接文件
+#[derive(Debug, Default)]
+pub(crate) struct SeenInodes(Mutex<HashSet<(u64, u64)>>);
+
+impl SeenInodes {
+    /// 登记文件；同一 inode 已被其他路径计入时返回 false。单链接文件无需登记
+    #[cfg(unix)]
+    pub(crate) fn first_link(&self, metadata: &Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        metadata.nlink() <= 1 || crate::lock(&self.0).insert((metadata.dev(), metadata.ino()))
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn first_link(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+}
@@ -0,0 +1,115 @@
+//! 文件年龄热力图
+//!
+//! 为 GUI 的彩色 Treemap 提供数据：扫描根目录及其下 `heatmap_depth` 层以内的每个目录，
+//! 按最后修改时间把字节数划入若干年龄段，给出各年龄段的字节数与占比，
+//! 用颜色深浅直观展示“旧数据集中在哪里”。
+//!
+//! 年龄段字节数与目录大小一样沿目录链向上累加，每个目录任务只累加一次。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::share;
+
+/// 年龄段数量
+pub const AGE_BUCKETS: usize = 6;
+
+/// 年龄段标签（与 [`DirAge::age_bytes`] / [`DirAge::age_shares`] 的下标一一对应）
+pub const AGE_BUCKET_LABELS: [&str; AGE_BUCKETS] = ["<30d", "30-90d", "90d-1y", "1-3y", ">3y", "unknown"];
+
+/// 前五个年龄段的上限（天，不含）；超过最后一个上限归入 ">3y"
+const AGE_BUCKET_MAX_DAYS: [u64; 4] = [30, 90, 365, 3 * 365];
+
+/// 默认统计的目录深度（根目录为 0）
+pub const DEFAULT_HEATMAP_DEPTH: usize = 2;
+
+/// 按修改时间确定年龄段；修改时间未知或晚于当前时间时归入 "unknown"（扫描时可疑的修改时间已先经 [`crate::timestamp::clamp`] 校正）
+pub(crate) fn bucket_of(modified: Option<SystemTime>, now: SystemTime) -> usize {
+    let Some(age) = modified.and_then(|m| now.duration_since(m).ok()) else {
+        return AGE_BUCKETS - 1;
+    };
+    let days = age.as_secs() / 86400;
+    AGE_BUCKET_MAX_DAYS
+        .iter()
+        .position(|&max| days < max)
+        .unwrap_or(AGE_BUCKET_MAX_DAYS.len())
+}
+
+/// 各年龄段的字节数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct AgeBytes(pub(crate) [u64; AGE_BUCKETS]);
+
+impl AgeBytes {
+    pub(crate) fn add(&mut self, bucket: usize, bytes: u64) {
+        self.0[bucket] += bytes;
+    }
+
+    pub(crate) fn total(&self) -> u64 {
+        self.0.iter().sum()
+    }
+}
+
+/// 单个目录的年龄构成
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DirAge {
+    /// 目录路径
+    pub path: PathBuf,
+    /// 相对扫描根目录的深度（根目录为 0）
+    pub depth: usize,
+    /// 目录（含子目录）中计入统计的文件总大小
+    pub size_bytes: u64,
+    /// 各年龄段的字节数
+    pub age_bytes: Vec<u64>,
+    /// 各年龄段占目录大小的比例（保留 4 位小数）
+    pub age_shares: Vec<f64>,
+}
+
+impl DirAge {
+    pub(crate) fn new(path: PathBuf, depth: usize, ages: AgeBytes) -> Self {
+        let size_bytes = ages.total();
+        Self {
+            path,
+            depth,
+            size_bytes,
+            age_bytes: ages.0.to_vec(),
+            age_shares: ages.0.iter().map(|&b| share(b, size_bytes)).collect(),
+        }
+    }
+
+    fn ages(&self) -> AgeBytes {
+        let mut ages = AgeBytes::default();
+        for (slot, &bytes) in ages.0.iter_mut().zip(&self.age_bytes) {
+            *slot = bytes;
+        }
+        ages
+    }
+}
+
+/// 按路径合并多份热力图（同一目录的年龄段字节数相加），按深度、大小排序
+pub(crate) fn merge(rows: impl IntoIterator<Item = DirAge>) -> Vec<DirAge> {
+    let mut merged: HashMap<PathBuf, (usize, AgeBytes)> = HashMap::new();
+    for row in rows {
+        let ages = row.ages();
+        let slot = merged.entry(row.path).or_insert((row.depth, AgeBytes::default()));
+        slot.0 = slot.0.min(row.depth);
+        for (total, bytes) in slot.1 .0.iter_mut().zip(ages.0) {
+            *total += bytes;
+        }
+    }
+    sorted(merged.into_iter().map(|(path, (depth, ages))| DirAge::new(path, depth, ages)).collect())
+}
+
+/// 按深度升序、大小降序排列，便于前端逐层构建 Treemap
+pub(crate) fn sorted(mut rows: Vec<DirAge>) -> Vec<DirAge> {
+    rows.sort_by(|a, b| {
+        a.depth
+            .cmp(&b.depth)
+            .then_with(|| b.size_bytes.cmp(&a.size_bytes))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    rows
+}
@@ -0,0 +1,97 @@
+//! 基于历史扫描结果的目录并发提示
+//!
+//! 扫描过程中记录条目较多的目录及其条目数，下一次扫描同一根目录时据此
+//! 预先拆分大目录、优先派发重负载子树，减少长尾任务拖慢整体耗时。
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// 目录条目数达到该值时才会被记录为提示
+pub(crate) const RECORD_MIN_ENTRIES: u64 = 256;
+
+/// 提示条目数达到该值的目录从第一个文件起就分批并行处理（否则先在遍历任务中直接处理一批）
+pub(crate) const SPLIT_THRESHOLD: u64 = 2048;
+
+/// 大目录分批读取元数据时每个批次包含的文件数
+pub(crate) const FILE_BATCH_SIZE: usize = 512;
+
+/// 单个根目录的并发提示
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConcurrencyHints {
+    /// 提示对应的扫描根目录
+    pub root_path: PathBuf,
+    /// 目录路径 -> 上次扫描时的直接条目数
+    pub dir_entries: HashMap<PathBuf, u64>,
+    /// 上次扫描计入统计的总字节数，作为下次扫描的进度估算总量，见 [`estimate`](crate::estimate)
+    #[serde(default)]
+    pub scanned_bytes: u64,
+}
+
+impl ConcurrencyHints {
+    /// 查询指定目录在上次扫描时的条目数
+    pub fn entries_for(&self, dir: &Path) -> Option<u64> {
+        self.dir_entries.get(dir).copied()
+    }
+
+    /// 从 JSON 文件加载提示
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read(path)?;
+        serde_json::from_slice(&content).map_err(io::Error::other)
+    }
+
+    /// 将提示保存为 JSON 文件（自动创建父目录）
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_vec(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+}
+
+/// Surf 扫描缓存目录：优先 `$XDG_CACHE_HOME/surf`，否则 `~/.cache/surf`
+pub fn cache_dir() -> Option<PathBuf> {
+    if let Some(xdg) = std::env::var_os("XDG_CACHE_HOME").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(xdg).join("surf"));
+    }
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache").join("surf"))
+}
+
+/// 指定根目录对应的提示缓存文件路径（按规范化后的绝对路径区分）
+pub fn cache_path_for(root: &Path) -> Option<PathBuf> {
+    keyed_cache_path("hints", root)
+}
+
+/// 扫描缓存中 `<kind>/<根目录哈希>.json` 形式的文件路径
+pub(crate) fn keyed_cache_path(kind: &str, root: &Path) -> Option<PathBuf> {
+    let canonical = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    cache_dir().map(|dir| dir.join(kind).join(format!("{:016x}.json", fnv1a(&canonical))))
+}
+
+/// 从扫描缓存加载根目录的提示；不存在或不可解析时返回 None
+pub fn load_cached(root: &Path) -> Option<ConcurrencyHints> {
+    let hints = ConcurrencyHints::load(&cache_path_for(root)?).ok()?;
+    // 防御哈希碰撞：根目录不一致时视为无提示
+    (hints.root_path == root).then_some(hints)
+}
+
+/// 将提示写入扫描缓存
+pub fn store_cached(hints: &ConcurrencyHints) -> io::Result<()> {
+    let path = cache_path_for(&hints.root_path)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "无法确定缓存目录"))?;
+    hints.save(&path)
+}
+
+/// 稳定的路径哈希（FNV-1a），用于生成缓存文件名
+fn fnv1a(path: &Path) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in path.to_string_lossy().as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
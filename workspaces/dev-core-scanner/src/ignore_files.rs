@@ -0,0 +1,87 @@
+//! `.gitignore` / `.surfignore` 支持
+//!
+//! 开启 `respect_ignore_files` 后，遍历器在每个目录中读取 `.gitignore` 与项目级的 `.surfignore`
+//! （语法与 `.gitignore` 相同），被忽略的文件不计入统计，被忽略的目录整棵跳过，
+//! 扫描源码树时可自动略过 `node_modules`、`target/` 等构建产物。
+//!
+//! 规则按目录层级叠加：离路径最近的忽略文件优先，同一目录中 `.surfignore` 优先于 `.gitignore`，
+//! `!pattern` 可重新包含上级规则忽略的路径。扫描根目录的各级祖先目录中的忽略文件同样生效
+//! （扫描仓库的子目录时仓库根目录的 `.gitignore` 仍然适用）；不读取 git 的全局忽略配置。
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+/// 同一目录中按优先级从低到高读取的忽略文件
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".surfignore"];
+
+/// 某个目录生效的忽略规则（链表指向上级目录的规则）
+#[derive(Debug)]
+pub(crate) struct IgnoreRules {
+    matcher: Gitignore,
+    parent: Option<Arc<IgnoreRules>>,
+}
+
+impl IgnoreRules {
+    /// 扫描根目录生效的规则：依次叠加各级祖先目录与根目录自身的忽略文件
+    pub(crate) fn for_root(root: &Path) -> Option<Arc<IgnoreRules>> {
+        let ancestors: Vec<&Path> = root.ancestors().collect();
+        ancestors
+            .into_iter()
+            .rev()
+            .fold(None, |parent, dir| Self::for_dir(dir, parent.as_ref()))
+    }
+
+    /// 读取目录中的忽略文件并叠加到上级规则上；目录中没有忽略文件时沿用上级规则
+    pub(crate) fn for_dir(dir: &Path, parent: Option<&Arc<IgnoreRules>>) -> Option<Arc<IgnoreRules>> {
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut found = false;
+        for name in IGNORE_FILES {
+            let path = dir.join(name);
+            if path.is_file() {
+                // 部分行无法解析时，其余规则照常生效
+                let _ = builder.add(path);
+                found = true;
+            }
+        }
+        match found.then(|| builder.build().ok()).flatten() {
+            Some(matcher) if !matcher.is_empty() => Some(Arc::new(IgnoreRules {
+                matcher,
+                parent: parent.cloned(),
+            })),
+            _ => parent.cloned(),
+        }
+    }
+
+    /// 检查根目录及其各级祖先目录中的忽略文件，返回（文件路径，无法解析的行）列表；
+    /// 子目录中的忽略文件在遍历时才读取，不在检查范围内
+    pub(crate) fn check_root(root: &Path) -> Vec<(PathBuf, String)> {
+        let mut problems = Vec::new();
+        for dir in root.ancestors() {
+            for path in IGNORE_FILES.iter().map(|name| dir.join(name)).filter(|p| p.is_file()) {
+                if let Some(error) = GitignoreBuilder::new(dir).add(&path) {
+                    // 多行出错时错误信息逐行列出，去掉与位置重复的文件路径前缀
+                    let prefix = format!("{}: ", path.display());
+                    let lines = error.to_string().lines().map(|line| line.strip_prefix(&prefix).unwrap_or(line).to_string()).collect::<Vec<_>>();
+                    problems.extend(lines.into_iter().map(|line| (path.clone(), line)));
+                }
+            }
+        }
+        problems
+    }
+
+    /// 路径是否被忽略：从最近的规则向上查找，第一条匹配的规则决定结果
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut rules = Some(self);
+        while let Some(current) = rules {
+            match current.matcher.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => rules = current.parent.as_deref(),
+            }
+        }
+        false
+    }
+}
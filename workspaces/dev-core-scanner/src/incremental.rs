@@ -0,0 +1,179 @@
+//! 按目录修改时间的增量扫描缓存
+//!
+//! 请求 [`ScanRequest::incremental`] 时，根目录下的每棵顶层子树单独扫描（与分片扫描相同的方式），
+//! 其结果连同子树内每个目录的修改时间一起写入缓存 `~/.cache/surf/incremental/<根目录哈希>.json`。
+//! 再次扫描同一根目录时只需逐一 stat 缓存中记录的目录：全部目录的修改时间都未变化、扫描选项也相同的
+//! 子树直接使用缓存结果，不再读取目录内容与文件元数据；其余子树与根目录的直接文件照常遍历，最后按分片
+//! 结果合并。复用与重新扫描的子树数见 `diagnostics.cached_subtrees` 与 `diagnostics.rescanned_subtrees`。
+//!
+//! 目录的修改时间只在其中增删、重命名条目时变化，原地改写的文件（大小或修改时间变化）不会使缓存失效；
+//! 复用的子树中陈旧文件、年龄热力图等按时间计算的统计也停留在缓存写入时。含遍历错误或被取消的子树
+//! 不写入缓存。需要跨子树判断的选项（重复检测、硬链接去重、跟随目录符号链接）以及分析器插件、
+//! 访问日志与自定义时钟无法按子树缓存，请求这些选项时照常完整扫描。
+//!
+//! [`ScanRequest::incremental`]: crate::ScanRequest::incremental
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::merge::merge_disjoint;
+use crate::{lock, ScanError, ScanRequest, ScanResult, ScanState, Scanner, ShardAssignment};
+
+/// 缓存文件内容
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    /// 扫描根目录
+    root_path: PathBuf,
+    /// 影响结果的扫描选项（去掉根目录、线程数等字段后的请求）
+    options: serde_json::Value,
+    /// 顶层目录名 -> 子树缓存
+    subtrees: BTreeMap<String, CachedSubtree>,
+}
+
+/// 一棵顶层子树的缓存
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedSubtree {
+    /// 子树内每个目录及扫描时的修改时间（无法读取时为 `None`，此时缓存总是失效）
+    dirs: Vec<(PathBuf, Option<SystemTime>)>,
+    /// 子树的扫描结果
+    result: ScanResult,
+}
+
+impl CachedSubtree {
+    /// 子树中的目录是否都未变化
+    fn is_fresh(&self) -> bool {
+        self.dirs
+            .iter()
+            .all(|(dir, mtime)| mtime.is_some() && modified(dir) == *mtime)
+    }
+}
+
+/// 遍历期间记录目录修改时间（由增量扫描通过请求传入）
+#[derive(Debug, Default)]
+pub(crate) struct DirMtimes(Mutex<Vec<(PathBuf, Option<SystemTime>)>>);
+
+impl DirMtimes {
+    /// 在读取目录内容之前记录修改时间，扫描期间发生的变化会使缓存在下次扫描时失效
+    pub(crate) fn record(&self, dir: &Path) {
+        lock(&self.0).push((dir.to_path_buf(), modified(dir)));
+    }
+}
+
+fn modified(dir: &Path) -> Option<SystemTime> {
+    fs::metadata(dir).and_then(|m| m.modified()).ok()
+}
+
+/// 默认缓存文件路径：`~/.cache/surf/incremental/<根目录哈希>.json`
+pub fn cache_path_for(root: &Path) -> Option<PathBuf> {
+    crate::hints::keyed_cache_path("incremental", root)
+}
+
+/// 使用 `cache_path` 处的缓存增量扫描 `request.root_path`，并把本次结果写回缓存
+///
+/// 请求的选项无法按子树缓存时直接完整扫描；缓存读写失败不影响扫描本身。
+pub fn scan(request: &ScanRequest, cache_path: &Path) -> Result<ScanResult, ScanError> {
+    let mut request = request.clone();
+    request.incremental = false;
+    if !cacheable(&request) {
+        return Scanner::new().scan_sync(&request);
+    }
+    let start_time = SystemTime::now();
+    let root = request.root_path.clone();
+    let options = options_key(&request);
+    let mut cache = load(cache_path)
+        .filter(|cache| cache.root_path == root && cache.options == options)
+        .unwrap_or_default();
+
+    // 根目录的直接文件每次都重新统计（只需读取根目录本身）
+    let mut parts = vec![scan_part(&request, None)?.result];
+    let mut subtrees = BTreeMap::new();
+    let (mut cached, mut rescanned) = (0, 0);
+    for name in top_level_dirs(&root).map_err(|e| ScanError::io(&root, e))? {
+        let subtree = match cache.subtrees.remove(&name).filter(CachedSubtree::is_fresh) {
+            Some(subtree) => {
+                cached += 1;
+                subtree
+            }
+            None => {
+                rescanned += 1;
+                scan_part(&request, Some(&name))?
+            }
+        };
+        parts.push(subtree.result.clone());
+        let complete = subtree.result.summary.state != ScanState::Canceled && subtree.result.scan_errors.is_empty();
+        if complete {
+            subtrees.insert(name, subtree);
+        }
+    }
+
+    let mut result = merge_disjoint(parts, root.clone());
+    result.summary.elapsed_seconds = start_time.elapsed().unwrap_or_default().as_secs_f64();
+    result.diagnostics.cached_subtrees = cached;
+    result.diagnostics.rescanned_subtrees = rescanned;
+    let _ = store(cache_path, &CacheFile { root_path: root, options, subtrees });
+    Ok(result)
+}
+
+/// 各子树能否独立扫描后合并而不影响结果
+fn cacheable(request: &ScanRequest) -> bool {
+    request.dedup.is_none()
+        && !request.deduplicate_hardlinks
+        && !request.follow_symlinks.follows_dirs()
+        && request.plugins.is_empty()
+        && request.access_log.is_none()
+        && request.clock.is_none()
+        && request.fs_provider.is_none()
+        && request.shard.is_none()
+}
+
+/// 缓存只在这些选项相同时复用；线程数、`fail_fast`、`keep_awake`、`memory_budget` 与 I/O 限速不影响完整扫描的结果
+fn options_key(request: &ScanRequest) -> serde_json::Value {
+    let mut value = serde_json::to_value(request).unwrap_or_default();
+    if let Some(fields) = value.as_object_mut() {
+        for field in ["root_path", "threads", "fail_fast", "incremental", "keep_awake", "memory_budget", "max_io_ops_per_sec", "nice_mode"] {
+            fields.remove(field);
+        }
+    }
+    value
+}
+
+/// 根目录下的直接子目录名（按名称排序，不含符号链接）
+fn top_level_dirs(root: &Path) -> io::Result<BTreeSet<String>> {
+    Ok(fs::read_dir(root)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect())
+}
+
+/// 扫描一棵顶层子树（`None` 为根目录的直接文件），同时记录其中各目录的修改时间
+fn scan_part(request: &ScanRequest, name: Option<&str>) -> Result<CachedSubtree, ScanError> {
+    let mut request = request.clone();
+    request.shard = Some(ShardAssignment {
+        top_level_dirs: name.into_iter().map(str::to_string).collect(),
+        include_root_files: name.is_none(),
+        index: None,
+    });
+    let mtimes = Arc::new(DirMtimes::default());
+    request.dir_mtimes = Some(mtimes.clone());
+    let result = Scanner::new().scan_sync(&request)?;
+    let dirs = std::mem::take(&mut *lock(&mtimes.0));
+    Ok(CachedSubtree { dirs, result })
+}
+
+fn load(path: &Path) -> Option<CacheFile> {
+    serde_json::from_slice(&fs::read(path).ok()?).ok()
+}
+
+fn store(path: &Path, cache: &CacheFile) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_vec(cache).map_err(io::Error::other)?)
+}
@@ -0,0 +1,333 @@
+//! 后台索引守护
+//!
+//! 对指定根目录执行首次扫描后常驻内存，并周期性对账：以全部目录修改时间计算的
+//! 指纹检测结构变化（新增/删除/重命名条目都会更新所在目录的 mtime），指纹变化或
+//! 快照超过最大存活时间时重新扫描。最新快照持久化到扫描缓存，守护重启后无需
+//! 等待首次扫描即可立即应答查询。查询根目录的直接子目录时由 [`subtree`] 从快照中截取。
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError, RwLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::hints::keyed_cache_path;
+use crate::symlink::VisitedDirs;
+use crate::{lock, ScanRequest, ScanResult, Scanner};
+
+/// 索引守护配置
+#[derive(Debug, Clone)]
+pub struct IndexOptions {
+    /// 对账间隔
+    pub interval: Duration,
+    /// 快照最大存活时间；超过后即使指纹未变也重新扫描（捕获仅内容变化的文件）
+    pub max_age: Duration,
+    /// 快照持久化路径；为 None 时仅保存在内存中
+    pub cache_path: Option<PathBuf>,
+}
+
+impl IndexOptions {
+    /// 默认配置：每 5 分钟对账一次，快照最长保留 1 小时，持久化到扫描缓存
+    pub fn for_root(root: &Path) -> Self {
+        Self {
+            interval: Duration::from_secs(300),
+            max_age: Duration::from_secs(3600),
+            cache_path: keyed_cache_path("index", root),
+        }
+    }
+}
+
+/// 索引快照：某一时刻的完整扫描结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct IndexSnapshot {
+    /// 扫描结果
+    pub result: ScanResult,
+    /// 快照代数，每次重新扫描后递增
+    pub generation: u64,
+    /// 快照生成时间
+    #[serde(with = "crate::serde_time")]
+    pub indexed_at: SystemTime,
+    /// 生成快照时的目录结构指纹
+    pub fingerprint: u64,
+}
+
+/// 索引守护状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct IndexStatus {
+    /// 索引根目录
+    pub root_path: PathBuf,
+    /// 是否已有可用快照
+    pub ready: bool,
+    /// 是否正在重新扫描
+    pub scanning: bool,
+    /// 当前快照代数
+    pub generation: u64,
+    /// 当前快照生成时间
+    #[serde(default, with = "crate::serde_time::option")]
+    pub indexed_at: Option<SystemTime>,
+    /// 最近一次对账失败的错误信息
+    pub last_error: Option<String>,
+}
+
+/// 后台索引守护：持有最新快照并在后台线程中周期性对账
+pub struct IndexDaemon {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    request: ScanRequest,
+    options: IndexOptions,
+    snapshot: RwLock<Option<Arc<IndexSnapshot>>>,
+    scanning: AtomicBool,
+    last_error: Mutex<Option<String>>,
+    /// 保证同一时刻只有一次对账
+    reconcile_lock: Mutex<()>,
+    stop: Mutex<bool>,
+    wake: Condvar,
+}
+
+impl IndexDaemon {
+    /// 启动索引守护：先加载持久化快照（如有），随后在后台线程中执行首次对账并周期性重复
+    pub fn start(request: ScanRequest, options: IndexOptions) -> Self {
+        let persisted = options
+            .cache_path
+            .as_deref()
+            .and_then(|path| load_snapshot(path).ok())
+            .filter(|s| s.result.summary.root_path == request.root_path)
+            .map(Arc::new);
+        let shared = Arc::new(Shared {
+            request,
+            options,
+            snapshot: RwLock::new(persisted),
+            scanning: AtomicBool::new(false),
+            last_error: Mutex::new(None),
+            reconcile_lock: Mutex::new(()),
+            stop: Mutex::new(false),
+            wake: Condvar::new(),
+        });
+
+        let worker_shared = shared.clone();
+        let worker = std::thread::spawn(move || loop {
+            let _ = worker_shared.reconcile();
+            let stop = lock(&worker_shared.stop);
+            let (stop, _) = worker_shared
+                .wake
+                .wait_timeout_while(stop, worker_shared.options.interval, |stop| !*stop)
+                .unwrap_or_else(PoisonError::into_inner);
+            if *stop {
+                break;
+            }
+        });
+
+        Self { shared, worker: Some(worker) }
+    }
+
+    /// 索引根目录
+    pub fn root_path(&self) -> &Path {
+        &self.shared.request.root_path
+    }
+
+    /// 立即返回当前快照（不触发扫描）
+    pub fn snapshot(&self) -> Option<Arc<IndexSnapshot>> {
+        self.shared.snapshot.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// 当前守护状态
+    pub fn status(&self) -> IndexStatus {
+        let snapshot = self.snapshot();
+        IndexStatus {
+            root_path: self.shared.request.root_path.clone(),
+            ready: snapshot.is_some(),
+            scanning: self.shared.scanning.load(Ordering::Relaxed),
+            generation: snapshot.as_ref().map(|s| s.generation).unwrap_or(0),
+            indexed_at: snapshot.as_ref().map(|s| s.indexed_at),
+            last_error: lock(&self.shared.last_error).clone(),
+        }
+    }
+
+    /// 同步执行一次对账，返回是否重新扫描
+    pub fn refresh(&self) -> io::Result<bool> {
+        self.shared.reconcile()
+    }
+}
+
+impl Drop for IndexDaemon {
+    fn drop(&mut self) {
+        *lock(&self.shared.stop) = true;
+        self.shared.wake.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Shared {
+    fn reconcile(&self) -> io::Result<bool> {
+        let _guard = lock(&self.reconcile_lock);
+        let outcome = self.reconcile_locked();
+        *lock(&self.last_error) = outcome.as_ref().err().map(|e| e.to_string());
+        outcome
+    }
+
+    fn reconcile_locked(&self) -> io::Result<bool> {
+        let fingerprint = fingerprint(&self.request)?;
+        let current = self.snapshot.read().unwrap_or_else(PoisonError::into_inner).clone();
+        if let Some(current) = &current {
+            let age = current.indexed_at.elapsed().unwrap_or_default();
+            if current.fingerprint == fingerprint && age < self.options.max_age {
+                return Ok(false);
+            }
+        }
+
+        self.scanning.store(true, Ordering::Relaxed);
+        let result = Scanner::new().scan_sync(&self.request);
+        self.scanning.store(false, Ordering::Relaxed);
+
+        let snapshot = Arc::new(IndexSnapshot {
+            result: result?,
+            generation: current.map(|s| s.generation + 1).unwrap_or(1),
+            indexed_at: SystemTime::now(),
+            fingerprint,
+        });
+        if let Some(path) = &self.options.cache_path {
+            // 持久化失败不影响内存中的索引
+            let _ = save_snapshot(path, &snapshot);
+        }
+        *self.snapshot.write().unwrap_or_else(PoisonError::into_inner) = Some(snapshot);
+        Ok(true)
+    }
+}
+
+/// 计算根目录下所有目录（路径 + 修改时间）的指纹；按与扫描相同的策略跟随目录链接，
+/// 单文件系统模式下同样不进入其他文件系统的挂载点
+fn fingerprint(request: &ScanRequest) -> io::Result<u64> {
+    let root = &request.root_path;
+    let symlinks = request.follow_symlinks;
+    let root_device = crate::mount::root_device(root, request.same_filesystem);
+    let mut hasher = DefaultHasher::new();
+    fs::metadata(root)?.modified()?.hash(&mut hasher);
+    let visited = VisitedDirs::for_policy(symlinks, false);
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        if visited.as_ref().is_some_and(|v| v.visit(&dir).is_some()) {
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            if file_type.is_dir() || (file_type.is_symlink() && symlinks.follows_dirs() && path.is_dir()) {
+                if crate::mount::crosses(root_device, &path) {
+                    continue;
+                }
+                path.hash(&mut hasher);
+                if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                    modified.hash(&mut hasher);
+                }
+                stack.push(path);
+            }
+        }
+    }
+    Ok(hasher.finish())
+}
+
+/// 从快照结果中截取以 `path` 为根的结果，供查询索引根目录或其子目录时直接应答
+///
+/// `path` 为快照根目录时返回完整结果。为根目录的直接子目录时，文件数、目录数与大小取自顶层速览，
+/// 文件、目录、重复文件与遍历错误等列表按路径过滤，空文件、遍历错误、可疑时间戳与冗余文件计数按过滤后的列表重新统计；
+/// 扩展名、类别、属主、冗余文件类型与年龄分布等无法按子树拆分的汇总被清空。更深的子目录或不在根目录之下的路径无法由
+/// 快照准确回答，返回 `None`。
+pub fn subtree(result: &ScanResult, path: &Path) -> Option<ScanResult> {
+    if path == result.summary.root_path {
+        return Some(result.clone());
+    }
+    let row = result.top_level.iter().find(|row| !row.root_files && row.path == path)?;
+    let within = |p: &Path| p.starts_with(path);
+    let files = |entries: &[crate::FileEntry]| entries.iter().filter(|f| within(&f.path)).cloned().collect::<Vec<_>>();
+
+    let mut sub = ScanResult {
+        summary: crate::ScanSummary {
+            root_path: path.to_path_buf(),
+            total_files: row.file_count,
+            total_dirs: row.dir_count + 1,
+            total_size_bytes: row.size_bytes,
+            total_allocated_bytes: None,
+            ..result.summary.clone()
+        },
+        ..Default::default()
+    };
+    sub.top_files = files(&result.top_files);
+    sub.stale_files = files(&result.stale_files);
+    sub.suspicious_files = files(&result.suspicious_files);
+    sub.redundant_files = files(&result.redundant_files);
+    sub.oldest_files = files(&result.oldest_files);
+    sub.newest_files = files(&result.newest_files);
+    sub.empty_files = result.empty_files.iter().filter(|p| within(p)).cloned().collect();
+    sub.empty_dirs = result.empty_dirs.iter().filter(|d| within(&d.path)).cloned().collect();
+    sub.summary.suspicious_timestamps = sub.suspicious_files.len() as u64;
+    sub.summary.redundant_file_count = sub.redundant_files.len() as u64;
+    sub.summary.redundant_bytes = sub.redundant_files.iter().map(|f| f.size_bytes).sum();
+    sub.summary.empty_file_count = sub.empty_files.len() as u64;
+    sub.summary.empty_dir_count = sub.empty_dirs.len() as u64;
+    sub.scan_errors = result.scan_errors.iter().filter(|e| within(&e.path)).cloned().collect();
+    sub.summary.scan_error_count = sub.scan_errors.len() as u64;
+    sub.directories = result.directories.iter().filter(|d| within(&d.path)).cloned().collect();
+    sub.top_dirs = result.top_dirs.iter().filter(|d| within(&d.path) && d.path != path).cloned().collect();
+    sub.top_dirs_shallow = result.top_dirs_shallow.iter().filter(|d| within(&d.path)).cloned().collect();
+    sub.density.heavy = result.density.heavy.iter().filter(|d| within(&d.path)).cloned().collect();
+    sub.density.crowded = result.density.crowded.iter().filter(|d| within(&d.path)).cloned().collect();
+    sub.heatmap = result
+        .heatmap
+        .iter()
+        .filter(|row| within(&row.path))
+        .cloned()
+        .map(|mut row| {
+            row.depth -= 1;
+            row
+        })
+        .collect();
+    sub.duplicates = result
+        .duplicates
+        .iter()
+        .cloned()
+        .filter_map(|mut group| {
+            group.paths.retain(|p| within(p));
+            (group.paths.len() > 1).then_some(group)
+        })
+        .collect();
+    sub.system_reserved = result.system_reserved.iter().filter(|e| within(&e.path)).cloned().collect();
+    sub.reclaimable_report = result.reclaimable_report.clone();
+    sub.reclaimable_report.retain(within);
+    sub.compression_candidates = result.compression_candidates.iter().filter(|c| within(&c.path)).cloned().collect();
+    sub.summary.compression_candidate_count = sub.compression_candidates.len() as u64;
+    sub.summary.compression_savings_bytes = sub.compression_candidates.iter().map(|c| c.estimated_savings_bytes).sum();
+    sub.diagnostics.denied_dirs = result.diagnostics.denied_dirs.iter().filter(|d| within(d)).cloned().collect();
+    crate::share::apply_shares(&mut sub);
+    Some(sub)
+}
+
+fn load_snapshot(path: &Path) -> io::Result<IndexSnapshot> {
+    let content = fs::read(path)?;
+    serde_json::from_slice(&content).map_err(io::Error::other)
+}
+
+fn save_snapshot(path: &Path, snapshot: &IndexSnapshot) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_vec(snapshot).map_err(io::Error::other)?;
+    fs::write(path, json)
+}
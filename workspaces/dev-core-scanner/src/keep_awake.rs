@@ -0,0 +1,232 @@
+//! 扫描期间阻止系统睡眠
+//!
+//! 完整扫描大磁盘或网络卷可能持续数十分钟，期间系统进入空闲睡眠会让扫描停滞，网络卷还可能因此断开。
+//! 请求 [`ScanRequest::keep_awake`] 时，扫描开始前向操作系统申请阻止睡眠，扫描结束（完成、失败或取消）
+//! 时释放：
+//!
+//! - macOS：IOKit 电源断言 `PreventUserIdleSystemSleep`；
+//! - Windows：`SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED)`，作用于执行扫描的线程；
+//! - Linux：`systemd-inhibit --what=idle:sleep` 子进程，持有抑制锁直到其标准输入关闭
+//!   （本进程异常退出时同样会关闭，不会遗留抑制锁）。
+//!
+//! 申请失败（如系统没有 systemd-logind）不影响扫描，状态与原因记录在 `diagnostics.keep_awake` 中。
+//!
+//! [`ScanRequest::keep_awake`]: crate::ScanRequest::keep_awake
+
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+/// 申请时向系统说明的原因（显示在 `pmset -g assertions`、`systemd-inhibit --list` 等处）
+const REASON: &str = "surf 正在扫描磁盘";
+
+/// 阻止睡眠的申请状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct KeepAwakeStatus {
+    /// 使用的机制：`iokit`、`set_thread_execution_state`、`systemd_inhibit`；平台不支持时为 `none`
+    pub mechanism: String,
+    /// 扫描期间是否成功阻止了睡眠
+    pub held: bool,
+    /// 申请失败的原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 阻止睡眠的守卫：持有期间系统不会进入空闲睡眠，丢弃时释放
+///
+/// Windows 上的执行状态属于调用线程，因此守卫不可跨线程移动，须在申请它的线程上丢弃。
+#[derive(Debug)]
+pub(crate) struct KeepAwake {
+    status: KeepAwakeStatus,
+    inner: Option<platform::Assertion>,
+    _thread_bound: PhantomData<*const ()>,
+}
+
+impl KeepAwake {
+    /// 向系统申请阻止睡眠；失败时返回未持有的守卫，原因见 [`status`](Self::status)
+    pub(crate) fn acquire() -> Self {
+        let (inner, error) = match platform::Assertion::create(REASON) {
+            Ok(assertion) => (Some(assertion), None),
+            Err(error) => (None, Some(error)),
+        };
+        let status = KeepAwakeStatus { mechanism: platform::MECHANISM.to_string(), held: inner.is_some(), error };
+        Self { status, inner, _thread_bound: PhantomData }
+    }
+
+    pub(crate) fn status(&self) -> &KeepAwakeStatus {
+        &self.status
+    }
+}
+
+impl Drop for KeepAwake {
+    fn drop(&mut self) {
+        if let Some(assertion) = self.inner.take() {
+            assertion.release();
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::{c_char, c_void, CString};
+
+    pub(super) const MECHANISM: &str = "iokit";
+
+    type CFStringRef = *const c_void;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(alloc: *const c_void, c_str: *const c_char, encoding: u32) -> CFStringRef;
+        fn CFRelease(cf: *const c_void);
+    }
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOPMAssertionCreateWithName(assertion_type: CFStringRef, level: u32, name: CFStringRef, id: *mut u32) -> i32;
+        fn IOPMAssertionRelease(id: u32) -> i32;
+    }
+
+    const CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+    const IOPM_ASSERTION_LEVEL_ON: u32 = 255;
+
+    /// 持有的 IOKit 电源断言
+    #[derive(Debug)]
+    pub(super) struct Assertion(u32);
+
+    /// 创建 CFString（调用方负责 `CFRelease`）；失败时为空指针
+    fn cf_string(text: &str) -> CFStringRef {
+        let Ok(text) = CString::new(text) else { return std::ptr::null() };
+        // SAFETY: `text` 以 NUL 结尾且在调用期间有效，分配器为空表示使用默认分配器
+        unsafe { CFStringCreateWithCString(std::ptr::null(), text.as_ptr(), CF_STRING_ENCODING_UTF8) }
+    }
+
+    impl Assertion {
+        pub(super) fn create(reason: &str) -> Result<Self, String> {
+            let kind = cf_string("PreventUserIdleSystemSleep");
+            let name = cf_string(reason);
+            let mut id = 0u32;
+            let code = if kind.is_null() || name.is_null() {
+                None
+            } else {
+                // SAFETY: 两个 CFString 均有效，`id` 为有效的输出指针
+                Some(unsafe { IOPMAssertionCreateWithName(kind, IOPM_ASSERTION_LEVEL_ON, name, &mut id) })
+            };
+            for string in [kind, name] {
+                if !string.is_null() {
+                    // SAFETY: 由 `cf_string` 创建且只释放一次
+                    unsafe { CFRelease(string) };
+                }
+            }
+            match code {
+                Some(0) => Ok(Assertion(id)),
+                Some(code) => Err(format!("IOPMAssertionCreateWithName 返回 {:#x}", code)),
+                None => Err("创建 CFString 失败".to_string()),
+            }
+        }
+
+        pub(super) fn release(self) {
+            // SAFETY: `self.0` 为创建成功的断言 ID，只释放一次
+            unsafe { IOPMAssertionRelease(self.0) };
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    pub(super) const MECHANISM: &str = "set_thread_execution_state";
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+
+    const ES_CONTINUOUS: u32 = 0x8000_0000;
+    const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+    /// 当前线程设置的执行状态
+    #[derive(Debug)]
+    pub(super) struct Assertion;
+
+    impl Assertion {
+        pub(super) fn create(_reason: &str) -> Result<Self, String> {
+            // SAFETY: 仅传入文档定义的标志位
+            match unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) } {
+                0 => Err(format!("SetThreadExecutionState 失败: {}", std::io::Error::last_os_error())),
+                _ => Ok(Assertion),
+            }
+        }
+
+        pub(super) fn release(self) {
+            // SAFETY: 同上；只保留 ES_CONTINUOUS 即清除本线程的要求
+            unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::io::Read;
+    use std::process::{Child, Command, Stdio};
+    use std::time::Duration;
+
+    pub(super) const MECHANISM: &str = "systemd_inhibit";
+
+    /// systemd-inhibit 无法取得抑制锁时会立即退出，启动后等待这么久确认其仍在运行
+    const STARTUP_CHECK: Duration = Duration::from_millis(50);
+
+    /// 持有抑制锁的 `systemd-inhibit ... cat` 子进程：关闭其标准输入后 `cat` 退出，锁随之释放
+    #[derive(Debug)]
+    pub(super) struct Assertion(Child);
+
+    impl Assertion {
+        pub(super) fn create(reason: &str) -> Result<Self, String> {
+            let mut child = Command::new("systemd-inhibit")
+                .args(["--what=idle:sleep", "--who=surf", "--mode=block"])
+                .arg(format!("--why={}", reason))
+                .arg("cat")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| format!("无法启动 systemd-inhibit: {}", e))?;
+            std::thread::sleep(STARTUP_CHECK);
+            match child.try_wait() {
+                Ok(None) => Ok(Assertion(child)),
+                Ok(Some(status)) => {
+                    let mut stderr = String::new();
+                    if let Some(mut pipe) = child.stderr.take() {
+                        let _ = pipe.read_to_string(&mut stderr);
+                    }
+                    Err(format!("systemd-inhibit 退出（{}）: {}", status, stderr.trim()))
+                }
+                Err(e) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    Err(format!("无法确认 systemd-inhibit 状态: {}", e))
+                }
+            }
+        }
+
+        pub(super) fn release(mut self) {
+            drop(self.0.stdin.take());
+            let _ = self.0.wait();
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+mod platform {
+    pub(super) const MECHANISM: &str = "none";
+
+    #[derive(Debug)]
+    pub(super) struct Assertion;
+
+    impl Assertion {
+        pub(super) fn create(_reason: &str) -> Result<Self, String> {
+            Err("当前平台不支持阻止睡眠".to_string())
+        }
+
+        pub(super) fn release(self) {}
+    }
+}
@@ -1,70 +1,514 @@
 //! Surf 核心扫描与分析引擎库
 //! 
 //! 提供文件系统扫描、目录树构建、统计分析等核心能力。
+//!
+//! # 公共 API 约定
+//!
+//! 下游 crate（CLI/TUI、服务、GUI、插件）应优先通过 [`prelude`] 引入类型。
+//! 结果类型（`ScanResult`、`ScanSummary`、`FileEntry` 等）与状态枚举均标注
+//! `#[non_exhaustive]`：后续版本新增字段或枚举值不视为破坏性变更，
+//! 因此下游不能用结构体字面量构造它们，需使用 `Default` 或提供的构造函数；
+//! 匹配 `ScanState` 时需保留通配分支。调度阈值等内部细节不属于公共 API。
 
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
-use std::collections::{BinaryHeap, HashMap};
+use std::time::{Duration, Instant, SystemTime};
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
 use std::cmp::Reverse;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex};
-use rayon;
-use glob::Pattern;
-use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use serde::{Deserialize, Serialize};
+
+pub mod access;
+pub mod age_histogram;
+mod allocation;
+pub mod budget;
+pub mod category;
+pub mod check;
+pub mod cleanup;
+pub mod clock;
+pub mod compression;
+pub mod content_hash;
+pub mod density;
+pub mod dedup;
+pub mod delete;
+pub mod diff;
+pub mod disk_space;
+pub mod empty;
+pub mod error;
+pub mod estimate;
+pub mod extension_top;
+mod filter;
+pub mod format;
+pub mod fs_provider;
+pub mod full_disk_access;
+pub mod handle;
+mod hardlink;
+pub mod heatmap;
+mod ignore_files;
+pub mod phase;
+pub mod hints;
+pub mod incremental;
+pub mod index;
+pub mod keep_awake;
+pub mod long_path;
+mod merge;
+mod multi_root;
+pub mod owner;
+pub mod min_size;
+pub mod mime;
+mod mount;
+pub mod package;
+pub mod placeholder;
+pub mod pacing;
+pub mod plugin;
+pub mod recency;
+pub mod reclaimable;
+pub mod redundant;
+pub mod reserved;
+pub mod retry;
+pub mod scan_error;
+pub mod serde_time;
+pub mod shard;
+pub mod size_histogram;
+pub mod stream;
+mod share;
+pub mod symlink;
+pub mod timestamp;
+pub mod top_level;
+mod worker_local;
+#[cfg(any(test, feature = "testutil"))]
+pub mod testutil;
+
+use access::AccessRecord;
+pub use access::{AccessLog, StaleMode, StaleSort};
+pub use age_histogram::AgeBucket;
+pub use category::{CategoryStat, FileCategory};
+pub use clock::{Clock, FixedClock, SystemClock};
+pub use compression::{CompressionCandidate, CompressionOptions};
+pub use content_hash::HashAlgo;
+pub use dedup::{DedupOptions, DuplicateGroup};
+pub use delete::{delete_entry, DeleteItem, DeleteMode, DeleteOptions, DeleteResult};
+pub use diff::{diff, ChangeKind, EntryChange, ScanDiff};
+pub use disk_space::DiskSpace;
+pub use empty::EmptyDir;
+pub use error::ScanError;
+pub use format::{FormatOptions, NumberLocale, UnitSystem};
+pub use fs_provider::{FsProvider, RealFs};
+pub use handle::ScanHandle;
+pub use heatmap::DirAge;
+pub use phase::{PhaseProgress, ScanPhase};
+pub use density::{DensityRanking, DirDensity};
+pub use hints::ConcurrencyHints;
+pub use index::{IndexDaemon, IndexOptions, IndexSnapshot, IndexStatus};
+pub use keep_awake::KeepAwakeStatus;
+pub use merge::{graft, merge};
+pub use owner::{OwnerStat, Ownership};
+pub use plugin::PluginSpec;
+pub use reclaimable::{ReclaimableKind, ReclaimableReport};
+pub use redundant::{RedundantKind, RedundantStat};
+pub use retry::RetryPolicy;
+pub use scan_error::{ScanOperation, TraversalError};
+use plugin::PluginSet;
+pub use shard::{ShardAssignment, ShardManifest};
+pub use size_histogram::{SizeBucket, SizeHistogram};
+pub use stream::ScanIter;
+pub use share::{share, DirStat};
+pub use symlink::{DirAlias, SymlinkPolicy};
+pub use top_level::TopLevelStat;
+use symlink::VisitedDirs;
+use filter::PathFilter;
+use ignore_files::IgnoreRules;
+use hardlink::SeenInodes;
+use share::DirNode;
+use reserved::{ReservedEntry, ReservedKind};
+use heatmap::AgeBytes;
+use worker_local::PerWorker;
+use budget::{TaskBudget, TaskPermit};
+use pacing::{IoPacer, PaceBatch};
+use package::PackageSize;
+use density::{DensityTracker, SplitDir};
+
+/// 常用公共类型的统一入口：`use surf_core::prelude::*;`
+pub mod prelude {
+    pub use crate::{
+        merge, scan_path, AccessLog, CancellationToken, ConcurrencyHints, DirStat, ExtensionStat, FileEntry, IndexDaemon, IndexOptions,
+        LiveProgress, PhaseProgress, ScanDiagnostics, ScanPhase, ScanProgress, ScanRequest, ScanResult, ScanState, ScanSummary, Scanner,
+        DedupOptions, DensityRanking, DirAge, DirDensity, DuplicateGroup, RetryPolicy, ShardManifest, SymlinkPolicy, WorkerPanic,
+    };
+}
+
+/// 未指定 `limit` 时的 Top N 数量
+pub const DEFAULT_LIMIT: usize = 20;
+
+/// Top N 数量的默认上限（见 [`ScanRequest::max_limit`]）
+pub const MAX_LIMIT: usize = 100_000;
+
+/// 大目录排名默认统计的目录深度（根目录为 0，排名不含根目录本身）
+pub const DEFAULT_TOP_DIRS_DEPTH: usize = 3;
+
 /// 扫描请求参数
-#[derive(Debug, Clone, Serialize)]
+///
+/// 反序列化时除 `root_path` 外的字段均可省略，取值与 [`ScanRequest::new`] 相同；
+/// 运行时对象（并发提示、访问日志、进度与取消令牌）不参与序列化。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ScanRequest {
     /// 扫描起始根目录
     pub root_path: PathBuf,
+    /// 同时扫描的多个根目录；非空时取代 `root_path`，各根目录并发扫描后合并，见 [`multi_root`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<PathBuf>,
     /// 并发扫描线程数（默认逻辑核心数）
+    #[serde(default)]
     pub threads: Option<u16>,
-    /// 最小文件大小过滤（字节）
+    /// 最小文件大小过滤（字节）；`Some(0)` 表示不过滤
+    #[serde(default)]
     pub min_size: Option<u64>,
+    /// 未指定 `min_size` 时依据文件系统容量自动选择默认阈值
+    #[serde(default)]
+    pub auto_min_size: bool,
     /// 排除规则（glob 模式）
+    #[serde(default)]
     pub exclude_patterns: Vec<String>,
+    /// 包含规则（glob 模式，支持 `{a,b}` 备选）；非空时只统计匹配任一规则的文件
+    ///
+    /// 先包含后排除：文件需匹配包含规则且不匹配排除规则才被统计。包含规则只作用于文件，
+    /// 目录总会被遍历（其中可能有匹配的文件）；排除规则匹配的目录仍整棵跳过。
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    /// 排除规则（正则表达式，匹配相对于根目录的路径，如 `logs/2024-05-01`），用于 glob 无法表达的规则，
+    /// 如任意层级按日期命名的目录 `(^|/)\d{4}-\d{2}-\d{2}$`；`anchored:` 前缀改为匹配完整路径。
+    /// 与 `exclude_patterns` 任一匹配即排除
+    #[serde(default)]
+    pub exclude_regex: Vec<String>,
+    /// 包含规则（正则表达式）；与 `include_patterns` 合并，文件匹配其中任一规则即视为包含
+    #[serde(default)]
+    pub include_regex: Vec<String>,
+    /// 遵循 `.gitignore` 与 `.surfignore`：被忽略的文件与目录不计入统计（见 [`ignore_files`] 模块说明）
+    #[serde(default)]
+    pub respect_ignore_files: bool,
+    /// 排除与包含规则中相对路径的基准目录，缺省为 `root_path`；以子目录为根补扫同一棵树时
+    /// 设为原扫描的根目录，使规则含义保持一致，见 [`filter`] 模块说明
+    #[serde(default)]
+    pub filter_root: Option<PathBuf>,
+    /// 最大遍历深度（根目录为 0），用于快速浅层扫描：只读取深度不超过该值的目录，
+    /// 更深的子目录仍计入目录数但不再进入，其内容不计入统计；`None` 表示不限制
+    #[serde(default)]
+    pub max_depth: Option<usize>,
     /// 时间分析阈值天数（识别陈旧文件）
+    #[serde(default)]
     pub stale_days: Option<u32>,
-    /// Top N 大文件数量限制（默认20）
+    /// 陈旧判断参照的时间（修改 / 访问 / 创建 / 三者最晚者，默认修改时间），见 [`StaleMode`]
+    #[serde(default)]
+    pub stale_mode: StaleMode,
+    /// 陈旧文件最多保留的数量（按 `stale_sort` 保留排名靠前者），`None` 表示全部保留
+    #[serde(default)]
+    pub stale_limit: Option<usize>,
+    /// 陈旧文件的排序依据；缺省时提供访问日志按冷度、否则按空闲时间排序，见 [`StaleSort`]
+    #[serde(default)]
+    pub stale_sort: Option<StaleSort>,
+    /// 空文件与空目录列表各自最多保留的条目数（按路径排序），`None` 表示全部保留，见 [`empty`]
+    #[serde(default)]
+    pub empty_limit: Option<usize>,
+    /// Top N 数量（大文件列表与目录密度排名，默认 [`DEFAULT_LIMIT`]）；0 表示不收集这些列表
+    #[serde(default)]
     pub limit: Option<usize>,
+    /// `limit` 的上限（默认 [`MAX_LIMIT`]），超出时按上限截断并在诊断信息中报告
+    #[serde(default = "default_max_limit")]
+    pub max_limit: usize,
+    /// `top_files` 保留的条目数上限（同样不超过 `max_limit`），大于 `limit` 时多保留的大文件供调用方
+    /// 分页浏览（见 [`ScanResult::page_top_files`]），其余排名仍按 `limit` 截断；`None` 表示与 `limit` 相同
+    #[serde(default)]
+    pub top_files_capacity: Option<usize>,
+    /// 来自同一根目录历史扫描的并发提示，用于预先拆分大目录
+    #[serde(skip)]
+    pub concurrency_hints: Option<Arc<ConcurrencyHints>>,
+    /// 多进程协同扫描时本进程负责的分片（仅遍历分配到的顶层目录）
+    #[serde(default)]
+    pub shard: Option<ShardAssignment>,
+    /// 分析器插件：扫描过程中接收所有通过过滤的文件条目，结果合并到 `plugin_sections`
+    ///
+    /// 只能在代码中或通过命令行参数指定，不参与序列化，反序列化得到的请求不会启动任何外部程序
+    #[serde(skip)]
+    pub plugins: Vec<PluginSpec>,
+    /// 访问日志：与 `stale_days` 配合使用，按最近活动时间判断陈旧并计算冷度评分
+    #[serde(skip)]
+    pub access_log: Option<Arc<AccessLog>>,
+    /// 陈旧判断、冷度评分与年龄热力图参照的时钟（可选，默认系统时钟），见 [`clock`]
+    #[serde(skip)]
+    pub clock: Option<Arc<dyn Clock>>,
+    /// 遍历访问文件系统的方式（可选，默认直接访问），见 [`fs_provider`]
+    #[serde(skip)]
+    pub fs_provider: Option<Arc<dyn FsProvider>>,
+    /// 在 `suspicious_files` 中列出修改时间可疑（早于 1980 年或晚于当前时间）且偏差最大的文件，
+    /// 最多 Top N 个；未开启时只计数，见 [`timestamp`]
+    #[serde(default)]
+    pub list_suspicious_timestamps: bool,
+    /// 在 `redundant_files` 中列出最大的 Top N 个冗余附属文件（备份、编号副本、锁文件等）；
+    /// 未开启时只按类型汇总，见 [`redundant`]
+    #[serde(default)]
+    pub list_redundant_files: bool,
+    /// 在 `oldest_files` 与 `newest_files` 中按修改时间列出最旧与最新的文件，各自最多保留的条目数
+    /// （与 Top N 数量无关）；`None` 表示不收集，见 [`recency`]
+    #[serde(default)]
+    pub recency_limit: Option<usize>,
+    /// 每个扩展名在 `by_extension` 对应行的 `top_files` 中保留的最大文件数；`None` 表示不收集，
+    /// 见 [`extension_top`]
+    #[serde(default)]
+    pub extension_top_limit: Option<usize>,
+    /// 统计对数刻度大小分布的选择器：扩展名（如 `mp4`）或 `category:<类别名>`（如 `category:video`）；
+    /// 为空表示不统计，见 [`size_histogram`]
+    #[serde(default)]
+    pub size_histograms: Vec<String>,
+    /// 年龄热力图统计的目录深度（根目录为 0）
+    #[serde(default = "default_heatmap_depth")]
+    pub heatmap_depth: usize,
+    /// 大目录排名（`top_dirs`）统计的最大目录深度
+    #[serde(default = "default_top_dirs_depth")]
+    pub top_dirs_depth: usize,
+    /// 大目录排名的条目数；未指定时与 Top N 数量（[`ScanRequest::effective_limit`]）相同
+    #[serde(default)]
+    pub top_dirs_limit: Option<usize>,
+    /// 重复文件检测选项；`None` 表示不检测（遍历结束后额外进行哈希阶段）
+    #[serde(default)]
+    pub dedup: Option<DedupOptions>,
+    /// 为 Top N 大文件计算内容摘要的算法；`None` 表示不计算，见 [`content_hash`]
+    #[serde(default)]
+    pub compute_hash: Option<HashAlgo>,
+    /// 内容摘要阶段的读取速率上限（字节/秒），`None` 表示不限速
+    #[serde(default)]
+    pub hash_max_bytes_per_sec: Option<u64>,
+    /// 可压缩文件分析选项；`None` 表示不分析（遍历结束后估算大而旧的文件压缩后可节省的空间），见 [`compression`]
+    #[serde(default)]
+    pub compression: Option<CompressionOptions>,
+    /// 读取文件头识别 MIME 类型，写入 `FileEntry::mime`（改过扩展名的文件也能正确识别），见 [`mime`]
+    #[serde(default)]
+    pub detect_mime: bool,
+    /// 只识别不小于该大小的文件（字节），跳过大量小文件以减少读取
+    #[serde(default = "default_mime_min_size")]
+    pub mime_min_size: u64,
+    /// 读取文件元数据遇到瞬时错误（EINTR/EAGAIN/ESTALE）时的重试策略
+    #[serde(default)]
+    pub metadata_retry: RetryPolicy,
+    /// 遇到第一个遍历错误（无法读取目录或元数据）即中止扫描并返回该错误，见 [`scan_error`]
+    #[serde(default)]
+    pub fail_fast: bool,
+    /// 符号链接跟随策略（默认不跟随）
+    #[serde(default)]
+    pub follow_symlinks: SymlinkPolicy,
+    /// 单文件系统模式（`du -x`）：不进入与根目录位于不同文件系统的子目录（挂载点），见 [`mount`]
+    #[serde(default)]
+    pub same_filesystem: bool,
+    /// 硬链接去重：同一 inode 的多个路径只计入最先遇到的一个，使大小反映实际磁盘占用
+    #[serde(default)]
+    pub deduplicate_hardlinks: bool,
+    /// 同时记录文件实际分配的磁盘空间（与 `du` 一致），见 `FileEntry::allocated_bytes`
+    #[serde(default)]
+    pub allocated_sizes: bool,
+    /// 记录文件属主（uid/gid 及名称）、权限位与只读标记，并按属主汇总到 `by_owner`，见 [`owner`]
+    #[serde(default)]
+    pub collect_owners: bool,
+    /// 保留文件条目（默认开启）。关闭后只计算摘要、扩展名与属主统计及各项目录汇总，
+    /// 不收集 Top N 大文件、陈旧文件、可疑时间戳文件与重复检测候选，内存占用与文件数无关，
+    /// 适合为仪表盘快速扫描上亿文件的文件系统
+    #[serde(default = "default_collect_entries")]
+    pub collect_entries: bool,
+    /// 复用增量缓存中未变化的顶层子树（按目录修改时间判断），并把本次结果写回缓存，见 [`incremental`]
+    #[serde(default)]
+    pub incremental: bool,
+    /// 扫描期间阻止系统睡眠，结束（含失败与取消）时释放，见 [`keep_awake`]
+    #[serde(default)]
+    pub keep_awake: bool,
+    /// 把包目录（`.app`、`.photoslibrary`、`.framework` 等）作为单个文件统计，大小为其内部文件之和，
+    /// 不再进入其内部，见 [`package`]
+    #[serde(default)]
+    pub opaque_packages: bool,
+    /// 遍历工作集的内存预算（字节）：限制在途遍历任务数，超出时改为在当前任务中深度优先遍历，
+    /// 见 [`budget`]；`None` 表示不限制
+    #[serde(default)]
+    pub memory_budget: Option<u64>,
+    /// 遍历读取元数据的速率上限（读取目录与文件 stat，次/秒），见 [`pacing`]；`None` 表示不限速
+    #[serde(default)]
+    pub max_io_ops_per_sec: Option<u64>,
+    /// 后台扫描预设：未指定时限速为 [`pacing::NICE_MODE_OPS_PER_SEC`]，并只用
+    /// [`pacing::NICE_MODE_THREADS`] 个遍历线程
+    #[serde(default)]
+    pub nice_mode: bool,
+    /// 增量扫描记录目录修改时间（仅在内部使用）
+    #[serde(skip)]
+    pub(crate) dir_mtimes: Option<Arc<incremental::DirMtimes>>,
+    /// 流式遍历时接收文件条目的队列（仅在内部使用，见 [`Scanner::scan_iter`]）
+    #[serde(skip)]
+    pub(crate) entry_sink: Option<stream::EntrySink>,
+    /// 实时进度计数（可选）：调用方保留同一个 `Arc` 即可在扫描期间无锁查询进度
+    #[serde(skip)]
+    pub progress: Option<Arc<LiveProgress>>,
+    /// 取消令牌（可选）：调用方保留克隆，调用 [`CancellationToken::cancel`] 即可中止扫描
+    #[serde(skip)]
+    pub cancel: Option<CancellationToken>,
+}
+
+fn default_max_limit() -> usize {
+    MAX_LIMIT
+}
+
+fn default_collect_entries() -> bool {
+    true
+}
+
+fn default_mime_min_size() -> u64 {
+    mime::DEFAULT_MIN_SIZE
+}
+
+fn default_heatmap_depth() -> usize {
+    heatmap::DEFAULT_HEATMAP_DEPTH
+}
+
+fn default_top_dirs_depth() -> usize {
+    DEFAULT_TOP_DIRS_DEPTH
 }
 
 impl ScanRequest {
+    /// 本次扫描实际采用的 Top N 数量：未指定时为 [`DEFAULT_LIMIT`]，超过 `max_limit` 时截断
+    pub fn effective_limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT).min(self.max_limit)
+    }
+
+    /// `top_files` 实际保留的条目数：不少于 [`effective_limit`](Self::effective_limit)，不超过 `max_limit`
+    pub fn effective_top_files_capacity(&self) -> usize {
+        self.top_files_capacity.unwrap_or(0).min(self.max_limit).max(self.effective_limit())
+    }
+
+    /// 陈旧文件实际采用的排序依据：未指定时提供访问日志按冷度，否则按空闲时间
+    pub fn effective_stale_sort(&self) -> StaleSort {
+        self.stale_sort
+            .unwrap_or(if self.access_log.is_some() { StaleSort::Coldness } else { StaleSort::Age })
+    }
+
+    /// 遍历实际采用的元数据操作速率上限：未指定时 `nice_mode` 下为 [`pacing::NICE_MODE_OPS_PER_SEC`]
+    pub fn effective_io_ops_per_sec(&self) -> Option<u64> {
+        self.max_io_ops_per_sec
+            .or(self.nice_mode.then_some(pacing::NICE_MODE_OPS_PER_SEC))
+            .filter(|&n| n > 0)
+    }
+
+    /// 创建同时扫描多个根目录的请求（`root_path` 取第一个根目录）
+    pub fn for_roots<I, P>(roots: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        let roots: Vec<PathBuf> = roots.into_iter().map(Into::into).collect();
+        let mut request = Self::new(roots.first().cloned().unwrap_or_default());
+        request.roots = roots;
+        request
+    }
+
     /// 创建一个新的扫描请求
     pub fn new<P: Into<PathBuf>>(root_path: P) -> Self {
         Self {
             root_path: root_path.into(),
+            roots: Vec::new(),
             threads: None,
             min_size: None,
+            auto_min_size: false,
             exclude_patterns: Vec::new(),
+            include_patterns: Vec::new(),
+            exclude_regex: Vec::new(),
+            include_regex: Vec::new(),
+            respect_ignore_files: false,
+            filter_root: None,
+            max_depth: None,
             stale_days: None,
+            stale_mode: StaleMode::default(),
+            stale_limit: None,
+            stale_sort: None,
+            empty_limit: None,
             limit: None,
+            max_limit: MAX_LIMIT,
+            top_files_capacity: None,
+            concurrency_hints: None,
+            shard: None,
+            plugins: Vec::new(),
+            access_log: None,
+            clock: None,
+            fs_provider: None,
+            list_suspicious_timestamps: false,
+            list_redundant_files: false,
+            recency_limit: None,
+            extension_top_limit: None,
+            size_histograms: Vec::new(),
+            heatmap_depth: heatmap::DEFAULT_HEATMAP_DEPTH,
+            top_dirs_depth: DEFAULT_TOP_DIRS_DEPTH,
+            top_dirs_limit: None,
+            dedup: None,
+            compute_hash: None,
+            hash_max_bytes_per_sec: None,
+            compression: None,
+            detect_mime: false,
+            mime_min_size: mime::DEFAULT_MIN_SIZE,
+            metadata_retry: RetryPolicy::default(),
+            fail_fast: false,
+            follow_symlinks: SymlinkPolicy::Never,
+            same_filesystem: false,
+            deduplicate_hardlinks: false,
+            allocated_sizes: false,
+            collect_owners: false,
+            collect_entries: true,
+            incremental: false,
+            keep_awake: false,
+            opaque_packages: false,
+            memory_budget: None,
+            max_io_ops_per_sec: None,
+            nice_mode: false,
+            dir_mtimes: None,
+            entry_sink: None,
+            progress: None,
+            cancel: None,
         }
     }
 }
 
 /// 扫描进度信息
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ScanProgress {
     /// 任务状态
     pub state: ScanState,
+    /// 当前阶段、阶段序号与阶段内进度
+    pub phase: PhaseProgress,
     /// 已扫描文件数
     pub scanned_files: u64,
+    /// 已遍历目录数
+    pub scanned_dirs: u64,
     /// 已遍历字节数
     pub scanned_bytes: u64,
-    /// 进度百分比 (0.0 - 1.0)
+    /// 整体进度百分比 (0.0 - 1.0)，由阶段序号与阶段内进度折算
     pub progress: f64,
-    /// 预计剩余时间（秒）
+    /// 预计剩余时间（秒）：遍历阶段按估算总量与已遍历字节的速率推算，无从估算时为 None
     pub eta_seconds: Option<u64>,
+    /// 遍历总字节数的估算值，见 [`estimate`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_bytes_estimate: Option<u64>,
+    /// 遍历阶段开始以来的平均元数据操作速率（次/秒），见 [`pacing`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_ops_per_sec: Option<f64>,
+    /// 生效的元数据操作速率上限（次/秒），未限速时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_ops_limit: Option<u64>,
 }
 
-/// 扫描任务状态
-#[derive(Debug, Clone, PartialEq, Serialize)]
+/// 扫描任务状态（作为扫描结果的状态时默认为已完成）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum ScanState {
     /// 任务排队中
     Queued,
     /// 扫描进行中
     Running,
     /// 扫描已完成
+    #[default]
     Completed,
     /// 任务已取消
     Canceled,
@@ -73,7 +517,8 @@ pub enum ScanState {
 }
 
 /// 扫描结果摘要
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ScanSummary {
     /// 扫描根路径
     pub root_path: PathBuf,
@@ -83,21 +528,187 @@ pub struct ScanSummary {
     pub total_dirs: u64,
     /// 总大小（字节）
     pub total_size_bytes: u64,
+    /// 实际分配的磁盘空间总量（字节），仅在请求 `allocated_sizes` 时统计
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_allocated_bytes: Option<u64>,
+    /// 根目录所在文件系统的容量与剩余空间（扫描结束时查询），见 [`disk_space`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_space: Option<DiskSpace>,
     /// 扫描耗时（秒）
     pub elapsed_seconds: f64,
+    /// 本次扫描实际采用的 Top N 数量，合并结果时据此截断 top_files
+    #[serde(default)]
+    pub top_limit: usize,
+    /// 请求 `top_files_capacity` 大于 `top_limit` 时 `top_files` 实际保留的条目数上限，见 [`ScanSummary::top_files_bound`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_files_capacity: Option<usize>,
+    /// 陈旧文件的排序依据与保留数量，合并结果时据此重新排序、截断 stale_files
+    #[serde(default)]
+    pub stale_sort: StaleSort,
+    #[serde(default)]
+    pub stale_limit: Option<usize>,
+    /// 零字节文件数与空目录树数（不受列表上限影响），合并结果时按 `empty_limit` 截断两个列表
+    #[serde(default)]
+    pub empty_file_count: u64,
+    #[serde(default)]
+    pub empty_dir_count: u64,
+    #[serde(default)]
+    pub empty_limit: Option<usize>,
+    /// 遍历错误总数（`scan_errors` 最多保留 [`scan_error::MAX_RECORDED`] 条）
+    #[serde(default)]
+    pub scan_error_count: u64,
+    /// 本次扫描实际采用的最小文件尺寸（字节）
+    #[serde(default)]
+    pub min_size: Option<u64>,
+    /// `min_size` 是否为依据文件系统容量自动选择的默认值
+    #[serde(default)]
+    pub min_size_is_default: bool,
+    /// 是否因缺少 macOS 完全磁盘访问权限而有受保护目录未被统计（见 [`full_disk_access`]）
+    #[serde(default)]
+    pub full_disk_access_missing: bool,
+    /// 修改时间可疑（早于 1980 年或晚于当前时间）的文件数；年龄分析中已将其校正到合理范围
+    #[serde(default)]
+    pub suspicious_timestamps: u64,
+    /// 冗余附属文件数，见 [`redundant`]
+    #[serde(default)]
+    pub redundant_file_count: u64,
+    /// 冗余附属文件的总大小（字节），即删除后可释放的空间
+    #[serde(default)]
+    pub redundant_bytes: u64,
+    /// 云端占位文件数，见 [`placeholder`]
+    #[serde(default)]
+    pub placeholder_files: u64,
+    /// 云端占位文件报告的总大小（字节）：已计入 `total_size_bytes`，但并不占用本地空间
+    #[serde(default)]
+    pub placeholder_bytes: u64,
+    /// 可压缩的候选文件数，见 [`compression`]（`compression_candidates` 最多保留 Top N 个）
+    #[serde(default)]
+    pub compression_candidate_count: u64,
+    /// 全部可压缩候选文件压缩后估计可节省的总字节数
+    #[serde(default)]
+    pub compression_savings_bytes: u64,
+    /// 最旧与最新文件列表的条目数，合并结果时据此截断 `oldest_files` 与 `newest_files`
+    #[serde(default)]
+    pub recency_limit: Option<usize>,
+    /// 每个扩展名的 Top N 条目数，合并结果时据此截断 `by_extension[].top_files`
+    #[serde(default)]
+    pub extension_top_limit: Option<usize>,
+    /// 是否为仅汇总扫描（请求关闭了 `collect_entries`，结果中没有文件条目列表）
+    #[serde(default)]
+    pub summary_only: bool,
+    /// Top N 文件条目 `digest` 所用的摘要算法（请求 `compute_hash` 时记录）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest_algo: Option<HashAlgo>,
+    /// 结果状态：`Completed`，或扫描中途被取消时为 `Canceled`（此时各项统计只覆盖已遍历部分）
+    #[serde(default)]
+    pub state: ScanState,
+    /// 分片扫描时本结果对应的分片（即请求的 `shard`），供 [`ShardManifest::merge`] 核对
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard: Option<ShardAssignment>,
+}
+
+impl ScanSummary {
+    /// `top_files` 最多保留的条目数：`top_limit` 与 `top_files_capacity` 中的较大者
+    pub fn top_files_bound(&self) -> usize {
+        self.top_files_capacity.unwrap_or(0).max(self.top_limit)
+    }
 }
 
 /// 文件条目信息
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct FileEntry {
     /// 文件路径
     pub path: PathBuf,
     /// 文件大小（字节）
     pub size_bytes: u64,
-    /// 最后修改时间
+    /// 实际分配的磁盘空间（字节），仅在请求 `allocated_sizes` 时记录；稀疏或压缩文件可能远小于 `size_bytes`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocated_bytes: Option<u64>,
+    /// 最后修改时间（JSON 中默认为 Unix 纪元毫秒数，见 [`serde_time`]）
+    #[serde(default, with = "serde_time::option")]
     pub last_modified: Option<SystemTime>,
+    /// 最后访问时间（atime），文件系统不提供时缺省
+    #[serde(default, with = "serde_time::option", skip_serializing_if = "Option::is_none")]
+    pub last_accessed: Option<SystemTime>,
+    /// 创建时间，文件系统不记录时缺省
+    #[serde(default, with = "serde_time::option", skip_serializing_if = "Option::is_none")]
+    pub created: Option<SystemTime>,
     /// 文件扩展名（不含点）
     pub extension: Option<String>,
+    /// 冷度评分（提供访问日志时针对陈旧文件计算，越接近 1 越冷）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coldness: Option<f64>,
+    /// 距陈旧判断参照时间的空闲秒数（仅陈旧文件）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_seconds: Option<u64>,
+    /// 占所在目录的比例（0~1）
+    #[serde(default)]
+    pub share_of_parent: Option<f64>,
+    /// 占扫描总量的比例（0~1）
+    #[serde(default)]
+    pub share_of_total: Option<f64>,
+    /// 系统保留文件类型（如 Windows 页面文件、休眠文件），见 [`reserved`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reserved: Option<ReservedKind>,
+    /// 冗余附属文件类型（备份副本、编号副本、锁文件等），见 [`redundant`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redundant: Option<RedundantKind>,
+    /// 属主与权限，仅在请求 `collect_owners` 时记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<Ownership>,
+    /// 内容摘要（十六进制），仅 Top N 文件在请求 `compute_hash` 时计算，算法见 `summary.digest_algo`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// 按文件头识别的 MIME 类型，仅在请求 `detect_mime` 时记录，见 [`mime`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime: Option<String>,
+    /// 符号链接（Windows 上含 NTFS 联接点）指向的目标，原样读取，可能为相对路径，见 [`symlink`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<PathBuf>,
+    /// 是否为仅在线的云端占位文件（OneDrive、iCloud 等）：大小为云端文件的大小，本地几乎不占空间，
+    /// 不参与重复检测与内容摘要，见 [`placeholder`]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_placeholder: bool,
+    /// 相对根目录的深度（根目录的直接文件为 1），出现在结果列表中时填充
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<usize>,
+    /// 所在目录在 `ScanResult::directories` 中的下标（所在目录不在结果中时缺省）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_index: Option<usize>,
+}
+
+impl FileEntry {
+    /// 创建文件条目，扩展名由路径推导（小写、不含点）
+    pub fn new<P: Into<PathBuf>>(path: P, size_bytes: u64) -> Self {
+        let path = path.into();
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|s| s.to_lowercase());
+        Self {
+            path,
+            size_bytes,
+            allocated_bytes: None,
+            last_modified: None,
+            last_accessed: None,
+            created: None,
+            extension,
+            coldness: None,
+            idle_seconds: None,
+            share_of_parent: None,
+            share_of_total: None,
+            reserved: None,
+            redundant: None,
+            owner: None,
+            digest: None,
+            mime: None,
+            link_target: None,
+            is_placeholder: false,
+            depth: None,
+            parent_index: None,
+        }
+    }
 }
 
 impl Ord for FileEntry {
@@ -125,7 +736,8 @@ impl PartialEq for FileEntry {
 }
 
 /// 文件类型统计
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ExtensionStat {
     /// 文件扩展名（不含点）
     pub extension: String,
@@ -133,10 +745,14 @@ pub struct ExtensionStat {
     pub file_count: u64,
     /// 总大小（字节）
     pub total_size_bytes: u64,
+    /// 该扩展名中最大的文件（请求 `extension_top_limit` 时收集，按大小降序），见 [`extension_top`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top_files: Vec<FileEntry>,
 }
 
 /// 扫描完整结果
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ScanResult {
     /// 扫描摘要
     pub summary: ScanSummary,
@@ -144,97 +760,796 @@ pub struct ScanResult {
     pub top_files: Vec<FileEntry>,
     /// 按扩展名统计
     pub by_extension: Vec<ExtensionStat>,
+    /// 按文件类别（图片、视频、文档、代码等）统计，按总大小降序，见 [`category`]
+    #[serde(default)]
+    pub by_category: Vec<CategoryStat>,
+    /// 按属主用户统计（请求 `collect_owners` 时收集，按总大小降序）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_owner: Vec<OwnerStat>,
     /// 陈旧文件列表（超过阈值未访问/修改）
     pub stale_files: Vec<FileEntry>,
+    /// 零字节文件（按路径排序，不受 `min_size` 影响），见 [`empty`]
+    #[serde(default)]
+    pub empty_files: Vec<PathBuf>,
+    /// 只含空目录的目录树的顶点（按路径排序，不含根目录）
+    #[serde(default)]
+    pub empty_dirs: Vec<EmptyDir>,
+    /// 遍历中无法读取的目录、目录条目与元数据（按路径排序），见 [`scan_error`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scan_errors: Vec<TraversalError>,
+    /// 扫描根目录及上述文件所在目录链的大小统计（按大小降序）
+    #[serde(default)]
+    pub directories: Vec<DirStat>,
+    /// 根目录各直接子目录及根目录直接文件的汇总（按大小降序），见 [`top_level`]
+    #[serde(default)]
+    pub top_level: Vec<TopLevelStat>,
+    /// 全部文件按最后修改时间划分的年龄分布（各段文件数、总大小与占比），见 [`age_histogram`]
+    #[serde(default)]
+    pub age_histogram: Vec<AgeBucket>,
+    /// 请求的各选择器的文件大小分布（按请求顺序），见 [`size_histogram`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub size_histograms: Vec<SizeHistogram>,
+    /// 按递归大小排名的目录（`top_dirs_depth` 层以内，不含根目录，按大小降序）
+    #[serde(default)]
+    pub top_dirs: Vec<DirStat>,
+    /// 按直接文件总大小（不含子目录）排名的目录（含根目录，不限深度，按大小降序，条目数与
+    /// `top_dirs` 相同），见 [`density`]
+    #[serde(default)]
+    pub top_dirs_shallow: Vec<DirDensity>,
+    /// 按直接文件的重量密度排名的目录（少量巨型文件 / 海量小文件）
+    #[serde(default)]
+    pub density: DensityRanking,
+    /// 根目录及其下 `heatmap_depth` 层以内各目录的文件年龄构成（按深度、大小排序）
+    #[serde(default)]
+    pub heatmap: Vec<DirAge>,
+    /// 重复文件组（启用重复检测时，按可释放字节数降序）
+    #[serde(default)]
+    pub duplicates: Vec<DuplicateGroup>,
+    /// 系统保留的文件与目录及其实际大小（按大小降序），已计入总量
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub system_reserved: Vec<ReservedEntry>,
+    /// 按类别估算的可释放空间（废纸篓、缓存、`node_modules` 等），见 [`reclaimable`]
+    #[serde(default, skip_serializing_if = "ReclaimableReport::is_empty")]
+    pub reclaimable_report: ReclaimableReport,
+    /// 大而旧、估计可压缩的文件（请求 `compression` 时收集，按估计可节省的字节数降序），见 [`compression`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compression_candidates: Vec<CompressionCandidate>,
+    /// 修改时间可疑且偏差最大的文件（请求 `list_suspicious_timestamps` 时收集，按偏差降序）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suspicious_files: Vec<FileEntry>,
+    /// 冗余附属文件按类型的汇总（按总大小降序），见 [`redundant`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redundant: Vec<RedundantStat>,
+    /// 最大的冗余附属文件（请求 `list_redundant_files` 时收集，按大小降序）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redundant_files: Vec<FileEntry>,
+    /// 修改时间最早的文件（请求 `recency_limit` 时收集，按修改时间升序），见 [`recency`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub oldest_files: Vec<FileEntry>,
+    /// 修改时间最晚的文件（请求 `recency_limit` 时收集，按修改时间降序）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub newest_files: Vec<FileEntry>,
+    /// 多根目录扫描时各根目录自身的摘要（按请求顺序，不含被其他根目录覆盖的根目录），`summary` 为合并后的汇总
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub root_summaries: Vec<ScanSummary>,
+    /// 扫描调度诊断信息
+    #[serde(default)]
+    pub diagnostics: ScanDiagnostics,
+    /// 本次扫描采集到的并发提示，可保存后供下次扫描同一根目录使用
+    #[serde(skip)]
+    pub concurrency_hints: Option<ConcurrencyHints>,
+    /// 分析器插件输出的结果段（插件名 -> 结果）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugin_sections: BTreeMap<String, serde_json::Value>,
 }
 
-/// 扫描过程中用于收集统计信息的内部结构
-struct ScanCollector {
-    /// 总文件数（经过 min_size 过滤后）
-    total_files: u64,
-    /// 总目录数
-    total_dirs: u64,
-    /// 总大小（字节，经过 min_size 过滤后）
-    total_size_bytes: u64,
-    /// 按扩展名统计的映射表
-    by_extension_map: HashMap<String, ExtensionStat>,
-    /// Top N 大文件列表（按大小降序），内部使用向量维护，上限为 TOP_FILES_LIMIT
-    top_files: Vec<FileEntry>,
-    /// 陈旧文件列表
-    stale_files: Vec<FileEntry>,
-    /// 当前时间，用于陈旧文件判断
-    now: SystemTime,
+impl ScanResult {
+    /// `top_files` 中从 `offset` 开始的至多 `limit` 个条目（按大小降序）；超出范围时为空
+    ///
+    /// 请求 `top_files_capacity` 后 `top_files` 保留的条目可多于 Top N，调用方据此分页浏览，
+    /// `top_files.len()` 即可浏览的总数。
+    pub fn page_top_files(&self, offset: usize, limit: usize) -> &[FileEntry] {
+        let rest = self.top_files.get(offset..).unwrap_or_default();
+        &rest[..limit.min(rest.len())]
+    }
+}
+
+/// 扫描调度诊断信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ScanDiagnostics {
+    /// 本次扫描是否使用了历史并发提示
+    pub hints_applied: bool,
+    /// 依据提示被拆分处理的大目录数量
+    pub hinted_dirs: u64,
+    /// 大目录拆分出的文件批次数量
+    pub split_batches: u64,
+    /// 本次扫描记录的目录提示条目数量
+    pub recorded_hints: u64,
+    /// 合并结果时因被其他根目录覆盖而跳过的重叠根目录
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overlapping_roots: Vec<PathBuf>,
+    /// 请求的 `limit` 超过上限被截断时记录原始值（实际采用值见 `summary.top_limit`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested_limit: Option<usize>,
+    /// 读取元数据时因瞬时错误进行的重试次数
+    #[serde(default)]
+    pub metadata_retries: u64,
+    /// 因指向已统计过的目录（符号链接环路、多条链接或 bind mount 指向同一目录）而跳过的次数
+    #[serde(default)]
+    pub revisited_dirs: u64,
+    /// 被跳过的重复目录及其先被统计的路径（按路径排序），见 [`symlink`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliased_dirs: Vec<DirAlias>,
+    /// 硬链接去重时跳过的重复链接数
+    #[serde(default)]
+    pub skipped_hardlinks: u64,
+    /// 硬链接去重避免重复计入的字节数
+    #[serde(default)]
+    pub skipped_hardlink_bytes: u64,
+    /// 因超出 `max_depth` 而未进入的目录数
+    #[serde(default)]
+    pub depth_limited_dirs: u64,
+    /// 单文件系统模式下因位于其他文件系统而跳过的挂载点
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_mount_points: Vec<PathBuf>,
+    /// 因权限不足无法读取而被跳过的目录（其中的内容未计入统计，可提权后补扫并用 [`graft`] 合并）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied_dirs: Vec<PathBuf>,
+    /// 遍历任务中被捕获的 panic（对应路径被跳过，其余部分照常完成）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub worker_panics: Vec<WorkerPanic>,
+    /// 排除与包含规则的匹配次数（未设置规则时为 0）
+    #[serde(default)]
+    pub filter_checks: u64,
+    /// 规则匹配累计耗时（纳秒），除以 `filter_checks` 即每个条目的平均匹配开销
+    #[serde(default)]
+    pub filter_match_nanos: u64,
+    /// 增量扫描中直接使用缓存结果的顶层子树数，见 [`incremental`]
+    #[serde(default)]
+    pub cached_subtrees: u64,
+    /// 增量扫描中因目录变化或缓存缺失而重新遍历的顶层子树数
+    #[serde(default)]
+    pub rescanned_subtrees: u64,
+    /// 请求 `keep_awake` 时阻止系统睡眠的申请状态，见 [`keep_awake`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_awake: Option<KeepAwakeStatus>,
+    /// 设置 `memory_budget` 时因在途任务名额用尽而留在当前任务中深度优先遍历的目录数
+    #[serde(default)]
+    pub budget_deferred_dirs: u64,
+}
+
+/// 遍历任务 panic 记录
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct WorkerPanic {
+    /// 发生 panic 时正在处理的文件或目录
+    pub path: PathBuf,
+    /// panic 信息
+    pub message: String,
+}
+
+/// 结果列表中的文件条目及其所在目录节点（用于计算目录大小与占比）
+struct Ranked {
+    entry: FileEntry,
+    parent: Arc<DirNode>,
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.entry.cmp(&other.entry)
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for Ranked {}
+
+/// 陈旧文件堆中的条目，按 [`access::rank_stale`] 比较
+struct StaleRanked {
+    sort: StaleSort,
+    ranked: Ranked,
+}
+
+impl Ord for StaleRanked {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        access::rank_stale(self.sort, &self.ranked.entry, &other.ranked.entry)
+    }
+}
+
+impl PartialOrd for StaleRanked {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for StaleRanked {}
+
+impl PartialEq for StaleRanked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.entry == other.entry
+    }
+}
+
+/// 获取互斥锁并忽略中毒状态
+///
+/// 受保护的数据都是逐条累加的统计，持锁线程 panic 时最多丢失正在写入的那一条，
+/// 其余数据仍然有效；恢复而非 unwrap 可避免一次 panic 让后续所有访问连锁失败。
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 扫描取消令牌：克隆共享同一状态，任意一方调用 [`cancel`](Self::cancel) 后遍历尽快停止，
+/// 已统计的部分作为状态为 `Canceled` 的结果返回
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// 创建未取消的令牌
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 请求取消
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// 是否已请求取消
+    pub fn is_canceled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// 按缓存行对齐的值：所有遍历线程都会写入的计数器各占一个缓存行，避免伪共享
+#[derive(Debug, Default)]
+#[repr(align(64))]
+struct CachePadded<T>(T);
+
+impl<T> std::ops::Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// 扫描实时进度：遍历线程以原子操作累加，其他线程可随时无锁读取
+///
+/// 文件数与字节数按目录批量累加（每个目录任务结束时写入一次），因此读取到的是
+/// 已处理完成的目录的统计。
+#[derive(Debug, Default)]
+pub struct LiveProgress {
+    files: CachePadded<AtomicU64>,
+    dirs: CachePadded<AtomicU64>,
+    bytes: CachePadded<AtomicU64>,
+    phase: AtomicU8,
+    /// 本次扫描登记的阶段（每个阶段占一位的位掩码）
+    planned: AtomicU8,
+    hashed_bytes: AtomicU64,
+    hash_total_bytes: AtomicU64,
+    /// 遍历总字节数的估算值（0 表示未知）
+    total_bytes_estimate: AtomicU64,
+    /// 开始按估算值计算进度的时刻，用于推算 ETA
+    estimate_started: Mutex<Option<Instant>>,
+    /// 遍历读取的元数据操作数（读取目录与文件 stat）
+    io_ops: AtomicU64,
+    /// 生效的元数据操作速率上限（0 表示不限速）
+    io_ops_limit: AtomicU64,
+    /// 开始统计元数据操作的时刻，用于计算实际速率
+    io_started: Mutex<Option<Instant>>,
+    /// 多根目录扫描时汇总各根目录进度的上级计数，文件、目录与字节数同时累加到其中
+    parent: Option<Arc<LiveProgress>>,
+}
+
+impl LiveProgress {
+    /// 创建共享的进度计数，通过 [`ScanRequest::progress`] 传入扫描
+    pub fn new() -> Arc<Self> {
+        Arc::default()
+    }
+
+    /// 创建把文件、目录与字节数同时累加到 `parent` 的进度计数（多根目录扫描中每个根目录一份）
+    pub(crate) fn child(parent: &Arc<Self>) -> Arc<Self> {
+        Arc::new(Self { parent: Some(parent.clone()), ..Default::default() })
+    }
+
+    fn add_files(&self, files: u64, bytes: u64) {
+        self.files.fetch_add(files, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        if let Some(parent) = &self.parent {
+            parent.add_files(files, bytes);
+        }
+    }
+
+    fn add_dir(&self) {
+        self.dirs.fetch_add(1, Ordering::Relaxed);
+        if let Some(parent) = &self.parent {
+            parent.add_dir();
+        }
+    }
+
+    /// 已计入统计的文件数
+    pub fn scanned_files(&self) -> u64 {
+        self.files.load(Ordering::Relaxed)
+    }
+
+    /// 已遍历的目录数
+    pub fn scanned_dirs(&self) -> u64 {
+        self.dirs.load(Ordering::Relaxed)
+    }
+
+    /// 已计入统计的字节数
+    pub fn scanned_bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// 当前所处阶段
+    pub fn phase(&self) -> ScanPhase {
+        ScanPhase::from_index(self.phase.load(Ordering::Relaxed))
+    }
+
+    /// 登记本次扫描将会经历的阶段（计入阶段总数），调用方需在扫描前登记自己负责的阶段，
+    /// 例如扫描结束后保存缓存的 [`ScanPhase::Persisting`]
+    pub fn plan_phase(&self, phase: ScanPhase) {
+        self.planned.fetch_or(phase.bit(), Ordering::Relaxed);
+    }
+
+    /// 进入新阶段（未登记的阶段会自动登记）
+    pub fn begin_phase(&self, phase: ScanPhase) {
+        self.plan_phase(phase);
+        self.phase.store(phase as u8, Ordering::Relaxed);
+    }
+
+    /// 当前阶段的位置与阶段内进度：遍历阶段按估算总量计算（无估算时未知），哈希阶段的总量是已知的
+    pub fn phase_progress(&self) -> PhaseProgress {
+        let phase = self.phase();
+        let progress = match phase {
+            ScanPhase::Enumerating => self.estimated_progress(),
+            ScanPhase::Hashing => Some(share::share(self.hashed_bytes(), self.hash_total_bytes())),
+            _ => None,
+        };
+        PhaseProgress::new(phase, self.planned.load(Ordering::Relaxed), progress)
+    }
+
+    /// 哈希阶段已读取的字节数
+    pub fn hashed_bytes(&self) -> u64 {
+        self.hashed_bytes.load(Ordering::Relaxed)
+    }
+
+    /// 哈希阶段需要读取的总字节数（进入哈希阶段后才确定）
+    pub fn hash_total_bytes(&self) -> u64 {
+        self.hash_total_bytes.load(Ordering::Relaxed)
+    }
+
+    /// 遍历总字节数的估算值（扫描开始时确定），见 [`estimate`]
+    pub fn total_bytes_estimate(&self) -> Option<u64> {
+        Some(self.total_bytes_estimate.load(Ordering::Relaxed)).filter(|&bytes| bytes > 0)
+    }
+
+    /// 记录本次遍历的估算总量并开始计时；`None` 清除估算
+    pub fn set_total_bytes_estimate(&self, bytes: Option<u64>) {
+        self.total_bytes_estimate.store(bytes.unwrap_or(0), Ordering::Relaxed);
+        *lock(&self.estimate_started) = bytes.map(|_| Instant::now());
+    }
+
+    /// 遍历读取的元数据操作数
+    pub fn io_ops(&self) -> u64 {
+        self.io_ops.load(Ordering::Relaxed)
+    }
+
+    /// 生效的元数据操作速率上限（次/秒），未限速时为 None
+    pub fn io_ops_limit(&self) -> Option<u64> {
+        Some(self.io_ops_limit.load(Ordering::Relaxed)).filter(|&n| n > 0)
+    }
+
+    /// 开始统计本次遍历的元数据操作并记录生效的速率上限，扫描开始遍历时调用
+    pub fn begin_io_pacing(&self, limit: Option<u64>) {
+        self.io_ops.store(0, Ordering::Relaxed);
+        self.io_ops_limit.store(limit.unwrap_or(0), Ordering::Relaxed);
+        *lock(&self.io_started) = Some(Instant::now());
+    }
+
+    pub(crate) fn add_io_ops(&self, ops: u64) {
+        self.io_ops.fetch_add(ops, Ordering::Relaxed);
+    }
+
+    /// 遍历阶段的平均元数据操作速率
+    fn io_ops_per_sec(&self) -> Option<f64> {
+        if self.phase() != ScanPhase::Enumerating {
+            return None;
+        }
+        let elapsed = (*lock(&self.io_started))?.elapsed().as_secs_f64();
+        (elapsed > 0.0).then(|| self.io_ops() as f64 / elapsed)
+    }
+
+    /// 按估算总量计算的遍历进度（封顶 [`estimate::MAX_ESTIMATED_PROGRESS`]）
+    fn estimated_progress(&self) -> Option<f64> {
+        let total = self.total_bytes_estimate()?;
+        Some(share::share(self.scanned_bytes(), total).min(estimate::MAX_ESTIMATED_PROGRESS))
+    }
+
+    /// 遍历阶段的预计剩余时间：按开始以来的平均速率推算剩余的估算字节
+    fn eta_seconds(&self) -> Option<u64> {
+        if self.phase() != ScanPhase::Enumerating {
+            return None;
+        }
+        let total = self.total_bytes_estimate()?;
+        let scanned = self.scanned_bytes();
+        let elapsed = (*lock(&self.estimate_started))?.elapsed();
+        if scanned == 0 || scanned >= total {
+            return None;
+        }
+        Some((elapsed.as_secs_f64() * (total - scanned) as f64 / scanned as f64).ceil() as u64)
+    }
+
+    /// 生成进度快照
+    pub fn snapshot(&self, state: ScanState) -> ScanProgress {
+        let phase = self.phase_progress();
+        ScanProgress {
+            state,
+            phase,
+            scanned_files: self.scanned_files(),
+            scanned_dirs: self.scanned_dirs(),
+            scanned_bytes: self.scanned_bytes(),
+            progress: phase.overall(),
+            eta_seconds: self.eta_seconds(),
+            total_bytes_estimate: self.total_bytes_estimate(),
+            io_ops_per_sec: self.io_ops_per_sec(),
+            io_ops_limit: self.io_ops_limit(),
+        }
+    }
+}
+
+/// 单个目录任务（或大目录的一个批次）内累计的统计，任务结束时一次性写入共享计数器，
+/// 避免每个文件都争用全局原子计数与扩展名表的锁
+#[derive(Default)]
+struct DirTally {
+    /// 计入统计的直接文件数
+    files: u64,
+    /// 按年龄段累计的字节数（总和即直接文件总大小）
+    ages: AgeBytes,
+    /// 按年龄分布各段累计的文件数与字节数
+    histogram: age_histogram::Tally,
+    /// 扩展名 -> (文件数, 总大小)
+    extensions: HashMap<String, (u64, u64)>,
+    /// 直接文件实际分配的字节数
+    allocated: u64,
+    /// 属主 uid -> (文件数, 总大小)，仅在记录属主时累计
+    owners: HashMap<u32, (u64, u64)>,
+}
+
+impl DirTally {
+    fn add(&mut self, entry: &FileEntry, age_bucket: usize, histogram_bucket: usize) {
+        self.files += 1;
+        self.ages.add(age_bucket, entry.size_bytes);
+        self.histogram.add(histogram_bucket, entry.size_bytes);
+        self.allocated += entry.allocated_bytes.unwrap_or(0);
+        let ext = entry.extension.as_deref().unwrap_or("no_extension");
+        let slot = match self.extensions.get_mut(ext) {
+            Some(slot) => slot,
+            None => self.extensions.entry(ext.to_string()).or_insert((0, 0)),
+        };
+        slot.0 += 1;
+        slot.1 += entry.size_bytes;
+        if let Some(uid) = entry.owner.as_ref().and_then(|o| o.uid) {
+            let slot = self.owners.entry(uid).or_insert((0, 0));
+            slot.0 += 1;
+            slot.1 += entry.size_bytes;
+        }
+    }
+}
+
+/// 向有界最小堆提交候选：未满时直接加入，已满时只替换排名最低者；`limit` 为 None 时不设上限
+fn offer_bounded<T: Ord>(heap: &mut BinaryHeap<Reverse<T>>, candidate: T, limit: Option<usize>) {
+    match limit {
+        Some(0) => {}
+        Some(limit) if heap.len() >= limit => {
+            if heap.peek().is_some_and(|lowest| candidate > lowest.0) {
+                heap.pop();
+                heap.push(Reverse(candidate));
+            }
+        }
+        _ => heap.push(Reverse(candidate)),
+    }
+}
+
+/// 把 (文件数, 总大小) 计数累加到映射中
+fn add_tallies<K: std::hash::Hash + Eq>(map: &mut HashMap<K, (u64, u64)>, part: impl IntoIterator<Item = (K, (u64, u64))>) {
+    for (key, (count, bytes)) in part {
+        let entry = map.entry(key).or_insert((0, 0));
+        entry.0 += count;
+        entry.1 += bytes;
+    }
 }
 
 /// 用于并行扫描的原子计数器
 struct AtomicCounters {
-    files: AtomicU64,
-    dirs: AtomicU64,
-    size: AtomicU64,
+    /// 文件数、目录数与总大小（可与调用方共享以便实时查询进度）
+    live: Arc<LiveProgress>,
     /// Top N 大文件限制
     limit: usize,
-    /// Top N 大文件堆（最小堆，使用 Reverse 包装 FileEntry 实现）
-    top_files: Arc<Mutex<BinaryHeap<Reverse<FileEntry>>>>,
-    /// 扩展名统计映射：扩展名 -> (文件数, 总大小)
-    extensions: Arc<Mutex<HashMap<String, (u64, u64)>>>,
-    /// 陈旧文件列表
-    stale_files: Arc<Mutex<Vec<FileEntry>>>,
+    /// 每个工作线程的 Top N 大文件堆（最小堆，使用 Reverse 包装实现），遍历结束后合并
+    top_files: PerWorker<BinaryHeap<Reverse<Ranked>>>,
+    /// Top N 大文件堆保留的条目数（不少于 `limit`，见 [`ScanRequest::top_files_capacity`]）
+    top_capacity: usize,
+    /// 任一工作线程的堆已满时其中最小文件大小的最大值：只增不减，且不超过合并后第 N 大的文件，
+    /// 不大于它的文件无需访问堆即可跳过
+    top_threshold: AtomicU64,
+    /// 每个工作线程的扩展名统计映射：扩展名 -> (文件数, 总大小)
+    extensions: PerWorker<HashMap<String, (u64, u64)>>,
+    /// 每个工作线程的属主统计映射：uid -> (文件数, 总大小)
+    owners: PerWorker<HashMap<u32, (u64, u64)>>,
+    /// 每个工作线程的陈旧文件堆（最小堆，按 `stale_sort` 排名，设置 `stale_limit` 时有界）
+    stale_files: PerWorker<BinaryHeap<Reverse<StaleRanked>>>,
+    stale_sort: StaleSort,
+    stale_limit: Option<usize>,
+    /// 条目较多的目录 -> 条目数，用于生成下次扫描的并发提示
+    dir_entries: Mutex<HashMap<PathBuf, u64>>,
+    /// 依据提示拆分的目录数
+    hinted_dirs: AtomicU64,
+    /// 拆分出的文件批次数
+    split_batches: AtomicU64,
+    /// 元数据读取的重试次数
+    metadata_retries: AtomicU64,
+    /// 重复进入同一目录而跳过的次数
+    revisited_dirs: AtomicU64,
+    /// 被跳过的重复目录
+    aliased_dirs: Mutex<Vec<DirAlias>>,
+    /// 实际分配的磁盘空间总量
+    allocated_bytes: AtomicU64,
+    /// 硬链接去重跳过的链接数与字节数
+    skipped_hardlinks: AtomicU64,
+    skipped_hardlink_bytes: AtomicU64,
+    /// 超出最大深度未进入的目录数
+    depth_limited_dirs: AtomicU64,
+    /// 单文件系统模式下跳过的挂载点
+    skipped_mount_points: Mutex<Vec<PathBuf>>,
+    /// 云端占位文件数与报告的总大小
+    placeholder_files: AtomicU64,
+    placeholder_bytes: AtomicU64,
+    /// 修改时间可疑的文件数，以及请求列出时收集的可疑文件
+    suspicious_timestamps: AtomicU64,
+    suspicious_files: Mutex<Vec<FileEntry>>,
+    /// 权限不足无法读取的目录
+    denied_dirs: Mutex<Vec<PathBuf>>,
+    /// 是否遇到缺少完全磁盘访问权限导致的拒绝
+    full_disk_access_missing: AtomicBool,
+    /// 被捕获的遍历任务 panic
+    worker_panics: Mutex<Vec<WorkerPanic>>,
+    /// 目录密度排名
+    density: DensityTracker,
+    /// 全部文件的年龄分布
+    age_histogram: age_histogram::Counters,
+    size_histograms: size_histogram::Counters,
+    /// 空文件与空目录
+    empty: empty::Counters,
+    /// 遍历错误
+    errors: scan_error::Collector,
+    /// 参与年龄热力图与大目录排名统计的目录节点及其深度
+    tracked_dirs: Mutex<Vec<(usize, Arc<DirNode>)>>,
+    /// 根目录的直接子目录节点（顶层速览）
+    top_level: Mutex<Vec<Arc<DirNode>>>,
+    /// 重复检测候选文件（路径, 大小），仅在启用重复检测时收集
+    dedup_candidates: Mutex<Vec<(PathBuf, u64)>>,
+    /// 系统保留文件，以及系统保留目录的节点（大小在遍历结束后读取）
+    reserved_files: Mutex<Vec<ReservedEntry>>,
+    redundant: redundant::Counters,
+    recency: recency::Counters,
+    extension_top: extension_top::Counters,
+    reserved_dirs: Mutex<Vec<(ReservedKind, Arc<DirNode>)>>,
+    /// 可回收位置的节点（大小在遍历结束后读取）
+    reclaimable: reclaimable::Locations,
+    /// 可压缩分析的候选文件（估算在遍历结束后进行）
+    compression: compression::Counters,
 }
 
 impl AtomicCounters {
-    fn new(limit: usize) -> Self {
+    fn new(limit: usize, workers: usize, live: Arc<LiveProgress>) -> Self {
         Self {
-            files: AtomicU64::new(0),
-            dirs: AtomicU64::new(0),
-            size: AtomicU64::new(0),
+            live,
             limit,
-            top_files: Arc::new(Mutex::new(BinaryHeap::with_capacity(limit))),
-            extensions: Arc::new(Mutex::new(HashMap::new())),
-            stale_files: Arc::new(Mutex::new(Vec::new())),
+            top_files: PerWorker::new(workers),
+            top_capacity: limit,
+            top_threshold: AtomicU64::new(0),
+            extensions: PerWorker::new(workers),
+            owners: PerWorker::new(workers),
+            stale_files: PerWorker::new(workers),
+            stale_sort: StaleSort::default(),
+            stale_limit: None,
+            dir_entries: Mutex::new(HashMap::new()),
+            hinted_dirs: AtomicU64::new(0),
+            split_batches: AtomicU64::new(0),
+            metadata_retries: AtomicU64::new(0),
+            revisited_dirs: AtomicU64::new(0),
+            aliased_dirs: Mutex::new(Vec::new()),
+            allocated_bytes: AtomicU64::new(0),
+            skipped_hardlinks: AtomicU64::new(0),
+            skipped_hardlink_bytes: AtomicU64::new(0),
+            depth_limited_dirs: AtomicU64::new(0),
+            skipped_mount_points: Mutex::new(Vec::new()),
+            placeholder_files: AtomicU64::new(0),
+            placeholder_bytes: AtomicU64::new(0),
+            suspicious_timestamps: AtomicU64::new(0),
+            suspicious_files: Mutex::new(Vec::new()),
+            denied_dirs: Mutex::new(Vec::new()),
+            full_disk_access_missing: AtomicBool::new(false),
+            worker_panics: Mutex::new(Vec::new()),
+            density: DensityTracker::new(limit),
+            age_histogram: age_histogram::Counters::default(),
+            size_histograms: size_histogram::Counters::default(),
+            empty: empty::Counters::default(),
+            errors: scan_error::Collector::default(),
+            tracked_dirs: Mutex::new(Vec::new()),
+            top_level: Mutex::new(Vec::new()),
+            dedup_candidates: Mutex::new(Vec::new()),
+            reserved_files: Mutex::new(Vec::new()),
+            redundant: redundant::Counters::default(),
+            recency: recency::Counters::default(),
+            extension_top: extension_top::Counters::default(),
+            reserved_dirs: Mutex::new(Vec::new()),
+            reclaimable: reclaimable::Locations::default(),
+            compression: compression::Counters::default(),
         }
     }
-    
-    fn add_file_with_extension(&self, extension: Option<String>, size: u64) {
-        let ext = extension.unwrap_or_else(|| "no_extension".to_string());
-        let mut map = self.extensions.lock().unwrap();
-        let entry = map.entry(ext).or_insert((0, 0));
-        entry.0 += 1;
-        entry.1 += size;
+
+    /// 记录可疑文件；超出两倍 Top N 时按偏差截断，避免问题压缩包中的大量文件占用内存
+    fn add_suspicious_file(&self, entry: FileEntry, now: SystemTime) {
+        let mut files = lock(&self.suspicious_files);
+        files.push(entry);
+        if files.len() >= self.limit.max(1) * 2 {
+            timestamp::sort_by_deviation(&mut files, now);
+            files.truncate(self.limit);
+        }
     }
 
-    fn add_file_to_top_list(&self, path: PathBuf, size: u64, last_modified: Option<SystemTime>, extension: Option<String>) {
-        // 过滤大小为0的文件
-        if size == 0 {
-            return;
+    fn suspicious_files_to_vec(&self, now: SystemTime) -> Vec<FileEntry> {
+        let mut files = std::mem::take(&mut *lock(&self.suspicious_files));
+        timestamp::sort_by_deviation(&mut files, now);
+        files.truncate(self.limit);
+        files
+    }
+
+    fn reserved_to_vec(&self) -> Vec<ReservedEntry> {
+        let mut entries = std::mem::take(&mut *lock(&self.reserved_files));
+        entries.extend(lock(&self.reserved_dirs).iter().map(|(kind, node)| ReservedEntry {
+            path: node.path().to_path_buf(),
+            kind: *kind,
+            size_bytes: node.size(),
+        }));
+        reserved::sort(&mut entries);
+        entries
+    }
+
+    fn record_dir_entries(&self, dir: &Path, entry_count: u64) {
+        // 只记录条目较多的目录，保持提示文件足够小
+        if entry_count >= hints::RECORD_MIN_ENTRIES {
+            lock(&self.dir_entries).insert(dir.to_path_buf(), entry_count);
         }
-        let entry = FileEntry {
-            path,
-            size_bytes: size,
-            last_modified,
-            extension,
+    }
+
+    fn record_panic(&self, path: &Path, payload: Box<dyn std::any::Any + Send>) {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => payload.downcast_ref::<&str>().map_or_else(|| "unknown panic".to_string(), |s| s.to_string()),
         };
-        let mut heap = self.top_files.lock().unwrap();
-        if heap.len() < self.limit {
-            heap.push(Reverse(entry));
-        } else {
-            // 堆已满，比较新文件与堆顶（当前堆中最小的文件）
-            if let Some(top) = heap.peek() {
-                if entry.size_bytes > top.0.size_bytes {
-                    heap.pop(); // 移除堆顶最小文件
-                    heap.push(Reverse(entry));
-                }
+        let record = WorkerPanic { path: path.to_path_buf(), message };
+        lock(&self.worker_panics).push(record);
+    }
+
+    fn take_hints(&self, root_path: PathBuf) -> ConcurrencyHints {
+        let dir_entries = std::mem::take(&mut *lock(&self.dir_entries));
+        ConcurrencyHints { root_path, dir_entries, scanned_bytes: self.live.scanned_bytes() }
+    }
+
+    fn to_diagnostics(&self, hints_applied: bool) -> ScanDiagnostics {
+        ScanDiagnostics {
+            hints_applied,
+            hinted_dirs: self.hinted_dirs.load(Ordering::Relaxed),
+            split_batches: self.split_batches.load(Ordering::Relaxed),
+            recorded_hints: lock(&self.dir_entries).len() as u64,
+            metadata_retries: self.metadata_retries.load(Ordering::Relaxed),
+            revisited_dirs: self.revisited_dirs.load(Ordering::Relaxed),
+            aliased_dirs: {
+                let mut aliases = std::mem::take(&mut *lock(&self.aliased_dirs));
+                aliases.sort_by(|a, b| a.path.cmp(&b.path));
+                aliases
+            },
+            skipped_hardlinks: self.skipped_hardlinks.load(Ordering::Relaxed),
+            skipped_hardlink_bytes: self.skipped_hardlink_bytes.load(Ordering::Relaxed),
+            depth_limited_dirs: self.depth_limited_dirs.load(Ordering::Relaxed),
+            skipped_mount_points: {
+                let mut dirs = std::mem::take(&mut *lock(&self.skipped_mount_points));
+                dirs.sort();
+                dirs
+            },
+            denied_dirs: {
+                let mut dirs = std::mem::take(&mut *lock(&self.denied_dirs));
+                dirs.sort();
+                dirs
+            },
+            worker_panics: std::mem::take(&mut *lock(&self.worker_panics)),
+            ..Default::default()
+        }
+    }
+    
+    /// 将一个目录任务累计的统计写入共享计数器与目录节点
+    fn flush_tally(&self, tally: &DirTally, node: &DirNode) {
+        node.add(&tally.ages, tally.files);
+        if tally.files == 0 {
+            return;
+        }
+        self.live.add_files(tally.files, tally.ages.total());
+        self.age_histogram.flush(&tally.histogram);
+        if tally.allocated > 0 {
+            self.allocated_bytes.fetch_add(tally.allocated, Ordering::Relaxed);
+        }
+        self.extensions.with(|map| {
+            for (ext, &(count, bytes)) in &tally.extensions {
+                let entry = match map.get_mut(ext) {
+                    Some(entry) => entry,
+                    None => map.entry(ext.clone()).or_insert((0, 0)),
+                };
+                entry.0 += count;
+                entry.1 += bytes;
             }
+        });
+        if !tally.owners.is_empty() {
+            self.owners.with(|owners| add_tallies(owners, tally.owners.iter().map(|(&uid, &slot)| (uid, slot))));
         }
     }
 
+    /// Top N 是否可能接纳该大小的文件（无锁预检，可能误判为“可能”，不会漏掉）
+    fn may_enter_top_list(&self, size: u64) -> bool {
+        self.top_capacity > 0 && size > self.top_threshold.load(Ordering::Relaxed)
+    }
+
+    fn add_file_to_top_list(&self, entry: FileEntry, parent: &Arc<DirNode>) {
+        let limit = self.top_capacity;
+        self.top_files.with(|heap| {
+            offer_bounded(heap, Ranked { entry, parent: parent.clone() }, Some(limit));
+            if heap.len() >= limit {
+                if let Some(top) = heap.peek() {
+                    self.top_threshold.fetch_max(top.0.entry.size_bytes, Ordering::Relaxed);
+                }
+            }
+        });
+    }
+
+    /// 合并各工作线程的 Top N 堆
+    fn merged_top_files(&self) -> MutexGuard<'_, BinaryHeap<Reverse<Ranked>>> {
+        let limit = self.top_capacity;
+        self.top_files.merge(|heap, part| part.into_iter().for_each(|r| offer_bounded(heap, r.0, Some(limit))))
+    }
+
+    /// 合并各工作线程的陈旧文件堆
+    fn merged_stale_files(&self) -> MutexGuard<'_, BinaryHeap<Reverse<StaleRanked>>> {
+        let limit = self.stale_limit;
+        self.stale_files.merge(|heap, part| part.into_iter().for_each(|r| offer_bounded(heap, r.0, limit)))
+    }
+
+    fn owners_to_map(&self) -> HashMap<u32, (u64, u64)> {
+        std::mem::take(&mut *self.owners.merge(add_tallies))
+    }
+
     fn extensions_to_vec(&self) -> Vec<ExtensionStat> {
-        let map = self.extensions.lock().unwrap();
+        let map = self.extensions.merge(add_tallies);
         let mut vec: Vec<ExtensionStat> = map
             .iter()
             .map(|(ext, &(file_count, total_size_bytes))| ExtensionStat {
                 extension: ext.clone(),
                 file_count,
                 total_size_bytes,
+                top_files: Vec::new(),
             })
             .collect();
         // 按总大小降序排序，如果大小相同则按文件数降序
@@ -247,41 +1562,91 @@ impl AtomicCounters {
     }
 
     fn top_files_to_vec(&self) -> Vec<FileEntry> {
-        let heap = self.top_files.lock().unwrap();
+        let heap = self.merged_top_files();
         // 将堆转换为向量，并反转顺序（从大到小）
-        let mut vec: Vec<FileEntry> = heap.iter().map(|rev| rev.0.clone()).collect();
+        let mut vec: Vec<FileEntry> = heap.iter().map(|rev| rev.0.entry.clone()).collect();
         // 由于堆是最小堆，堆顶是最小元素，但iter顺序不确定，需要按大小降序排序
         vec.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| b.path.cmp(&a.path)));
         vec
     }
 
-    fn add_stale_file(&self, path: PathBuf, size: u64, last_modified: Option<SystemTime>, extension: Option<String>) {
-        let entry = FileEntry {
-            path,
-            size_bytes: size,
-            last_modified,
-            extension,
-        };
-        let mut vec = self.stale_files.lock().unwrap();
-        vec.push(entry);
+    /// 与 Top N 大文件相同的最小堆做法：达到 `stale_limit` 后新文件只替换排名最低者
+    fn add_stale_file(&self, entry: FileEntry, parent: &Arc<DirNode>) {
+        let candidate = StaleRanked { sort: self.stale_sort, ranked: Ranked { entry, parent: parent.clone() } };
+        self.stale_files.with(|heap| offer_bounded(heap, candidate, self.stale_limit));
     }
 
     fn stale_files_to_vec(&self) -> Vec<FileEntry> {
-        let vec = self.stale_files.lock().unwrap();
-        vec.clone()
+        let mut vec: Vec<FileEntry> = self.merged_stale_files().iter().map(|r| r.0.ranked.entry.clone()).collect();
+        access::sort_stale(&mut vec, self.stale_sort, None);
+        vec
+    }
+
+    /// 收集根目录以及所有被报告文件所在目录链的大小统计
+    fn directories_to_vec(&self, root: &DirNode) -> Vec<DirStat> {
+        let mut sizes = HashMap::new();
+        root.collect_chain(&mut sizes);
+        // 只列出 Top N 文件所在的目录，为分页多保留的文件不计入
+        let heap = self.merged_top_files();
+        let mut top: Vec<&Ranked> = heap.iter().map(|rev| &rev.0).collect();
+        if top.len() > self.limit {
+            top.sort_by(|a, b| b.entry.size_bytes.cmp(&a.entry.size_bytes).then_with(|| b.entry.path.cmp(&a.entry.path)));
+            top.truncate(self.limit);
+        }
+        for ranked in top {
+            ranked.parent.collect_chain(&mut sizes);
+        }
+        drop(heap);
+        for ranked in self.merged_stale_files().iter() {
+            ranked.0.ranked.parent.collect_chain(&mut sizes);
+        }
+        share::dir_stats(sizes)
+    }
+
+    fn track_dir(&self, depth: usize, node: &Arc<DirNode>) {
+        lock(&self.tracked_dirs).push((depth, node.clone()));
+    }
+
+    fn heatmap_to_vec(&self, max_depth: usize) -> Vec<DirAge> {
+        let rows = lock(&self.tracked_dirs)
+            .iter()
+            .filter(|(depth, _)| *depth <= max_depth)
+            .map(|(depth, node)| DirAge::new(node.path().to_path_buf(), *depth, node.age_bytes()))
+            .collect();
+        heatmap::sorted(rows)
+    }
+
+    fn top_dirs_to_vec(&self, max_depth: usize, limit: usize) -> Vec<DirStat> {
+        let sizes = lock(&self.tracked_dirs)
+            .iter()
+            .filter(|(depth, node)| (1..=max_depth).contains(depth) && node.size() > 0)
+            .map(|(_, node)| (node.path().to_path_buf(), node.size()))
+            .collect();
+        let mut dirs = share::dir_stats(sizes);
+        dirs.truncate(limit);
+        dirs
     }
 
     fn to_summary(&self, root_path: PathBuf, elapsed_seconds: f64) -> ScanSummary {
         ScanSummary {
             root_path,
-            total_files: self.files.load(Ordering::SeqCst),
-            total_dirs: self.dirs.load(Ordering::SeqCst),
-            total_size_bytes: self.size.load(Ordering::SeqCst),
+            total_files: self.live.scanned_files(),
+            total_dirs: self.live.scanned_dirs(),
+            total_size_bytes: self.live.scanned_bytes(),
             elapsed_seconds,
+            top_limit: self.limit,
+            stale_sort: self.stale_sort,
+            stale_limit: self.stale_limit,
+            full_disk_access_missing: self.full_disk_access_missing.load(Ordering::Relaxed),
+            suspicious_timestamps: self.suspicious_timestamps.load(Ordering::Relaxed),
+            placeholder_files: self.placeholder_files.load(Ordering::Relaxed),
+            placeholder_bytes: self.placeholder_bytes.load(Ordering::Relaxed),
+            ..Default::default()
         }
     }
 }
 /// 核心扫描引擎
+#[derive(Default)]
 pub struct Scanner;
 
 impl Scanner {
@@ -290,179 +1655,837 @@ impl Scanner {
         Self
     }
     
+    /// 同步扫描并定期报告进度：扫描在后台线程执行，`on_progress` 在调用线程上每隔 `interval`
+    /// 收到一次进度快照（文件数、目录数、字节数与当前阶段），扫描结束时再收到一次最终快照
+    ///
+    /// 文件数与字节数按目录累加，快照反映的是已处理完成的目录。
+    pub fn scan_with_progress(
+        &self,
+        request: &ScanRequest,
+        interval: Duration,
+        mut on_progress: impl FnMut(&ScanProgress),
+    ) -> Result<ScanResult, ScanError> {
+        let mut handle = self.spawn(request);
+        loop {
+            match handle.wait_timeout(interval) {
+                Some(result) => {
+                    // 取消的扫描同样返回 Ok，最终状态以结果摘要为准
+                    let state = result.as_ref().map_or(ScanState::Failed, |r| r.summary.state.clone());
+                    on_progress(&handle.live().snapshot(state));
+                    return result;
+                }
+                None => on_progress(&handle.live().snapshot(ScanState::Running)),
+            }
+        }
+    }
+
+    /// 在后台线程中扫描并立即返回句柄，通过句柄读取进度、取消与等待结果，见 [`handle`]
+    pub fn spawn(&self, request: &ScanRequest) -> ScanHandle {
+        ScanHandle::start(request)
+    }
+
+    /// 在后台线程中扫描，按发现顺序逐个产出文件条目，见 [`stream`]
+    ///
+    /// 适合逐条处理而不需要完整结果的调用方：后台扫描不保留文件列表，内存占用与文件数无关。
+    pub fn scan_iter(&self, request: &ScanRequest) -> ScanIter {
+        ScanIter::start(request)
+    }
+
     /// 同步扫描指定目录，返回扫描结果
     /// 
     /// 这是一个简化的实现，仅统计总文件数和总大小。
     /// 后续迭代会添加多线程、文件类型分析、Top N 文件等功能。
-    pub fn scan_sync(&self, request: &ScanRequest) -> std::io::Result<ScanResult> {
+    pub fn scan_sync(&self, request: &ScanRequest) -> Result<ScanResult, ScanError> {
+        if request.keep_awake {
+            // 守卫在本次调用返回（含出错与取消）时释放
+            let guard = keep_awake::KeepAwake::acquire();
+            let mut request = request.clone();
+            request.keep_awake = false;
+            let mut result = self.scan_sync(&request)?;
+            result.diagnostics.keep_awake = Some(guard.status().clone());
+            return Ok(result);
+        }
+        if !request.roots.is_empty() {
+            return multi_root::scan(request);
+        }
+        if let Some(cache_path) = request.incremental.then(|| incremental::cache_path_for(&request.root_path)).flatten() {
+            return incremental::scan(request, &cache_path);
+        }
         let start_time = SystemTime::now();
         
-        // 验证根目录存在且可访问
-        if !request.root_path.exists() {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                format!("根目录不存在: {}", request.root_path.display()),
-            ));
+        // 验证根目录存在且为目录
+        match std::fs::metadata(&request.root_path) {
+            Ok(metadata) if !metadata.is_dir() => {
+                return Err(ScanError::RootNotDirectory { path: request.root_path.clone() });
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(ScanError::RootNotFound { path: request.root_path.clone() });
+            }
+            Err(e) => return Err(ScanError::io(&request.root_path, e)),
         }
         
         // 配置 rayon 线程池
-        let threads = request.threads.unwrap_or(0); // 0 表示使用默认值
+        // 0 表示使用默认值；nice_mode 未指定线程数时只用少量线程
+        let threads = request.threads.unwrap_or(if request.nice_mode { pacing::NICE_MODE_THREADS } else { 0 });
         let pool = rayon::ThreadPoolBuilder::new()
             .num_threads(if threads > 0 { threads as usize } else { rayon::current_num_threads() })
             .build()
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            .map_err(|e| ScanError::ThreadPool { message: e.to_string() })?;
         
-        let limit = request.limit.unwrap_or(20);
-        let counters = AtomicCounters::new(limit);
+        let limit = request.effective_limit();
+        let mut counters =
+            AtomicCounters::new(limit, pool.current_num_threads(), request.progress.clone().unwrap_or_default());
+        counters.top_capacity = request.effective_top_files_capacity();
+        counters.stale_sort = request.effective_stale_sort();
+        counters.stale_limit = request.stale_limit;
+        counters.empty.limit = request.empty_limit;
+        counters.empty.keep_entries = request.collect_entries;
+        counters.errors.fail_fast = request.fail_fast;
+        counters.redundant.limit = limit;
+        counters.redundant.keep_entries = request.list_redundant_files && request.collect_entries;
+        counters.recency.limit = request.recency_limit.filter(|_| request.collect_entries);
+        counters.compression.options = request.compression.clone().filter(|_| request.collect_entries);
+        counters.extension_top.limit = request.extension_top_limit.filter(|_| request.collect_entries);
+        counters.size_histograms = size_histogram::Counters::new(&request.size_histograms)
+            .map_err(|e| ScanError::InvalidRequest { message: e.to_string() })?;
+        counters.density.shallow_limit = request.top_dirs_limit.unwrap_or(limit);
+        counters.live.plan_phase(ScanPhase::Aggregating);
+        if request.dedup.is_some() || request.compute_hash.is_some() {
+            counters.live.plan_phase(ScanPhase::Hashing);
+        }
+        counters.live.begin_phase(ScanPhase::Enumerating);
+        let io_ops_limit = request.effective_io_ops_per_sec();
+        counters.live.begin_io_pacing(io_ops_limit);
+        // 分片扫描只覆盖部分子树（如增量扫描的各部分），不按整个根目录估算
+        if request.shard.is_none() {
+            counters.live.set_total_bytes_estimate(estimate::total_bytes(request));
+        }
 
-        // 预编译排除规则（glob 模式）；非法模式将被忽略
-        let exclude_patterns: Vec<Pattern> = request
-            .exclude_patterns
-            .iter()
-            .filter_map(|p| Pattern::new(p).ok())
-            .collect();
+        // 预编译排除与包含规则（glob 与正则），所有遍历任务共享同一份
+        let filter = PathFilter::new(request).map_err(|e| ScanError::InvalidRequest { message: e.to_string() })?;
+
+        // 未指定最小文件尺寸时按文件系统容量选择默认值
+        let default_min_size = match request.min_size {
+            None if request.auto_min_size => min_size::default_for(&request.root_path),
+            _ => None,
+        };
+        let min_size = request.min_size.or(default_min_size).filter(|&n| n > 0);
+
+        // 启动分析器插件子进程
+        let plugins = (!request.plugins.is_empty()).then(|| PluginSet::start(&request.plugins));
+
+        // 分析参照的当前时间在扫描开始时读取一次
+        let now = request.clock.as_ref().map_or(start_time, |clock| clock.now());
+        let ctx = WalkContext {
+            request,
+            counters: &counters,
+            filter: &filter,
+            hints: request.concurrency_hints.as_deref(),
+            min_size,
+            plugins: plugins.as_ref(),
+            access_root: request
+                .access_log
+                .as_ref()
+                .and_then(|_| std::fs::canonicalize(&request.root_path).ok()),
+            now,
+            visited_dirs: VisitedDirs::for_policy(request.follow_symlinks, request.collect_entries),
+            seen_inodes: request.deduplicate_hardlinks.then(SeenInodes::default),
+            root_device: mount::root_device(&request.root_path, request.same_filesystem),
+            budget: TaskBudget::new(request.memory_budget, pool.current_num_threads()),
+            io_pacer: io_ops_limit.map(IoPacer::new),
+            fs: request.fs_provider.as_deref().unwrap_or(&RealFs),
+        };
         
         // 使用线程池执行并行遍历
+        let root_node = DirNode::root(request.root_path.clone());
+        // 根目录本身不作为空目录报告，其下的空目录树以直接子目录为顶点
+        root_node.mark_occupied();
+        counters.track_dir(0, &root_node);
+        if let Some(kind) = reclaimable::classify(&request.root_path) {
+            counters.reclaimable.record(kind, &root_node);
+        }
+        let root_ignores = request
+            .respect_ignore_files
+            .then(|| IgnoreRules::for_root(&request.root_path))
+            .flatten();
         pool.scope(|scope| {
-            Self::parallel_walk_dir(scope, request.root_path.clone(), &counters, request, &exclude_patterns);
+            let root = DirTask { dir: request.root_path.clone(), node: root_node.clone(), depth: 0, ignores: root_ignores };
+            Self::parallel_walk_dir(scope, root, None, &ctx);
         });
         
-        let elapsed = start_time.elapsed().unwrap_or_default();
-        
-        Ok(ScanResult {
-            summary: counters.to_summary(request.root_path.clone(), elapsed.as_secs_f64()),
-            top_files: counters.top_files_to_vec(),
-            by_extension: counters.extensions_to_vec(),
-            stale_files: counters.stale_files_to_vec(),
-        })
-    }
-    
-    fn walk_dir(
-        dir: &Path,
-        total_files: &mut u64,
-        total_dirs: &mut u64,
-        total_size: &mut u64,
-    ) -> std::io::Result<()> {
-        if dir.is_dir() {
-            *total_dirs += 1;
-            for entry in std::fs::read_dir(dir)? {
-                let entry = entry?;
-                let path = entry.path();
-                
-                if path.is_dir() {
-                    Self::walk_dir(&path, total_files, total_dirs, total_size)?;
-                } else {
-                    *total_files += 1;
-                    *total_size += entry.metadata().map(|m| m.len()).unwrap_or(0);
-                }
+        let duplicates = match &request.dedup {
+            Some(options) if !ctx.canceled() => {
+                counters.live.begin_phase(ScanPhase::Hashing);
+                let candidates = std::mem::take(&mut *lock(&counters.dedup_candidates));
+                dedup::find_duplicates(candidates, options, &counters.live, request.cancel.as_ref())?
+            }
+            _ => Vec::new(),
+        };
+        let mut top_files = counters.top_files_to_vec();
+        if let Some(algo) = request.compute_hash.filter(|_| !ctx.canceled()) {
+            counters.live.begin_phase(ScanPhase::Hashing);
+            pool.install(|| {
+                // 只为 Top N 计算摘要，为分页多保留的文件不读取
+                let n = limit.min(top_files.len());
+                content_hash::digest_entries(
+                    &mut top_files[..n],
+                    algo,
+                    request.hash_max_bytes_per_sec,
+                    &counters.live,
+                    request.cancel.as_ref(),
+                )
+            });
+        }
+        let compression = match &counters.compression.options {
+            Some(_) if !ctx.canceled() => pool.install(|| counters.compression.to_vec(limit, request.cancel.as_ref())),
+            _ => Default::default(),
+        };
+        if let Some(error) = counters.errors.take_failure() {
+            return Err(error);
+        }
+        let canceled = ctx.canceled();
+        let budget_deferred_dirs = ctx.budget.deferred_dirs();
+        counters.live.begin_phase(ScanPhase::Aggregating);
+
+        let hints_applied = ctx.hints.is_some();
+        let plugin_sections = plugins.map(PluginSet::finish).unwrap_or_default();
+        let elapsed = start_time.elapsed().unwrap_or_default();
+        
+        let mut result = ScanResult {
+            summary: counters.to_summary(request.root_path.clone(), elapsed.as_secs_f64()),
+            top_files,
+            by_extension: counters.extensions_to_vec(),
+            by_category: Vec::new(),
+            by_owner: Vec::new(),
+            stale_files: counters.stale_files_to_vec(),
+            empty_files: Vec::new(),
+            empty_dirs: Vec::new(),
+            scan_errors: Vec::new(),
+            directories: counters.directories_to_vec(&root_node),
+            diagnostics: counters.to_diagnostics(hints_applied),
+            concurrency_hints: Some(counters.take_hints(request.root_path.clone())),
+            density: counters.density.to_ranking(),
+            heatmap: counters.heatmap_to_vec(request.heatmap_depth),
+            age_histogram: counters.age_histogram.to_vec(),
+            size_histograms: counters.size_histograms.to_vec(),
+            top_level: top_level::rows(&root_node, &lock(&counters.top_level)),
+            top_dirs: counters.top_dirs_to_vec(request.top_dirs_depth, request.top_dirs_limit.unwrap_or(limit)),
+            top_dirs_shallow: counters.density.shallow_to_vec(),
+            duplicates,
+            system_reserved: counters.reserved_to_vec(),
+            reclaimable_report: counters.reclaimable.to_report(limit),
+            compression_candidates: Vec::new(),
+            suspicious_files: counters.suspicious_files_to_vec(now),
+            redundant: Vec::new(),
+            redundant_files: Vec::new(),
+            oldest_files: Vec::new(),
+            newest_files: Vec::new(),
+            root_summaries: Vec::new(),
+            plugin_sections,
+        };
+        result.summary.min_size = min_size;
+        result.summary.top_files_capacity = Some(counters.top_capacity).filter(|&n| n > limit);
+        result.summary.disk_space = disk_space::query(&request.root_path);
+        result.summary.empty_limit = request.empty_limit;
+        (result.summary.empty_file_count, result.empty_files) = counters.empty.files_to_vec();
+        (result.summary.empty_dir_count, result.empty_dirs) = counters.empty.dirs_to_vec();
+        (result.summary.scan_error_count, result.scan_errors) = counters.errors.to_vec();
+        (result.redundant, result.redundant_files) = counters.redundant.to_vec();
+        (
+            result.compression_candidates,
+            result.summary.compression_candidate_count,
+            result.summary.compression_savings_bytes,
+        ) = compression;
+        result.summary.redundant_file_count = result.redundant.iter().map(|s| s.file_count).sum();
+        result.summary.redundant_bytes = result.redundant.iter().map(|s| s.total_size_bytes).sum();
+        (result.oldest_files, result.newest_files) = counters.recency.to_vec();
+        result.summary.recency_limit = request.recency_limit;
+        result.summary.shard = request.shard.clone();
+        counters.extension_top.attach(&mut result.by_extension);
+        result.summary.extension_top_limit = request.extension_top_limit;
+        result.summary.total_allocated_bytes = request
+            .allocated_sizes
+            .then(|| counters.allocated_bytes.load(Ordering::Relaxed));
+        if canceled {
+            result.summary.state = ScanState::Canceled;
+        }
+        result.diagnostics.requested_limit = request.limit.filter(|&n| n > limit);
+        (result.diagnostics.filter_checks, result.diagnostics.filter_match_nanos) = filter.cost();
+        result.diagnostics.budget_deferred_dirs = budget_deferred_dirs;
+        result.summary.min_size_is_default = default_min_size.is_some();
+        result.summary.summary_only = !request.collect_entries;
+        result.summary.digest_algo = request.compute_hash;
+        result.by_category = category::from_extensions(&result.by_extension);
+        if request.collect_owners {
+            // 属主名称在生成结果时统一解析，每个 ID 只查询一次
+            let mut names = owner::Names::default();
+            names.resolve(&mut result.top_files);
+            names.resolve(&mut result.stale_files);
+            names.resolve(&mut result.suspicious_files);
+            names.resolve(&mut result.redundant_files);
+            names.resolve(&mut result.oldest_files);
+            names.resolve(&mut result.newest_files);
+            for stat in &mut result.by_extension {
+                names.resolve(&mut stat.top_files);
             }
+            result.by_owner = names.owner_stats(counters.owners_to_map());
         }
-        Ok(())
+        share::apply_shares(&mut result);
+        Ok(result)
     }
     
     /// 并行遍历目录树（内部实现）
+    ///
+    /// 每个子目录是线程池中的一个任务，空闲线程从其他线程的队列中窃取待处理的目录与文件批次；
+    /// 含大量文件的目录按 [`hints::FILE_BATCH_SIZE`] 分批读取元数据，见 [`Self::spawn_file_batch`]。
+    /// 设置内存预算且在途任务名额用尽时，子目录不再派发，压入本任务的栈中按深度优先顺序继续遍历，
+    /// 见 [`budget`]。`permit` 为本任务占用的名额，任务结束时释放。
     fn parallel_walk_dir<'scope>(
         scope: &rayon::Scope<'scope>,
-        dir: PathBuf,
-        counters: &'scope AtomicCounters,
-        request: &'scope ScanRequest,
-        exclude_patterns: &'scope [Pattern],
+        task: DirTask,
+        permit: Option<TaskPermit>,
+        ctx: &'scope WalkContext<'scope>,
     ) {
-        // 检查是否为目录
-        if !dir.is_dir() {
-            return;
+        let _permit = permit;
+        let mut stack = vec![task];
+        while let Some(DirTask { dir, node, depth, ignores }) = stack.pop() {
+            let path = dir.clone();
+            let subdirs = Self::isolate(ctx, &path, || Self::walk_dir(scope, dir, node, depth, ignores, ctx));
+            for subdir in subdirs.unwrap_or_default() {
+                match ctx.budget.try_acquire() {
+                    Some(permit) => scope.spawn(move |scope| Self::parallel_walk_dir(scope, subdir, Some(permit), ctx)),
+                    None => {
+                        ctx.budget.defer_dir();
+                        stack.push(subdir);
+                    }
+                }
+            }
         }
+    }
+
+    /// 遍历单个目录：统计其中的文件，返回需要继续遍历的子目录
+    fn walk_dir<'scope>(
+        scope: &rayon::Scope<'scope>,
+        dir: PathBuf,
+        node: Arc<DirNode>,
+        depth: usize,
+        ignores: Option<Arc<IgnoreRules>>,
+        ctx: &'scope WalkContext<'scope>,
+    ) -> Vec<DirTask> {
+        // 检查是否为目录；已取消时不再进入新的目录，跟随链接时同一目录只进入一次
+        if ctx.canceled() || !long_path::extended(&dir).is_dir() || !ctx.enter_dir(&dir) {
+            node.mark_occupied();
+            return Vec::new();
+        }
+
+        // 增量扫描在读取目录内容之前记录其修改时间（根目录每次都重新读取）
+        if let Some(mtimes) = ctx.request.dir_mtimes.as_ref().filter(|_| depth > 0) {
+            mtimes.record(&dir);
+        }
+
+        // 分片扫描时，根目录本身及其直接文件只归属于负责根文件的分片，避免合并后重复计数
+        let shard_at_root = if depth == 0 { ctx.request.shard.as_ref() } else { None };
+        let owns_root_files = shard_at_root.is_none_or(|s| s.include_root_files);
         
         // 增加目录计数
-        counters.dirs.fetch_add(1, Ordering::SeqCst);
+        if owns_root_files {
+            ctx.counters.live.add_dir();
+            node.count_in_ancestors();
+        }
         
-        // 读取目录条目，如果失败则跳过（无法访问的目录）
-        let entries = match std::fs::read_dir(&dir) {
+        // 读取目录条目，如果失败则记录错误并跳过；权限不足的目录同时记入诊断信息
+        let mut pace = PaceBatch::new(ctx.io_pacer.as_ref(), &ctx.counters.live);
+        pace.tick();
+        let entries = match ctx.fs.read_dir(&dir) {
             Ok(entries) => entries,
-            Err(_) => return,
+            Err(e) => {
+                ctx.counters.errors.record(&dir, ScanOperation::ReadDir, &e);
+                if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    if full_disk_access::is_privacy_denial(&e) {
+                        ctx.counters.full_disk_access_missing.store(true, Ordering::Relaxed);
+                    }
+                    lock(&ctx.counters.denied_dirs).push(dir);
+                }
+                node.mark_occupied();
+                return Vec::new();
+            }
         };
+
+        // 叠加本目录的忽略文件（根目录的规则已在扫描开始时读取）
+        let ignores = if ctx.request.respect_ignore_files && depth > 0 {
+            IgnoreRules::for_dir(&dir, ignores.as_ref())
+        } else {
+            ignores
+        };
+
+        // 文件元数据分批读取：前 FILE_BATCH_SIZE 个文件在本任务中直接处理，之后每累计一批即派发为独立任务，
+        // 与继续枚举本目录并行，避免含大量文件的目录由单个任务串行 stat 而拖尾；
+        // 历史提示显示条目很多的目录从第一个文件起就分批
+        let hinted_entries = ctx.hints.and_then(|h| h.entries_for(&dir));
+        let split = hinted_entries.is_some_and(|n| n >= hints::SPLIT_THRESHOLD);
+        let mut inline_files = if split { hints::FILE_BATCH_SIZE } else { 0 };
+        let mut split_dir: Option<Arc<SplitDir>> = None;
         
         // 收集子目录和文件
         let mut subdirs = Vec::new();
+        let mut pending_files = Vec::new();
+        let mut entry_count: u64 = 0;
+        let mut direct = DirTally::default();
+        let mut complete = true;
         for entry in entries {
+            if ctx.canceled() {
+                complete = false;
+                break;
+            }
             let entry = match entry {
                 Ok(e) => e,
-                Err(_) => continue,
+                Err(e) => {
+                    ctx.counters.errors.record(&dir, ScanOperation::ReadEntry, &e);
+                    complete = false;
+                    continue;
+                }
             };
-            let path = entry.path();
-            
-            if path.is_dir() {
+            entry_count += 1;
+            // 报告的路径由目录路径拼接，不带读取目录时可能使用的 `\\?\` 前缀
+            let path = dir.join(entry.file_name());
+
+            // 优先使用目录项自带的类型（无需额外 stat）；指向目录的符号链接只在策略允许时进入，
+            // 否则与文件链接一样按链接自身计为文件
+            let is_dir = match entry.file_type() {
+                Ok(t) if !t.is_symlink() => t.is_dir(),
+                Ok(_) => ctx.request.follow_symlinks.follows_dirs() && long_path::extended(&path).is_dir(),
+                Err(_) => std::fs::symlink_metadata(long_path::extended(&path)).is_ok_and(|m| m.is_dir()),
+            };
+            // 被 .gitignore / .surfignore 忽略的文件不计入，目录整棵跳过
+            if ignores.as_ref().is_some_and(|rules| rules.is_ignored(&path, is_dir)) {
+                continue;
+            }
+            if is_dir {
                 // 目录匹配排除规则则跳过整棵子树
-                if is_excluded(&path, exclude_patterns) {
+                if ctx.filter.excludes_dir(&path) {
                     continue;
                 }
-                subdirs.push(path);
-            } else {
-                let metadata = entry.metadata();
-                let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-
-                // 文件匹配排除规则则跳过
-                if is_excluded(&path, exclude_patterns) {
+                // 分片扫描时跳过不属于本分片的顶层目录
+                if shard_at_root.is_some_and(|s| !s.owns_dir(&entry.file_name())) {
                     continue;
                 }
-
-                // 应用 min-size 过滤
-                if let Some(min_size) = request.min_size {
-                    if size < min_size {
-                        continue;
-                    }
+                // 单文件系统模式下不进入其他文件系统的挂载点
+                if mount::crosses(ctx.root_device, &path) {
+                    lock(&ctx.counters.skipped_mount_points).push(path);
+                    continue;
                 }
-                
-                // 增加文件计数和大小
-                counters.files.fetch_add(1, Ordering::SeqCst);
-                counters.size.fetch_add(size, Ordering::SeqCst);
-                // 提取扩展名
-                let extension = path
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|s| s.to_lowercase());
-                counters.add_file_with_extension(extension.clone(), size);
-                // 添加到 Top N 大文件列表
-                let last_modified = metadata.ok().and_then(|m| m.modified().ok());
-                counters.add_file_to_top_list(path.clone(), size, last_modified, extension.clone());
-                
-                // 检查是否为陈旧文件
-                if let Some(stale_days) = request.stale_days {
-                    if let Some(last_modified) = last_modified {
-                        if let Ok(duration) = SystemTime::now().duration_since(last_modified) {
-                            if duration.as_secs() >= (stale_days as u64) * 24 * 60 * 60 {
-                                counters.add_stale_file(path, size, Some(last_modified), extension);
-                            }
-                        }
+                // 包目录按不透明文件统计：在本任务中累计内部大小，不作为子目录遍历
+                if ctx.request.opaque_packages && package::is_package(&path) {
+                    if owns_root_files {
+                        Self::isolate(ctx, &path, || {
+                            let size = package::package_size(&path, ctx.request.allocated_sizes, &mut pace);
+                            Self::process_file(ctx, &path, ctx.metadata(&entry, &path), Some(size), &node, &mut direct)
+                        });
                     }
+                    continue;
+                }
+                subdirs.push(path);
+            } else if !owns_root_files {
+                continue;
+            } else if inline_files >= hints::FILE_BATCH_SIZE {
+                pending_files.push(entry);
+                if pending_files.len() == hints::FILE_BATCH_SIZE {
+                    let batch = std::mem::take(&mut pending_files);
+                    Self::dispatch_file_batch(scope, ctx, &node, &dir, &mut split_dir, batch, &mut direct);
                 }
+            } else {
+                inline_files += 1;
+                pace.tick();
+                Self::isolate(ctx, &path, || {
+                    Self::process_file(ctx, &path, ctx.metadata(&entry, &path), None, &node, &mut direct)
+                });
+            }
+        }
+        if !pending_files.is_empty() {
+            Self::dispatch_file_batch(scope, ctx, &node, &dir, &mut split_dir, pending_files, &mut direct);
+        }
+        ctx.counters.record_dir_entries(&dir, entry_count);
+        // 含有目录以外的条目（含被过滤、忽略的条目与不进入的子目录）或未能完整读取时不是空目录
+        if !complete || entry_count > subdirs.len() as u64 {
+            node.mark_occupied();
+        } else if depth > 0 {
+            ctx.counters.empty.add_dir_candidate(&node);
+        }
+        ctx.counters.flush_tally(&direct, &node);
+
+        match split_dir {
+            Some(split_dir) => {
+                if split {
+                    ctx.counters.hinted_dirs.fetch_add(1, Ordering::Relaxed);
+                }
+                split_dir.finish_batch(&ctx.counters.density, direct.files, direct.ages.total());
             }
+            None => ctx.counters.density.record(&dir, direct.files, direct.ages.total()),
+        }
+
+        // 按历史条目数降序派发子目录任务，让重负载子树尽早开始
+        if let Some(hints) = ctx.hints {
+            subdirs.sort_by_key(|d| Reverse(hints.entries_for(d).unwrap_or(0)));
         }
         
-        // 为每个子目录生成并行任务
+        // 为每个子目录生成遍历任务
+        let mut tasks = Vec::with_capacity(subdirs.len());
         for subdir in subdirs {
-            let counters = counters; // 捕获引用
-            let request = request; // 捕获引用
-            scope.spawn(move |scope| {
-                Self::parallel_walk_dir(scope, subdir, counters, request, exclude_patterns);
+            let child = node.child(subdir.clone());
+            if let Some(kind) = reserved::classify(&subdir, true) {
+                lock(&ctx.counters.reserved_dirs).push((kind, child.clone()));
+            }
+            if depth == 0 {
+                lock(&ctx.counters.top_level).push(child.clone());
+            }
+            // 超出最大深度的子目录只计数，不再派发遍历任务
+            if ctx.request.max_depth.is_some_and(|max| depth >= max) {
+                ctx.counters.live.add_dir();
+                child.count_in_ancestors();
+                child.mark_occupied();
+                ctx.counters.depth_limited_dirs.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            if let Some(kind) = reclaimable::classify(&subdir) {
+                ctx.counters.reclaimable.record(kind, &child);
+            }
+            if depth < ctx.request.heatmap_depth.max(ctx.request.top_dirs_depth) {
+                ctx.counters.track_dir(depth + 1, &child);
+            }
+            tasks.push(DirTask { dir: subdir, node: child, depth: depth + 1, ignores: ignores.clone() });
+        }
+        tasks
+    }
+
+    /// 处理一批文件：有在途任务名额时派发为独立任务，否则在当前任务中直接统计
+    fn dispatch_file_batch<'scope>(
+        scope: &rayon::Scope<'scope>,
+        ctx: &'scope WalkContext<'scope>,
+        node: &Arc<DirNode>,
+        dir: &Path,
+        split_dir: &mut Option<Arc<SplitDir>>,
+        entries: Vec<std::fs::DirEntry>,
+        direct: &mut DirTally,
+    ) {
+        match ctx.budget.try_acquire() {
+            Some(permit) => Self::spawn_file_batch(scope, ctx, node, dir, split_dir, entries, permit),
+            None => Self::process_entries(ctx, entries, node, direct),
+        }
+    }
+
+    fn process_entries(ctx: &WalkContext<'_>, entries: Vec<std::fs::DirEntry>, node: &Arc<DirNode>, tally: &mut DirTally) {
+        let mut pace = PaceBatch::new(ctx.io_pacer.as_ref(), &ctx.counters.live);
+        for entry in entries {
+            if ctx.canceled() {
+                break;
+            }
+            pace.tick();
+            let path = node.path().join(entry.file_name());
+            Self::isolate(ctx, &path, || Self::process_file(ctx, &path, ctx.metadata(&entry, &path), None, node, tally));
+        }
+    }
+
+    /// 把目录中的一批文件派发为独立任务读取元数据并统计；首个批次派发时创建该目录的 [`SplitDir`]
+    fn spawn_file_batch<'scope>(
+        scope: &rayon::Scope<'scope>,
+        ctx: &'scope WalkContext<'scope>,
+        node: &Arc<DirNode>,
+        dir: &Path,
+        split_dir: &mut Option<Arc<SplitDir>>,
+        entries: Vec<std::fs::DirEntry>,
+        permit: TaskPermit,
+    ) {
+        let split_dir = split_dir.get_or_insert_with(|| Arc::new(SplitDir::new(dir.to_path_buf()))).clone();
+        split_dir.add_batch();
+        ctx.counters.split_batches.fetch_add(1, Ordering::Relaxed);
+        let node = node.clone();
+        scope.spawn(move |_| {
+            let _permit = permit;
+            let mut batch = DirTally::default();
+            Self::process_entries(ctx, entries, &node, &mut batch);
+            ctx.counters.flush_tally(&batch, &node);
+            split_dir.finish_batch(&ctx.counters.density, batch.files, batch.ages.total());
+        });
+    }
+
+    /// 隔离单个遍历单元中的 panic：记录为该路径的错误并继续扫描其余部分，
+    /// 避免 panic 传播到线程池后中断整个扫描（服务端任务因此永远停留在运行中）
+    fn isolate<T>(ctx: &WalkContext<'_>, path: &Path, unit: impl FnOnce() -> T) -> Option<T> {
+        match std::panic::catch_unwind(AssertUnwindSafe(unit)) {
+            Ok(value) => Some(value),
+            Err(payload) => {
+                ctx.counters.record_panic(path, payload);
+                None
+            }
+        }
+    }
+
+    /// 处理单个文件条目：过滤并更新各项统计（文件数、字节数与扩展名先累计到所在目录的 `tally`），
+    /// 返回计入统计的字节数（被过滤时返回 None）
+    fn process_file(
+        ctx: &WalkContext<'_>,
+        path: &Path,
+        metadata: Option<std::fs::Metadata>,
+        package: Option<PackageSize>,
+        parent: &Arc<DirNode>,
+        tally: &mut DirTally,
+    ) -> Option<u64> {
+        let request = ctx.request;
+        let counters = ctx.counters;
+        // 作为不透明文件统计的包目录取内部文件的累计大小
+        let size = match package {
+            Some(package) => package.bytes,
+            None => metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+        };
+        // 访问文件系统时使用的路径（Windows 上较长的路径带 `\\?\` 前缀）
+        let fs_path = long_path::extended(path);
+        // 跟随文件链接时元数据来自目标文件，链接本身仍不视为普通文件
+        let is_link = metadata.as_ref().is_some_and(|m| m.file_type().is_symlink())
+            || (request.follow_symlinks.follows_files() && fs_path.is_symlink());
+        let regular_file = metadata.as_ref().is_some_and(|m| m.is_file()) && !is_link;
+        let placeholder = metadata.as_ref().is_some_and(|m| ctx.fs.is_placeholder(path, m));
+
+        // 先包含后排除：未匹配包含规则或匹配排除规则的文件跳过
+        if ctx.filter.excludes_file(path) {
+            return None;
+        }
+
+        // 零字节文件单独记录，不受 min-size 过滤影响
+        if regular_file && size == 0 {
+            counters.empty.add_file(path.to_path_buf());
+        }
+
+        // 应用 min-size 过滤
+        if let Some(min_size) = ctx.min_size {
+            if size < min_size {
+                return None;
+            }
+        }
+
+        // 硬链接去重：同一 inode 已由其他路径计入时跳过（在各项过滤之后登记，被过滤的路径不占位）
+        if let (Some(seen), Some(meta)) = (&ctx.seen_inodes, &metadata) {
+            if !seen.first_link(meta) {
+                counters.skipped_hardlinks.fetch_add(1, Ordering::Relaxed);
+                counters.skipped_hardlink_bytes.fetch_add(size, Ordering::Relaxed);
+                return None;
+            }
+        }
+        
+        // 提取扩展名
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|s| s.to_lowercase());
+        let allocated_bytes = metadata
+            .as_ref()
+            .filter(|_| request.allocated_sizes)
+            .and_then(|m| match package {
+                Some(package) => Some(package.allocated),
+                None => allocation::allocated_size(&fs_path, m),
             });
+        let owner = metadata.as_ref().filter(|_| request.collect_owners).map(owner::ownership);
+        // 按文件头识别类型（读取失败时不带类型；云端占位文件不读取）
+        let mime_type = if request.detect_mime && regular_file && !placeholder && size >= request.mime_min_size {
+            mime::sniff(&fs_path).ok().flatten().map(str::to_string)
+        } else {
+            None
+        };
+        let mut entry = FileEntry {
+            path: path.to_path_buf(),
+            size_bytes: size,
+            allocated_bytes,
+            last_modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+            last_accessed: metadata.as_ref().and_then(|m| m.accessed().ok()),
+            created: metadata.and_then(|m| m.created().ok()),
+            extension,
+            coldness: None,
+            idle_seconds: None,
+            share_of_parent: None,
+            share_of_total: None,
+            reserved: reserved::classify(path, false),
+            redundant: regular_file.then(|| redundant::classify(path)).flatten(),
+            owner,
+            digest: None,
+            mime: mime_type,
+            link_target: is_link.then(|| symlink::read_target(&fs_path)).flatten(),
+            is_placeholder: placeholder,
+            depth: None,
+            parent_index: None,
+        };
+
+        // 可疑的修改时间只计数（按需列出），年龄分析使用校正到合理范围内的时间
+        let modified = entry.last_modified.map(|m| {
+            if timestamp::deviation(m, ctx.now).is_some() {
+                counters.suspicious_timestamps.fetch_add(1, Ordering::Relaxed);
+                if request.list_suspicious_timestamps && request.collect_entries {
+                    counters.add_suspicious_file(entry.clone(), ctx.now);
+                }
+            }
+            timestamp::clamp(m, ctx.now)
+        });
+        tally.add(
+            &entry,
+            heatmap::bucket_of(modified, ctx.now),
+            age_histogram::bucket_of(modified, ctx.now),
+        );
+        counters.size_histograms.add(entry.extension.as_deref().unwrap_or("no_extension"), size);
+
+        if placeholder {
+            counters.placeholder_files.fetch_add(1, Ordering::Relaxed);
+            counters.placeholder_bytes.fetch_add(size, Ordering::Relaxed);
+        }
+        if let Some(kind) = entry.reserved {
+            lock(&counters.reserved_files).push(ReservedEntry { path: entry.path.clone(), kind, size_bytes: size });
+        }
+        if let Some(kind) = entry.redundant {
+            counters.redundant.add(&entry, kind);
+        }
+
+        // 将条目流式发送给分析器插件与 `scan_iter` 的迭代器
+        if let Some(plugins) = ctx.plugins {
+            plugins.send(&entry);
+        }
+        if let Some(sink) = &request.entry_sink {
+            sink.send(&entry);
+        }
+
+        // 仅汇总模式：统计已累计到目录，不再保留任何文件条目
+        if !request.collect_entries {
+            return Some(size);
+        }
+
+        // 修改时间可疑的文件不参与最旧/最新排名
+        if entry.last_modified.is_some_and(|m| timestamp::deviation(m, ctx.now).is_none()) {
+            counters.recency.add(&entry);
+        }
+        counters.extension_top.add(entry.extension.as_deref().unwrap_or("no_extension"), &entry);
+
+        // 启用重复检测时记录候选文件（符号链接不参与，避免与目标文件重复计入；云端占位文件不读取，不参与）
+        if request.dedup.is_some() && regular_file && !placeholder {
+            lock(&counters.dedup_candidates).push((entry.path.clone(), size));
+        }
+        // 启用可压缩分析时记录大而旧的候选文件（同样不含符号链接与云端占位文件）
+        if regular_file && !placeholder {
+            counters.compression.offer(&entry, modified, ctx.now);
         }
+
+        // 检查是否为陈旧文件（按 stale_mode 选取参照时间，提供访问日志时计入记录到的访问，并计算冷度评分）
+        let mut stale = false;
+        if let Some(stale_days) = request.stale_days {
+            let record = ctx.access_record(&entry.path);
+            let clamp = |t: Option<SystemTime>| t.map(|t| timestamp::clamp(t, ctx.now));
+            let last_active =
+                access::reference_time(request.stale_mode, modified, clamp(entry.last_accessed), clamp(entry.created), record);
+            if let Some(idle) = last_active.and_then(|t| access::stale_idle(t, ctx.now, stale_days)) {
+                stale = true;
+                entry.idle_seconds = Some(idle.as_secs());
+                entry.coldness = request
+                    .access_log
+                    .as_ref()
+                    .map(|_| access::coldness(idle, record.map_or(0, |r| r.count)));
+            }
+        }
+        if stale {
+            counters.add_stale_file(entry.clone(), parent);
+        }
+
+        // 添加到 Top N 大文件列表（Top N 中不携带冷度评分与空闲时间）
+        if counters.may_enter_top_list(size) {
+            entry.coldness = None;
+            entry.idle_seconds = None;
+            counters.add_file_to_top_list(entry, parent);
+        }
+        Some(size)
     }
 }
 
-/// 判断路径是否匹配任一排除模式
-fn is_excluded(path: &Path, patterns: &[Pattern]) -> bool {
-    // 使用绝对或相对路径进行匹配，glob::Pattern 支持路径分隔符
-    for pat in patterns {
-        if pat.matches_path(path) {
-            return true;
+/// 待遍历的目录
+struct DirTask {
+    dir: PathBuf,
+    node: Arc<DirNode>,
+    depth: usize,
+    /// 从祖先目录继承的忽略规则
+    ignores: Option<Arc<IgnoreRules>>,
+}
+
+/// 单次扫描中所有并行任务共享的只读上下文
+struct WalkContext<'a> {
+    request: &'a ScanRequest,
+    counters: &'a AtomicCounters,
+    /// 排除与包含规则
+    filter: &'a PathFilter,
+    /// 来自历史扫描的并发提示（可选）
+    hints: Option<&'a ConcurrencyHints>,
+    /// 实际生效的最小文件尺寸
+    min_size: Option<u64>,
+    /// 运行中的分析器插件（可选）
+    plugins: Option<&'a PluginSet>,
+    /// 规范化后的根目录，用于将扫描路径映射为访问日志中的绝对路径
+    access_root: Option<PathBuf>,
+    /// 分析参照的当前时间（扫描开始时读取一次），用于陈旧判断与文件年龄
+    now: SystemTime,
+    /// 已进入的目录，用于检测符号链接环路、重复路径与 bind mount
+    visited_dirs: Option<VisitedDirs>,
+    /// 启用硬链接去重时已计入的多链接文件
+    seen_inodes: Option<SeenInodes>,
+    /// 单文件系统模式下根目录的设备号
+    root_device: Option<u64>,
+    /// 在途遍历任务名额
+    budget: Arc<TaskBudget>,
+    /// 元数据操作限速器（可选）
+    io_pacer: Option<IoPacer>,
+    /// 读取目录与识别占位文件所用的文件系统
+    fs: &'a dyn FsProvider,
+}
+
+impl WalkContext<'_> {
+    /// 是否已请求取消扫描，或 `fail_fast` 时已遇到遍历错误
+    fn canceled(&self) -> bool {
+        self.request.cancel.as_ref().is_some_and(CancellationToken::is_canceled) || self.counters.errors.aborted()
+    }
+
+    /// 读取条目元数据，瞬时错误按请求中的策略重试。
+    /// 按策略跟随文件链接时读取目标文件的元数据（悬空链接退回链接自身）
+    fn metadata(&self, entry: &std::fs::DirEntry, path: &Path) -> Option<std::fs::Metadata> {
+        let follow = self.request.follow_symlinks.follows_files()
+            && entry.file_type().is_ok_and(|t| t.is_symlink());
+        let (result, retries) = self.request.metadata_retry.run(|| {
+            if follow {
+                std::fs::metadata(long_path::extended(path)).or_else(|_| entry.metadata())
+            } else {
+                entry.metadata()
+            }
+        });
+        if retries > 0 {
+            self.counters.metadata_retries.fetch_add(u64::from(retries), Ordering::Relaxed);
         }
+        result.map_err(|e| self.counters.errors.record(path, ScanOperation::Metadata, &e)).ok()
+    }
+
+    /// 登记即将进入的目录；重复进入同一目录（环路、多条链接或 bind mount）时记录别名并返回 false
+    fn enter_dir(&self, dir: &Path) -> bool {
+        let Some(alias_of) = self.visited_dirs.as_ref().and_then(|v| v.visit(dir)) else {
+            return true;
+        };
+        self.counters.revisited_dirs.fetch_add(1, Ordering::Relaxed);
+        lock(&self.counters.aliased_dirs).push(DirAlias { path: dir.to_path_buf(), alias_of });
+        false
+    }
+
+    /// 查询文件在访问日志中的统计（先按扫描路径，再按规范化后的绝对路径）
+    fn access_record(&self, path: &Path) -> Option<&AccessRecord> {
+        let log = self.request.access_log.as_deref()?;
+        log.get(path).or_else(|| {
+            let root = self.access_root.as_deref()?;
+            let relative = path.strip_prefix(&self.request.root_path).ok()?;
+            log.get(&root.join(relative))
+        })
     }
-    false
 }
 
 /// 便捷函数：快速扫描指定路径
-pub fn scan_path<P: Into<PathBuf>>(path: P) -> std::io::Result<ScanResult> {
+pub fn scan_path<P: Into<PathBuf>>(path: P) -> Result<ScanResult, ScanError> {
     let request = ScanRequest::new(path);
     let scanner = Scanner::new();
     scanner.scan_sync(&request)
@@ -473,8 +2496,9 @@ mod tests {
     use super::*;
     use std::fs::{self, File};
     use std::io::Write;
+    use std::time::{Duration, UNIX_EPOCH};
     use tempfile::tempdir;
-    
+
     #[test]
     fn test_scan_request_new() {
         let req = ScanRequest::new("/tmp");
@@ -699,8 +2723,52 @@ mod tests {
         assert_eq!(result_limit.top_files[3].size_bytes, 600);
         assert_eq!(result_limit.top_files[4].size_bytes, 500);
         
-        // 测试 limit = 0（应视为无限制？但实际 limit 应该大于0，这里测试默认行为）
-        // 跳过，因为 limit 为 0 时堆容量为 0，可能不存储任何文件。我们假设 limit >= 1
+    }
+
+    #[test]
+    fn test_top_files_capacity_keeps_pageable_set() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (i, size) in [100usize, 500, 300, 800, 200, 700].into_iter().enumerate() {
+            fs::create_dir(root.join(format!("d{i}"))).unwrap();
+            fs::write(root.join(format!("d{i}/f.bin")), vec![b'a'; size]).unwrap();
+        }
+
+        let mut request = ScanRequest::new(root);
+        request.limit = Some(2);
+        request.top_files_capacity = Some(5);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+
+        // 多保留到 5 个，按大小降序
+        let sizes: Vec<u64> = result.top_files.iter().map(|f| f.size_bytes).collect();
+        assert_eq!(sizes, vec![800, 700, 500, 300, 200]);
+        assert_eq!(result.summary.top_limit, 2);
+        assert_eq!(result.summary.top_files_capacity, Some(5));
+        assert_eq!(result.summary.top_files_bound(), 5);
+
+        // 分页
+        let page: Vec<u64> = result.page_top_files(2, 2).iter().map(|f| f.size_bytes).collect();
+        assert_eq!(page, vec![500, 300]);
+        assert_eq!(result.page_top_files(4, 10).len(), 1);
+        assert!(result.page_top_files(10, 2).is_empty());
+
+        // 其他列表仍按 Top N：目录只来自前 2 个文件
+        assert!(result.directories.iter().all(|d| !d.path.ends_with("d1") && !d.path.ends_with("d2")));
+        assert!(result.directories.iter().any(|d| d.path.ends_with("d3")));
+
+        // 容量不大于 limit 时不记录
+        request.top_files_capacity = Some(1);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.top_files.len(), 2);
+        assert_eq!(result.summary.top_files_capacity, None);
+
+        // 合并后按最大的容量截断
+        let mut other = ScanRequest::new(root);
+        other.limit = Some(2);
+        other.top_files_capacity = Some(3);
+        let merged = merge(vec![Scanner::new().scan_sync(&other).unwrap()]);
+        assert_eq!(merged.top_files.len(), 3);
+        assert_eq!(merged.summary.top_files_capacity, Some(3));
     }
 
     #[test]
@@ -814,4 +2882,3394 @@ mod tests {
         assert_eq!(result.top_files.len(), 1);
         assert_eq!(result.top_files[0].path.file_name().unwrap(), "b.txt");
     }
+
+    #[test]
+    fn test_include_then_exclude_patterns() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (path, len) in [
+            ("movies/a.mp4", 400),
+            ("movies/b.mkv", 300),
+            ("movies/notes.txt", 50),
+            ("movies/trailers/c.mp4", 200),
+            ("cache/d.mp4", 100),
+        ] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![0u8; len]).unwrap();
+        }
+
+        let mut request = ScanRequest::new(root);
+        request.include_patterns = vec!["**/*.{mp4,mkv}".to_string()];
+        let included = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(included.summary.total_files, 4);
+        assert_eq!(included.summary.total_size_bytes, 1000);
+        // 包含规则不剪枝目录：目录照常遍历与计数
+        assert_eq!(included.summary.total_dirs, 4);
+
+        // 先包含后排除：排除规则从包含结果中再剔除，匹配的目录整棵跳过
+        request.exclude_patterns = vec!["**/cache".to_string(), "**/trailers/*.mp4".to_string()];
+        let filtered = Scanner::new().scan_sync(&request).unwrap();
+        let mut names: Vec<_> = filtered.top_files.iter().map(|f| f.path.strip_prefix(root).unwrap()).collect();
+        names.sort();
+        assert_eq!(names, vec![Path::new("movies/a.mp4"), Path::new("movies/b.mkv")]);
+        assert_eq!(filtered.summary.total_dirs, 3);
+
+        // 包含规则全部非法时不匹配任何文件
+        request.include_patterns = vec!["[".to_string()];
+        assert_eq!(Scanner::new().scan_sync(&request).unwrap().summary.total_files, 0);
+
+        assert_eq!(filter::expand_braces("a{b,c{d,e}}f{1,2}").len(), 6);
+        assert_eq!(filter::expand_braces("{a,b"), vec!["{a,b".to_string()]);
+    }
+
+    #[test]
+    fn test_regex_filters() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (path, len) in [
+            ("backups/2024-01-31/db.dump", 700),
+            ("backups/2024-02-29/db.dump", 600),
+            ("backups/latest/db.dump", 500),
+            ("backups/latest/notes.txt", 10),
+        ] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![0u8; len]).unwrap();
+        }
+
+        // 按日期命名的目录整棵排除（glob 无法表达“恰好是日期”）
+        let mut request = ScanRequest::new(root);
+        request.exclude_regex = vec![r"[/\\]\d{4}-\d{2}-\d{2}$".to_string()];
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.summary.total_files, 2);
+        assert_eq!(result.summary.total_dirs, 3);
+
+        // 正则包含规则与 glob 包含规则合并生效，排除规则仍然优先
+        request.include_regex = vec![r"\.dump$".to_string()];
+        request.include_patterns = vec!["**/*.txt".to_string()];
+        request.exclude_patterns = vec!["**/notes.txt".to_string()];
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.summary.total_files, 1);
+        assert_eq!(result.top_files[0].path, root.join("backups/latest/db.dump"));
+
+        // 非法正则表达式在扫描开始前报错
+        request.exclude_regex = vec!["(".to_string()];
+        let err = Scanner::new().scan_sync(&request).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_filters_match_relative_to_root() {
+        let dir = tempdir().unwrap();
+        // 根目录本身位于名为 sub 的目录下，相对规则不应受其影响
+        let root = dir.path().join("sub").join("root");
+        for path in ["sub/a.txt", "keep/sub/b.txt", "cache/c.txt", "d.txt"] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"data").unwrap();
+        }
+        let names = |request: &ScanRequest| {
+            let result = Scanner::new().scan_sync(request).unwrap();
+            let mut names: Vec<String> = result
+                .top_files
+                .iter()
+                .map(|f| f.path.strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"))
+                .collect();
+            names.sort();
+            names
+        };
+
+        // `sub/**` 只排除根目录下的 sub，`**/sub/**` 排除任意层级
+        let mut request = ScanRequest::new(&root);
+        request.exclude_patterns = vec!["sub/**".to_string()];
+        assert_eq!(names(&request), ["cache/c.txt", "d.txt", "keep/sub/b.txt"]);
+        request.exclude_patterns = vec!["**/sub/**".to_string()];
+        assert_eq!(names(&request), ["cache/c.txt", "d.txt"]);
+
+        // 正则同样匹配相对路径，`^` 锚定在根目录
+        request.exclude_patterns.clear();
+        request.exclude_regex = vec!["^(cache|sub)/".to_string()];
+        assert_eq!(names(&request), ["d.txt", "keep/sub/b.txt"]);
+
+        // anchored: 前缀按绝对路径匹配
+        request.exclude_regex.clear();
+        let anchored = format!("anchored:{}/keep/**", root.to_string_lossy().replace('\\', "/"));
+        request.exclude_patterns = vec![anchored, "sub/**".to_string()];
+        assert_eq!(names(&request), ["cache/c.txt", "d.txt"]);
+        request.exclude_patterns = vec!["anchored:**/sub/root/cache/**".to_string()];
+        assert_eq!(names(&request), ["d.txt", "keep/sub/b.txt", "sub/a.txt"]);
+        request.exclude_patterns.clear();
+        request.include_regex = vec![r"anchored:[/\\]root[/\\]d\.txt$".to_string()];
+        assert_eq!(names(&request), ["d.txt"]);
+
+        // 以子目录为根时通过 filter_root 沿用原根目录的规则含义
+        let mut request = ScanRequest::new(root.join("keep"));
+        request.exclude_patterns = vec!["sub/**".to_string()];
+        assert_eq!(Scanner::new().scan_sync(&request).unwrap().summary.total_files, 0);
+        request.filter_root = Some(root.clone());
+        assert_eq!(Scanner::new().scan_sync(&request).unwrap().summary.total_files, 1);
+    }
+
+    #[test]
+    fn test_exclude_regex_matches_relative_path() {
+        let dir = tempdir().unwrap();
+        // 根目录本身按日期命名，按完整路径匹配时会排除全部内容
+        let root = dir.path().join("2024-01-01");
+        for path in ["logs/2024-05-01/a.log", "logs/latest/b.log", "2024-06-01/c.log", "d.log"] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"data").unwrap();
+        }
+
+        let mut request = ScanRequest::new(&root);
+        request.exclude_regex = vec![r"(^|/)\d{4}-\d{2}-\d{2}$".to_string()];
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let mut names: Vec<_> = result.top_files.iter().map(|f| f.path.file_name().unwrap().to_owned()).collect();
+        names.sort();
+        assert_eq!(names, ["b.log", "d.log"]);
+
+        // `^` 锚定在根目录：只排除根目录下的日期目录
+        request.exclude_regex = vec![r"^\d{4}-\d{2}-\d{2}$".to_string()];
+        assert_eq!(Scanner::new().scan_sync(&request).unwrap().summary.total_files, 3);
+    }
+
+    #[test]
+    fn test_by_category_from_extensions() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (name, len) in [("a.JPG", 100), ("b.mp4", 300), ("c.rs", 10), ("d.xyz", 5), ("README", 7), ("e.heic", 50)] {
+            File::create(root.join(name)).unwrap().write_all(&vec![0u8; len]).unwrap();
+        }
+        let result = Scanner::new().scan_sync(&ScanRequest::new(root)).unwrap();
+        let rows: Vec<(FileCategory, u64, u64)> =
+            result.by_category.iter().map(|r| (r.category, r.file_count, r.total_size_bytes)).collect();
+        assert_eq!(
+            rows,
+            vec![
+                (FileCategory::Video, 1, 300),
+                (FileCategory::Image, 2, 150),
+                (FileCategory::Other, 2, 12),
+                (FileCategory::Code, 1, 10),
+            ]
+        );
+        assert!((result.by_category[0].share_of_total - 300.0 / 472.0).abs() < 1e-3);
+        assert_eq!(serde_json::to_value(FileCategory::DiskImage).unwrap(), "disk_image");
+
+        // 合并结果由合并后的扩展名统计重新汇总
+        let merged = merge(vec![result.clone(), result]);
+        assert_eq!(merged.by_category.len(), 4);
+        assert_eq!(merged.by_category[0].category, FileCategory::Video);
+    }
+
+    #[test]
+    fn test_detect_mime_from_magic_bytes() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let pad = |head: &[u8], len: usize| {
+            let mut data = head.to_vec();
+            data.resize(len, 0);
+            data
+        };
+        // 改过扩展名的 PNG、无扩展名的 PDF、无法识别的文件与低于阈值的小 PNG
+        fs::write(root.join("photo.txt"), pad(b"\x89PNG\r\n\x1a\n", 4096)).unwrap();
+        fs::write(root.join("report"), pad(b"%PDF-1.7\n", 2048)).unwrap();
+        fs::write(root.join("data.bin"), pad(b"nothing", 2048)).unwrap();
+        fs::write(root.join("tiny.png"), pad(b"\x89PNG\r\n\x1a\n", 100)).unwrap();
+
+        let mut request = ScanRequest::new(root);
+        request.min_size = Some(0);
+        let mime_of = |request: &ScanRequest, name: &str| {
+            let result = Scanner::new().scan_sync(request).unwrap();
+            result.top_files.iter().find(|f| f.path.ends_with(name)).unwrap().mime.clone()
+        };
+        // 默认不读取文件内容
+        assert_eq!(mime_of(&request, "photo.txt"), None);
+
+        request.detect_mime = true;
+        request.mime_min_size = 1024;
+        assert_eq!(mime_of(&request, "photo.txt").as_deref(), Some("image/png"));
+        assert_eq!(mime_of(&request, "report").as_deref(), Some("application/pdf"));
+        assert_eq!(mime_of(&request, "data.bin"), None);
+        assert_eq!(mime_of(&request, "tiny.png"), None);
+
+        assert_eq!(mime::detect(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(mime::detect(b"\0\0\0\x18ftypmp42"), Some("video/mp4"));
+        let docx = mime::detect(b"PK\x03\x04\x14\0\0\0word/document.xml").unwrap();
+        assert_eq!(FileCategory::of_mime(docx), Some(FileCategory::Document));
+        assert_eq!(FileCategory::of_mime("application/x-7z-compressed"), Some(FileCategory::Archive));
+        assert_eq!(FileCategory::of_mime("application/vnd.sqlite3"), None);
+    }
+
+    #[test]
+    fn test_filter_limits_and_match_cost() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for path in ["node_modules/a/b.js", "node_modules/c.js", "src/main.rs", "src/lib.rs"] {
+            let full = root.join(path);
+            fs::create_dir_all(full.parent().unwrap()).unwrap();
+            File::create(full).unwrap().write_all(b"x").unwrap();
+        }
+
+        // `dir/**` 直接剪掉目录本身：目录不计数，其下条目不再逐项匹配
+        let mut request = ScanRequest::new(root);
+        request.exclude_patterns = vec!["**/node_modules/**".to_string()];
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.summary.total_files, 2);
+        assert_eq!(result.summary.total_dirs, 2);
+        // 根目录下 2 个目录项 + src 下 2 个文件
+        assert_eq!(result.diagnostics.filter_checks, 4);
+        assert!(result.diagnostics.filter_match_nanos > 0);
+
+        // 没有规则时不计时
+        let plain = Scanner::new().scan_sync(&ScanRequest::new(root)).unwrap();
+        assert_eq!(plain.diagnostics.filter_checks, 0);
+
+        // 规则数量、长度与展开数量超过上限时在扫描开始前报错
+        let rejected = |request: &ScanRequest| Scanner::new().scan_sync(request).unwrap_err().kind();
+        let mut many = ScanRequest::new(root);
+        many.exclude_patterns = (0..filter::MAX_FILTER_PATTERNS).map(|i| format!("**/f{}", i)).collect();
+        many.exclude_regex = vec!["x".to_string()];
+        assert_eq!(rejected(&many), std::io::ErrorKind::InvalidInput);
+        let mut long = ScanRequest::new(root);
+        long.include_patterns = vec!["a".repeat(filter::MAX_PATTERN_LEN + 1)];
+        assert_eq!(rejected(&long), std::io::ErrorKind::InvalidInput);
+        let mut explosive = ScanRequest::new(root);
+        explosive.exclude_patterns = vec!["{a,b}".repeat(40)];
+        assert_eq!(rejected(&explosive), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_owner_metadata_and_by_owner() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        File::create(root.join("mine.bin")).unwrap().write_all(&[0; 300]).unwrap();
+        let readonly = root.join("readonly.bin");
+        File::create(&readonly).unwrap().write_all(&[0; 100]).unwrap();
+        fs::set_permissions(&readonly, fs::Permissions::from_mode(0o444)).unwrap();
+        // 以 root 运行时把一个文件转给 nobody，验证按属主分组
+        let other = root.join("other.bin");
+        File::create(&other).unwrap().write_all(&[0; 50]).unwrap();
+        let chowned = std::os::unix::fs::chown(&other, Some(65534), None).is_ok();
+        let uid = fs::metadata(root).unwrap().uid();
+
+        let mut request = ScanRequest::new(root);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert!(result.top_files.iter().all(|f| f.owner.is_none()));
+        assert!(result.by_owner.is_empty());
+
+        request.collect_owners = true;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let owner_of = |path: &Path| result.top_files.iter().find(|f| f.path == path).unwrap().owner.clone().unwrap();
+        let ro = owner_of(&readonly);
+        assert_eq!(ro.uid, Some(uid));
+        assert_eq!(ro.mode, Some(0o444));
+        assert!(ro.readonly);
+        assert!(!owner_of(&root.join("mine.bin")).readonly);
+        if uid == 0 {
+            assert_eq!(ro.user.as_deref(), Some("root"));
+        }
+
+        let by_owner: Vec<_> = result.by_owner.iter().map(|s| (s.uid, s.file_count, s.total_size_bytes)).collect();
+        if chowned {
+            assert_eq!(by_owner, vec![(uid, 2, 400), (65534, 1, 50)]);
+        } else {
+            assert_eq!(by_owner, vec![(uid, 3, 450)]);
+        }
+        assert_eq!(result.by_owner[0].user, ro.user);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_same_filesystem_skips_mount_points() {
+        // 需要一个与临时目录位于不同文件系统的可写目录，常见为 tmpfs 挂载的 /dev/shm
+        let Ok(other_fs) = tempfile::tempdir_in("/dev/shm") else {
+            return;
+        };
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        if mount::device_of(other_fs.path()) == mount::device_of(root) {
+            return;
+        }
+        File::create(root.join("local.bin")).unwrap().write_all(&[0; 10]).unwrap();
+        File::create(other_fs.path().join("remote.bin")).unwrap().write_all(&[0; 100]).unwrap();
+        let link = root.join("mnt");
+        std::os::unix::fs::symlink(other_fs.path(), &link).unwrap();
+
+        let mut request = ScanRequest::new(root);
+        request.follow_symlinks = SymlinkPolicy::FollowDirs;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.summary.total_size_bytes, 110);
+
+        request.same_filesystem = true;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.summary.total_size_bytes, 10);
+        assert_eq!(result.summary.total_dirs, 1);
+        assert_eq!(result.diagnostics.skipped_mount_points, vec![link]);
+    }
+
+    #[test]
+    fn test_summary_only_scan_keeps_no_entries() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        File::create(root.join("a/one.log")).unwrap().write_all(&[1; 300]).unwrap();
+        File::create(root.join("a/b/two.log")).unwrap().write_all(&[1; 300]).unwrap();
+        File::create(root.join("three.bin")).unwrap().write_all(&[2; 50]).unwrap();
+
+        let mut request = ScanRequest::new(root);
+        request.stale_days = Some(0);
+        request.dedup = Some(DedupOptions::default());
+        let full = Scanner::new().scan_sync(&request).unwrap();
+        request.collect_entries = false;
+        let summary = Scanner::new().scan_sync(&request).unwrap();
+
+        // 汇总与目录聚合与完整扫描一致
+        assert_eq!(summary.summary.total_files, full.summary.total_files);
+        assert_eq!(summary.summary.total_size_bytes, 650);
+        let extensions = |r: &ScanResult| {
+            r.by_extension.iter().map(|e| (e.extension.clone(), e.file_count, e.total_size_bytes)).collect::<Vec<_>>()
+        };
+        assert_eq!(extensions(&summary), extensions(&full));
+        let top_dirs = |r: &ScanResult| r.top_dirs.iter().map(|d| (d.path.clone(), d.size_bytes)).collect::<Vec<_>>();
+        assert_eq!(top_dirs(&summary), top_dirs(&full));
+        assert_eq!(summary.heatmap, full.heatmap);
+
+        // 不保留文件条目
+        assert!(summary.summary.summary_only && !full.summary.summary_only);
+        assert!(!full.top_files.is_empty() && !full.stale_files.is_empty() && !full.duplicates.is_empty());
+        assert!(summary.top_files.is_empty());
+        assert!(summary.stale_files.is_empty());
+        assert!(summary.duplicates.is_empty());
+
+        // 反序列化时缺省为保留条目
+        let request: ScanRequest = serde_json::from_str(r#"{"root_path": "/tmp"}"#).unwrap();
+        assert!(request.collect_entries);
+    }
+
+    #[test]
+    fn test_top_files_carry_content_digest() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("abc.txt"), b"abc").unwrap();
+        fs::write(root.join("a.txt"), b"a").unwrap();
+
+        let mut request = ScanRequest::new(root);
+        request.limit = Some(1);
+        request.compute_hash = Some(HashAlgo::Sha256);
+        request.hash_max_bytes_per_sec = Some(1024 * 1024);
+        let progress = LiveProgress::new();
+        request.progress = Some(progress.clone());
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.summary.digest_algo, Some(HashAlgo::Sha256));
+        assert_eq!(result.top_files.len(), 1);
+        assert_eq!(
+            result.top_files[0].digest.as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        // 只为 Top N 文件计算摘要
+        assert_eq!(progress.hashed_bytes(), 3);
+
+        for (algo, expected) in [
+            (HashAlgo::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+            (HashAlgo::Blake3, "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"),
+        ] {
+            assert_eq!(content_hash::hash_file(&root.join("abc.txt"), algo).unwrap(), expected);
+            assert_eq!(algo.name().parse::<HashAlgo>(), Ok(algo));
+        }
+
+        // 未请求时不计算
+        let plain = scan_path(root).unwrap();
+        assert!(plain.summary.digest_algo.is_none());
+        assert!(plain.top_files.iter().all(|f| f.digest.is_none()));
+    }
+
+    #[test]
+    fn test_max_depth_limits_traversal() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let deep = root.join("l1/l2/l3");
+        fs::create_dir_all(&deep).unwrap();
+        File::create(root.join("a.bin")).unwrap().write_all(&[0; 10]).unwrap();
+        File::create(root.join("l1/b.bin")).unwrap().write_all(&[0; 20]).unwrap();
+        File::create(root.join("l1/l2/c.bin")).unwrap().write_all(&[0; 40]).unwrap();
+        File::create(deep.join("d.bin")).unwrap().write_all(&[0; 80]).unwrap();
+
+        let scan = |max_depth| {
+            let mut request = ScanRequest::new(root);
+            request.max_depth = max_depth;
+            Scanner::new().scan_sync(&request).unwrap()
+        };
+
+        // 深度 1：读取根目录与 l1，l2 只计数不进入
+        let result = scan(Some(1));
+        assert_eq!(result.summary.total_files, 2);
+        assert_eq!(result.summary.total_size_bytes, 30);
+        assert_eq!(result.summary.total_dirs, 3);
+        assert_eq!(result.diagnostics.depth_limited_dirs, 1);
+
+        // 深度 0：只读取根目录，直接子目录仍计入目录数
+        let result = scan(Some(0));
+        assert_eq!(result.summary.total_files, 1);
+        assert_eq!(result.summary.total_dirs, 2);
+
+        let result = scan(None);
+        assert_eq!(result.summary.total_size_bytes, 150);
+        assert_eq!(result.summary.total_dirs, 4);
+        assert_eq!(result.diagnostics.depth_limited_dirs, 0);
+    }
+
+    #[test]
+    fn test_respect_ignore_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("repo");
+        for (path, len) in [
+            ("src/main.rs", 100),
+            ("src/gen/big.rs", 5000),
+            ("target/debug/app", 9000),
+            ("node_modules/pkg/index.js", 3000),
+            ("web/dist/bundle.js", 2000),
+            ("web/dist/keep.js", 40),
+            ("notes.log", 70),
+        ] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![0u8; len]).unwrap();
+        }
+        fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        fs::write(root.join(".gitignore"), "target/\nnode_modules/\n").unwrap();
+        fs::write(root.join(".surfignore"), "src/gen/\n").unwrap();
+        fs::write(root.join("web/.gitignore"), "dist/*\n!dist/keep.js\n").unwrap();
+
+        let mut request = ScanRequest::new(&root);
+        request.min_size = Some(0);
+        let all = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(all.summary.total_files, 10);
+
+        request.respect_ignore_files = true;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let mut files: Vec<_> = result
+            .top_files
+            .iter()
+            .map(|f| f.path.strip_prefix(&root).unwrap().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        // 祖先目录的 *.log、根目录的 target/ 与 node_modules/、.surfignore 的 src/gen/、
+        // 子目录中带反向规则的 dist/* 均生效；忽略文件本身照常计入
+        assert_eq!(
+            files,
+            vec![".gitignore", ".surfignore", "src/main.rs", "web/.gitignore", "web/dist/keep.js"]
+        );
+        // 被忽略的目录整棵跳过，不计入目录数
+        assert_eq!(result.summary.total_dirs, 4);
+    }
+
+    #[test]
+    fn test_full_disk_access_denial_is_flagged() {
+        // 只有 macOS 上的 EPERM 视为缺少完全磁盘访问权限，普通的 EACCES 不算
+        let eperm = std::io::Error::from_raw_os_error(1);
+        assert_eq!(full_disk_access::is_privacy_denial(&eperm), cfg!(target_os = "macos"));
+        assert!(!full_disk_access::is_privacy_denial(&std::io::Error::from_raw_os_error(13)));
+
+        let dir = tempdir().unwrap();
+        let result = Scanner::new().scan_sync(&ScanRequest::new(dir.path())).unwrap();
+        assert!(!result.summary.full_disk_access_missing);
+
+        // 合并时任一部分缺少权限，合并结果即标记缺少权限
+        let other = tempdir().unwrap();
+        let mut flagged = Scanner::new().scan_sync(&ScanRequest::new(other.path())).unwrap();
+        flagged.summary.full_disk_access_missing = true;
+        assert!(merge(vec![result, flagged]).summary.full_disk_access_missing);
+    }
+
+    #[test]
+    fn test_reclaimable_locations_are_estimated() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let put = |rel: &str, size: usize| {
+            let path = root.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![0u8; size]).unwrap();
+        };
+        put("proj/Cargo.toml", 10);
+        put("proj/target/debug/app", 1000);
+        put("proj/node_modules/x/index.js", 200);
+        // 嵌套的 node_modules 只计入最外层一次
+        put("proj/node_modules/x/node_modules/y/index.js", 300);
+        put("lib/Cargo.toml", 10);
+        put("lib/target/debug/liblib.rlib", 400);
+        // 不含 Cargo.toml 的 target 不是构建目录
+        put("site/target/page.html", 50);
+        put("home/.cache/pip/wheels/a.whl", 70);
+        put("home/.cache/google-chrome/Default/data", 90);
+        put("home/.local/share/Trash/files/old.iso", 800);
+        put("home/Library/Developer/Xcode/DerivedData/App-abc/Build/app", 600);
+        put("home/AppData/Local/Google/Chrome/User Data/Default/Cache/f_0001", 80);
+
+        let mut request = ScanRequest::new(root);
+        request.limit = Some(1);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let report = &result.reclaimable_report;
+        let kinds: Vec<_> = report.categories.iter().map(|s| (s.kind, s.size_bytes, s.location_count)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (ReclaimableKind::CargoTarget, 1400, 2),
+                (ReclaimableKind::Trash, 800, 1),
+                (ReclaimableKind::XcodeDerivedData, 600, 1),
+                (ReclaimableKind::NodeModules, 500, 1),
+                (ReclaimableKind::BrowserCache, 170, 2),
+                (ReclaimableKind::PackageCache, 70, 1),
+            ]
+        );
+        assert_eq!(report.total_bytes, 3540);
+        let cargo = report.category(ReclaimableKind::CargoTarget).unwrap();
+        // 每个类别最多列出 limit 个位置
+        assert_eq!(cargo.locations.len(), 1);
+        assert_eq!(cargo.locations[0].path, root.join("proj/target"));
+        assert_eq!(cargo.file_count, 2);
+        assert_eq!(report.category(ReclaimableKind::NodeModules).unwrap().file_count, 2);
+
+        // 合并时类别总量相加
+        let other = tempdir().unwrap();
+        fs::create_dir_all(other.path().join("node_modules")).unwrap();
+        fs::write(other.path().join("node_modules/pkg.js"), [0u8; 25]).unwrap();
+        let second = Scanner::new().scan_sync(&ScanRequest::new(other.path())).unwrap();
+        let merged = merge(vec![result, second]);
+        let node_modules = merged.reclaimable_report.category(ReclaimableKind::NodeModules).unwrap();
+        assert_eq!((node_modules.size_bytes, node_modules.location_count), (525, 2));
+        assert_eq!(merged.reclaimable_report.total_bytes, 3565);
+    }
+
+    #[test]
+    fn test_compression_candidates_by_extension_and_sampling() {
+        use compression::CompressionBasis;
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let old = SystemTime::now() - Duration::from_secs(200 * 86_400);
+        let put = |name: &str, data: Vec<u8>, modified: SystemTime| {
+            let path = root.join(name);
+            fs::write(&path, data).unwrap();
+            File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        };
+        put("old.log", b"GET /index.html 200\n".repeat(100), old);
+        put("recent.log", vec![b'x'; 2000], SystemTime::now());
+        put("small.log", vec![b'x'; 100], old);
+        put("archive.zip", vec![0u8; 3000], old);
+        put("zeros.bin", vec![0u8; 4000], old);
+        put("noise.bin", (0..4096).map(|i| i as u8).collect(), old);
+
+        let mut request = ScanRequest::new(root);
+        request.compression = Some(CompressionOptions { min_size: 1000, min_age_days: 90, sample_bytes: 0 });
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        // 不采样时只有扩展名已知的旧文件入选，已压缩格式与未知扩展名被排除
+        let candidates = &result.compression_candidates;
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].path, root.join("old.log"));
+        assert_eq!(candidates[0].basis, CompressionBasis::Extension);
+        assert_eq!(candidates[0].estimated_ratio, 0.2);
+        assert_eq!(candidates[0].estimated_savings_bytes, 1600);
+        assert_eq!(result.summary.compression_candidate_count, 1);
+        assert_eq!(result.summary.compression_savings_bytes, 1600);
+
+        // 采样时按熵估计：全零文件几乎可以完全压缩，均匀分布的字节不可压缩
+        request.compression = Some(CompressionOptions { min_size: 1000, min_age_days: 90, sample_bytes: 1024 });
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let paths: Vec<_> = result.compression_candidates.iter().map(|c| c.path.clone()).collect();
+        assert_eq!(paths, vec![root.join("zeros.bin"), root.join("old.log")]);
+        let zeros = &result.compression_candidates[0];
+        assert_eq!((zeros.basis, zeros.entropy_bits, zeros.estimated_savings_bytes), (CompressionBasis::Sampled, Some(0.0), 4000));
+        assert!(result.compression_candidates[1].entropy_bits.is_some_and(|bits| bits > 0.0 && bits < 8.0));
+
+        // 列表按 Top N 截断，汇总仍计入全部候选文件
+        request.limit = Some(1);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.compression_candidates.len(), 1);
+        assert_eq!(result.summary.compression_candidate_count, 2);
+        assert!(result.summary.compression_savings_bytes > 4000);
+    }
+
+    #[test]
+    fn test_system_reserved_entries_are_labelled() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        File::create(root.join("pagefile.sys")).unwrap().write_all(&[0; 300]).unwrap();
+        File::create(root.join("HIBERFIL.SYS")).unwrap().write_all(&[0; 200]).unwrap();
+        File::create(root.join("data.bin")).unwrap().write_all(&[0; 50]).unwrap();
+        let svi = root.join("System Volume Information");
+        fs::create_dir(&svi).unwrap();
+        File::create(svi.join("tracking.log")).unwrap().write_all(&[0; 100]).unwrap();
+        // 同名的普通文件与目录不是系统保留条目
+        fs::create_dir(root.join("pagefile.sys.d")).unwrap();
+
+        let result = Scanner::new().scan_sync(&ScanRequest::new(root)).unwrap();
+        // 照常计入总量
+        assert_eq!(result.summary.total_size_bytes, 650);
+        let reserved: Vec<_> = result
+            .system_reserved
+            .iter()
+            .map(|e| (e.path.strip_prefix(root).unwrap().to_path_buf(), e.kind, e.size_bytes))
+            .collect();
+        assert_eq!(
+            reserved,
+            vec![
+                (PathBuf::from("pagefile.sys"), ReservedKind::PageFile, 300),
+                (PathBuf::from("HIBERFIL.SYS"), ReservedKind::Hibernation, 200),
+                (PathBuf::from("System Volume Information"), ReservedKind::VolumeInformation, 100),
+            ]
+        );
+        let kind_of = |name: &str| result.top_files.iter().find(|f| f.path == root.join(name)).unwrap().reserved;
+        assert_eq!(kind_of("pagefile.sys"), Some(ReservedKind::PageFile));
+        assert_eq!(kind_of("data.bin"), None);
+        // 目录内的文件本身不标注
+        assert_eq!(kind_of("System Volume Information/tracking.log"), None);
+    }
+
+    #[test]
+    fn test_concurrency_hints_split_large_dirs() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let big = root.join("big");
+        fs::create_dir(&big).unwrap();
+        for i in 0..3000 {
+            File::create(big.join(format!("f{}.dat", i))).unwrap().write_all(b"x").unwrap();
+        }
+
+        // 首次扫描：无提示，记录大目录的条目数
+        let scanner = Scanner::new();
+        let first = scanner.scan_sync(&ScanRequest::new(root)).unwrap();
+        assert!(!first.diagnostics.hints_applied);
+        let hints = first.concurrency_hints.clone().unwrap();
+        assert_eq!(hints.entries_for(&big), Some(3000));
+        assert_eq!(first.diagnostics.recorded_hints, 1);
+
+        // 提示可保存并重新加载
+        let cache = tempdir().unwrap();
+        let hints_path = cache.path().join("hints.json");
+        hints.save(&hints_path).unwrap();
+        let loaded = ConcurrencyHints::load(&hints_path).unwrap();
+        assert_eq!(loaded.entries_for(&big), Some(3000));
+
+        // 再次扫描：大目录被拆分为多个批次，统计结果保持一致
+        let mut request = ScanRequest::new(root);
+        request.concurrency_hints = Some(Arc::new(loaded));
+        let second = scanner.scan_sync(&request).unwrap();
+        assert!(second.diagnostics.hints_applied);
+        assert_eq!(second.diagnostics.hinted_dirs, 1);
+        assert_eq!(second.diagnostics.split_batches, 6);
+        assert_eq!(second.summary.total_files, first.summary.total_files);
+        assert_eq!(second.summary.total_dirs, first.summary.total_dirs);
+    }
+
+    #[test]
+    fn test_shard_scan_and_merge_matches_full_scan() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        File::create(root.join("root.txt")).unwrap().write_all(b"root file").unwrap();
+        for (i, name) in ["a", "b", "c"].iter().enumerate() {
+            let sub = root.join(name);
+            fs::create_dir(&sub).unwrap();
+            File::create(sub.join("data.bin")).unwrap().write_all(&vec![b'x'; 100 * (i + 1)]).unwrap();
+        }
+
+        let manifest = ShardManifest::plan(root, 2).unwrap();
+        assert_eq!(manifest.shards, vec![vec!["a".to_string(), "c".to_string()], vec!["b".to_string()]]);
+
+        // 两个进程依次认领分片，第三次认领时已无剩余分片
+        let work = tempdir().unwrap();
+        let manifest_path = work.path().join("manifest.json");
+        manifest.save(&manifest_path).unwrap();
+        let manifest = ShardManifest::load(&manifest_path).unwrap();
+        assert_eq!(manifest.claim_next(&manifest_path).unwrap(), Some(0));
+        assert_eq!(manifest.claim_next(&manifest_path).unwrap(), Some(1));
+        assert_eq!(manifest.claim_next(&manifest_path).unwrap(), None);
+
+        let scanner = Scanner::new();
+        let shard_results: Vec<ScanResult> = (0..2)
+            .map(|i| scanner.scan_sync(&manifest.request_for(i).unwrap()).unwrap())
+            .collect();
+        assert_eq!(shard_results[0].summary.total_files, 3); // root.txt + a + c
+        assert_eq!(shard_results[1].summary.total_files, 1);
+
+        assert_eq!(shard_results[1].summary.shard, manifest.assignment(1));
+        // 结果顺序不影响合并
+        let merged = manifest.merge(shard_results.iter().rev().cloned().collect()).unwrap();
+        assert_eq!(merged.summary.shard, None);
+        let full = scanner.scan_sync(&ScanRequest::new(root)).unwrap();
+        assert_eq!(merged.summary.root_path, full.summary.root_path);
+        assert_eq!(merged.summary.total_files, full.summary.total_files);
+        assert_eq!(merged.summary.total_dirs, full.summary.total_dirs);
+        assert_eq!(merged.summary.total_size_bytes, full.summary.total_size_bytes);
+        let sizes = |r: &ScanResult| r.top_files.iter().map(|f| f.size_bytes).collect::<Vec<_>>();
+        assert_eq!(sizes(&merged), sizes(&full));
+        assert_eq!(merged.by_extension.len(), full.by_extension.len());
+
+        // 缺少分片结果、同一分片重复、非分片结果或其他清单的分片时拒绝合并
+        assert!(manifest.merge(Vec::new()).is_err());
+        let duplicated = vec![shard_results[0].clone(), shard_results[0].clone()];
+        assert!(manifest.merge(duplicated).unwrap_err().to_string().contains("重复"));
+        assert!(manifest.merge(vec![shard_results[0].clone(), full.clone()]).is_err());
+        let other = ShardManifest::plan(root, 3).unwrap();
+        let foreign = scanner.scan_sync(&other.request_for(0).unwrap()).unwrap();
+        assert!(manifest.merge(vec![foreign, shard_results[1].clone()]).is_err());
+    }
+
+    #[test]
+    fn test_scan_multiple_roots_concurrently() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (name, sizes) in [("home", [100usize, 200]), ("var", [300, 400])] {
+            fs::create_dir_all(root.join(name).join("sub")).unwrap();
+            fs::write(root.join(name).join("a.bin"), vec![0u8; sizes[0]]).unwrap();
+            fs::write(root.join(name).join("sub/b.bin"), vec![0u8; sizes[1]]).unwrap();
+        }
+
+        // 位于另一根目录之下与重复的根目录不再扫描
+        let mut request =
+            ScanRequest::for_roots([root.join("home"), root.join("var"), root.join("var/sub"), root.join("home")]);
+        let live = LiveProgress::new();
+        request.progress = Some(live.clone());
+        request.threads = Some(4);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(request.root_path, root.join("home"));
+        assert_eq!(result.summary.root_path, fs::canonicalize(root).unwrap());
+        assert_eq!((result.summary.total_files, result.summary.total_size_bytes), (4, 1000));
+        assert_eq!(result.summary.total_dirs, 4);
+        let per_root: Vec<_> = result
+            .root_summaries
+            .iter()
+            .map(|s| (s.root_path.clone(), s.total_files, s.total_size_bytes))
+            .collect();
+        assert_eq!(per_root, vec![(root.join("home"), 2, 300), (root.join("var"), 2, 700)]);
+        assert_eq!(result.diagnostics.overlapping_roots, vec![root.join("var/sub"), root.join("home")]);
+        let sizes: Vec<u64> = result.top_files.iter().map(|f| f.size_bytes).collect();
+        assert_eq!(sizes, vec![400, 300, 200, 100]);
+        // 调用方的进度计数汇总全部根目录
+        assert_eq!((live.scanned_files(), live.scanned_bytes()), (4, 1000));
+
+        // 任一根目录不存在时整个请求失败
+        let missing = ScanRequest::for_roots([root.join("home"), root.join("missing")]);
+        assert!(matches!(Scanner::new().scan_sync(&missing), Err(ScanError::RootNotFound { .. })));
+    }
+
+    #[test]
+    fn test_merge_results_dedups_overlapping_roots() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (i, name) in ["a", "b"].iter().enumerate() {
+            let sub = root.join(name);
+            fs::create_dir(&sub).unwrap();
+            for j in 0..3 {
+                File::create(sub.join(format!("f{}.bin", j)))
+                    .unwrap()
+                    .write_all(&vec![b'x'; 10 * (i * 3 + j + 1)])
+                    .unwrap();
+            }
+        }
+        let scanner = Scanner::new();
+        let scan = |path: PathBuf| {
+            let mut request = ScanRequest::new(path);
+            request.limit = Some(4);
+            scanner.scan_sync(&request).unwrap()
+        };
+        let a = scan(root.join("a"));
+        let b = scan(root.join("b"));
+
+        // 互不重叠的根目录：求和，Top N 按 limit 重新截断，根目录为公共祖先
+        let merged = merge(vec![a.clone(), b.clone()]);
+        assert_eq!(merged.summary.total_files, 6);
+        assert_eq!(merged.summary.total_dirs, 2);
+        assert_eq!(merged.summary.total_size_bytes, a.summary.total_size_bytes + b.summary.total_size_bytes);
+        assert_eq!(merged.summary.root_path, root);
+        let sizes: Vec<u64> = merged.top_files.iter().map(|f| f.size_bytes).collect();
+        assert_eq!(sizes, vec![60, 50, 40, 30]);
+        assert!(merged.diagnostics.overlapping_roots.is_empty());
+
+        // 父目录覆盖子目录：子目录结果被跳过，不重复计数
+        let full = scan(root.to_path_buf());
+        let merged = merge(vec![a, full.clone(), b]);
+        assert_eq!(merged.summary.total_files, full.summary.total_files);
+        assert_eq!(merged.summary.total_size_bytes, full.summary.total_size_bytes);
+        assert_eq!(merged.diagnostics.overlapping_roots.len(), 2);
+
+        // 经由符号链接给出的根目录：合并根目录与条目路径都沿用链接形式，重叠仍按实际路径判断
+        #[cfg(unix)]
+        {
+            let link = tempdir().unwrap();
+            let linked = link.path().join("data");
+            std::os::unix::fs::symlink(root, &linked).unwrap();
+            let merged = merge(vec![scan(linked.join("a")), scan(linked.join("b")), scan(root.join("b"))]);
+            assert_eq!(merged.summary.root_path, linked);
+            assert_eq!(merged.diagnostics.overlapping_roots, vec![root.join("b")]);
+            assert_eq!(merged.summary.total_files, 6);
+            assert!(merged.top_files.iter().all(|f| f.path.starts_with(&linked)));
+            let rows: Vec<_> = merged.top_level.iter().map(|r| r.path.clone()).collect();
+            assert_eq!(rows, vec![linked.join("b"), linked.join("a")]);
+            assert!(!merged.directories.is_empty() && merged.directories.iter().all(|d| d.path.starts_with(&linked)));
+        }
+    }
+
+    #[test]
+    fn test_index_daemon_reconciles_changes() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        File::create(root.join("a.txt")).unwrap().write_all(b"hello").unwrap();
+        let cache = tempdir().unwrap();
+        let cache_path = cache.path().join("index.json");
+
+        let options = IndexOptions {
+            interval: std::time::Duration::from_secs(3600),
+            max_age: std::time::Duration::from_secs(3600),
+            cache_path: Some(cache_path.clone()),
+        };
+        let daemon = IndexDaemon::start(ScanRequest::new(root), options.clone());
+        // 首次对账与后台线程竞争时由锁串行化；未变化时不会重复扫描
+        daemon.refresh().unwrap();
+        let first = daemon.snapshot().expect("首次扫描后应有快照");
+        assert_eq!(first.result.summary.total_files, 1);
+        assert!(!daemon.refresh().unwrap());
+
+        // 新增子目录改变目录结构指纹，触发重新扫描（可能由后台线程或本次 refresh 完成）
+        fs::create_dir(root.join("sub")).unwrap();
+        File::create(root.join("sub").join("b.txt")).unwrap().write_all(b"world").unwrap();
+        daemon.refresh().unwrap();
+        let second = daemon.snapshot().unwrap();
+        assert_eq!(second.result.summary.total_files, 2);
+        assert!(second.generation > first.generation);
+        assert!(daemon.status().ready);
+        drop(daemon);
+
+        // 重启后立即从持久化快照应答
+        let restarted = IndexDaemon::start(ScanRequest::new(root), options);
+        let persisted = restarted.snapshot().expect("应加载持久化快照");
+        assert!(persisted.generation >= second.generation);
+        assert_eq!(persisted.result.summary.total_files, 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_sections_from_subprocess_analyzer() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for i in 0..3 {
+            File::create(root.join(format!("f{}.txt", i))).unwrap().write_all(b"data").unwrap();
+        }
+
+        // 插件：统计收到的条目数并输出一行 JSON
+        let plugin_dir = tempdir().unwrap();
+        let script = plugin_dir.path().join("counter.sh");
+        fs::write(
+            &script,
+            "#!/bin/sh\nn=$(grep -c '\"type\":\"entry\"')\necho \"{\\\"entries\\\": $n}\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(plugin_dir.path().join("README"), "not a plugin").unwrap();
+
+        let plugins = plugin::discover(plugin_dir.path()).unwrap();
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].name, "counter");
+
+        let mut request = ScanRequest::new(root);
+        request.plugins = plugins;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.plugin_sections["counter"]["entries"], 3);
+
+        // 无法启动的插件只产生错误结果段
+        request.plugins = vec![PluginSpec {
+            name: "missing".to_string(),
+            command: plugin_dir.path().join("does-not-exist"),
+        }];
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert!(result.plugin_sections["missing"]["error"].is_string());
+        assert_eq!(result.summary.total_files, 3);
+
+        // 插件不参与序列化
+        request.plugins = plugin::discover(plugin_dir.path()).unwrap();
+        let mut json = serde_json::to_value(&request).unwrap();
+        assert!(json.get("plugins").is_none());
+        json["plugins"] = serde_json::json!([{"name": "x", "command": "/bin/true"}]);
+        assert!(serde_json::from_value::<ScanRequest>(json).unwrap().plugins.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_receives_millis_for_pre_epoch_mtime() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let old = File::create(root.join("old.txt")).unwrap();
+        old.set_modified(UNIX_EPOCH - Duration::from_secs(86_400)).unwrap();
+        fs::write(root.join("new.txt"), b"data").unwrap();
+
+        // 插件：输出收到的条目数、是否收到结束标记与最小的 last_modified
+        let plugin_dir = tempdir().unwrap();
+        let script = plugin_dir.path().join("mtime.sh");
+        fs::write(
+            &script,
+            concat!(
+                "#!/bin/sh
+",
+                "input=$(cat)
+",
+                "n=$(printf '%s\n' \"$input\" | grep -c '\"type\":\"entry\"')
+",
+                "end=$(printf '%s\n' \"$input\" | grep -c '\"type\":\"end\"')
+",
+                "min=$(printf '%s\n' \"$input\" | grep -o '\"last_modified\":-*[0-9]*' | cut -d: -f2 | sort -n | head -1)
+",
+                "echo \"{\\\"entries\\\": $n, \\\"end\\\": $end, \\\"min\\\": $min}\"
+",
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut request = ScanRequest::new(root);
+        request.plugins = plugin::discover(plugin_dir.path()).unwrap();
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let section = &result.plugin_sections["mtime"];
+        assert_eq!(section["entries"], 2);
+        assert_eq!(section["end"], 1);
+        assert_eq!(section["min"], -86_400_000);
+    }
+
+    #[test]
+    fn test_access_log_coldness_for_stale_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let old = SystemTime::now() - Duration::from_secs(400 * 24 * 3600);
+        for name in ["hot.dat", "warm.dat", "cold.dat"] {
+            let file = File::create(root.join(name)).unwrap();
+            file.set_modified(old).unwrap();
+        }
+
+        // hot 近期被读取过；warm 访问多次但很久以前；cold 没有访问记录
+        let now_secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let canonical = fs::canonicalize(root).unwrap();
+        let mut log = String::from("# access log\n");
+        log.push_str(&format!("{}\t{}\n", now_secs - 3600, canonical.join("hot.dat").display()));
+        for _ in 0..3 {
+            log.push_str(&format!("{}\n", canonical.join("warm.dat").display()));
+        }
+        let access_log = AccessLog::parse(log.as_bytes()).unwrap();
+        assert_eq!(access_log.len(), 2);
+
+        let mut request = ScanRequest::new(root);
+        request.stale_days = Some(90);
+        request.access_log = Some(Arc::new(access_log));
+        let result = Scanner::new().scan_sync(&request).unwrap();
+
+        // hot 的最近活动时间为日志中的访问时间，不再视为陈旧
+        let names: Vec<String> = result
+            .stale_files
+            .iter()
+            .map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["cold.dat", "warm.dat"]);
+        let cold = result.stale_files[0].coldness.unwrap();
+        let warm = result.stale_files[1].coldness.unwrap();
+        assert!(cold > 0.9 && cold < 1.0);
+        assert!((warm - cold / 4.0).abs() < 1e-9);
+
+        // 未提供访问日志时仍按 mtime 判断，且不输出冷度
+        request.access_log = None;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.stale_files.len(), 3);
+        assert!(result.stale_files.iter().all(|f| f.coldness.is_none()));
+    }
+
+    #[test]
+    fn test_age_histogram_buckets() {
+        const DAY: Duration = Duration::from_secs(24 * 3600);
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (name, len, modified) in [
+            ("a/new.dat", 100, now - DAY),
+            ("a/week.dat", 200, now - 7 * DAY),
+            ("b/month.dat", 300, now - 45 * DAY),
+            ("b/old1.dat", 400, now - 400 * DAY),
+            ("old2.dat", 500, now - 4000 * DAY),
+            // 未来时间校正为当前时间
+            ("future.dat", 50, now + 30 * DAY),
+        ] {
+            let path = root.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            let mut file = File::create(path).unwrap();
+            file.write_all(&vec![0u8; len]).unwrap();
+            file.set_modified(modified).unwrap();
+        }
+
+        let mut request = ScanRequest::new(root);
+        request.clock = Some(Arc::new(FixedClock(now)));
+        request.collect_entries = false;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let rows: Vec<_> = result
+            .age_histogram
+            .iter()
+            .map(|b| (b.label.as_str(), b.min_days, b.max_days, b.file_count, b.size_bytes))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("<7d", Some(0), Some(7), 2, 150),
+                ("7-30d", Some(7), Some(30), 1, 200),
+                ("30-90d", Some(30), Some(90), 1, 300),
+                ("90d-1y", Some(90), Some(365), 0, 0),
+                (">1y", Some(365), None, 2, 900),
+                ("unknown", None, None, 0, 0),
+            ]
+        );
+        assert_eq!(result.age_histogram[4].share_of_total, 0.5806);
+
+        // 多份结果按年龄段累加
+        let parts = ["a", "b"].map(|p| {
+            request.root_path = root.join(p);
+            Scanner::new().scan_sync(&request).unwrap()
+        });
+        let merged = merge(parts.to_vec());
+        let counts: Vec<_> = merged.age_histogram.iter().map(|b| b.file_count).collect();
+        assert_eq!(counts, vec![1, 1, 1, 0, 1, 0]);
+        assert_eq!(merged.age_histogram[0].share_of_total, 0.1);
+    }
+
+    #[test]
+    fn test_size_histograms_by_extension_and_category() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (name, len) in [("a/x.mp4", 500), ("a/y.MP4", 5000), ("b/z.mov", 2_000_000), ("b/w.txt", 100)] {
+            let path = root.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![0u8; len]).unwrap();
+        }
+
+        // 扩展名不区分大小写、可带前导点，重复的选择器只统计一次；仅汇总扫描同样统计
+        let mut request = ScanRequest::new(root);
+        request.min_size = Some(0);
+        request.collect_entries = false;
+        request.size_histograms = vec!["mp4".into(), "category:video".into(), ".MP4".into()];
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let selectors: Vec<_> = result.size_histograms.iter().map(|h| (h.selector.as_str(), h.file_count, h.size_bytes)).collect();
+        assert_eq!(selectors, vec![("mp4", 2, 5500), ("category:video", 3, 2_005_500)]);
+        let mp4 = &result.size_histograms[0];
+        assert_eq!(mp4.buckets.len(), size_histogram::SIZE_BUCKETS);
+        let filled: Vec<_> = mp4
+            .buckets
+            .iter()
+            .filter(|b| b.file_count > 0)
+            .map(|b| (b.min_bytes, b.max_bytes, b.size_bytes, b.share_of_selector))
+            .collect();
+        assert_eq!(filled, vec![(0, Some(1024), 500, 0.0909), (4096, Some(16384), 5000, 0.9091)]);
+        assert_eq!(mp4.buckets.last().unwrap().max_bytes, None);
+        assert_eq!(result.size_histograms[1].buckets[6].min_bytes, 1 << 20);
+        assert_eq!(result.size_histograms[1].buckets[6].file_count, 1);
+
+        // 多份结果按选择器与分段累加
+        let parts = ["a", "b"].map(|p| {
+            request.root_path = root.join(p);
+            Scanner::new().scan_sync(&request).unwrap()
+        });
+        let merged = merge(parts.to_vec());
+        assert_eq!(merged.size_histograms, result.size_histograms);
+
+        let csv = size_histogram::to_csv(&result.size_histograms);
+        assert_eq!(csv.lines().count(), 1 + 2 * size_histogram::SIZE_BUCKETS);
+        assert!(csv.starts_with("selector,min_bytes,max_bytes,file_count,size_bytes,share_of_selector\n"));
+        assert!(csv.contains("\nmp4,4096,16384,1,5000,0.9091\n"));
+        assert!(csv.contains("\nmp4,17179869184,,0,0,0\n"));
+
+        request.size_histograms = vec!["category:nope".into()];
+        assert!(matches!(Scanner::new().scan_sync(&request), Err(ScanError::InvalidRequest { .. })));
+    }
+
+    #[test]
+    fn test_stale_files_with_fixed_clock() {
+        const DAY: Duration = Duration::from_secs(24 * 3600);
+        // 固定的“当前时间”，不依赖真实时钟
+        let base = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let files = [
+            ("recent.dat", base - DAY),
+            ("boundary.dat", base - 90 * DAY),
+            ("future.dat", base + 10 * DAY),
+            ("epoch.dat", UNIX_EPOCH),
+        ];
+        for (name, modified) in files {
+            let mut file = File::create(root.join(name)).unwrap();
+            file.write_all(b"x").unwrap();
+            file.set_modified(modified).unwrap();
+        }
+
+        let scan_at = |now: SystemTime| {
+            let mut request = ScanRequest::new(root);
+            request.stale_days = Some(90);
+            request.clock = Some(Arc::new(FixedClock(now)));
+            let result = Scanner::new().scan_sync(&request).unwrap();
+            let mut names: Vec<String> = result
+                .stale_files
+                .iter()
+                .map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            (names, result.heatmap[0].age_bytes.clone())
+        };
+
+        // 恰好满 90 天即视为陈旧；未来的 mtime 校正为当前时间，不陈旧；纪元零点校正为 1980 年，按最旧处理
+        let (stale, ages) = scan_at(base);
+        assert_eq!(stale, vec!["boundary.dat", "epoch.dat"]);
+        assert_eq!(ages, vec![2, 0, 1, 0, 1, 0]);
+
+        // 模拟 90 天之后：最近修改的文件也变为陈旧，未来的 mtime 此时只过去 80 天
+        let (stale, _) = scan_at(base + 90 * DAY);
+        assert_eq!(stale, vec!["boundary.dat", "epoch.dat", "recent.dat"]);
+
+        // 时钟早于所有 mtime（纪元零点）时没有陈旧文件，年龄无法计算
+        let (stale, ages) = scan_at(UNIX_EPOCH);
+        assert!(stale.is_empty());
+        assert_eq!(ages, vec![0, 0, 0, 0, 0, 4]);
+
+        assert_eq!(access::stale_idle(base, base, 0), Some(Duration::ZERO));
+        assert_eq!(access::stale_idle(base + DAY, base, 0), None);
+    }
+
+    #[test]
+    fn test_stale_mode_access_and_creation_time() {
+        const DAY: Duration = Duration::from_secs(24 * 3600);
+        let base = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        // 一个很久没改但最近读过，一个最近改过但很久没读
+        for (name, modified, accessed) in [
+            ("edited_long_ago.dat", base - 200 * DAY, base - DAY),
+            ("read_long_ago.dat", base - DAY, base - 200 * DAY),
+        ] {
+            let file = File::create(root.join(name)).unwrap();
+            file.set_times(std::fs::FileTimes::new().set_modified(modified).set_accessed(accessed)).unwrap();
+        }
+
+        let scan = |mode: StaleMode, now: SystemTime| {
+            let mut request = ScanRequest::new(root);
+            request.stale_days = Some(90);
+            request.stale_mode = mode;
+            request.clock = Some(Arc::new(FixedClock(now)));
+            let result = Scanner::new().scan_sync(&request).unwrap();
+            let mut names: Vec<String> = result
+                .stale_files
+                .iter()
+                .map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            (names, result)
+        };
+
+        let (stale, result) = scan(StaleMode::Modified, base);
+        assert_eq!(stale, vec!["edited_long_ago.dat"]);
+        assert_eq!(result.stale_files[0].last_accessed, Some(base - DAY));
+        assert_eq!(scan(StaleMode::Accessed, base).0, vec!["read_long_ago.dat"]);
+        // 最近一次修改或访问都在阈值之内，按“任何方式都没动过”判断均不陈旧
+        assert!(scan(StaleMode::Any, base).0.is_empty());
+
+        // 创建时间为测试运行时刻；文件系统不记录创建时间时无法判断
+        let later = SystemTime::now() + 100 * DAY;
+        let created = std::fs::metadata(root.join("read_long_ago.dat")).unwrap().created().is_ok();
+        let expected: Vec<&str> = if created { vec!["edited_long_ago.dat", "read_long_ago.dat"] } else { vec![] };
+        assert_eq!(scan(StaleMode::Created, later).0, expected);
+        assert!(scan(StaleMode::Created, base).0.is_empty());
+
+        assert_eq!("atime".parse::<StaleMode>(), Ok(StaleMode::Accessed));
+        assert!("ctime".parse::<StaleMode>().is_err());
+    }
+
+    #[test]
+    fn test_stale_files_bounded_and_sorted() {
+        const DAY: Duration = Duration::from_secs(24 * 3600);
+        let base = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (name, size, age_days) in [("a.dat", 10, 400), ("b.dat", 40, 100), ("c.dat", 30, 300), ("d.dat", 20, 200), ("new.dat", 99, 1)] {
+            let mut file = File::create(root.join(name)).unwrap();
+            file.write_all(&vec![0u8; size]).unwrap();
+            file.set_modified(base - age_days * DAY).unwrap();
+        }
+
+        let scan = |sort: Option<StaleSort>, limit: Option<usize>| {
+            let mut request = ScanRequest::new(root);
+            request.stale_days = Some(90);
+            request.stale_sort = sort;
+            request.stale_limit = limit;
+            request.clock = Some(Arc::new(FixedClock(base)));
+            Scanner::new().scan_sync(&request).unwrap()
+        };
+        let names = |result: &ScanResult| -> Vec<String> {
+            result.stale_files.iter().map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned()).collect()
+        };
+
+        // 默认按空闲时间降序、全部保留
+        let all = scan(None, None);
+        assert_eq!(names(&all), vec!["a.dat", "c.dat", "d.dat", "b.dat"]);
+        assert_eq!(all.stale_files[0].idle_seconds, Some(400 * 86400));
+        assert_eq!(all.summary.stale_sort, StaleSort::Age);
+
+        // 有界时只保留排名靠前者
+        assert_eq!(names(&scan(Some(StaleSort::Size), Some(2))), vec!["b.dat", "c.dat"]);
+        assert_eq!(names(&scan(Some(StaleSort::Age), Some(2))), vec!["a.dat", "c.dat"]);
+        assert!(scan(None, Some(0)).stale_files.is_empty());
+
+        // 合并时按同一依据重新排序并截断
+        let merged = merge(vec![scan(Some(StaleSort::Size), Some(2)), scan(Some(StaleSort::Size), Some(2))]);
+        assert_eq!(names(&merged), vec!["b.dat", "c.dat"]);
+        assert_eq!(merged.summary.stale_limit, Some(2));
+    }
+
+    #[test]
+    fn test_oldest_and_newest_files_by_mtime() {
+        const DAY: Duration = Duration::from_secs(24 * 3600);
+        let base = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let files = [
+            ("a/ancient.dat", base - 3000 * DAY),
+            ("a/old.dat", base - 400 * DAY),
+            ("b/recent.dat", base - DAY),
+            ("b/fresh.dat", base - Duration::from_secs(60)),
+            ("month.dat", base - 30 * DAY),
+            ("epoch.dat", UNIX_EPOCH),
+            ("future.dat", base + 400 * DAY),
+        ];
+        for (name, modified) in files {
+            let path = root.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            let mut file = File::create(path).unwrap();
+            file.write_all(b"x").unwrap();
+            file.set_modified(modified).unwrap();
+        }
+        let names = |files: &[FileEntry]| {
+            files.iter().map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned()).collect::<Vec<_>>()
+        };
+
+        let mut request = ScanRequest::new(root);
+        request.clock = Some(Arc::new(FixedClock(base)));
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        // 未请求时不收集
+        assert!(result.oldest_files.is_empty() && result.newest_files.is_empty());
+
+        // 可疑时间戳不参与排名；数量独立于 Top N
+        request.recency_limit = Some(2);
+        request.limit = Some(1);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(names(&result.oldest_files), vec!["ancient.dat", "old.dat"]);
+        assert_eq!(names(&result.newest_files), vec!["fresh.dat", "recent.dat"]);
+        assert_eq!(result.top_files.len(), 1);
+        assert_eq!(result.newest_files[0].depth, Some(2));
+        assert_eq!(result.summary.recency_limit, Some(2));
+
+        // 合并时按各结果中最大的条目数重新排序并截断
+        let scan_sub = |name: &str, limit: usize| {
+            let mut sub = ScanRequest::new(root.join(name));
+            sub.clock = request.clock.clone();
+            sub.recency_limit = Some(limit);
+            Scanner::new().scan_sync(&sub).unwrap()
+        };
+        let merged = merge(vec![scan_sub("a", 1), scan_sub("b", 2)]);
+        assert_eq!(names(&merged.oldest_files), vec!["ancient.dat", "recent.dat"]);
+        assert_eq!(names(&merged.newest_files), vec!["fresh.dat", "recent.dat"]);
+
+        // 仅汇总扫描不保留列表
+        request.collect_entries = false;
+        assert!(Scanner::new().scan_sync(&request).unwrap().oldest_files.is_empty());
+    }
+
+    #[test]
+    fn test_extension_top_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let files = [
+            ("a/one.mp4", 500),
+            ("a/two.mp4", 300),
+            ("b/three.mp4", 400),
+            ("b/four.mp4", 100),
+            ("a/notes.txt", 50),
+            ("b/Makefile", 20),
+        ];
+        for (name, size) in files {
+            let path = root.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            File::create(path).unwrap().write_all(&vec![b'x'; size]).unwrap();
+        }
+        let top = |result: &ScanResult, extension: &str| {
+            let stat = result.by_extension.iter().find(|s| s.extension == extension).unwrap();
+            stat.top_files.iter().map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned()).collect::<Vec<_>>()
+        };
+
+        let mut request = ScanRequest::new(root);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        // 未请求时不收集，JSON 中省略该字段
+        assert!(result.by_extension.iter().all(|s| s.top_files.is_empty()));
+        assert!(!serde_json::to_string(&result.by_extension).unwrap().contains("top_files"));
+
+        // 每个扩展名各自有界，与 Top N 的数量无关
+        request.extension_top_limit = Some(2);
+        request.limit = Some(1);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(top(&result, "mp4"), vec!["one.mp4", "three.mp4"]);
+        assert_eq!(top(&result, "txt"), vec!["notes.txt"]);
+        assert_eq!(top(&result, "no_extension"), vec!["Makefile"]);
+        assert_eq!(result.top_files.len(), 1);
+        let mp4 = result.by_extension.iter().find(|s| s.extension == "mp4").unwrap();
+        assert_eq!(mp4.file_count, 4);
+        assert_eq!(mp4.top_files[0].depth, Some(2));
+        assert_eq!(result.summary.extension_top_limit, Some(2));
+
+        // 合并时按扩展名拼接后重新排序并截断
+        let scan_sub = |name: &str, limit: usize| {
+            let mut sub = ScanRequest::new(root.join(name));
+            sub.extension_top_limit = Some(limit);
+            Scanner::new().scan_sync(&sub).unwrap()
+        };
+        let merged = merge(vec![scan_sub("a", 1), scan_sub("b", 2)]);
+        assert_eq!(top(&merged, "mp4"), vec!["one.mp4", "three.mp4"]);
+
+        // 仅汇总扫描不保留列表
+        request.collect_entries = false;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert!(result.by_extension.iter().all(|s| s.top_files.is_empty()));
+    }
+
+    #[test]
+    fn test_suspicious_timestamps_are_counted_and_listed() {
+        const DAY: Duration = Duration::from_secs(24 * 3600);
+        let base = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let files = [
+            ("normal.dat", base - 10 * DAY),
+            ("just-written.dat", base + Duration::from_secs(1800)),
+            ("epoch.dat", UNIX_EPOCH),
+            ("old-archive.dat", timestamp::min_plausible() - DAY),
+            ("future.dat", base + 400 * DAY),
+        ];
+        for (name, modified) in files {
+            let mut file = File::create(root.join(name)).unwrap();
+            file.write_all(b"x").unwrap();
+            file.set_modified(modified).unwrap();
+        }
+
+        let mut request = ScanRequest::new(root);
+        request.clock = Some(Arc::new(FixedClock(base)));
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        // 扫描期间写入（容差以内）的文件不算可疑；未请求时不列出
+        assert_eq!(result.summary.suspicious_timestamps, 3);
+        assert!(result.suspicious_files.is_empty());
+        // 原始修改时间保留在文件条目中，年龄分析使用校正后的时间
+        let epoch = result.top_files.iter().find(|f| f.path.ends_with("epoch.dat")).unwrap();
+        assert_eq!(epoch.last_modified, Some(UNIX_EPOCH));
+        assert_eq!(result.heatmap[0].age_bytes, vec![3, 0, 0, 0, 2, 0]);
+
+        // 按偏差降序列出，最多 Top N 个
+        request.list_suspicious_timestamps = true;
+        request.limit = Some(2);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let names: Vec<_> = result
+            .suspicious_files
+            .iter()
+            .map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["epoch.dat", "future.dat"]);
+
+        // 合并结果时计数相加
+        let other = tempdir().unwrap();
+        File::create(other.path().join("old.dat")).unwrap().set_modified(UNIX_EPOCH).unwrap();
+        let mut other_request = ScanRequest::new(other.path());
+        other_request.clock = Some(Arc::new(FixedClock(base)));
+        let other_result = Scanner::new().scan_sync(&other_request).unwrap();
+        assert_eq!(merge(vec![result, other_result]).summary.suspicious_timestamps, 4);
+    }
+
+    #[test]
+    fn test_redundant_files_are_classified_and_summed() {
+        let kind = |name: &str| redundant::classify(Path::new(name));
+        assert_eq!(kind("report.docx.bak"), Some(RedundantKind::Backup));
+        assert_eq!(kind("notes.txt~"), Some(RedundantKind::Backup));
+        assert_eq!(kind("photo (2).jpg"), Some(RedundantKind::NumberedCopy));
+        assert_eq!(kind("plan - Copy.xlsx"), Some(RedundantKind::NumberedCopy));
+        assert_eq!(kind("~$report.docx"), Some(RedundantKind::LockFile));
+        assert_eq!(kind("._photo.jpg"), Some(RedundantKind::SystemMetadata));
+        assert_eq!(kind(".main.rs.swp"), Some(RedundantKind::EditorSwap));
+        // 括号中的年份、普通隐藏文件与无扩展名的 `.old` 不算
+        assert_eq!(kind("Vacation (2023).mp4"), None);
+        assert_eq!(kind(".bashrc"), None);
+        assert_eq!(kind(".old"), None);
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("docs")).unwrap();
+        let files: [(&str, usize); 6] = [
+            (".DS_Store", 10),
+            ("docs/report.docx", 500),
+            ("docs/report.docx.bak", 400),
+            ("docs/report (1).docx", 300),
+            ("docs/~$report.docx", 20),
+            ("docs/.report.swp", 30),
+        ];
+        for (name, size) in files {
+            File::create(root.join(name)).unwrap().write_all(&vec![0u8; size]).unwrap();
+        }
+
+        let mut request = ScanRequest::new(root);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.summary.redundant_file_count, 5);
+        assert_eq!(result.summary.redundant_bytes, 760);
+        let kinds: Vec<_> = result.redundant.iter().map(|s| (s.kind, s.total_size_bytes)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (RedundantKind::Backup, 400),
+                (RedundantKind::NumberedCopy, 300),
+                (RedundantKind::EditorSwap, 30),
+                (RedundantKind::LockFile, 20),
+                (RedundantKind::SystemMetadata, 10),
+            ]
+        );
+        // 文件条目标注类型；未请求时不列出
+        let backup = result.top_files.iter().find(|f| f.path.ends_with("report.docx.bak")).unwrap();
+        assert_eq!(backup.redundant, Some(RedundantKind::Backup));
+        assert!(result.redundant_files.is_empty());
+
+        request.list_redundant_files = true;
+        request.limit = Some(2);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let names: Vec<_> = result
+            .redundant_files
+            .iter()
+            .map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["report.docx.bak", "report (1).docx"]);
+        assert_eq!(result.redundant_files[0].share_of_total, Some(0.3175));
+
+        // 合并结果时按类型相加
+        let other = tempdir().unwrap();
+        File::create(other.path().join("old.bak")).unwrap().write_all(&[0u8; 40]).unwrap();
+        let other_result = scan_path(other.path()).unwrap();
+        let merged = merge(vec![result, other_result]);
+        assert_eq!(merged.summary.redundant_bytes, 800);
+        assert_eq!(merged.redundant[0].file_count, 2);
+        assert_eq!(merged.redundant[0].total_size_bytes, 440);
+        assert_eq!(merged.redundant_files.len(), 2);
+    }
+
+    #[test]
+    fn test_diff_reports_changed_dirs_and_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("a/deep")).unwrap();
+        fs::create_dir(root.join("b")).unwrap();
+        File::create(root.join("a/x.bin")).unwrap().write_all(&[0u8; 100]).unwrap();
+        File::create(root.join("a/deep/y.bin")).unwrap().write_all(&[0u8; 200]).unwrap();
+        File::create(root.join("b/z.bin")).unwrap().write_all(&[0u8; 300]).unwrap();
+        let mut request = ScanRequest::new(root);
+        request.min_size = Some(0);
+        let old = Scanner::new().scan_sync(&request).unwrap();
+
+        File::create(root.join("a/x.bin")).unwrap().write_all(&[0u8; 500]).unwrap();
+        fs::remove_dir_all(root.join("b")).unwrap();
+        fs::create_dir(root.join("c")).unwrap();
+        File::create(root.join("c/new.bin")).unwrap().write_all(&[0u8; 400]).unwrap();
+        let new = Scanner::new().scan_sync(&request).unwrap();
+
+        let changes = diff(&old, &new);
+        assert_eq!(changes.total_size_delta, 500);
+        assert_eq!(changes.total_files_delta, 0);
+        let summarize = |list: &[EntryChange]| -> Vec<(String, ChangeKind, i64)> {
+            list.iter().map(|c| (c.path.to_string_lossy().into_owned(), c.kind, c.delta_bytes)).collect()
+        };
+        assert_eq!(
+            summarize(&changes.directories),
+            vec![
+                ("a".to_string(), ChangeKind::Grown, 400),
+                ("c".to_string(), ChangeKind::Added, 400),
+                ("b".to_string(), ChangeKind::Removed, -300),
+            ]
+        );
+        // 未变化的 a/deep/y.bin 不出现；列表完整时新增与删除是确定的
+        assert_eq!(
+            summarize(&changes.files),
+            vec![
+                ("a/x.bin".to_string(), ChangeKind::Grown, 400),
+                ("c/new.bin".to_string(), ChangeKind::Added, 400),
+                ("b/z.bin".to_string(), ChangeKind::Removed, -300),
+            ]
+        );
+        assert!(changes.files.iter().all(|c| !c.approximate));
+        assert_eq!(changes.files[0].old_size_bytes, Some(100));
+        assert_eq!(changes.files[0].new_size_bytes, Some(500));
+
+        // 按相对路径匹配，根目录不同的结果同样可以对比；Top N 截断时新增与删除只是近似
+        let moved = tempdir().unwrap();
+        fs::rename(root.join("a"), moved.path().join("a")).unwrap();
+        request.root_path = moved.path().to_path_buf();
+        request.limit = Some(1);
+        let truncated = Scanner::new().scan_sync(&request).unwrap();
+        let changes = diff(&new, &truncated);
+        assert_eq!(changes.total_size_delta, -400);
+        assert_eq!(summarize(&changes.directories), vec![("c".to_string(), ChangeKind::Removed, -400)]);
+        let removed = changes.files.iter().find(|c| c.path == Path::new("c/new.bin")).unwrap();
+        assert!(removed.approximate);
+        assert!(!changes.files.iter().any(|c| c.path == Path::new("a/x.bin")));
+    }
+
+    #[test]
+    fn test_scan_iter_streams_entries() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("sub")).unwrap();
+        for i in 0..50 {
+            let parent = if i % 2 == 0 { root.to_path_buf() } else { root.join("sub") };
+            File::create(parent.join(format!("f{:02}.dat", i))).unwrap().write_all(&vec![0u8; 10 + i]).unwrap();
+        }
+        let mut request = ScanRequest::new(root);
+        request.min_size = Some(20);
+
+        // 条目经过与完整扫描相同的过滤；结束后可取得仅汇总的结果
+        let mut names: Vec<String> = Scanner::new()
+            .scan_iter(&request)
+            .map(|e| e.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        let expected: Vec<String> = (10..50).map(|i| format!("f{:02}.dat", i)).collect();
+        assert_eq!(names, expected);
+
+        let mut iter = Scanner::new().scan_iter(&request);
+        assert!(iter.next().is_some());
+        let result = iter.finish().unwrap();
+        assert_eq!(result.summary.total_files, 40);
+        assert!(result.summary.summary_only);
+        assert!(result.top_files.is_empty());
+
+        // 提前丢弃迭代器会取消后台扫描，不会阻塞
+        let token = CancellationToken::new();
+        request.cancel = Some(token.clone());
+        let first: Vec<FileEntry> = Scanner::new().scan_iter(&request).take(1).collect();
+        assert_eq!(first.len(), 1);
+        assert!(token.is_canceled());
+
+        request.root_path = root.join("missing");
+        assert!(Scanner::new().scan_iter(&request).finish().is_err());
+    }
+
+    #[test]
+    fn test_share_of_parent_and_total() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("a")).unwrap();
+        File::create(root.join("a/x.bin")).unwrap().write_all(&[0u8; 400]).unwrap();
+        File::create(root.join("a/y.bin")).unwrap().write_all(&[0u8; 200]).unwrap();
+        File::create(root.join("b.bin")).unwrap().write_all(&[0u8; 400]).unwrap();
+
+        let result = scan_path(root).unwrap();
+        let x = result.top_files.iter().find(|f| f.path.ends_with("a/x.bin")).unwrap();
+        assert_eq!(x.share_of_total, Some(0.4));
+        assert_eq!(x.share_of_parent, Some(0.6667));
+        let b = result.top_files.iter().find(|f| f.path.ends_with("b.bin")).unwrap();
+        assert_eq!(b.share_of_parent, Some(0.4));
+
+        // 目录行：根目录占总量 100%，a 占根目录 60%
+        assert_eq!(result.directories.len(), 2);
+        assert_eq!(result.directories[0].size_bytes, 1000);
+        assert_eq!(result.directories[0].share_of_total, 1.0);
+        assert_eq!(result.directories[0].share_of_parent, None);
+        assert!(result.directories[1].path.ends_with("a"));
+        assert_eq!(result.directories[1].share_of_parent, Some(0.6));
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["directories"][1]["share_of_total"], 0.6);
+
+        // 分片合并后根目录大小与占比与整体扫描一致
+        let manifest = ShardManifest::plan(root, 2).unwrap();
+        let shards = (0..2)
+            .map(|i| Scanner::new().scan_sync(&manifest.request_for(i).unwrap()).unwrap())
+            .collect();
+        let merged = manifest.merge(shards).unwrap();
+        assert_eq!(merged.directories, result.directories);
+        let merged_b = merged.top_files.iter().find(|f| f.path.ends_with("b.bin")).unwrap();
+        assert_eq!(merged_b.share_of_parent, Some(0.4));
+    }
+
+    #[test]
+    fn test_empty_files_and_dirs() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for path in ["skel/x/y", "mixed/leaf", "hidden", "sub"] {
+            fs::create_dir_all(root.join(path)).unwrap();
+        }
+        for (path, len) in [("a.txt", 0), ("big.bin", 100), ("mixed/f.bin", 10), ("hidden/x.log", 0), ("sub/z.dat", 0)] {
+            fs::write(root.join(path), vec![0u8; len]).unwrap();
+        }
+
+        // 零字节文件不受 min_size 影响；被排除的文件不计入空文件，但其所在目录也不是空目录
+        let mut request = ScanRequest::new(root);
+        request.min_size = Some(50);
+        request.exclude_patterns = vec!["**/*.log".to_string()];
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.empty_files, vec![root.join("a.txt"), root.join("sub/z.dat")]);
+        let dirs: Vec<(PathBuf, u64)> = result.empty_dirs.iter().map(|d| (d.path.clone(), d.dir_count)).collect();
+        assert_eq!(dirs, vec![(root.join("mixed/leaf"), 1), (root.join("skel"), 3)]);
+        assert_eq!((result.summary.empty_file_count, result.summary.empty_dir_count), (2, 2));
+
+        // 列表按上限截断，计数不变
+        request.empty_limit = Some(1);
+        let limited = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(limited.empty_files, vec![root.join("a.txt")]);
+        assert_eq!(limited.empty_dirs.len(), 1);
+        assert_eq!(limited.summary.empty_dir_count, 2);
+
+        // 超出最大深度未进入的目录内容未知，不报告为空
+        request.empty_limit = None;
+        request.max_depth = Some(1);
+        assert!(Scanner::new().scan_sync(&request).unwrap().empty_dirs.is_empty());
+
+        // 分片合并后与整体扫描一致
+        let manifest = ShardManifest::plan(root, 2).unwrap();
+        let shards = (0..2)
+            .map(|i| {
+                let mut shard = manifest.request_for(i).unwrap();
+                shard.exclude_patterns = vec!["**/*.log".to_string()];
+                Scanner::new().scan_sync(&shard).unwrap()
+            })
+            .collect();
+        let merged = manifest.merge(shards).unwrap();
+        assert_eq!(merged.empty_files, result.empty_files);
+        assert_eq!(merged.empty_dirs, result.empty_dirs);
+        assert_eq!(merged.summary.empty_dir_count, 2);
+    }
+
+    #[test]
+    fn test_scan_errors_and_fail_fast() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for path in ["ok/a.bin", "ok/unreadable-trigger/b.bin", "other/c.bin"] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, [0u8; 10]).unwrap();
+        }
+        let unreadable = root.join("ok/unreadable-trigger");
+        let fs_provider: Arc<dyn FsProvider> = Arc::new(testutil::FaultyFs::new().unreadable_dir("unreadable-trigger"));
+        let scan_path = |path: PathBuf| {
+            let mut request = ScanRequest::new(path);
+            request.fs_provider = Some(fs_provider.clone());
+            Scanner::new().scan_sync(&request)
+        };
+
+        // 无法读取的目录记录为错误，其余部分照常统计
+        let result = scan_path(root.to_path_buf()).unwrap();
+        assert_eq!(result.summary.scan_error_count, 1);
+        assert_eq!(result.summary.total_files, 2);
+        let error = &result.scan_errors[0];
+        assert_eq!((&error.path, error.operation, error.kind.as_str()), (&unreadable, ScanOperation::ReadDir, "permission_denied"));
+        assert_eq!(result.diagnostics.denied_dirs, vec![unreadable.clone()]);
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["scan_errors"][0]["operation"], "read_dir");
+
+        // 合并结果累加错误数，补扫成功后移除对应的错误
+        let merged = merge(vec![scan_path(root.join("ok")).unwrap(), scan_path(root.join("other")).unwrap()]);
+        assert_eq!((merged.summary.scan_error_count, merged.scan_errors.len()), (1, 1));
+        let mut sub = ScanResult::default();
+        sub.summary.root_path = unreadable.clone();
+        let grafted = graft(result, vec![sub], &ScanRequest::new(root));
+        assert_eq!(grafted.summary.scan_error_count, 0);
+        assert!(grafted.scan_errors.is_empty());
+
+        // fail_fast：遇到第一个错误即中止并返回该错误
+        let mut request = ScanRequest::new(root);
+        request.fs_provider = Some(fs_provider.clone());
+        request.fail_fast = true;
+        let error = Scanner::new().scan_sync(&request).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+        assert!(error.to_string().contains("unreadable-trigger"));
+        assert!(matches!(&error, ScanError::PermissionDenied { path } if *path == unreadable));
+    }
+
+    #[test]
+    fn test_scan_error_variants() {
+        let dir = tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        let error = scan_path(&missing).unwrap_err();
+        assert!(matches!(&error, ScanError::RootNotFound { path } if *path == missing));
+        assert_eq!(error.path(), Some(missing.as_path()));
+
+        let file = dir.path().join("file.bin");
+        fs::write(&file, [0u8; 4]).unwrap();
+        let error = scan_path(&file).unwrap_err();
+        assert!(matches!(error, ScanError::RootNotDirectory { .. }));
+        assert_eq!(error.kind(), std::io::ErrorKind::NotADirectory);
+
+        let mut request = ScanRequest::new(dir.path());
+        request.include_regex = vec!["[".to_string()];
+        let error = Scanner::new().scan_sync(&request).unwrap_err();
+        assert!(matches!(error, ScanError::InvalidRequest { .. }));
+        assert_eq!(error.path(), None);
+
+        // 转换为 io::Error 时保留错误类别与原始错误
+        let io_error = std::io::Error::from(error);
+        assert_eq!(io_error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(io_error.get_ref().is_some_and(|inner| inner.is::<ScanError>()));
+    }
+
+    #[test]
+    fn test_tree_links_depth_and_parent_index() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        File::create(root.join("a/b/deep.bin")).unwrap().write_all(&[0u8; 300]).unwrap();
+        File::create(root.join("top.bin")).unwrap().write_all(&[0u8; 100]).unwrap();
+
+        let result = scan_path(root).unwrap();
+        let dirs = &result.directories;
+        let find = |name: &str| dirs.iter().position(|d| d.path == root.join(name)).unwrap();
+        let (root_idx, a, b) = (find(""), find("a"), find("a/b"));
+        assert_eq!((dirs[root_idx].depth, dirs[root_idx].parent_index), (0, None));
+        assert_eq!((dirs[a].depth, dirs[a].parent_index), (1, Some(root_idx)));
+        assert_eq!((dirs[b].depth, dirs[b].parent_index), (2, Some(a)));
+
+        let deep = result.top_files.iter().find(|f| f.path.ends_with("deep.bin")).unwrap();
+        assert_eq!((deep.depth, deep.parent_index), (Some(3), Some(b)));
+        let top = result.top_files.iter().find(|f| f.path.ends_with("top.bin")).unwrap();
+        assert_eq!((top.depth, top.parent_index), (Some(1), Some(root_idx)));
+
+        // 只凭下标即可由文件回溯到根目录
+        let mut chain = vec![deep.parent_index.unwrap()];
+        while let Some(parent) = dirs[*chain.last().unwrap()].parent_index {
+            chain.push(parent);
+        }
+        assert_eq!(chain, vec![b, a, root_idx]);
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["directories"][b]["parent_index"], a);
+        assert_eq!(json["top_files"][0]["depth"], 3);
+    }
+
+    #[test]
+    fn test_default_min_size_heuristics() {
+        assert_eq!(min_size::default_for_capacity(100 << 30), None);
+        assert_eq!(min_size::default_for_capacity(2 << 40), Some(1 << 20));
+        assert_eq!(min_size::default_for_capacity(3 << 40), Some(1 << 20));
+        assert_eq!(min_size::default_for_capacity(1 << 60), Some(64 << 20));
+
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("a.txt")).unwrap().write_all(b"x").unwrap();
+
+        // 显式 --min-size 0 强制完整收集，不应用默认值
+        let mut request = ScanRequest::new(dir.path());
+        request.auto_min_size = true;
+        request.min_size = Some(0);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.summary.total_files, 1);
+        assert_eq!(result.summary.min_size, None);
+        assert!(!result.summary.min_size_is_default);
+
+        // 自动选择时摘要中报告实际采用的默认值
+        request.min_size = None;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let expected = min_size::default_for(dir.path());
+        assert_eq!(result.summary.min_size, expected);
+        assert_eq!(result.summary.min_size_is_default, expected.is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_summary_reports_disk_space_of_root() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.bin"), vec![0u8; 4096]).unwrap();
+        let result = Scanner::new().scan_sync(&ScanRequest::new(dir.path())).unwrap();
+        let disk = result.summary.disk_space.expect("Unix 上应能查询根目录所在文件系统的容量");
+        assert!(disk.total_bytes > 0);
+        assert!(disk.free_bytes <= disk.total_bytes);
+        assert!(disk.available_bytes <= disk.free_bytes);
+        assert_eq!(disk.used_bytes(), disk.total_bytes - disk.free_bytes);
+        assert_eq!(min_size::filesystem_capacity(dir.path()), Some(disk.total_bytes));
+        let share = disk.share_of(result.summary.total_size_bytes);
+        assert!(share > 0.0 && share <= 1.0);
+        assert_eq!(DiskSpace::default().share_of(100), 0.0);
+
+        let json = serde_json::to_value(&result.summary).unwrap();
+        assert_eq!(json["disk_space"]["total_bytes"], disk.total_bytes);
+        // 合并结果沿用首个查询到的容量
+        let other = tempdir().unwrap();
+        let mut other_result = Scanner::new().scan_sync(&ScanRequest::new(other.path())).unwrap();
+        other_result.summary.disk_space = None;
+        let merged = merge(vec![other_result, result]);
+        assert_eq!(merged.summary.disk_space, Some(disk));
+    }
+
+    #[test]
+    fn test_tree_spec_generates_deterministic_tree() {
+        let spec = testutil::TreeSpec::new().seed(7).depth(2).fanout(2).files_per_dir(3).file_sizes(10, 500);
+        let a = tempdir().unwrap();
+        let b = tempdir().unwrap();
+        let tree = spec.generate(a.path()).unwrap();
+        assert_eq!(spec.generate(b.path()).unwrap().total_bytes, tree.total_bytes);
+        assert_eq!(tree.dirs, 7);
+        assert_eq!(tree.files, 21);
+
+        // 规格可通过 JSON 分享并原样还原
+        let json = serde_json::to_string(&spec).unwrap();
+        assert_eq!(serde_json::from_str::<testutil::TreeSpec>(&json).unwrap(), spec);
+
+        let result = Scanner::new().scan_sync(&ScanRequest::new(a.path())).unwrap();
+        assert_eq!(result.summary.total_files, tree.files);
+        assert_eq!(result.summary.total_dirs, tree.dirs);
+        assert_eq!(result.summary.total_size_bytes, tree.total_bytes);
+    }
+
+    #[test]
+    fn test_worker_panic_is_isolated_and_reported() {
+        let dir = tempdir().unwrap();
+        let tree = testutil::TreeSpec::new().depth(1).fanout(3).files_per_dir(4).generate(dir.path()).unwrap();
+        let trigger = dir.path().join("dir1").join("panic-trigger.bin");
+        File::create(&trigger).unwrap().write_all(b"boom").unwrap();
+
+        // panic 只跳过触发文件，扫描正常返回且其余文件全部计入
+        let mut request = ScanRequest::new(dir.path());
+        request.fs_provider = Some(Arc::new(testutil::FaultyFs::new().panic_on("panic-trigger.bin")));
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.summary.total_files, tree.files);
+        assert_eq!(result.summary.total_size_bytes, tree.total_bytes);
+        assert_eq!(result.diagnostics.worker_panics.len(), 1);
+        assert_eq!(result.diagnostics.worker_panics[0].path, trigger);
+        assert!(result.diagnostics.worker_panics[0].message.contains("injected panic"));
+    }
+
+    #[test]
+    fn test_live_progress_and_poisoned_lock_recovery() {
+        let dir = tempdir().unwrap();
+        let tree = testutil::TreeSpec::new().depth(1).fanout(2).generate(dir.path()).unwrap();
+        let progress = LiveProgress::new();
+        let mut request = ScanRequest::new(dir.path());
+        request.progress = Some(progress.clone());
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(progress.scanned_files(), tree.files);
+        assert_eq!(progress.scanned_dirs(), result.summary.total_dirs);
+        assert_eq!(progress.scanned_bytes(), result.summary.total_size_bytes);
+
+        // 持锁线程 panic 使锁中毒后，后续访问仍能正常读写
+        let mutex = Mutex::new(1);
+        let _ = std::thread::scope(|s| {
+            s.spawn(|| {
+                let _guard = mutex.lock().unwrap();
+                panic!("poison the lock");
+            })
+            .join()
+        });
+        assert!(mutex.is_poisoned());
+        *lock(&mutex) += 1;
+        assert_eq!(*lock(&mutex), 2);
+    }
+
+    #[test]
+    fn test_metadata_retry_on_transient_errors() {
+        let policy = RetryPolicy { max_retries: 3, base_delay: Duration::from_millis(1) };
+
+        // 瞬时错误重试后成功
+        let mut failures = 2;
+        let (result, retries) = policy.run(|| {
+            if failures > 0 {
+                failures -= 1;
+                return Err(std::io::Error::from(std::io::ErrorKind::StaleNetworkFileHandle));
+            }
+            Ok(42)
+        });
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(retries, 2);
+
+        // 重试次数耗尽后返回最后一次错误
+        let (result, retries) = policy.run(|| Err::<(), _>(std::io::Error::from(std::io::ErrorKind::Interrupted)));
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::Interrupted);
+        assert_eq!(retries, 3);
+
+        // 非瞬时错误立即返回
+        let (result, retries) = policy.run(|| Err::<(), _>(std::io::Error::from(std::io::ErrorKind::NotFound)));
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(retries, 0);
+        assert_eq!(RetryPolicy::none().run(|| Err::<(), _>(std::io::Error::from(std::io::ErrorKind::WouldBlock))).1, 0);
+    }
+
+    #[test]
+    fn test_density_rankings() {
+        let dir = tempdir().unwrap();
+        let giant = dir.path().join("images");
+        let tiny = dir.path().join("cache");
+        fs::create_dir_all(&giant).unwrap();
+        fs::create_dir_all(&tiny).unwrap();
+        File::create(giant.join("disk.img")).unwrap().set_len(8 << 20).unwrap();
+        for i in 0..density::CROWDED_MIN_FILES {
+            File::create(tiny.join(format!("{}.tmp", i))).unwrap().write_all(b"x").unwrap();
+        }
+        File::create(dir.path().join("notes.txt")).unwrap().write_all(&[0u8; 4096]).unwrap();
+
+        let result = Scanner::new().scan_sync(&ScanRequest::new(dir.path())).unwrap();
+        let heavy = &result.density.heavy;
+        assert_eq!(heavy[0].path, giant);
+        assert_eq!(heavy[0].bytes_per_file, 8 << 20);
+        assert_eq!(heavy.len(), 3);
+
+        // 只有达到最少文件数的目录进入碎文件排名
+        let crowded = &result.density.crowded;
+        assert_eq!(crowded.len(), 1);
+        assert_eq!(crowded[0].path, tiny);
+        assert_eq!(crowded[0].file_count, density::CROWDED_MIN_FILES);
+        assert_eq!(crowded[0].files_per_mib, 1 << 20);
+    }
+
+    #[test]
+    fn test_package_detection() {
+        assert!(package::is_package(Path::new("/Applications/Safari.app")));
+        assert!(package::is_package(Path::new("Foo.Framework")));
+        assert!(!package::is_package(Path::new("/Users/me/Documents")));
+        assert!(!package::is_package(Path::new("notes.txt")));
+
+        // 嵌套包目录取最外层
+        let inner = Path::new("/Applications/Xcode.app/Contents/Frameworks/Dev.framework/Dev");
+        assert_eq!(package::enclosing_package(inner), Some(Path::new("/Applications/Xcode.app")));
+        assert_eq!(package::enclosing_package(Path::new("/Applications/Xcode.app")), None);
+        assert_eq!(package::enclosing_package(Path::new("/tmp/a/b.txt")), None);
+    }
+
+    #[test]
+    fn test_placeholder_files_marked_and_not_read() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("placeholder-trigger.bin"), vec![7u8; 4096]).unwrap();
+        fs::write(dir.path().join("copy-a.bin"), vec![7u8; 4096]).unwrap();
+        fs::write(dir.path().join("copy-b.bin"), vec![7u8; 4096]).unwrap();
+
+        let mut request = ScanRequest::new(dir.path());
+        request.fs_provider = Some(Arc::new(testutil::FaultyFs::new().placeholder("placeholder-trigger.bin")));
+        request.min_size = Some(0);
+        request.dedup = Some(DedupOptions::default());
+        request.compute_hash = Some(HashAlgo::Sha256);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.summary.total_files, 3);
+        assert_eq!((result.summary.placeholder_files, result.summary.placeholder_bytes), (1, 4096));
+        let placeholder = result.top_files.iter().find(|f| f.is_placeholder).unwrap();
+        assert!(placeholder.path.ends_with("placeholder-trigger.bin"));
+        assert_eq!(placeholder.digest, None);
+        assert!(result.top_files.iter().filter(|f| !f.is_placeholder).all(|f| f.digest.is_some()));
+        // 内容相同的占位文件不参与重复检测
+        assert_eq!(result.duplicates.len(), 1);
+        assert_eq!(result.duplicates[0].paths.len(), 2);
+        let json = serde_json::to_value(&result.top_files).unwrap();
+        assert_eq!(json.as_array().unwrap().iter().filter(|f| f.get("is_placeholder").is_some()).count(), 1);
+    }
+
+    #[test]
+    fn test_opaque_packages_counted_as_single_files() {
+        let dir = tempdir().unwrap();
+        let app = dir.path().join("Tool.app");
+        fs::create_dir_all(app.join("Contents/MacOS")).unwrap();
+        fs::write(app.join("Contents/Info.plist"), vec![0u8; 100]).unwrap();
+        fs::write(app.join("Contents/MacOS/tool"), vec![0u8; 900]).unwrap();
+        fs::write(dir.path().join("notes.txt"), vec![0u8; 50]).unwrap();
+
+        let mut request = ScanRequest::new(dir.path());
+        request.min_size = Some(0);
+        let expanded = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!((expanded.summary.total_files, expanded.summary.total_dirs), (3, 4));
+
+        request.opaque_packages = true;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!((result.summary.total_files, result.summary.total_dirs), (2, 1));
+        assert_eq!(result.summary.total_size_bytes, 1050);
+        let bundle = result.top_files.iter().find(|f| f.path == app).unwrap();
+        assert_eq!(bundle.size_bytes, 1000);
+        assert_eq!(bundle.extension.as_deref(), Some("app"));
+        assert!(result.top_files.iter().all(|f| !f.path.starts_with(&app) || f.path == app));
+        assert!(result.by_extension.iter().any(|e| e.extension == "app" && e.total_size_bytes == 1000));
+
+        // 扫描根目录本身是包目录时照常进入
+        let mut request = ScanRequest::new(&app);
+        request.min_size = Some(0);
+        request.opaque_packages = true;
+        assert_eq!(Scanner::new().scan_sync(&request).unwrap().summary.total_files, 2);
+    }
+
+    #[test]
+    fn test_file_age_heatmap() {
+        let dir = tempdir().unwrap();
+        let old = dir.path().join("archive");
+        let nested = old.join("2019").join("raw");
+        fs::create_dir_all(&nested).unwrap();
+        let now = SystemTime::now();
+        let day = Duration::from_secs(86400);
+        let write = |path: PathBuf, len: usize, age_days: u32| {
+            let file = File::create(path).unwrap();
+            file.set_len(len as u64).unwrap();
+            file.set_modified(now - day * age_days).unwrap();
+        };
+        write(dir.path().join("fresh.log"), 1000, 0);
+        write(old.join("report.pdf"), 3000, 200);
+        write(nested.join("dump.bin"), 4000, 2000);
+
+        let result = Scanner::new().scan_sync(&ScanRequest::new(dir.path())).unwrap();
+        // 默认深度为 2：根目录、archive、archive/2019，不含 raw
+        let paths: Vec<_> = result.heatmap.iter().map(|row| (row.depth, row.path.clone())).collect();
+        assert_eq!(paths, vec![(0, dir.path().to_path_buf()), (1, old.clone()), (2, old.join("2019"))]);
+
+        let root = &result.heatmap[0];
+        assert_eq!(root.size_bytes, 8000);
+        assert_eq!(root.age_bytes, vec![1000, 0, 3000, 0, 4000, 0]);
+        assert_eq!(root.age_shares, vec![0.125, 0.0, 0.375, 0.0, 0.5, 0.0]);
+        let archive = &result.heatmap[1];
+        assert_eq!(archive.age_bytes, vec![0, 0, 3000, 0, 4000, 0]);
+        assert_eq!(archive.age_shares[4], 0.5714);
+    }
+
+    #[test]
+    fn test_top_dirs_ranking() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (path, len) in [
+            ("videos/2023/a.mp4", 5000),
+            ("videos/2024/b.mp4", 3000),
+            ("docs/c.txt", 1500),
+            ("videos/2024/raw/deep/d.bin", 2000),
+            ("empty/.keep", 0),
+        ] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![0u8; len]).unwrap();
+        }
+
+        let mut request = ScanRequest::new(root);
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let rows: Vec<_> = result
+            .top_dirs
+            .iter()
+            .map(|d| (d.path.strip_prefix(root).unwrap().to_path_buf(), d.size_bytes))
+            .collect();
+        // 默认深度 3：不含根目录与 videos/2024/raw/deep，空目录不参与排名
+        assert_eq!(
+            rows,
+            vec![
+                (PathBuf::from("videos"), 10_000),
+                (PathBuf::from("videos/2023"), 5000),
+                (PathBuf::from("videos/2024"), 5000),
+                (PathBuf::from("videos/2024/raw"), 2000),
+                (PathBuf::from("docs"), 1500),
+            ]
+        );
+        let videos = &result.top_dirs[0];
+        assert_eq!(videos.share_of_total, 0.8696);
+        assert_eq!(videos.share_of_parent, Some(0.8696));
+        assert_eq!(result.top_dirs[2].share_of_parent, Some(0.5));
+
+        // 只计直接文件：videos 本身没有文件，不参与排名；更深的目录不受 top_dirs_depth 限制
+        let shallow_rows: Vec<_> = result
+            .top_dirs_shallow
+            .iter()
+            .map(|d| (d.path.strip_prefix(root).unwrap().to_path_buf(), d.file_count, d.size_bytes))
+            .collect();
+        assert_eq!(
+            shallow_rows,
+            vec![
+                (PathBuf::from("videos/2023"), 1, 5000),
+                (PathBuf::from("videos/2024"), 1, 3000),
+                (PathBuf::from("videos/2024/raw/deep"), 1, 2000),
+                (PathBuf::from("docs"), 1, 1500),
+            ]
+        );
+
+        request.top_dirs_depth = 1;
+        request.top_dirs_limit = Some(1);
+        let shallow = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(shallow.top_dirs.len(), 1);
+        assert_eq!(shallow.top_dirs[0].path, root.join("videos"));
+        assert_eq!(shallow.top_dirs_shallow.len(), 1);
+        assert_eq!(shallow.top_dirs_shallow[0].path, root.join("videos/2023"));
+        // 热力图深度不受大目录排名深度影响
+        assert_eq!(shallow.heatmap.iter().map(|r| r.depth).max(), Some(2));
+
+        // 分片合并后与单次扫描一致
+        let plan = ShardManifest::plan(root, 2).unwrap();
+        let shards = (0..plan.shards.len())
+            .map(|i| {
+                let mut request = ScanRequest::new(root);
+                request.shard = plan.assignment(i);
+                Scanner::new().scan_sync(&request).unwrap()
+            })
+            .collect();
+        let merged = plan.merge(shards).unwrap();
+        assert_eq!(merged.top_dirs, result.top_dirs);
+        assert_eq!(merged.top_dirs_shallow, result.top_dirs_shallow);
+    }
+
+    #[test]
+    fn test_top_level_quick_view() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (path, len) in [
+            ("videos/2023/a.mp4", 5000),
+            ("videos/2024/raw/b.mp4", 3000),
+            ("docs/c.txt", 1500),
+            ("notes.txt", 500),
+            ("readme.md", 0),
+        ] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![0u8; len]).unwrap();
+        }
+        fs::create_dir(root.join("empty")).unwrap();
+
+        let mut request = ScanRequest::new(root);
+        request.collect_entries = false;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let rows: Vec<_> = result
+            .top_level
+            .iter()
+            .map(|r| (r.path.clone(), r.size_bytes, r.file_count, r.dir_count, r.root_files))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (root.join("videos"), 8000, 2, 3, false),
+                (root.join("docs"), 1500, 1, 0, false),
+                (root.to_path_buf(), 500, 2, 0, true),
+                (root.join("empty"), 0, 0, 0, false),
+            ]
+        );
+        assert_eq!(result.top_level[0].share_of_total, 0.8);
+        assert_eq!(result.top_level.iter().map(|r| r.size_bytes).sum::<u64>(), result.summary.total_size_bytes);
+
+        // 分片合并后与单次扫描一致
+        let plan = ShardManifest::plan(root, 2).unwrap();
+        let shards = (0..plan.shards.len())
+            .map(|i| {
+                let mut request = ScanRequest::new(root);
+                request.shard = plan.assignment(i);
+                Scanner::new().scan_sync(&request).unwrap()
+            })
+            .collect();
+        assert_eq!(plan.merge(shards).unwrap().top_level, result.top_level);
+
+        // 多根目录合并时各根目录整体归入合并根目录下的直接子目录
+        let parts = ["videos/2023", "videos/2024", "docs"].map(|p| scan_path(root.join(p)).unwrap());
+        let merged = merge(parts.to_vec());
+        let rows: Vec<_> = merged.top_level.iter().map(|r| (r.path.clone(), r.size_bytes, r.dir_count)).collect();
+        assert_eq!(rows, vec![(root.join("videos"), 8000, 3), (root.join("docs"), 1500, 0)]);
+    }
+
+    #[test]
+    fn test_index_subtree() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (path, len) in [("videos/2023/a.mp4", 5000), ("videos/b.mp4", 3000), ("docs/c.txt", 1500), ("docs/d.txt", 0)] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![0u8; len]).unwrap();
+        }
+        let result = scan_path(root).unwrap();
+
+        let whole = index::subtree(&result, root).unwrap();
+        assert_eq!(serde_json::to_value(&whole).unwrap(), serde_json::to_value(&result).unwrap());
+        let videos = index::subtree(&result, &root.join("videos")).unwrap();
+        assert_eq!(videos.summary.root_path, root.join("videos"));
+        assert_eq!((videos.summary.total_files, videos.summary.total_dirs), (2, 2));
+        assert_eq!(videos.summary.total_size_bytes, 8000);
+        assert_eq!(videos.top_files.len(), 2);
+        assert!(videos.top_files.iter().all(|f| f.path.starts_with(root.join("videos"))));
+        assert_eq!(videos.top_files[0].share_of_total, Some(0.625));
+        assert_eq!(videos.summary.empty_file_count, 0);
+        assert_eq!(videos.directories[0].path, root.join("videos"));
+        assert_eq!(videos.directories[0].depth, 0);
+
+        let docs = index::subtree(&result, &root.join("docs")).unwrap();
+        assert_eq!(docs.empty_files, vec![root.join("docs/d.txt")]);
+        assert_eq!(docs.summary.empty_file_count, 1);
+
+        // 更深的子目录与根目录之外的路径无法由快照回答
+        assert!(index::subtree(&result, &root.join("videos/2023")).is_none());
+        assert!(index::subtree(&result, Path::new("/elsewhere")).is_none());
+    }
+
+    #[test]
+    fn test_incremental_scan_reuses_unchanged_subtrees() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        for (path, len) in [("videos/2023/a.mp4", 5000), ("videos/b.mp4", 3000), ("docs/c.txt", 1500), ("d.bin", 700)] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![0u8; len]).unwrap();
+        }
+        // 目录修改时间统一设为过去，之后的改动必然改变修改时间
+        let old = SystemTime::now() - Duration::from_secs(3600);
+        for sub in ["videos", "videos/2023", "docs"] {
+            fs::File::open(root.join(sub)).unwrap().set_modified(old).unwrap();
+        }
+        let cache = dir.path().join("cache/incremental.json");
+        let mut request = ScanRequest::new(&root);
+        request.min_size = Some(0);
+        let summary = |r: &ScanResult| {
+            let files: Vec<_> = r.top_files.iter().map(|f| (f.path.clone(), f.size_bytes)).collect();
+            (r.summary.total_files, r.summary.total_dirs, r.summary.total_size_bytes, files)
+        };
+
+        // 首次扫描逐棵遍历，结果与完整扫描一致
+        let full = Scanner::new().scan_sync(&request).unwrap();
+        let first = incremental::scan(&request, &cache).unwrap();
+        assert_eq!(summary(&first), summary(&full));
+        assert_eq!(first.top_level, full.top_level);
+        assert_eq!((first.diagnostics.cached_subtrees, first.diagnostics.rescanned_subtrees), (0, 2));
+
+        // 未变化时全部来自缓存
+        let second = incremental::scan(&request, &cache).unwrap();
+        assert_eq!(summary(&second), summary(&full));
+        assert_eq!((second.diagnostics.cached_subtrees, second.diagnostics.rescanned_subtrees), (2, 0));
+
+        // 深层目录新增文件只重新遍历所在的顶层子树
+        fs::write(root.join("videos/2023/e.mp4"), vec![0u8; 100]).unwrap();
+        let third = incremental::scan(&request, &cache).unwrap();
+        assert_eq!((third.diagnostics.cached_subtrees, third.diagnostics.rescanned_subtrees), (1, 1));
+        assert_eq!(summary(&third), summary(&Scanner::new().scan_sync(&request).unwrap()));
+        assert_eq!(third.summary.total_files, 5);
+
+        // 扫描选项变化时缓存整体失效
+        request.limit = Some(1);
+        let fourth = incremental::scan(&request, &cache).unwrap();
+        assert_eq!((fourth.diagnostics.cached_subtrees, fourth.diagnostics.rescanned_subtrees), (0, 2));
+        assert_eq!(fourth.top_files.len(), 1);
+
+        // 无法按子树缓存的选项照常完整扫描
+        request.dedup = Some(DedupOptions::default());
+        let whole = incremental::scan(&request, &cache).unwrap();
+        assert_eq!(whole.diagnostics.rescanned_subtrees, 0);
+        assert_eq!(whole.summary.total_files, 5);
+    }
+
+    #[test]
+    fn test_graft_rescanned_denied_dir() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("data/locked")).unwrap();
+        fs::write(root.join("data/a.bin"), vec![0u8; 1000]).unwrap();
+        fs::write(root.join("b.bin"), vec![0u8; 500]).unwrap();
+
+        // 以 root 运行时 chmod 000 不会拒绝读取，此时只校验嫁接
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let locked = root.join("data/locked");
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+            let denied = fs::read_dir(&locked).is_err();
+            let result = Scanner::new().scan_sync(&ScanRequest::new(root)).unwrap();
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+            if denied {
+                assert_eq!(result.diagnostics.denied_dirs, vec![locked]);
+            }
+        }
+
+        // 首次扫描时 locked 为空（相当于无法读取），补扫时已有内容
+        let mut request = ScanRequest::new(root);
+        request.min_size = Some(0);
+        let mut base = Scanner::new().scan_sync(&request).unwrap();
+        base.diagnostics.denied_dirs = vec![root.join("data/locked")];
+        for (path, len) in [("data/locked/c.bin", 3000), ("data/locked/deep/d.bin", 200)] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![0u8; len]).unwrap();
+        }
+        request.root_path = root.join("data/locked");
+        let sub = Scanner::new().scan_sync(&request).unwrap();
+        request.root_path = root.to_path_buf();
+        let full = Scanner::new().scan_sync(&request).unwrap();
+
+        let grafted = graft(base, vec![sub], &request);
+        assert!(grafted.diagnostics.denied_dirs.is_empty());
+        assert_eq!(grafted.summary.total_files, full.summary.total_files);
+        assert_eq!(grafted.summary.total_dirs, full.summary.total_dirs);
+        assert_eq!(grafted.summary.total_size_bytes, 4700);
+        assert_eq!(grafted.top_files, full.top_files);
+        assert_eq!(grafted.top_dirs, full.top_dirs);
+        assert_eq!(grafted.top_level, full.top_level);
+        let ages = |result: &ScanResult| {
+            result.heatmap.iter().map(|r| (r.path.clone(), r.depth, r.size_bytes)).collect::<Vec<_>>()
+        };
+        assert_eq!(ages(&grafted), ages(&full));
+
+        // 不在根目录之下的结果不会被嫁接
+        let outside = tempdir().unwrap();
+        request.root_path = outside.path().to_path_buf();
+        let other = Scanner::new().scan_sync(&request).unwrap();
+        let rejected = graft(full.clone(), vec![other], &request);
+        assert_eq!(rejected.summary.total_size_bytes, full.summary.total_size_bytes);
+        assert_eq!(rejected.diagnostics.overlapping_roots, vec![outside.path().to_path_buf()]);
+    }
+
+    #[test]
+    fn test_duplicate_hashing_phase() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("backup")).unwrap();
+        let photo = vec![7u8; 200 * 1024];
+        fs::write(dir.path().join("photo.jpg"), &photo).unwrap();
+        fs::write(dir.path().join("backup").join("photo copy.jpg"), &photo).unwrap();
+        // 大小相同但内容不同的文件需要哈希，但不构成重复
+        fs::write(dir.path().join("other.jpg"), vec![8u8; 200 * 1024]).unwrap();
+        fs::write(dir.path().join("unique.txt"), b"only one").unwrap();
+
+        let progress = LiveProgress::new();
+        let mut request = ScanRequest::new(dir.path());
+        request.progress = Some(progress.clone());
+        request.dedup = Some(DedupOptions {
+            hash_threads: Some(1),
+            max_bytes_per_sec: Some(2 << 20),
+        });
+        let started = std::time::Instant::now();
+        let result = Scanner::new().scan_sync(&request).unwrap();
+
+        assert_eq!(result.duplicates.len(), 1);
+        let group = &result.duplicates[0];
+        assert_eq!(group.paths, vec![dir.path().join("backup").join("photo copy.jpg"), dir.path().join("photo.jpg")]);
+        assert_eq!(group.wasted_bytes(), 200 * 1024);
+
+        // 三个同尺寸文件被哈希；限速 2 MiB/s 下读取 600 KiB 至少耗时约 0.29 秒
+        assert_eq!(progress.hash_total_bytes(), 600 * 1024);
+        assert_eq!(progress.hashed_bytes(), 600 * 1024);
+        assert!(started.elapsed() >= Duration::from_millis(250));
+        let snapshot = progress.snapshot(ScanState::Completed);
+        assert_eq!(snapshot.phase.phase, ScanPhase::Aggregating);
+        assert_eq!(snapshot.scanned_files, 4);
+
+        // 未启用时不进入哈希阶段
+        let plain = Scanner::new().scan_sync(&ScanRequest::new(dir.path())).unwrap();
+        assert!(plain.duplicates.is_empty());
+    }
+
+    #[test]
+    fn test_multi_phase_progress() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.bin"), vec![1u8; 4096]).unwrap();
+
+        // 调用方登记保存阶段：枚举 -> 汇总 -> 保存，共 3 个阶段
+        let progress = LiveProgress::new();
+        progress.plan_phase(ScanPhase::Persisting);
+        let mut request = ScanRequest::new(dir.path());
+        request.progress = Some(progress.clone());
+        Scanner::new().scan_sync(&request).unwrap();
+        let phase = progress.phase_progress();
+        assert_eq!((phase.phase, phase.index, phase.count), (ScanPhase::Aggregating, 2, 3));
+        assert_eq!(phase.to_string(), "阶段 2/3：汇总");
+
+        progress.begin_phase(ScanPhase::Persisting);
+        let snapshot = progress.snapshot(ScanState::Running);
+        assert_eq!((snapshot.phase.index, snapshot.phase.count), (3, 3));
+        assert!((snapshot.progress - 2.0 / 3.0).abs() < 1e-9);
+
+        // 哈希阶段给出阶段内进度
+        let hashing = PhaseProgress::new(ScanPhase::Hashing, 0b11101, Some(0.45));
+        assert_eq!(hashing.to_string(), "阶段 2/4：哈希 45%");
+        assert!((hashing.overall() - 1.45 / 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_limit_zero_and_clamping() {
+        let dir = tempdir().unwrap();
+        for (name, len) in [("a", 300), ("b/c", 200), ("b/d", 100)] {
+            let path = dir.path().join(format!("{}.bin", name));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            File::create(path).unwrap().set_len(len).unwrap();
+        }
+        let scan = |limit: usize, max_limit: usize, shard: Option<ShardAssignment>| {
+            let mut request = ScanRequest::new(dir.path());
+            request.limit = Some(limit);
+            request.max_limit = max_limit;
+            request.min_size = Some(0);
+            request.shard = shard;
+            Scanner::new().scan_sync(&request).unwrap()
+        };
+        let sharded = |limit: usize, max_limit: usize| {
+            let plan = ShardManifest::plan(dir.path(), 2).unwrap();
+            merge((0..plan.shards.len()).map(|i| scan(limit, max_limit, plan.assignment(i))).collect())
+        };
+
+        // limit = 0：不收集 Top N 与密度排名，其余统计不受影响；单次扫描与分片合并一致
+        for result in [scan(0, MAX_LIMIT, None), sharded(0, MAX_LIMIT)] {
+            assert!(result.top_files.is_empty());
+            assert!(result.density.heavy.is_empty());
+            assert_eq!(result.summary.top_limit, 0);
+            assert_eq!(result.summary.total_files, 3);
+            assert_eq!(result.diagnostics.requested_limit, None);
+        }
+
+        // 超过上限：按上限截断并报告原始请求值
+        for result in [scan(usize::MAX, 2, None), sharded(usize::MAX, 2)] {
+            let sizes: Vec<u64> = result.top_files.iter().map(|f| f.size_bytes).collect();
+            assert_eq!(sizes, vec![300, 200]);
+            assert_eq!(result.summary.top_limit, 2);
+            assert_eq!(result.diagnostics.requested_limit, Some(usize::MAX));
+        }
+        assert_eq!(ScanRequest::new(dir.path()).effective_limit(), DEFAULT_LIMIT);
+    }
+
+    #[test]
+    fn test_scan_with_progress_reports_snapshots() {
+        let dir = tempdir().unwrap();
+        for i in 0..5 {
+            let sub = dir.path().join(format!("d{}", i));
+            fs::create_dir(&sub).unwrap();
+            fs::write(sub.join("f.bin"), vec![0u8; 100]).unwrap();
+        }
+
+        let mut snapshots = Vec::new();
+        let result = Scanner::new()
+            .scan_with_progress(&ScanRequest::new(dir.path()), Duration::from_millis(1), |p| {
+                snapshots.push(p.clone())
+            })
+            .unwrap();
+
+        // 快照单调递增，最后一次为完成状态且与结果一致
+        assert!(snapshots.windows(2).all(|w| w[0].scanned_files <= w[1].scanned_files));
+        let last = snapshots.last().expect("至少收到最终快照");
+        assert_eq!(last.state, ScanState::Completed);
+        assert_eq!(last.scanned_files, result.summary.total_files);
+        assert_eq!(last.scanned_dirs, 6);
+        assert_eq!(last.scanned_bytes, 500);
+
+        let missing = Scanner::new().scan_with_progress(&ScanRequest::new(dir.path().join("missing")), Duration::from_millis(1), |_| {});
+        assert!(missing.is_err());
+
+        // 取消的扫描最终快照为取消状态，与结果摘要一致
+        let mut request = ScanRequest::new(dir.path());
+        let token = CancellationToken::new();
+        token.cancel();
+        request.cancel = Some(token);
+        let mut last = None;
+        let canceled = Scanner::new()
+            .scan_with_progress(&request, Duration::from_millis(1), |p| last = Some(p.state.clone()))
+            .unwrap();
+        assert_eq!(canceled.summary.state, ScanState::Canceled);
+        assert_eq!(last, Some(ScanState::Canceled));
+    }
+
+    #[test]
+    fn test_cancellation_token_stops_scan() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub").join("a.bin"), vec![0u8; 100]).unwrap();
+
+        let token = CancellationToken::new();
+        let mut request = ScanRequest::new(dir.path());
+        request.cancel = Some(token.clone());
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.summary.state, ScanState::Completed);
+        assert_eq!(result.summary.total_files, 1);
+
+        // 取消后返回部分结果（此处在开始前取消，因此不包含任何条目）
+        token.cancel();
+        let canceled = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(canceled.summary.state, ScanState::Canceled);
+        assert_eq!(canceled.summary.total_files, 0);
+        assert!(canceled.top_files.is_empty());
+
+        // 合并结果中任一部分被取消，整体即为取消状态
+        let other = tempdir().unwrap();
+        let mut other_request = ScanRequest::new(other.path());
+        other_request.cancel = Some(token);
+        let other = Scanner::new().scan_sync(&other_request).unwrap();
+        assert_eq!(merge(vec![result, other]).summary.state, ScanState::Canceled);
+    }
+
+    #[test]
+    fn test_scan_handle_progress_cancel_and_join() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub").join("a.bin"), vec![0u8; 100]).unwrap();
+        let mut request = ScanRequest::new(dir.path());
+        request.min_size = Some(0);
+
+        // 结果只返回一次，之后的进度快照为已完成
+        let mut handle = Scanner::new().spawn(&request);
+        let result = loop {
+            if let Some(result) = handle.wait_timeout(Duration::from_millis(10)) {
+                break result.unwrap();
+            }
+        };
+        assert_eq!(result.summary.total_files, 1);
+        assert!(handle.is_finished());
+        assert!(handle.wait_timeout(Duration::ZERO).is_none());
+        let progress = handle.progress();
+        assert_eq!((progress.state, progress.scanned_files, progress.scanned_bytes), (ScanState::Completed, 1, 100));
+        assert!(handle.join().is_err());
+
+        // 取消后等待得到取消状态的部分结果；错误与 scan_sync 相同
+        let token = CancellationToken::new();
+        token.cancel();
+        request.cancel = Some(token);
+        let handle = Scanner::new().spawn(&request);
+        assert_eq!(handle.progress().state, ScanState::Canceled);
+        assert_eq!(handle.join().unwrap().summary.state, ScanState::Canceled);
+        let missing = Scanner::new().spawn(&ScanRequest::new(dir.path().join("missing")));
+        assert!(matches!(missing.join(), Err(ScanError::RootNotFound { .. })));
+
+        // 出错后进度快照为失败状态
+        let mut missing = Scanner::new().spawn(&ScanRequest::new(dir.path().join("missing")));
+        assert!(missing.wait_timeout(Duration::from_secs(10)).unwrap().is_err());
+        assert_eq!(missing.progress().state, ScanState::Failed);
+    }
+
+    #[test]
+    fn test_total_bytes_estimate_drives_progress_and_eta() {
+        let live = LiveProgress::new();
+        live.plan_phase(ScanPhase::Aggregating);
+        live.begin_phase(ScanPhase::Enumerating);
+        assert_eq!(live.snapshot(ScanState::Running).phase.progress, None);
+        live.set_total_bytes_estimate(Some(1000));
+        live.bytes.fetch_add(250, Ordering::Relaxed);
+        let snapshot = live.snapshot(ScanState::Running);
+        assert_eq!(snapshot.total_bytes_estimate, Some(1000));
+        assert_eq!(snapshot.phase.progress, Some(0.25));
+        assert_eq!(snapshot.progress, 0.125);
+        assert!(snapshot.eta_seconds.is_some());
+        // 实际总量超出估算时进度封顶，不再给出 ETA
+        live.bytes.fetch_add(2000, Ordering::Relaxed);
+        let snapshot = live.snapshot(ScanState::Running);
+        assert_eq!(snapshot.phase.progress, Some(estimate::MAX_ESTIMATED_PROGRESS));
+        assert_eq!(snapshot.eta_seconds, None);
+
+        // 首次扫描普通子目录无从估算；并发提示记录本次总量，下次扫描据此估算
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.bin"), vec![0u8; 300]).unwrap();
+        let mut request = ScanRequest::new(dir.path());
+        request.min_size = Some(0);
+        let live = LiveProgress::new();
+        request.progress = Some(live.clone());
+        let first = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(live.total_bytes_estimate(), None);
+        let hints = first.concurrency_hints.unwrap();
+        assert_eq!(hints.scanned_bytes, 300);
+        request.concurrency_hints = Some(Arc::new(hints));
+        let live = LiveProgress::new();
+        request.progress = Some(live.clone());
+        Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(live.total_bytes_estimate(), Some(300));
+    }
+
+    #[test]
+    fn test_per_worker_statistics_merge_like_single_thread() {
+        let dir = tempdir().unwrap();
+        for d in 0..6 {
+            let sub = dir.path().join(format!("d{}", d));
+            fs::create_dir(&sub).unwrap();
+            for f in 0..40 {
+                let ext = ["txt", "log", "bin"][f % 3];
+                fs::write(sub.join(format!("f{}.{}", f, ext)), vec![0u8; d * 40 + f + 1]).unwrap();
+            }
+        }
+        let scan = |threads| {
+            let mut request = ScanRequest::new(dir.path());
+            request.threads = Some(threads);
+            request.min_size = Some(0);
+            request.limit = Some(7);
+            request.stale_days = Some(0);
+            request.stale_limit = Some(5);
+            Scanner::new().scan_sync(&request).unwrap()
+        };
+        let single = scan(1);
+        let parallel = scan(4);
+        let paths = |files: &[FileEntry]| files.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+        assert_eq!(single.top_files.len(), 7);
+        assert_eq!(single.top_files[0].size_bytes, 240);
+        assert_eq!(paths(&parallel.top_files), paths(&single.top_files));
+        assert_eq!(single.stale_files.len(), 5);
+        assert_eq!(paths(&parallel.stale_files), paths(&single.stale_files));
+        let extensions = |r: &ScanResult| {
+            r.by_extension.iter().map(|e| (e.extension.clone(), e.file_count, e.total_size_bytes)).collect::<Vec<_>>()
+        };
+        assert_eq!(extensions(&parallel), extensions(&single));
+        assert_eq!(parallel.by_extension.iter().map(|e| e.file_count).sum::<u64>(), 240);
+        assert_eq!(parallel.directories, single.directories);
+    }
+
+    #[test]
+    fn test_memory_budget_bounds_in_flight_tasks() {
+        let budget = TaskBudget::new(Some(3 * budget::TASK_BYTES), 2);
+        let permits: Vec<_> = (0..3).map(|_| budget.try_acquire().unwrap()).collect();
+        assert!(budget.try_acquire().is_none());
+        drop(permits);
+        assert!(budget.try_acquire().is_some());
+        // 预算再小也为每个工作线程保留名额
+        let budget = TaskBudget::new(Some(1), 2);
+        let _permits = (budget.try_acquire().unwrap(), budget.try_acquire().unwrap());
+        assert!(budget.try_acquire().is_none());
+
+        let dir = tempdir().unwrap();
+        let tree = testutil::TreeSpec::new().depth(3).fanout(4).files_per_dir(5).generate(dir.path()).unwrap();
+        let mut request = ScanRequest::new(dir.path());
+        request.min_size = Some(0);
+        request.threads = Some(2);
+        let unbounded = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(unbounded.diagnostics.budget_deferred_dirs, 0);
+        request.memory_budget = Some(1);
+        let bounded = Scanner::new().scan_sync(&request).unwrap();
+        assert!(bounded.diagnostics.budget_deferred_dirs > 0);
+        assert_eq!(bounded.summary.total_files, tree.files);
+        assert_eq!(bounded.summary.total_dirs, unbounded.summary.total_dirs);
+        assert_eq!(bounded.summary.total_size_bytes, unbounded.summary.total_size_bytes);
+        assert_eq!(bounded.directories, unbounded.directories);
+    }
+
+    #[test]
+    fn test_io_pacing_limits_metadata_rate() {
+        let mut request = ScanRequest::new("/");
+        assert_eq!(request.effective_io_ops_per_sec(), None);
+        request.nice_mode = true;
+        assert_eq!(request.effective_io_ops_per_sec(), Some(pacing::NICE_MODE_OPS_PER_SEC));
+        request.max_io_ops_per_sec = Some(50);
+        assert_eq!(request.effective_io_ops_per_sec(), Some(50));
+
+        let live = LiveProgress::new();
+        live.begin_phase(ScanPhase::Enumerating);
+        live.begin_io_pacing(Some(50));
+        live.add_io_ops(10);
+        std::thread::sleep(Duration::from_millis(5));
+        let snapshot = live.snapshot(ScanState::Running);
+        assert_eq!(snapshot.io_ops_limit, Some(50));
+        assert!(snapshot.io_ops_per_sec.is_some_and(|rate| rate > 0.0));
+
+        // 1 次读取目录 + 40 次 stat，按 200 次/秒约需 0.2 秒
+        let dir = tempdir().unwrap();
+        for i in 0..40 {
+            fs::write(dir.path().join(format!("f{}", i)), b"x").unwrap();
+        }
+        let mut request = ScanRequest::new(dir.path());
+        request.min_size = Some(0);
+        request.max_io_ops_per_sec = Some(200);
+        let live = LiveProgress::new();
+        request.progress = Some(live.clone());
+        let start = Instant::now();
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(150));
+        assert_eq!(result.summary.total_files, 40);
+        assert_eq!(live.io_ops(), 41);
+        assert_eq!(live.io_ops_limit(), Some(200));
+        // 遍历结束后不再报告实时速率
+        assert_eq!(live.snapshot(ScanState::Completed).io_ops_per_sec, None);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_long_path_extended_form() {
+        let name = "x".repeat(250);
+        let long = PathBuf::from(format!(r"C:\dir\..\{}", name));
+        assert_eq!(long_path::extended(&long), Path::new(&format!(r"\\?\C:\{}", name)));
+        let unc = PathBuf::from(format!(r"\\srv\share\{}", name));
+        assert_eq!(long_path::extended(&unc), Path::new(&format!(r"\\?\UNC\srv\share\{}", name)));
+        assert_eq!(long_path::extended(Path::new(r"C:\short")), Path::new(r"C:\short"));
+        assert_eq!(long_path::strip_extended(Path::new(r"\\?\C:\target")), Path::new(r"C:\target"));
+        assert_eq!(long_path::strip_extended(Path::new(r"\\?\UNC\srv\share")), Path::new(r"\\srv\share"));
+    }
+
+    #[test]
+    fn test_check_request_reports_silently_ignored_settings() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".gitignore"), "target/\n[z-a]\n").unwrap();
+        let mut request = ScanRequest::new(dir.path());
+        assert!(check::check(&request).is_empty());
+
+        request.exclude_patterns = vec!["**/*.log".into(), "a[".into()];
+        request.include_regex = vec!["anchored:(".into()];
+        request.size_histograms = vec!["category:nope".into()];
+        request.respect_ignore_files = true;
+        let issues = check::check(&request);
+        let summary: Vec<_> = issues.iter().map(|i| (i.location.as_str(), i.fatal)).collect();
+        let gitignore = dir.path().join(".gitignore").display().to_string();
+        assert_eq!(
+            summary,
+            [("exclude_patterns[1]", false), ("include_regex[0]", true), ("size_histograms[0]", true), (gitignore.as_str(), false)]
+        );
+        assert!(issues[3].message.starts_with("无法解析，该行被跳过: line 2:"), "{}", issues[3].message);
+
+        request.root_path = dir.path().join("missing");
+        assert_eq!(check::check(&request)[0].location, "root_path");
+    }
+
+    #[test]
+    fn test_keep_awake_status_in_diagnostics() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.bin"), vec![0u8; 100]).unwrap();
+        let mut request = ScanRequest::new(dir.path());
+        assert!(Scanner::new().scan_sync(&request).unwrap().diagnostics.keep_awake.is_none());
+
+        // 申请失败（如沙箱中没有 systemd-logind）不影响扫描，只记录原因
+        request.keep_awake = true;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(result.summary.total_files, 1);
+        let status = result.diagnostics.keep_awake.clone().unwrap();
+        if cfg!(target_os = "linux") {
+            assert_eq!(status.mechanism, "systemd_inhibit");
+        }
+        assert_ne!(status.held, status.error.is_some());
+        assert_eq!(merge(vec![result]).diagnostics.keep_awake, Some(status));
+
+        // 取消与失败同样释放守卫
+        let token = CancellationToken::new();
+        token.cancel();
+        request.cancel = Some(token);
+        assert_eq!(Scanner::new().scan_sync(&request).unwrap().summary.state, ScanState::Canceled);
+        request.root_path = dir.path().join("missing");
+        assert!(matches!(Scanner::new().scan_sync(&request), Err(ScanError::RootNotFound { .. })));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_bind_mount_counted_once() {
+        // 需要挂载权限（以 root 运行），否则跳过
+        struct Unmount(PathBuf);
+        impl Drop for Unmount {
+            fn drop(&mut self) {
+                let _ = std::process::Command::new("umount").arg(&self.0).status();
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let data = root.join("data");
+        let mirror = root.join("mirror");
+        fs::create_dir_all(data.join("sub")).unwrap();
+        fs::create_dir(&mirror).unwrap();
+        File::create(data.join("sub/big.bin")).unwrap().write_all(&[0; 1000]).unwrap();
+        let mounted = std::process::Command::new("mount")
+            .arg("--bind")
+            .arg(&data)
+            .arg(&mirror)
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success());
+        if !mounted {
+            return;
+        }
+        let _guard = Unmount(mirror.clone());
+
+        let result = Scanner::new().scan_sync(&ScanRequest::new(root)).unwrap();
+        assert_eq!(result.summary.total_size_bytes, 1000);
+        assert_eq!(result.diagnostics.revisited_dirs, 1);
+        let alias = &result.diagnostics.aliased_dirs[0];
+        let mut pair = [alias.path.clone(), alias.alias_of.clone().unwrap()];
+        pair.sort();
+        assert_eq!(pair, [data, mirror]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_policies_and_cycle_detection() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempdir().unwrap();
+        let real = dir.path().join("real");
+        fs::create_dir(&real).unwrap();
+        fs::write(real.join("a.bin"), vec![0u8; 100]).unwrap();
+        symlink(&real, dir.path().join("alias")).unwrap();
+        symlink(dir.path(), real.join("loop")).unwrap();
+        symlink(real.join("a.bin"), dir.path().join("a.lnk")).unwrap();
+        let link_size = |name: &str| fs::symlink_metadata(dir.path().join(name)).unwrap().len();
+
+        let scan = |policy: SymlinkPolicy| {
+            let mut request = ScanRequest::new(dir.path());
+            request.follow_symlinks = policy;
+            Scanner::new().scan_sync(&request).unwrap()
+        };
+
+        // 不跟随：三个链接都按自身计为文件
+        let never = scan(SymlinkPolicy::Never);
+        assert_eq!((never.summary.total_files, never.summary.total_dirs), (4, 2));
+        assert_eq!(never.diagnostics.revisited_dirs, 0);
+        assert!(never.diagnostics.aliased_dirs.is_empty());
+        let target = |result: &ScanResult, path: PathBuf| {
+            result.top_files.iter().find(|f| f.path == path).and_then(|f| f.link_target.clone())
+        };
+        assert_eq!(target(&never, dir.path().join("alias")), Some(real.clone()));
+        assert_eq!(target(&never, dir.path().join("a.lnk")), Some(real.join("a.bin")));
+        assert_eq!(target(&never, real.join("a.bin")), None);
+
+        // 只跟随目录链接：alias 与 real 是同一目录，loop 指回根目录，均只统计一次
+        let dirs = scan(SymlinkPolicy::FollowDirs);
+        assert_eq!((dirs.summary.total_files, dirs.summary.total_dirs), (2, 2));
+        assert_eq!(dirs.summary.total_size_bytes, 100 + link_size("a.lnk"));
+        assert_eq!(dirs.diagnostics.revisited_dirs, 2);
+        // 被跳过的路径及其先被统计的路径：loop 指回根目录；alias 与 real 哪个先被统计取决于调度
+        let aliases = &dirs.diagnostics.aliased_dirs;
+        assert_eq!(aliases.len(), 2);
+        assert!(aliases.contains(&DirAlias { path: real.join("loop"), alias_of: Some(dir.path().to_path_buf()) }));
+        let pair = aliases.iter().find(|a| a.path != real.join("loop")).unwrap();
+        let mut pair = [pair.path.clone(), pair.alias_of.clone().unwrap()];
+        pair.sort();
+        assert_eq!(pair, [dir.path().join("alias"), real.clone()]);
+
+        // 仅汇总扫描只记录被跳过的路径
+        let mut request = ScanRequest::new(dir.path());
+        request.follow_symlinks = SymlinkPolicy::FollowDirs;
+        request.collect_entries = false;
+        let summary = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(summary.diagnostics.aliased_dirs.len(), 2);
+        assert!(summary.diagnostics.aliased_dirs.iter().all(|a| a.alias_of.is_none()));
+
+        // 全部跟随：文件链接按目标大小统计
+        let all = scan(SymlinkPolicy::FollowAll);
+        assert_eq!((all.summary.total_files, all.summary.total_size_bytes), (2, 200));
+        assert_eq!(target(&all, dir.path().join("a.lnk")), Some(real.join("a.bin")));
+        assert_eq!(all.diagnostics.revisited_dirs, 2);
+
+        assert_eq!("follow-dirs".parse::<SymlinkPolicy>(), Ok(SymlinkPolicy::FollowDirs));
+        assert!("sometimes".parse::<SymlinkPolicy>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_deduplicate_hardlinks() {
+        let dir = tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        fs::write(dir.path().join("a.bin"), vec![0u8; 100]).unwrap();
+        fs::hard_link(dir.path().join("a.bin"), sub.join("b.bin")).unwrap();
+        fs::hard_link(dir.path().join("a.bin"), sub.join("c.bin")).unwrap();
+        fs::write(sub.join("d.bin"), vec![0u8; 50]).unwrap();
+
+        let mut request = ScanRequest::new(dir.path());
+        let plain = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!((plain.summary.total_files, plain.summary.total_size_bytes), (4, 350));
+        assert_eq!(plain.diagnostics.skipped_hardlinks, 0);
+
+        request.deduplicate_hardlinks = true;
+        let deduped = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!((deduped.summary.total_files, deduped.summary.total_size_bytes), (2, 150));
+        assert_eq!(deduped.diagnostics.skipped_hardlinks, 2);
+        assert_eq!(deduped.diagnostics.skipped_hardlink_bytes, 200);
+        // 目录大小同样只计入一次
+        let root = deduped.directories.iter().find(|d| d.path == dir.path()).unwrap();
+        assert_eq!(root.size_bytes, 150);
+        assert_eq!(deduped.top_files.iter().filter(|f| f.size_bytes == 100).count(), 1);
+    }
+
+    #[test]
+    fn test_cleanup_plan_prefers_low_risk_and_skips_protected() {
+        use crate::cleanup::{plan, CleanupOptions, CleanupSource};
+        const MB: u64 = 1 << 20;
+        let entry = |path: &str, size: u64| FileEntry::new(path, size * MB);
+        let mut result = ScanResult::default();
+        result.summary.root_path = "/data".into();
+        let mut backup = entry("/data/report.doc.bak", 5);
+        backup.redundant = Some(redundant::RedundantKind::Backup);
+        let mut swap = entry("/data/pagefile.sys", 800);
+        swap.redundant = Some(redundant::RedundantKind::Backup);
+        swap.reserved = Some(reserved::ReservedKind::PageFile);
+        result.redundant_files = vec![backup, swap];
+        result.top_files = vec![entry("/data/home/.cache/pip/wheel.whl", 40), entry("/data/videos/film.mkv", 900)];
+        let mut old = entry("/data/archive/2019.tar", 300);
+        old.idle_seconds = Some(400 * 86_400);
+        result.stale_files = vec![old, entry("/data/home/.cache/old.bin", 20), entry("/data/keep/thesis.pdf", 70)];
+        result.duplicates = vec![DuplicateGroup {
+            size_bytes: 60 * MB,
+            digest: "d".into(),
+            paths: vec!["/data/a/photo.jpg".into(), "/data/b/photo.jpg".into(), "/data/keep/photo.jpg".into()],
+        }];
+
+        let options = CleanupOptions { target_bytes: 180 * MB, keep_patterns: vec!["keep/**".into()] };
+        let cleanup = plan(&result, &options).unwrap();
+        let paths: Vec<_> = cleanup.items.iter().map(|item| item.path.to_str().unwrap()).collect();
+        // 冗余 → 缓存（大的在前，stale 中的缓存文件按缓存计一次）→ 重复副本（保留受保护的那份），达到目标即止
+        assert_eq!(
+            paths,
+            [
+                "/data/report.doc.bak",
+                "/data/home/.cache/pip/wheel.whl",
+                "/data/home/.cache/old.bin",
+                "/data/a/photo.jpg",
+                "/data/b/photo.jpg",
+            ]
+        );
+        assert_eq!(cleanup.items[3].source, CleanupSource::Duplicate);
+        assert_eq!(cleanup.items[3].reason, "与 /data/keep/photo.jpg 内容相同");
+        assert_eq!(cleanup.planned_bytes, 185 * MB);
+        assert!(cleanup.target_met);
+
+        // 目标更大时依次纳入陈旧文件；系统保留与保留规则匹配的路径始终不出现
+        let options = CleanupOptions { target_bytes: 10_000 * MB, ..options };
+        let cleanup = plan(&result, &options).unwrap();
+        assert!(!cleanup.target_met);
+        assert_eq!(cleanup.items.last().unwrap().reason, "400 天未使用");
+        assert!(cleanup.items.iter().all(|item| !item.path.starts_with("/data/keep") && !item.path.ends_with("pagefile.sys")));
+        assert_eq!(cleanup.protected_skipped, 2);
+
+        // 条目可直接用于 delete_entry 的预演校验
+        let options = cleanup.items[0].delete_options(crate::delete::DeleteOrigin::Cli, true);
+        assert!(options.dry_run && options.expected.is_some());
+        let options = cleanup.delete_options(crate::delete::DeleteOrigin::Cli, true);
+        assert_eq!(options.scan_root.as_deref(), Some(cleanup.root_path.as_path()));
+        assert_eq!(cleanup.delete_items().len(), cleanup.items.len());
+    }
+
+    #[test]
+    fn test_cleanup_selections_apply_with_transcript() {
+        use crate::cleanup::{select, CleanupPlan, CleanupSource, Selection};
+        use delete::{DeleteOrigin, ERROR_CHANGED};
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let now = SystemTime::now();
+        let write = |rel: &str, byte: u8, size: usize, age_days: u64| {
+            let path = root.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, vec![byte; size]).unwrap();
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(now - Duration::from_secs(age_days * 86_400)).unwrap();
+        };
+        write("archive/2019.tar", 1, 3000, 400);
+        write("archive/recent.tar", 2, 3100, 10);
+        write("a/photo.jpg", 3, 2000, 30);
+        write("b/photo.jpg", 3, 2000, 5);
+        write("logs/app.log", 4, 500, 1);
+        write("keep/app.log", 5, 600, 1);
+
+        let mut request = ScanRequest::new(root);
+        request.min_size = Some(0);
+        request.stale_days = Some(7);
+        request.dedup = Some(DedupOptions::default());
+        let result = Scanner::new().scan_sync(&request).unwrap();
+
+        let selections: Vec<Selection> =
+            ["stale:100", "duplicates", "glob:**/*.log"].iter().map(|s| s.parse().unwrap()).collect();
+        assert_eq!(selections[0], Selection::StaleOlderThan { days: 100 });
+        assert!("stale:soon".parse::<Selection>().is_err() && "glob:".parse::<Selection>().is_err());
+        let plan = select(&result, &selections, &["keep/**".to_string()]).unwrap();
+        let items: Vec<_> =
+            plan.items.iter().map(|item| (item.path.strip_prefix(root).unwrap().to_path_buf(), item.source)).collect();
+        // 按风险排列；最新的副本与受保护的路径不在方案中
+        assert_eq!(
+            items,
+            vec![
+                (PathBuf::from("a/photo.jpg"), CleanupSource::Duplicate),
+                (PathBuf::from("archive/2019.tar"), CleanupSource::Stale),
+                (PathBuf::from("logs/app.log"), CleanupSource::Matched),
+            ]
+        );
+        assert_eq!(plan.items[0].reason, format!("与 {} 内容相同（保留最新的副本）", root.join("b/photo.jpg").display()));
+        assert_eq!((plan.planned_bytes, plan.protected_skipped), (5500, 1));
+
+        // 序列化为 JSON 供审阅后原样加载
+        let saved = root.join("plan.json");
+        plan.save(&saved).unwrap();
+        let plan = CleanupPlan::load(&saved).unwrap();
+        assert_eq!(plan.items.iter().map(|item| item.path.strip_prefix(root).unwrap().to_path_buf()).collect::<Vec<_>>(), items.iter().map(|(path, _)| path.clone()).collect::<Vec<_>>());
+        assert_eq!(plan.selections, selections);
+
+        // 扫描后发生变化的条目在预演与执行中都被拒绝，其余各项不受影响
+        write("logs/app.log", 4, 700, 0);
+        let preview = plan.apply(DeleteMode::Permanent, DeleteOrigin::Cli, true, false);
+        assert_eq!((preview.succeeded, preview.failed, preview.freed_bytes), (2, 1, 5000));
+        assert!(root.join("a/photo.jpg").exists() && root.join("archive/2019.tar").exists());
+        let transcript = plan.apply(DeleteMode::Permanent, DeleteOrigin::Cli, false, false);
+        assert_eq!(transcript.entries.len(), 3);
+        assert_eq!(transcript.entries[2].result.error.as_ref().map(|e| e.code), Some(ERROR_CHANGED));
+        assert_eq!((transcript.succeeded, transcript.freed_bytes), (2, 5000));
+        assert!(!root.join("a/photo.jpg").exists() && !root.join("archive/2019.tar").exists());
+        assert!(root.join("b/photo.jpg").exists() && root.join("logs/app.log").exists());
+        let json = serde_json::to_value(&transcript).unwrap();
+        assert_eq!(json["entries"][0]["success"], true);
+    }
+
+    #[test]
+    fn test_delete_entry_verifies_scanned_entry() {
+        use delete::{ExpectedEntry, Verification, ERROR_CHANGED, ERROR_NOT_FOUND};
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.bin");
+        fs::write(&path, vec![0u8; 100]).unwrap();
+        let result = Scanner::new().scan_sync(&ScanRequest::new(dir.path())).unwrap();
+        let scanned = ExpectedEntry::from(&result.top_files[0]);
+
+        let mut options = DeleteOptions {
+            mode: DeleteMode::Permanent,
+            dry_run: true,
+            expected: Some(scanned.clone()),
+            ..DeleteOptions::default()
+        };
+        let checked = delete_entry(&path, &options);
+        assert!(checked.success && path.exists(), "dry_run 不应删除文件: {:?}", checked);
+
+        // 扫描后被替换为不同大小的文件：拒绝删除并说明原因
+        fs::write(&path, vec![0u8; 120]).unwrap();
+        let refused = delete_entry(&path, &options);
+        let error = refused.error.expect("大小变化时应拒绝删除");
+        assert_eq!(error.code, ERROR_CHANGED);
+        assert!(error.message.contains("100") && error.message.contains("120"), "{}", error.message);
+
+        // 大小相同但修改时间不同：只有 SizeAndMtime 会拒绝
+        fs::write(&path, vec![1u8; 100]).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(scanned.last_modified.unwrap() + Duration::from_secs(60)).unwrap();
+        assert!(delete_entry(&path, &options).success);
+        options.verify = Verification::SizeAndMtime;
+        assert_eq!(delete_entry(&path, &options).error.map(|e| e.code), Some(ERROR_CHANGED));
+
+        // 换成了目录
+        fs::remove_file(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        assert_eq!(delete_entry(&path, &options).error.map(|e| e.code), Some(ERROR_CHANGED));
+
+        // 校验通过后真正删除
+        options = DeleteOptions {
+            mode: DeleteMode::Permanent,
+            expected: Some(ExpectedEntry::dir()),
+            ..DeleteOptions::default()
+        };
+        let deleted = delete_entry(&path, &options);
+        assert!(deleted.success && !path.exists());
+        assert_eq!(deleted.effective_mode, DeleteMode::Permanent);
+        assert_eq!(delete_entry(&path, &options).error.map(|e| e.code), Some(ERROR_NOT_FOUND));
+    }
+
+    #[test]
+    fn test_batch_delete_reports_each_item_and_protects_roots() {
+        use delete::{DeleteItem, ExpectedEntry, ERROR_CHANGED, ERROR_NOT_FOUND, ERROR_PROTECTED};
+
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("scan");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("a.bin"), [0u8; 10]).unwrap();
+        fs::write(root.join("b.bin"), [0u8; 20]).unwrap();
+        fs::write(root.join("pagefile.sys"), [0u8; 30]).unwrap();
+        let result = Scanner::new().scan_sync(&ScanRequest::new(&root)).unwrap();
+        let scanned = |name: &str| result.top_files.iter().find(|f| f.path.ends_with(name)).unwrap();
+
+        let mut stale = DeleteItem::from(scanned("b.bin"));
+        stale.expected = Some(ExpectedEntry { size_bytes: 99, ..ExpectedEntry::from(scanned("b.bin")) });
+        let items = vec![
+            DeleteItem::from(scanned("a.bin")),
+            stale,
+            DeleteItem::new(root.join("missing.bin"), None),
+            DeleteItem::new(root.join("pagefile.sys"), None),
+            // 扫描根目录本身及其上级目录（含以 `..` 迂回给出的路径）
+            DeleteItem::new(&root, None),
+            DeleteItem::new(root.join("..").join("scan"), None),
+            DeleteItem::new(dir.path(), None),
+        ];
+        let options = DeleteOptions { scan_root: Some(root.clone()), ..DeleteOptions::default() };
+        let results = delete::permanent(&items, &options);
+        let codes: Vec<_> = results.iter().map(|r| r.result.error.as_ref().map(|e| e.code)).collect();
+        assert_eq!(
+            codes,
+            vec![None, Some(ERROR_CHANGED), Some(ERROR_NOT_FOUND), Some(ERROR_PROTECTED), Some(ERROR_PROTECTED), Some(ERROR_PROTECTED), Some(ERROR_PROTECTED)]
+        );
+        assert_eq!(results.iter().map(|r| r.path.clone()).collect::<Vec<_>>(), items.iter().map(|i| i.path.clone()).collect::<Vec<_>>());
+        assert!(!root.join("a.bin").exists() && root.join("b.bin").exists() && root.join("pagefile.sys").exists());
+        assert_eq!(results[0].result.effective_mode, DeleteMode::Permanent);
+
+        // 保护规则在 dry_run 中同样生效；单个删除同样适用（允许退化，不依赖测试环境中的回收站）
+        let dry_run = DeleteOptions { dry_run: true, allow_permanent_fallback: true, ..options };
+        let checked = delete::to_trash(&[DeleteItem::new(&root, None), DeleteItem::new(root.join("b.bin"), None)], &dry_run);
+        assert_eq!(checked[0].result.error.as_ref().map(|e| e.code), Some(ERROR_PROTECTED));
+        assert!(checked[1].result.success && root.join("b.bin").exists());
+        assert_eq!(delete_entry(&root, &dry_run).error.map(|e| e.code), Some(ERROR_PROTECTED));
+
+        #[cfg(unix)]
+        {
+            assert!(delete::protection_reason(Path::new("/"), None).is_some());
+            assert!(delete::protection_reason(Path::new("/usr/bin/env"), None).is_some());
+            assert!(delete::protection_reason(Path::new("/var"), None).is_some());
+            assert!(delete::protection_reason(Path::new("/var/tmp/surf-missing"), None).is_none());
+            // 指向系统目录的符号链接按链接本身判断
+            std::os::unix::fs::symlink("/usr", root.join("usr-link")).unwrap();
+            assert!(delete::protection_reason(&root.join("usr-link"), None).is_none());
+        }
+    }
+
+    #[test]
+    fn test_trash_failure_does_not_fall_back_silently() {
+        use delete::ERROR_IO;
+
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("a.bin");
+        fs::write(&file, b"x").unwrap();
+        let metadata = fs::metadata(&file).unwrap();
+
+        // 回收站可用性：没有回收站时不可用；与条目同一文件系统且可写的回收站（尚未创建）可用
+        assert!(delete::usable_trash(None, &metadata).is_err());
+        let trash = dir.path().join("Trash");
+        assert_eq!(delete::usable_trash(Some(&trash), &metadata), Ok(trash.clone()));
+        #[cfg(target_os = "linux")]
+        {
+            // /proc 与临时目录不在同一文件系统
+            use std::os::unix::fs::MetadataExt;
+            if fs::metadata("/proc").map(|proc| proc.dev() != metadata.dev()).unwrap_or(false) {
+                assert!(delete::usable_trash(Some(Path::new("/proc/Trash")), &metadata).unwrap_err().contains("同一文件系统"));
+            }
+        }
+
+        // 预演同样检查回收站：不可用时报告 ERROR_IO 而不是永久删除；允许退化时按永久删除预演
+        let options = DeleteOptions { dry_run: true, ..DeleteOptions::default() };
+        let result = delete_entry(&file, &options);
+        match delete::trash_root().as_deref().map(|trash| delete::usable_trash(Some(trash), &metadata)) {
+            Some(Ok(_)) => assert!(result.success && result.effective_mode == DeleteMode::MoveToTrash),
+            _ => {
+                assert_eq!(result.error.map(|e| e.code), Some(ERROR_IO));
+                assert_eq!(result.effective_mode, DeleteMode::MoveToTrash);
+                let result = delete_entry(&file, &DeleteOptions { allow_permanent_fallback: true, ..options });
+                assert!(result.success && result.effective_mode == DeleteMode::Permanent);
+            }
+        }
+        assert!(file.exists());
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_move_to_xdg_trash() {
+        let dir = tempdir().unwrap();
+        let trash = dir.path().join("Trash");
+        for _ in 0..2 {
+            fs::write(dir.path().join("a b.txt"), b"x").unwrap();
+        }
+        let first = delete::move_to_trash(&dir.path().join("a b.txt"), &trash).unwrap();
+        fs::write(dir.path().join("a b.txt"), b"y").unwrap();
+        let second = delete::move_to_trash(&dir.path().join("a b.txt"), &trash).unwrap();
+        assert_eq!(first, trash.join("files").join("a b.txt"));
+        assert_eq!(second, trash.join("files").join("a b.txt.2"));
+        assert!(!dir.path().join("a b.txt").exists());
+        let info = fs::read_to_string(trash.join("info").join("a b.txt.trashinfo")).unwrap();
+        assert!(info.starts_with("[Trash Info]\nPath=/"), "{}", info);
+        assert!(info.contains("a%20b.txt\nDeletionDate="), "{}", info);
+
+        // 恢复：原位置被占用时拒绝，腾空后移回并清理 .trashinfo
+        let original = dir.path().join("a b.txt");
+        fs::write(&original, b"z").unwrap();
+        assert!(delete::restore_from_trash(&second, &original).is_err());
+        fs::remove_file(&original).unwrap();
+        delete::restore_from_trash(&second, &original).unwrap();
+        assert_eq!(fs::read(&original).unwrap(), b"y");
+        assert!(!second.exists());
+        assert!(!trash.join("info").join("a b.txt.2.trashinfo").exists());
+        assert!(trash.join("info").join("a b.txt.trashinfo").exists());
+
+        // 目录同样可以恢复，原位置被占用时不覆盖
+        fs::create_dir(dir.path().join("d")).unwrap();
+        fs::write(dir.path().join("d").join("f"), b"f").unwrap();
+        let trashed_dir = delete::move_to_trash(&dir.path().join("d"), &trash).unwrap();
+        fs::create_dir(dir.path().join("d")).unwrap();
+        let err = delete::restore_from_trash(&trashed_dir, &dir.path().join("d")).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert!(trashed_dir.join("f").exists());
+        fs::remove_dir(dir.path().join("d")).unwrap();
+        delete::restore_from_trash(&trashed_dir, &dir.path().join("d")).unwrap();
+        assert_eq!(fs::read(dir.path().join("d").join("f")).unwrap(), b"f");
+        assert!(!trash.join("info").join("d.trashinfo").exists());
+
+        // 不在回收站 files 目录中的路径不清理任何 .trashinfo
+        let other = dir.path().join("other");
+        fs::create_dir_all(other.join("stash")).unwrap();
+        fs::create_dir_all(other.join("info")).unwrap();
+        fs::write(other.join("stash").join("x"), b"x").unwrap();
+        fs::write(other.join("info").join("x.trashinfo"), b"keep").unwrap();
+        delete::restore_from_trash(&other.join("stash").join("x"), &dir.path().join("x")).unwrap();
+        assert!(other.join("info").join("x.trashinfo").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rename_exclusive_fallback_does_not_replace() {
+        let dir = tempdir().unwrap();
+        let (from, to) = (dir.path().join("from"), dir.path().join("to"));
+
+        // 文件：目标已存在时失败，两边保持原样；目标空出后移动
+        fs::write(&from, b"new").unwrap();
+        fs::write(&to, b"old").unwrap();
+        assert_eq!(delete::rename_exclusive(&from, &to).unwrap_err().kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!((fs::read(&from).unwrap(), fs::read(&to).unwrap()), (b"new".to_vec(), b"old".to_vec()));
+        fs::remove_file(&to).unwrap();
+        delete::rename_exclusive(&from, &to).unwrap();
+        assert!(!from.exists() && fs::read(&to).unwrap() == b"new");
+
+        // 目录：已存在的目标（即使为空目录）不被替换
+        fs::remove_file(&to).unwrap();
+        fs::create_dir(&from).unwrap();
+        fs::write(from.join("f"), b"f").unwrap();
+        fs::create_dir(&to).unwrap();
+        assert_eq!(delete::rename_exclusive(&from, &to).unwrap_err().kind(), std::io::ErrorKind::AlreadyExists);
+        assert!(from.join("f").exists() && fs::read_dir(&to).unwrap().next().is_none());
+        fs::remove_dir(&to).unwrap();
+        delete::rename_exclusive(&from, &to).unwrap();
+        assert!(!from.exists() && to.join("f").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_allocated_size_of_sparse_files() {
+        let dir = tempdir().unwrap();
+        let sparse = fs::File::create(dir.path().join("sparse.img")).unwrap();
+        sparse.set_len(64 * 1024 * 1024).unwrap();
+        fs::write(dir.path().join("dense.bin"), vec![1u8; 8192]).unwrap();
+
+        let mut request = ScanRequest::new(dir.path());
+        let plain = Scanner::new().scan_sync(&request).unwrap();
+        assert_eq!(plain.summary.total_allocated_bytes, None);
+        assert!(plain.top_files.iter().all(|f| f.allocated_bytes.is_none()));
+
+        request.allocated_sizes = true;
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        let entry = |name: &str| result.top_files.iter().find(|f| f.path.ends_with(name)).unwrap();
+        assert_eq!(entry("sparse.img").size_bytes, 64 * 1024 * 1024);
+        assert!(entry("sparse.img").allocated_bytes.unwrap() < 64 * 1024 * 1024);
+        assert!(entry("dense.bin").allocated_bytes.unwrap() >= 8192);
+        let total = result.summary.total_allocated_bytes.unwrap();
+        assert_eq!(total, entry("sparse.img").allocated_bytes.unwrap() + entry("dense.bin").allocated_bytes.unwrap());
+
+        // 合并时求和；任一部分未统计时为 None
+        let other = tempdir().unwrap();
+        fs::write(other.path().join("c.bin"), vec![1u8; 4096]).unwrap();
+        let mut other_request = ScanRequest::new(other.path());
+        other_request.allocated_sizes = true;
+        let other_result = Scanner::new().scan_sync(&other_request).unwrap();
+        let other_total = other_result.summary.total_allocated_bytes.unwrap();
+        assert_eq!(merge(vec![result.clone(), other_result]).summary.total_allocated_bytes, Some(total + other_total));
+        let other_plain = Scanner::new().scan_sync(&ScanRequest::new(other.path())).unwrap();
+        assert_eq!(merge(vec![result, other_plain]).summary.total_allocated_bytes, None);
+    }
+
+    /// 序列化 -> 反序列化 -> 再序列化，JSON 应保持不变
+    fn assert_round_trip<T: Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+        let json = serde_json::to_value(value).unwrap();
+        let back: T = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), json);
+        back
+    }
+
+    #[test]
+    fn test_serde_round_trip_of_public_types() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("copies")).unwrap();
+        for name in ["a.log", "copies/a.log"] {
+            let file = File::create(dir.path().join(name)).unwrap();
+            file.set_len(2048).unwrap();
+            file.set_modified(UNIX_EPOCH + Duration::from_millis(1_700_000_000_123)).unwrap();
+        }
+
+        let mut request = ScanRequest::new(dir.path());
+        request.stale_days = Some(1);
+        request.limit = Some(5);
+        request.dedup = Some(DedupOptions::default());
+        request.cancel = Some(CancellationToken::new());
+        let back = assert_round_trip(&request);
+        assert_eq!(back.effective_limit(), 5);
+
+        // 省略的字段取与 ScanRequest::new 相同的默认值
+        let minimal: ScanRequest = serde_json::from_value(serde_json::json!({"root_path": "/data"})).unwrap();
+        assert_eq!(minimal.max_limit, MAX_LIMIT);
+        assert_eq!(minimal.heatmap_depth, heatmap::DEFAULT_HEATMAP_DEPTH);
+        assert_eq!(minimal.metadata_retry, RetryPolicy::default());
+
+        let result = Scanner::new().scan_sync(&request).unwrap();
+        assert!(!result.stale_files.is_empty() && !result.duplicates.is_empty() && !result.heatmap.is_empty());
+        let back = assert_round_trip(&result);
+        // 修改时间以毫秒精度序列化
+        let json = serde_json::to_value(&result.top_files[0]).unwrap();
+        assert_eq!(json["last_modified"], serde_json::json!(1_700_000_000_123i64));
+        assert_eq!(back.top_files[0].last_modified, result.top_files[0].last_modified);
+
+        assert_round_trip(&LiveProgress::new().snapshot(ScanState::Running));
+        assert_round_trip(&ShardManifest::plan(dir.path(), 2).unwrap());
+        let snapshot = index::IndexSnapshot {
+            result,
+            generation: 3,
+            indexed_at: UNIX_EPOCH + Duration::from_millis(1_700_000_000_000),
+            fingerprint: 42,
+        };
+        assert_round_trip(&snapshot);
+        assert_round_trip(&index::IndexStatus {
+            root_path: dir.path().to_path_buf(),
+            ready: true,
+            scanning: false,
+            generation: 3,
+            indexed_at: Some(snapshot.indexed_at),
+            last_error: None,
+        });
+
+        // 兼容旧版写出的 SystemTime 结构
+        let legacy: FileEntry = serde_json::from_value(serde_json::json!({
+            "path": "/a", "size_bytes": 1, "extension": null,
+            "last_modified": {"secs_since_epoch": 1, "nanos_since_epoch": 500_000_000}
+        }))
+        .unwrap();
+        assert_eq!(legacy.last_modified, Some(UNIX_EPOCH + Duration::from_millis(1500)));
+        assert_eq!(serde_time::to_millis(UNIX_EPOCH - Duration::from_millis(5)), -5);
+    }
+
+    #[test]
+    fn test_rfc3339_time_format() {
+        use serde_time::{parse_rfc3339, to_rfc3339, with_format, TimeFormat};
+
+        let at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        assert_eq!(to_rfc3339(at), "2023-11-14T22:13:20.123Z");
+        assert_eq!(to_rfc3339(UNIX_EPOCH - Duration::from_millis(1)), "1969-12-31T23:59:59.999Z");
+        assert_eq!(to_rfc3339(UNIX_EPOCH + Duration::from_secs(951_782_400)), "2000-02-29T00:00:00.000Z");
+        assert_eq!(parse_rfc3339("2023-11-14T22:13:20.123Z"), Some(at));
+        assert_eq!(parse_rfc3339("2023-11-15T06:13:20.1234+08:00"), Some(at));
+        assert_eq!(parse_rfc3339("2023-11-14 22:13:20Z"), Some(at - Duration::from_millis(123)));
+        assert_eq!(parse_rfc3339("2023-13-01T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339("yesterday"), None);
+
+        let entry: FileEntry = serde_json::from_value(serde_json::json!({
+            "path": "/a", "size_bytes": 1, "extension": null, "last_modified": 1_700_000_000_123i64
+        }))
+        .unwrap();
+        let text = with_format(TimeFormat::Rfc3339, || serde_json::to_value(&entry)).unwrap();
+        assert_eq!(text["last_modified"], "2023-11-14T22:13:20.123Z");
+        // 作用域结束后恢复为毫秒数
+        assert_eq!(serde_json::to_value(&entry).unwrap()["last_modified"], 1_700_000_000_123i64);
+        // 反序列化同时接受两种格式
+        let back: FileEntry = serde_json::from_value(text).unwrap();
+        assert_eq!(back.last_modified, Some(at));
+        assert!(serde_json::from_value::<FileEntry>(serde_json::json!({
+            "path": "/a", "size_bytes": 1, "extension": null, "last_modified": "not a time"
+        }))
+        .is_err());
+        assert_eq!("RFC3339".parse::<TimeFormat>(), Ok(TimeFormat::Rfc3339));
+        assert!("iso".parse::<TimeFormat>().is_err());
+    }
+
+    #[test]
+    fn test_human_format_units_and_locale() {
+        use crate::format::{annotate_json, format_bytes};
+
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.50 KB");
+        let iec = FormatOptions::new(UnitSystem::Iec, None);
+        assert_eq!(iec.bytes(3 * 1024 * 1024), "3.00 MiB");
+        let si = FormatOptions::new(UnitSystem::Si, Some("de-DE".parse().unwrap()));
+        assert_eq!(si.bytes(1_400_000_000), "1,40 GB");
+        assert_eq!(si.bytes(1_234_500_000_000_000_000), "1.234,50 PB");
+        assert_eq!(si.count(1_234_567), "1.234.567");
+        let en = FormatOptions::new(UnitSystem::Binary, Some("en".parse().unwrap()));
+        assert_eq!(en.bytes(999), "999 B");
+        assert_eq!(en.count(1000), "1,000");
+        assert!("xx".parse::<NumberLocale>().is_err());
+        assert_eq!("SI".parse::<UnitSystem>(), Ok(UnitSystem::Si));
+
+        // 选项按 JSON 对象传入；地区以语言标签序列化
+        let options: FormatOptions = serde_json::from_str(r#"{"units":"si","locale":"fr-CA"}"#).unwrap();
+        assert_eq!(options.count(12345), "12\u{a0}345");
+        assert_eq!(serde_json::to_value(&options).unwrap()["locale"], "fr-CA");
+        assert!(serde_json::from_str::<FormatOptions>(r#"{"locale":"tlh"}"#).is_err());
+
+        let mut value = serde_json::json!({
+            "summary": {"total_size_bytes": 2_000_000, "total_files": 4200},
+            "top_files": [{"size_bytes": 1500, "path": "/a"}],
+            "share_of_total": 0.5,
+        });
+        annotate_json(&mut value, &si);
+        assert_eq!(value["summary"]["total_size_bytes_human"], "2,00 MB");
+        assert_eq!(value["summary"]["total_size_bytes"], 2_000_000);
+        assert_eq!(value["summary"]["total_files_human"], "4.200");
+        assert_eq!(value["top_files"][0]["size_bytes_human"], "1,50 kB");
+        assert!(value.get("share_of_total_human").is_none());
+    }
 }
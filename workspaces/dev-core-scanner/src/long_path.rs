@@ -0,0 +1,86 @@
+//! Windows 长路径
+//!
+//! Windows 的文件 API 默认只接受不超过 `MAX_PATH`（260 个字符）的路径，深层目录树中的条目会在
+//! 读取目录或元数据时失败。遍历器在访问文件系统时改用 `\\?\` 扩展长度形式（UNC 路径为
+//! `\\?\UNC\server\share`），该形式的路径可长达约 32767 个字符；结果与错误中的路径仍保持
+//! 用户给出的形式，不带前缀。其他平台上两个函数都原样返回。
+
+use std::borrow::Cow;
+use std::path::Path;
+#[cfg(windows)]
+use std::ffi::OsString;
+#[cfg(windows)]
+use std::path::{Component, PathBuf, Prefix};
+
+/// 访问文件系统时使用的路径：Windows 上较长的路径转换为 `\\?\` 扩展长度形式
+#[cfg(windows)]
+pub fn extended(path: &Path) -> Cow<'_, Path> {
+    // 远低于上限的路径不必转换，避免为每次访问分配新路径
+    const THRESHOLD: usize = 200;
+    if path.as_os_str().len() < THRESHOLD {
+        return Cow::Borrowed(path);
+    }
+    let absolute = match std::path::absolute(path) {
+        Ok(absolute) => absolute,
+        Err(_) => return Cow::Borrowed(path),
+    };
+    let mut components = absolute.components();
+    let mut converted = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            // 前缀按字符串拼接：`PathBuf::push` 遇到带盘符的路径会整体替换而不是追加
+            Prefix::Disk(_) => {
+                let mut converted = OsString::from(r"\\?\");
+                converted.push(prefix.as_os_str());
+                PathBuf::from(converted)
+            }
+            Prefix::UNC(server, share) => {
+                let mut converted = OsString::from(r"\\?\UNC\");
+                converted.push(server);
+                converted.push(r"\");
+                converted.push(share);
+                PathBuf::from(converted)
+            }
+            // 已是扩展长度形式或设备路径
+            _ => return Cow::Owned(absolute),
+        },
+        _ => return Cow::Owned(absolute),
+    };
+    // `\\?\` 形式不再解析 `.` 与 `..`，逐段拼接规范化后的组件
+    for component in components {
+        match component {
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => {
+                converted.pop();
+            }
+            Component::Normal(name) => converted.push(name),
+            Component::Prefix(_) => {}
+        }
+    }
+    Cow::Owned(converted)
+}
+
+#[cfg(not(windows))]
+pub fn extended(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+/// 去掉 `\\?\` 前缀（`read_link` 读取联接点目标、`canonicalize` 的结果均带有该前缀），
+/// 还原为常见的 `C:\...` 或 `\\server\share\...` 形式
+#[cfg(windows)]
+pub fn strip_extended(path: &Path) -> Cow<'_, Path> {
+    let Some(text) = path.to_str() else {
+        return Cow::Borrowed(path);
+    };
+    if let Some(rest) = text.strip_prefix(r"\\?\UNC\") {
+        Cow::Owned(PathBuf::from(format!(r"\\{}", rest)))
+    } else if let Some(rest) = text.strip_prefix(r"\\?\").filter(|rest| rest.as_bytes().get(1) == Some(&b':')) {
+        Cow::Owned(PathBuf::from(rest))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+#[cfg(not(windows))]
+pub fn strip_extended(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
@@ -0,0 +1,347 @@
+//! 扫描结果合并
+//!
+//! 将多个 ScanResult（多根目录扫描、分片扫描等）合并为一个结果：摘要求和、
+//! Top N 与目录密度排名重新排序截断、扩展名统计按扩展名累加、同一目录的大小累加后重新计算占比。
+//! 顶层速览按合并后的根目录重新归类，位于其下的各根目录整体计入所在的直接子目录。根目录存在包含关系时，
+//! 被覆盖的结果会被跳过，避免同一棵子树被重复计数。补扫得到的子树结果则通过 [`graft`] 嫁接回原结果。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::top_level::{self, TopLevelStat};
+use crate::{DensityRanking, DirStat, ExtensionStat, TraversalError, ScanOperation, ScanRequest, ScanResult, ScanState};
+
+/// 合并多个扫描结果
+///
+/// - 根目录相同或位于另一结果根目录之下的结果视为重叠，仅保留覆盖范围最大的一份，
+///   被跳过的根目录记录在 `diagnostics.overlapping_roots` 中；
+/// - 合并后的根目录为所有保留根目录的最长公共祖先。根目录与各条目路径沿用调用方给出的形式（如经由符号链接的路径），
+///   规范化路径只用于判断重叠，避免合并根目录与条目路径形式不一致；
+/// - Top N 按各结果中最大的 `top_limit` 截断，`top_files` 按最大的 [`ScanSummary::top_files_bound`](crate::ScanSummary::top_files_bound) 截断。
+pub fn merge(results: Vec<ScanResult>) -> ScanResult {
+    // 按根目录层级由浅到深排序，保证祖先目录先被保留
+    let mut results: Vec<(PathBuf, ScanResult)> = results
+        .into_iter()
+        .map(|r| (normalize(&r.summary.root_path), r))
+        .collect();
+    results.sort_by_key(|(root, _)| root.components().count());
+
+    let mut kept: Vec<(PathBuf, ScanResult)> = Vec::new();
+    let mut overlapping = Vec::new();
+    for (root, result) in results {
+        if kept.iter().any(|(k, _)| root.starts_with(k)) {
+            overlapping.push(result.summary.root_path);
+        } else {
+            kept.push((root, result));
+        }
+    }
+
+    let root = common_ancestor(kept.iter().map(|(_, result)| result.summary.root_path.as_path()));
+    // 根目录位于合并根目录之下的结果，其顶层速览整体归入所在的直接子目录
+    for (_, result) in &mut kept {
+        let sub_root = &result.summary.root_path;
+        let Some(path) = top_level::top_child(&root, sub_root) else {
+            continue;
+        };
+        let dirs = result.summary.total_dirs;
+        result.top_level = vec![TopLevelStat {
+            dir_count: if path == *sub_root { dirs.saturating_sub(1) } else { dirs },
+            path,
+            size_bytes: result.summary.total_size_bytes,
+            file_count: result.summary.total_files,
+            ..Default::default()
+        }];
+    }
+    let mut merged = merge_disjoint(kept.into_iter().map(|(_, r)| r).collect(), root);
+    merged.diagnostics.overlapping_roots = overlapping;
+    merged
+}
+
+/// 把以 `base` 根目录下某个子目录为根的扫描结果嫁接进 `base`
+///
+/// 用于补扫首次扫描中被跳过的目录（如提权后重新扫描 `diagnostics.denied_dirs`）：
+/// 子树的大小累加到 `base` 中各祖先目录，子树中的目录排名与热力图行换算为在 `base` 中的深度后
+/// 并入，超出 `request`（`base` 的扫描请求）统计深度的行被丢弃；成功补扫的目录从 `denied_dirs` 与 `scan_errors` 中移除。
+/// 根目录不在 `base` 之下的结果不会被嫁接，记录在 `diagnostics.overlapping_roots` 中。
+pub fn graft(mut base: ScanResult, subtrees: Vec<ScanResult>, request: &ScanRequest) -> ScanResult {
+    let root = base.summary.root_path.clone();
+    let depth_of = |path: &Path| path.strip_prefix(&root).map_or(0, |p| p.components().count());
+
+    let mut parts = Vec::new();
+    let mut rejected = Vec::new();
+    for mut sub in subtrees {
+        let sub_root = sub.summary.root_path.clone();
+        if sub_root == root || !sub_root.starts_with(&root) {
+            rejected.push(sub_root);
+            continue;
+        }
+        let offset = depth_of(&sub_root);
+        let size = sub.summary.total_size_bytes;
+        let sub_ages = sub.heatmap.iter().find(|row| row.depth == 0).map(|row| row.age_bytes.clone());
+        for ancestor in sub_root.ancestors().skip(1).take_while(|a| a.starts_with(&root)) {
+            for dir in base.directories.iter_mut().chain(base.top_dirs.iter_mut()) {
+                if dir.path == ancestor {
+                    dir.size_bytes += size;
+                }
+            }
+            if let Some(ages) = &sub_ages {
+                for row in base.heatmap.iter_mut().filter(|row| row.path == ancestor) {
+                    for (total, bytes) in row.age_bytes.iter_mut().zip(ages) {
+                        *total += bytes;
+                    }
+                }
+            }
+        }
+
+        // 子树计入其所在的顶层子目录（子树根目录在 base 中已计为一个目录）
+        if let Some(path) = top_level::top_child(&root, &sub_root) {
+            base.top_level.push(TopLevelStat {
+                path,
+                size_bytes: size,
+                file_count: sub.summary.total_files,
+                dir_count: sub.summary.total_dirs.saturating_sub(1),
+                ..Default::default()
+            });
+        }
+        sub.top_level.clear();
+
+        // 补扫的目录位于系统保留目录之内（含其自身，如无权读取的 System Volume Information）时补上其大小
+        for entry in base.system_reserved.iter_mut().filter(|e| sub_root.starts_with(&e.path)) {
+            entry.size_bytes += size;
+        }
+        // 位于可回收位置之内时计入该位置，子树中嵌套的位置不再单独计入
+        if base.reclaimable_report.add_subtree(&sub_root, size, sub.summary.total_files) {
+            sub.reclaimable_report = Default::default();
+        }
+
+        // 子树根目录在 base 中已计为一个目录（只是无法读取其内容）
+        base.summary.total_dirs = base.summary.total_dirs.saturating_sub(1);
+        base.diagnostics.denied_dirs.retain(|d| d != &sub_root);
+        let unreadable = |e: &TraversalError| e.path == sub_root && e.operation == ScanOperation::ReadDir;
+        let resolved = base.scan_errors.iter().filter(|e| unreadable(e)).count() as u64;
+        base.scan_errors.retain(|e| !unreadable(e));
+        base.summary.scan_error_count = base.summary.scan_error_count.saturating_sub(resolved);
+        base.top_dirs.retain(|d| d.path != sub_root);
+        base.heatmap.retain(|row| row.path != sub_root);
+
+        if size > 0 {
+            sub.top_dirs.push(DirStat { path: sub_root.clone(), size_bytes: size, ..Default::default() });
+        }
+        sub.top_dirs.retain(|d| depth_of(&d.path) <= request.top_dirs_depth);
+        for row in &mut sub.heatmap {
+            row.depth += offset;
+        }
+        sub.heatmap.retain(|row| row.depth <= request.heatmap_depth);
+        parts.push(sub);
+    }
+
+    // merge_disjoint 按各部分的行数截断目录排名，嫁接后的排名按 base 的请求单独汇总
+    let top_dirs_limit = request.top_dirs_limit.unwrap_or(base.summary.top_limit);
+    parts.insert(0, base);
+    let mut top_dir_sizes: HashMap<PathBuf, u64> = HashMap::new();
+    for part in &mut parts {
+        for dir in std::mem::take(&mut part.top_dirs) {
+            *top_dir_sizes.entry(dir.path).or_insert(0) += dir.size_bytes;
+        }
+    }
+    let shallow_rows = parts.iter_mut().flat_map(|part| std::mem::take(&mut part.top_dirs_shallow)).collect::<Vec<_>>();
+    let mut merged = merge_disjoint(parts, root);
+    merged.top_dirs = crate::share::dir_stats(top_dir_sizes);
+    merged.top_dirs.truncate(top_dirs_limit);
+    merged.top_dirs_shallow = crate::density::merge_shallow(shallow_rows, top_dirs_limit);
+    crate::share::apply_shares(&mut merged);
+    merged.diagnostics.overlapping_roots.extend(rejected);
+    merged
+}
+
+/// 合并覆盖范围互不重叠的扫描结果（各项统计直接求和/拼接）
+pub(crate) fn merge_disjoint(results: Vec<ScanResult>, root_path: PathBuf) -> ScanResult {
+    let mut merged = ScanResult::default();
+    merged.summary.root_path = root_path;
+    let mut extensions: HashMap<String, ExtensionStat> = HashMap::new();
+    // 分片扫描时根目录会出现在每个分片的结果中，按路径累加得到完整大小
+    let mut dir_sizes: HashMap<PathBuf, u64> = HashMap::new();
+    let mut top_dir_sizes: HashMap<PathBuf, u64> = HashMap::new();
+    let mut top_dirs_limit = 0;
+    let mut shallow_rows = Vec::new();
+    let mut shallow_limit = 0;
+    let mut heatmap_rows = Vec::new();
+    let mut duplicate_groups = Vec::new();
+    let mut owner_stats = Vec::new();
+    let mut top_level_rows = Vec::new();
+    let mut age_buckets = Vec::new();
+    let mut size_histograms = Vec::new();
+    let mut densities = Vec::new();
+    let mut redundant_stats = Vec::new();
+    let mut reclaimable_reports = Vec::new();
+    let mut allocated = (!results.is_empty()).then_some(0);
+    for result in results {
+        let summary = &result.summary;
+        merged.summary.total_files += summary.total_files;
+        merged.summary.total_dirs += summary.total_dirs;
+        merged.summary.total_size_bytes += summary.total_size_bytes;
+        // 任一部分未统计分配空间时，合并结果的总量也无从得知
+        allocated = allocated.zip(summary.total_allocated_bytes).map(|(a, b)| a + b);
+        // 各部分通常位于同一文件系统（分片、增量子树），取首个查询到的容量
+        merged.summary.disk_space = merged.summary.disk_space.or(summary.disk_space);
+        merged.summary.elapsed_seconds = merged.summary.elapsed_seconds.max(summary.elapsed_seconds);
+        merged.summary.top_limit = merged.summary.top_limit.max(summary.top_limit);
+        merged.summary.top_files_capacity = merged.summary.top_files_capacity.max(summary.top_files_capacity);
+        merged.summary.stale_sort = summary.stale_sort;
+        merged.summary.stale_limit = merged.summary.stale_limit.max(summary.stale_limit);
+        merged.summary.empty_file_count += summary.empty_file_count;
+        merged.summary.empty_dir_count += summary.empty_dir_count;
+        merged.summary.empty_limit = merged.summary.empty_limit.max(summary.empty_limit);
+        merged.summary.scan_error_count += summary.scan_error_count;
+        merged.summary.min_size = merged.summary.min_size.max(summary.min_size);
+        merged.summary.min_size_is_default |= summary.min_size_is_default;
+        merged.summary.full_disk_access_missing |= summary.full_disk_access_missing;
+        merged.summary.suspicious_timestamps += summary.suspicious_timestamps;
+        merged.summary.redundant_file_count += summary.redundant_file_count;
+        merged.summary.redundant_bytes += summary.redundant_bytes;
+        merged.summary.placeholder_files += summary.placeholder_files;
+        merged.summary.placeholder_bytes += summary.placeholder_bytes;
+        merged.summary.compression_candidate_count += summary.compression_candidate_count;
+        merged.summary.compression_savings_bytes += summary.compression_savings_bytes;
+        merged.summary.recency_limit = merged.summary.recency_limit.max(summary.recency_limit);
+        merged.summary.extension_top_limit = merged.summary.extension_top_limit.max(summary.extension_top_limit);
+        merged.summary.summary_only |= summary.summary_only;
+        merged.summary.digest_algo = merged.summary.digest_algo.or(summary.digest_algo);
+        if summary.state == ScanState::Canceled {
+            merged.summary.state = ScanState::Canceled;
+        }
+        merged.top_files.extend(result.top_files);
+        merged.stale_files.extend(result.stale_files);
+        merged.empty_files.extend(result.empty_files);
+        merged.empty_dirs.extend(result.empty_dirs);
+        merged.scan_errors.extend(result.scan_errors);
+        for stat in result.by_extension {
+            let entry = extensions.entry(stat.extension.clone()).or_insert(ExtensionStat {
+                extension: stat.extension,
+                file_count: 0,
+                total_size_bytes: 0,
+                top_files: Vec::new(),
+            });
+            entry.file_count += stat.file_count;
+            entry.total_size_bytes += stat.total_size_bytes;
+            entry.top_files.extend(stat.top_files);
+        }
+        top_level_rows.extend(result.top_level);
+        top_dirs_limit = top_dirs_limit.max(result.top_dirs.len());
+        for dir in result.top_dirs {
+            *top_dir_sizes.entry(dir.path).or_insert(0) += dir.size_bytes;
+        }
+        shallow_limit = shallow_limit.max(result.top_dirs_shallow.len());
+        shallow_rows.extend(result.top_dirs_shallow);
+        for dir in result.directories {
+            *dir_sizes.entry(dir.path).or_insert(0) += dir.size_bytes;
+        }
+        densities.push(result.density);
+        heatmap_rows.extend(result.heatmap);
+        age_buckets.extend(result.age_histogram);
+        size_histograms.extend(result.size_histograms);
+        duplicate_groups.extend(result.duplicates);
+        merged.system_reserved.extend(result.system_reserved);
+        owner_stats.extend(result.by_owner);
+        merged.suspicious_files.extend(result.suspicious_files);
+        redundant_stats.extend(result.redundant);
+        reclaimable_reports.push(result.reclaimable_report);
+        merged.redundant_files.extend(result.redundant_files);
+        merged.compression_candidates.extend(result.compression_candidates);
+        merged.oldest_files.extend(result.oldest_files);
+        merged.newest_files.extend(result.newest_files);
+        merged.root_summaries.extend(result.root_summaries);
+        let diagnostics = &result.diagnostics;
+        merged.diagnostics.hints_applied |= diagnostics.hints_applied;
+        merged.diagnostics.hinted_dirs += diagnostics.hinted_dirs;
+        merged.diagnostics.split_batches += diagnostics.split_batches;
+        merged.diagnostics.recorded_hints += diagnostics.recorded_hints;
+        merged.diagnostics.metadata_retries += diagnostics.metadata_retries;
+        merged.diagnostics.revisited_dirs += diagnostics.revisited_dirs;
+        merged.diagnostics.skipped_hardlinks += diagnostics.skipped_hardlinks;
+        merged.diagnostics.skipped_hardlink_bytes += diagnostics.skipped_hardlink_bytes;
+        merged.diagnostics.depth_limited_dirs += diagnostics.depth_limited_dirs;
+        merged.diagnostics.filter_checks += diagnostics.filter_checks;
+        merged.diagnostics.filter_match_nanos += diagnostics.filter_match_nanos;
+        merged.diagnostics.budget_deferred_dirs += diagnostics.budget_deferred_dirs;
+        merged.diagnostics.cached_subtrees += diagnostics.cached_subtrees;
+        merged.diagnostics.rescanned_subtrees += diagnostics.rescanned_subtrees;
+        merged.diagnostics.requested_limit = merged.diagnostics.requested_limit.max(diagnostics.requested_limit);
+        if merged.diagnostics.keep_awake.is_none() {
+            merged.diagnostics.keep_awake = diagnostics.keep_awake.clone();
+        }
+        merged.diagnostics.overlapping_roots.extend(result.diagnostics.overlapping_roots);
+        merged.diagnostics.denied_dirs.extend(result.diagnostics.denied_dirs);
+        merged.diagnostics.skipped_mount_points.extend(result.diagnostics.skipped_mount_points);
+        merged.diagnostics.aliased_dirs.extend(result.diagnostics.aliased_dirs);
+        merged.diagnostics.worker_panics.extend(result.diagnostics.worker_panics);
+        // 插件结果段无法通用地合并，同名结果段以后出现的为准
+        merged.plugin_sections.extend(result.plugin_sections);
+    }
+
+    // 按大小降序、路径降序排列（与扫描器输出顺序一致）
+    merged.top_files.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| b.path.cmp(&a.path)));
+    merged.top_files.truncate(merged.summary.top_files_bound());
+    merged.summary.total_allocated_bytes = allocated;
+    crate::access::sort_stale(&mut merged.stale_files, merged.summary.stale_sort, merged.summary.stale_limit);
+    crate::empty::sort_and_truncate(&mut merged.empty_files, merged.summary.empty_limit);
+    crate::empty::sort_and_truncate(&mut merged.empty_dirs, merged.summary.empty_limit);
+    crate::scan_error::sort_and_truncate(&mut merged.scan_errors);
+    merged.directories = crate::share::dir_stats(dir_sizes);
+    merged.top_level = top_level::merge(top_level_rows);
+    merged.top_dirs = crate::share::dir_stats(top_dir_sizes);
+    merged.top_dirs.truncate(top_dirs_limit);
+    merged.top_dirs_shallow = crate::density::merge_shallow(shallow_rows, shallow_limit);
+    merged.density = DensityRanking::merge(densities, merged.summary.top_limit);
+    merged.heatmap = crate::heatmap::merge(heatmap_rows);
+    merged.age_histogram = crate::age_histogram::merge(age_buckets);
+    merged.size_histograms = crate::size_histogram::merge(size_histograms);
+    merged.duplicates = crate::dedup::merge(duplicate_groups);
+    crate::reserved::sort(&mut merged.system_reserved);
+    merged.by_owner = crate::owner::merge(owner_stats);
+    crate::timestamp::sort_by_deviation(&mut merged.suspicious_files, SystemTime::now());
+    merged.suspicious_files.truncate(merged.summary.top_limit);
+    merged.redundant = crate::redundant::merge(redundant_stats);
+    merged.reclaimable_report = crate::reclaimable::merge(reclaimable_reports, merged.summary.top_limit);
+    crate::redundant::sort_and_truncate(&mut merged.redundant_files, merged.summary.top_limit);
+    crate::compression::sort_and_truncate(&mut merged.compression_candidates, merged.summary.top_limit);
+    let recency_limit = merged.summary.recency_limit.unwrap_or(0);
+    crate::recency::sort_oldest(&mut merged.oldest_files, recency_limit);
+    crate::recency::sort_newest(&mut merged.newest_files, recency_limit);
+
+    merged.by_extension = extensions.into_values().collect();
+    let extension_top_limit = merged.summary.extension_top_limit.unwrap_or(0);
+    for stat in &mut merged.by_extension {
+        crate::redundant::sort_and_truncate(&mut stat.top_files, extension_top_limit);
+    }
+    merged.by_extension.sort_by(|a, b| {
+        b.total_size_bytes
+            .cmp(&a.total_size_bytes)
+            .then_with(|| b.file_count.cmp(&a.file_count))
+    });
+    merged.by_category = crate::category::from_extensions(&merged.by_extension);
+    crate::share::apply_shares(&mut merged);
+    merged
+}
+
+/// 尽量规范化为绝对路径，便于判断根目录之间的包含关系
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// 计算多个路径的最长公共祖先
+fn common_ancestor<'a>(mut paths: impl Iterator<Item = &'a Path>) -> PathBuf {
+    let Some(first) = paths.next() else {
+        return PathBuf::new();
+    };
+    let mut ancestor = first.to_path_buf();
+    for path in paths {
+        while !path.starts_with(&ancestor) {
+            if !ancestor.pop() {
+                break;
+            }
+        }
+    }
+    ancestor
+}
@@ -0,0 +1,112 @@
+//! 按文件头识别 MIME 类型
+//!
+//! 扩展名统计依赖文件名，改过扩展名或没有扩展名的文件会被归错类。指定 [`ScanRequest::detect_mime`]
+//! 后，遍历时读取不小于 `mime_min_size` 的普通文件的前 [`HEAD_LEN`] 字节，按签名表（与 `infer`
+//! 库相同的“魔数”方式）识别 MIME 类型并写入 `FileEntry::mime`。签名表只收录能由文件头可靠区分的
+//! 常见格式；无法识别或无法读取的文件不带 `mime`。读取文件内容会增加 IO，阈值用于跳过大量小文件。
+//!
+//! [`ScanRequest::detect_mime`]: crate::ScanRequest::detect_mime
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// 识别时读取的文件头长度（字节）
+pub const HEAD_LEN: usize = 8 * 1024;
+
+/// 默认只识别不小于该大小的文件（字节）
+pub const DEFAULT_MIN_SIZE: u64 = 64 * 1024;
+
+/// 判断文件头是否符合某种格式
+type Matcher = fn(&[u8]) -> bool;
+
+/// 签名表：按顺序匹配，先列更具体的签名（如 Office 文档先于通用 ZIP）
+const SIGNATURES: &[(&str, Matcher)] = &[
+    // 图片
+    ("image/png", |b| b.starts_with(b"\x89PNG\r\n\x1a\n")),
+    ("image/jpeg", |b| b.starts_with(b"\xff\xd8\xff")),
+    ("image/gif", |b| b.starts_with(b"GIF87a") || b.starts_with(b"GIF89a")),
+    ("image/webp", |b| riff(b, b"WEBP")),
+    ("image/bmp", |b| b.starts_with(b"BM") && b.len() > 14 && b[14] <= 124),
+    ("image/tiff", |b| b.starts_with(b"II*\0") || b.starts_with(b"MM\0*")),
+    ("image/vnd.microsoft.icon", |b| b.starts_with(b"\0\0\x01\0")),
+    ("image/vnd.adobe.photoshop", |b| b.starts_with(b"8BPS")),
+    ("image/heif", |b| ftyp(b, &[b"heic", b"heix", b"mif1", b"msf1"])),
+    ("image/avif", |b| ftyp(b, &[b"avif", b"avis"])),
+    // 音视频
+    ("video/quicktime", |b| ftyp(b, &[b"qt  "])),
+    ("audio/mp4", |b| ftyp(b, &[b"M4A ", b"M4B "])),
+    ("video/mp4", |b| ftyp(b, &[b"isom", b"iso2", b"mp41", b"mp42", b"avc1", b"dash", b"M4V "])),
+    ("video/webm", |b| b.starts_with(b"\x1a\x45\xdf\xa3") && contains(b, b"webm")),
+    ("video/x-matroska", |b| b.starts_with(b"\x1a\x45\xdf\xa3")),
+    ("video/x-msvideo", |b| riff(b, b"AVI ")),
+    ("audio/wav", |b| riff(b, b"WAVE")),
+    ("audio/mpeg", |b| {
+        b.starts_with(b"ID3") || b.starts_with(b"\xff\xfb") || b.starts_with(b"\xff\xf3") || b.starts_with(b"\xff\xf2")
+    }),
+    ("audio/flac", |b| b.starts_with(b"fLaC")),
+    ("audio/ogg", |b| b.starts_with(b"OggS")),
+    ("audio/midi", |b| b.starts_with(b"MThd")),
+    // 文档
+    ("application/pdf", |b| b.starts_with(b"%PDF-")),
+    ("application/rtf", |b| b.starts_with(b"{\\rtf")),
+    ("application/postscript", |b| b.starts_with(b"%!PS")),
+    ("application/vnd.sqlite3", |b| b.starts_with(b"SQLite format 3\0")),
+    ("application/epub+zip", |b| b.starts_with(b"PK\x03\x04") && contains(b, b"application/epub+zip")),
+    (
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        |b| b.starts_with(b"PK\x03\x04") && contains(b, b"word/"),
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        |b| b.starts_with(b"PK\x03\x04") && contains(b, b"xl/"),
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        |b| b.starts_with(b"PK\x03\x04") && contains(b, b"ppt/"),
+    ),
+    // 压缩包
+    ("application/zip", |b| b.starts_with(b"PK\x03\x04") || b.starts_with(b"PK\x05\x06")),
+    ("application/gzip", |b| b.starts_with(b"\x1f\x8b")),
+    ("application/x-bzip2", |b| b.starts_with(b"BZh")),
+    ("application/x-xz", |b| b.starts_with(b"\xfd7zXZ\0")),
+    ("application/zstd", |b| b.starts_with(b"\x28\xb5\x2f\xfd")),
+    ("application/x-7z-compressed", |b| b.starts_with(b"7z\xbc\xaf\x27\x1c")),
+    ("application/vnd.rar", |b| b.starts_with(b"Rar!\x1a\x07")),
+    ("application/x-tar", |b| b.get(257..262) == Some(b"ustar")),
+    // 可执行文件
+    ("application/x-executable", |b| b.starts_with(b"\x7fELF")),
+    ("application/x-mach-binary", |b| {
+        [b"\xfe\xed\xfa\xce", b"\xfe\xed\xfa\xcf", b"\xce\xfa\xed\xfe", b"\xcf\xfa\xed\xfe"]
+            .iter()
+            .any(|magic| b.starts_with(*magic))
+    }),
+    ("application/vnd.microsoft.portable-executable", |b| b.starts_with(b"MZ")),
+    ("application/wasm", |b| b.starts_with(b"\0asm")),
+];
+
+/// 由文件头识别 MIME 类型
+pub fn detect(head: &[u8]) -> Option<&'static str> {
+    SIGNATURES.iter().find(|(_, matches)| matches(head)).map(|(mime, _)| *mime)
+}
+
+/// 读取文件头并识别 MIME 类型
+pub fn sniff(path: &Path) -> io::Result<Option<&'static str>> {
+    let mut head = Vec::with_capacity(HEAD_LEN);
+    File::open(path)?.take(HEAD_LEN as u64).read_to_end(&mut head)?;
+    Ok(detect(&head))
+}
+
+/// RIFF 容器且格式标记为 `format`
+fn riff(b: &[u8], format: &[u8; 4]) -> bool {
+    b.starts_with(b"RIFF") && b.get(8..12) == Some(format)
+}
+
+/// ISO 基本媒体文件（`ftyp` 盒）且主品牌为 `brands` 之一
+fn ftyp(b: &[u8], brands: &[&[u8; 4]]) -> bool {
+    b.get(4..8) == Some(b"ftyp") && b.get(8..12).is_some_and(|brand| brands.iter().any(|b| brand == *b))
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
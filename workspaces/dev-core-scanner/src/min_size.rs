@@ -0,0 +1,42 @@
+//! 最小文件尺寸默认值启发式
+//!
+//! 未显式指定 `min_size` 时，按扫描根目录所在文件系统的容量选择默认过滤阈值，
+//! 使大容量卷上的结果集保持精简：容量低于 256 GiB 时不过滤；否则阈值约为容量的
+//! 1/2^21（2 TiB 卷约为 1 MiB），向下取整到 2 的幂并以 64 MiB 为上限。
+
+use std::path::Path;
+
+/// 低于该容量的文件系统不应用默认过滤
+const MIN_FILESYSTEM_BYTES: u64 = 256 << 30;
+
+/// 默认阈值与文件系统容量之比的位移量（容量 >> 21）
+const FILESYSTEM_SHIFT: u32 = 21;
+
+/// 默认阈值上限
+const MAX_DEFAULT_MIN_SIZE: u64 = 64 << 20;
+
+/// 按文件系统容量计算默认最小文件尺寸；无需过滤时返回 None
+pub fn default_for_capacity(filesystem_bytes: u64) -> Option<u64> {
+    if filesystem_bytes < MIN_FILESYSTEM_BYTES {
+        return None;
+    }
+    let raw = filesystem_bytes >> FILESYSTEM_SHIFT;
+    // 向下取整到 2 的幂
+    let rounded = 1u64 << (63 - raw.leading_zeros());
+    Some(rounded.min(MAX_DEFAULT_MIN_SIZE))
+}
+
+/// 依据根目录所在文件系统的容量选择默认最小文件尺寸
+pub fn default_for(root: &Path) -> Option<u64> {
+    filesystem_capacity(root).and_then(default_for_capacity)
+}
+
+/// 查询路径所在文件系统的总容量（字节）
+pub fn filesystem_capacity(path: &Path) -> Option<u64> {
+    crate::disk_space::query(path).map(|space| space.total_bytes)
+}
+
+/// 查询路径所在文件系统的已用空间（字节，总容量减去空闲空间）
+pub fn filesystem_used(path: &Path) -> Option<u64> {
+    crate::disk_space::query(path).map(|space| space.used_bytes())
+}
@@ -0,0 +1,32 @@
+//! 单文件系统模式（与 `du -x` 一致）
+//!
+//! 开启 `same_filesystem` 后，扫描开始时记录根目录所在的设备号，遍历中遇到设备号不同的子目录
+//! （即其他文件系统的挂载点，如 `/proc`、`/mnt` 下的磁盘或网络共享）时不再进入，也不计入统计；
+//! 被跳过的挂载点列在 `diagnostics.skipped_mount_points` 中。后台索引的目录指纹采用同样的规则。
+//!
+//! 设备号只在 Unix 上可用；其他平台上该选项不生效，仍遍历整棵目录树。
+
+use std::path::Path;
+
+/// 目录所在文件系统的设备号（跟随符号链接）；无法读取或平台不支持时返回 None
+#[cfg(unix)]
+pub(crate) fn device_of(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|m| m.dev())
+}
+
+/// 目录所在文件系统的设备号（跟随符号链接）；无法读取或平台不支持时返回 None
+#[cfg(not(unix))]
+pub(crate) fn device_of(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// 根目录的设备号：未开启单文件系统模式时为 None
+pub(crate) fn root_device(root: &Path, same_filesystem: bool) -> Option<u64> {
+    same_filesystem.then(|| device_of(root)).flatten()
+}
+
+/// `dir` 是否位于与根目录不同的文件系统上（`root_device` 为 None 时从不跨越）
+pub(crate) fn crosses(root_device: Option<u64>, dir: &Path) -> bool {
+    root_device.is_some_and(|root| device_of(dir).is_some_and(|dev| dev != root))
+}
@@ -0,0 +1,73 @@
+//! 多根目录扫描
+//!
+//! 请求 [`ScanRequest::roots`] 时，一次扫描同时覆盖多个根目录（如 `/home` 与 `/var`）：每个根目录在
+//! 各自的线程中并发扫描，线程数在根目录之间平分；结果按 [`merge`](crate::merge) 合并为以最长公共祖先
+//! 为根的汇总结果，各根目录自身的摘要按请求顺序保留在 `ScanResult::root_summaries` 中。
+//!
+//! 相同或位于另一根目录之下的根目录不会重复扫描，记录在 `diagnostics.overlapping_roots` 中。
+//! 任一根目录扫描失败（不存在、不是目录等）时整个请求失败。调用方提供的 [`LiveProgress`] 汇总全部
+//! 根目录的文件、目录与字节数。
+//!
+//! [`ScanRequest::roots`]: crate::ScanRequest::roots
+
+use std::path::PathBuf;
+use std::thread;
+
+use crate::merge::normalize;
+use crate::{LiveProgress, ScanError, ScanRequest, ScanResult, Scanner};
+
+/// 并发扫描请求中的各根目录并合并结果
+pub(crate) fn scan(request: &ScanRequest) -> Result<ScanResult, ScanError> {
+    let (roots, overlapping) = distinct_roots(&request.roots);
+    let parent = request.progress.clone();
+    let threads = request.threads.filter(|&n| n > 0).map_or_else(rayon::current_num_threads, usize::from);
+    let per_root = (threads / roots.len()).max(1);
+    let requests: Vec<ScanRequest> = roots
+        .iter()
+        .map(|root| {
+            let mut sub = request.clone();
+            sub.root_path = root.clone();
+            sub.roots = Vec::new();
+            sub.threads = Some(u16::try_from(per_root).unwrap_or(u16::MAX));
+            sub.progress = parent.as_ref().map(LiveProgress::child);
+            // 并发提示按根目录记录，只用于对应的根目录
+            sub.concurrency_hints = request.concurrency_hints.clone().filter(|hints| hints.root_path == *root);
+            sub
+        })
+        .collect();
+
+    let results = thread::scope(|scope| {
+        let handles: Vec<_> = requests
+            .iter()
+            .map(|sub| scope.spawn(move || Scanner::new().scan_sync(sub)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+
+    let root_summaries = results.iter().map(|r| r.summary.clone()).collect();
+    let mut merged = crate::merge(results);
+    merged.root_summaries = root_summaries;
+    merged.diagnostics.overlapping_roots.extend(overlapping);
+    Ok(merged)
+}
+
+/// 去除重复与被其他根目录覆盖的根目录，返回（保留的根目录, 被跳过的根目录），保留的根目录维持请求顺序
+fn distinct_roots(roots: &[PathBuf]) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let normalized: Vec<PathBuf> = roots.iter().map(|root| normalize(root)).collect();
+    let mut kept = Vec::new();
+    let mut overlapping = Vec::new();
+    for (i, root) in roots.iter().enumerate() {
+        let covered = normalized.iter().enumerate().any(|(j, other)| {
+            j != i && normalized[i].starts_with(other) && (normalized[i] != *other || j < i)
+        });
+        if covered {
+            overlapping.push(root.clone());
+        } else {
+            kept.push(root.clone());
+        }
+    }
+    (kept, overlapping)
+}
@@ -0,0 +1,195 @@
+//! 文件属主与权限
+//!
+//! 启用 [`ScanRequest::collect_owners`] 后，文件条目附带属主（uid/gid 及解析出的用户名、组名）、
+//! 权限位与只读标记，结果中的 `by_owner` 按用户汇总文件数与总大小，便于回答“哪个用户占用了
+//! 最多空间”。遍历时只记录数值 ID（流式发送给插件的条目同样只有 ID），名称在生成结果时统一解析，
+//! 每个 ID 只查询一次。
+//!
+//! uid/gid 与权限位只在 Unix 上可用；其他平台只记录只读标记，`by_owner` 为空。
+//!
+//! [`ScanRequest::collect_owners`]: crate::ScanRequest::collect_owners
+
+use std::collections::HashMap;
+use std::fs::Metadata;
+
+use serde::{Deserialize, Serialize};
+
+use crate::FileEntry;
+
+/// 文件属主与权限
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Ownership {
+    /// 属主用户 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>,
+    /// 属主组 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gid: Option<u32>,
+    /// 属主用户名（无法解析时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// 属主组名（无法解析时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// 权限位（如 `0o644`，含 setuid/setgid/sticky 位）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    /// 是否只读
+    #[serde(default)]
+    pub readonly: bool,
+}
+
+/// 按属主用户汇总的统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct OwnerStat {
+    /// 用户 ID
+    pub uid: u32,
+    /// 用户名（无法解析时为 None）
+    #[serde(default)]
+    pub user: Option<String>,
+    /// 文件数量
+    pub file_count: u64,
+    /// 总大小（字节）
+    pub total_size_bytes: u64,
+}
+
+/// 从元数据读取属主与权限（不解析名称）
+#[cfg(unix)]
+pub(crate) fn ownership(metadata: &Metadata) -> Ownership {
+    use std::os::unix::fs::MetadataExt;
+    Ownership {
+        uid: Some(metadata.uid()),
+        gid: Some(metadata.gid()),
+        mode: Some(metadata.mode() & 0o7777),
+        readonly: metadata.permissions().readonly(),
+        ..Default::default()
+    }
+}
+
+/// 从元数据读取属主与权限（不解析名称）
+#[cfg(not(unix))]
+pub(crate) fn ownership(metadata: &Metadata) -> Ownership {
+    Ownership { readonly: metadata.permissions().readonly(), ..Default::default() }
+}
+
+/// 用户名、组名解析缓存
+#[derive(Default)]
+pub(crate) struct Names {
+    users: HashMap<u32, Option<String>>,
+    groups: HashMap<u32, Option<String>>,
+}
+
+impl Names {
+    fn user(&mut self, uid: u32) -> Option<String> {
+        self.users.entry(uid).or_insert_with(|| user_name(uid)).clone()
+    }
+
+    fn group(&mut self, gid: u32) -> Option<String> {
+        self.groups.entry(gid).or_insert_with(|| group_name(gid)).clone()
+    }
+
+    /// 为条目补上用户名与组名
+    pub(crate) fn resolve(&mut self, entries: &mut [FileEntry]) {
+        for owner in entries.iter_mut().filter_map(|e| e.owner.as_mut()) {
+            owner.user = owner.uid.and_then(|uid| self.user(uid));
+            owner.group = owner.gid.and_then(|gid| self.group(gid));
+        }
+    }
+
+    /// 由 uid -> (文件数, 总大小) 生成按总大小降序的属主统计
+    pub(crate) fn owner_stats(&mut self, owners: impl IntoIterator<Item = (u32, (u64, u64))>) -> Vec<OwnerStat> {
+        let mut stats: Vec<OwnerStat> = owners
+            .into_iter()
+            .map(|(uid, (file_count, total_size_bytes))| OwnerStat {
+                uid,
+                user: self.user(uid),
+                file_count,
+                total_size_bytes,
+            })
+            .collect();
+        sort(&mut stats);
+        stats
+    }
+}
+
+/// 按总大小降序排列，大小相同时按 uid 升序
+pub(crate) fn sort(stats: &mut [OwnerStat]) {
+    stats.sort_by(|a, b| b.total_size_bytes.cmp(&a.total_size_bytes).then_with(|| a.uid.cmp(&b.uid)));
+}
+
+/// 合并多个结果的属主统计
+pub(crate) fn merge(stats: impl IntoIterator<Item = OwnerStat>) -> Vec<OwnerStat> {
+    let mut by_uid: HashMap<u32, OwnerStat> = HashMap::new();
+    for stat in stats {
+        let merged = by_uid.entry(stat.uid).or_insert_with(|| OwnerStat { uid: stat.uid, ..Default::default() });
+        merged.file_count += stat.file_count;
+        merged.total_size_bytes += stat.total_size_bytes;
+        merged.user = merged.user.take().or(stat.user);
+    }
+    let mut merged: Vec<OwnerStat> = by_uid.into_values().collect();
+    sort(&mut merged);
+    merged
+}
+
+/// 查询 uid 对应的用户名
+#[cfg(unix)]
+fn user_name(uid: u32) -> Option<String> {
+    lookup(
+        // SAFETY: entry 与 result 为有效的输出指针，缓冲区在调用期间有效且长度如实传入
+        |entry: &mut libc::passwd, buf, result| unsafe {
+            libc::getpwuid_r(uid, entry, buf.as_mut_ptr(), buf.len(), result)
+        },
+        |entry| entry.pw_name,
+    )
+}
+
+/// 查询 gid 对应的组名
+#[cfg(unix)]
+fn group_name(gid: u32) -> Option<String> {
+    lookup(
+        // SAFETY: 同上
+        |entry: &mut libc::group, buf, result| unsafe {
+            libc::getgrgid_r(gid, entry, buf.as_mut_ptr(), buf.len(), result)
+        },
+        |entry| entry.gr_name,
+    )
+}
+
+/// 调用 `getpwuid_r` / `getgrgid_r` 一类的接口并读取名称，缓冲区不足（ERANGE）时加倍重试
+#[cfg(unix)]
+fn lookup<T>(
+    call: impl Fn(&mut T, &mut [libc::c_char], &mut *mut T) -> libc::c_int,
+    name: impl Fn(&T) -> *const libc::c_char,
+) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        // SAFETY: passwd / group 均为只含整数与指针的 C 结构体，全零是合法的初始值
+        let mut entry: T = unsafe { std::mem::zeroed() };
+        let mut result: *mut T = std::ptr::null_mut();
+        match call(&mut entry, &mut buf, &mut result) {
+            libc::ERANGE if buf.len() < 1 << 20 => buf.resize(buf.len() * 2, 0),
+            0 if !result.is_null() => {
+                let ptr = name(&entry);
+                if ptr.is_null() {
+                    return None;
+                }
+                // SAFETY: 名称指向仍然存活的 buf 中以 NUL 结尾的字符串
+                let name = unsafe { std::ffi::CStr::from_ptr(ptr) }.to_str().ok()?;
+                return (!name.is_empty()).then(|| name.to_string());
+            }
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn user_name(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+fn group_name(_gid: u32) -> Option<String> {
+    None
+}
@@ -0,0 +1,73 @@
+//! 遍历的 I/O 限速
+//!
+//! 机械硬盘上的长时间扫描会占满磁头寻道，电池供电的笔记本上则持续唤醒磁盘与 CPU。
+//! 设置 `max_io_ops_per_sec` 后，遍历读取的元数据操作（每次读取目录与每个文件的 stat）
+//! 按该速率进行：各任务在本地累计操作数，每满一批向全线程池共享的限速器申请一次，
+//! 超前时休眠补齐，因此限速作用于元数据批次之间，而不是在每个文件上加锁。
+//!
+//! `nice_mode` 是面向后台扫描的预设：未指定速率时采用 [`NICE_MODE_OPS_PER_SEC`]，
+//! 未指定线程数时只使用 [`NICE_MODE_THREADS`] 个遍历线程。
+//!
+//! 实际速率（遍历阶段开始以来的平均值）与生效的上限见 [`ScanProgress`](crate::ScanProgress)。
+
+use crate::dedup::Throttle;
+use crate::LiveProgress;
+
+/// `nice_mode` 未指定速率时的元数据操作上限（次/秒）
+pub const NICE_MODE_OPS_PER_SEC: u64 = 2000;
+
+/// `nice_mode` 未指定线程数时的遍历线程数
+pub const NICE_MODE_THREADS: u16 = 2;
+
+/// 每批操作数的上限；速率较低时批次相应缩小，使每次休眠不超过约 50 毫秒，取消仍能及时生效
+const MAX_BATCH_OPS: u64 = 64;
+
+/// 全线程池共享的元数据操作限速器
+pub(crate) struct IoPacer {
+    throttle: Throttle,
+    batch: u64,
+}
+
+impl IoPacer {
+    pub(crate) fn new(ops_per_sec: u64) -> Self {
+        Self { throttle: Throttle::new(ops_per_sec), batch: (ops_per_sec / 20).clamp(1, MAX_BATCH_OPS) }
+    }
+}
+
+/// 单个遍历任务内累计的元数据操作，每满一批计入进度并（限速时）向共享限速器申请
+pub(crate) struct PaceBatch<'a> {
+    pacer: Option<&'a IoPacer>,
+    live: &'a LiveProgress,
+    pending: u64,
+}
+
+impl<'a> PaceBatch<'a> {
+    pub(crate) fn new(pacer: Option<&'a IoPacer>, live: &'a LiveProgress) -> Self {
+        Self { pacer, live, pending: 0 }
+    }
+
+    /// 记录一次元数据操作
+    pub(crate) fn tick(&mut self) {
+        self.pending += 1;
+        if self.pending >= self.pacer.map_or(MAX_BATCH_OPS, |p| p.batch) {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.pending == 0 {
+            return;
+        }
+        self.live.add_io_ops(self.pending);
+        if let Some(pacer) = self.pacer {
+            pacer.throttle.acquire(self.pending);
+        }
+        self.pending = 0;
+    }
+}
+
+impl Drop for PaceBatch<'_> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
@@ -0,0 +1,82 @@
+//! 包目录（bundle）识别
+//!
+//! macOS 上的 `.app`、`.framework` 等目录在 Finder 中显示为单个文件。扫描仍会进入其内部
+//! 统计大小，但展示层（TUI/GUI 目录树）默认把包目录作为一个叶子条目呈现其累计大小，
+//! 与用户在 Finder 中的认知保持一致；需要时可切换为展开内部结构。
+//!
+//! 请求 `opaque_packages` 时扫描本身也把包目录视为不透明：遍历器不再进入（根目录除外），
+//! 而是在当前任务中累计其内部所有文件的大小，把包目录作为一个文件条目计入统计（扩展名即包类型），
+//! 内部文件不出现在 Top N、扩展名统计与目录汇总中，与 Finder 的“显示简介”一致。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::pacing::PaceBatch;
+
+/// 按扩展名识别的包目录类型
+pub const PACKAGE_EXTENSIONS: &[&str] = &[
+    "app",
+    "appex",
+    "bundle",
+    "framework",
+    "kext",
+    "plugin",
+    "xpc",
+    "dsym",
+    "pkg",
+    "mpkg",
+    "photoslibrary",
+    "musiclibrary",
+    "xcodeproj",
+    "xcworkspace",
+    "playground",
+    "rtfd",
+];
+
+/// 路径是否为包目录（仅依据扩展名判断，不访问文件系统）
+pub fn is_package(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| PACKAGE_EXTENSIONS.iter().any(|p| p.eq_ignore_ascii_case(ext)))
+}
+
+/// 返回包含该路径的最外层包目录（路径本身不计入）
+pub fn enclosing_package(path: &Path) -> Option<&Path> {
+    path.ancestors().skip(1).filter(|p| is_package(p)).last()
+}
+
+/// 包目录内部文件的累计大小
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct PackageSize {
+    /// 文件大小之和
+    pub(crate) bytes: u64,
+    /// 实际分配的磁盘空间之和（仅在 `allocated` 为 true 时累计）
+    pub(crate) allocated: u64,
+}
+
+/// 累计包目录内部所有文件的大小；内部的符号链接按自身计入，不跟随。无法读取的子目录与条目被跳过
+pub(crate) fn package_size(package: &Path, allocated: bool, pace: &mut PaceBatch<'_>) -> PackageSize {
+    let mut total = PackageSize::default();
+    let mut stack: Vec<PathBuf> = vec![package.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        pace.tick();
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            pace.tick();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                stack.push(entry.path());
+            } else {
+                total.bytes += metadata.len();
+                if allocated {
+                    total.allocated += crate::allocation::allocated_size(&entry.path(), &metadata).unwrap_or(0);
+                }
+            }
+        }
+    }
+    total
+}
@@ -0,0 +1,101 @@
+//! 多阶段进度模型
+//!
+//! 一次扫描可能依次经历多个阶段（遍历、哈希、汇总、保存缓存……），单一百分比无法说明
+//! “现在在做什么、还剩几步”。每次扫描开始时登记本次会经历的阶段，进度快照据此给出
+//! 当前阶段的序号、阶段总数以及阶段内进度，前端可显示为“阶段 2/3：哈希 45%”。
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// 扫描阶段（按执行顺序排列）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum ScanPhase {
+    /// 枚举目录条目；当前遍历器在枚举的同时读取元数据，整个遍历过程都报告为此阶段
+    #[default]
+    Enumerating,
+    /// 读取文件元数据（先枚举后统计的两段式遍历使用）
+    Stating,
+    /// 计算文件内容摘要（重复文件检测与 Top N 文件摘要）
+    Hashing,
+    /// 汇总统计结果
+    Aggregating,
+    /// 保存缓存、索引等扫描产物（由调用方登记与进入）
+    Persisting,
+}
+
+impl ScanPhase {
+    /// 全部阶段（按执行顺序）
+    pub const ALL: [ScanPhase; 5] = [
+        ScanPhase::Enumerating,
+        ScanPhase::Stating,
+        ScanPhase::Hashing,
+        ScanPhase::Aggregating,
+        ScanPhase::Persisting,
+    ];
+
+    /// 阶段的中文名称
+    pub fn label(self) -> &'static str {
+        match self {
+            ScanPhase::Enumerating => "枚举目录",
+            ScanPhase::Stating => "读取元数据",
+            ScanPhase::Hashing => "哈希",
+            ScanPhase::Aggregating => "汇总",
+            ScanPhase::Persisting => "保存",
+        }
+    }
+
+    pub(crate) fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    pub(crate) fn from_index(index: u8) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+}
+
+/// 当前阶段及其在本次扫描中的位置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PhaseProgress {
+    /// 当前阶段
+    pub phase: ScanPhase,
+    /// 当前阶段序号（从 1 开始）
+    pub index: usize,
+    /// 本次扫描登记的阶段总数
+    pub count: usize,
+    /// 阶段内进度（0.0 - 1.0）；总量未知的阶段（如遍历）为 `None`
+    pub progress: Option<f64>,
+}
+
+impl PhaseProgress {
+    /// 由已登记阶段的位掩码计算当前阶段的序号与阶段总数
+    pub(crate) fn new(phase: ScanPhase, planned: u8, progress: Option<f64>) -> Self {
+        let planned = planned | phase.bit();
+        Self {
+            phase,
+            index: (planned & (phase.bit() - 1)).count_ones() as usize + 1,
+            count: planned.count_ones() as usize,
+            progress,
+        }
+    }
+
+    /// 折算为整体进度：已完成阶段各占一份，当前阶段按阶段内进度计入
+    pub fn overall(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        ((self.index - 1) as f64 + self.progress.unwrap_or(0.0)) / self.count as f64
+    }
+}
+
+impl fmt::Display for PhaseProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "阶段 {}/{}：{}", self.index, self.count, self.phase.label())?;
+        if let Some(progress) = self.progress {
+            write!(f, " {:.0}%", progress * 100.0)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+//! 云端占位文件识别
+//!
+//! OneDrive、iCloud Drive、Dropbox 等同步服务可以把文件设为“仅在线”：本地只保留占位条目，
+//! 元数据中的大小是云端文件的大小，实际几乎不占磁盘；读取内容会触发下载。扫描仍按报告的大小统计
+//! 这些文件，但在条目上标记 `is_placeholder`，并在摘要中汇总数量与大小，便于区分本地占用；
+//! 重复检测、内容摘要与 MIME 识别不会读取占位文件。
+//!
+//! - Windows：文件属性含 `FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS`、`FILE_ATTRIBUTE_RECALL_ON_OPEN`
+//!   或 `FILE_ATTRIBUTE_OFFLINE`；
+//! - macOS：`st_flags` 含 `SF_DATALESS`（iCloud 与 File Provider 的无数据文件）；
+//! - 其他平台没有对应标记，不识别。
+
+use std::fs::Metadata;
+
+/// 元数据是否表明该文件为仅在线的占位文件
+#[cfg(windows)]
+pub fn is_placeholder(metadata: &Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x0000_1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x0004_0000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+    metadata.file_attributes() & (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+        != 0
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_placeholder(metadata: &Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    const SF_DATALESS: u32 = 0x4000_0000;
+    metadata.st_flags() & SF_DATALESS != 0
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn is_placeholder(_metadata: &Metadata) -> bool {
+    false
+}
@@ -0,0 +1,218 @@
+//! 分析器插件（子进程 + NDJSON 协议）
+//!
+//! 插件目录中的每个可执行文件都是一个分析器插件，插件名为文件名（不含扩展名）。
+//! 扫描时引擎为每个插件启动一个子进程，并通过 stdin 逐行发送 JSON：
+//!
+//! ```text
+//! {"type":"entry","path":"/data/a.mp4","size_bytes":1024,"last_modified":1714552200000,"extension":"mp4"}
+//! ...
+//! {"type":"end"}
+//! ```
+//!
+//! `last_modified` 为 Unix 纪元毫秒数（早于 1970 年时为负数），无法获取时为 `null`。
+//!
+//! 插件读完输入后向 stdout 输出一行 JSON 作为其结果段，引擎以插件名为键合并到
+//! `ScanResult::plugin_sections`。插件失败时对应结果段为 `{"error": "..."}`，不影响扫描本身。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{lock, serde_time, FileEntry};
+
+/// 发送给插件的条目队列容量，插件处理较慢时对扫描线程形成背压
+const PLUGIN_QUEUE_CAPACITY: usize = 1024;
+
+/// 插件描述
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginSpec {
+    /// 插件名（结果段的键）
+    pub name: String,
+    /// 可执行文件路径
+    pub command: PathBuf,
+}
+
+/// 默认插件目录：`~/.config/surf/plugins`
+pub fn default_plugin_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("surf").join("plugins"))
+}
+
+/// 发现插件目录中的所有可执行文件，按插件名排序；目录不存在时返回空列表
+pub fn discover(dir: &Path) -> io::Result<Vec<PluginSpec>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut plugins: Vec<PluginSpec> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| is_executable(p))
+        .filter_map(|command| {
+            let name = command.file_stem()?.to_string_lossy().into_owned();
+            Some(PluginSpec { name, command })
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(plugins)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// 扫描期间运行中的插件集合
+pub(crate) struct PluginSet {
+    running: Vec<RunningPlugin>,
+}
+
+struct RunningPlugin {
+    name: String,
+    /// 条目发送端；扫描结束时置空以关闭输入
+    sender: Mutex<Option<SyncSender<FileEntry>>>,
+    child: Option<Child>,
+    writer: Option<JoinHandle<()>>,
+    reader: Option<JoinHandle<io::Result<String>>>,
+    start_error: Option<String>,
+}
+
+impl PluginSet {
+    /// 启动全部插件子进程
+    pub(crate) fn start(specs: &[PluginSpec]) -> Self {
+        Self {
+            running: specs.iter().map(RunningPlugin::start).collect(),
+        }
+    }
+
+    /// 将条目发送给所有插件
+    pub(crate) fn send(&self, entry: &FileEntry) {
+        for plugin in &self.running {
+            if let Some(sender) = lock(&plugin.sender).as_ref() {
+                // 插件提前退出时发送失败，忽略即可
+                let _ = sender.send(entry.clone());
+            }
+        }
+    }
+
+    /// 关闭输入并收集各插件的结果段
+    pub(crate) fn finish(self) -> BTreeMap<String, Value> {
+        self.running
+            .into_iter()
+            .map(|plugin| {
+                let name = plugin.name.clone();
+                let section = plugin.finish().unwrap_or_else(|e| json!({ "error": e }));
+                (name, section)
+            })
+            .collect()
+    }
+}
+
+impl RunningPlugin {
+    fn start(spec: &PluginSpec) -> Self {
+        let spawned = Command::new(&spec.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                return Self {
+                    name: spec.name.clone(),
+                    sender: Mutex::new(None),
+                    child: None,
+                    writer: None,
+                    reader: None,
+                    start_error: Some(format!("启动插件失败: {}", e)),
+                }
+            }
+        };
+
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, receiver) = sync_channel::<FileEntry>(PLUGIN_QUEUE_CAPACITY);
+        let writer = std::thread::spawn(move || {
+            let mut out = BufWriter::new(stdin);
+            let mut broken = false;
+            for entry in receiver {
+                // 插件已关闭输入时继续消费队列，避免阻塞扫描线程
+                if broken {
+                    continue;
+                }
+                let line = json!({
+                    "type": "entry",
+                    "path": entry.path,
+                    "size_bytes": entry.size_bytes,
+                    "last_modified": entry.last_modified.map(serde_time::to_millis),
+                    "extension": entry.extension,
+                });
+                broken = writeln!(out, "{}", line).is_err();
+            }
+            if !broken {
+                let _ = writeln!(out, "{}", json!({ "type": "end" }));
+                let _ = out.flush();
+            }
+        });
+        let reader = std::thread::spawn(move || read_all(stdout));
+
+        Self {
+            name: spec.name.clone(),
+            sender: Mutex::new(Some(sender)),
+            child: Some(child),
+            writer: Some(writer),
+            reader: Some(reader),
+            start_error: None,
+        }
+    }
+
+    fn finish(mut self) -> Result<Value, String> {
+        if let Some(error) = self.start_error.take() {
+            return Err(error);
+        }
+        // 关闭发送端，写线程写出结束标记后退出并关闭插件 stdin
+        lock(&self.sender).take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+        let output = self
+            .reader
+            .take()
+            .map(|r| r.join().unwrap_or_else(|_| Ok(String::new())))
+            .transpose()
+            .map_err(|e| format!("读取插件输出失败: {}", e))?
+            .unwrap_or_default();
+        let status = self.child.take().map(|mut c| c.wait());
+        if let Some(Ok(status)) = status {
+            if !status.success() {
+                return Err(format!("插件异常退出: {}", status));
+            }
+        }
+        let line = output
+            .lines()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .ok_or_else(|| "插件未输出结果".to_string())?;
+        serde_json::from_str(line).map_err(|e| format!("插件输出不是合法 JSON: {}", e))
+    }
+}
+
+fn read_all(mut stdout: ChildStdout) -> io::Result<String> {
+    let mut output = String::new();
+    stdout.read_to_string(&mut output)?;
+    Ok(output)
+}
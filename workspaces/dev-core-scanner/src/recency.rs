@@ -0,0 +1,63 @@
+//! 最旧与最新文件
+//!
+//! 按时间排查（“刚刚冒出来的是什么？”“哪些文件已经多年没动过？”）与按大小排查同样常见。
+//! 请求 `recency_limit` 时按最后修改时间收集两个列表：`oldest_files`（修改时间最早者在前）与
+//! `newest_files`（修改时间最晚者在前），各自最多保留 `recency_limit` 个文件，与 Top N 的数量无关。
+//! 修改时间未知或可疑（早于 1980 年或晚于当前时间，见 [`timestamp`](crate::timestamp)）的文件
+//! 不参与排名，否则纪元零点与未来时间会占满两个列表。仅汇总扫描不保留列表。
+
+use std::cmp::Reverse;
+use std::sync::Mutex;
+
+use crate::{lock, FileEntry};
+
+/// 全部遍历任务共享的最旧与最新文件收集
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    /// 每个列表最多保留的条目数；`None` 表示不收集
+    pub(crate) limit: Option<usize>,
+    oldest: Mutex<Vec<FileEntry>>,
+    newest: Mutex<Vec<FileEntry>>,
+}
+
+impl Counters {
+    /// 记录一个修改时间在合理范围内的文件
+    pub(crate) fn add(&self, entry: &FileEntry) {
+        let Some(limit) = self.limit.filter(|&limit| limit > 0) else {
+            return;
+        };
+        push(&self.oldest, entry, limit, sort_oldest);
+        push(&self.newest, entry, limit, sort_newest);
+    }
+
+    /// 最旧与最新的文件列表
+    pub(crate) fn to_vec(&self) -> (Vec<FileEntry>, Vec<FileEntry>) {
+        let limit = self.limit.unwrap_or(0);
+        let mut oldest = std::mem::take(&mut *lock(&self.oldest));
+        let mut newest = std::mem::take(&mut *lock(&self.newest));
+        sort_oldest(&mut oldest, limit);
+        sort_newest(&mut newest, limit);
+        (oldest, newest)
+    }
+}
+
+fn push(files: &Mutex<Vec<FileEntry>>, entry: &FileEntry, limit: usize, sort: fn(&mut Vec<FileEntry>, usize)) {
+    let mut files = lock(files);
+    files.push(entry.clone());
+    // 超出两倍上限时截断，避免大量文件占用内存
+    if files.len() >= limit * 2 {
+        sort(&mut files, limit);
+    }
+}
+
+/// 按修改时间升序、路径升序排列并截断（合并结果时同样使用）
+pub(crate) fn sort_oldest(files: &mut Vec<FileEntry>, limit: usize) {
+    files.sort_by(|a, b| a.last_modified.cmp(&b.last_modified).then_with(|| a.path.cmp(&b.path)));
+    files.truncate(limit);
+}
+
+/// 按修改时间降序、路径升序排列并截断（合并结果时同样使用）
+pub(crate) fn sort_newest(files: &mut Vec<FileEntry>, limit: usize) {
+    files.sort_by(|a, b| Reverse(a.last_modified).cmp(&Reverse(b.last_modified)).then_with(|| a.path.cmp(&b.path)));
+    files.truncate(limit);
+}
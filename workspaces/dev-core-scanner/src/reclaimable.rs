@@ -0,0 +1,325 @@
+//! 可回收位置识别
+//!
+//! 废纸篓 / 回收站、浏览器缓存、包管理器缓存、Xcode DerivedData、`node_modules` 与 Cargo 的 `target`
+//! 目录往往占据大量空间，且清空后可以由对应工具重新生成（或本就等待删除）。遍历时按内置的位置规则
+//! 识别这些目录，结束后读取其递归大小，按类别汇总到 `ScanResult::reclaimable_report`，作为“能释放多少”
+//! 的估算；每个类别附带占用最大的若干个位置（最多 `limit` 个）与建议的清理方式。
+//!
+//! 识别只依据路径（不区分大小写），Cargo 的 `target` 目录另要求其所在目录含 `Cargo.toml`。
+//! 嵌套的位置（如 `node_modules` 内的 `node_modules`）只计入最外层一次。估算只包含计入统计的文件：
+//! 被排除、无法读取或超出 `max_depth` 的部分不在其中，仅在线的云端占位文件按报告的大小计入。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::lock;
+use crate::share::DirNode;
+
+/// 可回收位置的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ReclaimableKind {
+    /// 废纸篓与回收站（`~/.Trash`、`.Trashes`、`~/.local/share/Trash`、`.Trash-1000`、`$Recycle.Bin`）
+    Trash,
+    /// 浏览器缓存（Chrome、Edge、Firefox、Safari 等）
+    BrowserCache,
+    /// 包管理器的下载缓存（npm、pnpm、Yarn、pip、Homebrew、Cargo registry、Gradle、Maven 等）
+    PackageCache,
+    /// Xcode 的中间构建产物（`~/Library/Developer/Xcode/DerivedData`）
+    XcodeDerivedData,
+    /// Node.js 项目的依赖目录
+    NodeModules,
+    /// Cargo 项目的构建目录（`target`）
+    CargoTarget,
+}
+
+impl ReclaimableKind {
+    /// 中文名称
+    pub fn label(self) -> &'static str {
+        match self {
+            ReclaimableKind::Trash => "废纸篓 / 回收站",
+            ReclaimableKind::BrowserCache => "浏览器缓存",
+            ReclaimableKind::PackageCache => "包管理器缓存",
+            ReclaimableKind::XcodeDerivedData => "Xcode DerivedData",
+            ReclaimableKind::NodeModules => "node_modules",
+            ReclaimableKind::CargoTarget => "Cargo 构建目录",
+        }
+    }
+
+    /// 建议的清理方式
+    pub fn advice(self) -> &'static str {
+        match self {
+            ReclaimableKind::Trash => "清空废纸篓 / 回收站",
+            ReclaimableKind::BrowserCache => "在浏览器设置中清除缓存",
+            ReclaimableKind::PackageCache => "使用包管理器的清理命令，如 npm cache clean --force、pip cache purge、brew cleanup",
+            ReclaimableKind::XcodeDerivedData => "退出 Xcode 后删除，下次构建时重新生成",
+            ReclaimableKind::NodeModules => "删除不再开发的项目中的 node_modules，需要时重新安装依赖",
+            ReclaimableKind::CargoTarget => "在项目目录中执行 cargo clean",
+        }
+    }
+}
+
+impl fmt::Display for ReclaimableKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// 一个可回收位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ReclaimableLocation {
+    /// 目录路径
+    pub path: PathBuf,
+    /// 目录中计入统计的文件总大小（字节）
+    pub size_bytes: u64,
+    /// 目录中计入统计的文件数
+    pub file_count: u64,
+}
+
+/// 一类可回收位置的汇总
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ReclaimableStat {
+    /// 类别
+    pub kind: ReclaimableKind,
+    /// 可释放的总字节数
+    pub size_bytes: u64,
+    /// 文件总数
+    pub file_count: u64,
+    /// 位置总数
+    pub location_count: u64,
+    /// 占用最大的位置（按大小降序，最多 `limit` 个）
+    pub locations: Vec<ReclaimableLocation>,
+}
+
+/// 按类别估算的可释放空间
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ReclaimableReport {
+    /// 各类别可释放的总字节数
+    pub total_bytes: u64,
+    /// 各类别的汇总（按大小降序）
+    pub categories: Vec<ReclaimableStat>,
+}
+
+impl ReclaimableReport {
+    /// 未发现任何可回收位置
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+    }
+
+    /// 指定类别的汇总
+    pub fn category(&self, kind: ReclaimableKind) -> Option<&ReclaimableStat> {
+        self.categories.iter().find(|stat| stat.kind == kind)
+    }
+
+    /// 补扫的子树位于某个位置之内时，把子树的大小与文件数计入该位置及其类别；返回是否计入
+    pub(crate) fn add_subtree(&mut self, sub_root: &Path, size_bytes: u64, file_count: u64) -> bool {
+        for stat in &mut self.categories {
+            if let Some(location) = stat.locations.iter_mut().find(|l| sub_root.starts_with(&l.path)) {
+                location.size_bytes += size_bytes;
+                location.file_count += file_count;
+                stat.size_bytes += size_bytes;
+                stat.file_count += file_count;
+                self.total_bytes += size_bytes;
+                sort(&mut stat.locations);
+                sort_categories(&mut self.categories);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// 只保留 `keep` 为真的位置（用于从索引快照中截取子树），类别总量按保留的位置重新计算
+    pub(crate) fn retain(&mut self, keep: impl Fn(&Path) -> bool) {
+        for stat in &mut self.categories {
+            stat.locations.retain(|l| keep(&l.path));
+            stat.size_bytes = stat.locations.iter().map(|l| l.size_bytes).sum();
+            stat.file_count = stat.locations.iter().map(|l| l.file_count).sum();
+            stat.location_count = stat.locations.len() as u64;
+        }
+        self.categories.retain(|stat| stat.location_count > 0);
+        self.total_bytes = self.categories.iter().map(|stat| stat.size_bytes).sum();
+        sort_categories(&mut self.categories);
+    }
+}
+
+/// 视为浏览器缓存根目录的名称（位于 `.cache` 或 `Caches` 之下）
+const BROWSER_CACHE_ROOTS: &[&str] =
+    &["google-chrome", "chromium", "mozilla", "firefox", "microsoft-edge", "bravesoftware", "google", "com.apple.safari"];
+
+/// 浏览器配置目录中的缓存目录名
+const BROWSER_CACHE_DIRS: &[&str] = &["cache", "code cache", "gpucache", "cache2"];
+
+/// 浏览器配置目录的名称（出现在缓存目录的祖先中）
+const BROWSER_PROFILE_DIRS: &[&str] =
+    &["chrome", "chromium", "edge", "firefox", "brave-browser", "opera stable", "vivaldi"];
+
+/// 包管理器缓存：（父目录名，目录名）
+const PACKAGE_CACHES: &[(&str, &str)] = &[
+    (".npm", "_cacache"),
+    ("npm-cache", "_cacache"),
+    (".cache", "pip"),
+    ("caches", "pip"),
+    ("pip", "cache"),
+    (".cache", "yarn"),
+    ("caches", "yarn"),
+    ("yarn", "cache"),
+    (".cache", "pnpm"),
+    ("caches", "homebrew"),
+    ("caches", "cocoapods"),
+    (".cache", "go-build"),
+    ("caches", "go-build"),
+    (".cargo", "registry"),
+    (".gradle", "caches"),
+    (".m2", "repository"),
+    (".nuget", "packages"),
+];
+
+/// 按路径识别可回收的目录
+pub fn classify(path: &Path) -> Option<ReclaimableKind> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    let parent = path.parent().and_then(Path::file_name).and_then(|n| n.to_str()).map(str::to_lowercase);
+    let parent = parent.as_deref().unwrap_or("");
+    let grandparent = path
+        .parent()
+        .and_then(Path::parent)
+        .and_then(Path::file_name)
+        .and_then(|n| n.to_str())
+        .map(str::to_lowercase);
+
+    if matches!(name.as_str(), ".trash" | ".trashes" | "$recycle.bin")
+        || name.starts_with(".trash-")
+        || (name == "trash" && parent == "share" && grandparent.as_deref() == Some(".local"))
+    {
+        return Some(ReclaimableKind::Trash);
+    }
+    match name.as_str() {
+        "node_modules" => return Some(ReclaimableKind::NodeModules),
+        "deriveddata" if parent == "xcode" => return Some(ReclaimableKind::XcodeDerivedData),
+        ".pnpm-store" => return Some(ReclaimableKind::PackageCache),
+        "target" => {
+            let manifest = path.with_file_name("Cargo.toml");
+            if crate::long_path::extended(&manifest).is_file() {
+                return Some(ReclaimableKind::CargoTarget);
+            }
+        }
+        _ => {}
+    }
+    if PACKAGE_CACHES.iter().any(|&(p, n)| p == parent && n == name) {
+        return Some(ReclaimableKind::PackageCache);
+    }
+    if matches!(parent, ".cache" | "caches") && BROWSER_CACHE_ROOTS.contains(&name.as_str()) {
+        return Some(ReclaimableKind::BrowserCache);
+    }
+    if BROWSER_CACHE_DIRS.contains(&name.as_str()) && in_browser_profile(path) {
+        return Some(ReclaimableKind::BrowserCache);
+    }
+    None
+}
+
+/// 路径的祖先中是否有浏览器配置目录
+fn in_browser_profile(path: &Path) -> bool {
+    path.components().any(|component| match component {
+        Component::Normal(name) => {
+            name.to_str().is_some_and(|name| BROWSER_PROFILE_DIRS.contains(&name.to_lowercase().as_str()))
+        }
+        _ => false,
+    })
+}
+
+/// 遍历中识别出的位置；结束后读取各目录节点的递归大小
+#[derive(Debug, Default)]
+pub(crate) struct Locations(Mutex<Vec<(ReclaimableKind, Arc<DirNode>)>>);
+
+impl Locations {
+    pub(crate) fn record(&self, kind: ReclaimableKind, node: &Arc<DirNode>) {
+        lock(&self.0).push((kind, node.clone()));
+    }
+
+    /// 去掉嵌套在其他位置之内的位置后按类别汇总，各类别保留最大的 `limit` 个位置
+    pub(crate) fn to_report(&self, limit: usize) -> ReclaimableReport {
+        let locations = std::mem::take(&mut *lock(&self.0))
+            .into_iter()
+            .map(|(kind, node)| {
+                (kind, ReclaimableLocation { path: node.path().to_path_buf(), size_bytes: node.size(), file_count: node.files() })
+            })
+            .collect();
+        build(locations, limit)
+    }
+}
+
+/// 由位置列表构建汇总
+fn build(mut locations: Vec<(ReclaimableKind, ReclaimableLocation)>, limit: usize) -> ReclaimableReport {
+    // 按路径组件排序后，目录的全部后代紧随其后
+    locations.sort_by(|a, b| a.1.path.cmp(&b.1.path));
+    let mut outermost: Vec<(ReclaimableKind, ReclaimableLocation)> = Vec::with_capacity(locations.len());
+    for (kind, location) in locations {
+        if outermost.last().is_some_and(|(_, last)| location.path.starts_with(&last.path)) {
+            continue;
+        }
+        outermost.push((kind, location));
+    }
+    let mut by_kind: HashMap<ReclaimableKind, ReclaimableStat> = HashMap::new();
+    for (kind, location) in outermost {
+        let stat = by_kind.entry(kind).or_insert_with(|| ReclaimableStat {
+            kind,
+            size_bytes: 0,
+            file_count: 0,
+            location_count: 0,
+            locations: Vec::new(),
+        });
+        stat.size_bytes += location.size_bytes;
+        stat.file_count += location.file_count;
+        stat.location_count += 1;
+        stat.locations.push(location);
+    }
+    let mut categories: Vec<ReclaimableStat> = by_kind.into_values().collect();
+    for stat in &mut categories {
+        sort(&mut stat.locations);
+        stat.locations.truncate(limit);
+    }
+    sort_categories(&mut categories);
+    ReclaimableReport { total_bytes: categories.iter().map(|stat| stat.size_bytes).sum(), categories }
+}
+
+/// 合并多个结果的汇总：类别总量相加，位置合并后按大小重新截断
+pub(crate) fn merge(reports: Vec<ReclaimableReport>, limit: usize) -> ReclaimableReport {
+    let mut by_kind: HashMap<ReclaimableKind, ReclaimableStat> = HashMap::new();
+    for stat in reports.into_iter().flat_map(|report| report.categories) {
+        match by_kind.get_mut(&stat.kind) {
+            Some(merged) => {
+                merged.size_bytes += stat.size_bytes;
+                merged.file_count += stat.file_count;
+                merged.location_count += stat.location_count;
+                merged.locations.extend(stat.locations);
+            }
+            None => {
+                by_kind.insert(stat.kind, stat);
+            }
+        }
+    }
+    let mut categories: Vec<ReclaimableStat> = by_kind.into_values().collect();
+    for stat in &mut categories {
+        sort(&mut stat.locations);
+        stat.locations.truncate(limit);
+    }
+    sort_categories(&mut categories);
+    ReclaimableReport { total_bytes: categories.iter().map(|stat| stat.size_bytes).sum(), categories }
+}
+
+/// 位置按大小降序、路径升序排列
+fn sort(locations: &mut [ReclaimableLocation]) {
+    locations.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| a.path.cmp(&b.path)));
+}
+
+/// 类别按大小降序、类别排列
+fn sort_categories(categories: &mut [ReclaimableStat]) {
+    categories.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| a.kind.cmp(&b.kind)));
+}
@@ -0,0 +1,169 @@
+//! 冗余附属文件识别
+//!
+//! 备份副本（`file.bak`、`file~`）、重复下载或复制产生的编号副本（`file (1).ext`、`file copy.ext`）、
+//! Office 锁文件（`~$file.docx`）、系统生成的元数据（`.DS_Store`、`._file`、`Thumbs.db`）与编辑器
+//! 交换文件（`.file.swp`、`#file#`）通常可以安全删除。扫描时按文件名识别这些普通文件，在文件条目上
+//! 标注类型，并按类型汇总到 `ScanResult::redundant`，可释放的总字节数见 `summary.redundant_bytes`。
+//! 识别只依据名称，编号副本不比较内容（需要确认时配合重复检测）。请求 `list_redundant_files` 时
+//! 另在 `redundant_files` 中列出其中最大的 Top N 个文件。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{lock, FileEntry};
+
+/// 冗余文件的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum RedundantKind {
+    /// 备份副本（`.bak`、`.backup`、`.old`、`.orig` 与以 `~` 结尾的文件）
+    Backup,
+    /// 编号或复制副本（`file (1).ext`、`file copy.ext`、`file - Copy.ext`）
+    NumberedCopy,
+    /// Office / LibreOffice 打开文档时留下的锁文件（`~$file.docx`、`.~lock.file#`）
+    LockFile,
+    /// 系统生成的文件夹元数据（`.DS_Store`、AppleDouble `._file`、`Thumbs.db`、`desktop.ini`）
+    SystemMetadata,
+    /// 编辑器交换与自动保存文件（`.file.swp`、`#file#`、`.#file`）
+    EditorSwap,
+}
+
+impl RedundantKind {
+    /// 中文名称
+    pub fn label(self) -> &'static str {
+        match self {
+            RedundantKind::Backup => "备份副本",
+            RedundantKind::NumberedCopy => "编号副本",
+            RedundantKind::LockFile => "锁文件",
+            RedundantKind::SystemMetadata => "系统元数据",
+            RedundantKind::EditorSwap => "编辑器交换文件",
+        }
+    }
+}
+
+impl fmt::Display for RedundantKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// 一类冗余文件的汇总
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RedundantStat {
+    /// 类型
+    pub kind: RedundantKind,
+    /// 文件数
+    pub file_count: u64,
+    /// 总大小（字节），即删除后可释放的空间
+    pub total_size_bytes: u64,
+}
+
+/// 按文件名识别冗余文件
+pub fn classify(path: &Path) -> Option<RedundantKind> {
+    let name = path.file_name()?.to_str()?;
+    let lower = name.to_lowercase();
+    if matches!(lower.as_str(), ".ds_store" | "thumbs.db" | "ehthumbs.db" | "desktop.ini") || name.starts_with("._") {
+        return Some(RedundantKind::SystemMetadata);
+    }
+    if name.starts_with("~$") || (name.starts_with(".~lock.") && name.ends_with('#')) {
+        return Some(RedundantKind::LockFile);
+    }
+    let swap = lower.ends_with(".swp") || lower.ends_with(".swo") || lower.ends_with(".swn");
+    if (name.starts_with('.') && swap) || (name.len() > 2 && name.starts_with('#') && name.ends_with('#')) || name.starts_with(".#") {
+        return Some(RedundantKind::EditorSwap);
+    }
+    if name.len() > 1 && name.ends_with('~') {
+        return Some(RedundantKind::Backup);
+    }
+    let (stem, extension) = match lower.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (lower.as_str(), None),
+    };
+    if matches!(extension, Some("bak" | "backup" | "old" | "orig" | "bck")) {
+        return Some(RedundantKind::Backup);
+    }
+    numbered_copy(stem).then_some(RedundantKind::NumberedCopy)
+}
+
+/// `name (2)`、`name copy`、`name copy 3`、`name - copy`；括号中超过 3 位的数字（如年份）不算编号
+fn numbered_copy(stem: &str) -> bool {
+    if let Some(rest) = stem.strip_suffix(')') {
+        if let Some((base, number)) = rest.rsplit_once(" (") {
+            return !base.is_empty() && (1..=3).contains(&number.len()) && number.bytes().all(|b| b.is_ascii_digit());
+        }
+    }
+    let stem = stem.trim_end_matches(|c: char| c.is_ascii_digit()).trim_end();
+    stem.strip_suffix(" copy")
+        .map(|base| base.strip_suffix(" -").unwrap_or(base))
+        .is_some_and(|base| !base.trim().is_empty())
+}
+
+/// 全部遍历任务共享的冗余文件汇总
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    /// 列表最多保留的条目数
+    pub(crate) limit: usize,
+    /// 是否保留文件列表
+    pub(crate) keep_entries: bool,
+    stats: Mutex<HashMap<RedundantKind, (u64, u64)>>,
+    files: Mutex<Vec<FileEntry>>,
+}
+
+impl Counters {
+    pub(crate) fn add(&self, entry: &FileEntry, kind: RedundantKind) {
+        let mut stats = lock(&self.stats);
+        let stat = stats.entry(kind).or_default();
+        stat.0 += 1;
+        stat.1 += entry.size_bytes;
+        drop(stats);
+        if !self.keep_entries || self.limit == 0 {
+            return;
+        }
+        let mut files = lock(&self.files);
+        files.push(entry.clone());
+        // 超出两倍上限时截断，避免大量冗余文件占用内存
+        if files.len() >= self.limit * 2 {
+            sort_and_truncate(&mut files, self.limit);
+        }
+    }
+
+    /// 按类型的汇总与最大的若干个文件
+    pub(crate) fn to_vec(&self) -> (Vec<RedundantStat>, Vec<FileEntry>) {
+        let stats = lock(&self.stats)
+            .iter()
+            .map(|(&kind, &(file_count, total_size_bytes))| RedundantStat { kind, file_count, total_size_bytes })
+            .collect();
+        let mut files = std::mem::take(&mut *lock(&self.files));
+        sort_and_truncate(&mut files, self.limit);
+        (sorted(stats), files)
+    }
+}
+
+/// 按总大小降序、类型排列
+fn sorted(mut stats: Vec<RedundantStat>) -> Vec<RedundantStat> {
+    stats.sort_by(|a, b| b.total_size_bytes.cmp(&a.total_size_bytes).then_with(|| a.kind.cmp(&b.kind)));
+    stats
+}
+
+/// 合并多个结果的汇总
+pub(crate) fn merge(stats: Vec<RedundantStat>) -> Vec<RedundantStat> {
+    let mut merged: HashMap<RedundantKind, RedundantStat> = HashMap::new();
+    for stat in stats {
+        let entry = merged.entry(stat.kind).or_insert(RedundantStat { kind: stat.kind, file_count: 0, total_size_bytes: 0 });
+        entry.file_count += stat.file_count;
+        entry.total_size_bytes += stat.total_size_bytes;
+    }
+    sorted(merged.into_values().collect())
+}
+
+/// 按大小降序、路径升序排列并截断（合并结果时同样使用）
+pub(crate) fn sort_and_truncate(files: &mut Vec<FileEntry>, limit: usize) {
+    files.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| a.path.cmp(&b.path)));
+    files.truncate(limit);
+}
@@ -0,0 +1,77 @@
+//! Windows 系统保留文件识别
+//!
+//! 卷根目录下的 `pagefile.sys`、`hiberfil.sys` 与 `System Volume Information` 往往是最大的几个条目，
+//! 但由系统管理、无法直接删除。扫描照常统计它们的实际大小（计入总量与所在目录），同时在文件条目上
+//! 标注为系统保留，并汇总到 `ScanResult::system_reserved`，前端据此说明用途与释放方式，
+//! 避免用户把它们当作普通大文件处理。
+//!
+//! 识别只依据名称（不区分大小写），在其他系统上挂载的 NTFS 卷同样适用。
+//! `System Volume Information` 通常只有 SYSTEM 账户可读，未提权扫描时其大小只包含可读取的部分。
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// 系统保留条目的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ReservedKind {
+    /// 虚拟内存页面文件（`pagefile.sys`）
+    PageFile,
+    /// 应用商店应用使用的交换文件（`swapfile.sys`）
+    SwapFile,
+    /// 休眠文件（`hiberfil.sys`），关闭休眠（`powercfg /h off`）后释放
+    Hibernation,
+    /// 卷影副本与系统还原点（`System Volume Information` 目录）
+    VolumeInformation,
+}
+
+impl ReservedKind {
+    /// 中文名称
+    pub fn label(self) -> &'static str {
+        match self {
+            ReservedKind::PageFile => "页面文件",
+            ReservedKind::SwapFile => "交换文件",
+            ReservedKind::Hibernation => "休眠文件",
+            ReservedKind::VolumeInformation => "卷影副本与系统还原",
+        }
+    }
+}
+
+impl fmt::Display for ReservedKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// 扫描中发现的系统保留条目
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ReservedEntry {
+    /// 路径
+    pub path: PathBuf,
+    /// 类型
+    pub kind: ReservedKind,
+    /// 实际大小（字节）；目录为其中可读取部分的递归大小
+    pub size_bytes: u64,
+}
+
+/// 按名称识别系统保留的文件或目录
+pub fn classify(path: &Path, is_dir: bool) -> Option<ReservedKind> {
+    let name = path.file_name()?.to_str()?;
+    let is = |expected: &str| name.eq_ignore_ascii_case(expected);
+    match is_dir {
+        true if is("System Volume Information") => Some(ReservedKind::VolumeInformation),
+        false if is("pagefile.sys") => Some(ReservedKind::PageFile),
+        false if is("swapfile.sys") => Some(ReservedKind::SwapFile),
+        false if is("hiberfil.sys") => Some(ReservedKind::Hibernation),
+        _ => None,
+    }
+}
+
+/// 按大小降序、路径升序排列
+pub(crate) fn sort(entries: &mut [ReservedEntry]) {
+    entries.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| a.path.cmp(&b.path)));
+}
@@ -0,0 +1,67 @@
+//! 瞬时错误重试
+//!
+//! 网络文件系统（NFS/SMB）上的 `stat` 偶尔会因 EINTR、EAGAIN、ESTALE 等瞬时错误失败，
+//! 立即放弃会让结果中零星缺失文件大小。对这类错误按指数退避（叠加随机抖动）重试
+//! 有限次数，其余错误（如 ENOENT、EACCES）立即返回。
+
+use std::io;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// 元数据读取的重试策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 首次失败后的最大重试次数（0 表示不重试）
+    pub max_retries: u32,
+    /// 首次重试前的基础等待时间，之后每次翻倍
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 不重试
+    pub fn none() -> Self {
+        Self { max_retries: 0, ..Self::default() }
+    }
+
+    /// 第 `attempt` 次重试（从 0 开始）前的等待时间：指数退避并叠加至多 50% 的抖动，
+    /// 避免大量并发任务在同一时刻重新访问服务端
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.base_delay.saturating_mul(1 << attempt.min(16));
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+        backoff + backoff.mul_f64(f64::from(nanos % 1000) / 2000.0)
+    }
+
+    /// 执行 `op`，遇到瞬时错误时按策略重试；返回最终结果与实际重试次数
+    pub fn run<T>(&self, mut op: impl FnMut() -> io::Result<T>) -> (io::Result<T>, u32) {
+        let mut attempt = 0;
+        loop {
+            match op() {
+                Err(e) if attempt < self.max_retries && is_transient(&e) => {
+                    thread::sleep(self.delay(attempt));
+                    attempt += 1;
+                }
+                result => return (result, attempt),
+            }
+        }
+    }
+}
+
+/// 是否为值得重试的瞬时错误（EINTR / EAGAIN / ESTALE）
+pub fn is_transient(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::StaleNetworkFileHandle
+    )
+}
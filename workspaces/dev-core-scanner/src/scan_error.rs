@@ -0,0 +1,145 @@
+//! 遍历错误
+//!
+//! 并行遍历中无法读取的目录、目录条目与文件元数据不再被静默跳过：每个错误记录为一条
+//! [`TraversalError`]（路径、失败的操作、错误类别与信息），写入结果的 `scan_errors`（按路径排序，最多保留
+//! [`MAX_RECORDED`] 条），总数见 `summary.scan_error_count`。错误不影响其余部分的遍历；请求
+//! `fail_fast` 时遇到第一个错误即停止遍历，扫描以该错误（[`ScanError::PermissionDenied`] 或
+//! [`ScanError::Io`]）失败而不是返回不完整的结果。
+//! 权限不足的目录同时记入 `diagnostics.denied_dirs`，供提权补扫使用。
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{lock, ScanError};
+
+/// `scan_errors` 最多保留的条目数（总数不受限制）
+pub const MAX_RECORDED: usize = 10_000;
+
+/// 出错的操作
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ScanOperation {
+    /// 打开目录读取条目列表（目录内容整体未统计）
+    #[default]
+    ReadDir,
+    /// 读取目录中的某个条目（其余条目照常统计）
+    ReadEntry,
+    /// 读取文件元数据（文件按 0 字节计入）
+    Metadata,
+}
+
+impl ScanOperation {
+    /// 中文显示名称
+    pub fn label(self) -> &'static str {
+        match self {
+            ScanOperation::ReadDir => "读取目录",
+            ScanOperation::ReadEntry => "读取目录条目",
+            ScanOperation::Metadata => "读取元数据",
+        }
+    }
+}
+
+/// 一条遍历错误
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TraversalError {
+    /// 出错的路径（读取目录条目失败时为所在目录）
+    pub path: PathBuf,
+    /// 出错的操作
+    pub operation: ScanOperation,
+    /// 错误类别（`std::io::ErrorKind` 的 snake_case 形式，如 `permission_denied`）
+    pub kind: String,
+    /// 错误信息
+    pub message: String,
+}
+
+impl TraversalError {
+    /// 由 IO 错误构造
+    pub fn new(path: &Path, operation: ScanOperation, error: &io::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            operation,
+            kind: kind_name(error.kind()),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for TraversalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}失败: {}: {}", self.operation.label(), self.path.display(), self.message)
+    }
+}
+
+/// `PermissionDenied` → `permission_denied`
+fn kind_name(kind: io::ErrorKind) -> String {
+    let mut name = String::new();
+    for ch in format!("{:?}", kind).chars() {
+        if ch.is_ascii_uppercase() {
+            if !name.is_empty() {
+                name.push('_');
+            }
+            name.push(ch.to_ascii_lowercase());
+        } else {
+            name.push(ch);
+        }
+    }
+    name
+}
+
+/// 全部遍历任务共享的错误收集
+#[derive(Debug, Default)]
+pub(crate) struct Collector {
+    /// 遇到第一个错误即中止遍历
+    pub(crate) fail_fast: bool,
+    count: AtomicU64,
+    errors: Mutex<Vec<TraversalError>>,
+    /// `fail_fast` 时触发中止的第一个错误
+    first: Mutex<Option<ScanError>>,
+    aborted: AtomicBool,
+}
+
+impl Collector {
+    pub(crate) fn record(&self, path: &Path, operation: ScanOperation, error: &io::Error) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let record = TraversalError::new(path, operation, error);
+        if self.fail_fast && !self.aborted.swap(true, Ordering::Relaxed) {
+            *lock(&self.first) = Some(ScanError::io(path, io::Error::new(error.kind(), error.to_string())));
+        }
+        let mut errors = lock(&self.errors);
+        errors.push(record);
+        // 超出两倍上限时按路径截断，避免大量错误占用内存
+        if errors.len() >= MAX_RECORDED * 2 {
+            sort_and_truncate(&mut errors);
+        }
+    }
+
+    /// 是否因 `fail_fast` 已中止遍历
+    pub(crate) fn aborted(&self) -> bool {
+        self.aborted.load(Ordering::Relaxed)
+    }
+
+    /// 触发中止的错误
+    pub(crate) fn take_failure(&self) -> Option<ScanError> {
+        lock(&self.first).take()
+    }
+
+    /// 错误总数与按路径排序、截断后的列表
+    pub(crate) fn to_vec(&self) -> (u64, Vec<TraversalError>) {
+        let mut errors = std::mem::take(&mut *lock(&self.errors));
+        sort_and_truncate(&mut errors);
+        (self.count.load(Ordering::Relaxed), errors)
+    }
+}
+
+/// 按路径排序并截断到 [`MAX_RECORDED`] 条（合并结果时同样使用）
+pub(crate) fn sort_and_truncate(errors: &mut Vec<TraversalError>) {
+    errors.sort();
+    errors.truncate(MAX_RECORDED);
+}
@@ -0,0 +1,223 @@
+//! `SystemTime` 的 JSON 表示
+//!
+//! 默认输出 Unix 纪元以来的毫秒数（早于纪元时为负数），前端与脚本可以直接按数字处理；
+//! 面向人阅读的导出可在 [`with_format`] 作用域内改为 RFC 3339 字符串（UTC，毫秒精度）。
+//! 反序列化不受输出格式影响，同时接受毫秒数、RFC 3339 字符串以及旧版本写出的
+//! `{"secs_since_epoch": .., "nanos_since_epoch": ..}` 结构（如已有的索引缓存）。
+//!
+//! 用法：`#[serde(with = "crate::serde_time")]`，可选字段使用 `crate::serde_time::option`。
+
+use std::cell::Cell;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// 时间戳的输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeFormat {
+    /// Unix 纪元毫秒数（整数）
+    #[default]
+    Millis,
+    /// RFC 3339 字符串，如 `2024-05-01T08:30:00.000Z`
+    Rfc3339,
+}
+
+impl FromStr for TimeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "millis" | "ms" | "epoch-millis" => Ok(TimeFormat::Millis),
+            "rfc3339" => Ok(TimeFormat::Rfc3339),
+            _ => Err(format!("未知的时间格式: {}（可选 millis、rfc3339）", s)),
+        }
+    }
+}
+
+impl fmt::Display for TimeFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TimeFormat::Millis => "millis",
+            TimeFormat::Rfc3339 => "rfc3339",
+        })
+    }
+}
+
+thread_local! {
+    static FORMAT: Cell<TimeFormat> = const { Cell::new(TimeFormat::Millis) };
+}
+
+/// 在当前线程上以指定格式执行序列化（作用域结束后恢复原格式）
+pub fn with_format<R>(format: TimeFormat, f: impl FnOnce() -> R) -> R {
+    struct Restore(TimeFormat);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FORMAT.with(|cell| cell.set(self.0));
+        }
+    }
+    let _restore = Restore(FORMAT.with(|cell| cell.replace(format)));
+    f()
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    Millis(i64),
+    Text(String),
+    Legacy { secs_since_epoch: u64, nanos_since_epoch: u32 },
+}
+
+impl Repr {
+    fn into_time<E: serde::de::Error>(self) -> Result<SystemTime, E> {
+        Ok(match self {
+            Repr::Millis(ms) => from_millis(ms),
+            Repr::Text(text) => parse_rfc3339(&text)
+                .ok_or_else(|| E::custom(format!("无法解析的 RFC 3339 时间: {}", text)))?,
+            Repr::Legacy { secs_since_epoch, nanos_since_epoch } => {
+                UNIX_EPOCH + Duration::new(secs_since_epoch, nanos_since_epoch)
+            }
+        })
+    }
+}
+
+/// 转换为纪元毫秒数（超出 i64 范围时饱和）
+pub fn to_millis(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => i64::try_from(after.as_millis()).unwrap_or(i64::MAX),
+        Err(before) => i64::try_from(before.duration().as_millis()).map_or(i64::MIN, |ms| -ms),
+    }
+}
+
+/// 由纪元毫秒数得到时间
+pub fn from_millis(ms: i64) -> SystemTime {
+    if ms >= 0 {
+        UNIX_EPOCH + Duration::from_millis(ms as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_millis(ms.unsigned_abs())
+    }
+}
+
+/// 格式化为 RFC 3339 字符串（UTC，毫秒精度）
+pub fn to_rfc3339(time: SystemTime) -> String {
+    let ms = to_millis(time);
+    let (days, ms_of_day) = (ms.div_euclid(86_400_000), ms.rem_euclid(86_400_000));
+    let (year, month, day) = civil_from_days(days);
+    let secs = ms_of_day / 1000;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        ms_of_day % 1000
+    )
+}
+
+/// 解析 RFC 3339 字符串（`Z` 或 `±HH:MM` 时区，小数秒截断到毫秒）
+pub fn parse_rfc3339(text: &str) -> Option<SystemTime> {
+    let text = text.trim();
+    let (date, rest) = text.split_once(['T', 't', ' '])?;
+    let mut date = date.splitn(3, '-');
+    let year: i64 = date.next()?.parse().ok()?;
+    let month: u32 = date.next()?.parse().ok()?;
+    let day: u32 = date.next()?.parse().ok()?;
+
+    let (clock, offset_minutes) = if let Some(clock) = rest.strip_suffix(['Z', 'z']) {
+        (clock, 0)
+    } else {
+        let at = rest.rfind(['+', '-'])?;
+        let (clock, offset) = rest.split_at(at);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (h, m) = offset[1..].split_once(':')?;
+        (clock, sign * (h.parse::<i64>().ok()? * 60 + m.parse::<i64>().ok()?))
+    };
+    let (clock, fraction) = clock.split_once('.').unwrap_or((clock, ""));
+    let mut clock = clock.splitn(3, ':');
+    let hour: i64 = clock.next()?.parse().ok()?;
+    let minute: i64 = clock.next()?.parse().ok()?;
+    let second: i64 = clock.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let millis = fraction
+        .bytes()
+        .chain(std::iter::repeat(b'0'))
+        .take(3)
+        .fold(0i64, |acc, b| acc * 10 + (b - b'0') as i64);
+
+    let secs = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second
+        - offset_minutes * 60;
+    Some(from_millis(secs * 1000 + millis))
+}
+
+/// 公历日期 → 纪元以来的天数（Howard Hinnant 的 days_from_civil 算法）
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// 纪元以来的天数 → 公历日期
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (yoe + era * 400 + (month <= 2) as i64, month, day)
+}
+
+/// 按当前线程的输出格式序列化的时间值
+struct Formatted(SystemTime);
+
+impl Serialize for Formatted {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match FORMAT.with(Cell::get) {
+            TimeFormat::Millis => serializer.serialize_i64(to_millis(self.0)),
+            TimeFormat::Rfc3339 => serializer.serialize_str(&to_rfc3339(self.0)),
+        }
+    }
+}
+
+pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+    Formatted(*time).serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+    Repr::deserialize(deserializer)?.into_time()
+}
+
+/// `Option<SystemTime>` 字段的序列化（`None` 为 `null`）
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(time: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error> {
+        time.map(Formatted).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<SystemTime>, D::Error> {
+        Option::<Repr>::deserialize(deserializer)?
+            .map(Repr::into_time)
+            .transpose()
+    }
+}
+
+/// 按当前线程的输出格式转换单个时间值（用于手工拼装的 JSON）
+pub fn to_value(time: SystemTime) -> serde_json::Value {
+    serde_json::to_value(Formatted(time)).unwrap_or_default()
+}
@@ -0,0 +1,153 @@
+//! 多进程协同分片扫描
+//!
+//! 针对超大文件系统，可将根目录下的顶层目录划分为多个分片，由多个 surf 进程
+//! （可以位于不同的 NFS 客户端上）分别扫描。各进程通过共享的清单文件协调：
+//! 清单记录分片划分，进程通过在清单旁原子创建认领文件来领取尚未处理的分片，
+//! 最后将各分片的 ScanResult 合并为一个完整结果。
+
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::merge::merge_disjoint;
+use crate::{ScanRequest, ScanResult};
+
+/// 单个进程负责的分片
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShardAssignment {
+    /// 本分片负责的顶层目录名（相对扫描根目录）
+    pub top_level_dirs: BTreeSet<String>,
+    /// 是否负责根目录本身及其直接文件（有且仅有一个分片为 true）
+    pub include_root_files: bool,
+    /// 在清单中的序号（由 [`ShardManifest::assignment`] 填写），合并时据此核对各分片结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+}
+
+impl ShardAssignment {
+    /// 判断顶层目录是否属于本分片
+    pub fn owns_dir(&self, name: &OsStr) -> bool {
+        self.top_level_dirs.contains(name.to_string_lossy().as_ref())
+    }
+}
+
+/// 分片清单：描述根目录的分片划分，供多个进程共享
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShardManifest {
+    /// 扫描根目录
+    pub root_path: PathBuf,
+    /// 每个分片包含的顶层目录名
+    pub shards: Vec<Vec<String>>,
+    /// 各分片及合并结果的 Top N 数量（默认 20）
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl ShardManifest {
+    /// 列出根目录下的顶层目录，按名称排序后轮询分配到 `shard_count` 个分片
+    pub fn plan<P: Into<PathBuf>>(root_path: P, shard_count: usize) -> io::Result<Self> {
+        if shard_count == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "分片数量必须为正整数"));
+        }
+        let root_path = root_path.into();
+        let mut names: Vec<String> = fs::read_dir(&root_path)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+
+        let mut shards = vec![Vec::new(); shard_count];
+        for (i, name) in names.into_iter().enumerate() {
+            shards[i % shard_count].push(name);
+        }
+        Ok(Self { root_path, shards, limit: None })
+    }
+
+    /// 获取第 `index` 个分片的分配；分片 0 额外负责根目录的直接文件
+    pub fn assignment(&self, index: usize) -> Option<ShardAssignment> {
+        self.shards.get(index).map(|dirs| ShardAssignment {
+            top_level_dirs: dirs.iter().cloned().collect(),
+            include_root_files: index == 0,
+            index: Some(index),
+        })
+    }
+
+    /// 构造扫描第 `index` 个分片的请求
+    pub fn request_for(&self, index: usize) -> Option<ScanRequest> {
+        let mut request = ScanRequest::new(&self.root_path);
+        request.shard = Some(self.assignment(index)?);
+        request.limit = self.limit;
+        Some(request)
+    }
+
+    /// 从 JSON 文件加载清单
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read(path)?;
+        serde_json::from_slice(&content).map_err(io::Error::other)
+    }
+
+    /// 将清单保存为 JSON 文件
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// 认领下一个尚未被任何进程认领的分片
+    ///
+    /// 通过在清单文件旁以 `create_new` 方式创建 `<manifest>.shard-<i>.claim` 实现互斥，
+    /// 在共享文件系统上也能保证同一分片只被一个进程领取。所有分片均已被认领时返回 `Ok(None)`。
+    pub fn claim_next(&self, manifest_path: &Path) -> io::Result<Option<usize>> {
+        for index in 0..self.shards.len() {
+            let claim = claim_path(manifest_path, index);
+            match OpenOptions::new().write(true).create_new(true).open(&claim) {
+                Ok(_) => return Ok(Some(index)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// 合并各分片的扫描结果为一个完整的 ScanResult
+    ///
+    /// 结果数量必须与分片数量一致，否则说明仍有分片未完成。各结果按扫描时记录的分片
+    /// （[`ScanSummary::shard`](crate::ScanSummary::shard)）核对：必须来自本清单的同一根目录，
+    /// 与清单中的分配一致，且每个分片恰好出现一次，否则返回错误而不是合并出重复或缺失的统计。
+    pub fn merge(&self, results: Vec<ScanResult>) -> io::Result<ScanResult> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+        if results.len() != self.shards.len() {
+            return Err(invalid(format!("分片结果数量不匹配: 期望 {}，实际 {}", self.shards.len(), results.len())));
+        }
+        let mut seen = vec![false; self.shards.len()];
+        for result in &results {
+            let summary = &result.summary;
+            if summary.root_path != self.root_path {
+                return Err(invalid(format!(
+                    "分片结果的根目录 {} 与清单 {} 不一致",
+                    summary.root_path.display(),
+                    self.root_path.display()
+                )));
+            }
+            let shard = summary.shard.as_ref().ok_or_else(|| invalid("扫描结果不是分片结果".to_string()))?;
+            let index = shard.index.filter(|&i| self.assignment(i).as_ref() == Some(shard));
+            let index = index.ok_or_else(|| invalid(format!("分片结果与清单中的分片不符: {:?}", shard)))?;
+            if std::mem::replace(&mut seen[index], true) {
+                return Err(invalid(format!("分片 {} 的结果重复", index)));
+            }
+        }
+        // 各分片覆盖的顶层目录互不相交，直接求和合并
+        Ok(merge_disjoint(results, self.root_path.clone()))
+    }
+}
+
+/// 分片认领文件路径
+fn claim_path(manifest_path: &Path, index: usize) -> PathBuf {
+    let mut name = manifest_path.as_os_str().to_owned();
+    name.push(format!(".shard-{}.claim", index));
+    PathBuf::from(name)
+}
@@ -0,0 +1,255 @@
+//! 占比计算
+//!
+//! 扫描结果中的文件与目录行统一附带 `share_of_parent`（占所在目录的比例）与
+//! `share_of_total`（占扫描总量的比例），取值为 0~1 的比例并保留 4 位小数，
+//! 各消费端（CLI 表格、TUI、JSON、RPC）直接展示，无需各自实现除法与舍入。
+//!
+//! 目录大小在遍历过程中沿目录链向上累加：每个目录任务只在处理完直接文件后
+//! 累加一次，结果中仅保留被报告文件的祖先目录，内存占用与结果规模成正比。
+//!
+//! 同一遍处理还为目录与文件行填充 `depth`（相对根目录的深度）与 `parent_index`（所在目录在
+//! `directories` 中的下标），`directories` 的下标即目录在本结果中的 id。GUI 与远程客户端据此
+//! 可在 O(n) 内重建目录树，无需解析路径字符串。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::heatmap::{AgeBytes, AGE_BUCKETS};
+use crate::ScanResult;
+
+/// 目录统计行
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DirStat {
+    /// 目录路径
+    pub path: PathBuf,
+    /// 目录（含子目录）中计入统计的文件总大小
+    pub size_bytes: u64,
+    /// 占父目录的比例（父目录不在结果中时为 None）
+    pub share_of_parent: Option<f64>,
+    /// 占扫描总量的比例
+    pub share_of_total: f64,
+    /// 相对根目录的深度（根目录为 0）
+    #[serde(default)]
+    pub depth: usize,
+    /// 父目录在 `directories` 中的下标（父目录不在结果中时为 None）
+    #[serde(default)]
+    pub parent_index: Option<usize>,
+}
+
+/// 计算 `part / whole` 的比例，保留 4 位小数；`whole` 为 0 时返回 0
+pub fn share(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        return 0.0;
+    }
+    (part as f64 / whole as f64 * 10_000.0).round() / 10_000.0
+}
+
+/// 遍历期间的目录节点，持有父节点引用以便向上累加大小
+#[derive(Debug)]
+pub(crate) struct DirNode {
+    path: PathBuf,
+    bytes: AtomicU64,
+    /// 计入统计的文件数（含子目录）
+    files: AtomicU64,
+    /// 已进入的下级目录数（含更深层，不含自身）
+    dirs: AtomicU64,
+    /// 各年龄段的字节数（见 [`crate::heatmap`]）
+    ages: [AtomicU64; AGE_BUCKETS],
+    /// 子树中是否有目录以外的条目或未能遍历的部分（见 [`crate::empty`]）
+    occupied: AtomicBool,
+    parent: Option<Arc<DirNode>>,
+}
+
+impl DirNode {
+    pub(crate) fn root(path: PathBuf) -> Arc<Self> {
+        Arc::new(Self {
+            path,
+            bytes: AtomicU64::new(0),
+            files: AtomicU64::new(0),
+            dirs: AtomicU64::new(0),
+            ages: Default::default(),
+            occupied: AtomicBool::new(false),
+            parent: None,
+        })
+    }
+
+    pub(crate) fn child(self: &Arc<Self>, path: PathBuf) -> Arc<Self> {
+        Arc::new(Self {
+            path,
+            bytes: AtomicU64::new(0),
+            files: AtomicU64::new(0),
+            dirs: AtomicU64::new(0),
+            ages: Default::default(),
+            occupied: AtomicBool::new(false),
+            parent: Some(self.clone()),
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn parent(&self) -> Option<&DirNode> {
+        self.parent.as_deref()
+    }
+
+    /// 标记本目录及全部祖先目录不是空目录（祖先已被标记时提前结束）
+    pub(crate) fn mark_occupied(&self) {
+        let mut node = Some(self);
+        while let Some(current) = node {
+            if current.occupied.swap(true, Ordering::Relaxed) {
+                break;
+            }
+            node = current.parent.as_deref();
+        }
+    }
+
+    pub(crate) fn is_occupied(&self) -> bool {
+        self.occupied.load(Ordering::Relaxed)
+    }
+
+    /// 当前累计的大小（含子目录）
+    pub(crate) fn size(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// 当前累计的文件数（含子目录）
+    pub(crate) fn files(&self) -> u64 {
+        self.files.load(Ordering::Relaxed)
+    }
+
+    /// 当前累计的下级目录数（含更深层）
+    pub(crate) fn dirs(&self) -> u64 {
+        self.dirs.load(Ordering::Relaxed)
+    }
+
+    /// 将 `files` 个文件的各年龄段字节数（及其总和）累加到本目录及全部祖先目录
+    pub(crate) fn add(&self, ages: &AgeBytes, files: u64) {
+        let bytes = ages.total();
+        if bytes == 0 && files == 0 {
+            return;
+        }
+        let mut node = Some(self);
+        while let Some(current) = node {
+            current.bytes.fetch_add(bytes, Ordering::Relaxed);
+            current.files.fetch_add(files, Ordering::Relaxed);
+            for (slot, &age_bytes) in current.ages.iter().zip(&ages.0) {
+                if age_bytes > 0 {
+                    slot.fetch_add(age_bytes, Ordering::Relaxed);
+                }
+            }
+            node = current.parent.as_deref();
+        }
+    }
+
+    /// 把本目录计入全部祖先目录的下级目录数
+    pub(crate) fn count_in_ancestors(&self) {
+        let mut node = self.parent.as_deref();
+        while let Some(current) = node {
+            current.dirs.fetch_add(1, Ordering::Relaxed);
+            node = current.parent.as_deref();
+        }
+    }
+
+    /// 当前累计的各年龄段字节数
+    pub(crate) fn age_bytes(&self) -> AgeBytes {
+        AgeBytes(std::array::from_fn(|i| self.ages[i].load(Ordering::Relaxed)))
+    }
+
+    /// 收集本目录及全部祖先目录的大小
+    pub(crate) fn collect_chain(&self, sizes: &mut HashMap<PathBuf, u64>) {
+        let mut node = Some(self);
+        while let Some(current) = node {
+            if sizes.contains_key(&current.path) {
+                break;
+            }
+            sizes.insert(current.path.clone(), current.bytes.load(Ordering::Relaxed));
+            node = current.parent.as_deref();
+        }
+    }
+}
+
+/// 由目录大小构建按大小降序排列的目录统计行
+pub(crate) fn dir_stats(sizes: HashMap<PathBuf, u64>) -> Vec<DirStat> {
+    let mut dirs: Vec<DirStat> = sizes
+        .into_iter()
+        .map(|(path, size_bytes)| DirStat { path, size_bytes, ..Default::default() })
+        .collect();
+    dirs.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| a.path.cmp(&b.path)));
+    dirs
+}
+
+/// 依据结果中的目录大小与扫描总量填充所有文件/目录行的占比，并填充深度与父目录下标
+pub(crate) fn apply_shares(result: &mut ScanResult) {
+    let total = result.summary.total_size_bytes;
+    let sizes: HashMap<PathBuf, u64> = result
+        .directories
+        .iter()
+        .chain(&result.top_dirs)
+        .map(|d| (d.path.clone(), d.size_bytes))
+        .collect();
+    let parent_share = |path: &Path, size: u64| {
+        path.parent()
+            .and_then(|parent| sizes.get(parent))
+            .map(|&parent_size| share(size, parent_size))
+    };
+
+    for dir in result.directories.iter_mut().chain(result.top_dirs.iter_mut()) {
+        dir.share_of_total = share(dir.size_bytes, total);
+        dir.share_of_parent = parent_share(&dir.path, dir.size_bytes);
+    }
+    for row in &mut result.top_level {
+        row.share_of_total = share(row.size_bytes, total);
+    }
+    for bucket in &mut result.age_histogram {
+        bucket.share_of_total = share(bucket.size_bytes, total);
+    }
+    for row in &mut result.by_category {
+        row.share_of_total = share(row.total_size_bytes, total);
+    }
+    let files = result
+        .top_files
+        .iter_mut()
+        .chain(result.stale_files.iter_mut())
+        .chain(result.redundant_files.iter_mut())
+        .chain(result.oldest_files.iter_mut())
+        .chain(result.newest_files.iter_mut())
+        .chain(result.by_extension.iter_mut().flat_map(|stat| stat.top_files.iter_mut()));
+    for file in files {
+        file.share_of_total = Some(share(file.size_bytes, total));
+        file.share_of_parent = parent_share(&file.path, file.size_bytes);
+    }
+    link_tree(result);
+}
+
+/// 填充目录与文件行的深度及其父目录在 `directories` 中的下标
+fn link_tree(result: &mut ScanResult) {
+    let root = result.summary.root_path.clone();
+    let index: HashMap<PathBuf, usize> =
+        result.directories.iter().enumerate().map(|(i, d)| (d.path.clone(), i)).collect();
+    let depth = |path: &Path| path.strip_prefix(&root).map_or(0, |rel| rel.components().count());
+    let parent_index = |path: &Path| path.parent().and_then(|parent| index.get(parent)).copied();
+
+    for dir in result.directories.iter_mut().chain(result.top_dirs.iter_mut()) {
+        dir.depth = depth(&dir.path);
+        dir.parent_index = parent_index(&dir.path);
+    }
+    let files = result
+        .top_files
+        .iter_mut()
+        .chain(result.stale_files.iter_mut())
+        .chain(result.suspicious_files.iter_mut())
+        .chain(result.redundant_files.iter_mut())
+        .chain(result.oldest_files.iter_mut())
+        .chain(result.newest_files.iter_mut())
+        .chain(result.by_extension.iter_mut().flat_map(|stat| stat.top_files.iter_mut()));
+    for file in files {
+        file.depth = Some(depth(&file.path));
+        file.parent_index = parent_index(&file.path);
+    }
+}
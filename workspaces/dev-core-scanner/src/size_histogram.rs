@@ -0,0 +1,243 @@
+//! 按扩展名或类别的文件大小分布
+//!
+//! `by_extension` 只给出每个扩展名的总量，规划容量时还需要知道这些空间由多少个多大的文件构成：
+//! 同样是 80 GB 的 jpg，几万张 3 MB 的照片与几个 20 GB 的导出文件，去重与压缩的收益、合适的
+//! `min_size` 阈值都完全不同。请求 `size_histograms` 时，扫描为每个选择器（扩展名，或以
+//! `category:` 开头的类别名，如 `category:video`）统计对数刻度的大小分布：以 1 KiB 为起点、
+//! 每段 4 倍，共 [`SIZE_BUCKETS`] 段，给出各段的文件数、总大小与占该选择器总量的比例。
+//!
+//! 分布在遍历时按文件累计，不需要保留文件条目，仅汇总扫描同样完整；与 `by_extension` 一样只统计
+//! 通过过滤与 `min_size` 的文件，因此用于选择 `min_size` 时应以 `min_size` 为 0 的扫描为准。
+//! [`to_csv`] 把分布导出为每段一行的 CSV，便于在表格软件中建模。
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::FileCategory;
+
+/// 大小分段数量
+pub const SIZE_BUCKETS: usize = 14;
+
+/// 第一段的上限（字节，不含）；之后每段上限为前一段的 4 倍，最后一段无上限
+const FIRST_BUCKET_MAX: u64 = 1024;
+
+/// 类别选择器的前缀
+const CATEGORY_PREFIX: &str = "category:";
+
+/// 一个选择器的大小分布
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SizeHistogram {
+    /// 选择器（规范化后的扩展名，或 `category:<类别名>`）
+    pub selector: String,
+    /// 匹配的文件数
+    pub file_count: u64,
+    /// 匹配文件的总大小（字节）
+    pub size_bytes: u64,
+    /// 按大小从小到大的全部分段
+    pub buckets: Vec<SizeBucket>,
+}
+
+/// 大小分布中的一段
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SizeBucket {
+    /// 大小下限（字节，含）
+    pub min_bytes: u64,
+    /// 大小上限（字节，不含）；最后一段为 None
+    pub max_bytes: Option<u64>,
+    /// 文件数
+    pub file_count: u64,
+    /// 总大小（字节）
+    pub size_bytes: u64,
+    /// 占该选择器总大小的比例（保留 4 位小数）
+    pub share_of_selector: f64,
+}
+
+/// 解析后的选择器
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Selector {
+    Extension(String),
+    Category(FileCategory),
+}
+
+impl Selector {
+    /// 扩展名不区分大小写、可带前导点；类别名须为 [`FileCategory::name`] 之一
+    fn parse(text: &str) -> io::Result<Self> {
+        let text = text.trim();
+        if let Some(name) = text.strip_prefix(CATEGORY_PREFIX) {
+            return all_categories()
+                .find(|category| category.name() == name)
+                .map(Selector::Category)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("未知的文件类别: {}", name)));
+        }
+        let extension = text.trim_start_matches('.').to_lowercase();
+        if extension.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "大小分布的选择器不能为空"));
+        }
+        Ok(Selector::Extension(extension))
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Selector::Extension(extension) => extension.clone(),
+            Selector::Category(category) => format!("{}{}", CATEGORY_PREFIX, category.name()),
+        }
+    }
+
+    fn matches(&self, extension: &str, category: FileCategory) -> bool {
+        match self {
+            Selector::Extension(wanted) => wanted == extension,
+            Selector::Category(wanted) => *wanted == category,
+        }
+    }
+}
+
+fn all_categories() -> impl Iterator<Item = FileCategory> {
+    [
+        FileCategory::Image,
+        FileCategory::Video,
+        FileCategory::Audio,
+        FileCategory::Document,
+        FileCategory::Code,
+        FileCategory::Archive,
+        FileCategory::DiskImage,
+        FileCategory::Executable,
+        FileCategory::Other,
+    ]
+    .into_iter()
+}
+
+/// 文件大小所在的分段
+fn bucket_of(size: u64) -> usize {
+    let mut max = FIRST_BUCKET_MAX;
+    for index in 0..SIZE_BUCKETS - 1 {
+        if size < max {
+            return index;
+        }
+        max = max.saturating_mul(4);
+    }
+    SIZE_BUCKETS - 1
+}
+
+/// 分段的 (下限, 上限)
+fn bounds(index: usize) -> (u64, Option<u64>) {
+    let max = |i: usize| FIRST_BUCKET_MAX << (2 * i);
+    let min = index.checked_sub(1).map_or(0, max);
+    (min, (index < SIZE_BUCKETS - 1).then(|| max(index)))
+}
+
+/// 全部遍历任务共享的大小分布计数
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    selectors: Vec<Selector>,
+    files: Vec<[AtomicU64; SIZE_BUCKETS]>,
+    bytes: Vec<[AtomicU64; SIZE_BUCKETS]>,
+}
+
+impl Counters {
+    /// 按请求的选择器建立计数（重复的选择器只统计一次）；选择器非法时返回 `InvalidInput` 错误
+    pub(crate) fn new(selectors: &[String]) -> io::Result<Self> {
+        let mut parsed: Vec<Selector> = Vec::new();
+        for text in selectors {
+            let selector = Selector::parse(text)?;
+            if !parsed.contains(&selector) {
+                parsed.push(selector);
+            }
+        }
+        Ok(Self {
+            files: parsed.iter().map(|_| Default::default()).collect(),
+            bytes: parsed.iter().map(|_| Default::default()).collect(),
+            selectors: parsed,
+        })
+    }
+
+    /// 记录一个文件（扩展名与 `by_extension` 的统计口径相同）
+    pub(crate) fn add(&self, extension: &str, size: u64) {
+        if self.selectors.is_empty() {
+            return;
+        }
+        let category = FileCategory::of_extension(extension);
+        let bucket = bucket_of(size);
+        for (i, selector) in self.selectors.iter().enumerate() {
+            if selector.matches(extension, category) {
+                self.files[i][bucket].fetch_add(1, Ordering::Relaxed);
+                self.bytes[i][bucket].fetch_add(size, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// 按请求顺序生成各选择器的分布
+    pub(crate) fn to_vec(&self) -> Vec<SizeHistogram> {
+        self.selectors
+            .iter()
+            .enumerate()
+            .map(|(i, selector)| {
+                let load = |counts: &[AtomicU64; SIZE_BUCKETS]| counts.each_ref().map(|c| c.load(Ordering::Relaxed));
+                histogram(selector.name(), load(&self.files[i]), load(&self.bytes[i]))
+            })
+            .collect()
+    }
+}
+
+fn histogram(selector: String, files: [u64; SIZE_BUCKETS], bytes: [u64; SIZE_BUCKETS]) -> SizeHistogram {
+    let size_bytes: u64 = bytes.iter().sum();
+    let buckets = (0..SIZE_BUCKETS)
+        .map(|i| {
+            let (min_bytes, max_bytes) = bounds(i);
+            let share = if size_bytes == 0 { 0.0 } else { bytes[i] as f64 / size_bytes as f64 };
+            SizeBucket {
+                min_bytes,
+                max_bytes,
+                file_count: files[i],
+                size_bytes: bytes[i],
+                share_of_selector: (share * 10_000.0).round() / 10_000.0,
+            }
+        })
+        .collect();
+    SizeHistogram { selector, file_count: files.iter().sum(), size_bytes, buckets }
+}
+
+/// 合并多份结果的大小分布（按选择器与分段下限累加，保持首次出现的顺序）
+pub(crate) fn merge(histograms: impl IntoIterator<Item = SizeHistogram>) -> Vec<SizeHistogram> {
+    let mut order = Vec::new();
+    let mut sums: HashMap<String, ([u64; SIZE_BUCKETS], [u64; SIZE_BUCKETS])> = HashMap::new();
+    for histogram in histograms {
+        let slot = sums.entry(histogram.selector.clone()).or_insert_with(|| {
+            order.push(histogram.selector.clone());
+            Default::default()
+        });
+        for bucket in histogram.buckets {
+            let i = bucket_of(bucket.min_bytes);
+            slot.0[i] += bucket.file_count;
+            slot.1[i] += bucket.size_bytes;
+        }
+    }
+    order
+        .into_iter()
+        .map(|selector| {
+            let (files, bytes) = sums[&selector];
+            histogram(selector, files, bytes)
+        })
+        .collect()
+}
+
+/// 导出为 CSV：表头加每个选择器的每段一行（`max_bytes` 为空表示无上限）
+pub fn to_csv(histograms: &[SizeHistogram]) -> String {
+    let mut csv = String::from("selector,min_bytes,max_bytes,file_count,size_bytes,share_of_selector\n");
+    for histogram in histograms {
+        for bucket in &histogram.buckets {
+            let max = bucket.max_bytes.map(|max| max.to_string()).unwrap_or_default();
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                histogram.selector, bucket.min_bytes, max, bucket.file_count, bucket.size_bytes, bucket.share_of_selector
+            );
+        }
+    }
+    csv
+}
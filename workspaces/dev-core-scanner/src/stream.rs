@@ -0,0 +1,77 @@
+//! 流式遍历文件条目
+//!
+//! [`Scanner::scan_iter`] 在后台线程中扫描，遍历线程每处理完一个文件（经过过滤与 `min_size`
+//! 之后，与发送给分析器插件的条目相同）就把条目送入容量有限的队列，调用方按发现顺序逐个读取，
+//! 无需等待扫描结束，也不必在内存中保留全部结果。后台扫描以仅汇总模式运行（不保留 Top N
+//! 等文件列表），队列满时遍历线程等待调用方消费。条目中的占比、深度等需要全局信息的字段为空。
+//!
+//! 迭代器被提前丢弃时取消后台扫描；读取完毕后可通过 [`ScanIter::finish`] 取得扫描摘要与错误。
+//!
+//! [`Scanner::scan_iter`]: crate::Scanner::scan_iter
+
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread::JoinHandle;
+
+use crate::{CancellationToken, FileEntry, ScanError, ScanRequest, ScanResult, Scanner};
+
+/// 条目队列容量
+const STREAM_QUEUE_CAPACITY: usize = 1024;
+
+/// 遍历线程向迭代器发送条目的一端（通过请求传入扫描）
+#[derive(Debug, Clone)]
+pub(crate) struct EntrySink(SyncSender<FileEntry>);
+
+impl EntrySink {
+    /// 发送条目；迭代器已被丢弃时忽略（扫描随后会被取消）
+    pub(crate) fn send(&self, entry: &FileEntry) {
+        let _ = self.0.send(entry.clone());
+    }
+}
+
+/// 按发现顺序产出文件条目的迭代器，见 [模块文档](self)
+#[derive(Debug)]
+pub struct ScanIter {
+    receiver: Receiver<FileEntry>,
+    cancel: CancellationToken,
+    handle: Option<JoinHandle<Result<ScanResult, ScanError>>>,
+}
+
+impl ScanIter {
+    pub(crate) fn start(request: &ScanRequest) -> Self {
+        let mut request = request.clone();
+        let (sender, receiver) = sync_channel(STREAM_QUEUE_CAPACITY);
+        request.entry_sink = Some(EntrySink(sender));
+        request.collect_entries = false;
+        // 增量扫描复用的子树不会重新遍历，无法产出其中的条目
+        request.incremental = false;
+        let cancel = request.cancel.get_or_insert_with(CancellationToken::new).clone();
+        // 扫描结束时请求随线程一同释放，队列的发送端随之关闭，迭代结束
+        let handle = std::thread::spawn(move || Scanner::new().scan_sync(&request));
+        Self { receiver, cancel, handle: Some(handle) }
+    }
+
+    /// 读取剩余条目（丢弃）直到扫描结束，返回仅汇总模式的扫描结果
+    pub fn finish(mut self) -> Result<ScanResult, ScanError> {
+        self.receiver.iter().for_each(drop);
+        match self.handle.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            _ => Err(ScanError::ThreadPool { message: "扫描线程异常退出".to_string() }),
+        }
+    }
+}
+
+impl Iterator for ScanIter {
+    type Item = FileEntry;
+
+    fn next(&mut self) -> Option<FileEntry> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl Drop for ScanIter {
+    fn drop(&mut self) {
+        if self.handle.is_some() {
+            self.cancel.cancel();
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! 符号链接处理策略
+//!
+//! 默认不跟随符号链接（与 `du` 一致）：指向文件或目录的链接都按链接本身计为一个文件，
+//! 大小为链接自身的大小。需要统计链接目标时可选择只跟随目录链接，或同时跟随文件链接。
+//!
+//! 跟随目录链接时，链接可能指回祖先目录形成环路，或多条路径指向同一目录导致重复计数。
+//! 不跟随链接时，bind mount 同样会让同一棵物理目录树出现在多个路径下。遍历器按（设备号, inode）
+//! 记录已进入的目录，同一目录只统计一次，后续访问被跳过，计入 `diagnostics.revisited_dirs`，
+//! 并在 `diagnostics.aliased_dirs` 中列出被跳过的路径及其先被统计的路径（仅汇总扫描不记录后者，
+//! 只保存目录标识以控制内存）。并行遍历时哪条路径先被统计取决于调度顺序。
+//!
+//! Unix 上始终按（设备号, inode）检测；其他平台没有廉价的目录标识，只在跟随目录链接时按规范化路径检测。
+//!
+//! Windows 上的 NTFS 联接点（junction）与目录符号链接一样是名称代理型重解析点，按同一策略处理：
+//! 默认不进入、按自身计为文件，跟随目录链接时进入并参与上述重复检测，因此联接点环路不会被反复遍历。
+//! 其他重解析点（如云同步占位文件、重复数据删除文件）按普通文件或目录统计。
+//! 未被跟随的链接在 `FileEntry::link_target` 中记录其目标。
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+/// 符号链接跟随策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkPolicy {
+    /// 不跟随：链接按自身计为文件
+    #[default]
+    Never,
+    /// 跟随指向目录的链接；指向文件的链接仍按自身计为文件
+    FollowDirs,
+    /// 跟随全部链接：目录链接进入遍历，文件链接按目标文件统计大小与修改时间
+    FollowAll,
+}
+
+impl SymlinkPolicy {
+    /// 是否进入指向目录的链接
+    pub fn follows_dirs(self) -> bool {
+        !matches!(self, SymlinkPolicy::Never)
+    }
+
+    /// 文件链接是否按目标文件统计
+    pub fn follows_files(self) -> bool {
+        matches!(self, SymlinkPolicy::FollowAll)
+    }
+}
+
+impl FromStr for SymlinkPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "never" => Ok(SymlinkPolicy::Never),
+            "dirs" | "follow_dirs" => Ok(SymlinkPolicy::FollowDirs),
+            "all" | "follow_all" => Ok(SymlinkPolicy::FollowAll),
+            _ => Err(format!("未知的符号链接策略: {}（可选 never、dirs、all）", s)),
+        }
+    }
+}
+
+impl fmt::Display for SymlinkPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SymlinkPolicy::Never => "never",
+            SymlinkPolicy::FollowDirs => "dirs",
+            SymlinkPolicy::FollowAll => "all",
+        })
+    }
+}
+
+/// 目录的唯一标识：Unix 上为（设备号, inode），其他平台退化为规范化路径
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum DirId {
+    #[cfg(unix)]
+    Inode(u64, u64),
+    #[cfg(not(unix))]
+    Path(std::path::PathBuf),
+}
+
+impl DirId {
+    #[cfg(unix)]
+    fn of(dir: &Path) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        let meta = fs::metadata(dir).ok()?;
+        Some(DirId::Inode(meta.dev(), meta.ino()))
+    }
+
+    #[cfg(not(unix))]
+    fn of(dir: &Path) -> Option<Self> {
+        fs::canonicalize(crate::long_path::extended(dir)).ok().map(DirId::Path)
+    }
+}
+
+/// 读取链接（或联接点）的目标，去掉 Windows 上的 `\\?\` 前缀；读取失败时返回 None
+pub(crate) fn read_target(link: &Path) -> Option<PathBuf> {
+    let target = fs::read_link(link).ok()?;
+    Some(crate::long_path::strip_extended(&target).into_owned())
+}
+
+/// 以另一路径重复出现而被跳过的目录
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DirAlias {
+    /// 被跳过的路径
+    pub path: PathBuf,
+    /// 同一目录先被统计的路径（仅汇总扫描时不记录）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias_of: Option<PathBuf>,
+}
+
+/// 已进入的目录：目录标识 -> 首次进入时的路径（`keep_paths` 为 false 时不保存路径）
+#[derive(Debug, Default)]
+pub(crate) struct VisitedDirs {
+    dirs: Mutex<HashMap<DirId, Option<PathBuf>>>,
+    keep_paths: bool,
+}
+
+impl VisitedDirs {
+    /// 按平台与链接策略创建：Unix 上始终启用，其他平台仅在跟随目录链接时启用
+    pub(crate) fn for_policy(policy: SymlinkPolicy, keep_paths: bool) -> Option<Self> {
+        (cfg!(unix) || policy.follows_dirs()).then(|| Self { dirs: Mutex::default(), keep_paths })
+    }
+
+    /// 登记目录；已进入过（环路、重复链接或 bind mount）时返回 `Some(先进入的路径)`。
+    /// 无法读取标识的目录视为首次进入
+    pub(crate) fn visit(&self, dir: &Path) -> Option<Option<PathBuf>> {
+        let id = DirId::of(dir)?;
+        match crate::lock(&self.dirs).entry(id) {
+            Entry::Occupied(first) => Some(first.get().clone()),
+            Entry::Vacant(slot) => {
+                slot.insert(self.keep_paths.then(|| dir.to_path_buf()));
+                None
+            }
+        }
+    }
+}
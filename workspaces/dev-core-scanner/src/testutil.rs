@@ -0,0 +1,307 @@
+//! 确定性合成目录树生成器与故障注入（`testutil` feature）
+//!
+//! `TreeSpec` 描述一棵目录树的形状：层数、每层子目录数、每个目录的文件数、
+//! 文件大小范围、扩展名、符号链接与无权限目录数量。同一份 spec（含随机种子）
+//! 在任何机器上生成完全相同的目录树，可序列化为 JSON 在问题报告中分享，
+//! 供单元测试、基准测试与 `surf selftest --generate` 复现性能/正确性问题。
+//!
+//! `FaultyFs` 是 [`FsProvider`] 的测试替身，按名称让指定目录读取失败、指定文件视为云端占位文件
+//! 或在处理时 panic，用于覆盖真实文件系统上难以制造的情形。
+
+use std::ffi::OsString;
+use std::fs::{self, Metadata, ReadDir};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::fs_provider::{FsProvider, RealFs};
+
+use serde::{Deserialize, Serialize};
+
+/// 合成目录树规格
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TreeSpec {
+    /// 随机种子：决定文件大小与扩展名的分配
+    pub seed: u64,
+    /// 目录层数（根目录之下）
+    pub depth: usize,
+    /// 每个目录的子目录数
+    pub fanout: usize,
+    /// 每个目录的文件数
+    pub files_per_dir: usize,
+    /// 文件大小下限（字节，含）
+    pub min_file_size: u64,
+    /// 文件大小上限（字节，含）
+    pub max_file_size: u64,
+    /// 文件扩展名（不含点），空字符串表示无扩展名
+    pub extensions: Vec<String>,
+    /// 指向已生成文件的符号链接数量（仅 Unix）
+    pub symlinks: usize,
+    /// 生成后移除全部权限的叶子目录数量（仅 Unix）
+    pub denied_dirs: usize,
+}
+
+impl Default for TreeSpec {
+    fn default() -> Self {
+        Self {
+            seed: 42,
+            depth: 2,
+            fanout: 3,
+            files_per_dir: 5,
+            min_file_size: 1,
+            max_file_size: 4096,
+            extensions: ["txt", "log", "bin", "mp4"].iter().map(|s| s.to_string()).collect(),
+            symlinks: 0,
+            denied_dirs: 0,
+        }
+    }
+}
+
+/// 生成结果：可供断言使用的预期统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct GeneratedTree {
+    /// 生成的目录数（含根目录）
+    pub dirs: u64,
+    /// 生成的普通文件数
+    pub files: u64,
+    /// 普通文件总大小
+    pub total_bytes: u64,
+    /// 生成的符号链接路径
+    pub symlinks: Vec<PathBuf>,
+    /// 被移除权限的叶子目录路径（测试结束后需调用 [`GeneratedTree::restore_permissions`] 才能删除）
+    pub denied_dirs: Vec<PathBuf>,
+    /// 位于无权限目录中的文件数
+    pub denied_files: u64,
+    /// 位于无权限目录中的文件总大小
+    pub denied_bytes: u64,
+}
+
+impl TreeSpec {
+    /// 以默认规格创建
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置随机种子
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// 设置目录层数
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// 设置每个目录的子目录数
+    pub fn fanout(mut self, fanout: usize) -> Self {
+        self.fanout = fanout;
+        self
+    }
+
+    /// 设置每个目录的文件数
+    pub fn files_per_dir(mut self, files_per_dir: usize) -> Self {
+        self.files_per_dir = files_per_dir;
+        self
+    }
+
+    /// 设置文件大小范围（含两端）
+    pub fn file_sizes(mut self, min: u64, max: u64) -> Self {
+        self.min_file_size = min;
+        self.max_file_size = max.max(min);
+        self
+    }
+
+    /// 设置扩展名集合
+    pub fn extensions<I, S>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extensions = extensions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 设置符号链接数量
+    pub fn symlinks(mut self, symlinks: usize) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// 设置无权限目录数量
+    pub fn denied_dirs(mut self, denied_dirs: usize) -> Self {
+        self.denied_dirs = denied_dirs;
+        self
+    }
+
+    /// 从 JSON 文件加载规格（缺失字段取默认值）
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read(path)?;
+        serde_json::from_slice(&content).map_err(io::Error::other)
+    }
+
+    /// 在 `root` 下生成目录树；`root` 不存在时自动创建
+    pub fn generate(&self, root: &Path) -> io::Result<GeneratedTree> {
+        fs::create_dir_all(root)?;
+        let mut rng = SplitMix64(self.seed);
+        let mut tree = GeneratedTree::default();
+        let mut files = Vec::new();
+        let mut leaves = Vec::new();
+        self.generate_dir(root, 0, &mut rng, &mut tree, &mut files, &mut leaves)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            for i in 0..self.symlinks.min(files.len()) {
+                let (target, _) = &files[rng.below(files.len() as u64) as usize];
+                let link = root.join(format!("link{}", i));
+                std::os::unix::fs::symlink(target, &link)?;
+                tree.symlinks.push(link);
+            }
+
+            // 只选取叶子目录，无权限目录之间不会相互嵌套，也不会遮蔽其他子目录
+            for dir in leaves.iter().rev().take(self.denied_dirs) {
+                for (path, size) in &files {
+                    if path.starts_with(dir) {
+                        tree.denied_files += 1;
+                        tree.denied_bytes += size;
+                    }
+                }
+                fs::set_permissions(dir, fs::Permissions::from_mode(0o000))?;
+                tree.denied_dirs.push(dir.clone());
+            }
+        }
+        Ok(tree)
+    }
+
+    fn generate_dir(
+        &self,
+        dir: &Path,
+        level: usize,
+        rng: &mut SplitMix64,
+        tree: &mut GeneratedTree,
+        files: &mut Vec<(PathBuf, u64)>,
+        leaves: &mut Vec<PathBuf>,
+    ) -> io::Result<()> {
+        tree.dirs += 1;
+        for i in 0..self.files_per_dir {
+            let span = self.max_file_size.saturating_sub(self.min_file_size);
+            let size = self.min_file_size + rng.below(span.saturating_add(1));
+            let name = match self.extensions.get(rng.below(self.extensions.len().max(1) as u64) as usize) {
+                Some(ext) if !ext.is_empty() => format!("file{}.{}", i, ext),
+                _ => format!("file{}", i),
+            };
+            let path = dir.join(name);
+            fs::File::create(&path)?.set_len(size)?;
+            tree.files += 1;
+            tree.total_bytes += size;
+            files.push((path, size));
+        }
+        if level < self.depth {
+            for i in 0..self.fanout {
+                let child = dir.join(format!("dir{}", i));
+                fs::create_dir(&child)?;
+                if level + 1 == self.depth {
+                    leaves.push(child.clone());
+                }
+                self.generate_dir(&child, level + 1, rng, tree, files, leaves)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GeneratedTree {
+    /// 恢复无权限目录的权限，使临时目录可以被正常删除
+    pub fn restore_permissions(&self) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            for dir in &self.denied_dirs {
+                fs::set_permissions(dir, fs::Permissions::from_mode(0o755))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 注入故障的文件系统：按文件名（不含路径）匹配，其余操作交给 [`RealFs`]
+#[derive(Debug, Clone, Default)]
+pub struct FaultyFs {
+    /// 读取时返回权限错误的目录名
+    pub unreadable_dirs: Vec<OsString>,
+    /// 视为云端占位文件的文件名
+    pub placeholders: Vec<OsString>,
+    /// 处理时触发 panic 的文件名
+    pub panics: Vec<OsString>,
+}
+
+impl FaultyFs {
+    /// 不注入任何故障
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 读取该名称的目录时返回权限错误（以 root 运行测试时 chmod 无法制造读取失败）
+    pub fn unreadable_dir(mut self, name: impl Into<OsString>) -> Self {
+        self.unreadable_dirs.push(name.into());
+        self
+    }
+
+    /// 该名称的文件视为云端占位文件（测试平台上无法制造真实的占位文件）
+    pub fn placeholder(mut self, name: impl Into<OsString>) -> Self {
+        self.placeholders.push(name.into());
+        self
+    }
+
+    /// 处理该名称的文件时触发 panic
+    pub fn panic_on(mut self, name: impl Into<OsString>) -> Self {
+        self.panics.push(name.into());
+        self
+    }
+}
+
+fn named(path: &Path, names: &[OsString]) -> bool {
+    path.file_name().is_some_and(|name| names.iter().any(|n| n.as_os_str() == name))
+}
+
+impl FsProvider for FaultyFs {
+    fn read_dir(&self, dir: &Path) -> io::Result<ReadDir> {
+        if named(dir, &self.unreadable_dirs) {
+            return Err(io::Error::from(io::ErrorKind::PermissionDenied));
+        }
+        RealFs.read_dir(dir)
+    }
+
+    fn is_placeholder(&self, path: &Path, metadata: &Metadata) -> bool {
+        // 遍历为每个文件判断一次占位状态，在此模拟处理文件时的 panic
+        if named(path, &self.panics) {
+            panic!("injected panic for {}", path.display());
+        }
+        named(path, &self.placeholders) || RealFs.is_placeholder(path, metadata)
+    }
+}
+
+/// 确定性伪随机数生成器（SplitMix64），保证同一种子在各平台生成相同序列
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// 返回 `[0, bound)` 内的随机数；`bound` 为 0 时返回 0
+    fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return 0;
+        }
+        self.next() % bound
+    }
+}
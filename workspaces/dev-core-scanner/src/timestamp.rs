@@ -0,0 +1,43 @@
+//! 可疑时间戳的识别与校正
+//!
+//! 来自有问题的压缩包或时钟错误的设备的文件，修改时间可能早于 1980 年（常见为纪元零点）
+//! 或远在未来。这类时间戳会让年龄热力图与陈旧判断失真：未来的时间无法计算年龄，
+//! 纪元零点则被当作“极旧”数据。扫描时把修改时间校正到合理范围内再参与年龄分析，
+//! 文件条目中仍保留原始值；可疑文件的数量计入 `summary.suspicious_timestamps`，
+//! 请求 `list_suspicious_timestamps` 时偏差最大的文件列在 `suspicious_files` 中供用户修复。
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 合理修改时间的下限（Unix 秒）：1980-01-01T00:00:00Z，FAT/ZIP 时间戳可表示的最早时间
+pub const MIN_PLAUSIBLE_SECS: u64 = 315_532_800;
+
+/// 晚于当前时间的容差：扫描期间写入的文件与轻微的时钟偏差不视为可疑
+pub const FUTURE_TOLERANCE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 合理修改时间的下限
+pub fn min_plausible() -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(MIN_PLAUSIBLE_SECS)
+}
+
+/// 修改时间超出合理范围 [1980-01-01, `now` + [`FUTURE_TOLERANCE`]] 的偏差；在范围内时返回 None
+pub fn deviation(modified: SystemTime, now: SystemTime) -> Option<Duration> {
+    if let Ok(before) = min_plausible().duration_since(modified) {
+        return (!before.is_zero()).then_some(before);
+    }
+    modified
+        .duration_since(now + FUTURE_TOLERANCE)
+        .ok()
+        .filter(|after| !after.is_zero())
+}
+
+/// 校正后的修改时间：早于下限的取下限，晚于当前时间的取当前时间
+pub fn clamp(modified: SystemTime, now: SystemTime) -> SystemTime {
+    let min = min_plausible();
+    modified.max(min).min(now.max(min))
+}
+
+/// 按偏差降序排列可疑文件（偏差相同时按路径升序）
+pub(crate) fn sort_by_deviation(entries: &mut [crate::FileEntry], now: SystemTime) {
+    let key = |entry: &crate::FileEntry| entry.last_modified.and_then(|m| deviation(m, now)).unwrap_or_default();
+    entries.sort_by(|a, b| key(b).cmp(&key(a)).then_with(|| a.path.cmp(&b.path)));
+}
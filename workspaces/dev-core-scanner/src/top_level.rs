@@ -0,0 +1,87 @@
+//! 顶层目录速览
+//!
+//! “空间都去哪了”最常见的回答方式是看根目录下每个直接子项各占多少。扫描时为根目录的每个直接子目录
+//! 累计递归大小、文件数与目录数（随目录节点向上累加，不需要保留任何文件条目），根目录自身的直接文件
+//! 汇总为一行，结果中的 `top_level` 按大小降序排列，与 `summary_only` 扫描一起使用时同样可用。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::share::DirNode;
+
+/// 顶层速览中的一行：根目录的一个直接子目录，或根目录自身的直接文件
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TopLevelStat {
+    /// 子目录路径（`root_files` 为 true 时为根目录本身）
+    pub path: PathBuf,
+    /// 递归大小（字节）
+    pub size_bytes: u64,
+    /// 计入统计的文件数（含子目录）
+    pub file_count: u64,
+    /// 其下的目录数（含更深层，不含自身）
+    pub dir_count: u64,
+    /// 占扫描总量的比例
+    pub share_of_total: f64,
+    /// 是否为根目录直接文件的汇总行
+    #[serde(default)]
+    pub root_files: bool,
+}
+
+/// 由根目录节点及其直接子目录节点生成速览行（按大小降序）；根目录没有直接文件时不生成汇总行
+pub(crate) fn rows(root: &DirNode, children: &[Arc<DirNode>]) -> Vec<TopLevelStat> {
+    let mut rows: Vec<TopLevelStat> = children
+        .iter()
+        .map(|child| TopLevelStat {
+            path: child.path().to_path_buf(),
+            size_bytes: child.size(),
+            file_count: child.files(),
+            dir_count: child.dirs(),
+            ..Default::default()
+        })
+        .collect();
+    let direct_files = root.files().saturating_sub(rows.iter().map(|r| r.file_count).sum());
+    let direct_bytes = root.size().saturating_sub(rows.iter().map(|r| r.size_bytes).sum());
+    if direct_files > 0 || direct_bytes > 0 {
+        rows.push(TopLevelStat {
+            path: root.path().to_path_buf(),
+            size_bytes: direct_bytes,
+            file_count: direct_files,
+            root_files: true,
+            ..Default::default()
+        });
+    }
+    sort(&mut rows);
+    rows
+}
+
+/// 按大小降序、路径升序排列
+pub(crate) fn sort(rows: &mut [TopLevelStat]) {
+    rows.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| a.path.cmp(&b.path)));
+}
+
+/// `path` 所在的 `root` 直接子目录（`path` 不在 `root` 之下或就是 `root` 时为 None）
+pub(crate) fn top_child(root: &Path, path: &Path) -> Option<PathBuf> {
+    let first = path.strip_prefix(root).ok()?.components().next()?;
+    Some(root.join(first))
+}
+
+/// 合并同一根目录下的速览行（分片扫描或多个子树的结果），同一路径的行累加
+pub(crate) fn merge(rows: impl IntoIterator<Item = TopLevelStat>) -> Vec<TopLevelStat> {
+    let mut by_path: HashMap<PathBuf, TopLevelStat> = HashMap::new();
+    for row in rows {
+        let merged = by_path
+            .entry(row.path.clone())
+            .or_insert_with(|| TopLevelStat { path: row.path.clone(), ..Default::default() });
+        merged.size_bytes += row.size_bytes;
+        merged.file_count += row.file_count;
+        merged.dir_count += row.dir_count;
+        merged.root_files |= row.root_files;
+    }
+    let mut merged: Vec<TopLevelStat> = by_path.into_values().collect();
+    sort(&mut merged);
+    merged
+}
@@ -0,0 +1,44 @@
+//! 按工作线程划分的统计收集器
+//!
+//! 遍历的热路径上每个文件都要更新 Top N 堆、陈旧文件堆与扩展名表。所有线程共用一份时，
+//! 这些更新经同一把锁串行执行，线程越多争用越严重。[`PerWorker`] 为线程池中的每个工作线程
+//! 保留一个独立的槽位，线程只访问自己的槽位（按 `rayon::current_thread_index` 选取），
+//! 槽位上的锁因此从不发生争用；遍历结束后再由 [`PerWorker::merge`] 把各槽位合并为一份。
+//!
+//! 线程池之外的线程（如调用 `scan_sync` 的线程）共用最后一个槽位。
+
+use std::sync::{Mutex, MutexGuard};
+
+/// 独占一条缓存行的槽位，避免相邻槽位因伪共享互相拖慢
+#[derive(Default)]
+#[repr(align(128))]
+struct Slot<T>(Mutex<T>);
+
+/// 每个工作线程一份的收集器
+pub(crate) struct PerWorker<T> {
+    slots: Vec<Slot<T>>,
+}
+
+impl<T: Default> PerWorker<T> {
+    /// 为 `workers` 个工作线程创建槽位（另加一个供线程池之外的线程使用）
+    pub(crate) fn new(workers: usize) -> Self {
+        Self { slots: (0..=workers).map(|_| Slot::default()).collect() }
+    }
+
+    /// 访问当前线程的槽位
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let last = self.slots.len() - 1;
+        let index = rayon::current_thread_index().map_or(last, |i| i.min(last));
+        f(&mut crate::lock(&self.slots[index].0))
+    }
+
+    /// 把其余槽位并入第一个槽位并返回它；可重复调用，之后的调用只合并期间新增的内容
+    pub(crate) fn merge(&self, mut combine: impl FnMut(&mut T, T)) -> MutexGuard<'_, T> {
+        let mut merged = crate::lock(&self.slots[0].0);
+        for slot in &self.slots[1..] {
+            let part = std::mem::take(&mut *crate::lock(&slot.0));
+            combine(&mut merged, part);
+        }
+        merged
+    }
+}
@@ -0,0 +1,124 @@
+//! 端到端测试辅助：构建各层二进制、启动服务、生成合成目录树
+//!
+//! 测试用例位于 `tests/e2e.rs`：在临时端口上启动 surf-service，分别通过 surf-client
+//! 与 `surf --remote` 驱动扫描，并与同一目录树的本地 `scan_sync` 结果逐项比对，
+//! 用于发现 CLI → 服务 → 核心之间的字段漂移。
+
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::OnceLock;
+
+use surf_core::testutil::{GeneratedTree, TreeSpec};
+
+/// 各工作区 crate 所在目录
+fn workspace_dir(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(name)
+}
+
+/// 构建指定 crate 的二进制到本 crate 的 target 目录，返回可执行文件路径
+fn build_binary(crate_dir: &str, bin: &str) -> PathBuf {
+    let target_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join("e2e-bins");
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--quiet", "--bin", bin, "--manifest-path"])
+        .arg(workspace_dir(crate_dir).join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("failed to run cargo build");
+    assert!(status.success(), "building {} failed", bin);
+    target_dir.join("debug").join(bin)
+}
+
+/// surf-service 可执行文件（同一测试进程内只构建一次）
+pub fn service_binary() -> &'static Path {
+    static BIN: OnceLock<PathBuf> = OnceLock::new();
+    BIN.get_or_init(|| build_binary("dev-service-api", "surf-service"))
+}
+
+/// surf CLI 可执行文件（同一测试进程内只构建一次）
+pub fn cli_binary() -> &'static Path {
+    static BIN: OnceLock<PathBuf> = OnceLock::new();
+    BIN.get_or_init(|| build_binary("dev-cli-tui", "surf"))
+}
+
+/// 运行中的 surf-service 进程，Drop 时终止
+pub struct ServiceProcess {
+    child: Child,
+    addr: String,
+    fingerprint: Option<String>,
+}
+
+impl ServiceProcess {
+    /// 在系统分配的临时端口上启动服务，并从启动日志中解析实际地址
+    pub fn start() -> Self {
+        Self::spawn(&[])
+    }
+
+    /// 以 HTTPS 启动服务，证书与私钥不存在时在 `dir` 中生成自签名证书
+    pub fn start_tls(dir: &Path) -> Self {
+        let cert = dir.join("cert.pem");
+        let key = dir.join("key.pem");
+        Self::spawn(&[
+            "--tls-cert".as_ref(),
+            cert.as_os_str(),
+            "--tls-key".as_ref(),
+            key.as_os_str(),
+            "--generate-tls-cert".as_ref(),
+        ])
+    }
+
+    fn spawn(extra: &[&std::ffi::OsStr]) -> Self {
+        let mut child = Command::new(service_binary())
+            .args(["--service", "--host", "127.0.0.1", "--port", "0"])
+            .args(extra)
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to start surf-service");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        // HTTPS 服务先打印地址再打印证书指纹
+        let mut lines = BufReader::new(stdout).lines().map_while(Result::ok);
+        let (addr, tls) = lines
+            .find_map(|line| {
+                let (scheme, rest) = line.split_once("://")?;
+                let tls = scheme.ends_with("https");
+                Some((rest.trim_end_matches("/rpc").to_string(), tls))
+            })
+            .expect("surf-service did not report its listening address");
+        let fingerprint = tls.then(|| {
+            lines
+                .find_map(|line| Some(line.split_once("(SHA-256): ")?.1.to_string()))
+                .expect("surf-service did not report its certificate fingerprint")
+        });
+        Self { child, addr, fingerprint }
+    }
+
+    /// 服务地址（host:port）
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// HTTPS 服务的证书指纹（SHA-256）
+    pub fn fingerprint(&self) -> Option<&str> {
+        self.fingerprint.as_deref()
+    }
+}
+
+impl Drop for ServiceProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// 生成确定性的合成目录树：多层目录、多种扩展名、不同大小的文件
+pub fn generate_tree(root: &Path) -> GeneratedTree {
+    TreeSpec::new()
+        .seed(2024)
+        .depth(2)
+        .fanout(4)
+        .files_per_dir(5)
+        .file_sizes(100, 8192)
+        .generate(root)
+        .expect("failed to generate synthetic tree")
+}
@@ -0,0 +1,120 @@
+use std::process::Command;
+
+use serde_json::Value;
+use surf_client::{CertFingerprint, ClientError, ClientOptions, SurfClient};
+use surf_core::{ScanRequest, ScanResult, Scanner};
+use surf_e2e::{cli_binary, generate_tree, ServiceProcess};
+
+/// 与远程扫描一致的本地扫描请求
+fn local_request(root: &std::path::Path) -> ScanRequest {
+    let mut request = ScanRequest::new(root);
+    request.min_size = Some(0);
+    request.limit = Some(30);
+    request
+}
+
+/// 比较跨层结果中与扫描时机无关的字段
+fn assert_same_result(remote: &ScanResult, local: &ScanResult) {
+    let (r, l) = (&remote.summary, &local.summary);
+    assert_eq!(r.root_path, l.root_path);
+    assert_eq!(r.total_files, l.total_files);
+    assert_eq!(r.total_dirs, l.total_dirs);
+    assert_eq!(r.total_size_bytes, l.total_size_bytes);
+    assert_eq!(r.top_limit, l.top_limit);
+
+    let files = |result: &ScanResult| -> Vec<(std::path::PathBuf, u64, Option<f64>)> {
+        result
+            .top_files
+            .iter()
+            .map(|f| (f.path.clone(), f.size_bytes, f.share_of_parent))
+            .collect()
+    };
+    assert_eq!(files(remote), files(local));
+
+    let extensions = |result: &ScanResult| -> Vec<(String, u64, u64)> {
+        result
+            .by_extension
+            .iter()
+            .map(|e| (e.extension.clone(), e.file_count, e.total_size_bytes))
+            .collect()
+    };
+    assert_eq!(extensions(remote), extensions(local));
+    assert_eq!(remote.directories, local.directories);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_client_and_remote_cli_match_local_scan() {
+    let dir = tempfile::tempdir().unwrap();
+    let tree = generate_tree(dir.path());
+    let local = Scanner::new().scan_sync(&local_request(dir.path())).unwrap();
+    assert_eq!(local.summary.total_files, tree.files);
+
+    let service = tokio::task::spawn_blocking(ServiceProcess::start).await.unwrap();
+
+    // surf-client → surf-service → surf_core
+    let client = SurfClient::new(service.addr());
+    let task_id = client.start_scan(&local_request(dir.path())).await.unwrap();
+    client.wait_for_completion(&task_id, |_| {}).await.unwrap();
+    let page = client.get_results_page(&task_id, 0, 10).await.unwrap();
+    assert_eq!(page.result.top_files.len(), 10);
+    assert_eq!(page.total_top_files, 30);
+    let via_client = client.get_results(&task_id).await.unwrap();
+    assert_same_result(&via_client, &local);
+
+    // surf --remote → surf-client → surf-service → surf_core
+    let output = Command::new(cli_binary())
+        .arg("--remote")
+        .arg(service.addr())
+        .arg("--path")
+        .arg(dir.path())
+        .args(["--min-size", "0", "--limit", "30", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let via_cli: ScanResult = serde_json::from_value(json).unwrap();
+    assert_same_result(&via_cli, &local);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_tls_service_with_pinned_fingerprint() {
+    let dir = tempfile::tempdir().unwrap();
+    let tree_dir = dir.path().join("tree");
+    generate_tree(&tree_dir);
+    let local = Scanner::new().scan_sync(&local_request(&tree_dir)).unwrap();
+
+    let certs = dir.path().to_path_buf();
+    let service = tokio::task::spawn_blocking(move || ServiceProcess::start_tls(&certs)).await.unwrap();
+    assert!(dir.path().join("cert.pem").exists() && dir.path().join("key.pem").exists());
+    let fingerprint: CertFingerprint = service.fingerprint().unwrap().parse().unwrap();
+
+    // 固定正确指纹的客户端经 TLS 完成扫描
+    let options = ClientOptions { tls_fingerprint: Some(fingerprint), ..Default::default() };
+    let client = SurfClient::with_options(service.addr(), options);
+    let task_id = client.start_scan(&local_request(&tree_dir)).await.unwrap();
+    client.wait_for_completion(&task_id, |_| {}).await.unwrap();
+    assert_same_result(&client.get_results(&task_id).await.unwrap(), &local);
+
+    // 指纹不符时握手失败且不重试
+    let options = ClientOptions { tls_fingerprint: Some(CertFingerprint::of_der(b"other")), ..Default::default() };
+    let err = SurfClient::with_options(service.addr(), options).status(&task_id).await.unwrap_err();
+    assert!(matches!(err, ClientError::Tls(_)), "{:?}", err);
+
+    // 明文客户端无法与 HTTPS 服务通信
+    assert!(SurfClient::new(service.addr()).status(&task_id).await.is_err());
+
+    // surf --remote --remote-fingerprint
+    let output = Command::new(cli_binary())
+        .arg("--remote")
+        .arg(service.addr())
+        .arg("--remote-fingerprint")
+        .arg(service.fingerprint().unwrap())
+        .arg("--path")
+        .arg(&tree_dir)
+        .args(["--min-size", "0", "--limit", "30", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let via_cli: ScanResult = serde_json::from_slice(&output.stdout).unwrap();
+    assert_same_result(&via_cli, &local);
+}
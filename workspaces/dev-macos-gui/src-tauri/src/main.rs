@@ -8,6 +8,9 @@ use std::fs;
 use std::path::PathBuf;
 use tauri::api::path::home_dir;
 
+#[allow(dead_code)]
+mod rpc_client;
+
 /// 与 Architecture.md 4.5.1 中约定的配置结构相对应的最小子集。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SurfConfig {
@@ -82,9 +85,21 @@ fn write_config(config: SurfConfig) -> Result<(), String> {
   fs::write(&path, json).map_err(|e| format!("写入配置文件失败: {e}"))
 }
 
+/// 打开 macOS 系统设置中的“完全磁盘访问权限”面板。
+///
+/// 扫描结果的 `summary.full_disk_access_missing` 为 true 时，前端提示用户授权并调用此命令。
+#[tauri::command]
+fn open_full_disk_access_settings() -> Result<(), String> {
+  surf_core::full_disk_access::open_settings().map_err(|e| format!("打开系统设置失败: {e}"))
+}
+
 fn main() {
   tauri::Builder::default()
-    .invoke_handler(tauri::generate_handler![read_config, write_config])
+    .invoke_handler(tauri::generate_handler![
+      read_config,
+      write_config,
+      open_full_disk_access_settings
+    ])
     .setup(|_app| {
       // 预留与 JSON-RPC 客户端 (`rpc_client` 模块) 的集成位置。
       // 后续可在此注册更多 Tauri 命令（例如 scan_*），调用 Rust 侧的 TCP/HTTP 客户端实现。
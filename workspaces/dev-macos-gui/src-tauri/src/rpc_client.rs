@@ -1,77 +1,63 @@
-//! JSON-RPC 客户端占位模块。
-+ //!
-+ //! 当前仅返回模拟数据，默认目标地址为 `127.0.0.1:1234`，用于为后续
-+ //! 与 `dev-service-api` 的真实集成预留接口位置。
+//! JSON-RPC 客户端模块。
+//!
+//! 基于 `surf-client` 访问本地 `dev-service-api`（默认地址 `127.0.0.1:1234`），
+//! 错误统一转换为字符串，便于直接作为 Tauri 命令的返回值。
 
-use serde::{Deserialize, Serialize};
+use surf_client::{Heatmap, SurfClient, TaskStatus};
+use surf_core::{ScanRequest, ScanResult};
 
 const DEFAULT_ADDR: &str = "127.0.0.1:1234";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ScanStatus {
-  pub task_id: String,
-  pub state: String,
-  pub progress: f64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ScanSummaryMock {
-  pub root_path: String,
-  pub total_files: u64,
-  pub total_dirs: u64,
-  pub total_size_bytes: u64,
-}
-
-#[derive(Debug, Clone)]
 pub struct RpcClient {
-  addr: String,
+  inner: SurfClient,
 }
 
 impl Default for RpcClient {
   fn default() -> Self {
-    Self {
-      addr: DEFAULT_ADDR.to_string(),
-    }
+    Self::new(DEFAULT_ADDR)
   }
 }
 
 impl RpcClient {
   pub fn new(addr: impl Into<String>) -> Self {
-    Self { addr: addr.into() }
+    Self { inner: SurfClient::new(addr) }
+  }
+
+  /// 启动扫描任务，返回 task_id。
+  pub async fn scan_start(&self, path: &str) -> Result<String, String> {
+    self
+      .inner
+      .start_scan(&ScanRequest::new(path))
+      .await
+      .map_err(|e| e.to_string())
   }
 
-  /// 启动扫描任务（占位实现）。
-  ///
-  /// 未来将在此通过 TCP JSON-RPC 调用 `scan.start`，当前仅返回模拟 task_id。
-  pub async fn scan_start(&self, _path: &str) -> Result<String, String> {
-    let _ = &self.addr; // 仅为占位，避免未使用字段警告。
-    Ok("mock-task-id".to_string())
+  /// 查询扫描任务状态。
+  pub async fn scan_status(&self, task_id: &str) -> Result<TaskStatus, String> {
+    self.inner.status(task_id).await.map_err(|e| e.to_string())
   }
 
-  /// 查询扫描任务状态（占位实现）。
-  pub async fn scan_status(&self, task_id: &str) -> Result<ScanStatus, String> {
-    let _ = &self.addr;
-    Ok(ScanStatus {
-      task_id: task_id.to_string(),
-      state: "disconnected".to_string(),
-      progress: 0.0,
-    })
+  /// 获取完整扫描结果（内部分页拉取）。
+  pub async fn scan_result(&self, task_id: &str) -> Result<ScanResult, String> {
+    self.inner.get_results(task_id).await.map_err(|e| e.to_string())
   }
 
-  /// 获取扫描结果（占位实现）。
-  pub async fn scan_result(&self, task_id: &str) -> Result<ScanSummaryMock, String> {
-    let _ = &self.addr;
-    Ok(ScanSummaryMock {
-      root_path: "/".to_string(),
-      total_files: 0,
-      total_dirs: 0,
-      total_size_bytes: 0,
-    })
+  /// 获取文件年龄热力图数据（用于着色 Treemap）。
+  pub async fn scan_heatmap(&self, task_id: &str, max_depth: Option<usize>) -> Result<Heatmap, String> {
+    self
+      .inner
+      .get_heatmap(task_id, max_depth)
+      .await
+      .map_err(|e| e.to_string())
   }
 
-  /// 取消扫描任务（占位实现）。
+  /// 取消扫描任务。
   pub async fn scan_cancel(&self, task_id: &str) -> Result<bool, String> {
-    let _ = (&self.addr, task_id);
-    Ok(false)
+    self
+      .inner
+      .cancel(task_id)
+      .await
+      .map(|_| true)
+      .map_err(|e| e.to_string())
   }
 }
@@ -1,9 +1,14 @@
 import React, { useEffect, useState } from "react";
 import {
+  CategoryEntry,
+  FILE_CATEGORY_LABELS,
+  openFullDiskAccessSettings,
   RpcError,
+  SCAN_PHASE_LABELS,
   ScanResultPayload,
   ScanStatus,
   TopFile,
+  TopLevelEntry,
   useServiceClient
 } from "../services/ServiceClient";
 
@@ -14,8 +19,13 @@ export const CentralView: React.FC = () => {
   const [currentTaskId, setCurrentTaskId] = useState<string | null>(null);
   const [status, setStatus] = useState<ScanStatus | null>(null);
   const [topFiles, setTopFiles] = useState<TopFile[]>([]);
+  const [topLevel, setTopLevel] = useState<TopLevelEntry[]>([]);
+  const [categories, setCategories] = useState<CategoryEntry[]>([]);
+  const [rootPath, setRootPath] = useState<string>("");
+  const [fullDiskAccessMissing, setFullDiskAccessMissing] = useState(false);
   const [isStarting, setIsStarting] = useState(false);
   const [isPolling, setIsPolling] = useState(false);
+  const [pinned, setPinned] = useState(false);
   const [error, setError] = useState<string | null>(null);
 
   const handleStartScan = async () => {
@@ -25,7 +35,11 @@ export const CentralView: React.FC = () => {
     }
     setError(null);
     setTopFiles([]);
+    setTopLevel([]);
+    setCategories([]);
+    setFullDiskAccessMissing(false);
     setStatus(null);
+    setPinned(false);
     setIsStarting(true);
 
     try {
@@ -56,6 +70,19 @@ export const CentralView: React.FC = () => {
     }
   };
 
+  // 快速扫描默认是临时的，窗口关闭后丢弃；固定后保留在服务端的历史中
+  const handleTogglePin = async () => {
+    if (!currentTaskId) return;
+    try {
+      const result = await client.setPinned(currentTaskId, !pinned);
+      setPinned(result.pinned);
+    } catch (e) {
+      const msg =
+        e instanceof RpcError ? e.message : "更新任务的保留状态失败，请稍后重试";
+      setError(msg);
+    }
+  };
+
   useEffect(() => {
     if (!currentTaskId || !isPolling) return;
 
@@ -68,6 +95,9 @@ export const CentralView: React.FC = () => {
         if (canceled) return;
 
         setStatus(s);
+        if (s.pinned != null) {
+          setPinned(s.pinned);
+        }
 
         const state = s.state.toLowerCase();
         if (state === "completed") {
@@ -84,6 +114,12 @@ export const CentralView: React.FC = () => {
                   ? result.top_files
                   : fromSummary) || [];
               setTopFiles(list);
+              setTopLevel(result.top_level || []);
+              setCategories(result.by_category || []);
+              setRootPath(result.summary?.root_path || "");
+              setFullDiskAccessMissing(
+                Boolean(result.summary?.full_disk_access_missing)
+              );
               setIsPolling(false);
             }
           } catch (e) {
@@ -132,6 +168,14 @@ export const CentralView: React.FC = () => {
     };
   }, [client, currentTaskId, isPolling]);
 
+  const handleOpenSettings = async () => {
+    try {
+      await openFullDiskAccessSettings();
+    } catch (e) {
+      setError("打开系统设置失败，请手动前往 系统设置 > 隐私与安全性 > 完全磁盘访问权限");
+    }
+  };
+
   const progressPercent =
     status && Number.isFinite(status.progress)
       ? Math.round(status.progress * 100)
@@ -164,6 +208,101 @@ export const CentralView: React.FC = () => {
         <p style={{ fontSize: "0.8rem", color: "#6b7280" }}>
           未来将在此展示磁盘占用 Treemap，可通过鼠标悬停与点击进行下钻浏览。
         </p>
+        <strong style={{ fontSize: "0.78rem", color: "#9ca3af" }}>
+          顶层速览（根目录各直接子项）
+        </strong>
+        {topLevel.length === 0 ? (
+          <div style={{ fontSize: "0.75rem", color: "#6b7280" }}>
+            任务完成后将展示 top_level。
+          </div>
+        ) : (
+          <ul
+            style={{
+              listStyle: "none",
+              padding: 0,
+              margin: 0,
+              display: "flex",
+              flexDirection: "column",
+              gap: "0.3rem",
+              overflow: "auto"
+            }}
+          >
+            {topLevel.map((row) => (
+              <li
+                key={`${row.path}-${row.root_files ? "files" : "dir"}`}
+                style={{ display: "flex", flexDirection: "column", gap: "0.15rem" }}
+              >
+                <div
+                  style={{
+                    display: "flex",
+                    justifyContent: "space-between",
+                    fontSize: "0.75rem",
+                    color: "#e5e7eb"
+                  }}
+                >
+                  <span style={{ wordBreak: "break-all" }}>
+                    {topLevelName(row, rootPath)}
+                  </span>
+                  <span style={{ color: "#9ca3af", whiteSpace: "nowrap" }}>
+                    {formatBytes(row.size_bytes)} · {row.file_count} 个文件 ·{" "}
+                    {(row.share_of_total * 100).toFixed(2)}%
+                  </span>
+                </div>
+                <div
+                  style={{
+                    height: 4,
+                    borderRadius: 999,
+                    backgroundColor: "#0f172a",
+                    overflow: "hidden"
+                  }}
+                >
+                  <div
+                    style={{
+                      width: `${row.share_of_total * 100}%`,
+                      height: "100%",
+                      backgroundColor: "#22c55e"
+                    }}
+                  />
+                </div>
+              </li>
+            ))}
+          </ul>
+        )}
+        {categories.length > 0 && (
+          <>
+            <strong style={{ fontSize: "0.78rem", color: "#9ca3af" }}>
+              文件类别分布
+            </strong>
+            <ul
+              style={{
+                listStyle: "none",
+                padding: 0,
+                margin: 0,
+                display: "flex",
+                flexDirection: "column",
+                gap: "0.2rem"
+              }}
+            >
+              {categories.map((row) => (
+                <li
+                  key={row.category}
+                  style={{
+                    display: "flex",
+                    justifyContent: "space-between",
+                    fontSize: "0.75rem",
+                    color: "#e5e7eb"
+                  }}
+                >
+                  <span>{FILE_CATEGORY_LABELS[row.category] ?? row.category}</span>
+                  <span style={{ color: "#9ca3af", whiteSpace: "nowrap" }}>
+                    {formatBytes(row.total_size_bytes)} · {row.file_count} 个文件 ·{" "}
+                    {(row.share_of_total * 100).toFixed(2)}%
+                  </span>
+                </li>
+              ))}
+            </ul>
+          </>
+        )}
       </section>
       <section
         style={{
@@ -262,6 +401,28 @@ export const CentralView: React.FC = () => {
             >
               取消当前任务
             </button>
+            <button
+              type="button"
+              onClick={handleTogglePin}
+              disabled={!currentTaskId}
+              title={
+                pinned
+                  ? "已保留到历史，关闭窗口后仍可查看"
+                  : "快速扫描：关闭窗口后自动丢弃"
+              }
+              style={{
+                padding: "0.35rem 0.9rem",
+                borderRadius: 999,
+                border: "1px solid #38bdf8",
+                background: pinned ? "#0c4a6e" : "transparent",
+                color: "#bae6fd",
+                fontSize: "0.8rem",
+                cursor: !currentTaskId ? "not-allowed" : "pointer",
+                opacity: !currentTaskId ? 0.4 : 1
+              }}
+            >
+              {pinned ? "已保留" : "保留到历史"}
+            </button>
             {currentTaskId && (
               <span
                 style={{
@@ -285,6 +446,39 @@ export const CentralView: React.FC = () => {
               错误：{error}
             </div>
           )}
+          {fullDiskAccessMissing && (
+            <div
+              style={{
+                marginTop: "0.25rem",
+                fontSize: "0.75rem",
+                color: "#fde68a",
+                display: "flex",
+                gap: "0.5rem",
+                alignItems: "center"
+              }}
+            >
+              <span>
+                Surf 缺少「完全磁盘访问权限」，~/Library 等受保护目录未计入本次结果。
+                授权后请重新扫描。
+              </span>
+              <button
+                type="button"
+                onClick={handleOpenSettings}
+                style={{
+                  padding: "0.2rem 0.7rem",
+                  borderRadius: 999,
+                  border: "1px solid #facc15",
+                  background: "transparent",
+                  color: "#fde68a",
+                  fontSize: "0.75rem",
+                  cursor: "pointer",
+                  whiteSpace: "nowrap"
+                }}
+              >
+                打开系统设置
+              </button>
+            </div>
+          )}
         </div>
 
         <div
@@ -315,6 +509,14 @@ export const CentralView: React.FC = () => {
                 进度：{progressPercent}%
               </span>
             )}
+            {status?.phase && (
+              <span style={{ marginLeft: "0.75rem" }}>
+                阶段 {status.phase.index}/{status.phase.count}：
+                {SCAN_PHASE_LABELS[status.phase.phase] ?? status.phase.phase}
+                {status.phase.progress != null &&
+                  ` ${Math.round(status.phase.progress * 100)}%`}
+              </span>
+            )}
           </div>
           {status && (
             <div style={{ color: "#9ca3af", fontSize: "0.75rem" }}>
@@ -421,13 +623,18 @@ export const CentralView: React.FC = () => {
                     }}
                   >
                     大小：{file.size_bytes ?? "未知"}
-                    {file.last_modified && (
+                    {file.last_modified != null && (
                       <>
                         <span style={{ marginLeft: "0.75rem" }}>
-                          修改时间：{file.last_modified}
+                          修改时间：{new Date(file.last_modified).toLocaleString()}
                         </span>
                       </>
                     )}
+                    {file.digest && (
+                      <span style={{ marginLeft: "0.75rem", wordBreak: "break-all" }}>
+                        摘要：{file.digest}
+                      </span>
+                    )}
                   </span>
                 </li>
               ))}
@@ -438,3 +645,25 @@ export const CentralView: React.FC = () => {
     </main>
   );
 };
+
+/** 顶层速览行的显示名称：子目录显示为相对根目录的名称，根目录直接文件单独标注。 */
+function topLevelName(row: TopLevelEntry, rootPath: string): string {
+  if (row.root_files) {
+    return "（根目录下的文件）";
+  }
+  const prefix = rootPath.endsWith("/") ? rootPath : `${rootPath}/`;
+  const name = row.path.startsWith(prefix) ? row.path.slice(prefix.length) : row.path;
+  return `${name}/`;
+}
+
+/** 以 1024 为进制格式化字节数。 */
+function formatBytes(bytes: number): string {
+  const units = ["B", "KB", "MB", "GB", "TB"];
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1024 && unit < units.length - 1) {
+    value /= 1024;
+    unit += 1;
+  }
+  return unit === 0 ? `${value} B` : `${value.toFixed(2)} ${units[unit]}`;
+}
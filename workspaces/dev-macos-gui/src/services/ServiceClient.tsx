@@ -1,4 +1,4 @@
-import React, { createContext, useContext, useMemo, useState } from "react";
+import React, { createContext, useContext, useEffect, useMemo, useState } from "react";
 import { invoke } from "@tauri-apps/api/tauri";
 
 /**
@@ -98,6 +98,20 @@ export async function writeConfig(config: SurfConfig): Promise<void> {
   }
 }
 
+/**
+ * 通过 Tauri `invoke` 打开 macOS 系统设置中的「完全磁盘访问权限」面板。
+ *
+ * 非 Tauri 环境下无法打开系统设置，记录 warning 后直接返回。
+ */
+export async function openFullDiskAccessSettings(): Promise<void> {
+  if (!isTauriAvailable()) {
+    console.warn("[surf gui] 当前不在 Tauri 环境中，无法打开系统设置");
+    return;
+  }
+
+  await invoke("open_full_disk_access_settings");
+}
+
 export type ScanState =
   | "queued"
   | "running"
@@ -105,14 +119,63 @@ export type ScanState =
   | "canceled"
   | "failed";
 
+export type ScanPhase =
+  | "Enumerating"
+  | "Stating"
+  | "Hashing"
+  | "Aggregating"
+  | "Persisting";
+
+export const SCAN_PHASE_LABELS: Record<ScanPhase, string> = {
+  Enumerating: "枚举目录",
+  Stating: "读取元数据",
+  Hashing: "哈希",
+  Aggregating: "汇总",
+  Persisting: "保存",
+};
+
+/** 当前阶段及其在本次扫描中的位置（仅运行中的任务携带） */
+export interface PhaseProgress {
+  phase: ScanPhase;
+  index: number; // 从 1 开始
+  count: number;
+  progress: number | null; // 阶段内进度，总量未知时为 null
+}
+
+export interface ScanThroughput {
+  files_per_sec: number;
+  bytes_per_sec: number;
+  mb_per_sec: number;
+  /** 已连续 10 秒以上没有新的文件计入。 */
+  stalled: boolean;
+  /** 逐秒速率，timestamp 为 Unix 纪元毫秒数。 */
+  series: { timestamp: number; files_per_sec: number; bytes_per_sec: number }[];
+}
+
 export interface ScanStatus {
   task_id: string;
   state: ScanState | string;
-  progress: number; // 0.0 - 1.0
+  progress: number; // 0.0 - 1.0，按阶段折算的整体进度
+  phase?: PhaseProgress;
   scanned_files?: number;
   scanned_bytes?: number;
   eta_seconds?: number;
+  /** 遍历总字节数的估算值，无从估算时缺省。 */
+  total_bytes_estimate?: number;
+  /** 遍历阶段的实际元数据操作速率（次/秒）。 */
+  io_ops_per_sec?: number;
+  /** 生效的元数据操作速率上限，未限速时缺省。 */
+  io_ops_limit?: number;
   error?: { code: number; message: string } | null;
+  /** 最近的扫描速率与逐秒序列，仅运行中的任务。 */
+  throughput?: ScanThroughput;
+  /** 根目录重叠、需要先结束遍历的任务 id；存在时本任务保持排队。 */
+  overlapping_tasks?: string[];
+  /** 临时任务：窗口关闭时由 scan.prune 清除，除非已固定。 */
+  ephemeral?: boolean;
+  /** 固定的任务保留在历史中。 */
+  pinned?: boolean;
+  session_id?: string;
 }
 
 export interface ScanSummary {
@@ -120,19 +183,237 @@ export interface ScanSummary {
   total_files: number;
   total_dirs: number;
   total_size_bytes: number;
+  /** 根目录所在文件系统的容量、空闲与当前用户可用空间（字节），查询失败时省略。 */
+  disk_space?: { total_bytes: number; free_bytes: number; available_bytes: number };
+  /** 请求 top_files_capacity 时 top_files 实际保留的条目数上限（可多于 Top N）。 */
+  top_files_capacity?: number;
   elapsed_seconds?: number;
+  /** macOS 上因缺少完全磁盘访问权限，有受保护目录未计入总量。 */
+  full_disk_access_missing?: boolean;
+  empty_file_count?: number;
+  empty_dir_count?: number;
+  /** 遍历错误总数（scan_errors 最多保留 10000 条）。 */
+  scan_error_count?: number;
+  /** 冗余附属文件（备份、编号副本、锁文件等）的数量与可释放的总字节数。 */
+  redundant_file_count?: number;
+  redundant_bytes?: number;
+  /** 云端“仅在线”占位文件的数量与报告的总大小（已计入总大小，但不占用本地空间）。 */
+  placeholder_files?: number;
+  placeholder_bytes?: number;
+  /** 可压缩候选文件的总数与压缩后估计可节省的总字节数（compression_candidates 最多保留 Top N 个）。 */
+  compression_candidate_count?: number;
+  compression_savings_bytes?: number;
 }
 
 export interface TopFile {
   path: string;
   size_bytes?: number;
-  last_modified?: string;
+  last_modified?: number | null; // Unix 纪元毫秒数
+  last_accessed?: number | null;
+  created?: number | null;
+  /** 距陈旧判断参照时间的空闲秒数，仅陈旧文件。 */
+  idle_seconds?: number | null;
+  /** 内容摘要（十六进制），仅在请求 compute_hash 时存在。 */
+  digest?: string | null;
+  /** 按文件头识别的 MIME 类型，仅在请求 detect_mime 时存在。 */
+  mime?: string | null;
+  /** 符号链接或 NTFS 联接点的目标，仅链接条目。 */
+  link_target?: string | null;
+  /** 云端“仅在线”占位文件，内容未下载到本地。 */
+  is_placeholder?: boolean;
+  /** 相对根目录的深度（根目录的直接文件为 1）。 */
+  depth?: number;
+  /** 所在目录在 directories 中的下标。 */
+  parent_index?: number | null;
+  /** 冗余附属文件类型，仅冗余文件。 */
+  redundant?: RedundantKind | null;
+}
+
+/** 冗余附属文件类型。 */
+export type RedundantKind = "backup" | "numbered_copy" | "lock_file" | "system_metadata" | "editor_swap";
+
+export const REDUNDANT_KIND_LABELS: Record<RedundantKind, string> = {
+  backup: "备份副本",
+  numbered_copy: "编号副本",
+  lock_file: "锁文件",
+  system_metadata: "系统元数据",
+  editor_swap: "编辑器交换文件"
+};
+
+export type ReclaimableKind =
+  | "trash"
+  | "browser_cache"
+  | "package_cache"
+  | "xcode_derived_data"
+  | "node_modules"
+  | "cargo_target";
+
+export const RECLAIMABLE_KIND_LABELS: Record<ReclaimableKind, string> = {
+  trash: "废纸篓 / 回收站",
+  browser_cache: "浏览器缓存",
+  package_cache: "包管理器缓存",
+  xcode_derived_data: "Xcode DerivedData",
+  node_modules: "node_modules",
+  cargo_target: "Cargo 构建目录"
+};
+
+export interface ReclaimableLocation {
+  path: string;
+  size_bytes: number;
+  file_count: number;
+}
+
+export interface ReclaimableStat {
+  kind: ReclaimableKind;
+  size_bytes: number;
+  file_count: number;
+  location_count: number;
+  /** 占用最大的位置（按大小降序，最多 limit 个）。 */
+  locations: ReclaimableLocation[];
+}
+
+export interface CompressionCandidate {
+  path: string;
+  size_bytes: number;
+  last_modified?: number | null; // Unix 纪元毫秒数
+  /** 估计依据：按扩展名或按文件开头的熵采样。 */
+  basis: "extension" | "sampled";
+  /** 压缩后大小与原大小之比（0~1）。 */
+  estimated_ratio: number;
+  estimated_savings_bytes: number;
+  /** 采样得到的熵（比特/字节），仅 basis 为 sampled 时存在。 */
+  entropy_bits?: number;
+}
+
+export interface CompressionOptions {
+  min_size?: number;
+  min_age_days?: number;
+  /** 熵采样读取的字节数，0 表示只按扩展名估计。 */
+  sample_bytes?: number;
+}
+
+/** 目录条目；directories 中的下标即目录 id。 */
+export interface DirectoryEntry {
+  path: string;
+  size_bytes: number;
+  share_of_parent?: number | null;
+  share_of_total: number;
+  /** 相对根目录的深度（根目录为 0）。 */
+  depth: number;
+  /** 父目录在 directories 中的下标，根目录为 null。 */
+  parent_index?: number | null;
+}
+
+/** 按 parent_index 求各目录的子目录下标列表（O(n)，无需解析路径）。 */
+export function directoryChildren(directories: DirectoryEntry[]): number[][] {
+  const children: number[][] = directories.map(() => []);
+  directories.forEach((dir, index) => {
+    if (dir.parent_index != null) {
+      children[dir.parent_index].push(index);
+    }
+  });
+  return children;
+}
+
+/** 顶层速览行：根目录的一个直接子目录，或根目录自身的直接文件（root_files 为 true）。 */
+export interface TopLevelEntry {
+  path: string;
+  size_bytes: number;
+  file_count: number;
+  dir_count: number;
+  share_of_total: number;
+  root_files?: boolean;
+}
+
+/** 文件类别（按扩展名归类）。 */
+export type FileCategory =
+  | "image"
+  | "video"
+  | "audio"
+  | "document"
+  | "code"
+  | "archive"
+  | "disk_image"
+  | "executable"
+  | "other";
+
+export const FILE_CATEGORY_LABELS: Record<FileCategory, string> = {
+  image: "图片",
+  video: "视频",
+  audio: "音频",
+  document: "文档",
+  code: "代码",
+  archive: "压缩包",
+  disk_image: "磁盘镜像",
+  executable: "可执行文件",
+  other: "其他"
+};
+
+export interface CategoryEntry {
+  category: FileCategory;
+  file_count: number;
+  total_size_bytes: number;
+  share_of_total: number;
+}
+
+export interface ScanErrorEntry {
+  path: string;
+  operation: "read_dir" | "read_entry" | "metadata";
+  /** std::io::ErrorKind 的 snake_case 形式，如 permission_denied。 */
+  kind: string;
+  message: string;
+}
+
+export interface SizeHistogram {
+  /** 扩展名或 category:<类别名>。 */
+  selector: string;
+  file_count: number;
+  size_bytes: number;
+  /** 以 1 KiB 为起点、每段 4 倍的对数刻度分段；最后一段 max_bytes 为 null。 */
+  buckets: {
+    min_bytes: number;
+    max_bytes: number | null;
+    file_count: number;
+    size_bytes: number;
+    share_of_selector: number;
+  }[];
 }
 
 export interface ScanResultPayload {
   task_id?: string;
   summary?: ScanSummary;
+  /** 多根目录扫描时各根目录自身的摘要（按请求顺序），summary 为合并后的汇总。 */
+  root_summaries?: ScanSummary[];
   top_files?: TopFile[];
+  top_level?: TopLevelEntry[];
+  by_category?: CategoryEntry[];
+  directories?: DirectoryEntry[];
+  /** 零字节文件路径（按路径排序）。 */
+  empty_files?: string[];
+  /** 只含空目录的目录树顶点及树中目录数。 */
+  empty_dirs?: { path: string; dir_count: number }[];
+  /** 遍历中无法读取的目录、目录条目与元数据（按路径排序）。 */
+  scan_errors?: ScanErrorEntry[];
+  /** 冗余附属文件按类型的汇总（按总大小降序）。 */
+  redundant?: { kind: RedundantKind; file_count: number; total_size_bytes: number }[];
+  /** 最大的冗余附属文件，仅在请求 list_redundant_files 时存在。 */
+  redundant_files?: TopFile[];
+  /** 按类别估算的可释放空间（废纸篓、缓存、node_modules 等），未发现时省略。 */
+  reclaimable_report?: { total_bytes: number; categories: ReclaimableStat[] };
+  /** 大而旧、估计可压缩的文件（按估计可节省的字节数降序），仅在请求 compression 时存在。 */
+  compression_candidates?: CompressionCandidate[];
+  /** 修改时间最早的文件（升序），仅在请求 recency_limit 时存在。 */
+  oldest_files?: TopFile[];
+  /** 修改时间最晚的文件（降序），仅在请求 recency_limit 时存在。 */
+  newest_files?: TopFile[];
+  /** 各选择器的文件大小分布，仅在请求 size_histograms 时存在。 */
+  size_histograms?: SizeHistogram[];
+  /** 结果来源：实际遍历或索引守护的快照。 */
+  source?: "walk" | "index";
+  /** 由索引应答时快照的代数与生成时间。 */
+  index?: { generation: number; indexed_at: number | string };
+  /** 分页信息：total_top_files 为可浏览的 top_files 总数。 */
+  page?: { offset: number; limit?: number; total_top_files: number; total_stale_files: number };
   // 其他字段按需扩展
   by_extension?: unknown;
   stale_files?: unknown;
@@ -144,6 +425,43 @@ export interface ScanOptions {
   limit?: number;
   exclude_patterns?: string[];
   stale_days?: number;
+  /** 陈旧判断参照的时间，缺省为 modified。 */
+  stale_mode?: "modified" | "accessed" | "created" | "any";
+  stale_limit?: number;
+  stale_sort?: "age" | "size" | "coldness";
+  empty_limit?: number;
+  /** 遇到第一个遍历错误即中止，任务以该错误失败。 */
+  fail_fast?: boolean;
+  /** 复用增量缓存中目录未变化的顶层子树。 */
+  incremental?: boolean;
+  /** 扫描期间阻止系统睡眠，结束或取消时释放。 */
+  keep_awake?: boolean;
+  /** 把 .app 等包目录作为单个文件统计，大小为内部文件之和。 */
+  opaque_packages?: boolean;
+  /** 遍历工作集的内存预算（字节），超出时改为深度优先遍历。 */
+  memory_budget?: number;
+  /** 遍历读取元数据的速率上限（次/秒）。 */
+  max_io_ops_per_sec?: number;
+  /** 后台扫描预设：默认限速并减少遍历线程。 */
+  nice_mode?: boolean;
+  /** 在 redundant_files 中列出最大的冗余附属文件。 */
+  list_redundant_files?: boolean;
+  /** oldest_files 与 newest_files 各自的条目数，缺省时不收集。 */
+  recency_limit?: number;
+  /** by_extension 每行嵌入的最大文件数（top_files），缺省时不收集。 */
+  extension_top_limit?: number;
+  /** top_files 多保留的条目数（大于 limit 时生效），用 scanResultPage 分页浏览。 */
+  top_files_capacity?: number;
+  /** 统计对数刻度大小分布的扩展名或 category:<类别名>（size_histograms），缺省时不统计。 */
+  size_histograms?: string[];
+  /** 找出大而旧、估计可压缩的文件（compression_candidates），缺省时不分析。 */
+  compression?: CompressionOptions;
+  /** 根目录被索引守护覆盖时是否直接由索引应答，缺省为 true。 */
+  use_index?: boolean;
+  /** 登记为临时任务，缺省为 true（GUI 发起的扫描默认随窗口关闭清除）。 */
+  ephemeral?: boolean;
+  /** 发起任务的会话，缺省为当前窗口的 GUI_SESSION_ID。 */
+  session_id?: string;
 }
 
 export interface ServiceState {
@@ -171,6 +489,9 @@ export interface ServiceClient {
 
   /** 取消任务，对应 JSON-RPC `scan.cancel`。 */
   cancel(taskId: string): Promise<{ canceled: boolean }>;
+
+  /** 固定或取消固定任务，对应 JSON-RPC `scan.pin` / `scan.unpin`。 */
+  setPinned(taskId: string, pinned: boolean): Promise<{ pinned: boolean }>;
 }
 
 interface ServiceClientContextValue {
@@ -203,6 +524,15 @@ export class RpcError extends Error {
 }
 
 const JSON_RPC_ENDPOINT = "/rpc";
+
+/**
+ * 当前窗口的会话标识：GUI 发起的扫描默认登记为该会话的临时任务，
+ * 窗口关闭时统一清除（见 `pruneSessionOnClose`）。
+ */
+export const GUI_SESSION_ID: string =
+  typeof crypto !== "undefined" && "randomUUID" in crypto
+    ? crypto.randomUUID()
+    : `${Date.now()}-${Math.random().toString(16).slice(2)}`;
 const JSON_RPC_TIMEOUT_MS = 10_000; // 默认 10 秒超时，避免请求挂死
 
 interface JsonRpcResponse<TResult> {
@@ -327,6 +657,8 @@ function createServiceClient(
         const result = await request<{ task_id: string }>("scan.start", {
           // 仅传递 root_path，服务端通过 serde alias 兼容早期使用 `path` 的实现
           root_path: path,
+          ephemeral: true,
+          session_id: GUI_SESSION_ID,
           ...options
         });
         markConnected();
@@ -383,21 +715,76 @@ function createServiceClient(
         markError(e);
         throw e;
       }
+    },
+
+    async setPinned(taskId: string, pinned: boolean): Promise<{ pinned: boolean }> {
+      try {
+        const result = await request<{ task_id: string; pinned: boolean }>(
+          pinned ? "scan.pin" : "scan.unpin",
+          { task_id: taskId }
+        );
+        markConnected();
+        return { pinned: result.pinned };
+      } catch (e) {
+        markError(e);
+        throw e;
+      }
     }
   };
 }
 
+/**
+ * 窗口关闭时以 JSON-RPC 通知调用 `scan.prune`，清除本会话未固定的临时任务。
+ *
+ * 页面卸载期间普通 fetch 可能被中止，这里使用 `navigator.sendBeacon` 保证请求发出；
+ * 通知没有响应，返回用于注销监听的函数。
+ */
+export function pruneSessionOnClose(sessionId: string = GUI_SESSION_ID): () => void {
+  const onPageHide = () => {
+    const body = JSON.stringify({
+      jsonrpc: "2.0",
+      method: "scan.prune",
+      params: { session_id: sessionId }
+    });
+    navigator.sendBeacon(JSON_RPC_ENDPOINT, body);
+  };
+  window.addEventListener("pagehide", onPageHide);
+  return () => window.removeEventListener("pagehide", onPageHide);
+}
+
 // ---- 与 PRD/Architecture 约定一致的最小 JSON-RPC API ----
 
 export async function scanStart(
   params: {
     path?: string;
     root_path?: string;
+    /** 同时扫描的多个根目录，指定时可省略 path。 */
+    roots?: string[];
     threads?: number;
     min_size?: string | number;
     limit?: number;
     exclude_patterns?: string[];
     stale_days?: number;
+    stale_mode?: "modified" | "accessed" | "created" | "any";
+    stale_limit?: number;
+    stale_sort?: "age" | "size" | "coldness";
+    empty_limit?: number;
+    fail_fast?: boolean;
+    incremental?: boolean;
+    keep_awake?: boolean;
+    opaque_packages?: boolean;
+    memory_budget?: number;
+    max_io_ops_per_sec?: number;
+    nice_mode?: boolean;
+    list_redundant_files?: boolean;
+    recency_limit?: number;
+    extension_top_limit?: number;
+    top_files_capacity?: number;
+    size_histograms?: string[];
+    compression?: CompressionOptions;
+    use_index?: boolean;
+    ephemeral?: boolean;
+    session_id?: string;
   } = {
     path: ".",
     limit: 10
@@ -415,10 +802,53 @@ export async function scanResult(taskId: string): Promise<ScanResultPayload> {
   return request<ScanResultPayload>("scan.result", { task_id: taskId });
 }
 
+/** 按页获取结果：top_files 与 stale_files 只含 [offset, offset + limit) 的条目。 */
+export async function scanResultPage(taskId: string, offset: number, limit: number): Promise<ScanResultPayload> {
+  return request<ScanResultPayload>("scan.result", { task_id: taskId, offset, limit });
+}
+
+/** 目录或文件在两次扫描之间的变化；path 相对根目录。 */
+export interface EntryChange {
+  path: string;
+  kind: "added" | "removed" | "grown" | "shrunk";
+  old_size_bytes: number | null;
+  new_size_bytes: number | null;
+  /** 缩小与删除为负。 */
+  delta_bytes: number;
+  /** 另一侧列表不完整，新增或删除也可能只是大小越过了 Top N 下限。 */
+  approximate?: boolean;
+}
+
+export interface ScanDiff {
+  old_root: string;
+  new_root: string;
+  total_size_delta: number;
+  total_files_delta: number;
+  total_dirs_delta: number;
+  directories: EntryChange[];
+  files: EntryChange[];
+}
+
+export async function scanDiff(oldTaskId: string, newTaskId: string, limit?: number): Promise<ScanDiff> {
+  return request<ScanDiff>("scan.diff", { old_task_id: oldTaskId, new_task_id: newTaskId, limit });
+}
+
 export async function scanCancel(taskId: string): Promise<unknown> {
   return request<unknown>("scan.cancel", { task_id: taskId });
 }
 
+export async function scanPin(taskId: string): Promise<{ task_id: string; pinned: boolean }> {
+  return request<{ task_id: string; pinned: boolean }>("scan.pin", { task_id: taskId });
+}
+
+export async function scanUnpin(taskId: string): Promise<{ task_id: string; pinned: boolean }> {
+  return request<{ task_id: string; pinned: boolean }>("scan.unpin", { task_id: taskId });
+}
+
+export async function scanPrune(sessionId: string): Promise<{ pruned: string[] }> {
+  return request<{ pruned: string[] }>("scan.prune", { session_id: sessionId });
+}
+
 export const ServiceClientProvider: React.FC<React.PropsWithChildren> = ({
   children
 }) => {
@@ -430,6 +860,9 @@ export const ServiceClientProvider: React.FC<React.PropsWithChildren> = ({
 
   const client = useMemo(() => createServiceClient(setServiceState), []);
 
+  // 本窗口发起的临时扫描随窗口关闭清除
+  useEffect(() => pruneSessionOnClose(), []);
+
   const value = useMemo<ServiceClientContextValue>(
     () => ({ serviceState, client }),
     [serviceState, client]
@@ -0,0 +1,152 @@
+//! 最小化的 HTTP/1.1 传输层
+//!
+//! 服务端只暴露 `POST /rpc` 一个端点，客户端每次请求建立一条短连接
+//! （`Connection: close`），读取完整响应后关闭。短连接让重连逻辑保持简单：
+//! 服务重启后下一次请求自然会重新建立连接。配置了证书指纹时在 TCP 连接上先完成 TLS 握手，
+//! 见 [`crate::tls`]。
+
+use std::io;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::TlsConnector;
+
+/// 传输层错误
+#[derive(Debug)]
+pub(crate) enum TransportError {
+    /// 建立连接失败（请求尚未发出，可安全重试）
+    Connect(io::Error),
+    /// 连接建立后读写失败
+    Io(io::Error),
+    /// 请求超时
+    Timeout,
+    /// 非 2xx 响应或无法解析的 HTTP 响应
+    Http(String),
+    /// TLS 握手失败（如证书指纹不匹配），重试无济于事
+    Tls(String),
+}
+
+/// 发送 JSON 请求体并返回响应体；提供 `tls` 时经 TLS 连接
+pub(crate) async fn post_json(
+    addr: &str,
+    path: &str,
+    body: &[u8],
+    limit: Duration,
+    tls: Option<&TlsConnector>,
+) -> Result<Vec<u8>, TransportError> {
+    let stream = match timeout(limit, TcpStream::connect(addr)).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(e)) => return Err(TransportError::Connect(e)),
+        Err(_) => return Err(TransportError::Timeout),
+    };
+    let Some(connector) = tls else {
+        return exchange(stream, addr, path, body, limit).await;
+    };
+    let name = crate::tls::server_name(addr)
+        .ok_or_else(|| TransportError::Connect(io::Error::new(io::ErrorKind::InvalidInput, format!("无效的服务地址: {}", addr))))?;
+    let stream = match timeout(limit, connector.connect(name, stream)).await {
+        Ok(Ok(stream)) => stream,
+        // 证书或协议错误以 InvalidData 返回，其余为握手期间的连接错误（请求尚未发出）
+        Ok(Err(e)) if e.kind() == io::ErrorKind::InvalidData => return Err(TransportError::Tls(e.to_string())),
+        Ok(Err(e)) => return Err(TransportError::Connect(e)),
+        Err(_) => return Err(TransportError::Timeout),
+    };
+    exchange(stream, addr, path, body, limit).await
+}
+
+/// 在已建立的连接上完成一次请求与响应
+async fn exchange<S>(mut stream: S, addr: &str, path: &str, body: &[u8], limit: Duration) -> Result<Vec<u8>, TransportError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let exchange = async {
+        let head = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nConnection: close\r\nContent-Length: {}\r\n\r\n",
+            path,
+            addr,
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body).await?;
+        stream.flush().await?;
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw).await?;
+        Ok::<_, io::Error>(raw)
+    };
+    let raw = match timeout(limit, exchange).await {
+        Ok(Ok(raw)) => raw,
+        Ok(Err(e)) => return Err(TransportError::Io(e)),
+        Err(_) => return Err(TransportError::Timeout),
+    };
+    if raw.is_empty() {
+        // 对端未响应即关闭连接（例如服务正在重启）
+        return Err(TransportError::Io(io::ErrorKind::UnexpectedEof.into()));
+    }
+    parse_response(&raw)
+}
+
+/// 解析 HTTP 响应，支持 Content-Length 与 chunked 两种响应体
+fn parse_response(raw: &[u8]) -> Result<Vec<u8>, TransportError> {
+    let split = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| TransportError::Http("响应缺少头部结束标记".to_string()))?;
+    let head = String::from_utf8_lossy(&raw[..split]);
+    let body = &raw[split + 4..];
+
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or_default();
+    let status: u16 = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| TransportError::Http(format!("无法解析状态行: {}", status_line)))?;
+    if !(200..300).contains(&status) {
+        return Err(TransportError::Http(status_line.to_string()));
+    }
+
+    let mut content_length = None;
+    let mut chunked = false;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse::<usize>().ok();
+        } else if name.eq_ignore_ascii_case("transfer-encoding") && value.eq_ignore_ascii_case("chunked") {
+            chunked = true;
+        }
+    }
+
+    if chunked {
+        return decode_chunked(body);
+    }
+    match content_length {
+        Some(len) if len <= body.len() => Ok(body[..len].to_vec()),
+        Some(_) => Err(TransportError::Http("响应体不完整".to_string())),
+        None => Ok(body.to_vec()),
+    }
+}
+
+fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>, TransportError> {
+    let invalid = || TransportError::Http("chunked 响应体格式错误".to_string());
+    let mut out = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|w| w == b"\r\n").ok_or_else(invalid)?;
+        let size_text = String::from_utf8_lossy(&body[..line_end]);
+        let size_hex = size_text.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16).map_err(|_| invalid())?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(out);
+        }
+        if body.len() < size + 2 {
+            return Err(invalid());
+        }
+        out.extend_from_slice(&body[..size]);
+        body = &body[size + 2..];
+    }
+}
@@ -0,0 +1,562 @@
+//! surf-client：Surf JSON-RPC 服务的类型化异步客户端
+//!
+//! 封装 `surf-service` 的 HTTP `POST /rpc` 协议，提供：
+//! - `start_scan`：按 `ScanRequest` 创建扫描任务；
+//! - `wait_for_completion`：轮询任务状态直至结束，并通过回调报告进度；
+//! - `get_results` / `get_results_page`：分页拉取扫描结果；
+//! - `get_heatmap`：获取按文件年龄段着色的目录热力图数据；
+//! - 连接失败与服务重启时按退避策略自动重试（`scan.start` 仅在请求未发出时重试，避免重复建任务）；
+//! - 配置 [`ClientOptions::tls_fingerprint`] 时经 TLS 连接，并固定服务端证书指纹。
+//!
+//! ```no_run
+//! # async fn demo() -> surf_client::Result<()> {
+//! use surf_client::SurfClient;
+//! use surf_core::ScanRequest;
+//!
+//! let client = SurfClient::new("127.0.0.1:1234");
+//! let task_id = client.start_scan(&ScanRequest::new("/data")).await?;
+//! client
+//!     .wait_for_completion(&task_id, |status| println!("{:.0}%", status.progress * 100.0))
+//!     .await?;
+//! let result = client.get_results(&task_id).await?;
+//! println!("共 {} 个文件", result.summary.total_files);
+//! # Ok(())
+//! # }
+//! ```
+
+mod http;
+mod tls;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use surf_core::{DirAge, PhaseProgress, ScanDiff, ScanRequest, ScanResult, ScanState};
+
+use http::TransportError;
+use tokio_rustls::TlsConnector;
+
+pub use tls::{CertFingerprint, ParseFingerprintError};
+
+/// 客户端错误
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// 网络读写失败（重试耗尽后返回）
+    #[error("连接服务失败: {0}")]
+    Io(#[from] std::io::Error),
+    /// 请求超时
+    #[error("请求超时")]
+    Timeout,
+    /// HTTP 层错误
+    #[error("HTTP 错误: {0}")]
+    Http(String),
+    /// TLS 握手失败（如服务端证书指纹与配置不符）
+    #[error("TLS 握手失败: {0}")]
+    Tls(String),
+    /// 服务端返回的 JSON-RPC 错误
+    #[error("JSON-RPC 错误 {code}: {message}")]
+    Rpc { code: i64, message: String },
+    /// 响应解析失败
+    #[error("响应解析失败: {0}")]
+    Decode(#[from] serde_json::Error),
+    /// 扫描任务失败
+    #[error("扫描任务失败: {0}")]
+    TaskFailed(String),
+    /// 扫描任务已被取消
+    #[error("扫描任务已取消")]
+    TaskCanceled,
+}
+
+/// 客户端结果类型
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// 客户端配置
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// 失败后的最大重试次数
+    pub max_retries: u32,
+    /// 首次重试前的等待时间，之后每次翻倍
+    pub retry_backoff: Duration,
+    /// 单次请求超时
+    pub request_timeout: Duration,
+    /// `wait_for_completion` 的轮询间隔
+    pub poll_interval: Duration,
+    /// `get_results` 每页拉取的条目数
+    pub page_size: usize,
+    /// 服务端证书指纹；设置时经 TLS 连接，只接受该指纹的证书（适用于自签名证书），
+    /// 未设置时使用明文 HTTP
+    pub tls_fingerprint: Option<CertFingerprint>,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(200),
+            request_timeout: Duration::from_secs(30),
+            poll_interval: Duration::from_millis(500),
+            page_size: 1000,
+            tls_fingerprint: None,
+        }
+    }
+}
+
+/// 扫描任务状态（`scan.status` 的返回）
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct TaskStatus {
+    /// 任务 ID
+    pub task_id: String,
+    /// 任务状态
+    pub state: ScanState,
+    /// 进度 (0.0 - 1.0)
+    pub progress: f64,
+    /// 已扫描文件数
+    pub scanned_files: u64,
+    /// 已扫描字节数
+    pub scanned_bytes: u64,
+    /// 预计剩余时间（秒）
+    pub eta_seconds: Option<u64>,
+    /// 当前阶段（仅运行中的任务携带）
+    #[serde(default)]
+    pub phase: Option<PhaseProgress>,
+    /// 最近的扫描速率（仅运行中的任务携带）
+    #[serde(default)]
+    pub throughput: Option<Throughput>,
+    /// 失败原因
+    pub error: Option<String>,
+    /// 失败时的 JSON-RPC 错误码（与 `scan.result` 返回的错误码相同）
+    #[serde(default)]
+    pub error_code: Option<i32>,
+    /// 根目录重叠、需要先结束遍历的任务（存在时本任务保持排队）
+    #[serde(default)]
+    pub overlapping_tasks: Vec<String>,
+}
+
+impl TaskStatus {
+    /// 任务是否已结束（完成、失败或取消）
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, ScanState::Completed | ScanState::Failed | ScanState::Canceled)
+    }
+}
+
+/// 一页扫描结果：`top_files` 与 `stale_files` 仅包含本页条目，其余字段完整
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ResultPage {
+    /// 本页结果
+    pub result: ScanResult,
+    /// 本页起始位置
+    pub offset: usize,
+    /// `top_files` 可浏览的总数（请求 `top_files_capacity` 时可多于 Top N）
+    pub total_top_files: usize,
+    /// 陈旧文件总数
+    pub total_stale_files: usize,
+}
+
+impl ResultPage {
+    /// 在 `page_size` 分页下是否还有后续页
+    pub fn has_more(&self, page_size: usize) -> bool {
+        self.offset + page_size < self.total_top_files.max(self.total_stale_files)
+    }
+}
+
+/// 服务端采样的扫描速率
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct Throughput {
+    /// 最近几秒的平均速率（文件/秒）
+    pub files_per_sec: f64,
+    /// 最近几秒的平均速率（字节/秒）
+    pub bytes_per_sec: f64,
+    /// 已连续 10 秒以上没有新的文件计入
+    pub stalled: bool,
+    /// 逐秒速率序列，按时间升序
+    #[serde(default)]
+    pub series: Vec<RatePoint>,
+}
+
+/// 一个采样间隔内的速率
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[non_exhaustive]
+pub struct RatePoint {
+    /// 间隔结束的时间（Unix 纪元毫秒数）
+    pub timestamp: u64,
+    /// 文件/秒
+    pub files_per_sec: f64,
+    /// 字节/秒
+    pub bytes_per_sec: f64,
+}
+
+/// 文件年龄热力图：各目录按年龄段划分的字节数与占比
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct Heatmap {
+    /// 年龄段标签，与每个目录的 `age_bytes` / `age_shares` 下标一一对应
+    pub buckets: Vec<String>,
+    /// 目录行（按深度升序、大小降序）
+    pub directories: Vec<DirAge>,
+}
+
+#[derive(Deserialize)]
+struct PageInfo {
+    offset: usize,
+    total_top_files: usize,
+    total_stale_files: usize,
+}
+
+/// Surf JSON-RPC 服务客户端
+pub struct SurfClient {
+    addr: String,
+    options: ClientOptions,
+    tls: Option<TlsConnector>,
+    next_id: AtomicU64,
+}
+
+impl SurfClient {
+    /// 以默认配置连接 `host:port` 上的服务
+    pub fn new(addr: impl Into<String>) -> Self {
+        Self::with_options(addr, ClientOptions::default())
+    }
+
+    /// 以指定配置连接 `host:port` 上的服务
+    pub fn with_options(addr: impl Into<String>, options: ClientOptions) -> Self {
+        Self {
+            addr: addr.into(),
+            tls: options.tls_fingerprint.map(tls::connector),
+            options,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// 服务地址
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// 发送原始 JSON-RPC 调用，返回 `result` 字段
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = serde_json::to_vec(&json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        }))?;
+        // 创建任务不是幂等操作：只有请求确定未发出（连接失败）时才重试
+        let idempotent = method != "scan.start";
+
+        let mut attempt = 0;
+        let raw = loop {
+            match http::post_json(&self.addr, "/rpc", &body, self.options.request_timeout, self.tls.as_ref()).await {
+                Ok(raw) => break raw,
+                Err(e) => {
+                    let retryable = match e {
+                        TransportError::Connect(_) => true,
+                        TransportError::Io(_) | TransportError::Timeout => idempotent,
+                        TransportError::Http(_) | TransportError::Tls(_) => false,
+                    };
+                    if !retryable || attempt >= self.options.max_retries {
+                        return Err(e.into());
+                    }
+                    tokio::time::sleep(self.options.retry_backoff * 2u32.pow(attempt)).await;
+                    attempt += 1;
+                }
+            }
+        };
+
+        let mut response: Value = serde_json::from_slice(&raw)?;
+        if let Some(error) = response.get("error").filter(|e| !e.is_null()) {
+            return Err(ClientError::Rpc {
+                code: error.get("code").and_then(Value::as_i64).unwrap_or_default(),
+                message: error.get("message").and_then(Value::as_str).unwrap_or_default().to_string(),
+            });
+        }
+        Ok(response.get_mut("result").map(Value::take).unwrap_or(Value::Null))
+    }
+
+    /// 创建扫描任务，返回任务 ID
+    pub async fn start_scan(&self, request: &ScanRequest) -> Result<String> {
+        let mut params = json!({
+            "root_path": request.root_path,
+            "threads": request.threads,
+            "min_size": request.min_size,
+            "exclude_patterns": request.exclude_patterns,
+            "include_patterns": request.include_patterns,
+            "exclude_regex": request.exclude_regex,
+            "include_regex": request.include_regex,
+            "respect_ignore_files": request.respect_ignore_files,
+            "max_depth": request.max_depth,
+            "list_suspicious_timestamps": request.list_suspicious_timestamps,
+            "list_redundant_files": request.list_redundant_files,
+            "recency_limit": request.recency_limit,
+            "extension_top_limit": request.extension_top_limit,
+            "size_histograms": request.size_histograms,
+            "stale_days": request.stale_days,
+            "stale_mode": request.stale_mode,
+            "stale_limit": request.stale_limit,
+            "stale_sort": request.stale_sort,
+            "empty_limit": request.empty_limit,
+            "fail_fast": request.fail_fast,
+            "incremental": request.incremental,
+            "keep_awake": request.keep_awake,
+            "opaque_packages": request.opaque_packages,
+            "memory_budget": request.memory_budget,
+            "max_io_ops_per_sec": request.max_io_ops_per_sec,
+            "nice_mode": request.nice_mode,
+            "limit": request.limit,
+            "dedup": request.dedup,
+            "compute_hash": request.compute_hash,
+            "hash_max_bytes_per_sec": request.hash_max_bytes_per_sec,
+            "compression": request.compression,
+            "detect_mime": request.detect_mime,
+            "mime_min_size": request.mime_min_size,
+            "follow_symlinks": request.follow_symlinks,
+            "same_filesystem": request.same_filesystem,
+            "deduplicate_hardlinks": request.deduplicate_hardlinks,
+            "allocated_sizes": request.allocated_sizes,
+            "collect_owners": request.collect_owners,
+            "collect_entries": request.collect_entries,
+            "top_dirs_depth": request.top_dirs_depth,
+            "top_dirs_limit": request.top_dirs_limit,
+        });
+        // 多根目录请求才附带 roots（json! 宏已接近递归上限，单独写入）
+        if !request.roots.is_empty() {
+            params["roots"] = json!(request.roots);
+        }
+        if let Some(capacity) = request.top_files_capacity {
+            params["top_files_capacity"] = json!(capacity);
+        }
+        let result = self.call("scan.start", params).await?;
+        result
+            .get("task_id")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| ClientError::Http("scan.start 响应缺少 task_id".to_string()))
+    }
+
+    /// 查询任务状态
+    pub async fn status(&self, task_id: &str) -> Result<TaskStatus> {
+        let result = self.call("scan.status", json!({ "task_id": task_id })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// 轮询任务直至结束，每次轮询后调用 `on_progress`
+    ///
+    /// 任务失败或被取消时返回对应错误。
+    pub async fn wait_for_completion<F>(&self, task_id: &str, mut on_progress: F) -> Result<TaskStatus>
+    where
+        F: FnMut(&TaskStatus),
+    {
+        loop {
+            let status = self.status(task_id).await?;
+            on_progress(&status);
+            match status.state {
+                ScanState::Completed => return Ok(status),
+                ScanState::Failed => {
+                    return Err(ClientError::TaskFailed(status.error.unwrap_or_default()));
+                }
+                ScanState::Canceled => return Err(ClientError::TaskCanceled),
+                _ => tokio::time::sleep(self.options.poll_interval).await,
+            }
+        }
+    }
+
+    /// 拉取一页结果
+    pub async fn get_results_page(&self, task_id: &str, offset: usize, limit: usize) -> Result<ResultPage> {
+        let value = self
+            .call("scan.result", json!({ "task_id": task_id, "offset": offset, "limit": limit }))
+            .await?;
+        let page: Option<PageInfo> = value.get("page").cloned().map(serde_json::from_value).transpose()?;
+        let result: ScanResult = serde_json::from_value(value)?;
+        // 旧版服务不支持分页时一次返回全部条目
+        let page = page.unwrap_or(PageInfo {
+            offset: 0,
+            total_top_files: result.top_files.len(),
+            total_stale_files: result.stale_files.len(),
+        });
+        Ok(ResultPage {
+            result,
+            offset: page.offset,
+            total_top_files: page.total_top_files,
+            total_stale_files: page.total_stale_files,
+        })
+    }
+
+    /// 分页拉取并拼接完整结果
+    pub async fn get_results(&self, task_id: &str) -> Result<ScanResult> {
+        let page_size = self.options.page_size.max(1);
+        let first = self.get_results_page(task_id, 0, page_size).await?;
+        let mut more = first.has_more(page_size);
+        let mut result = first.result;
+        let mut offset = 0;
+        while more {
+            offset += page_size;
+            let page = self.get_results_page(task_id, offset, page_size).await?;
+            more = page.has_more(page_size);
+            result.top_files.extend(page.result.top_files);
+            result.stale_files.extend(page.result.stale_files);
+        }
+        Ok(result)
+    }
+
+    /// 获取文件年龄热力图，`max_depth` 限制返回的目录深度
+    pub async fn get_heatmap(&self, task_id: &str, max_depth: Option<usize>) -> Result<Heatmap> {
+        let value = self
+            .call("scan.heatmap", json!({ "task_id": task_id, "max_depth": max_depth }))
+            .await?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// 对比两个任务的结果，`limit` 限制目录与文件变化各自返回的条数
+    pub async fn get_diff(&self, old_task_id: &str, new_task_id: &str, limit: Option<usize>) -> Result<ScanDiff> {
+        let value = self
+            .call("scan.diff", json!({ "old_task_id": old_task_id, "new_task_id": new_task_id, "limit": limit }))
+            .await?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// 取消任务
+    pub async fn cancel(&self, task_id: &str) -> Result<()> {
+        self.call("scan.cancel", json!({ "task_id": task_id })).await?;
+        Ok(())
+    }
+}
+
+impl From<TransportError> for ClientError {
+    fn from(e: TransportError) -> Self {
+        match e {
+            TransportError::Connect(e) | TransportError::Io(e) => ClientError::Io(e),
+            TransportError::Timeout => ClientError::Timeout,
+            TransportError::Http(message) => ClientError::Http(message),
+            TransportError::Tls(message) => ClientError::Tls(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 模拟服务：首个连接直接断开（验证重试），status 第二次查询起返回完成，
+    /// result 按 offset/limit 分页返回 5 个文件
+    async fn spawn_mock() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let status_calls = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                if connections.fetch_add(1, Ordering::SeqCst) == 0 {
+                    continue;
+                }
+                let status_calls = status_calls.clone();
+                tokio::spawn(async move {
+                    let mut raw = Vec::new();
+                    let mut buf = [0u8; 4096];
+                    let request: Value = loop {
+                        let n = stream.read(&mut buf).await.unwrap();
+                        raw.extend_from_slice(&buf[..n]);
+                        let text = String::from_utf8_lossy(&raw);
+                        if let Some((_, body)) = text.split_once("\r\n\r\n") {
+                            if let Ok(v) = serde_json::from_str(body) {
+                                break v;
+                            }
+                        }
+                    };
+                    let params = &request["params"];
+                    let result = match request["method"].as_str().unwrap() {
+                        "scan.start" => json!({ "task_id": "t1" }),
+                        "scan.status" => {
+                            let done = status_calls.fetch_add(1, Ordering::SeqCst) > 0;
+                            json!({
+                                "task_id": "t1",
+                                "state": if done { "Completed" } else { "Running" },
+                                "progress": if done { 1.0 } else { 0.5 },
+                                "scanned_files": 5,
+                                "scanned_bytes": 50,
+                                "eta_seconds": null,
+                                "error": null,
+                            })
+                        }
+                        "scan.result" => {
+                            let offset = params["offset"].as_u64().unwrap() as usize;
+                            let limit = params["limit"].as_u64().unwrap() as usize;
+                            let files: Vec<Value> = (0..5)
+                                .map(|i| json!({ "path": format!("/f{}", i), "size_bytes": 50 - i, "last_modified": null, "extension": null }))
+                                .skip(offset)
+                                .take(limit)
+                                .collect();
+                            json!({
+                                "task_id": "t1",
+                                "summary": { "root_path": "/", "total_files": 5, "total_dirs": 1, "total_size_bytes": 240, "elapsed_seconds": 0.1 },
+                                "top_files": files,
+                                "by_extension": [],
+                                "stale_files": [],
+                                "page": { "offset": offset, "limit": limit, "total_top_files": 5, "total_stale_files": 0 },
+                            })
+                        }
+                        _ => Value::Null,
+                    };
+                    let body = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result, "error": null }).to_string();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    stream.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_client_waits_and_paginates_with_retry() {
+        let addr = spawn_mock().await;
+        let options = ClientOptions {
+            retry_backoff: Duration::from_millis(10),
+            poll_interval: Duration::from_millis(10),
+            page_size: 2,
+            ..Default::default()
+        };
+        let client = SurfClient::with_options(addr, options);
+
+        // 第一次连接被断开，status 为幂等调用，自动重试
+        let mut updates = Vec::new();
+        let status = client
+            .wait_for_completion("t1", |s| updates.push(s.progress))
+            .await
+            .unwrap();
+        assert_eq!(status.state, ScanState::Completed);
+        assert_eq!(updates, vec![0.5, 1.0]);
+
+        let result = client.get_results("t1").await.unwrap();
+        assert_eq!(result.top_files.len(), 5);
+        assert_eq!(result.top_files[4].path, std::path::PathBuf::from("/f4"));
+
+        let task_id = client.start_scan(&ScanRequest::new("/data")).await.unwrap();
+        assert_eq!(task_id, "t1");
+    }
+
+    #[test]
+    fn test_cert_fingerprint_parsing() {
+        let fingerprint = CertFingerprint::of_der(b"certificate");
+        let text = fingerprint.to_string();
+        assert_eq!(text.len(), 32 * 3 - 1);
+        assert_eq!(text.parse::<CertFingerprint>(), Ok(fingerprint));
+        // 不区分大小写，可省略冒号，可带 openssl 风格的前缀
+        let bare = text.replace(':', "").to_lowercase();
+        assert_eq!(bare.parse::<CertFingerprint>(), Ok(fingerprint));
+        assert_eq!(format!("SHA256:{}", text).parse::<CertFingerprint>(), Ok(fingerprint));
+        assert_eq!(format!("sha256 Fingerprint={}", text).parse::<CertFingerprint>(), Ok(fingerprint));
+        assert!(bare[2..].parse::<CertFingerprint>().is_err());
+        assert!("zz".repeat(32).parse::<CertFingerprint>().is_err());
+    }
+}
@@ -0,0 +1,139 @@
+//! TLS 与证书指纹固定
+//!
+//! 远程部署的 surf-service 通常使用自签名证书（见服务端 `--generate-tls-cert`），无法通过 CA 校验。
+//! 客户端改为固定证书指纹：握手时只比较服务端叶子证书 DER 编码的 SHA-256 是否与配置一致，
+//! 不检查签发者、有效期与主机名；握手签名仍按证书公钥校验，证明对端持有对应私钥。
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+use tokio_rustls::TlsConnector;
+
+/// 证书指纹：证书 DER 编码的 SHA-256
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CertFingerprint([u8; 32]);
+
+impl CertFingerprint {
+    /// 计算证书（DER 编码）的指纹
+    pub fn of_der(der: &[u8]) -> Self {
+        Self(Sha256::digest(der).into())
+    }
+}
+
+/// 以冒号分隔的大写十六进制输出（与 `openssl x509 -fingerprint -sha256` 相同）
+impl fmt::Display for CertFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(":")?;
+            }
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// 指纹格式错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("无效的证书指纹（应为 64 位十六进制 SHA-256，可带冒号分隔）: {0}")]
+pub struct ParseFingerprintError(String);
+
+/// 接受十六进制字符串，大小写不限，可带冒号分隔以及 `SHA256:` 或 openssl 输出的
+/// `sha256 Fingerprint=` 前缀
+impl FromStr for CertFingerprint {
+    type Err = ParseFingerprintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseFingerprintError(s.to_string());
+        let text = s.trim();
+        let text = text
+            .split_once(['=', ':'])
+            .filter(|(prefix, _)| prefix.to_ascii_lowercase().starts_with("sha256"))
+            .map_or(text, |(_, rest)| rest);
+        let hex: Vec<u8> = text.bytes().filter(|&b| b != b':').collect();
+        if hex.len() != 64 {
+            return Err(invalid());
+        }
+        let mut bytes = [0u8; 32];
+        for (byte, pair) in bytes.iter_mut().zip(hex.chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+/// 只接受指定指纹证书的连接器
+pub(crate) fn connector(fingerprint: CertFingerprint) -> TlsConnector {
+    let provider = Arc::new(ring::default_provider());
+    let verifier = Arc::new(PinnedVerifier { fingerprint, provider: provider.clone() });
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .expect("ring 支持默认的 TLS 协议版本")
+        .dangerous()
+        .with_custom_certificate_verifier(verifier)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+}
+
+/// 握手使用的服务器名称：取地址中的主机部分（证书不按名称校验，仅用于 SNI）
+pub(crate) fn server_name(addr: &str) -> Option<ServerName<'static>> {
+    let host = match addr.rsplit_once(':') {
+        Some((host, port)) if port.bytes().all(|b| b.is_ascii_digit()) => host,
+        _ => addr,
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    ServerName::try_from(host.to_string()).ok()
+}
+
+#[derive(Debug)]
+struct PinnedVerifier {
+    fingerprint: CertFingerprint,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let actual = CertFingerprint::of_der(end_entity);
+        if actual == self.fingerprint {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!("服务端证书指纹不匹配: {}", actual)))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
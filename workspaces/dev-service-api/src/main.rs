@@ -1,6 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::Duration;
 
 use anyhow::Result;
 use axum::body::Bytes;
@@ -12,20 +16,42 @@ use axum::Router;
 use clap::{CommandFactory, Parser};
 use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize};
 use serde_json::{json, Value};
-use surf_core::{ScanRequest, ScanResult, ScanState, Scanner};
-use tokio::net::TcpListener;
-use tokio::sync::RwLock;
+use surf_core::format::{annotate_json, FormatOptions};
+use surf_core::serde_time::{with_format, TimeFormat};
+use surf_core::{CancellationToken, LiveProgress, ScanError, ScanPhase, ScanRequest, ScanResult, ScanState, Scanner};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{watch, RwLock};
 use uuid::Uuid;
 
+mod throughput;
+mod tls;
+
 // JSON-RPC 请求
 #[derive(Debug, Deserialize)]
 struct JsonRpcRequest {
+    /// 协议版本；要求请求中带有该字段，但不校验取值
+    #[allow(dead_code)]
     jsonrpc: String,
-    id: Value,
+    /// 请求 id；不带 id 成员的请求是通知（`"id": null` 仍是普通请求）
+    #[serde(default, deserialize_with = "present")]
+    id: Option<Value>,
     method: String,
     params: Option<Value>,
 }
 
+impl JsonRpcRequest {
+    /// 是否为通知：服务端照常执行，但不返回任何响应（包括错误）
+    fn is_notification(&self) -> bool {
+        self.id.is_none()
+    }
+}
+
+/// 字段存在时（含 `null`）反序列化为 `Some`，缺省时由 `#[serde(default)]` 得到 `None`
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
 // JSON-RPC 响应
 #[derive(Debug, Serialize)]
 struct JsonRpcResponse {
@@ -53,6 +79,21 @@ impl JsonRpcError {
     }
 }
 
+/// 权限不足，无法读取根目录或（`fail_fast` 时）遍历中的路径
+const ERROR_PERMISSION_DENIED: i32 = -32003;
+/// 扫描在得到结果前被中止
+const ERROR_SCAN_ABORTED: i32 = -32004;
+
+/// 扫描失败原因对应的 JSON-RPC 错误码：根目录或参数有误为 -32602，其余按类别区分
+fn scan_error_code(error: &ScanError) -> i32 {
+    match error {
+        ScanError::RootNotFound { .. } | ScanError::RootNotDirectory { .. } | ScanError::InvalidRequest { .. } => -32602,
+        ScanError::PermissionDenied { .. } => ERROR_PERMISSION_DENIED,
+        ScanError::Canceled => ERROR_SCAN_ABORTED,
+        _ => -32603,
+    }
+}
+
 // 任务状态跟踪
 #[derive(Debug, Clone, Serialize)]
 struct TaskInfo {
@@ -64,6 +105,50 @@ struct TaskInfo {
     eta_seconds: Option<u64>,
     result: Option<ScanResult>,
     error: Option<String>,
+    /// 失败时的 JSON-RPC 错误码（见 [`scan_error_code`]），scan.result 以此码返回错误
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_code: Option<i32>,
+    /// 结果来源：实际遍历或索引守护的快照
+    source: ResultSource,
+    /// 由索引应答时快照的代数与生成时间
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index: Option<Value>,
+    /// 扫描线程实时累加的进度计数，scan.status 无需等待扫描结束即可读取
+    #[serde(skip)]
+    live: Option<Arc<LiveProgress>>,
+    /// 取消令牌，scan.cancel 通过它中止正在进行的遍历
+    #[serde(skip)]
+    cancel: Option<CancellationToken>,
+    /// 根目录与本任务重叠、需要先结束的任务（登记时仍在排队或运行）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    overlapping_tasks: Vec<String>,
+    /// 实际遍历的根目录（规范化后，多根目录扫描时为全部根目录），用于判断后续任务是否与之重叠
+    #[serde(skip)]
+    roots: Vec<PathBuf>,
+    /// 遍历结束（含失败与取消）时变为 true
+    #[serde(skip)]
+    finished: Option<watch::Receiver<bool>>,
+    /// 遍历期间的吞吐量采样，scan.status 据此返回速率与停滞状态
+    #[serde(skip)]
+    throughput: Option<Arc<throughput::ThroughputHistory>>,
+    /// 临时任务：由 GUI 等会话发起的快速扫描，会话结束时经 scan.prune 清除
+    ephemeral: bool,
+    /// 固定的任务保留在任务列表中，不受 scan.prune 影响；非临时任务总是固定的
+    pinned: bool,
+    /// 发起临时任务的会话
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_id: Option<String>,
+}
+
+/// 扫描结果的来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ResultSource {
+    /// 实际遍历文件系统
+    #[default]
+    Walk,
+    /// 由索引守护（`surf daemon --index`）的快照应答
+    Index,
 }
 
 // 共享任务存储
@@ -72,21 +157,233 @@ type TaskStore = Arc<RwLock<HashMap<String, TaskInfo>>>;
 #[derive(Clone)]
 struct AppState {
     task_store: TaskStore,
+    index: Arc<IndexRouter>,
+    /// 允许 scan.start 读取访问日志的目录（`--access-log-dir`）；未配置时不接受 access_log 参数
+    access_log_dir: Option<Arc<PathBuf>>,
+}
+
+/// 解析 scan.start 的 access_log：相对路径相对于允许的目录，规范化后必须位于该目录之内
+fn resolve_access_log(dir: Option<&Path>, path: &str) -> std::result::Result<PathBuf, String> {
+    let dir = dir.ok_or("access_log is not enabled on this server (see --access-log-dir)")?;
+    let dir = dir.canonicalize().map_err(|e| format!("access log directory unavailable: {}", e))?;
+    let resolved = dir.join(path).canonicalize().map_err(|e| format!("access_log {}: {}", path, e))?;
+    if !resolved.starts_with(&dir) {
+        return Err(format!("access_log must be inside {}", dir.display()));
+    }
+    Ok(resolved)
+}
+
+/// 单次查询索引守护的超时；超时或失败时回退为实际扫描
+const INDEX_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 索引路由：位于任务管理与索引守护之间，scan.start 的根目录（或其直接子目录）被某个
+/// 索引守护覆盖时直接由快照应答，否则交由实际遍历
+#[derive(Debug, Default)]
+struct IndexRouter {
+    /// 索引守护地址（host:port），按顺序查询
+    endpoints: Vec<String>,
+}
+
+/// 索引守护的应答
+struct IndexAnswer {
+    result: ScanResult,
+    /// 快照代数与生成时间
+    index: Value,
+}
+
+impl IndexRouter {
+    /// 依次向各索引守护查询请求的根目录，第一个能回答的守护胜出；请求含索引无法满足的选项、
+    /// 守护不可达或不覆盖该路径时返回 None
+    async fn query(&self, request: &ScanRequest) -> Option<IndexAnswer> {
+        if self.endpoints.is_empty() || !answerable_from_index(request) {
+            return None;
+        }
+        for endpoint in &self.endpoints {
+            let query = query_index(endpoint, &request.root_path, request.limit);
+            if let Ok(Ok(answer)) = tokio::time::timeout(INDEX_QUERY_TIMEOUT, query).await {
+                return Some(answer);
+            }
+        }
+        None
+    }
+}
+
+/// 请求能否由索引快照应答：快照按守护自身的选项生成，带过滤、深度限制或额外分析（陈旧文件、
+/// 重复文件、摘要、MIME、属主等）的请求必须实际扫描
+fn answerable_from_index(request: &ScanRequest) -> bool {
+    let defaults = ScanRequest::new(&request.root_path);
+    request.min_size.is_none()
+        && request.exclude_patterns.is_empty()
+        && request.include_patterns.is_empty()
+        && request.exclude_regex.is_empty()
+        && request.include_regex.is_empty()
+        && !request.respect_ignore_files
+        && request.max_depth.is_none()
+        && request.stale_days.is_none()
+        && request.empty_limit.is_none()
+        && !request.fail_fast
+        && request.access_log.is_none()
+        && !request.list_suspicious_timestamps
+        && !request.list_redundant_files
+        && request.recency_limit.is_none()
+        && request.extension_top_limit.is_none()
+        && request.top_files_capacity.is_none()
+        && request.size_histograms.is_empty()
+        && request.dedup.is_none()
+        && request.compute_hash.is_none()
+        && request.compression.is_none()
+        && request.roots.is_empty()
+        && !request.detect_mime
+        && request.follow_symlinks == defaults.follow_symlinks
+        && !request.same_filesystem
+        && !request.collect_owners
+        && !request.deduplicate_hardlinks
+        && !request.allocated_sizes
+        && !request.opaque_packages
+        && request.top_dirs_depth == defaults.top_dirs_depth
+        && request.top_dirs_limit.is_none()
+        && request.collect_entries
+}
+
+/// 通过换行分隔的 JSON-RPC 向单个索引守护发送 `index.query`
+async fn query_index(endpoint: &str, path: &std::path::Path, limit: Option<usize>) -> Result<IndexAnswer> {
+    let mut stream = TcpStream::connect(endpoint).await?;
+    let query = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "index.query",
+        "params": { "path": path, "limit": limit },
+    });
+    stream.write_all(format!("{}\n", query).as_bytes()).await?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).await?;
+    let mut response: Value = serde_json::from_str(&line)?;
+    if let Some(error) = response.get("error") {
+        anyhow::bail!("索引守护 {} 无法应答: {}", endpoint, error);
+    }
+    let mut answer = response["result"].take();
+    let result = serde_json::from_value(answer["result"].take())?;
+    Ok(IndexAnswer {
+        result,
+        index: json!({ "generation": answer["generation"], "indexed_at": answer["indexed_at"] }),
+    })
 }
 
 // scan.start 参数
 #[derive(Debug, Deserialize)]
 struct StartScanParams {
-    /// 扫描根路径，对应 Architecture.md 中的 `path`
-    #[serde(alias = "path")]
+    /// 扫描根路径，对应 Architecture.md 中的 `path`；指定 `roots` 时可省略
+    #[serde(default, alias = "path")]
     root_path: String,
+    /// 同时扫描的多个根目录，结果合并汇总并附带各根目录的摘要（root_summaries）
+    #[serde(default)]
+    roots: Vec<String>,
     threads: Option<u16>,
     /// 最小文件大小，兼容数值字节和带单位字符串（如 "100MB"）
     #[serde(default, deserialize_with = "deserialize_size_opt")]
     min_size: Option<u64>,
     exclude_patterns: Option<Vec<String>>,
+    /// 包含规则（glob），非空时只统计匹配的文件；先包含后排除
+    #[serde(default)]
+    include_patterns: Vec<String>,
+    /// 排除 / 包含规则（正则表达式，匹配相对于根目录的路径，如 `(^|/)\d{4}-\d{2}-\d{2}$` 排除任意层级的
+    /// 日期目录；`anchored:` 前缀改为匹配完整路径），与 glob 规则合并生效
+    #[serde(default)]
+    exclude_regex: Vec<String>,
+    #[serde(default)]
+    include_regex: Vec<String>,
+    /// 遵循 .gitignore / .surfignore
+    #[serde(default)]
+    respect_ignore_files: bool,
+    /// 最大遍历深度（根目录为 0），缺省不限制
+    max_depth: Option<usize>,
+    /// 列出修改时间可疑的文件
+    #[serde(default)]
+    list_suspicious_timestamps: bool,
+    /// 列出最大的冗余附属文件
+    #[serde(default)]
+    list_redundant_files: bool,
+    /// 最旧与最新文件列表各自的条目数，缺省时不收集
+    recency_limit: Option<usize>,
+    /// 每个扩展名嵌入的最大文件数，缺省时不收集
+    extension_top_limit: Option<usize>,
+    /// 统计大小分布的扩展名或 `category:<类别名>`，缺省时不统计
+    #[serde(default)]
+    size_histograms: Vec<String>,
     stale_days: Option<u32>,
+    /// 陈旧判断参照的时间："modified"（默认）/ "accessed" / "created" / "any"
+    #[serde(default)]
+    stale_mode: surf_core::StaleMode,
+    /// 陈旧文件最多保留的数量，缺省时全部保留
+    stale_limit: Option<usize>,
+    /// 陈旧文件的排序依据："age" / "size" / "coldness"
+    stale_sort: Option<surf_core::StaleSort>,
+    /// 空文件与空目录列表各自最多保留的数量，缺省时全部保留
+    empty_limit: Option<usize>,
+    /// 遇到第一个遍历错误即中止，任务以该错误失败
+    #[serde(default)]
+    fail_fast: bool,
+    /// 复用增量缓存中未变化的顶层子树
+    #[serde(default)]
+    incremental: bool,
+    /// 扫描期间阻止服务端所在机器睡眠
+    #[serde(default)]
+    keep_awake: bool,
+    /// 把包目录作为单个文件统计
+    #[serde(default)]
+    opaque_packages: bool,
+    /// 遍历工作集的内存预算（字节），超出时改为深度优先遍历
+    memory_budget: Option<u64>,
+    /// 遍历读取元数据的速率上限（次/秒）
+    max_io_ops_per_sec: Option<u64>,
+    /// 后台扫描预设：默认限速并减少遍历线程
+    #[serde(default)]
+    nice_mode: bool,
     limit: Option<usize>,
+    /// top_files 多保留的条目数（大于 limit 时生效），供 scan.result 分页浏览
+    top_files_capacity: Option<usize>,
+    /// 访问日志文件路径，与 stale_days 配合按最近活动时间识别冷数据
+    access_log: Option<String>,
+    /// 重复文件检测选项（hash_threads / max_bytes_per_sec），缺省时不检测
+    dedup: Option<surf_core::DedupOptions>,
+    /// 为 Top N 大文件计算内容摘要："md5" / "sha256" / "blake3"，缺省时不计算
+    compute_hash: Option<surf_core::HashAlgo>,
+    /// 内容摘要阶段的读取速率上限（字节/秒）
+    hash_max_bytes_per_sec: Option<u64>,
+    /// 可压缩文件分析选项（min_size / min_age_days / sample_bytes），缺省时不分析
+    compression: Option<surf_core::CompressionOptions>,
+    /// 读取文件头识别 MIME 类型，写入条目的 mime 字段
+    #[serde(default)]
+    detect_mime: bool,
+    /// 只识别不小于该大小的文件（字节），缺省为 64KB
+    mime_min_size: Option<u64>,
+    /// 符号链接跟随策略："never"（默认）/ "follow_dirs" / "follow_all"
+    #[serde(default)]
+    follow_symlinks: surf_core::SymlinkPolicy,
+    /// 单文件系统模式（du -x）：不进入其他文件系统的挂载点
+    #[serde(default)]
+    same_filesystem: bool,
+    /// 记录文件属主与权限，并按属主汇总到 by_owner
+    #[serde(default)]
+    collect_owners: bool,
+    /// 是否保留文件条目（缺省为 true）；false 时只计算汇总统计
+    collect_entries: Option<bool>,
+    /// 硬链接去重：同一 inode 的多个路径只计入一次
+    #[serde(default)]
+    deduplicate_hardlinks: bool,
+    /// 同时统计文件实际分配的磁盘空间
+    #[serde(default)]
+    allocated_sizes: bool,
+    /// 最大目录排名（top_dirs）的统计深度与条目数
+    top_dirs_depth: Option<usize>,
+    top_dirs_limit: Option<usize>,
+    /// 根目录被索引守护覆盖时是否直接由索引应答（缺省为 true）
+    use_index: Option<bool>,
+    /// 登记为临时任务，会话结束（scan.prune）时若仍未固定则清除；需同时提供 session_id
+    #[serde(default)]
+    ephemeral: bool,
+    /// 发起任务的会话（如 GUI 窗口）
+    session_id: Option<String>,
 }
 
 /// 解析带可选单位的文件大小字符串，支持纯数字或带单位后缀（B/KB/MB/GB/TB）。
@@ -151,7 +448,7 @@ where
             .map(Some),
         Some(Value::String(s)) => parse_size_string(&s)
             .map(Some)
-            .map_err(|e| DeError::custom(e)),
+            .map_err(DeError::custom),
         Some(other) => Err(DeError::custom(format!(
             "invalid min_size type: {}",
             other
@@ -175,6 +472,35 @@ struct GetStatusParams {
 #[derive(Debug, Deserialize)]
 struct GetResultParams {
     task_id: String,
+    /// 分页起始位置（作用于 top_files 与 stale_files）
+    #[serde(default)]
+    offset: usize,
+    /// 每页条目数；缺省时返回全部条目
+    limit: Option<usize>,
+    /// 时间戳格式："millis"（默认，Unix 纪元毫秒数）或 "rfc3339"
+    #[serde(default)]
+    time_format: TimeFormat,
+    /// 提供时为字节数与计数字段补充 `<字段名>_human` 文本，如 `{"units": "si", "locale": "de"}`
+    format: Option<FormatOptions>,
+}
+
+// scan.heatmap 参数
+#[derive(Debug, Deserialize)]
+struct GetHeatmapParams {
+    task_id: String,
+    /// 只返回不超过该深度的目录（根目录为 0）；缺省时返回扫描时统计的全部层级
+    max_depth: Option<usize>,
+}
+
+// scan.diff 参数
+#[derive(Debug, Deserialize)]
+struct DiffParams {
+    /// 旧结果所属的任务
+    old_task_id: String,
+    /// 新结果所属的任务
+    new_task_id: String,
+    /// 目录与文件变化各自最多返回的条数，缺省时全部返回
+    limit: Option<usize>,
 }
 
 // scan.cancel 参数
@@ -183,26 +509,177 @@ struct CancelScanParams {
     task_id: String,
 }
 
+// scan.pin / scan.unpin 参数
+#[derive(Debug, Deserialize)]
+struct PinParams {
+    task_id: String,
+}
+
+// scan.prune 参数
+#[derive(Debug, Deserialize)]
+struct PruneParams {
+    /// 结束的会话，其未固定的临时任务将被清除
+    session_id: String,
+}
+
 // 处理 scan.start 方法
 async fn handle_scan_start(
     params: Value,
-    task_store: TaskStore,
+    state: AppState,
 ) -> Result<JsonRpcResponse> {
+    let task_store = state.task_store;
     let params: StartScanParams = serde_json::from_value(params)?;
+    if params.ephemeral && params.session_id.is_none() {
+        return Ok(JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Value::Null,
+            result: None,
+            error: Some(JsonRpcError::new(-32602, "ephemeral requires session_id")),
+        });
+    }
+    if params.root_path.is_empty() && params.roots.is_empty() {
+        return Ok(JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Value::Null,
+            result: None,
+            error: Some(JsonRpcError::new(-32602, "root_path or roots is required")),
+        });
+    }
+    let (ephemeral, session_id) = (params.ephemeral, params.session_id);
 
     let task_id = Uuid::new_v4().to_string();
 
     // 创建扫描请求
-    let mut request = ScanRequest::new(params.root_path);
+    let mut request = if params.roots.is_empty() {
+        ScanRequest::new(params.root_path)
+    } else {
+        ScanRequest::for_roots(params.roots)
+    };
     request.threads = params.threads;
     request.min_size = params.min_size;
+    // 未指定 min_size 时按文件系统容量选择默认值，min_size 为 0 时完整收集
+    request.auto_min_size = true;
     request.exclude_patterns = params.exclude_patterns.unwrap_or_default();
+    request.include_patterns = params.include_patterns;
+    request.exclude_regex = params.exclude_regex;
+    request.include_regex = params.include_regex;
+    request.respect_ignore_files = params.respect_ignore_files;
+    request.max_depth = params.max_depth;
+    request.list_suspicious_timestamps = params.list_suspicious_timestamps;
+    request.list_redundant_files = params.list_redundant_files;
+    request.recency_limit = params.recency_limit;
+    request.extension_top_limit = params.extension_top_limit;
+    request.size_histograms = params.size_histograms;
     request.stale_days = params.stale_days;
+    request.stale_mode = params.stale_mode;
+    request.stale_limit = params.stale_limit;
+    request.stale_sort = params.stale_sort;
+    request.empty_limit = params.empty_limit;
+    request.fail_fast = params.fail_fast;
+    request.incremental = params.incremental;
+    request.keep_awake = params.keep_awake;
+    request.opaque_packages = params.opaque_packages;
+    request.memory_budget = params.memory_budget;
+    request.max_io_ops_per_sec = params.max_io_ops_per_sec;
+    request.nice_mode = params.nice_mode;
     request.limit = params.limit;
+    request.top_files_capacity = params.top_files_capacity;
+    request.dedup = params.dedup;
+    request.compute_hash = params.compute_hash;
+    request.hash_max_bytes_per_sec = params.hash_max_bytes_per_sec;
+    request.compression = params.compression;
+    request.detect_mime = params.detect_mime;
+    if let Some(min) = params.mime_min_size {
+        request.mime_min_size = min;
+    }
+    request.follow_symlinks = params.follow_symlinks;
+    request.same_filesystem = params.same_filesystem;
+    request.collect_owners = params.collect_owners;
+    request.collect_entries = params.collect_entries.unwrap_or(true);
+    request.deduplicate_hardlinks = params.deduplicate_hardlinks;
+    request.allocated_sizes = params.allocated_sizes;
+    if let Some(depth) = params.top_dirs_depth {
+        request.top_dirs_depth = depth;
+    }
+    request.top_dirs_limit = params.top_dirs_limit;
+    if let Some(path) = params.access_log {
+        // 只读取服务端配置目录中的日志，路径越界或无法解析时均为参数错误
+        let loaded = resolve_access_log(state.access_log_dir.as_deref().map(PathBuf::as_path), &path)
+            .and_then(|path| surf_core::AccessLog::load(&path).map_err(|e| format!("access_log {}: {}", path.display(), e)));
+        match loaded {
+            Ok(log) => request.access_log = Some(Arc::new(log)),
+            Err(message) => {
+                return Ok(JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: Value::Null,
+                    result: None,
+                    error: Some(JsonRpcError::new(-32602, &message)),
+                });
+            }
+        }
+    }
+
+    // 索引守护覆盖该根目录时直接登记为已完成的任务，无需遍历
+    let answer = if params.use_index.unwrap_or(true) {
+        state.index.query(&request).await
+    } else {
+        None
+    };
+    if let Some(answer) = answer {
+        let summary = &answer.result.summary;
+        let task = TaskInfo {
+            task_id: task_id.clone(),
+            state: ScanState::Completed,
+            progress: 1.0,
+            scanned_files: summary.total_files,
+            scanned_bytes: summary.total_size_bytes,
+            eta_seconds: None,
+            result: Some(answer.result),
+            error: None,
+            error_code: None,
+            source: ResultSource::Index,
+            index: Some(answer.index),
+            live: None,
+            cancel: None,
+            overlapping_tasks: Vec::new(),
+            roots: Vec::new(),
+            finished: None,
+            throughput: None,
+            ephemeral,
+            pinned: !ephemeral,
+            session_id,
+        };
+        task_store.write().await.insert(task_id.clone(), task);
+        return Ok(JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Value::Null,
+            result: Some(serde_json::to_value(StartScanResponse { task_id }).unwrap()),
+            error: None,
+        });
+    }
 
-    // 在任务存储中添加新任务（排队状态）
-    {
+    // 复用扫描缓存中同一根目录的并发提示
+    request.concurrency_hints = surf_core::hints::load_cached(&request.root_path).map(Arc::new);
+    let live = LiveProgress::new();
+    let history = Arc::new(throughput::ThroughputHistory::default());
+    let sampler = throughput::sample(live.clone(), history.clone());
+    // 扫描结束后还需保存并发提示缓存，登记为最后一个阶段
+    live.plan_phase(ScanPhase::Persisting);
+    request.progress = Some(live.clone());
+    let cancel = CancellationToken::new();
+    request.cancel = Some(cancel.clone());
+
+    // 在任务存储中添加新任务（排队状态）；根目录与仍在进行的任务重叠时排在其后，避免重复读取同一棵子树
+    let requested = if request.roots.is_empty() { std::slice::from_ref(&request.root_path) } else { &request.roots[..] };
+    let roots: Vec<PathBuf> =
+        requested.iter().map(|root| std::fs::canonicalize(root).unwrap_or_else(|_| root.clone())).collect();
+    let (finished_tx, finished_rx) = watch::channel(false);
+    let waits: Vec<watch::Receiver<bool>> = {
         let mut store = task_store.write().await;
+        let mut overlapping: Vec<String> = roots.iter().flat_map(|root| overlapping_tasks(&store, root)).collect();
+        overlapping.sort();
+        overlapping.dedup();
+        let waits = overlapping.iter().filter_map(|id| store[id].finished.clone()).collect();
         store.insert(
             task_id.clone(),
             TaskInfo {
@@ -214,30 +691,75 @@ async fn handle_scan_start(
                 eta_seconds: None,
                 result: None,
                 error: None,
+                error_code: None,
+                source: ResultSource::Walk,
+                index: None,
+                live: Some(live),
+                cancel: Some(cancel),
+                overlapping_tasks: overlapping,
+                roots,
+                finished: Some(finished_rx),
+                throughput: Some(history.clone()),
+                ephemeral,
+                pinned: !ephemeral,
+                session_id,
             },
         );
-    }
+        waits
+    };
 
     // 启动异步扫描任务
     let task_store_clone = task_store.clone();
     let task_id_clone = task_id.clone();
     tokio::spawn(async move {
-        let scanner = Scanner::new();
+        for mut finished in waits {
+            // 发送端随任务结束而释放时同样视为已结束
+            let _ = finished.wait_for(|done| *done).await;
+        }
         let mut store = task_store_clone.write().await;
-        let task = store.get_mut(&task_id_clone).unwrap();
-        task.state = ScanState::Running;
-        task.progress = 0.1; // 初始进度
+        if let Some(task) = store.get_mut(&task_id_clone) {
+            // 排队期间已被取消的任务不再转为运行中（扫描会立即以取消状态结束）
+            if task.state == ScanState::Queued {
+                task.state = ScanState::Running;
+                task.progress = 0.1; // 初始进度
+            }
+        }
         drop(store);
-
-        // 执行同步扫描（在 tokio 任务中）
-        let result = scanner.scan_sync(&request);
+        let sampler = tokio::spawn(sampler);
+
+        // 在阻塞线程池中执行同步扫描；即使扫描线程 panic 也要将任务置为结束状态，
+        // 否则客户端轮询 scan.status 会永远停留在 running
+        let result = tokio::task::spawn_blocking(move || {
+            let scan_result = Scanner::new().scan_sync(&request)?;
+            if let Some(hints) = &scan_result.concurrency_hints {
+                if let Some(live) = &request.progress {
+                    live.begin_phase(ScanPhase::Persisting);
+                }
+                let _ = surf_core::hints::store_cached(hints);
+            }
+            Ok(scan_result)
+        })
+        .await
+        .unwrap_or_else(|e| {
+            Err(if e.is_cancelled() {
+                ScanError::Canceled
+            } else {
+                ScanError::ThreadPool { message: format!("扫描线程异常退出: {}", e) }
+            })
+        });
+        sampler.abort();
 
         let mut store = task_store_clone.write().await;
-        let task = store.get_mut(&task_id_clone).unwrap();
+        let Some(task) = store.get_mut(&task_id_clone) else {
+            return;
+        };
         match result {
             Ok(scan_result) => {
-                task.state = ScanState::Completed;
-                task.progress = 1.0;
+                // 被取消的任务保留已遍历部分的结果，状态保持为已取消
+                if scan_result.summary.state != ScanState::Canceled && task.state != ScanState::Canceled {
+                    task.state = ScanState::Completed;
+                    task.progress = 1.0;
+                }
                 task.scanned_files = scan_result.summary.total_files;
                 task.scanned_bytes = scan_result.summary.total_size_bytes;
                 task.result = Some(scan_result);
@@ -245,8 +767,10 @@ async fn handle_scan_start(
             Err(e) => {
                 task.state = ScanState::Failed;
                 task.error = Some(e.to_string());
+                task.error_code = Some(scan_error_code(&e));
             }
         }
+        let _ = finished_tx.send(true);
     });
 
     Ok(JsonRpcResponse {
@@ -257,6 +781,20 @@ async fn handle_scan_start(
     })
 }
 
+/// 根目录与 `root` 重叠（相同、祖先或后代）且遍历尚未结束的任务，按任务 id 排序
+///
+/// 以遍历是否结束而非任务状态判断：已请求取消的任务在遍历真正停止前仍在读取磁盘。
+fn overlapping_tasks(store: &HashMap<String, TaskInfo>, root: &Path) -> Vec<String> {
+    let mut ids: Vec<String> = store
+        .values()
+        .filter(|task| task.finished.as_ref().is_some_and(|finished| !*finished.borrow()))
+        .filter(|task| task.roots.iter().any(|other| other.starts_with(root) || root.starts_with(other)))
+        .map(|task| task.task_id.clone())
+        .collect();
+    ids.sort();
+    ids
+}
+
 // 处理 scan.status 方法
 async fn handle_scan_status(
     params: Value,
@@ -266,12 +804,35 @@ async fn handle_scan_status(
 
     let store = task_store.read().await;
     match store.get(&params.task_id) {
-        Some(info) => Ok(JsonRpcResponse {
-            jsonrpc: "2.0".to_string(),
-            id: Value::Null,
-            result: Some(serde_json::to_value(info.clone()).unwrap()),
-            error: None,
-        }),
+        Some(info) => {
+            let mut status = serde_json::to_value(info)?;
+            if let (ScanState::Running, Some(live)) = (&info.state, &info.live) {
+                status["scanned_files"] = json!(live.scanned_files());
+                status["scanned_bytes"] = json!(live.scanned_bytes());
+                let snapshot = live.snapshot(ScanState::Running);
+                status["phase"] = json!(snapshot.phase);
+                status["progress"] = json!(snapshot.progress);
+                status["eta_seconds"] = json!(snapshot.eta_seconds);
+                if let Some(estimate) = snapshot.total_bytes_estimate {
+                    status["total_bytes_estimate"] = json!(estimate);
+                }
+                if let Some(rate) = snapshot.io_ops_per_sec {
+                    status["io_ops_per_sec"] = json!(rate);
+                }
+                if let Some(limit) = snapshot.io_ops_limit {
+                    status["io_ops_limit"] = json!(limit);
+                }
+                if let Some(throughput) = info.throughput.as_ref().and_then(|history| history.report()) {
+                    status["throughput"] = json!(throughput);
+                }
+            }
+            Ok(JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: Value::Null,
+                result: Some(status),
+                error: None,
+            })
+        }
         None => Ok(JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
             id: Value::Null,
@@ -293,12 +854,25 @@ async fn handle_scan_result(
         Some(info) => {
             // 对齐 Architecture.md 6.2.3：返回 task_id + 扁平化的 ScanResult 字段
             if let Some(scan_result) = &info.result {
-                let payload = json!({
-                    "task_id": info.task_id,
-                    "summary": scan_result.summary.clone(),
-                    "top_files": scan_result.top_files.clone(),
-                    "by_extension": scan_result.by_extension.clone(),
-                    "stale_files": scan_result.stale_files.clone(),
+                let limit = params.limit.unwrap_or(usize::MAX);
+                let mut paged = scan_result.clone();
+                paged.top_files = scan_result.page_top_files(params.offset, limit).to_vec();
+                let stale = scan_result.stale_files.get(params.offset..).unwrap_or_default();
+                paged.stale_files = stale[..limit.min(stale.len())].to_vec();
+                let mut payload = with_format(params.time_format, || serde_json::to_value(&paged))?;
+                if let Some(options) = &params.format {
+                    annotate_json(&mut payload, options);
+                }
+                payload["task_id"] = json!(info.task_id);
+                payload["source"] = json!(info.source);
+                if let Some(index) = &info.index {
+                    payload["index"] = index.clone();
+                }
+                payload["page"] = json!({
+                    "offset": params.offset,
+                    "limit": params.limit,
+                    "total_top_files": scan_result.top_files.len(),
+                    "total_stale_files": scan_result.stale_files.len(),
                 });
 
                 Ok(JsonRpcResponse {
@@ -307,6 +881,14 @@ async fn handle_scan_result(
                     result: Some(payload),
                     error: None,
                 })
+            } else if let (Some(code), Some(message)) = (info.error_code, &info.error) {
+                // 失败的任务以扫描错误对应的错误码返回失败原因
+                Ok(JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: Value::Null,
+                    result: None,
+                    error: Some(JsonRpcError::new(code, message)),
+                })
             } else {
                 Ok(JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
@@ -328,6 +910,65 @@ async fn handle_scan_result(
     }
 }
 
+// 处理 scan.heatmap 方法：返回各目录按文件年龄段划分的字节数与占比，供 GUI 绘制彩色 Treemap
+async fn handle_scan_heatmap(
+    params: Value,
+    task_store: TaskStore,
+) -> Result<JsonRpcResponse> {
+    let params: GetHeatmapParams = serde_json::from_value(params)?;
+
+    let store = task_store.read().await;
+    let (result, error) = match store.get(&params.task_id) {
+        Some(TaskInfo { result: Some(scan_result), .. }) => {
+            let max_depth = params.max_depth.unwrap_or(usize::MAX);
+            let directories: Vec<_> = scan_result.heatmap.iter().filter(|row| row.depth <= max_depth).collect();
+            let payload = json!({
+                "task_id": params.task_id,
+                "buckets": surf_core::heatmap::AGE_BUCKET_LABELS,
+                "directories": directories,
+            });
+            (Some(payload), None)
+        }
+        Some(_) => (None, Some(JsonRpcError::new(-32001, "Result not ready for the given task_id"))),
+        None => (None, Some(JsonRpcError::new(-32602, "Invalid task_id"))),
+    };
+    Ok(JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id: Value::Null,
+        result,
+        error,
+    })
+}
+
+// 处理 scan.diff 方法：对比两个任务的结果，列出新增、删除、增长与缩小的目录和文件
+async fn handle_scan_diff(
+    params: Value,
+    task_store: TaskStore,
+) -> Result<JsonRpcResponse> {
+    let params: DiffParams = serde_json::from_value(params)?;
+
+    let store = task_store.read().await;
+    let (old, new) = (store.get(&params.old_task_id), store.get(&params.new_task_id));
+    let (result, error) = match (old, new) {
+        (Some(TaskInfo { result: Some(old), .. }), Some(TaskInfo { result: Some(new), .. })) => {
+            let mut changes = surf_core::diff(old, new);
+            if let Some(limit) = params.limit {
+                changes.directories.truncate(limit);
+                changes.files.truncate(limit);
+            }
+            (Some(serde_json::to_value(&changes)?), None)
+        }
+        (Some(_), Some(_)) => (None, Some(JsonRpcError::new(-32001, "Result not ready for the given task_id"))),
+        _ => (None, Some(JsonRpcError::new(-32602, "Invalid task_id"))),
+    };
+    Ok(JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id: Value::Null,
+        result,
+        error,
+    })
+}
+
 // 处理 scan.cancel 方法
 async fn handle_scan_cancel(
     params: Value,
@@ -340,6 +981,9 @@ async fn handle_scan_cancel(
         Some(info) => {
             if info.state == ScanState::Running || info.state == ScanState::Queued {
                 info.state = ScanState::Canceled;
+                if let Some(cancel) = &info.cancel {
+                    cancel.cancel();
+                }
             }
             Ok(JsonRpcResponse {
                 jsonrpc: "2.0".to_string(),
@@ -357,32 +1001,122 @@ async fn handle_scan_cancel(
     }
 }
 
-// 处理 JSON-RPC 请求
-async fn handle_request(
-    request: JsonRpcRequest,
+// 处理 scan.pin / scan.unpin 方法：固定的任务不会被 scan.prune 清除
+async fn handle_scan_pin(
+    params: Value,
     task_store: TaskStore,
+    pinned: bool,
 ) -> Result<JsonRpcResponse> {
-    // 先拷贝一份请求 id，避免在 match 分支中 move
-    let req_id = request.id.clone();
+    let params: PinParams = serde_json::from_value(params)?;
 
-    let mut response = match request.method.as_str() {
-        "scan.start" => {
-            let params = request.params.ok_or_else(|| anyhow::anyhow!("Missing params"))?;
-            handle_scan_start(params, task_store).await?
+    let mut store = task_store.write().await;
+    let (result, error) = match store.get_mut(&params.task_id) {
+        Some(info) => {
+            info.pinned = pinned;
+            (Some(json!({"task_id": info.task_id, "pinned": info.pinned})), None)
         }
-        "scan.status" => {
-            let params = request.params.ok_or_else(|| anyhow::anyhow!("Missing params"))?;
-            handle_scan_status(params, task_store).await?
+        None => (None, Some(JsonRpcError::new(-32602, "Invalid task_id"))),
+    };
+    Ok(JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id: Value::Null,
+        result,
+        error,
+    })
+}
+
+// 处理 scan.prune 方法：会话结束时清除其未固定的临时任务，仍在进行的遍历一并取消
+async fn handle_scan_prune(
+    params: Value,
+    task_store: TaskStore,
+) -> Result<JsonRpcResponse> {
+    let params: PruneParams = serde_json::from_value(params)?;
+
+    let mut store = task_store.write().await;
+    let mut pruned: Vec<String> = store
+        .values()
+        .filter(|task| task.ephemeral && !task.pinned && task.session_id.as_deref() == Some(params.session_id.as_str()))
+        .map(|task| task.task_id.clone())
+        .collect();
+    pruned.sort();
+    for task_id in &pruned {
+        if let Some(cancel) = store.remove(task_id).and_then(|task| task.cancel) {
+            cancel.cancel();
         }
-        "scan.result" => {
-            let params = request.params.ok_or_else(|| anyhow::anyhow!("Missing params"))?;
-            handle_scan_result(params, task_store).await?
+    }
+    Ok(JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id: Value::Null,
+        result: Some(json!({"pruned": pruned})),
+        error: None,
+    })
+}
+
+// 处理 JSON-RPC 请求
+type HandlerFuture = Pin<Box<dyn Future<Output = Result<JsonRpcResponse>> + Send>>;
+
+/// 方法注册表条目：规范方法名及其处理函数
+struct MethodSpec {
+    name: &'static str,
+    handler: fn(Value, AppState) -> HandlerFuture,
+}
+
+/// 已注册的 JSON-RPC 方法
+const METHODS: &[MethodSpec] = &[
+    MethodSpec { name: "scan.start", handler: |p, s| Box::pin(handle_scan_start(p, s)) },
+    MethodSpec { name: "scan.status", handler: |p, s| Box::pin(handle_scan_status(p, s.task_store)) },
+    MethodSpec { name: "scan.result", handler: |p, s| Box::pin(handle_scan_result(p, s.task_store)) },
+    MethodSpec { name: "scan.cancel", handler: |p, s| Box::pin(handle_scan_cancel(p, s.task_store)) },
+    MethodSpec { name: "scan.heatmap", handler: |p, s| Box::pin(handle_scan_heatmap(p, s.task_store)) },
+    MethodSpec { name: "scan.diff", handler: |p, s| Box::pin(handle_scan_diff(p, s.task_store)) },
+    MethodSpec { name: "scan.pin", handler: |p, s| Box::pin(handle_scan_pin(p, s.task_store, true)) },
+    MethodSpec { name: "scan.unpin", handler: |p, s| Box::pin(handle_scan_pin(p, s.task_store, false)) },
+    MethodSpec { name: "scan.prune", handler: |p, s| Box::pin(handle_scan_prune(p, s.task_store)) },
+];
+
+/// 已弃用的旧方法名（`Surf.*` 风格）及其对应的规范方法名
+const DEPRECATED_ALIASES: &[(&str, &str)] = &[
+    ("Surf.Scan", "scan.start"),
+    ("Surf.Status", "scan.status"),
+    ("Surf.GetResults", "scan.result"),
+    ("Surf.Cancel", "scan.cancel"),
+    ("Surf.GetHeatmap", "scan.heatmap"),
+];
+
+/// 解析方法名（含已弃用别名）；别名首次使用时输出弃用警告
+fn resolve_method(name: &str) -> Option<&'static MethodSpec> {
+    let canonical = match DEPRECATED_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        Some(&(alias, canonical)) => {
+            warn_deprecated(alias, canonical);
+            canonical
         }
-        "scan.cancel" => {
+        None => name,
+    };
+    METHODS.iter().find(|m| m.name == canonical)
+}
+
+/// 每个弃用别名在进程生命周期内只警告一次，避免轮询请求刷屏
+fn warn_deprecated(alias: &'static str, canonical: &str) {
+    static WARNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut warned = WARNED.get_or_init(Default::default).lock().unwrap_or_else(PoisonError::into_inner);
+    if warned.insert(alias) {
+        eprintln!("警告: JSON-RPC 方法 `{}` 已弃用，请改用 `{}`", alias, canonical);
+    }
+}
+
+async fn handle_request(
+    request: JsonRpcRequest,
+    state: AppState,
+) -> Result<JsonRpcResponse> {
+    // 先拷贝一份请求 id，避免在 match 分支中 move
+    let req_id = request.id.clone().unwrap_or(Value::Null);
+
+    let mut response = match resolve_method(&request.method) {
+        Some(method) => {
             let params = request.params.ok_or_else(|| anyhow::anyhow!("Missing params"))?;
-            handle_scan_cancel(params, task_store).await?
+            (method.handler)(params, state).await?
         }
-        _ => JsonRpcResponse {
+        None => JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
             id: req_id.clone(),
             result: None,
@@ -399,8 +1133,9 @@ async fn handle_request(
 /// 统一的 JSON-RPC 字节流调度函数
 ///
 /// - 入参：原始请求体字节（来自 HTTP body 或未来的 TCP 字节流）
-/// - 出参：序列化后的 JSON-RPC Response 字节
-async fn handle_jsonrpc(payload: &[u8], task_store: TaskStore) -> Vec<u8> {
+/// - 出参：序列化后的 JSON-RPC Response 字节；请求为通知（不带 id）时为 None，
+///   即使处理失败也不响应。无法解析的请求体无从判断是否为通知，仍返回解析错误
+async fn handle_jsonrpc(payload: &[u8], state: AppState) -> Option<Vec<u8>> {
     // 尝试按 UTF-8 解码请求体
     let req_str = match String::from_utf8(payload.to_vec()) {
         Ok(s) => s,
@@ -414,7 +1149,7 @@ async fn handle_jsonrpc(payload: &[u8], task_store: TaskStore) -> Vec<u8> {
                     &format!("Parse error: {}", e),
                 )),
             };
-            return serde_json::to_vec(&err).unwrap();
+            return Some(serde_json::to_vec(&err).unwrap());
         }
     };
 
@@ -431,12 +1166,13 @@ async fn handle_jsonrpc(payload: &[u8], task_store: TaskStore) -> Vec<u8> {
                     &format!("Parse error: {}", e),
                 )),
             };
-            return serde_json::to_vec(&err).unwrap();
+            return Some(serde_json::to_vec(&err).unwrap());
         }
     };
 
     // 调用已有的 JSON-RPC 业务分发函数
-    let resp = match handle_request(req, task_store).await {
+    let notification = req.is_notification();
+    let resp = match handle_request(req, state).await {
         Ok(r) => r,
         Err(e) => JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
@@ -448,8 +1184,11 @@ async fn handle_jsonrpc(payload: &[u8], task_store: TaskStore) -> Vec<u8> {
             )),
         },
     };
+    if notification {
+        return None;
+    }
 
-    serde_json::to_vec(&resp).unwrap()
+    Some(serde_json::to_vec(&resp).unwrap())
 }
 
 /// HTTP `/rpc` 入口处理函数：接收 HTTP 请求体并转交给统一的 JSON-RPC 调度层
@@ -458,13 +1197,24 @@ async fn http_rpc_handler(
     body: Bytes,
 ) -> impl IntoResponse {
     let bytes = body.to_vec();
-    let resp_bytes = handle_jsonrpc(&bytes, state.task_store.clone()).await;
+    match handle_jsonrpc(&bytes, state).await {
+        Some(resp_bytes) => (
+            StatusCode::OK,
+            [("content-type", "application/json")],
+            resp_bytes,
+        )
+            .into_response(),
+        // 通知没有响应体
+        None => StatusCode::NO_CONTENT.into_response(),
+    }
+}
 
-    (
-        StatusCode::OK,
-        [("content-type", "application/json")],
-        resp_bytes,
-    )
+/// HTTP 路由：请求体的分帧（Content-Length / chunked、分片到达的 TCP 报文与同一连接上的流水线请求）
+/// 由 HTTP 层处理，`http_rpc_handler` 总是收到一个完整的请求体
+fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/rpc", post(http_rpc_handler))
+        .with_state(state)
 }
 
 /// 命令行参数解析
@@ -480,8 +1230,45 @@ struct Args {
     host: String,
 
     /// 服务监听端口
-    #[arg(long = "port", default_value = "1234", help = "服务监听端口（默认: 1234）")]
+    #[arg(long = "port", default_value = "1234", help = "服务监听端口（默认: 1234，0 表示由系统分配）")]
     port: u16,
+
+    /// 已运行的索引守护地址
+    #[arg(
+        long = "index-daemon",
+        value_name = "ADDR",
+        help = "已运行的索引守护地址（host:port，可重复）；scan.start 的根目录被其覆盖时直接由索引应答"
+    )]
+    index_daemons: Vec<String>,
+
+    /// 允许 scan.start 读取访问日志的目录
+    #[arg(
+        long = "access-log-dir",
+        value_name = "DIR",
+        help = "允许 scan.start 的 access_log 参数读取的目录；未指定时不接受 access_log"
+    )]
+    access_log_dir: Option<PathBuf>,
+
+    /// TLS 证书（PEM）
+    #[arg(
+        long = "tls-cert",
+        value_name = "FILE",
+        requires = "tls_key",
+        help = "TLS 证书链（PEM）；与 --tls-key 一同指定时经 HTTPS 提供 /rpc"
+    )]
+    tls_cert: Option<PathBuf>,
+
+    /// TLS 私钥（PEM）
+    #[arg(long = "tls-key", value_name = "FILE", requires = "tls_cert", help = "TLS 私钥（PEM，PKCS#8 / PKCS#1 / SEC1）")]
+    tls_key: Option<PathBuf>,
+
+    /// 证书与私钥文件不存在时生成自签名证书
+    #[arg(
+        long = "generate-tls-cert",
+        requires = "tls_cert",
+        help = "--tls-cert / --tls-key 指向的文件不存在时为 --host 生成自签名证书（实验环境使用，客户端需固定指纹）"
+    )]
+    generate_tls_cert: bool,
 }
 
 #[tokio::main]
@@ -502,20 +1289,41 @@ async fn main() -> Result<()> {
 
     let state = AppState {
         task_store: task_store.clone(),
+        index: Arc::new(IndexRouter { endpoints: args.index_daemons }),
+        access_log_dir: args.access_log_dir.map(Arc::new),
     };
 
-    let app = Router::new()
-        .route("/rpc", post(http_rpc_handler))
-        .with_state(state);
+    let app = router(state);
 
-    let addr: SocketAddr = format!("{}:{}", args.host, args.port).parse()?;
-    println!(
-        "Surf JSON-RPC HTTP Server listening on http://{}:{}/rpc",
-        args.host, args.port
-    );
+    let tls = match (&args.tls_cert, &args.tls_key) {
+        (Some(cert), Some(key)) => {
+            if args.generate_tls_cert && !cert.exists() && !key.exists() {
+                tls::generate_self_signed(&args.host, cert, key)?;
+                println!("已生成自签名证书: {}，私钥: {}", cert.display(), key.display());
+            }
+            Some(tls::load(cert, key)?)
+        }
+        _ => None,
+    };
 
+    let addr: SocketAddr = format!("{}:{}", args.host, args.port).parse()?;
     let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // 打印实际监听地址（--port 0 时为系统分配的端口）
+    let local_addr = listener.local_addr()?;
+    match tls {
+        Some(tls) => {
+            println!("Surf JSON-RPC HTTPS Server listening on https://{}/rpc", local_addr);
+            println!("证书指纹 (SHA-256): {}", tls.fingerprint);
+            tls::serve(listener, app, tls.acceptor).await?;
+        }
+        None => {
+            if !local_addr.ip().is_loopback() {
+                eprintln!("警告: 在非回环地址上以明文 HTTP 提供服务，远程部署请使用 --tls-cert / --tls-key 加密连接");
+            }
+            println!("Surf JSON-RPC HTTP Server listening on http://{}/rpc", local_addr);
+            axum::serve(listener, app).await?;
+        }
+    }
 
     Ok(())
 }
@@ -527,6 +1335,10 @@ mod tests {
     use tokio::sync::RwLock;
     use std::collections::HashMap;
 
+    fn app_state(task_store: &TaskStore) -> AppState {
+        AppState { task_store: task_store.clone(), index: Default::default(), access_log_dir: None }
+    }
+
     #[tokio::test]
     async fn test_handle_scan_start() {
         let task_store = Arc::new(RwLock::new(HashMap::new()));
@@ -541,7 +1353,7 @@ mod tests {
             "limit": 10
         });
 
-        let resp = handle_scan_start(params, task_store.clone()).await.unwrap();
+        let resp = handle_scan_start(params, app_state(&task_store)).await.unwrap();
         assert!(resp.error.is_none());
         assert!(resp.result.is_some());
 
@@ -556,6 +1368,335 @@ mod tests {
         assert!(store.contains_key(task_id));
     }
 
+    #[tokio::test]
+    async fn test_scan_result_human_format() {
+        let task_store: TaskStore = Arc::new(RwLock::new(HashMap::new()));
+        let mut result = ScanResult::default();
+        result.summary.total_size_bytes = 1_400_000_000;
+        result.summary.total_files = 12345;
+        task_store.write().await.insert(
+            "t".to_string(),
+            TaskInfo {
+                task_id: "t".to_string(),
+                state: ScanState::Completed,
+                progress: 1.0,
+                scanned_files: 0,
+                scanned_bytes: 0,
+                eta_seconds: None,
+                result: Some(result),
+                error: None,
+                error_code: None,
+                source: ResultSource::Walk,
+                index: None,
+                live: None,
+                cancel: None,
+                overlapping_tasks: Vec::new(),
+                roots: Vec::new(),
+                finished: None,
+                throughput: None,
+                ephemeral: false,
+                pinned: true,
+                session_id: None,
+            },
+        );
+
+        let params = serde_json::json!({"task_id": "t", "format": {"units": "si", "locale": "de-DE"}});
+        let payload = handle_scan_result(params, task_store.clone()).await.unwrap().result.unwrap();
+        assert_eq!(payload["summary"]["total_size_bytes"], 1_400_000_000u64);
+        assert_eq!(payload["summary"]["total_size_bytes_human"], "1,40 GB");
+        assert_eq!(payload["summary"]["total_files_human"], "12.345");
+
+        // 未提供 format 时不附加文本字段；不支持的地区视为参数错误
+        let payload = handle_scan_result(serde_json::json!({"task_id": "t"}), task_store.clone()).await.unwrap().result.unwrap();
+        assert!(payload["summary"].get("total_size_bytes_human").is_none());
+        let params = serde_json::json!({"task_id": "t", "format": {"locale": "tlh"}});
+        assert!(handle_scan_result(params, task_store).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scan_diff_between_tasks() {
+        let task_store: TaskStore = Arc::new(RwLock::new(HashMap::new()));
+        let snapshot = |sizes: &[(&str, u64)]| {
+            let mut result = ScanResult::default();
+            result.summary.root_path = "/data".into();
+            result.summary.top_limit = 10;
+            result.summary.total_size_bytes = sizes.iter().map(|(_, size)| size).sum();
+            result.top_files = sizes.iter().map(|(path, size)| surf_core::FileEntry::new(*path, *size)).collect();
+            result
+        };
+        let results = [
+            ("old", Some(snapshot(&[("/data/a.bin", 100), ("/data/b.bin", 50)]))),
+            ("new", Some(snapshot(&[("/data/a.bin", 300), ("/data/c.bin", 20)]))),
+            ("running", None),
+        ];
+        for (task_id, result) in results {
+            task_store.write().await.insert(
+                task_id.to_string(),
+                TaskInfo {
+                    task_id: task_id.to_string(),
+                    state: if result.is_some() { ScanState::Completed } else { ScanState::Running },
+                    progress: 1.0,
+                    scanned_files: 0,
+                    scanned_bytes: 0,
+                    eta_seconds: None,
+                    result,
+                    error: None,
+                    error_code: None,
+                    source: ResultSource::Walk,
+                    index: None,
+                    live: None,
+                    cancel: None,
+                    overlapping_tasks: Vec::new(),
+                    roots: Vec::new(),
+                    finished: None,
+                    throughput: None,
+                    ephemeral: false,
+                    pinned: true,
+                    session_id: None,
+                },
+            );
+        }
+
+        let params = serde_json::json!({"old_task_id": "old", "new_task_id": "new", "limit": 2});
+        let payload = handle_scan_diff(params, task_store.clone()).await.unwrap().result.unwrap();
+        assert_eq!(payload["total_size_delta"], 170);
+        assert_eq!(payload["files"].as_array().unwrap().len(), 2);
+        assert_eq!(payload["files"][0]["path"], "a.bin");
+        assert_eq!(payload["files"][0]["kind"], "grown");
+        assert_eq!(payload["files"][1]["kind"], "removed");
+
+        // 未完成的任务与不存在的任务
+        let params = serde_json::json!({"old_task_id": "old", "new_task_id": "running"});
+        assert_eq!(handle_scan_diff(params, task_store.clone()).await.unwrap().error.unwrap().code, -32001);
+        let params = serde_json::json!({"old_task_id": "missing", "new_task_id": "new"});
+        assert_eq!(handle_scan_diff(params, task_store).await.unwrap().error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_ephemeral_tasks_are_pruned_unless_pinned() {
+        let task_store: TaskStore = Arc::new(RwLock::new(HashMap::new()));
+        let dir = tempdir().unwrap();
+        let start = |extra: Value| {
+            let mut params = serde_json::json!({"root_path": dir.path(), "min_size": 0});
+            params.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            let state = app_state(&task_store);
+            async move {
+                let resp = handle_scan_start(params, state).await.unwrap();
+                match resp.result {
+                    Some(result) => Ok(result["task_id"].as_str().unwrap().to_string()),
+                    None => Err(resp.error.unwrap().code),
+                }
+            }
+        };
+
+        // 临时任务必须属于某个会话
+        assert_eq!(start(serde_json::json!({"ephemeral": true})).await, Err(-32602));
+
+        let scratch = start(serde_json::json!({"ephemeral": true, "session_id": "w1"})).await.unwrap();
+        let kept = start(serde_json::json!({"ephemeral": true, "session_id": "w1"})).await.unwrap();
+        let other_window = start(serde_json::json!({"ephemeral": true, "session_id": "w2"})).await.unwrap();
+        let regular = start(serde_json::json!({"session_id": "w1"})).await.unwrap();
+
+        let status = handle_scan_status(serde_json::json!({"task_id": scratch}), task_store.clone()).await.unwrap().result.unwrap();
+        assert_eq!(status["ephemeral"], true);
+        assert_eq!(status["pinned"], false);
+        assert_eq!(status["session_id"], "w1");
+
+        let pin = |task_id: &str, pinned: bool| handle_scan_pin(serde_json::json!({"task_id": task_id}), task_store.clone(), pinned);
+        assert_eq!(pin(&kept, true).await.unwrap().result.unwrap()["pinned"], true);
+        assert_eq!(pin("missing", true).await.unwrap().error.unwrap().code, -32602);
+
+        // 只清除该会话中未固定的临时任务
+        let params = serde_json::json!({"session_id": "w1"});
+        let payload = handle_scan_prune(params.clone(), task_store.clone()).await.unwrap().result.unwrap();
+        assert_eq!(payload["pruned"], serde_json::json!([scratch]));
+        let store = task_store.read().await;
+        assert!(!store.contains_key(&scratch));
+        assert!(store.contains_key(&kept) && store.contains_key(&other_window) && store.contains_key(&regular));
+        drop(store);
+
+        // 取消固定后在下次清除时移除
+        pin(&kept, false).await.unwrap();
+        let payload = handle_scan_prune(params, task_store.clone()).await.unwrap().result.unwrap();
+        assert_eq!(payload["pruned"], serde_json::json!([kept]));
+        assert_eq!(task_store.read().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_roots_are_serialized() {
+        let task_store: TaskStore = Arc::new(RwLock::new(HashMap::new()));
+        let dir = tempdir().unwrap();
+        let home = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(home.join("user")).unwrap();
+        std::fs::write(home.join("user/a.bin"), [0u8; 64]).unwrap();
+        let other = tempdir().unwrap();
+
+        // 模拟正在遍历 home 的任务，由测试控制其何时结束
+        let (finished_tx, finished_rx) = watch::channel(false);
+        task_store.write().await.insert(
+            "home".to_string(),
+            TaskInfo {
+                task_id: "home".to_string(),
+                state: ScanState::Running,
+                progress: 0.1,
+                scanned_files: 0,
+                scanned_bytes: 0,
+                eta_seconds: None,
+                result: None,
+                error: None,
+                error_code: None,
+                source: ResultSource::Walk,
+                index: None,
+                live: None,
+                cancel: None,
+                overlapping_tasks: Vec::new(),
+                roots: vec![home.clone()],
+                finished: Some(finished_rx),
+                throughput: None,
+                ephemeral: false,
+                pinned: true,
+                session_id: None,
+            },
+        );
+        {
+            let store = task_store.read().await;
+            assert_eq!(overlapping_tasks(&store, &home.join("user")), vec!["home"]);
+            assert_eq!(overlapping_tasks(&store, &home), vec!["home"]);
+            assert_eq!(overlapping_tasks(&store, home.parent().unwrap()), vec!["home"]);
+            assert!(overlapping_tasks(&store, other.path()).is_empty());
+            // 前缀相同但不是祖先的目录不算重叠
+            assert!(overlapping_tasks(&store, Path::new(&format!("{}-sibling", home.display()))).is_empty());
+        }
+
+        // 子目录的扫描排在 home 之后，状态中列出等待的任务
+        let params = json!({"root_path": home.join("user"), "min_size": 0});
+        let resp = handle_scan_start(params, app_state(&task_store)).await.unwrap();
+        let task_id = resp.result.unwrap()["task_id"].as_str().unwrap().to_string();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let status = handle_scan_status(json!({"task_id": task_id}), task_store.clone()).await.unwrap().result.unwrap();
+        assert_eq!(status["state"], "Queued");
+        assert_eq!(status["overlapping_tasks"], json!(["home"]));
+
+        // home 遍历结束后开始扫描；之后的任务不再与已结束的任务重叠
+        finished_tx.send(true).unwrap();
+        for _ in 0..200 {
+            if task_store.read().await[&task_id].state == ScanState::Completed {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let store = task_store.read().await;
+        assert_eq!(store[&task_id].state, ScanState::Completed);
+        assert_eq!(store[&task_id].result.as_ref().unwrap().summary.total_size_bytes, 64);
+        assert!(overlapping_tasks(&store, &home).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_scan_reports_error_code() {
+        let task_store: TaskStore = Arc::new(RwLock::new(HashMap::new()));
+        let dir = tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        let resp = handle_scan_start(json!({"root_path": missing, "use_index": false}), app_state(&task_store)).await.unwrap();
+        let task_id = resp.result.unwrap()["task_id"].as_str().unwrap().to_string();
+        for _ in 0..200 {
+            if task_store.read().await[&task_id].state == ScanState::Failed {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // scan.status 给出失败原因与错误码，scan.result 以该错误码返回错误
+        let status = handle_scan_status(json!({"task_id": task_id}), task_store.clone()).await.unwrap().result.unwrap();
+        assert_eq!(status["state"], "Failed");
+        assert_eq!(status["error_code"], -32602);
+        assert!(status["error"].as_str().unwrap().contains("missing"));
+        let error = handle_scan_result(json!({"task_id": task_id}), task_store.clone()).await.unwrap().error.unwrap();
+        assert_eq!(error.code, -32602);
+
+        let permission = ScanError::PermissionDenied { path: missing.clone() };
+        assert_eq!(scan_error_code(&permission), ERROR_PERMISSION_DENIED);
+        assert_eq!(scan_error_code(&ScanError::Canceled), ERROR_SCAN_ABORTED);
+        let io = ScanError::Io { path: missing, source: std::io::Error::other("boom") };
+        assert_eq!(scan_error_code(&io), -32603);
+    }
+
+    /// 从连接中读取一个按 Content-Length 分帧的 HTTP 响应，返回状态码与响应体
+    async fn read_response<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> (u16, Value) {
+        use tokio::io::AsyncReadExt;
+
+        let mut status_line = String::new();
+        reader.read_line(&mut status_line).await.unwrap();
+        let status = status_line.split_whitespace().nth(1).unwrap().parse().unwrap();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            if line.trim().is_empty() {
+                break;
+            }
+            if let Some(value) = line.split_once(':').filter(|(name, _)| name.eq_ignore_ascii_case("content-length")).map(|(_, v)| v) {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0u8; length];
+        reader.read_exact(&mut body).await.unwrap();
+        (status, if body.is_empty() { Value::Null } else { serde_json::from_slice(&body).unwrap() })
+    }
+
+    #[tokio::test]
+    async fn test_http_framing_with_fragmented_and_pipelined_requests() {
+        let task_store: TaskStore = Arc::new(RwLock::new(HashMap::new()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(axum::serve(listener, router(app_state(&task_store))).into_future());
+
+        let request = |id: u64| {
+            let body = json!({"jsonrpc": "2.0", "id": id, "method": "scan.status", "params": {"task_id": "missing"}}).to_string();
+            format!(
+                "POST /rpc HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                addr,
+                body.len(),
+                body
+            )
+        };
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        // 头部与请求体被拆成多个 TCP 报文到达（包括在行中间与 JSON 中间断开）
+        let first = request(1);
+        for chunk in first.as_bytes().chunks(7) {
+            writer.write_all(chunk).await.unwrap();
+            writer.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        let (status, body) = read_response(&mut reader).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["id"], 1);
+        assert_eq!(body["error"]["code"], -32602);
+
+        // 同一连接上一次写入的两个流水线请求，以及跨越两者边界的分片
+        let pipelined = format!("{}{}", request(2), request(3));
+        let (head, tail) = pipelined.split_at(request(2).len() + 10);
+        writer.write_all(head.as_bytes()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        writer.write_all(tail.as_bytes()).await.unwrap();
+        for id in [2, 3] {
+            let (status, body) = read_response(&mut reader).await;
+            assert_eq!(status, 200);
+            assert_eq!(body["id"], id);
+        }
+
+        // 请求体不是合法 JSON 时返回解析错误，连接仍可继续使用
+        let broken = "{\"jsonrpc\": \"2.0\", \"id\": 4";
+        let raw = format!("POST /rpc HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\n\r\n{}", addr, broken.len(), broken);
+        writer.write_all(raw.as_bytes()).await.unwrap();
+        let (_, body) = read_response(&mut reader).await;
+        assert_eq!(body["error"]["code"], -32700);
+        writer.write_all(request(5).as_bytes()).await.unwrap();
+        assert_eq!(read_response(&mut reader).await.1["id"], 5);
+    }
+
     #[tokio::test]
     async fn test_handle_scan_status_not_found() {
         let task_store = Arc::new(RwLock::new(HashMap::new()));
@@ -564,4 +1705,264 @@ mod tests {
         assert!(resp.error.is_some());
         assert_eq!(resp.error.unwrap().code, -32602);
     }
+
+    #[tokio::test]
+    async fn test_deprecated_method_aliases_resolve() {
+        for (alias, canonical) in DEPRECATED_ALIASES {
+            assert_eq!(resolve_method(alias).unwrap().name, *canonical);
+        }
+        assert!(resolve_method("Surf.Unknown").is_none());
+
+        // 旧方法名与新方法名走同一处理函数
+        let task_store = Arc::new(RwLock::new(HashMap::new()));
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(serde_json::json!(7)),
+            method: "Surf.Status".to_string(),
+            params: Some(serde_json::json!({"task_id": "missing"})),
+        };
+        let resp = handle_request(request, app_state(&task_store)).await.unwrap();
+        assert_eq!(resp.id, serde_json::json!(7));
+        assert_ne!(resp.error.unwrap().code, -32601);
+    }
+
+    #[tokio::test]
+    async fn test_legacy_get_results_returns_full_aggregation() {
+        let task_store: TaskStore = Arc::new(RwLock::new(HashMap::new()));
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("media")).unwrap();
+        let file = std::fs::File::create(dir.path().join("media").join("old.mp4")).unwrap();
+        file.set_len(4096).unwrap();
+        file.set_modified(std::time::SystemTime::now() - Duration::from_secs(400 * 86_400)).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), [0u8; 16]).unwrap();
+        let call = |method: &str, params: Value| {
+            let body = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+            let state = app_state(&task_store);
+            async move {
+                let resp: Value = serde_json::from_slice(&handle_jsonrpc(body.to_string().as_bytes(), state).await.unwrap()).unwrap();
+                resp["result"].clone()
+            }
+        };
+
+        // 弃用的 Surf.* 方法与 scan.* 共用同一扫描任务：结果是完整的汇总而不只是文件列表
+        let params = json!({"root_path": dir.path(), "min_size": 0, "stale_days": 365, "use_index": false});
+        let task_id = call("Surf.Scan", params).await["task_id"].as_str().unwrap().to_string();
+        for _ in 0..200 {
+            if call("Surf.Status", json!({"task_id": task_id})).await["state"] == "Completed" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let result = call("Surf.GetResults", json!({"task_id": task_id})).await;
+        assert_eq!(result["summary"]["total_files"], 2);
+        assert_eq!(result["top_files"][0]["size_bytes"], 4096);
+        assert_eq!(result["by_extension"].as_array().unwrap().len(), 2);
+        assert_eq!(result["stale_files"].as_array().unwrap().len(), 1);
+        assert!(result["top_dirs"].as_array().unwrap().iter().any(|d| d["path"].as_str().unwrap().ends_with("media")));
+    }
+
+    #[tokio::test]
+    async fn test_scan_start_with_multiple_roots() {
+        let task_store: TaskStore = Arc::new(RwLock::new(HashMap::new()));
+        let (a, b) = (tempdir().unwrap(), tempdir().unwrap());
+        std::fs::write(a.path().join("a.bin"), [0u8; 100]).unwrap();
+        std::fs::write(b.path().join("b.bin"), [0u8; 300]).unwrap();
+        let call = |method: &str, params: Value| {
+            let body = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+            let state = app_state(&task_store);
+            async move { serde_json::from_slice::<Value>(&handle_jsonrpc(body.to_string().as_bytes(), state).await.unwrap()).unwrap() }
+        };
+
+        // 只给 roots 时以第一个根目录为 root_path；两者都缺省时参数无效
+        let resp = call("scan.start", json!({"roots": [a.path(), b.path()], "min_size": 0})).await;
+        let task_id = resp["result"]["task_id"].as_str().unwrap().to_string();
+        assert_eq!(task_store.read().await[&task_id].roots.len(), 2);
+        for _ in 0..200 {
+            if call("scan.status", json!({"task_id": task_id})).await["result"]["state"] == "Completed" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let result = call("scan.result", json!({"task_id": task_id})).await["result"].clone();
+        assert_eq!(result["summary"]["total_files"], 2);
+        assert_eq!(result["summary"]["total_size_bytes"], 400);
+        let roots = result["root_summaries"].as_array().unwrap();
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[1]["total_size_bytes"], 300);
+
+        let resp = call("scan.start", json!({"min_size": 0})).await;
+        assert_eq!(resp["error"]["code"], -32602);
+    }
+
+    #[tokio::test]
+    async fn test_access_log_restricted_to_configured_dir() {
+        let task_store: TaskStore = Arc::new(RwLock::new(HashMap::new()));
+        let (dir, logs, outside) = (tempdir().unwrap(), tempdir().unwrap(), tempdir().unwrap());
+        std::fs::write(logs.path().join("access.log"), format!("{}\n", dir.path().join("a").display())).unwrap();
+        std::fs::write(outside.path().join("secret.log"), "x\n").unwrap();
+        let start = |access_log_dir: Option<&Path>, access_log: String| {
+            let mut state = app_state(&task_store);
+            state.access_log_dir = access_log_dir.map(|d| Arc::new(d.to_path_buf()));
+            let params = json!({"root_path": dir.path(), "min_size": 0, "stale_days": 1, "access_log": access_log});
+            async move { handle_scan_start(params, state).await.unwrap() }
+        };
+
+        // 未配置目录时不接受 access_log
+        let resp = start(None, "access.log".to_string()).await;
+        assert_eq!(resp.error.unwrap().code, -32602);
+        // 目录之外的路径（绝对路径或 ..）与不存在的文件都是参数错误
+        let secret = outside.path().join("secret.log").display().to_string();
+        for path in [secret, "../secret.log".to_string(), "missing.log".to_string()] {
+            let resp = start(Some(logs.path()), path).await;
+            assert_eq!(resp.error.unwrap().code, -32602);
+        }
+        assert!(task_store.read().await.is_empty());
+
+        // 目录中的日志按相对路径或绝对路径读取
+        let resp = start(Some(logs.path()), "access.log".to_string()).await;
+        assert!(resp.error.is_none(), "{:?}", resp.error);
+        let absolute = logs.path().join("access.log").display().to_string();
+        assert!(start(Some(logs.path()), absolute).await.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scan_result_pages_top_files_capacity() {
+        let task_store: TaskStore = Arc::new(RwLock::new(HashMap::new()));
+        let dir = tempdir().unwrap();
+        for size in 1..=6 {
+            std::fs::write(dir.path().join(format!("{size}.bin")), vec![0u8; size * 100]).unwrap();
+        }
+        let call = |method: &str, params: Value| {
+            let body = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+            let state = app_state(&task_store);
+            async move { serde_json::from_slice::<Value>(&handle_jsonrpc(body.to_string().as_bytes(), state).await.unwrap()).unwrap() }
+        };
+
+        let params = json!({"root_path": dir.path(), "min_size": 0, "limit": 2, "top_files_capacity": 5});
+        let resp = call("scan.start", params).await;
+        let task_id = resp["result"]["task_id"].as_str().unwrap().to_string();
+        for _ in 0..200 {
+            if call("scan.status", json!({"task_id": task_id})).await["result"]["state"] == "Completed" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // top_files 保留 5 个，按页浏览超出 limit 的部分
+        let result = call("scan.result", json!({"task_id": task_id, "offset": 2, "limit": 2})).await["result"].clone();
+        assert_eq!(result["summary"]["top_files_capacity"], 5);
+        assert_eq!(result["page"]["total_top_files"], 5);
+        let sizes: Vec<u64> = result["top_files"].as_array().unwrap().iter().map(|f| f["size_bytes"].as_u64().unwrap()).collect();
+        assert_eq!(sizes, vec![400, 300]);
+        let result = call("scan.result", json!({"task_id": task_id, "offset": 4, "limit": 2})).await["result"].clone();
+        assert_eq!(result["top_files"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_notifications_receive_no_response() {
+        let task_store: TaskStore = Arc::new(RwLock::new(HashMap::new()));
+        let dir = tempdir().unwrap();
+        let call = |body: Value| {
+            let state = app_state(&task_store);
+            async move { handle_jsonrpc(body.to_string().as_bytes(), state).await }
+        };
+
+        // 每个方法（含弃用别名）的通知都不返回响应，失败的调用同样如此
+        let names = METHODS.iter().map(|m| m.name).chain(DEPRECATED_ALIASES.iter().map(|(alias, _)| *alias));
+        for name in names {
+            let params = if name.ends_with("start") || name == "Surf.Scan" {
+                serde_json::json!({"root_path": dir.path(), "min_size": 0})
+            } else {
+                serde_json::json!({"task_id": "missing"})
+            };
+            let body = serde_json::json!({"jsonrpc": "2.0", "method": name, "params": params});
+            assert!(call(body).await.is_none(), "{} 的通知不应有响应", name);
+        }
+        assert!(call(serde_json::json!({"jsonrpc": "2.0", "method": "Surf.Unknown"})).await.is_none());
+        assert!(call(serde_json::json!({"jsonrpc": "1.0", "method": "scan.status"})).await.is_none());
+        // 通知启动的扫描照常执行
+        assert_eq!(task_store.read().await.len(), 2);
+
+        // "id": null 是普通请求，需要响应；无法解析的请求体返回解析错误
+        let body = serde_json::json!({"jsonrpc": "2.0", "id": null, "method": "scan.status", "params": {"task_id": "missing"}});
+        let resp: Value = serde_json::from_slice(&call(body).await.unwrap()).unwrap();
+        assert_eq!(resp["id"], Value::Null);
+        assert_eq!(resp["error"]["code"], -32602);
+        assert!(handle_jsonrpc(b"{", app_state(&task_store)).await.is_some());
+
+        // 即发即弃的取消：通知同样会取消任务
+        let body = serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "scan.start", "params": {"root_path": dir.path()}});
+        let resp: Value = serde_json::from_slice(&call(body).await.unwrap()).unwrap();
+        let task_id = resp["result"]["task_id"].as_str().unwrap().to_string();
+        task_store.write().await.get_mut(&task_id).unwrap().state = ScanState::Running;
+        let body = serde_json::json!({"jsonrpc": "2.0", "method": "Surf.Cancel", "params": {"task_id": task_id}});
+        assert!(call(body).await.is_none());
+        assert_eq!(task_store.read().await[&task_id].state, ScanState::Canceled);
+    }
+
+    #[tokio::test]
+    async fn test_scan_start_answers_from_index_daemon() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("a.bin"), [0u8; 64]).unwrap();
+        let snapshot = Scanner::new().scan_sync(&ScanRequest::new(dir.path())).unwrap();
+
+        // 模拟索引守护：只覆盖 dir，其余路径返回 -32602
+        let daemon = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let daemon_addr = daemon.local_addr().unwrap().to_string();
+        let root = dir.path().to_path_buf();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = daemon.accept().await {
+                let (reader, mut writer) = stream.into_split();
+                let mut line = String::new();
+                BufReader::new(reader).read_line(&mut line).await.unwrap();
+                let request: Value = serde_json::from_str(&line).unwrap();
+                let response = if request["params"]["path"] == json!(root) {
+                    json!({"jsonrpc": "2.0", "id": request["id"], "result": {
+                        "generation": 3, "indexed_at": 1_700_000_000, "result": snapshot,
+                    }})
+                } else {
+                    json!({"jsonrpc": "2.0", "id": request["id"], "error": {"code": -32602, "message": "Path not covered by index"}})
+                };
+                writer.write_all(format!("{}\n", response).as_bytes()).await.unwrap();
+            }
+        });
+        // 不可达的守护排在前面，查询失败后继续尝试下一个
+        let unreachable = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().to_string();
+        let task_store: TaskStore = Arc::new(RwLock::new(HashMap::new()));
+        let state = AppState {
+            task_store: task_store.clone(),
+            index: Arc::new(IndexRouter { endpoints: vec![unreachable, daemon_addr] }),
+            access_log_dir: None,
+        };
+        let start = |params: Value| {
+            let state = state.clone();
+            async move {
+                let resp = handle_scan_start(params, state).await.unwrap().result.unwrap();
+                resp["task_id"].as_str().unwrap().to_string()
+            }
+        };
+
+        // 索引覆盖的根目录立即完成，结果注明来源
+        let task_id = start(json!({"root_path": dir.path()})).await;
+        let info = task_store.read().await[&task_id].clone();
+        assert_eq!((info.state, info.source), (ScanState::Completed, ResultSource::Index));
+        let payload = handle_scan_result(json!({"task_id": task_id}), task_store.clone()).await.unwrap().result.unwrap();
+        assert_eq!(payload["source"], "index");
+        assert_eq!(payload["index"]["generation"], 3);
+        assert_eq!(payload["summary"]["total_files"], 1);
+
+        // 带过滤规则、显式关闭索引或未被覆盖的路径回退为实际扫描
+        let sub = dir.path().join("sub");
+        std::fs::create_dir(&sub).unwrap();
+        for params in [
+            json!({"root_path": dir.path(), "exclude_patterns": ["*.bin"]}),
+            json!({"root_path": dir.path(), "use_index": false}),
+            json!({"root_path": sub}),
+        ] {
+            let task_id = start(params).await;
+            let info = task_store.read().await[&task_id].clone();
+            assert_eq!(info.source, ResultSource::Walk);
+            assert!(info.index.is_none());
+        }
+    }
 }
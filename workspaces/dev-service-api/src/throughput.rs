@@ -0,0 +1,163 @@
+//! 扫描吞吐量历史
+//!
+//! 遍历期间每秒从 `LiveProgress` 采样一次已扫描的文件数与字节数，保存在容量固定的环形缓冲中
+//! （最近 60 个采样），`scan.status` 据此返回最近几秒的吞吐量、逐秒速率序列（便于绘制迷你折线图）
+//! 以及是否停滞，客户端无需自行记录历史。进度按目录批量累加，单个巨大目录处理期间速率可能短暂为 0。
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use surf_core::LiveProgress;
+
+/// 采样间隔
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 保留的采样数
+const CAPACITY: usize = 60;
+
+/// 计算当前吞吐量所用的窗口（采样间隔数）
+const RECENT_WINDOW: usize = 5;
+
+/// 连续无进展超过该时长视为停滞
+const STALL_AFTER: Duration = Duration::from_secs(10);
+
+/// 一次采样：累计的文件数与字节数
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: SystemTime,
+    files: u64,
+    bytes: u64,
+}
+
+/// 一个采样间隔内的速率
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RatePoint {
+    /// 间隔结束的时间（Unix 纪元毫秒数）
+    pub timestamp: u64,
+    /// 文件/秒
+    pub files_per_sec: f64,
+    /// 字节/秒
+    pub bytes_per_sec: f64,
+}
+
+/// `scan.status` 中的吞吐量信息
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Throughput {
+    /// 最近几秒的平均速率（文件/秒）
+    pub files_per_sec: f64,
+    /// 最近几秒的平均速率（字节/秒）
+    pub bytes_per_sec: f64,
+    /// 同上，以 MB/s（10^6 字节）表示
+    pub mb_per_sec: f64,
+    /// 已连续超过 10 秒没有新的文件计入
+    pub stalled: bool,
+    /// 逐个采样间隔的速率，按时间升序（最多 59 个点）
+    pub series: Vec<RatePoint>,
+}
+
+/// 单个任务的吞吐量采样历史
+#[derive(Debug, Default)]
+pub struct ThroughputHistory {
+    samples: Mutex<VecDeque<Sample>>,
+}
+
+impl ThroughputHistory {
+    /// 记录一次采样，超出容量时丢弃最早的采样
+    pub fn record(&self, at: SystemTime, files: u64, bytes: u64) {
+        let mut samples = self.samples.lock().unwrap_or_else(PoisonError::into_inner);
+        if samples.len() == CAPACITY {
+            samples.pop_front();
+        }
+        samples.push_back(Sample { at, files, bytes });
+    }
+
+    /// 由采样历史计算吞吐量；不足两个采样时返回 None
+    pub fn report(&self) -> Option<Throughput> {
+        let samples = self.samples.lock().unwrap_or_else(PoisonError::into_inner);
+        if samples.len() < 2 {
+            return None;
+        }
+        let last = samples[samples.len() - 1];
+        let (files_per_sec, bytes_per_sec) = rate(&samples[samples.len().saturating_sub(RECENT_WINDOW + 1)], &last);
+        let series = samples
+            .iter()
+            .zip(samples.iter().skip(1))
+            .map(|(a, b)| {
+                let (files_per_sec, bytes_per_sec) = rate(a, b);
+                RatePoint { timestamp: millis(b.at), files_per_sec, bytes_per_sec }
+            })
+            .collect();
+        // 文件数最后一次变化时的采样（历史中从未变化时为最早的采样）
+        let idle_since = samples.iter().rev().take_while(|s| s.files == last.files).last().map_or(last.at, |s| s.at);
+        let stalled = last.at.duration_since(idle_since).unwrap_or_default() >= STALL_AFTER;
+        Some(Throughput { files_per_sec, bytes_per_sec, mb_per_sec: bytes_per_sec / 1e6, stalled, series })
+    }
+}
+
+/// 两次采样之间的速率（文件/秒，字节/秒）
+fn rate(from: &Sample, to: &Sample) -> (f64, f64) {
+    let secs = to.at.duration_since(from.at).unwrap_or_default().as_secs_f64();
+    if secs <= 0.0 {
+        return (0.0, 0.0);
+    }
+    let per_sec = |a: u64, b: u64| b.saturating_sub(a) as f64 / secs;
+    (per_sec(from.files, to.files), per_sec(from.bytes, to.bytes))
+}
+
+fn millis(at: SystemTime) -> u64 {
+    at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+/// 按 [`SAMPLE_INTERVAL`] 持续采样，直到任务被中止（由调用方在扫描结束后 abort）
+pub async fn sample(live: Arc<LiveProgress>, history: Arc<ThroughputHistory>) {
+    let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+    loop {
+        interval.tick().await;
+        history.record(SystemTime::now(), live.scanned_files(), live.scanned_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throughput_rates_series_and_stall() {
+        let history = ThroughputHistory::default();
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        history.record(at(0), 0, 0);
+        assert!(history.report().is_none());
+
+        // 每秒 100 个文件、2 MB
+        for i in 1..=10 {
+            history.record(at(i), i * 100, i * 2_000_000);
+        }
+        let report = history.report().unwrap();
+        assert_eq!(report.files_per_sec, 100.0);
+        assert_eq!(report.mb_per_sec, 2.0);
+        assert_eq!(report.series.len(), 10);
+        assert_eq!(report.series[0], RatePoint { timestamp: 1_700_000_001_000, files_per_sec: 100.0, bytes_per_sec: 2e6 });
+        assert!(!report.stalled);
+
+        // 之后不再有进展：最近窗口的速率降为 0，超过 10 秒后视为停滞
+        for i in 11..=19 {
+            history.record(at(i), 1000, 20_000_000);
+        }
+        let report = history.report().unwrap();
+        assert_eq!(report.files_per_sec, 0.0);
+        assert!(!report.stalled);
+        history.record(at(20), 1000, 20_000_000);
+        assert!(history.report().unwrap().stalled);
+
+        // 环形缓冲只保留最近的采样
+        for i in 21..=200 {
+            history.record(at(i), 1000 + i, 20_000_000);
+        }
+        let report = history.report().unwrap();
+        assert_eq!(report.series.len(), CAPACITY - 1);
+        assert!(!report.stalled);
+    }
+}
@@ -0,0 +1,106 @@
+//! HTTPS 传输
+//!
+//! 监听回环以外的地址时，`--tls-cert` / `--tls-key` 让 `/rpc` 经 TLS（rustls）提供，远程 CLI 与
+//! 客户端的连接随之加密。实验环境可用 `--generate-tls-cert` 在文件不存在时生成自签名证书；
+//! 客户端无法通过 CA 校验自签名证书，改为固定启动时打印的 SHA-256 证书指纹
+//! （surf-client 的 `ClientOptions::tls_fingerprint`，CLI 的 `--remote-fingerprint`）。
+
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use axum::Router;
+use hyper_util::rt::TokioIo;
+use hyper_util::service::TowerToHyperService;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
+use sha2::{Digest, Sha256};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+
+/// 单个连接完成 TLS 握手的时限，避免半开连接占用任务
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 已加载的证书与对应的握手配置
+pub struct TlsSetup {
+    pub acceptor: TlsAcceptor,
+    /// 叶子证书的 SHA-256 指纹（冒号分隔的大写十六进制）
+    pub fingerprint: String,
+}
+
+/// 从 PEM 文件加载证书链与私钥
+pub fn load(cert_path: &Path, key_path: &Path) -> Result<TlsSetup> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("读取证书失败: {}", cert_path.display()))?;
+    let leaf = certs.first().with_context(|| format!("证书文件中没有证书: {}", cert_path.display()))?;
+    let fingerprint = fingerprint(leaf);
+    let key = PrivateKeyDer::from_pem_file(key_path).with_context(|| format!("读取私钥失败: {}", key_path.display()))?;
+    let config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("证书与私钥不匹配")?;
+    Ok(TlsSetup { acceptor: TlsAcceptor::from(Arc::new(config)), fingerprint })
+}
+
+/// 证书（DER 编码）的 SHA-256 指纹，格式与 `openssl x509 -fingerprint -sha256` 相同
+pub fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der).iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":")
+}
+
+/// 为 `host` 生成自签名证书并写入 PEM 文件（另含 localhost 与回环地址，便于本机调试）
+pub fn generate_self_signed(host: &str, cert_path: &Path, key_path: &Path) -> Result<()> {
+    let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+    // 监听所有地址时没有可写入证书的主机名；客户端固定指纹，不依赖名称校验
+    let unspecified = host.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified());
+    if !unspecified && !names.iter().any(|name| name == host) {
+        names.push(host.to_string());
+    }
+    let certified = rcgen::generate_simple_self_signed(names).context("生成自签名证书失败")?;
+    std::fs::write(cert_path, certified.cert.pem()).with_context(|| format!("写入证书失败: {}", cert_path.display()))?;
+    write_private(key_path, &certified.key_pair.serialize_pem())
+        .with_context(|| format!("写入私钥失败: {}", key_path.display()))?;
+    Ok(())
+}
+
+/// 写入私钥文件（Unix 上仅所有者可读写）
+fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+/// 在 `listener` 上接受 TLS 连接并以 HTTP/1.1 提供 `app`
+pub async fn serve(listener: TcpListener, app: Router, acceptor: TlsAcceptor) -> Result<()> {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                // 文件描述符耗尽等错误是暂时的，稍后继续接受连接
+                eprintln!("接受连接失败: {}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let service = TowerToHyperService::new(app.clone());
+        tokio::spawn(async move {
+            let stream = match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => return eprintln!("TLS 握手失败（{}）: {}", peer, e),
+                Err(_) => return eprintln!("TLS 握手超时（{}）", peer),
+            };
+            let _ = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await;
+        });
+    }
+}
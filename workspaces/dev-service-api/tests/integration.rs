@@ -20,7 +20,7 @@ impl ServiceHandle {
         let bin_path = env!("CARGO_BIN_EXE_surf-service");
 
         let child = Command::new(bin_path)
-            .args(&["--service", "--host", HOST, "--port", &PORT.to_string()])
+            .args(["--service", "--host", HOST, "--port", &PORT.to_string()])
             .spawn()
             .expect("failed to start service");
         // 等待服务启动